@@ -0,0 +1,183 @@
+//! Example of a market-data enrichment plugin composing the table, dictionary, null and error APIs of
+//!  `rusty_api`. `enrich_trades` does the same as the q expression below and is tested against it.
+//!
+//! ```q
+//! `time xasc update notional: price * size * multiplier from update multiplier: 1f^multiplier from trades lj refdata
+//! ```
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+//                            Load Libraries                            //
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use kdbplus::api::rusty_api::*;
+use kdbplus::api::*;
+use kdbplus::{qnull_base, qtype};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+//                          Global Variables                            //
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Columns required in the trades.
+const TRADE_COLUMNS: [&str; 4] = ["time", "sym", "price", "size"];
+
+/// Types of the columns required in the trades.
+const TRADE_TYPES: [i8; 4] = [
+    qtype::TIMESTAMP_LIST,
+    qtype::SYMBOL_LIST,
+    qtype::FLOAT_LIST,
+    qtype::LONG_LIST,
+];
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+//                            Enrichment                                //
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Enrich trades with the multiplier of each symbol in reference data and the notional of each trade, sorted by
+///  time.
+/// - `trades`: table with `time` (timestamp), `sym` (symbol), `price` (float) and `size` (long) columns. Other
+///   columns are dropped.
+/// - `refdata`: table keyed by `sym` with `multiplier` (float) column.
+///
+/// A symbol missing in the reference data or a null multiplier is regarded as the multiplier of 1.
+#[no_mangle]
+pub extern "C" fn enrich_trades(trades: K, refdata: K) -> K {
+    match enrich(&KVal::from_raw(trades), &KVal::from_raw(refdata)) {
+        Ok(enriched) => enriched.to_k(),
+        Err(error) => error.to_k(),
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+//                          Private Functions                           //
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Body of `enrich_trades`.
+fn enrich(trades: &KVal, refdata: &KVal) -> Result<KTable<'static>, KError> {
+    let trades = match trades {
+        KVal::Table(table) => table.project(Some(&TRADE_COLUMNS), None)?,
+        other => return Err(invalid("trades", other, "table")),
+    };
+    let columns = match trades.dict.values.as_ref() {
+        KVal::CompoundList(columns) => columns,
+        _ => unreachable!("projected table has a compound list of columns"),
+    };
+    if let Some((column, _)) = columns
+        .iter()
+        .zip(TRADE_TYPES)
+        .find(|(column, qtype)| column.qtype() != *qtype)
+    {
+        return Err(invalid(
+            "trades",
+            column,
+            "timestamp, symbol, float and long columns",
+        ));
+    }
+    let (time, sym, price, size) = match columns.as_slice() {
+        [KVal::Timestamp(KData::List(time)), KVal::Symbol(KData::List(sym)), KVal::Float(KData::List(price)), KVal::Long(KData::List(size))] => {
+            (time, sym, price, size)
+        }
+        _ => unreachable!("types of the columns have been checked"),
+    };
+    let multipliers = multipliers(refdata)?;
+
+    // Left join the multiplier and fill missing ones.
+    let multiplier = KVal::Float(KData::List(Cow::Owned(
+        sym.iter()
+            .map(|sym| {
+                multipliers
+                    .get(sym.as_str())
+                    .copied()
+                    .unwrap_or(qnull_base::F)
+            })
+            .collect(),
+    )))
+    .coalesce(&KVal::Float(KData::Atom(Cow::Owned(1.0))))?;
+    let multiplier = match multiplier {
+        KVal::Float(KData::List(multiplier)) => multiplier.into_owned(),
+        _ => unreachable!("coalesce keeps the type and the shape"),
+    };
+
+    let notional = price
+        .iter()
+        .zip(size.iter())
+        .zip(multiplier.iter())
+        .map(|((price, size), multiplier)| match *size {
+            qnull_base::J => qnull_base::F,
+            // q evaluates `price * size * multiplier` from the right.
+            size => price * (size as f64 * multiplier),
+        })
+        .collect::<Vec<_>>();
+
+    // Stable sort by time in the same manner as `xasc`.
+    let mut order = (0..time.len()).collect::<Vec<_>>();
+    order.sort_by_key(|index| time[*index]);
+
+    Ok(KTable::new(KDict::new(
+        KVal::Symbol(KData::List(Cow::Owned(
+            ["time", "sym", "price", "size", "multiplier", "notional"]
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        ))),
+        KVal::CompoundList(vec![
+            KVal::Timestamp(KData::List(Cow::Owned(reorder(time, &order)))),
+            KVal::Symbol(KData::List(Cow::Owned(reorder(sym, &order)))),
+            KVal::Float(KData::List(Cow::Owned(reorder(price, &order)))),
+            KVal::Long(KData::List(Cow::Owned(reorder(size, &order)))),
+            KVal::Float(KData::List(Cow::Owned(reorder(&multiplier, &order)))),
+            KVal::Float(KData::List(Cow::Owned(reorder(&notional, &order)))),
+        ]),
+    )))
+}
+
+/// Build a map from a symbol to its multiplier from reference data keyed by `sym`. The first row of a symbol is
+///  used in the same manner as `lj`.
+fn multipliers(refdata: &KVal) -> Result<HashMap<String, f64>, KError> {
+    let (keys, values) = match refdata {
+        KVal::Dictionary(KDict { keys, values, .. }) => match (keys.as_ref(), values.as_ref()) {
+            (KVal::Table(keys), KVal::Table(values)) => (
+                keys.project(Some(&["sym"]), None)?,
+                values.project(Some(&["multiplier"]), None)?,
+            ),
+            _ => return Err(invalid("refdata", refdata, "keyed table")),
+        },
+        other => return Err(invalid("refdata", other, "keyed table")),
+    };
+    let sym = match keys.dict.values.as_ref() {
+        KVal::CompoundList(columns) => match columns.as_slice() {
+            [KVal::Symbol(KData::List(sym))] => sym,
+            [other] => return Err(invalid("refdata", other, "sym column of symbol")),
+            _ => unreachable!("one column is projected"),
+        },
+        _ => unreachable!("projected table has a compound list of columns"),
+    };
+    let multiplier = match values.dict.values.as_ref() {
+        KVal::CompoundList(columns) => match columns.as_slice() {
+            [KVal::Float(KData::List(multiplier))] => multiplier,
+            [other] => return Err(invalid("refdata", other, "multiplier column of float")),
+            _ => unreachable!("one column is projected"),
+        },
+        _ => unreachable!("projected table has a compound list of columns"),
+    };
+    let mut map = HashMap::new();
+    for (index, sym) in sym.iter().enumerate() {
+        map.entry(sym.clone()).or_insert(multiplier[index]);
+    }
+    Ok(map)
+}
+
+/// Reorder elements by indices.
+fn reorder<T: Clone>(list: &[T], order: &[usize]) -> Vec<T> {
+    order.iter().map(|index| list[*index].clone()).collect()
+}
+
+/// Build an error of an invalid argument.
+fn invalid(operator: &'static str, operand: &KVal, expected: &'static str) -> KError {
+    KError::InvalidOperation {
+        operator,
+        operand_type: operand.type_name(),
+        expected: Some(expected),
+    }
+}
@@ -9,10 +9,17 @@
 extern crate kdbplus;
 
 use kdbplus::api::native::*;
+use kdbplus::api::rusty_api::*;
 use kdbplus::api::*;
-use kdbplus::{qattribute, qinf_base, qninf_base, qnull_base, qtype};
+use kdbplus::{kval, qattribute, qinf_base, qninf_base, qnull_base, qtype};
 use libc::{pipe, send};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::ffi::c_void;
+use std::sync::atomic::{AtomicI64, Ordering};
+
+mod enrich;
+mod self_test;
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
 //                          Global Variables                            //
@@ -89,7 +96,7 @@ pub extern "C" fn float_borders(_: K) -> K {
 /// Example of `qnull::C`.
 #[no_mangle]
 pub extern "C" fn char_border(_: K) -> K {
-    new_char(qnull_base::C)
+    new_char_truncating(qnull_base::C)
 }
 
 /// Example of `qnull::S`.
@@ -460,6 +467,12 @@ pub extern "C" fn create_real(_: K) -> K {
     unsafe { ke(0.00324) }
 }
 
+/// Example of `new_real32`.
+#[no_mangle]
+pub extern "C" fn create_real32(_: K) -> K {
+    new_real32(0.00324)
+}
+
 /// Example of `kf`.
 #[no_mangle]
 pub extern "C" fn create_float(_: K) -> K {
@@ -859,10 +872,19 @@ pub extern "C" fn pingpong2(_: K) -> K {
 //++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
 
 #[no_mangle]
+#[allow(deprecated)]
 pub extern "C" fn create_char2(_: K) -> K {
     new_char('t')
 }
 
+#[no_mangle]
+pub extern "C" fn create_char3(_: K) -> K {
+    match try_new_char('t') {
+        Ok(character) => character,
+        Err(error) => error.to_k(),
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn create_symbol2(_: K) -> K {
     new_symbol("symbolic")
@@ -1161,20 +1183,13 @@ pub extern "C" fn probe(planet: K) -> K {
     }
 }
 
-/// Example of `simple_to_compound`.
+/// Example of `kval!`.
 #[no_mangle]
 pub extern "C" fn drift(_: K) -> K {
-    let simple = new_list(qtype::INT_LIST, 2);
-    simple.as_mut_slice::<I>().copy_from_slice(&[12, 34]);
-    let extra = new_list(qtype::COMPOUND_LIST, 2);
-    extra
-        .as_mut_slice::<K>()
-        .copy_from_slice(&[new_symbol("vague"), new_int(-3000)]);
-    let mut compound = simple_to_compound(simple, "");
-    compound.append(extra).unwrap()
+    kval!([12i32, 34i32, "vague", -3000i32]).to_k()
 }
 
-/// Second example of `simple_to_compound`.
+/// Example of `simple_to_compound`.
 #[no_mangle]
 pub extern "C" fn drift2(_: K) -> K {
     let simple = new_list(qtype::ENUM_LIST, 2);
@@ -1184,3 +1199,1157 @@ pub extern "C" fn drift2(_: K) -> K {
     compound.push(new_month(3)).unwrap();
     compound
 }
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+//                              Rusty API                               //
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Foreign %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// The number of `Colony` dropped by q.
+static ABANDONED_COLONIES: AtomicI64 = AtomicI64::new(0);
+
+struct Colony {
+    name: String,
+    settlers: i64,
+}
+
+impl Colony {
+    /// Grow the colony and describe it.
+    fn census(&mut self, newcomers: i64) -> String {
+        self.settlers += newcomers;
+        format!("{} has {} settlers.", self.name, self.settlers)
+    }
+}
+
+impl Drop for Colony {
+    fn drop(&mut self) {
+        ABANDONED_COLONIES.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Example of `Foreign::new`.
+#[no_mangle]
+pub extern "C" fn settle(name: K) -> K {
+    match name.get_symbol() {
+        Ok(name) => Foreign::new(Colony {
+            name: name.to_string(),
+            settlers: 0,
+        }),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `Foreign::new` with another type.
+#[no_mangle]
+pub extern "C" fn settle_planet(_: K) -> K {
+    Foreign::new(Planet::new("mars", 0, false))
+}
+
+/// Example of `Foreign::from_raw_mut`.
+#[no_mangle]
+pub extern "C" fn census(colony: K, newcomers: K) -> K {
+    match Foreign::<Colony>::from_raw_mut(colony) {
+        Ok(colony) => match newcomers.get_long() {
+            Ok(newcomers) => new_string(&colony.census(newcomers)),
+            Err(error) => new_error(error),
+        },
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of dropping a value of `Foreign`.
+#[no_mangle]
+pub extern "C" fn abandoned_colonies(_: K) -> K {
+    new_long(ABANDONED_COLONIES.load(Ordering::SeqCst))
+}
+
+//%% KVal %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Example of `KVal::get`.
+#[no_mangle]
+pub extern "C" fn pick(list: K, index: K) -> K {
+    match index.get_long() {
+        Ok(index) if index >= 0 => match KVal::from_raw(list).get(index as usize) {
+            Some(element) => element.to_k(),
+            None => new_error("index out of bounds\0"),
+        },
+        _ => new_error("index must be a non-negative long\0"),
+    }
+}
+
+/// Example of `KVal::get` with an enum list.
+#[no_mangle]
+pub extern "C" fn pick_enum(index: K) -> K {
+    let list = KVal::Enum(
+        KData::List(Cow::Owned(vec![2_i64, 0, 1])),
+        Some("enum".to_string()),
+    );
+    match index.get_long() {
+        Ok(index) if index >= 0 => match list.get(index as usize) {
+            Some(element) => element.to_k(),
+            None => new_error("index out of bounds\0"),
+        },
+        _ => new_error("index must be a non-negative long\0"),
+    }
+}
+
+/// Example of `FromIterator` of `KVal`.
+#[no_mangle]
+pub extern "C" fn collect_compound_list(int: K) -> K {
+    (0..5)
+        .map(|i| KVal::Long(KData::Atom(Cow::Owned(i))))
+        .chain(KVal::from_raw(int))
+        .collect::<KVal>()
+        .to_k()
+}
+
+/// Example of `KVal::coalesce`.
+#[no_mangle]
+pub extern "C" fn fill_gap(primary: K, fallback: K) -> K {
+    match KVal::from_raw(primary).coalesce(&KVal::from_raw(fallback)) {
+        Ok(filled) => filled.to_k(),
+        Err(error) => error.to_k(),
+    }
+}
+
+/// Example of `KVal::slice`.
+#[no_mangle]
+pub extern "C" fn window(list: K, start: K, end: K) -> K {
+    match (start.get_long(), end.get_long()) {
+        (Ok(start), Ok(end)) if start >= 0 && end >= 0 => {
+            match KVal::from_raw(list).slice(start as usize..end as usize) {
+                Ok(window) => window.to_k(),
+                Err(error) => new_error(error),
+            }
+        }
+        _ => new_error("indices must be non-negative longs\0"),
+    }
+}
+
+/// Example of `peek_table_rows`.
+#[no_mangle]
+pub extern "C" fn small_table_only(table: K) -> K {
+    match peek_table_rows(table) {
+        Ok(rows) if rows > 10_000_000 => new_error("table is too large\0"),
+        Ok(_) => increment_reference_count(table),
+        Err(error) => error.to_k(),
+    }
+}
+
+/// Example of `peek_table_schema`.
+#[no_mangle]
+pub extern "C" fn column_types(table: K) -> K {
+    match peek_table_schema(table) {
+        Ok(schema) => {
+            let types = schema
+                .iter()
+                .map(|(_, qtype)| *qtype as i16)
+                .collect::<Vec<_>>();
+            KVal::Short(KData::List(Cow::Owned(types))).to_k()
+        }
+        Err(error) => error.to_k(),
+    }
+}
+
+/// Example of `insert_into`.
+#[no_mangle]
+pub extern "C" fn insert_ticks(table: K, rows: K) -> K {
+    match (table.get_symbol(), rows.get_long()) {
+        (Ok(table), Ok(rows)) if rows >= 0 => {
+            let rows = KTable::new(KDict::new(
+                KVal::Symbol(KData::List(Cow::Owned(vec![
+                    "sym".to_string(),
+                    "price".to_string(),
+                ]))),
+                KVal::CompoundList(vec![
+                    KVal::Symbol(KData::List(Cow::Owned(vec![
+                        "a".to_string();
+                        rows as usize
+                    ]))),
+                    KVal::Float(KData::List(Cow::Owned(
+                        (0..rows).map(|i| i as f64 + 0.5).collect(),
+                    ))),
+                ]),
+            ));
+            match insert_into(table, rows) {
+                Ok(inserted) => new_long(inserted),
+                Err(error) => error.to_k(),
+            }
+        }
+        _ => new_error("table must be a symbol and rows must be a non-negative long\0"),
+    }
+}
+
+/// Example of `upsert_keyed`.
+#[no_mangle]
+pub extern "C" fn upsert_quotes(table: K, bid: K) -> K {
+    match (table.get_symbol(), bid.get_float()) {
+        (Ok(table), Ok(bid)) => {
+            let keys = KTable::new(KDict::new(
+                KVal::Symbol(KData::List(Cow::Owned(vec!["sym".to_string()]))),
+                KVal::CompoundList(vec![KVal::Symbol(KData::List(Cow::Owned(vec![
+                    "a".to_string()
+                ])))]),
+            ));
+            let values = KTable::new(KDict::new(
+                KVal::Symbol(KData::List(Cow::Owned(vec!["bid".to_string()]))),
+                KVal::CompoundList(vec![KVal::Float(KData::List(Cow::Owned(vec![bid])))]),
+            ));
+            match upsert_keyed(table, KDict::new(KVal::Table(keys), KVal::Table(values))) {
+                Ok(upserted) => new_long(upserted),
+                Err(error) => error.to_k(),
+            }
+        }
+        _ => new_error("table must be a symbol and bid must be a float\0"),
+    }
+}
+
+//%% Calendar %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Example of `date_add_months`.
+#[no_mangle]
+pub extern "C" fn shift_months(dates: K, months: K) -> K {
+    match months.get_long() {
+        Ok(months) => match date_add_months(&KVal::from_raw(dates), months as i32) {
+            Ok(shifted) => shifted.to_k(),
+            Err(error) => error.to_k(),
+        },
+        Err(_) => new_error("months must be a long\0"),
+    }
+}
+
+/// Example of `end_of_month`.
+#[no_mangle]
+pub extern "C" fn month_end(dates: K) -> K {
+    match end_of_month(&KVal::from_raw(dates)) {
+        Ok(ends) => ends.to_k(),
+        Err(error) => error.to_k(),
+    }
+}
+
+/// Example of `day_of_week`.
+#[no_mangle]
+pub extern "C" fn weekday(dates: K) -> K {
+    match day_of_week(&KVal::from_raw(dates)) {
+        Ok(days) => days.to_k(),
+        Err(error) => error.to_k(),
+    }
+}
+
+/// Example of `date_to_ymd`.
+#[no_mangle]
+pub extern "C" fn to_ymd(dates: K) -> K {
+    match date_to_ymd(&KVal::from_raw(dates)) {
+        Ok(ymd) => ymd.to_k(),
+        Err(error) => error.to_k(),
+    }
+}
+
+/// Example of `ymd_to_date`.
+#[no_mangle]
+pub extern "C" fn from_ymd(ymd: K) -> K {
+    match ymd_to_date(&KVal::from_raw(ymd)) {
+        Ok(dates) => dates.to_k(),
+        Err(error) => error.to_k(),
+    }
+}
+
+//%% Round Trip %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Example of `KVal::from_raw` and `KVal::to_k`.
+#[no_mangle]
+pub extern "C" fn mirror(value: K) -> K {
+    KVal::from_raw(value).to_k()
+}
+
+/// Example of `KVal::deep_clone`.
+#[no_mangle]
+pub extern "C" fn doubled(list: K) -> K {
+    let mut copy = KVal::from_raw(list).deep_clone();
+    match &mut copy {
+        KVal::Long(KData::List(longs)) => longs.to_mut().iter_mut().for_each(|long| *long *= 2),
+        _ => return new_error("not a long list\0"),
+    }
+    copy.to_k()
+}
+
+//%% Cast %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Example of `KVal::cast`.
+#[no_mangle]
+pub extern "C" fn sum_as_long(list: K) -> K {
+    match KVal::from_raw(list).cast(qtype::LONG_LIST) {
+        Ok(KVal::Long(KData::List(longs))) => new_long(longs.iter().sum()),
+        Ok(_) => new_error("not a list\0"),
+        Err(error) => error.to_k(),
+    }
+}
+
+/// Another example of `KVal::cast`.
+#[no_mangle]
+pub extern "C" fn cast_to(value: K, target: K) -> K {
+    match target.get_short() {
+        Ok(target) => match KVal::from_raw(value).cast(target as i8) {
+            Ok(cast) => cast.to_k(),
+            Err(error) => error.to_k(),
+        },
+        Err(_) => new_error("target must be a short\0"),
+    }
+}
+
+//%% Errors %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Example of `errors`.
+#[no_mangle]
+pub extern "C" fn only_long_list(list: K) -> K {
+    match KVal::from_raw(list) {
+        KVal::Long(KData::List(_)) => increment_reference_count(list),
+        _ => new_error(errors::TYPE),
+    }
+}
+
+//%% Compound %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Example of `KVal::to_simple_list`.
+#[no_mangle]
+pub extern "C" fn collapse(list: K) -> K {
+    match KVal::from_raw(list).to_simple_list() {
+        Ok(simple) => simple.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+//%% Null %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Example of `KVal::null_of`, `KVal::inf_of` and `KVal::ninf_of`.
+#[no_mangle]
+pub extern "C" fn borders_of(qtype: K) -> K {
+    let borders = match qtype.get_short() {
+        Ok(qtype) => [
+            KVal::null_of(qtype as i8),
+            KVal::inf_of(qtype as i8),
+            KVal::ninf_of(qtype as i8),
+        ],
+        Err(_) => return new_error("type must be a short\0"),
+    };
+    match borders.into_iter().collect::<Result<KVal, _>>() {
+        Ok(borders) => borders.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KVal::is_null_atom`.
+#[no_mangle]
+pub extern "C" fn drop_nulls(list: K) -> K {
+    let list = KVal::from_raw(list);
+    // Only a simple list is accepted.
+    if list.qtype() <= 0 || list.qtype() > qtype::ENUM_LIST {
+        return new_error(errors::TYPE);
+    }
+    let kept = list
+        .iter()
+        .filter(|element| !element.is_null_atom())
+        .collect::<KVal>();
+    // An empty result cannot tell its type. Keep the type of the argument.
+    let simple = match kept.is_empty() {
+        true => list.slice(0..0),
+        false => kept.to_simple_list(),
+    };
+    match simple {
+        Ok(simple) => simple.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+//%% Join %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Example of `KVal::join`.
+#[no_mangle]
+pub extern "C" fn joined(base: K, other: K) -> K {
+    match KVal::join(KVal::from_raw(base), KVal::from_raw(other)) {
+        Ok(joined) => joined.to_k(),
+        Err(error) => error.to_k(),
+    }
+}
+
+/// Example of `KVal::append`.
+#[no_mangle]
+pub extern "C" fn append_to(list: K, other: K) -> K {
+    let mut list = KVal::from_raw(list);
+    match list.append(KVal::from_raw(other)) {
+        Ok(()) => list.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KVal::remove`.
+#[no_mangle]
+pub extern "C" fn take_out(list: K, index: K) -> K {
+    let mut list = KVal::from_raw(list);
+    match index.get_long() {
+        Ok(index) if index >= 0 => match list.remove(index as usize) {
+            Ok(_) => list.to_k(),
+            Err(error) => new_error(error),
+        },
+        _ => new_error("index must be a non-negative long\0"),
+    }
+}
+
+/// Example of `KVal::as_string_vec`.
+#[no_mangle]
+pub extern "C" fn longest_line(lines: K) -> K {
+    match KVal::from_raw(lines).as_string_vec() {
+        Ok(lines) => match lines.into_iter().max_by_key(|line| line.len()) {
+            Some(line) => new_string(&line),
+            None => new_string(""),
+        },
+        Err(error) => error.to_k(),
+    }
+}
+
+/// Example of `KVal::join_strings`.
+#[no_mangle]
+pub extern "C" fn unlines(lines: K) -> K {
+    match KVal::from_raw(lines).join_strings("\n") {
+        Ok(text) => text.to_k(),
+        Err(error) => error.to_k(),
+    }
+}
+
+/// Example of `LazyDict`.
+#[no_mangle]
+pub extern "C" fn entry_of(namespace: K, name: K) -> K {
+    let namespace = LazyDict::from_raw(namespace);
+    match namespace.get(name.get_symbol().unwrap_or("")) {
+        Some(value) => value.to_k(),
+        None => new_error("no such entry\0"),
+    }
+}
+
+/// Example of `KVal::insert`.
+#[no_mangle]
+pub extern "C" fn insert_at(list: K, index: K, value: K) -> K {
+    let mut list = KVal::from_raw(list);
+    match index.get_long() {
+        Ok(index) if index >= 0 => match list.insert(index as usize, KVal::from_raw(value)) {
+            Ok(()) => list.to_k(),
+            Err(error) => new_error(error),
+        },
+        _ => new_error("index must be a non-negative long\0"),
+    }
+}
+
+/// Example of `KVal::reverse`.
+#[no_mangle]
+pub extern "C" fn reversed(list: K) -> K {
+    let mut list = KVal::from_raw(list);
+    match list.reverse() {
+        Ok(()) => list.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KVal::rotate`.
+#[no_mangle]
+pub extern "C" fn rotated(n: K, list: K) -> K {
+    let mut list = KVal::from_raw(list);
+    match n.get_long() {
+        Ok(n) => match list.rotate(n as isize) {
+            Ok(()) => list.to_k(),
+            Err(error) => new_error(error),
+        },
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KVal::sort`.
+#[no_mangle]
+pub extern "C" fn sorted(list: K) -> K {
+    match KVal::from_raw(list).sort() {
+        Ok(sorted) => sorted.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KVal::distinct`.
+#[no_mangle]
+pub extern "C" fn distinct_of(list: K) -> K {
+    match KVal::from_raw(list).distinct() {
+        Ok(distinct) => distinct.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KTable::render`.
+#[no_mangle]
+pub extern "C" fn rendered(table: K, rows: K, width: K) -> K {
+    match (KVal::from_raw(table), rows.get_long(), width.get_long()) {
+        (KVal::Table(table), Ok(rows), Ok(width)) if rows >= 0 && width >= 0 => {
+            new_string(&table.render(rows as usize, width as usize))
+        }
+        (_, Err(error), _) | (_, _, Err(error)) => new_error(error),
+        _ => new_error("not a table\0"),
+    }
+}
+
+/// Example of `KVal::find_all`.
+#[no_mangle]
+pub extern "C" fn indices_of(list: K, needle: K) -> K {
+    KVal::from_raw(list)
+        .find_all(&KVal::from_raw(needle))
+        .to_k()
+}
+
+/// Example of `try_new_byte` and `try_new_short`.
+#[no_mangle]
+pub extern "C" fn checked_narrow(value: K) -> K {
+    let narrowed = match value.get_type() {
+        qtype::INT_ATOM => value.get_int().map(try_new_short),
+        qtype::LONG_ATOM => value.get_long().map(|value| try_new_byte(value as I)),
+        _ => return new_error("type\0"),
+    };
+    match narrowed {
+        Ok(Ok(narrowed)) => narrowed,
+        Ok(Err(error)) => error.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KVal::take`.
+#[no_mangle]
+pub extern "C" fn taken(n: K, list: K) -> K {
+    match n.get_long() {
+        Ok(n) => match KVal::from_raw(list).take(n) {
+            Ok(taken) => taken.to_k(),
+            Err(error) => new_error(error),
+        },
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KVal::drop`.
+#[no_mangle]
+pub extern "C" fn dropped(n: K, list: K) -> K {
+    match n.get_long() {
+        Ok(n) => match KVal::from_raw(list).drop(n) {
+            Ok(dropped) => dropped.to_k(),
+            Err(error) => new_error(error),
+        },
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KVal::split_at`.
+#[no_mangle]
+pub extern "C" fn halves(mid: K, list: K) -> K {
+    match mid.get_long().map(usize::try_from) {
+        Ok(Ok(mid)) => match KVal::from_raw(list).split_at(mid) {
+            Ok((head, tail)) => KVal::CompoundList(vec![head, tail]).to_k(),
+            Err(error) => new_error(error),
+        },
+        Ok(Err(_)) => new_error(errors::INDEX_OUT_OF_BOUNDS),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KVal::chunks`.
+#[no_mangle]
+pub extern "C" fn chunked(size: K, list: K) -> K {
+    match size.get_long() {
+        Ok(size) => {
+            let list = KVal::from_raw(list);
+            let chunks = match list.chunks(usize::try_from(size).unwrap_or(0)) {
+                Ok(chunks) => chunks.collect(),
+                Err(error) => return new_error(error),
+            };
+            KVal::CompoundList(chunks).to_k()
+        }
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KVal::concat`.
+#[no_mangle]
+pub extern "C" fn razed(parts: K) -> K {
+    match KVal::from_raw(parts) {
+        KVal::CompoundList(parts) => match KVal::concat(parts) {
+            Ok(razed) => razed.to_k(),
+            Err(error) => new_error(error),
+        },
+        _ => new_error(errors::TYPE),
+    }
+}
+
+/// Example of `new_error_chained`.
+#[no_mangle]
+pub extern "C" fn load_step(step: K, message: K) -> K {
+    match (step.get_symbol(), message.get_str()) {
+        (Ok(step), Ok(message)) => new_error_chained(step, message),
+        _ => new_error(errors::TYPE),
+    }
+}
+
+/// Example of `KVal::from_raw_tracked`.
+#[no_mangle]
+pub extern "C" fn echo(list: K) -> K {
+    KVal::from_raw_tracked(list).to_k()
+}
+
+/// Example of `KVal::to_k` of an enum atom or list. The null symbol is taken as no source.
+#[no_mangle]
+pub extern "C" fn enumerated(source: K, indices: K) -> K {
+    match (source.get_symbol(), KVal::from_raw(indices)) {
+        (Ok(source), KVal::Long(indices)) => {
+            let source = (!source.is_empty()).then(|| source.to_string());
+            KVal::Enum(indices, source).to_k()
+        }
+        _ => new_error(errors::TYPE),
+    }
+}
+
+/// Example of `KVal::from_json`.
+#[no_mangle]
+pub extern "C" fn load_config(path: K) -> K {
+    let json = match path.get_str().map(std::fs::read_to_string) {
+        Ok(Ok(json)) => json,
+        _ => return new_error("cannot read the file\0"),
+    };
+    match KVal::from_json(&json) {
+        Ok(config) => config.to_k(),
+        Err(error) => new_error_chained("invalid config", &error),
+    }
+}
+
+/// Example of `KVal::to_q_literal`.
+#[no_mangle]
+pub extern "C" fn to_literal(value: K) -> K {
+    new_string(&KVal::from_raw(value).to_q_literal())
+}
+
+/// Example of `KData::map_in_place`.
+#[no_mangle]
+pub extern "C" fn scale(prices: K) -> K {
+    match KVal::from_raw(prices) {
+        KVal::Float(mut prices) => {
+            prices.map_in_place(|price| *price *= 2.0);
+            KVal::Float(prices).to_k()
+        }
+        _ => new_error(errors::TYPE),
+    }
+}
+
+/// Example of `KVal::commit`.
+#[no_mangle]
+pub extern "C" fn double_in_place(list: K) -> K {
+    let mut value = KVal::from_raw(list);
+    if let KVal::Long(data) = &mut value {
+        data.map_in_place(|long| *long *= 2);
+    }
+    match value.commit(list) {
+        Ok(()) => KNULL,
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KVal::commit` on a column of a table.
+#[no_mangle]
+pub extern "C" fn scale_column(trades: K, column: K, factor: K) -> K {
+    let (column, factor) = match (column.get_symbol(), factor.get_float()) {
+        (Ok(column), Ok(factor)) => (column, factor),
+        _ => return new_error(errors::TYPE),
+    };
+    let mut table = match KVal::from_raw(trades) {
+        KVal::Table(table) => table,
+        _ => return new_error(errors::TYPE),
+    };
+    let position = match table.dict.keys.index_of(&kval!(column)) {
+        Some(position) => position,
+        None => return new_error("no such column\0"),
+    };
+    match table.dict.values.as_mut() {
+        KVal::CompoundList(columns) => match &mut columns[position] {
+            KVal::Float(prices) => prices.map_in_place(|price| *price *= factor),
+            _ => return new_error(errors::TYPE),
+        },
+        _ => return new_error(errors::TYPE),
+    }
+    match KVal::Table(table).commit(trades) {
+        Ok(()) => KNULL,
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KVal::sum`, `KVal::min`, `KVal::max` and `KVal::mean`.
+#[no_mangle]
+pub extern "C" fn summarize(list: K) -> K {
+    let list = KVal::from_raw(list);
+    match [list.sum(), list.min(), list.max(), list.mean()]
+        .into_iter()
+        .collect::<Result<KVal, _>>()
+    {
+        Ok(summary) => summary.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KVal::bin`.
+#[no_mangle]
+pub extern "C" fn bin_of(list: K, needle: K) -> K {
+    match KVal::from_raw(list).bin(&KVal::from_raw(needle)) {
+        Ok(index) => new_long(index),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KDict::get_symbol`, `KDict::get_long`, `KDict::get_float` and `KDict::get_table`.
+#[no_mangle]
+pub extern "C" fn describe_feed(config: K) -> K {
+    let config = match KVal::from_raw(config) {
+        KVal::Dictionary(config) => config,
+        _ => return new_error("config must be a dictionary\0"),
+    };
+    let describe = || -> Result<String, &'static str> {
+        Ok(format!(
+            "{}:{} every {}s for {} instruments",
+            config.get_symbol("host")?,
+            config.get_long("port")?,
+            config.get_float("interval")?,
+            config.get_table("instruments")?.len()
+        ))
+    };
+    match describe() {
+        Ok(description) => new_string(&description),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KDict::upsert`.
+#[no_mangle]
+pub extern "C" fn upsert_all(dictionary: K, keys: K, values: K) -> K {
+    let mut dictionary = KDict::from_raw(dictionary);
+    let (keys, values) = (KVal::from_raw(keys), KVal::from_raw(values));
+    for (key, value) in keys.iter().zip(values.iter()) {
+        if let Err(error) = dictionary.upsert(key, value) {
+            return new_error(error);
+        }
+    }
+    dictionary.to_k()
+}
+
+/// Example of `KDict::insert` and `KDict::remove`.
+#[no_mangle]
+pub extern "C" fn rekey(dictionary: K, old: K, new: K) -> K {
+    let mut dictionary = KDict::from_raw(dictionary);
+    let value = match dictionary.remove(&KVal::from_raw(old)) {
+        Some(value) => value,
+        None => return new_error(errors::KEY_NOT_FOUND),
+    };
+    match dictionary.insert(KVal::from_raw(new), value) {
+        Ok(None) => dictionary.to_k(),
+        Ok(Some(_)) => new_error("key already exists\0"),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KDict::merge`.
+#[no_mangle]
+pub extern "C" fn merge_dicts(left: K, right: K) -> K {
+    match KDict::from_raw(left).merge(KDict::from_raw(right)) {
+        Ok(merged) => merged.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KDict::merge_with`.
+#[no_mangle]
+pub extern "C" fn fill_join(left: K, right: K) -> K {
+    let fill = |left: KVal<'static>, right: KVal<'static>| {
+        if right.is_null_atom() {
+            left
+        } else {
+            right
+        }
+    };
+    match KDict::from_raw(left).merge_with(KDict::from_raw(right), fill) {
+        Ok(merged) => merged.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KDict::iter`.
+#[no_mangle]
+pub extern "C" fn value_types(dictionary: K) -> K {
+    let dictionary = KDict::from_raw(dictionary);
+    let (keys, types): (Vec<KVal>, Vec<i16>) = dictionary
+        .iter()
+        .map(|(key, value)| (key, value.qtype() as i16))
+        .unzip();
+    match KVal::concat(keys) {
+        Ok(keys) => KDict::new(keys, KVal::Short(KData::List(Cow::Owned(types)))).to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KDict::to_hashmap` and `TryFrom<HashMap<String, KVal>>` for `KDict`.
+#[no_mangle]
+pub extern "C" fn sort_by_key(dictionary: K) -> K {
+    let map = match KDict::from_raw(dictionary).to_hashmap::<String, KVal>() {
+        Ok(map) => map,
+        Err(error) => return new_error(error),
+    };
+    match KDict::try_from(map) {
+        Ok(sorted) => sorted.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KDict::contains_key`.
+#[no_mangle]
+pub extern "C" fn has_option(options: K, name: K) -> K {
+    match (KVal::from_raw(options), name.get_symbol()) {
+        (KVal::Dictionary(options), Ok(name)) => new_bool(options.contains_key(name) as i32),
+        _ => new_error("expected a dictionary and a symbol\0"),
+    }
+}
+
+/// Example of `KDict::into_sorted`.
+#[no_mangle]
+pub extern "C" fn step_function(dictionary: K) -> K {
+    match KDict::from_raw(dictionary).into_sorted() {
+        Ok(sorted) => sorted.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KDict::is_sorted`.
+#[no_mangle]
+pub extern "C" fn echo_dictionary(dictionary: K) -> K {
+    let dictionary = KDict::from_raw(dictionary);
+    if !dictionary.is_sorted() && dictionary.get("sorted").is_some() {
+        return new_error("sorted dictionary is expected\0");
+    }
+    dictionary.to_k()
+}
+
+/// Example of `LazyTable::get_column`.
+#[no_mangle]
+pub extern "C" fn lazy_column(table: K, name: K) -> K {
+    match (KVal::from_raw_lazy(table), name.get_symbol()) {
+        (LazyKVal::Table(table), Ok(name)) => match table.get_column(name) {
+            Some(column) => column.to_k(),
+            None => new_error("no such column\0"),
+        },
+        _ => new_error("expected a table and a symbol\0"),
+    }
+}
+
+/// Example of `KDict::get_in_or`.
+#[no_mangle]
+pub extern "C" fn connection_of(config: K) -> K {
+    let config = KDict::from_raw(config);
+    let host = config.get_in_or(&["conn", "host"], String::from("localhost"));
+    let port = config.get_in_or(&["conn", "port"], 5000_i64);
+    new_string(&format!("{}:{}", host, port))
+}
+
+/// Example of `KDict::filter` and `KDict::map_values`.
+#[no_mangle]
+pub extern "C" fn double_prefixed(dictionary: K, prefix: K) -> K {
+    let prefix = match prefix.get_symbol() {
+        Ok(prefix) => prefix,
+        Err(error) => return new_error(error),
+    };
+    let dictionary = KDict::from_raw(dictionary);
+    let selected = dictionary
+        .filter(|key, _| matches!(key, KVal::Symbol(KData::Atom(key)) if key.starts_with(prefix)));
+    let doubled = selected.map_values(|value| match value {
+        KVal::Long(KData::Atom(long)) => kval!(*long * 2),
+        KVal::Float(KData::Atom(float)) => kval!(*float * 2.0),
+        other => other,
+    });
+    match doubled {
+        Ok(doubled) => doubled.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KTable::get_column_by_name`.
+#[no_mangle]
+pub extern "C" fn pick_column(table: K, name: K) -> K {
+    let table = KTable::from_raw(table);
+    match table.get_column_by_name(name.get_symbol().unwrap_or(""), Some("sym")) {
+        Ok(column) => column.to_k(),
+        Err(error) => error.to_k(),
+    }
+}
+
+/// Example of `KTable::from_columns`.
+#[no_mangle]
+pub extern "C" fn zip_columns(names: K, columns: K) -> K {
+    let (names, columns) = match (KVal::from_raw(names), KVal::from_raw(columns)) {
+        (KVal::Symbol(KData::List(names)), KVal::CompoundList(columns)) => (names, columns),
+        _ => return new_error("expected a symbol list and a compound list\0"),
+    };
+    match KTable::from_columns(names.iter().map(String::as_str).zip(columns).collect()) {
+        Ok(table) => table.to_k(),
+        Err(error) => error.to_k(),
+    }
+}
+
+/// Example of `KTable::append_row`.
+#[no_mangle]
+pub extern "C" fn accumulate_rows(table: K, rows: K) -> K {
+    let mut table = KTable::from_raw(table);
+    for row in KVal::from_raw(rows) {
+        if let Err(error) = table.append_row(row) {
+            return error.to_k();
+        }
+    }
+    table.to_k()
+}
+
+/// Example of `KTable::append`.
+#[no_mangle]
+pub extern "C" fn append_tables(table: K, other: K) -> K {
+    match KTable::from_raw(table).append(KTable::from_raw(other)) {
+        Ok(appended) => appended.to_k(),
+        Err(error) => error.to_k(),
+    }
+}
+
+/// Example of `KTable::union`.
+#[no_mangle]
+pub extern "C" fn union_tables(table: K, other: K) -> K {
+    match KTable::from_raw(table).union(KTable::from_raw(other)) {
+        Ok(union) => union.to_k(),
+        Err(error) => error.to_k(),
+    }
+}
+
+/// Example of `KTable::get_rows`.
+#[no_mangle]
+pub extern "C" fn page(table: K, start: K, size: K) -> K {
+    match (start.get_long(), size.get_long()) {
+        (Ok(start), Ok(size)) if start >= 0 && size >= 0 => {
+            let table = KTable::from_raw(table);
+            let end = table.len().min((start + size) as usize);
+            match table.get_rows(start as usize..end) {
+                Ok(page) => page.to_k(),
+                Err(error) => new_error(error),
+            }
+        }
+        _ => new_error("start and size must be non-negative longs\0"),
+    }
+}
+
+/// Example of `KTable::take_rows`.
+#[no_mangle]
+pub extern "C" fn pick_rows(table: K, indices: K) -> K {
+    match KVal::from_raw(indices) {
+        KVal::Long(indices) => match KTable::from_raw(table).take_rows(indices.as_slice()) {
+            Ok(picked) => picked.to_k(),
+            Err(error) => new_error(error),
+        },
+        _ => new_error("indices must be longs\0"),
+    }
+}
+
+/// Example of `KTable::get_cell`.
+#[no_mangle]
+pub extern "C" fn cell(table: K, row: K, column: K) -> K {
+    let table = KTable::from_raw(table);
+    match table.get_cell(
+        row.get_long().unwrap_or(-1),
+        column.get_symbol().unwrap_or(""),
+        Some("sym"),
+    ) {
+        Ok(cell) => cell.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KTable::sort_by`.
+#[no_mangle]
+pub extern "C" fn sort_table(table: K, columns: K, descending: K) -> K {
+    let table = KTable::from_raw(table);
+    let (columns, descending) = match (KVal::from_raw(columns), KVal::from_raw(descending)) {
+        (KVal::Symbol(KData::List(columns)), KVal::Bool(KData::List(descending))) => {
+            (columns, descending)
+        }
+        _ => return new_error("expected a symbol list and a bool list\0"),
+    };
+    let keys = columns
+        .iter()
+        .map(String::as_str)
+        .zip(descending.iter().copied())
+        .collect::<Vec<_>>();
+    match table.sort_by(&keys) {
+        Ok(sorted) => sorted.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KTable::where_indices`.
+#[no_mangle]
+pub extern "C" fn price_above(table: K, threshold: K) -> K {
+    let table = KTable::from_raw(table);
+    let (prices, threshold) = match (table.get_column("price"), threshold.get_float()) {
+        (Some(KVal::Float(prices)), Ok(threshold)) => (prices, threshold),
+        _ => return new_error("expected a float price column and a float\0"),
+    };
+    let indices = prices
+        .iter()
+        .enumerate()
+        .filter_map(|(i, price)| (*price > threshold).then_some(i as i64))
+        .collect::<Vec<_>>();
+    match table.where_indices(&indices) {
+        Ok(selected) => selected.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KTable::filter`.
+#[no_mangle]
+pub extern "C" fn rows_above(table: K, threshold: K) -> K {
+    let threshold = match threshold.get_float() {
+        Ok(threshold) => threshold,
+        Err(error) => return new_error(error),
+    };
+    let table = KTable::from_raw(table);
+    let selected = table.filter(|row| match row {
+        KVal::Dictionary(row) => row.get_float("price").is_ok_and(|price| price > threshold),
+        _ => false,
+    });
+    match selected {
+        Ok(selected) => selected.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KTable::left_join` and `KTable::inner_join`.
+#[no_mangle]
+pub extern "C" fn join_on(table: K, other: K, on: K, inner: K) -> K {
+    let (table, other) = (KTable::from_raw(table), KTable::from_raw(other));
+    let on = match KVal::from_raw(on) {
+        KVal::Symbol(KData::List(on)) => on.into_owned(),
+        _ => return new_error("keys must be a symbol list\0"),
+    };
+    let on = on.iter().map(String::as_str).collect::<Vec<_>>();
+    let joined = match inner.get_bool() {
+        Ok(true) => table.inner_join(&other, &on),
+        _ => table.left_join(&other, &on),
+    };
+    match joined {
+        Ok(joined) => joined.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KTable::enkey`.
+#[no_mangle]
+pub extern "C" fn keyed_by(table: K, n: K) -> K {
+    match n.get_long() {
+        Ok(n) if n >= 0 => match KTable::from_raw(table).enkey(n as usize) {
+            Ok(keyed) => keyed.to_k(),
+            Err(error) => new_error(error),
+        },
+        _ => new_error("n must be a non-negative long\0"),
+    }
+}
+
+/// Example of `KKeyedTable::unkey`.
+#[no_mangle]
+pub extern "C" fn unkeyed(keyed: K) -> K {
+    match KKeyedTable::try_from(KVal::from_raw(keyed)) {
+        Ok(keyed) => keyed.unkey().to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KKeyedTable::lookup`.
+#[no_mangle]
+pub extern "C" fn find_key(keyed: K, key: K) -> K {
+    match KKeyedTable::from_raw(keyed).lookup(&KVal::from_raw(key)) {
+        Some(row) => new_long(row as i64),
+        None => new_long(-1),
+    }
+}
+
+/// Example of `KTable::upsert`.
+#[no_mangle]
+pub extern "C" fn upsert_rows(table: K, key_cols: K, rows: K) -> K {
+    let mut table = KTable::from_raw(table);
+    let key_cols = match KVal::from_raw(key_cols) {
+        KVal::Symbol(KData::List(key_cols)) => key_cols.into_owned(),
+        _ => return new_error("keys must be a symbol list\0"),
+    };
+    let key_cols = key_cols.iter().map(String::as_str).collect::<Vec<_>>();
+    match table.upsert(&key_cols, KTable::from_raw(rows)) {
+        Ok(()) => table.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KKeyedTable::upsert`.
+#[no_mangle]
+pub extern "C" fn upsert_keyed_rows(keyed: K, rows: K) -> K {
+    let mut keyed = KKeyedTable::from_raw(keyed);
+    match keyed.upsert(KTable::from_raw(rows)) {
+        Ok(()) => keyed.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Row of a table of fills used by `fills_round_trip`.
+#[derive(QRow)]
+struct Fill {
+    #[q(type = "timestamp")]
+    time: i64,
+    sym: String,
+    price: Option<f64>,
+    size: Option<i64>,
+    #[q(type = "string")]
+    venue: Option<String>,
+}
+
+/// Example of `#[derive(QRow)]`.
+#[no_mangle]
+pub extern "C" fn fills_round_trip(table: K) -> K {
+    match Fill::from_table(&KTable::from_raw(table)) {
+        Ok(fills) => Fill::table_from_slice(&fills).to_k(),
+        Err(_) => new_error("not a table of fills\0"),
+    }
+}
+
+/// Example of `KTable::column_as`.
+#[no_mangle]
+pub extern "C" fn total_size(table: K) -> K {
+    match KTable::from_raw(table).column_as::<i64>("size") {
+        Ok(sizes) => new_long(sizes.iter().sum()),
+        Err(error) => error.to_k(),
+    }
+}
+
+/// Example of `KTable::get_row_with_sources`.
+#[no_mangle]
+pub extern "C" fn row_at(table: K, index: K) -> K {
+    let table = match KTable::try_from_raw(table) {
+        Ok(table) => table,
+        Err(error) => return error.to_k(),
+    };
+    let sources = HashMap::from([("sym", "sym")]);
+    match table.get_row_with_sources(index.get_long().unwrap_or(-1), &sources) {
+        Ok(row) => row.to_k(),
+        Err(error) => new_error(error),
+    }
+}
+
+/// Example of `KTable::try_from_raw`.
+#[no_mangle]
+pub extern "C" fn count_rows(table: K) -> K {
+    match KTable::try_from_raw(table) {
+        Ok(table) => new_long(table.len() as i64),
+        Err(error) => error.to_k(),
+    }
+}
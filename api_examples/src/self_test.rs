@@ -0,0 +1,194 @@
+//! Integrity check of the library in the q process loading it. `self_test` runs built-in checks of the parts of the
+//!  C API the library relies on and returns a table of the results, so that a deployed library can be verified in
+//!  the target q version without running the test suite.
+//!
+//! The checks allocate only small objects which are freed before returning and do not modify any global variable
+//!  of q, so `self_test` is safe to run in a production process.
+//!
+//! ```q
+//! q)self_test: `libapi_examples 2: (`self_test; 1);
+//! q)select from self_test[] where not passed
+//! check passed message
+//! --------------------
+//! ```
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+//                            Load Libraries                            //
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use kdbplus::api::native;
+use kdbplus::api::rusty_api::*;
+use kdbplus::api::*;
+use kdbplus::{qnull_base, qtype};
+use std::borrow::Cow;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+//                             Self Test                                //
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Run the built-in checks and return a table of `check` (symbol), `passed` (bool) and `message` (string), one row
+///  per check. `message` describes a failure and is empty for a passed check.
+#[no_mangle]
+pub extern "C" fn self_test(_: K) -> K {
+    let mut results = vec![("layout", check_layout())];
+    results.extend(
+        samples()
+            .into_iter()
+            .map(|(check, sample)| (check, check_round_trip(&sample))),
+    );
+    results.push(("eval", check_eval()));
+    results.push(("error trap", check_error_trap()));
+
+    KTable::new(KDict::new(
+        KVal::Symbol(KData::List(Cow::Owned(
+            ["check", "passed", "message"]
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        ))),
+        KVal::CompoundList(vec![
+            KVal::Symbol(KData::List(Cow::Owned(
+                results.iter().map(|(check, _)| check.to_string()).collect(),
+            ))),
+            KVal::Bool(KData::List(Cow::Owned(
+                results.iter().map(|(_, result)| result.is_ok()).collect(),
+            ))),
+            KVal::CompoundList(
+                results
+                    .into_iter()
+                    .map(|(_, result)| {
+                        KVal::String(Cow::Owned(result.err().unwrap_or_default().into_bytes()))
+                    })
+                    .collect(),
+            ),
+        ]),
+    ))
+    .to_k()
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+//                          Private Functions                           //
+//++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Check that a list built by q has the type, the length and the attribute at the offsets this crate reads.
+fn check_layout() -> Result<(), String> {
+    let list = new_list(qtype::LONG_LIST, 3);
+    list.as_mut_slice::<J>().copy_from_slice(&[1, 2, 3]);
+    let result = match (list.get_type(), list.len(), list.get_attribute()) {
+        (qtype::LONG_LIST, 3, 0) => match KVal::from_raw(list) {
+            KVal::Long(KData::List(longs)) if *longs == [1, 2, 3] => Ok(()),
+            other => Err(format!("long list is read as {}", other)),
+        },
+        (qtype, len, attribute) => Err(format!(
+            "long list of 3 is read as type {}h of {} with attribute {}",
+            qtype, len, attribute
+        )),
+    };
+    decrement_reference_count(list);
+    result
+}
+
+/// A sample value of each family of `KVal`.
+fn samples() -> Vec<(&'static str, KVal<'static>)> {
+    let symbols = |symbols: &[&str]| {
+        KVal::Symbol(KData::List(Cow::Owned(
+            symbols.iter().map(|symbol| symbol.to_string()).collect(),
+        )))
+    };
+    let longs = KVal::Long(KData::List(Cow::Owned(vec![1, qnull_base::J, 3])));
+    vec![
+        (
+            "bool",
+            KVal::Bool(KData::List(Cow::Owned(vec![true, false]))),
+        ),
+        ("guid", KVal::Guid(KData::List(Cow::Owned(vec![[7; 16]])))),
+        ("byte", KVal::Byte(KData::List(Cow::Owned(vec![0, 255])))),
+        (
+            "short",
+            KVal::Short(KData::List(Cow::Owned(vec![1, qnull_base::H]))),
+        ),
+        (
+            "int",
+            KVal::Int(KData::List(Cow::Owned(vec![1, qnull_base::I]))),
+        ),
+        ("long", longs.clone()),
+        (
+            "real",
+            KVal::Real(KData::List(Cow::Owned(vec![1.5, qnull_base::E]))),
+        ),
+        (
+            "float",
+            KVal::Float(KData::List(Cow::Owned(vec![1.5, qnull_base::F]))),
+        ),
+        ("char", KVal::Char('q')),
+        ("string", KVal::String(Cow::Borrowed(b"self test"))),
+        ("symbol", symbols(&["self", "test"])),
+        ("timestamp", KVal::Timestamp(KData::Atom(Cow::Owned(1)))),
+        ("month", KVal::Month(KData::Atom(Cow::Owned(1)))),
+        ("date", KVal::Date(KData::Atom(Cow::Owned(1)))),
+        ("datetime", KVal::Datetime(KData::Atom(Cow::Owned(1.5)))),
+        ("timespan", KVal::Timespan(KData::Atom(Cow::Owned(1)))),
+        ("minute", KVal::Minute(KData::Atom(Cow::Owned(1)))),
+        ("second", KVal::Second(KData::Atom(Cow::Owned(1)))),
+        ("time", KVal::Time(KData::Atom(Cow::Owned(1)))),
+        (
+            "compound",
+            KVal::CompoundList(vec![KVal::Char('q'), longs.clone(), KVal::Null]),
+        ),
+        (
+            "dictionary",
+            KVal::Dictionary(KDict::new(
+                symbols(&["a", "b"]),
+                KVal::CompoundList(vec![longs.clone(), KVal::Char('q')]),
+            )),
+        ),
+        (
+            "table",
+            KVal::Table(KTable::new(KDict::new(
+                symbols(&["sym", "size"]),
+                KVal::CompoundList(vec![symbols(&["a", "b", "c"]), longs]),
+            ))),
+        ),
+    ]
+}
+
+/// Check that a value is converted into a q object and back into the same value.
+fn check_round_trip(sample: &KVal) -> Result<(), String> {
+    let k = sample.to_k();
+    if k.is_null() {
+        return Err(format!("{} is not converted", sample));
+    }
+    let result = match KVal::from_raw(k) {
+        converted if converted == *sample => Ok(()),
+        converted => Err(format!("{} is converted back into {}", sample, converted)),
+    };
+    decrement_reference_count(k);
+    result
+}
+
+/// Check that a q function is evaluated with arguments built in Rust.
+fn check_eval() -> Result<(), String> {
+    let result = unsafe { native::k(0, str_to_S!("{x+y}"), new_long(1), new_long(2), KNULL) };
+    let checked = match KVal::from_raw(result) {
+        KVal::Long(KData::Atom(sum)) if *sum == 3 => Ok(()),
+        other => Err(format!("{{x+y}}[1; 2] returned {}", other)),
+    };
+    if !result.is_null() {
+        decrement_reference_count(result);
+    }
+    checked
+}
+
+/// Check that an error signalled by a q function is returned as an error object.
+fn check_error_trap() -> Result<(), String> {
+    let result = unsafe { native::k(0, str_to_S!("{'x}"), new_symbol("self_test"), KNULL) };
+    if result.is_null() {
+        return Err("signal was not returned".to_string());
+    }
+    let checked = match (result.get_type(), result.get_error_string()) {
+        (qtype::ERROR, Ok("self_test")) => Ok(()),
+        (qtype, _) => Err(format!("signal was returned as type {}h", qtype)),
+    };
+    decrement_reference_count(result);
+    checked
+}
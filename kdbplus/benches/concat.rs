@@ -0,0 +1,54 @@
+//! Benchmark of `KVal::concat` against joining the same parts one by one with `KVal::join`, which copies the joined
+//!  list on each join.
+//!
+//! Run with `cargo bench -p kdbplus --features api --bench concat`.
+
+use kdbplus::api::rusty_api::*;
+use std::borrow::Cow;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Number of parts joined.
+const PARTS: i64 = 1_000;
+/// Number of elements of each part.
+const LENGTH: i64 = 100;
+/// Number of runs of each measurement. The fastest run is reported.
+const RUNS: usize = 10;
+
+/// Long lists of `LENGTH` elements.
+fn parts() -> Vec<KVal<'static>> {
+    (0..PARTS)
+        .map(|part| KVal::Long(KData::List(Cow::Owned((part..part + LENGTH).collect()))))
+        .collect()
+}
+
+/// Fastest time of `RUNS` runs, each of which starts from new parts.
+fn measure(mut f: impl FnMut(Vec<KVal<'static>>) -> usize) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let parts = parts();
+            let start = Instant::now();
+            black_box(f(parts));
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let concatenated = measure(|parts| KVal::concat(parts).unwrap().len());
+    let joined = measure(|parts| {
+        parts
+            .into_iter()
+            .reduce(|joined, part| KVal::join(joined, part).unwrap())
+            .unwrap()
+            .len()
+    });
+    println!("{} parts of {} longs", PARTS, LENGTH);
+    println!("concat:        {:?}", concatenated);
+    println!("repeated join: {:?}", joined);
+    println!(
+        "speedup:       {:.1}x",
+        joined.as_secs_f64() / concatenated.as_secs_f64()
+    );
+}
@@ -0,0 +1,101 @@
+//! Benchmark of `KVal::content_hash` against encoding a large numeric table into bytes and hashing them, which is
+//!  the route taken without a structural hash.
+//!
+//! Run with `cargo bench -p kdbplus --features api --bench content_hash`.
+
+use kdbplus::api::rusty_api::*;
+use kdbplus::qtype;
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Number of rows of the benchmarked table.
+const ROWS: usize = 1_000_000;
+/// Number of runs of each measurement. The fastest run is reported.
+const RUNS: usize = 10;
+
+/// Table of timestamp, float, long and int columns.
+fn numeric_table(rows: usize) -> KVal<'static> {
+    KVal::Table(KTable::new(KDict::new(
+        KVal::Symbol(KData::List(Cow::Owned(
+            ["time", "price", "size", "venue"]
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        ))),
+        KVal::CompoundList(vec![
+            KVal::Timestamp(KData::List(Cow::Owned(
+                (0..rows as i64).map(|row| row * 1_000_000).collect(),
+            ))),
+            KVal::Float(KData::List(Cow::Owned(
+                (0..rows).map(|row| 100.0 + row as f64 * 0.01).collect(),
+            ))),
+            KVal::Long(KData::List(Cow::Owned(
+                (0..rows as i64).map(|row| row % 1000).collect(),
+            ))),
+            KVal::Int(KData::List(Cow::Owned(
+                (0..rows as i32).map(|row| row % 7).collect(),
+            ))),
+        ]),
+    )))
+}
+
+/// Copy the columns into a byte buffer in the manner of the IPC encoder and hash the buffer.
+fn encode_then_hash(table: &KVal) -> u64 {
+    let columns = match table {
+        KVal::Table(table) => match table.dict.values.as_ref() {
+            KVal::CompoundList(columns) => columns,
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    };
+    let mut buffer = vec![qtype::TABLE as u8];
+    for column in columns {
+        buffer.push(column.qtype() as u8);
+        buffer.extend_from_slice(&(column.len() as u32).to_le_bytes());
+        match column {
+            KVal::Timestamp(KData::List(list)) | KVal::Long(KData::List(list)) => list
+                .iter()
+                .for_each(|element| buffer.extend_from_slice(&element.to_le_bytes())),
+            KVal::Float(KData::List(list)) => list
+                .iter()
+                .for_each(|element| buffer.extend_from_slice(&element.to_le_bytes())),
+            KVal::Int(KData::List(list)) => list
+                .iter()
+                .for_each(|element| buffer.extend_from_slice(&element.to_le_bytes())),
+            _ => unreachable!(),
+        }
+    }
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&buffer);
+    hasher.finish()
+}
+
+/// Fastest time of `RUNS` runs.
+fn measure(mut f: impl FnMut() -> u64) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let table = numeric_table(ROWS);
+    let structural = measure(|| table.content_hash());
+    let structural128 = measure(|| table.content_hash128() as u64);
+    let encoded = measure(|| encode_then_hash(&table));
+    println!("{} rows x 4 columns", ROWS);
+    println!("content_hash:     {:?}", structural);
+    println!("content_hash128:  {:?}", structural128);
+    println!("encode then hash: {:?}", encoded);
+    println!(
+        "speedup:          {:.1}x",
+        encoded.as_secs_f64() / structural.as_secs_f64()
+    );
+}
@@ -0,0 +1,96 @@
+//! Benchmark of `KTable::get_row_with_sources` against cloning the underlying dictionary of the table before
+//!  reading a row, which copies every column including every symbol.
+//!
+//! Run with `cargo bench -p kdbplus --features api --bench get_row`.
+
+use kdbplus::api::rusty_api::*;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Number of rows of the benchmarked table.
+const ROWS: usize = 1_000_000;
+/// Number of columns of the benchmarked table. Every fourth column is a symbol column.
+const COLUMNS: usize = 20;
+/// Number of rows read in a run.
+const READS: usize = 10;
+/// Number of runs of each measurement. The fastest run is reported.
+const RUNS: usize = 3;
+
+/// Table of float, long, int and symbol columns.
+fn wide_table(rows: usize) -> KTable<'static> {
+    let names = (0..COLUMNS)
+        .map(|column| format!("c{}", column))
+        .collect::<Vec<_>>();
+    let columns = (0..COLUMNS)
+        .map(|column| match column % 4 {
+            0 => KVal::Float(KData::List(Cow::Owned(
+                (0..rows).map(|row| row as f64 * 0.5).collect(),
+            ))),
+            1 => KVal::Long(KData::List(Cow::Owned((0..rows as i64).collect()))),
+            2 => KVal::Int(KData::List(Cow::Owned(
+                (0..rows as i32).map(|row| row % 7).collect(),
+            ))),
+            _ => KVal::Symbol(KData::List(Cow::Owned(
+                (0..rows).map(|row| format!("s{}", row % 100)).collect(),
+            ))),
+        })
+        .collect();
+    KTable::new(KDict::new(
+        KVal::Symbol(KData::List(Cow::Owned(names))),
+        KVal::CompoundList(columns),
+    ))
+}
+
+/// Clone the dictionary and read the row from the clone.
+fn clone_then_get_row(table: &KTable, index: i64) -> usize {
+    let cloned = KTable::new(table.dict.to_owned());
+    cloned
+        .get_row_with_sources(index, &HashMap::new())
+        .unwrap()
+        .len()
+}
+
+/// Fastest time of `RUNS` runs.
+fn measure(mut f: impl FnMut() -> usize) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let table = wide_table(ROWS);
+    let indices = (0..READS as i64)
+        .map(|read| read * (ROWS / READS) as i64)
+        .collect::<Vec<_>>();
+    let borrowed = measure(|| {
+        indices
+            .iter()
+            .map(|index| {
+                table
+                    .get_row_with_sources(*index, &HashMap::new())
+                    .unwrap()
+                    .len()
+            })
+            .sum()
+    });
+    let cloned = measure(|| {
+        indices
+            .iter()
+            .map(|index| clone_then_get_row(&table, *index))
+            .sum()
+    });
+    println!("{} rows x {} columns, {} rows read", ROWS, COLUMNS, READS);
+    println!("get_row_with_sources: {:?}", borrowed);
+    println!("clone then get_row:   {:?}", cloned);
+    println!(
+        "speedup:              {:.1}x",
+        cloned.as_secs_f64() / borrowed.as_secs_f64()
+    );
+}
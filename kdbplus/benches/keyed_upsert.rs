@@ -0,0 +1,75 @@
+//! Benchmark of `KKeyedTable::upsert`, which keeps the index of the rows by their keys across calls, against
+//!  `KTable::upsert`, which builds the index on each call, for a stream of rows upserted one at a time.
+//!
+//! Run with `cargo bench -p kdbplus --features api --bench keyed_upsert`.
+
+use kdbplus::api::rusty_api::*;
+use std::borrow::Cow;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Number of rows of the benchmarked table before the stream is upserted.
+const ROWS: i64 = 100_000;
+/// Number of rows upserted one at a time. Half of them replace existing rows and the other half are appended.
+const UPSERTS: i64 = 1_000;
+/// Number of runs of each measurement. The fastest run is reported.
+const RUNS: usize = 3;
+
+/// Table of a long key column `id` and a long value column `size`.
+fn table(ids: impl Iterator<Item = i64>) -> KTable<'static> {
+    let ids = ids.collect::<Vec<_>>();
+    let sizes = ids.clone();
+    KTable::from_columns(vec![
+        ("id", KVal::Long(KData::List(Cow::Owned(ids)))),
+        ("size", KVal::Long(KData::List(Cow::Owned(sizes)))),
+    ])
+    .unwrap()
+}
+
+/// Rows of the stream, each of which is a table of one row.
+fn stream() -> Vec<KTable<'static>> {
+    (ROWS - UPSERTS / 2..ROWS + UPSERTS / 2)
+        .map(|id| table(id..id + 1))
+        .collect()
+}
+
+/// Fastest time of `RUNS` runs, each of which starts from a new table.
+fn measure<T>(build: impl Fn() -> T, mut f: impl FnMut(T) -> usize) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let built = build();
+            let start = Instant::now();
+            black_box(f(built));
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let keyed = measure(
+        || (table(0..ROWS).enkey(1).unwrap(), stream()),
+        |(mut keyed, rows)| {
+            for row in rows {
+                keyed.upsert(row).unwrap();
+            }
+            keyed.len()
+        },
+    );
+    let rebuilt = measure(
+        || (table(0..ROWS), stream()),
+        |(mut table, rows)| {
+            for row in rows {
+                table.upsert(&["id"], row).unwrap();
+            }
+            table.len()
+        },
+    );
+    println!("{} rows, {} rows upserted one at a time", ROWS, UPSERTS);
+    println!("KKeyedTable::upsert: {:?}", keyed);
+    println!("KTable::upsert:      {:?}", rebuilt);
+    println!(
+        "speedup:             {:.1}x",
+        rebuilt.as_secs_f64() / keyed.as_secs_f64()
+    );
+}
@@ -0,0 +1,71 @@
+//! Benchmark of reading one column of a wide table converted by `KVal::from_raw` against the same table converted by
+//!  `KVal::from_raw_lazy`.
+//!
+//! The q C API is provided by the mock of the tests. Run with
+//!  `cargo bench -p kdbplus --features api --bench lazy_table`.
+
+#[path = "../tests/mock/mod.rs"]
+mod mock;
+
+use kdbplus::api::rusty_api::*;
+use kdbplus::api::*;
+use std::borrow::Cow;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Number of rows of the benchmarked table.
+const ROWS: usize = 100_000;
+/// Number of columns of the benchmarked table. Half of them are symbol columns.
+const COLUMNS: usize = 50;
+/// Number of runs of each measurement. The fastest run is reported.
+const RUNS: usize = 5;
+
+/// Fastest time of `RUNS` reads of the last column.
+fn measure(read: impl Fn() -> usize) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(read());
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let names = (0..COLUMNS)
+        .map(|column| format!("c{}", column))
+        .collect::<Vec<_>>();
+    let columns = (0..COLUMNS)
+        .map(|column| match column % 2 {
+            0 => KVal::Float(KData::List(Cow::Owned(
+                (0..ROWS).map(|row| row as f64 * 0.25).collect(),
+            ))),
+            _ => KVal::Symbol(KData::List(Cow::Owned(
+                (0..ROWS).map(|row| format!("s{}", row % 100)).collect(),
+            ))),
+        })
+        .collect();
+    let table = KVal::Table(KTable::new(KDict::new(
+        KVal::Symbol(KData::List(Cow::Owned(names))),
+        KVal::CompoundList(columns),
+    )))
+    .to_k();
+    let last = format!("c{}", COLUMNS - 1);
+    let eager = measure(|| match KVal::from_raw(table) {
+        KVal::Table(table) => table.get_column(&last).map_or(0, |column| column.len()),
+        _ => unreachable!(),
+    });
+    let lazy = measure(|| match KVal::from_raw_lazy(table) {
+        LazyKVal::Table(table) => table.get_column(&last).map_or(0, KVal::len),
+        _ => unreachable!(),
+    });
+    decrement_reference_count(table);
+    println!("{} rows x {} columns", ROWS, COLUMNS);
+    println!("eager conversion:          {:?}", eager);
+    println!("lazy conversion:           {:?}", lazy);
+    println!(
+        "speedup:                   {:.1}x",
+        eager.as_secs_f64() / lazy.as_secs_f64()
+    );
+}
@@ -0,0 +1,79 @@
+//! Benchmark of `symbols_to_arrow` against decoding every symbol into a string and dictionary-encoding the strings
+//!  again, which is the route taken by a conversion through `KVal`.
+//!
+//! The q C API is provided by the mock of the tests. Run with
+//!  `cargo bench -p kdbplus --features arrow --bench symbols_to_arrow`.
+
+#[path = "../tests/mock/mod.rs"]
+mod mock;
+
+use arrow_array::builder::StringDictionaryBuilder;
+use arrow_array::types::Int32Type;
+use arrow_array::DictionaryArray;
+use kdbplus::api::rusty_api::*;
+use kdbplus::api::*;
+use std::borrow::Cow;
+use std::ffi::CStr;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Number of elements of the benchmarked symbol list.
+const ROWS: usize = 10_000_000;
+/// Number of distinct symbols.
+const DISTINCT: usize = 100;
+/// Number of runs of each measurement. The fastest run is reported.
+const RUNS: usize = 5;
+
+/// Decode every symbol into a string and build the dictionary from the strings.
+fn decode_then_encode(list: K) -> DictionaryArray<Int32Type> {
+    let strings = list
+        .as_mut_slice::<S>()
+        .iter()
+        .map(|symbol| {
+            unsafe { CStr::from_ptr(*symbol) }
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect::<Vec<_>>();
+    let mut builder = StringDictionaryBuilder::<Int32Type>::new();
+    for string in &strings {
+        match string.as_str() {
+            "" => builder.append_null(),
+            string => {
+                builder.append_value(string);
+            }
+        }
+    }
+    builder.finish()
+}
+
+/// Fastest time of `RUNS` runs.
+fn measure(mut f: impl FnMut() -> usize) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let list = KVal::Symbol(KData::List(Cow::Owned(
+        (0..ROWS)
+            .map(|row| format!("sym{}", row % DISTINCT))
+            .collect(),
+    )))
+    .to_k();
+    let direct = measure(|| symbols_to_arrow(list).unwrap().len());
+    let naive = measure(|| decode_then_encode(list).len());
+    decrement_reference_count(list);
+    println!("{} symbols of {} distinct", ROWS, DISTINCT);
+    println!("symbols_to_arrow:          {:?}", direct);
+    println!("decode then encode:        {:?}", naive);
+    println!(
+        "speedup:                   {:.1}x",
+        naive.as_secs_f64() / direct.as_secs_f64()
+    );
+}
@@ -0,0 +1,49 @@
+//! Benchmark of `KVal::to_k` on a long float list copied serially against the same list copied in parallel.
+//!
+//! The q C API is provided by the mock of the tests. Run with
+//!  `cargo bench -p kdbplus --features parallel --bench to_k_parallel`.
+
+#[path = "../tests/mock/mod.rs"]
+mod mock;
+
+use kdbplus::api::rusty_api::*;
+use kdbplus::api::*;
+use std::borrow::Cow;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Number of elements of the benchmarked float list.
+const ROWS: usize = 10_000_000;
+/// Number of runs of each measurement. The fastest run is reported.
+const RUNS: usize = 5;
+
+/// Fastest time of `RUNS` conversions of the list.
+fn measure(list: &KVal) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            let k = black_box(list.to_k());
+            let elapsed = start.elapsed();
+            decrement_reference_count(k);
+            elapsed
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let list = KVal::Float(KData::List(Cow::Owned(
+        (0..ROWS).map(|row| row as f64 * 0.25).collect(),
+    )));
+    let previous = set_parallel_threshold(usize::MAX);
+    let serial = measure(&list);
+    set_parallel_threshold(previous);
+    let parallel = measure(&list);
+    println!("{} floats", ROWS);
+    println!("serial copy:               {:?}", serial);
+    println!("parallel copy:             {:?}", parallel);
+    println!(
+        "speedup:                   {:.1}x",
+        serial.as_secs_f64() / parallel.as_secs_f64()
+    );
+}
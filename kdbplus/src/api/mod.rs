@@ -22,7 +22,9 @@
 //! #[macro_use]
 //! extern crate kdbplus;
 //! use kdbplus::api::*;
+//! use kdbplus::api::rusty_api::*;
 //! use kdbplus::qtype;
+//! use std::borrow::Cow;
 //!
 //! #[no_mangle]
 //! pub extern "C" fn create_symbol_list2(_: K) -> K{
@@ -51,22 +53,13 @@
 //!
 //! #[no_mangle]
 //! pub extern "C" fn create_table2(_: K) -> K{
-//!   // Build keys
-//!   let keys=new_list(qtype::SYMBOL_LIST, 2);
-//!   let keys_slice=keys.as_mut_slice::<S>();
-//!   keys_slice[0]=enumerate(str_to_S!("time"));
-//!   keys_slice[1]=enumerate_n(str_to_S!("temperature_and_humidity"), 11);
-//!
-//!   // Build values
-//!   let values=new_list(qtype::COMPOUND_LIST, 2);
-//!   let time=new_list(qtype::TIMESTAMP_LIST, 3);
 //!   // 2003.10.10D02:24:19.167018272 2006.05.24D06:16:49.419710368 2008.08.12D23:12:24.018691392
-//!   time.as_mut_slice::<J>().copy_from_slice(&[119067859167018272_i64, 201766609419710368, 271897944018691392]);
-//!   let temperature=new_list(qtype::FLOAT_LIST, 3);
-//!   temperature.as_mut_slice::<F>().copy_from_slice(&[22.1_f64, 24.7, 30.5]);
-//!   values.as_mut_slice::<K>().copy_from_slice(&[time, temperature]);
-//!   
-//!   flip(new_dictionary(keys, values))
+//!   let time=KVal::Timestamp(KData::List(Cow::Owned(vec![119067859167018272_i64, 201766609419710368, 271897944018691392])));
+//!   let temperature=KVal::Float(KData::List(Cow::Owned(vec![22.1_f64, 24.7, 30.5])));
+//!   match KTable::from_columns(vec![("time", time), ("temperature", temperature)]){
+//!     Ok(table) => table.to_k(),
+//!     Err(error) => error.to_k()
+//!   }
 //! }
 //! ```
 //!
@@ -104,6 +97,7 @@
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 
 use super::qtype;
+use rusty_api::KError;
 use std::convert::TryInto;
 use std::ffi::CStr;
 use std::os::raw::{
@@ -111,6 +105,7 @@ use std::os::raw::{
 };
 use std::str;
 pub mod native;
+pub mod rusty_api;
 
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Global Variables
@@ -166,6 +161,93 @@ macro_rules! str_to_S {
     };
 }
 
+/// Build [`KVal`](rusty_api/enum.KVal.html) from a literal of q values.
+/// - A literal is an atom of the type given by its suffix, e.g., `1i32` is an int atom and `2.5f32` is a real atom.
+///   An integer without a suffix is a long atom and a float without a suffix is a float atom in the same manner as q.
+///   A string is a symbol atom and a char is a char atom.
+/// - `[...]` is a simple list if the elements are atoms of the same type and a compound list otherwise.
+/// - `{"key" => value, ...}` is a dictionary with symbol keys whose values are built in the same manner as a list.
+/// - Any other expression, e.g., a variable or `(KVal::String(...))`, is taken as an atom of its Rust type or as
+///   `KVal` itself.
+/// # Example
+/// ```
+/// use kdbplus::kval;
+/// use kdbplus::api::rusty_api::*;
+/// use std::borrow::Cow;
+///
+/// let config = kval!({"a" => [1i64, 2, 3], "b" => ["x", "y", "z"]});
+/// assert_eq!(config, KVal::Dictionary(KDict::new(
+///   KVal::Symbol(KData::List(Cow::Owned(vec!["a".to_string(), "b".to_string()]))),
+///   KVal::CompoundList(vec![
+///     KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3]))),
+///     KVal::Symbol(KData::List(Cow::Owned(vec!["x".to_string(), "y".to_string(), "z".to_string()])))
+///   ])
+/// )));
+/// assert_eq!(kval!([1i32, "sym", 2.5f64]), KVal::CompoundList(vec![
+///   KVal::Int(KData::Atom(Cow::Owned(1))),
+///   KVal::Symbol(KData::Atom(Cow::Owned("sym".to_string()))),
+///   KVal::Float(KData::Atom(Cow::Owned(2.5)))
+/// ]));
+/// ```
+/// ```no_run
+/// use kdbplus::kval;
+/// use kdbplus::api::*;
+///
+/// #[no_mangle]
+/// pub extern "C" fn drift(_: K) -> K{
+///   kval!([12i32, 34i32, "vague", -3000i32]).to_k()
+/// }
+/// ```
+/// ```q
+/// q)drift: LIBPATH_ (`drift; 1);
+/// q)drift[]
+/// 12i
+/// 34i
+/// `vague
+/// -3000i
+/// ```
+/// # Note
+/// An integer without a suffix is `i32` for Rust, so an integer beyond the range of int needs `i64` suffix.
+#[macro_export]
+macro_rules! kval {
+    ({ $($key: literal => $value: literal),* $(,)? }) => {
+        $crate::api::rusty_api::literal::dict(vec![$($key),*], vec![$($crate::kval!($value)),*])
+    };
+    ({ $($body: tt)* }) => {
+        $crate::kval!(@dict [] [] $($body)*)
+    };
+    ([ $($element: literal),* $(,)? ]) => {
+        $crate::api::rusty_api::literal::list(vec![$($crate::kval!($element)),*])
+    };
+    ([ $($body: tt)* ]) => {
+        $crate::kval!(@list [] $($body)*)
+    };
+    (@dict [$($key: expr),*] [$($value: expr),*]) => {
+        $crate::api::rusty_api::literal::dict(vec![$($key),*], vec![$($value),*])
+    };
+    (@dict [$($key: expr),*] [$($value: expr),*] $next_key: literal => $next: literal $(, $($rest: tt)*)?) => {
+        $crate::kval!(@dict [$($key,)* $next_key] [$($value,)* $crate::kval!($next)] $($($rest)*)?)
+    };
+    (@dict [$($key: expr),*] [$($value: expr),*] $next_key: literal => $next: tt $(, $($rest: tt)*)?) => {
+        $crate::kval!(@dict [$($key,)* $next_key] [$($value,)* $crate::kval!($next)] $($($rest)*)?)
+    };
+    (@list [$($element: expr),*]) => {
+        $crate::api::rusty_api::literal::list(vec![$($element),*])
+    };
+    (@list [$($element: expr),*] $next: literal $(, $($rest: tt)*)?) => {
+        $crate::kval!(@list [$($element,)* $crate::kval!($next)] $($($rest)*)?)
+    };
+    (@list [$($element: expr),*] $next: tt $(, $($rest: tt)*)?) => {
+        $crate::kval!(@list [$($element,)* $crate::kval!($next)] $($($rest)*)?)
+    };
+    ($atom: literal) => {
+        $crate::api::rusty_api::literal::atom($atom, stringify!($atom))
+    };
+    ($atom: expr) => {
+        $crate::api::rusty_api::literal::KLiteral::into_kval($atom)
+    };
+}
+
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Structs
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -548,6 +630,10 @@ pub trait KUtility {
     fn get_symbol(&self) -> Result<&str, &'static str>;
 
     /// Get an underlying q string as `&str`.
+    /// # Errors
+    /// - `"not a string"` if the object is not a string.
+    /// - `"string is not valid UTF-8"` if the string holds bytes which are not valid UTF-8. A q string can hold
+    ///   arbitrary bytes. Use [`get_string`](#tymethod.get_string) to replace them instead.
     /// # Example
     /// ```no_run
     /// use kdbplus::api::*;
@@ -567,10 +653,14 @@ pub trait KUtility {
     /// q)print_string: `libapi_examples 2: (`print_string; 1);
     /// q)print_string["gnat"]
     /// string: "gnat"
+    /// q)print_string["\377"]
+    /// 'string is not valid UTF-8
     /// ```
     fn get_str(&self) -> Result<&str, &'static str>;
 
-    /// Get an underlying q string as `String`.
+    /// Get an underlying q string as `String`. Bytes which are not valid UTF-8 are replaced with
+    ///  `U+FFFD REPLACEMENT CHARACTER`. Use [`KVal::from_raw`](rusty_api/enum.KVal.html#method.from_raw) to keep the
+    ///  original bytes.
     /// # Example
     /// ```no_run
     /// use kdbplus::api::*;
@@ -773,6 +863,7 @@ pub trait KUtility {
     /// # Note
     /// - Concrete type of `T` is not checked. Its type must be either of `I`, `J`, `E` and `F` and it must be compatible
     ///  with the list type. For example, timestamp list requires `J` type atom.
+    /// - An atom whose size differs from the element size of the list is rejected, e.g., `F` (`1.5`) for real list.
     /// - For symbol list, use [`push_symbol`](#fn.push_symbol) or [`push_symbol_n`](#fn.push_symbol_n).
     fn push_raw<T>(&mut self, atom: T) -> Result<K, &'static str>;
 
@@ -972,10 +1063,12 @@ impl KUtility for K {
                                 row_slice[i] = new_bool(column.as_mut_slice::<G>()[index] as i32);
                             }
                             qtype::BYTE_LIST => {
-                                row_slice[i] = new_byte(column.as_mut_slice::<G>()[index] as i32);
+                                row_slice[i] =
+                                    new_byte_wrapping(column.as_mut_slice::<G>()[index] as i32);
                             }
                             qtype::SHORT_LIST => {
-                                row_slice[i] = new_short(column.as_mut_slice::<H>()[index] as i32);
+                                row_slice[i] =
+                                    new_short_wrapping(column.as_mut_slice::<H>()[index] as i32);
                             }
                             qtype::INT_LIST => {
                                 row_slice[i] = new_int(column.as_mut_slice::<I>()[index]);
@@ -984,13 +1077,14 @@ impl KUtility for K {
                                 row_slice[i] = new_long(column.as_mut_slice::<J>()[index]);
                             }
                             qtype::REAL_LIST => {
-                                row_slice[i] = new_real(column.as_mut_slice::<E>()[index] as f64);
+                                row_slice[i] = new_real32(column.as_mut_slice::<E>()[index]);
                             }
                             qtype::FLOAT_LIST => {
                                 row_slice[i] = new_float(column.as_mut_slice::<F>()[index]);
                             }
                             qtype::STRING => {
-                                row_slice[i] = new_char(column.as_mut_slice::<G>()[index] as char);
+                                row_slice[i] =
+                                    new_char_truncating(column.as_mut_slice::<G>()[index] as char);
                             }
                             qtype::SYMBOL_LIST => {
                                 row_slice[i] =
@@ -1154,7 +1248,9 @@ impl KUtility for K {
     #[inline]
     fn get_str(&self) -> Result<&str, &'static str> {
         match unsafe { (**self).qtype } {
-            qtype::STRING => Ok(unsafe { str::from_utf8_unchecked_mut(self.as_mut_slice::<G>()) }),
+            qtype::STRING => {
+                str::from_utf8(self.as_mut_slice::<G>()).map_err(|_| "string is not valid UTF-8\0")
+            }
             _ => Err("not a string\0"),
         }
     }
@@ -1162,9 +1258,7 @@ impl KUtility for K {
     #[inline]
     fn get_string(&self) -> Result<String, &'static str> {
         match unsafe { (**self).qtype } {
-            qtype::STRING => {
-                Ok(unsafe { String::from_utf8_unchecked(self.as_mut_slice::<G>().to_vec()) })
-            }
+            qtype::STRING => Ok(String::from_utf8_lossy(self.as_mut_slice::<G>()).into_owned()),
             _ => Err("not a string\0"),
         }
     }
@@ -1224,6 +1318,11 @@ impl KUtility for K {
     #[inline]
     fn push_raw<T>(&mut self, mut atom: T) -> Result<K, &'static str> {
         match unsafe { (**self).qtype } {
+            t @ qtype::BOOL_LIST..=qtype::ENUM_LIST
+                if std::mem::size_of::<T>() != simple_element_size(t) =>
+            {
+                Err("size of the atom does not match the list\0")
+            }
             _t @ qtype::BOOL_LIST..=qtype::ENUM_LIST => {
                 Ok(unsafe { native::ja(self, std::mem::transmute::<*mut T, *mut V>(&mut atom)) })
             }
@@ -1438,6 +1537,23 @@ pub fn null_terminated_str_to_const_S(string: &str) -> const_S {
     string.as_bytes().as_ptr() as const_S
 }
 
+/// Get the size in bytes of an element of a simple list.
+fn simple_element_size(qtype: i8) -> usize {
+    match qtype {
+        qtype::BOOL_LIST | qtype::BYTE_LIST | qtype::STRING => 1,
+        qtype::GUID_LIST => 16,
+        qtype::SHORT_LIST => 2,
+        qtype::INT_LIST
+        | qtype::REAL_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST => 4,
+        _ => 8,
+    }
+}
+
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
 // >> Re-export
 //++++++++++++++++++++++++++++++++++++++++++++++++++//
@@ -1484,14 +1600,28 @@ pub fn new_guid(guid: [G; 16]) -> K {
     unsafe { native::ku(U::new(guid)) }
 }
 
-/// Constructor of q byte object. Relabeling of `kg`.
+/// Constructor of q byte object. Relabeling of `kg`. A value out of the range of byte is truncated silently.
+#[deprecated(
+    note = "use `try_new_byte` or `new_byte_wrapping` which tell how an out of range value is handled"
+)]
+#[inline]
+pub fn new_byte(byte: I) -> K {
+    new_byte_wrapping(byte)
+}
+
+/// Constructor of q byte object which checks the range of the value.
+/// # Errors
+/// [`KError::OutOfRange`](rusty_api/enum.KError.html#variant.OutOfRange) if the value is not between 0 and 255.
 /// # Example
 /// ```no_run
 /// use kdbplus::api::*;
 ///
 /// #[no_mangle]
 /// pub extern "C" fn create_byte(_: K) -> K{
-///   new_byte(0x3c)
+///   match try_new_byte(0x3c){
+///     Ok(byte) => byte,
+///     Err(error) => error.to_k()
+///   }
 /// }
 /// ```
 /// ```q
@@ -1499,19 +1629,44 @@ pub fn new_guid(guid: [G; 16]) -> K {
 /// q)create_byte[]
 /// 0x3c
 /// ```
+pub fn try_new_byte(byte: I) -> Result<K, KError> {
+    match u8::try_from(byte) {
+        Ok(_) => Ok(new_byte_wrapping(byte)),
+        Err(_) => Err(KError::out_of_range(byte.to_string(), "byte atom")),
+    }
+}
+
+/// Constructor of q byte object. Relabeling of `kg`. A value out of the range of byte wraps around, e.g., `300`
+///  becomes `0x2c`.
 #[inline]
-pub fn new_byte(byte: I) -> K {
+pub fn new_byte_wrapping(byte: I) -> K {
     unsafe { native::kg(byte) }
 }
 
-/// Constructor of q short object. Relabeling of `kh`.
+/// Constructor of q short object. Relabeling of `kh`. A value out of the range of short is truncated silently.
+#[deprecated(
+    note = "use `try_new_short` or `new_short_wrapping` which tell how an out of range value is handled"
+)]
+#[inline]
+pub fn new_short(short: I) -> K {
+    new_short_wrapping(short)
+}
+
+/// Constructor of q short object which checks the range of the value. The null and the infinities of short are
+///  within the range and built as they are.
+/// # Errors
+/// [`KError::OutOfRange`](rusty_api/enum.KError.html#variant.OutOfRange) if the value is not between -32768 and
+///  32767.
 /// # Example
 /// ```no_run
 /// use kdbplus::api::*;
 ///
 /// #[no_mangle]
 /// pub extern "C" fn create_short(_: K) -> K{
-///   new_short(-144)
+///   match try_new_short(-144){
+///     Ok(short) => short,
+///     Err(error) => error.to_k()
+///   }
 /// }
 /// ```
 /// ```q
@@ -1519,8 +1674,17 @@ pub fn new_byte(byte: I) -> K {
 /// q)shortage[]
 /// -144h
 /// ```
+pub fn try_new_short(short: I) -> Result<K, KError> {
+    match i16::try_from(short) {
+        Ok(_) => Ok(new_short_wrapping(short)),
+        Err(_) => Err(KError::out_of_range(short.to_string(), "short atom")),
+    }
+}
+
+/// Constructor of q short object. Relabeling of `kh`. A value out of the range of short wraps around, e.g.,
+///  `32768` becomes `-32768h`, i.e., `0Nh`.
 #[inline]
-pub fn new_short(short: I) -> K {
+pub fn new_short_wrapping(short: I) -> K {
     unsafe { native::kh(short) }
 }
 
@@ -1579,11 +1743,36 @@ pub fn new_long(long: J) -> K {
 /// q)reality[]
 /// 0.00324e
 /// ```
+/// # Note
+/// `ke` takes `F` and narrows it to `E`. A value which is not representable in `E` loses precision, e.g., `0.1`
+///  becomes `0.100000001490116`. Use [`new_real32`](fn.new_real32.html) to build a real from `E` without conversion.
 #[inline]
 pub fn new_real(real: F) -> K {
     unsafe { native::ke(real) }
 }
 
+/// Constructor of q real object from `E`. The value is stored as it is including the bit pattern of NaN.
+/// # Example
+/// ```no_run
+/// use kdbplus::api::*;
+///
+/// #[no_mangle]
+/// pub extern "C" fn create_real32(_: K) -> K{
+///   new_real32(0.00324)
+/// }
+/// ```
+/// ```q
+/// q)reality: `libapi_examples 2: (`create_real32; 1);
+/// q)reality[]
+/// 0.00324e
+/// ```
+#[inline]
+pub fn new_real32(real: E) -> K {
+    let atom = unsafe { native::ka(qtype::REAL_ATOM as I) };
+    unsafe { (*atom).value.real = real };
+    atom
+}
+
 /// Constructor of q float object. Relabeling of `kf`.
 /// # Example
 /// ```
@@ -1604,14 +1793,30 @@ pub fn new_float(float: F) -> K {
     unsafe { native::kf(float) }
 }
 
-///  Constructor of q char object. Relabeling of `kc`.
+/// Constructor of q char object. Relabeling of `kc`. A char above `U+00FF` is truncated silently.
+#[deprecated(
+    note = "use `try_new_char` or `new_char_truncating` which tell how a char above `U+00FF` is handled"
+)]
+#[inline]
+pub fn new_char(character: char) -> K {
+    new_char_truncating(character)
+}
+
+/// Constructor of q char object which checks the code point of the char. A q char is a byte holding the code
+///  point, i.e., a char between `U+0000` and `U+00FF` such as `'é'` is built as it is.
+/// # Errors
+/// [`KError::OutOfRange`](rusty_api/enum.KError.html#variant.OutOfRange) if the char is above `U+00FF`, e.g.,
+///  `'🦀'`, which does not fit in a byte.
 /// # Example
 /// ```no_run
 /// use kdbplus::api::*;
 ///
 /// #[no_mangle]
 /// pub extern "C" fn create_char2(_: K) -> K{
-///   new_char('t')
+///   match try_new_char('t'){
+///     Ok(character) => character,
+///     Err(error) => error.to_k()
+///   }
 /// }
 /// ```
 /// ```q
@@ -1619,8 +1824,20 @@ pub fn new_float(float: F) -> K {
 /// q)heavy[]
 /// "t"
 /// ```
+pub fn try_new_char(character: char) -> Result<K, KError> {
+    match u8::try_from(character) {
+        Ok(_) => Ok(new_char_truncating(character)),
+        Err(_) => Err(KError::out_of_range(
+            format!("U+{:04X}", character as u32),
+            "char atom",
+        )),
+    }
+}
+
+/// Constructor of q char object. Relabeling of `kc`. A char above `U+00FF` is truncated to the low byte of its
+///  code point, e.g., `'Ā'` (`U+0100`) becomes `"\000"`.
 #[inline]
-pub fn new_char(character: char) -> K {
+pub fn new_char_truncating(character: char) -> K {
     unsafe { native::kc(character as I) }
 }
 
@@ -2172,6 +2389,9 @@ pub fn enumerate(string: S) -> S {
 /// # Note
 /// Basically this is a `flip` command of q. Hence the value of the dictionary must have
 ///  lists as its elements.
+///
+/// The dictionary is consumed, i.e., its reference count is not incremented and it is freed if it does not form a
+///  table.
 /// ```no_run
 /// #[macro_use]
 /// extern crate kdbplus;
@@ -2211,7 +2431,11 @@ pub fn enumerate(string: S) -> S {
 pub fn flip(dictionary: K) -> K {
     match unsafe { (*dictionary).qtype } {
         qtype::DICTIONARY => unsafe { native::xT(dictionary) },
-        _ => unsafe { native::krr(null_terminated_str_to_const_S("not a dictionary\0")) },
+        _ => {
+            // Consume the object in the same manner as `xT` does.
+            decrement_reference_count(dictionary);
+            unsafe { native::krr(null_terminated_str_to_const_S("not a dictionary\0")) }
+        }
     }
 }
 
@@ -2511,7 +2735,23 @@ pub fn unpin_symbol() -> I {
 /// - `obj`: List of (function to free the object; foreign object).
 /// # Example
 /// See the example of [`load_as_q_function`](fn.load_as_q_function.html).
+/// # Note
+/// - `obj` is left untouched unless it is a foreign object of length 2 whose second element is not null. This
+///   makes a second call on the same object a no-op.
+/// - The type of the embedded object cannot be verified. [`Foreign`](rusty_api/struct.Foreign.html) builds a
+///   foreign object which also records the type of the embedded value.
+// `obj` is a q object passed by q, which keeps it alive during the call. Its length is read only after it is checked
+//  not to be null.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub fn drop_q_object(obj: K) -> K {
+    if obj.is_null()
+        || obj.get_type() != qtype::FOREIGN
+        || unsafe { (*obj).value.list.n } != 2
+        || obj.as_mut_slice::<K>()[1].is_null()
+    {
+        eprintln!("drop of foreign object was skipped: not a live foreign object");
+        return obj;
+    }
     let obj_slice = obj.as_mut_slice::<K>();
     // Take ownership of `K` object from a raw pointer and drop at the end of this scope.
     let _ = unsafe { Box::from_raw(obj_slice[1]) };
@@ -2714,13 +2954,13 @@ pub fn simple_to_compound(simple: K, enum_source: &str) -> K {
         qtype::BYTE_LIST => {
             let simple_slice = simple.as_mut_slice::<G>();
             for i in 0..size {
-                compound_slice[i] = new_byte(simple_slice[i] as I);
+                compound_slice[i] = new_byte_wrapping(simple_slice[i] as I);
             }
         }
         qtype::SHORT_LIST => {
             let simple_slice = simple.as_mut_slice::<H>();
             for i in 0..size {
-                compound_slice[i] = new_short(simple_slice[i] as I);
+                compound_slice[i] = new_short_wrapping(simple_slice[i] as I);
             }
         }
         qtype::INT_LIST => {
@@ -2738,7 +2978,7 @@ pub fn simple_to_compound(simple: K, enum_source: &str) -> K {
         qtype::REAL_LIST => {
             let simple_slice = simple.as_mut_slice::<E>();
             for i in 0..size {
-                compound_slice[i] = new_real(simple_slice[i] as F);
+                compound_slice[i] = new_real32(simple_slice[i]);
             }
         }
         qtype::FLOAT_LIST => {
@@ -2750,7 +2990,7 @@ pub fn simple_to_compound(simple: K, enum_source: &str) -> K {
         qtype::STRING => {
             let simple_slice = simple.as_mut_slice::<G>();
             for i in 0..size {
-                compound_slice[i] = new_char(simple_slice[i] as char);
+                compound_slice[i] = new_char_truncating(simple_slice[i] as char);
             }
         }
         qtype::SYMBOL_LIST => {
@@ -0,0 +1,288 @@
+//! Aggregation of numeric `KVal` skipping q nulls in the same manner as `sum`, `min`, `max` and `avg` in q.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::errors::TYPE;
+use super::kval::atom;
+use super::{KData, KVal, Nullable};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Aggregation picking an element.
+#[derive(Clone, Copy)]
+enum Extremum {
+    Min,
+    Max,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Traits
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Element type of `KData` which can be aggregated, i.e., bool, byte, short, int, long, real and float. Temporal
+///  types are aggregated with their underlying types.
+pub trait Numeric: Nullable + Copy + PartialOrd {
+    /// Type of a sum: `i64` for integers and `f64` for floats.
+    type Sum: Copy + Default;
+    /// Add the value to a sum. An integer sum wraps around in the same manner as q.
+    fn add_to(self, sum: Self::Sum) -> Self::Sum;
+    /// Convert the value into `f64`.
+    fn to_f64(self) -> f64;
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Macros
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Implement `Numeric` for integer types summed into `i64`.
+macro_rules! integer {
+    ($($type: ty),*) => {
+        $(
+            impl Numeric for $type {
+                type Sum = i64;
+                fn add_to(self, sum: i64) -> i64 {
+                    sum.wrapping_add(self as i64)
+                }
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+/// Implement `Numeric` for float types summed into `f64`.
+macro_rules! float {
+    ($($type: ty),*) => {
+        $(
+            impl Numeric for $type {
+                type Sum = f64;
+                fn add_to(self, sum: f64) -> f64 {
+                    sum + self as f64
+                }
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl Numeric for bool {
+    type Sum = i64;
+    fn add_to(self, sum: i64) -> i64 {
+        sum + self as i64
+    }
+    fn to_f64(self) -> f64 {
+        self as u8 as f64
+    }
+}
+
+integer!(u8, i16, i32, i64);
+float!(f32, f64);
+
+impl<T: Numeric> KData<'_, T> {
+    /// Count the elements which are not null.
+    pub fn count_non_null(&self) -> usize {
+        self.non_null().count()
+    }
+
+    /// Sum the elements which are not null. An integer sum is taken in `i64` and a float sum in `f64`. Returns
+    ///  `None` if every element is null.
+    /// # Example
+    /// ```
+    /// use kdbplus::qnull_base;
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let sizes = KData::List(Cow::Owned(vec![100_i32, qnull_base::I, 250]));
+    /// assert_eq!(sizes.sum(), Some(350_i64));
+    /// assert_eq!(sizes.count_non_null(), 2);
+    /// assert_eq!(KData::List(Cow::Owned(vec![f64::NAN])).sum(), None);
+    /// ```
+    pub fn sum(&self) -> Option<T::Sum> {
+        let mut elements = self.non_null().peekable();
+        elements.peek()?;
+        Some(elements.fold(T::Sum::default(), |sum, element| element.add_to(sum)))
+    }
+
+    /// Get the least element which is not null. Returns `None` if every element is null.
+    pub fn min(&self) -> Option<T> {
+        self.non_null()
+            .reduce(|min, element| if element < min { element } else { min })
+    }
+
+    /// Get the greatest element which is not null. Returns `None` if every element is null.
+    pub fn max(&self) -> Option<T> {
+        self.non_null()
+            .reduce(|max, element| if element > max { element } else { max })
+    }
+
+    /// Get the arithmetic mean of the elements which are not null. Returns `None` if every element is null.
+    /// # Example
+    /// ```
+    /// use kdbplus::qnull_base;
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let prices = KData::List(Cow::Owned(vec![1.5_f32, f32::NAN, 2.5]));
+    /// assert_eq!(prices.mean(), Some(2.0));
+    /// assert_eq!(KData::List(Cow::Owned(vec![qnull_base::J])).mean(), None);
+    /// ```
+    pub fn mean(&self) -> Option<f64> {
+        let (count, sum) = self
+            .non_null()
+            .fold((0_usize, 0.0), |(count, sum), element| {
+                (count + 1, sum + element.to_f64())
+            });
+        (count != 0).then(|| sum / count as f64)
+    }
+
+    /// Iterate over the elements which are not null.
+    fn non_null(&self) -> impl Iterator<Item = T> + '_ {
+        self.iter()
+            .copied()
+            .filter(|element| !element.is_null_value())
+    }
+}
+
+impl KVal<'_> {
+    /// Sum a numeric atom or list skipping nulls in the same manner as `sum` in q. The sum is widened so that it
+    ///  does not overflow as early as in q.
+    /// - Bool, byte, short, int and long result in a long atom.
+    /// - Real and float result in a float atom.
+    /// - Timespan results in a timespan atom.
+    ///
+    /// A list of nulls or an empty list sums to zero.
+    /// # Errors
+    /// - `"type"` if the value is not of the types above.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn summarize(list: K) -> K{
+    ///   let list = KVal::from_raw(list);
+    ///   match [list.sum(), list.min(), list.max(), list.mean()].into_iter().collect::<Result<KVal, _>>(){
+    ///     Ok(summary) => summary.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)summarize: `libapi_examples 2: (`summarize; 1);
+    /// q)summarize 3 0N 1 4
+    /// 8
+    /// 1
+    /// 4
+    /// 2.666667
+    /// q)summarize 0n 0n
+    /// 0f
+    /// 0w
+    /// -0w
+    /// 0n
+    /// ```
+    pub fn sum(&self) -> Result<KVal<'static>, &'static str> {
+        let long = |sum: Option<i64>| Ok(KVal::Long(atom(sum.unwrap_or(0))));
+        let float = |sum: Option<f64>| Ok(KVal::Float(atom(sum.unwrap_or(0.0))));
+        match self {
+            KVal::Bool(data) => long(data.sum()),
+            KVal::Byte(data) => long(data.sum()),
+            KVal::Short(data) => long(data.sum()),
+            KVal::Int(data) => long(data.sum()),
+            KVal::Long(data) => long(data.sum()),
+            KVal::Real(data) => float(data.sum()),
+            KVal::Float(data) => float(data.sum()),
+            KVal::Timespan(data) => Ok(KVal::Timespan(atom(data.sum().unwrap_or(0)))),
+            _ => Err(TYPE),
+        }
+    }
+
+    /// Get the least element of a numeric or temporal atom or list skipping nulls in the same manner as `min` in q.
+    ///  The result is an atom of the same type.
+    ///
+    /// A list of nulls or an empty list results in the positive infinity of the type, `1b` for bool and `0xff` for
+    ///  byte, which q also returns.
+    /// # Errors
+    /// - `"type"` if the value is not bool, byte, short, int, long, real, float or a temporal type.
+    pub fn min(&self) -> Result<KVal<'static>, &'static str> {
+        self.extremum(Extremum::Min)
+    }
+
+    /// Get the greatest element of a numeric or temporal atom or list skipping nulls in the same manner as `max` in
+    ///  q. The result is an atom of the same type.
+    ///
+    /// A list of nulls or an empty list results in the negative infinity of the type, `0b` for bool and `0x00` for
+    ///  byte, which q also returns.
+    /// # Errors
+    /// - `"type"` if the value is not bool, byte, short, int, long, real, float or a temporal type.
+    pub fn max(&self) -> Result<KVal<'static>, &'static str> {
+        self.extremum(Extremum::Max)
+    }
+
+    /// Get the arithmetic mean of a numeric atom or list skipping nulls in the same manner as `avg` in q. The result
+    ///  is a float atom, which is null for a list of nulls or an empty list.
+    /// # Errors
+    /// - `"type"` if the value is not bool, byte, short, int, long, real or float.
+    pub fn mean(&self) -> Result<KVal<'static>, &'static str> {
+        let mean = match self {
+            KVal::Bool(data) => data.mean(),
+            KVal::Byte(data) => data.mean(),
+            KVal::Short(data) => data.mean(),
+            KVal::Int(data) => data.mean(),
+            KVal::Long(data) => data.mean(),
+            KVal::Real(data) => data.mean(),
+            KVal::Float(data) => data.mean(),
+            _ => return Err(TYPE),
+        };
+        Ok(KVal::Float(atom(mean.unwrap_or(f64::NAN))))
+    }
+
+    /// Pick the least or the greatest element.
+    fn extremum(&self, extremum: Extremum) -> Result<KVal<'static>, &'static str> {
+        macro_rules! pick {
+            ($data: expr, $variant: path) => {
+                match extremum {
+                    Extremum::Min => $data.min(),
+                    Extremum::Max => $data.max(),
+                }
+                .map(|element| $variant(atom(element)))
+            };
+        }
+        let picked = match self {
+            KVal::Bool(data) => pick!(data, KVal::Bool),
+            KVal::Byte(data) => pick!(data, KVal::Byte),
+            KVal::Short(data) => pick!(data, KVal::Short),
+            KVal::Int(data) => pick!(data, KVal::Int),
+            KVal::Long(data) => pick!(data, KVal::Long),
+            KVal::Real(data) => pick!(data, KVal::Real),
+            KVal::Float(data) => pick!(data, KVal::Float),
+            KVal::Timestamp(data) => pick!(data, KVal::Timestamp),
+            KVal::Month(data) => pick!(data, KVal::Month),
+            KVal::Date(data) => pick!(data, KVal::Date),
+            KVal::Datetime(data) => pick!(data, KVal::Datetime),
+            KVal::Timespan(data) => pick!(data, KVal::Timespan),
+            KVal::Minute(data) => pick!(data, KVal::Minute),
+            KVal::Second(data) => pick!(data, KVal::Second),
+            KVal::Time(data) => pick!(data, KVal::Time),
+            _ => return Err(TYPE),
+        };
+        match (picked, self, extremum) {
+            (Some(picked), _, _) => Ok(picked),
+            (None, KVal::Bool(_), _) => Ok(KVal::Bool(atom(matches!(extremum, Extremum::Min)))),
+            (None, KVal::Byte(_), Extremum::Min) => Ok(KVal::Byte(atom(u8::MAX))),
+            (None, KVal::Byte(_), Extremum::Max) => Ok(KVal::Byte(atom(0))),
+            (None, _, Extremum::Min) => KVal::inf_of(self.qtype()),
+            (None, _, Extremum::Max) => KVal::ninf_of(self.qtype()),
+        }
+    }
+}
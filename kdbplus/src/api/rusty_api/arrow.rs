@@ -0,0 +1,437 @@
+//! Conversion of q data into Apache Arrow arrays and of Arrow record batches into tables. Available only when `arrow`
+//!  feature is enabled.
+//!
+//! A q symbol is interned, i.e., every occurrence of the same symbol in the process is the same pointer, so a symbol
+//!  list is already dictionary-encoded. [`symbols_to_arrow`] builds the Arrow dictionary directly from the distinct
+//!  pointers. Each distinct symbol is decoded once and no string is materialized per element, which is what makes
+//!  decoding into strings and re-encoding them the slowest part of a naive conversion.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::super::{KUtility, K, S};
+use super::ktable::from_parts;
+use super::kval::{as_slice, qtype_name};
+use super::{KData, KTable, KVal};
+use crate::{qinf_base, qninf_base, qnull_base, qtype};
+use arrow_array::builder::{Int32Builder, StringBuilder, StringDictionaryBuilder};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Date32Type, Float64Type, Int32Type, Int64Type, TimestampNanosecondType};
+use arrow_array::{
+    Array, ArrayRef, BooleanArray, Date32Array, DictionaryArray, Float64Array, Int64Array,
+    LargeStringArray, RecordBatch, RecordBatchOptions, TimestampNanosecondArray,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, TimeUnit};
+use std::borrow::Cow;
+use std::collections::hash_map::{Entry, HashMap};
+use std::ffi::CStr;
+use std::sync::Arc;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Timestamp of `2000.01.01D00:00:00` in nanoseconds since the Unix epoch.
+const KDB_TIMESTAMP_OFFSET: i64 = 946684800000000000;
+
+/// `2000.01.01` in days since the Unix epoch.
+const KDB_DATE_OFFSET: i32 = 10957;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl KTable<'_> {
+    /// Convert the table into an Arrow `RecordBatch` with a field of the same name for each column.
+    ///
+    /// | q column  | Arrow array                           | null                  |
+    /// |-----------|---------------------------------------|-----------------------|
+    /// | long      | `Int64`                               | `0Nj`                 |
+    /// | float     | `Float64`                             | `0n`                  |
+    /// | symbol    | `Dictionary(Int32, Utf8)`             | `` ` ``               |
+    /// | timestamp | `Timestamp(Nanosecond)` since 1970    | `0Np`                 |
+    /// | date      | `Date32` since 1970                   | `0Nd`                 |
+    /// | bool      | `Boolean`                             | none                  |
+    /// | string    | `LargeUtf8`                           | `""`                  |
+    ///
+    /// A string column is a compound list of strings, and an empty compound list is taken as a string column. q nulls
+    ///  are mapped into the validity bitmap of the array. Infinities of timestamp and date are kept as they are
+    ///  instead of being shifted to the Unix epoch, so that they remain the greatest and the least values.
+    /// # Errors
+    /// - `InvalidArgumentError` listing every column of an unsupported type, or if the table is a keyed table.
+    /// - `ComputeError` if a timestamp or a date is out of the range of Arrow after the shift of the epoch.
+    /// - `DictionaryKeyOverflowError` if a symbol column has more distinct symbols than an `Int32` key can index.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let table = KTable::from_columns(vec![("sym", kval!(["a", ""])), ("price", kval!([1.5, 2.5]))]).unwrap();
+    /// let batch = table.to_arrow().unwrap();
+    /// assert_eq!(batch.num_rows(), 2);
+    /// assert_eq!(batch.column(0).null_count(), 1);
+    /// assert_eq!(KTable::from_arrow(&batch).unwrap(), table);
+    ///
+    /// let table = KTable::from_columns(vec![("id", kval!([1_i32, 2_i32]))]).unwrap();
+    /// assert_eq!(
+    ///   table.to_arrow().unwrap_err().to_string(),
+    ///   "Invalid argument error: unsupported columns: id (int list)"
+    /// );
+    /// ```
+    pub fn to_arrow(&self) -> Result<RecordBatch, ArrowError> {
+        let (names, columns) = match (self.dict.keys.as_ref(), self.dict.values.as_ref()) {
+            (KVal::Symbol(KData::List(names)), KVal::CompoundList(columns)) => (names, columns),
+            _ => {
+                return Err(ArrowError::InvalidArgumentError(
+                    "expected table, got keyed table".to_string(),
+                ))
+            }
+        };
+        let unsupported = names
+            .iter()
+            .zip(columns)
+            .filter(|(_, column)| arrow_type(column).is_none())
+            .map(|(name, column)| format!("{} ({})", name, qtype_name(column.qtype())))
+            .collect::<Vec<_>>();
+        if !unsupported.is_empty() {
+            return Err(unsupported_columns(unsupported));
+        }
+        let fields = names
+            .iter()
+            .zip(columns)
+            .map(|(name, column)| {
+                let data_type =
+                    arrow_type(column).unwrap_or_else(|| unreachable!("types were checked"));
+                let nullable = data_type != DataType::Boolean;
+                Field::new(name.as_str(), data_type, nullable)
+            })
+            .collect::<Vec<_>>();
+        let arrays = names
+            .iter()
+            .zip(columns)
+            .map(|(name, column)| to_array(name, column))
+            .collect::<Result<Vec<_>, _>>()?;
+        RecordBatch::try_new_with_options(
+            Arc::new(Schema::new(fields)),
+            arrays,
+            &RecordBatchOptions::new().with_row_count(Some(self.len())),
+        )
+    }
+
+    /// Build a table from an Arrow `RecordBatch` in the reverse manner of [`to_arrow`](#method.to_arrow). Nulls are
+    ///  mapped into the q nulls of the column types and a null of a `Boolean` array into `0b`. Besides the arrays
+    ///  built by `to_arrow`, `Utf8` is taken as a string column and `Timestamp(Nanosecond)` of any time zone as a
+    ///  timestamp column.
+    /// # Errors
+    /// - `InvalidArgumentError` listing every column of an unsupported type.
+    /// - `ComputeError` if a timestamp or a date is out of the range of q after the shift of the epoch.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn through_arrow(table: K) -> K{
+    ///   match KTable::from_raw(table).to_arrow().and_then(|batch| KTable::from_arrow(&batch)){
+    ///     Ok(table) => table.to_k(),
+    ///     Err(_) => new_error("not convertible through arrow\0")
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)through_arrow: `libapi_examples 2: (`through_arrow; 1);
+    /// q)through_arrow ([] sym: `a``c; price: 1.5 0n 3.5)
+    /// sym price
+    /// ---------
+    /// a   1.5
+    ///
+    /// c   3.5
+    /// ```
+    pub fn from_arrow(batch: &RecordBatch) -> Result<KTable<'static>, ArrowError> {
+        let schema = batch.schema();
+        let unsupported = schema
+            .fields()
+            .iter()
+            .filter(|field| !is_supported(field.data_type()))
+            .map(|field| format!("{} ({})", field.name(), field.data_type()))
+            .collect::<Vec<_>>();
+        if !unsupported.is_empty() {
+            return Err(unsupported_columns(unsupported));
+        }
+        let names = schema
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect::<Vec<_>>();
+        let columns = names
+            .iter()
+            .zip(batch.columns())
+            .map(|(name, array)| from_array(name, array.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(from_parts(names, columns))
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Public Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Convert a q symbol list into an Arrow `DictionaryArray<Int32Type>` of UTF-8 values. The dictionary holds the
+///  distinct symbols in the order of their first occurrence and the null symbol `` ` `` is mapped into a null entry.
+///  A symbol which is not valid UTF-8 is decoded lossily.
+///
+/// The dictionary is keyed by the interned pointer of each symbol, so the list is read once without decoding any
+///  symbol more than once.
+/// # Errors
+/// - `list` is not a symbol list.
+/// - The list has more distinct symbols than an `Int32` key can index.
+/// # Example
+/// ```no_run
+/// use kdbplus::api::*;
+/// use kdbplus::api::rusty_api::*;
+/// use arrow_array::Array;
+///
+/// #[no_mangle]
+/// pub extern "C" fn distinct_symbol_count(list: K) -> K{
+///   match symbols_to_arrow(list){
+///     Ok(array) => new_long(array.values().len() as i64),
+///     Err(_) => new_error("not a symbol list\0")
+///   }
+/// }
+/// ```
+pub fn symbols_to_arrow(list: K) -> Result<DictionaryArray<Int32Type>, ArrowError> {
+    if list.get_type() != qtype::SYMBOL_LIST {
+        return Err(ArrowError::InvalidArgumentError(format!(
+            "expected symbol list, got {}",
+            qtype_name(list.get_type())
+        )));
+    }
+    let symbols = as_slice::<S>(list);
+    let mut indices = HashMap::<S, i32>::new();
+    let mut keys = Int32Builder::with_capacity(symbols.len());
+    let mut values = StringBuilder::new();
+    for &symbol in symbols {
+        // The null symbol is an empty string.
+        if unsafe { *symbol } == 0 {
+            keys.append_null();
+            continue;
+        }
+        let next = indices.len();
+        let key = match indices.entry(symbol) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                let key =
+                    i32::try_from(next).map_err(|_| ArrowError::DictionaryKeyOverflowError)?;
+                values.append_value(unsafe { CStr::from_ptr(symbol) }.to_string_lossy());
+                *entry.insert(key)
+            }
+        };
+        keys.append_value(key);
+    }
+    DictionaryArray::try_new(keys.finish(), Arc::new(values.finish()))
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Build the error listing columns of unsupported types.
+fn unsupported_columns(columns: Vec<String>) -> ArrowError {
+    ArrowError::InvalidArgumentError(format!("unsupported columns: {}", columns.join(", ")))
+}
+
+/// Error of a temporal value out of the range after the shift of the epoch.
+fn out_of_range(name: &str, type_name: &str) -> ArrowError {
+    ArrowError::ComputeError(format!("{} of column {} is out of range", type_name, name))
+}
+
+/// Get the Arrow type of a column. Returns `None` if the column has no Arrow counterpart.
+fn arrow_type(column: &KVal) -> Option<DataType> {
+    match column {
+        KVal::Long(KData::List(_)) => Some(DataType::Int64),
+        KVal::Float(KData::List(_)) => Some(DataType::Float64),
+        KVal::Symbol(KData::List(_)) => Some(DataType::Dictionary(
+            Box::new(DataType::Int32),
+            Box::new(DataType::Utf8),
+        )),
+        KVal::Timestamp(KData::List(_)) => Some(DataType::Timestamp(TimeUnit::Nanosecond, None)),
+        KVal::Date(KData::List(_)) => Some(DataType::Date32),
+        KVal::Bool(KData::List(_)) => Some(DataType::Boolean),
+        KVal::CompoundList(list)
+            if list
+                .iter()
+                .all(|element| matches!(element, KVal::String(_))) =>
+        {
+            Some(DataType::LargeUtf8)
+        }
+        _ => None,
+    }
+}
+
+/// Check if an Arrow array of the type can be converted into a column.
+fn is_supported(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Int64
+        | DataType::Float64
+        | DataType::Timestamp(TimeUnit::Nanosecond, _)
+        | DataType::Date32
+        | DataType::Boolean
+        | DataType::Utf8
+        | DataType::LargeUtf8 => true,
+        DataType::Dictionary(key, value) => {
+            key.as_ref() == &DataType::Int32 && value.as_ref() == &DataType::Utf8
+        }
+        _ => false,
+    }
+}
+
+/// Convert a column whose type was checked by `arrow_type` into an Arrow array.
+fn to_array(name: &str, column: &KVal) -> Result<ArrayRef, ArrowError> {
+    let array: ArrayRef = match column {
+        KVal::Long(KData::List(list)) => Arc::new(
+            list.iter()
+                .map(|long| (*long != qnull_base::J).then_some(*long))
+                .collect::<Int64Array>(),
+        ),
+        KVal::Float(KData::List(list)) => Arc::new(
+            list.iter()
+                .map(|float| (!float.is_nan()).then_some(*float))
+                .collect::<Float64Array>(),
+        ),
+        KVal::Symbol(KData::List(list)) => {
+            let mut builder = StringDictionaryBuilder::<Int32Type>::with_capacity(list.len(), 0, 0);
+            for symbol in list.iter() {
+                match symbol.is_empty() {
+                    true => builder.append_null(),
+                    false => {
+                        builder.append(symbol)?;
+                    }
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        KVal::Timestamp(KData::List(list)) => Arc::new(
+            list.iter()
+                .map(|timestamp| match *timestamp {
+                    qnull_base::J => Ok(None),
+                    qinf_base::J | qninf_base::J => Ok(Some(*timestamp)),
+                    timestamp => timestamp
+                        .checked_add(KDB_TIMESTAMP_OFFSET)
+                        .map(Some)
+                        .ok_or_else(|| out_of_range(name, "timestamp")),
+                })
+                .collect::<Result<TimestampNanosecondArray, _>>()?,
+        ),
+        KVal::Date(KData::List(list)) => Arc::new(
+            list.iter()
+                .map(|date| match *date {
+                    qnull_base::I => Ok(None),
+                    qinf_base::I | qninf_base::I => Ok(Some(*date)),
+                    date => date
+                        .checked_add(KDB_DATE_OFFSET)
+                        .map(Some)
+                        .ok_or_else(|| out_of_range(name, "date")),
+                })
+                .collect::<Result<Date32Array, _>>()?,
+        ),
+        KVal::Bool(KData::List(list)) => Arc::new(BooleanArray::from(list.to_vec())),
+        KVal::CompoundList(list) => Arc::new(
+            list.iter()
+                .map(|element| match element {
+                    KVal::String(string) if !string.is_empty() => {
+                        std::str::from_utf8(string).map(Some).map_err(|_| {
+                            ArrowError::ComputeError(format!(
+                                "string of column {} is not valid UTF-8",
+                                name
+                            ))
+                        })
+                    }
+                    _ => Ok(None),
+                })
+                .collect::<Result<LargeStringArray, _>>()?,
+        ),
+        _ => unreachable!("type was checked"),
+    };
+    Ok(array)
+}
+
+/// Convert an Arrow array whose type was checked by `is_supported` into a column.
+fn from_array(name: &str, array: &dyn Array) -> Result<KVal<'static>, ArrowError> {
+    let column = match array.data_type() {
+        DataType::Int64 => KVal::Long(KData::List(Cow::Owned(
+            array
+                .as_primitive::<Int64Type>()
+                .iter()
+                .map(|long| long.unwrap_or(qnull_base::J))
+                .collect(),
+        ))),
+        DataType::Float64 => KVal::Float(KData::List(Cow::Owned(
+            array
+                .as_primitive::<Float64Type>()
+                .iter()
+                .map(|float| float.unwrap_or(qnull_base::F))
+                .collect(),
+        ))),
+        DataType::Dictionary(_, _) => {
+            let dictionary = array.as_dictionary::<Int32Type>();
+            let values = dictionary.values().as_string::<i32>();
+            KVal::Symbol(KData::List(Cow::Owned(
+                dictionary
+                    .keys()
+                    .iter()
+                    .map(|key| match key {
+                        Some(key) => values.value(key as usize).to_string(),
+                        None => String::new(),
+                    })
+                    .collect(),
+            )))
+        }
+        DataType::Timestamp(_, _) => KVal::Timestamp(KData::List(Cow::Owned(
+            array
+                .as_primitive::<TimestampNanosecondType>()
+                .iter()
+                .map(|timestamp| match timestamp {
+                    None => Ok(qnull_base::J),
+                    Some(timestamp @ (qinf_base::J | qninf_base::J)) => Ok(timestamp),
+                    Some(timestamp) => timestamp
+                        .checked_sub(KDB_TIMESTAMP_OFFSET)
+                        .ok_or_else(|| out_of_range(name, "timestamp")),
+                })
+                .collect::<Result<_, _>>()?,
+        ))),
+        DataType::Date32 => KVal::Date(KData::List(Cow::Owned(
+            array
+                .as_primitive::<Date32Type>()
+                .iter()
+                .map(|date| match date {
+                    None => Ok(qnull_base::I),
+                    Some(date @ (qinf_base::I | qninf_base::I)) => Ok(date),
+                    Some(date) => date
+                        .checked_sub(KDB_DATE_OFFSET)
+                        .ok_or_else(|| out_of_range(name, "date")),
+                })
+                .collect::<Result<_, _>>()?,
+        ))),
+        DataType::Boolean => KVal::Bool(KData::List(Cow::Owned(
+            array
+                .as_boolean()
+                .iter()
+                .map(|boolean| boolean.unwrap_or(false))
+                .collect(),
+        ))),
+        DataType::Utf8 => strings(array.as_string::<i32>().iter()),
+        DataType::LargeUtf8 => strings(array.as_string::<i64>().iter()),
+        _ => unreachable!("type was checked"),
+    };
+    Ok(column)
+}
+
+/// Build a string column mapping a null into an empty string.
+fn strings<'b>(strings: impl Iterator<Item = Option<&'b str>>) -> KVal<'static> {
+    KVal::CompoundList(
+        strings
+            .map(|string| KVal::String(Cow::Owned(string.unwrap_or_default().as_bytes().to_vec())))
+            .collect(),
+    )
+}
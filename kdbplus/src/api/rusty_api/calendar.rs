@@ -0,0 +1,295 @@
+//! Calendar arithmetic on dates and months of `KVal`.
+//!
+//! Every function accepts an atom or a simple list and returns a value of the same shape. Nulls and infinities are
+//!  returned as they are instead of being shifted as an integer.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::super::{days_to_ymd, ymd_to_days};
+use super::temporal::{civil_from_days, days_from_civil};
+use super::{KData, KError, KVal, QDate};
+use crate::{qinf_base, qninf_base, qnull_base};
+use std::borrow::Cow;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Public Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Add days to dates.
+/// # Errors
+/// `dates` is not a date atom or list.
+/// # Example
+/// ```
+/// use kdbplus::api::rusty_api::*;
+/// use std::borrow::Cow;
+///
+/// // 2020.02.28 2020.12.31
+/// let dates = KVal::Date(KData::List(Cow::Owned(vec![7363, 7670])));
+/// let shifted = date_add_days(&dates, 1).unwrap();
+/// assert_eq!(shifted.to_string(), "2020.02.29 2021.01.01");
+/// ```
+/// ```q
+/// q)2020.02.28 2020.12.31+1
+/// 2020.02.29 2021.01.01
+/// ```
+pub fn date_add_days(dates: &KVal, days: i32) -> Result<KVal<'static>, KError> {
+    match dates {
+        KVal::Date(data) => Ok(KVal::Date(map_data(data, |date| {
+            keep_border(date, |date| date.wrapping_add(days))
+        }))),
+        _ => Err(KError::invalid_operation(
+            "date_add_days",
+            dates.type_name(),
+            Some("date"),
+        )),
+    }
+}
+
+/// Add months to dates. A day which does not exist in the resulting month is clamped to the end of the month,
+///  e.g., `2020.01.31` plus one month is `2020.02.29`.
+/// # Errors
+/// `dates` is not a date atom or list.
+/// # Example
+/// ```
+/// use kdbplus::api::rusty_api::*;
+/// use std::borrow::Cow;
+///
+/// // 2020.01.31 2019.01.31 2020.03.15
+/// let dates = KVal::Date(KData::List(Cow::Owned(vec![7335, 6970, 7379])));
+/// let shifted = date_add_months(&dates, 1).unwrap();
+/// assert_eq!(shifted.to_string(), "2020.02.29 2019.02.28 2020.04.15");
+/// ```
+/// ```q
+/// q)add_months:{m:y+"m"$x; ("d"$m)-1-(`dd$x)&`dd$-1+"d"$m+1}
+/// q)add_months[2020.01.31 2019.01.31 2020.03.15; 1]
+/// 2020.02.29 2019.02.28 2020.04.15
+/// ```
+pub fn date_add_months(dates: &KVal, months: i32) -> Result<KVal<'static>, KError> {
+    match dates {
+        KVal::Date(data) => Ok(KVal::Date(map_data(data, |date| {
+            keep_border(date, |date| {
+                let (year, month, day) = civil_from_days(date as i64);
+                let (year, month) = shift_month(year, month, months);
+                days_from_civil(year, month, day.min(days_in_month(year, month))) as i32
+            })
+        }))),
+        _ => Err(KError::invalid_operation(
+            "date_add_months",
+            dates.type_name(),
+            Some("date"),
+        )),
+    }
+}
+
+/// Add months to months.
+/// # Errors
+/// `months` is not a month atom or list.
+/// # Example
+/// ```
+/// use kdbplus::api::rusty_api::*;
+/// use std::borrow::Cow;
+///
+/// // 2020.11m
+/// let month = KVal::Month(KData::Atom(Cow::Owned(250)));
+/// assert_eq!(month_add(&month, 3).unwrap().to_string(), "2021.02m");
+/// ```
+/// ```q
+/// q)2020.11m+3
+/// 2021.02m
+/// ```
+pub fn month_add(months: &KVal, offset: i32) -> Result<KVal<'static>, KError> {
+    match months {
+        KVal::Month(data) => Ok(KVal::Month(map_data(data, |month| {
+            keep_border(month, |month| month.wrapping_add(offset))
+        }))),
+        _ => Err(KError::invalid_operation(
+            "month_add",
+            months.type_name(),
+            Some("month"),
+        )),
+    }
+}
+
+/// Get the last day of the month of each date.
+/// # Errors
+/// `dates` is not a date atom or list.
+/// # Example
+/// ```
+/// use kdbplus::api::rusty_api::*;
+/// use std::borrow::Cow;
+///
+/// // 2020.02.10 2100.02.10
+/// let dates = KVal::Date(KData::List(Cow::Owned(vec![7345, 36565])));
+/// assert_eq!(end_of_month(&dates).unwrap().to_string(), "2020.02.29 2100.02.28");
+/// ```
+/// ```q
+/// q)-1+"d"$1+"m"$2020.02.10 2100.02.10
+/// 2020.02.29 2100.02.28
+/// ```
+pub fn end_of_month(dates: &KVal) -> Result<KVal<'static>, KError> {
+    match dates {
+        KVal::Date(data) => Ok(KVal::Date(map_data(data, |date| {
+            keep_border(date, |date| {
+                let (year, month, _) = civil_from_days(date as i64);
+                days_from_civil(year, month, days_in_month(year, month)) as i32
+            })
+        }))),
+        _ => Err(KError::invalid_operation(
+            "end_of_month",
+            dates.type_name(),
+            Some("date"),
+        )),
+    }
+}
+
+/// Get the day of the week of each date as an int in the same manner as `date mod 7` in q, i.e., `0` for Saturday,
+///  `1` for Sunday, ... and `6` for Friday. The result of a null or an infinity is the null int.
+/// # Errors
+/// `dates` is not a date atom or list.
+/// # Example
+/// ```
+/// use kdbplus::api::rusty_api::*;
+/// use std::borrow::Cow;
+///
+/// // 2000.01.01 1999.12.31 2020.02.29
+/// let dates = KVal::Date(KData::List(Cow::Owned(vec![0, -1, 7364])));
+/// assert_eq!(day_of_week(&dates).unwrap().to_string(), "0 6 0i");
+/// ```
+/// ```q
+/// q)2000.01.01 1999.12.31 2020.02.29 mod 7
+/// 0 6 0i
+/// ```
+pub fn day_of_week(dates: &KVal) -> Result<KVal<'static>, KError> {
+    match dates {
+        KVal::Date(data) => Ok(KVal::Int(map_data(data, |date| {
+            if is_border(date) {
+                qnull_base::I
+            } else {
+                date.rem_euclid(7)
+            }
+        }))),
+        _ => Err(KError::invalid_operation(
+            "day_of_week",
+            dates.type_name(),
+            Some("date"),
+        )),
+    }
+}
+
+/// Convert dates into ints expressed as `yyyymmdd` with [`days_to_ymd`](../fn.days_to_ymd.html). The result of a
+///  null or an infinity is the null int.
+/// # Errors
+/// `dates` is not a date atom or list.
+/// # Example
+/// ```no_run
+/// use kdbplus::api::*;
+/// use kdbplus::api::rusty_api::*;
+///
+/// #[no_mangle]
+/// pub extern "C" fn to_ymd(dates: K) -> K{
+///   match date_to_ymd(&KVal::from_raw(dates)){
+///     Ok(ymd) => ymd.to_k(),
+///     Err(error) => error.to_k()
+///   }
+/// }
+/// ```
+/// ```q
+/// q)to_ymd: `libapi_examples 2: (`to_ymd; 1);
+/// q)to_ymd 2020.04.01 0Nd
+/// 20200401 0Ni
+/// ```
+pub fn date_to_ymd(dates: &KVal) -> Result<KVal<'static>, KError> {
+    match dates {
+        KVal::Date(data) => Ok(KVal::Int(map_data(data, |date| {
+            if is_border(date) {
+                qnull_base::I
+            } else {
+                days_to_ymd(date)
+            }
+        }))),
+        _ => Err(KError::invalid_operation(
+            "date_to_ymd",
+            dates.type_name(),
+            Some("date"),
+        )),
+    }
+}
+
+/// Convert ints expressed as `yyyymmdd` into dates with [`ymd_to_days`](../fn.ymd_to_days.html). The result of a
+///  null int is the null date.
+/// # Errors
+/// `ymd` is not an int atom or list.
+/// # Example
+/// ```no_run
+/// use kdbplus::api::*;
+/// use kdbplus::api::rusty_api::*;
+///
+/// #[no_mangle]
+/// pub extern "C" fn from_ymd(ymd: K) -> K{
+///   match ymd_to_date(&KVal::from_raw(ymd)){
+///     Ok(dates) => dates.to_k(),
+///     Err(error) => error.to_k()
+///   }
+/// }
+/// ```
+/// ```q
+/// q)from_ymd: `libapi_examples 2: (`from_ymd; 1);
+/// q)from_ymd 20200401 0Ni
+/// 2020.04.01 0N
+/// ```
+pub fn ymd_to_date(ymd: &KVal) -> Result<KVal<'static>, KError> {
+    match ymd {
+        KVal::Int(data) => Ok(KVal::Date(map_data(data, |number| {
+            if number == qnull_base::I {
+                QDate::NULL.0
+            } else {
+                ymd_to_days(number / 10000, number / 100 % 100, number % 100)
+            }
+        }))),
+        _ => Err(KError::invalid_operation(
+            "ymd_to_date",
+            ymd.type_name(),
+            Some("int"),
+        )),
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Apply `f` to an atom or each element of a list.
+fn map_data<T: Copy, U: Clone>(data: &KData<T>, f: impl Fn(T) -> U) -> KData<'static, U> {
+    match data {
+        KData::Atom(atom) => KData::Atom(Cow::Owned(f(**atom))),
+        KData::List(list) => KData::List(Cow::Owned(list.iter().copied().map(f).collect())),
+    }
+}
+
+/// Check if a date or a month is a null or an infinity.
+fn is_border(value: i32) -> bool {
+    value == qnull_base::I || value == qinf_base::I || value == qninf_base::I
+}
+
+/// Apply `f` to a date or a month unless it is a null or an infinity.
+fn keep_border(value: i32, f: impl Fn(i32) -> i32) -> i32 {
+    if is_border(value) {
+        value
+    } else {
+        f(value)
+    }
+}
+
+/// Shift (year, month) by months.
+fn shift_month(year: i64, month: u32, months: i32) -> (i64, u32) {
+    let total = year * 12 + month as i64 - 1 + months as i64;
+    (total.div_euclid(12), total.rem_euclid(12) as u32 + 1)
+}
+
+/// Number of days in the month of the proleptic Gregorian calendar.
+fn days_in_month(year: i64, month: u32) -> u32 {
+    let (next_year, next_month) = shift_month(year, month, 1);
+    (days_from_civil(next_year, next_month, 1) - days_from_civil(year, month, 1)) as u32
+}
@@ -0,0 +1,306 @@
+//! Numeric cast between variants of `KVal`.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::kval::qtype_name;
+use super::{KData, KError, KVal};
+use crate::{qinf_base, qnull_base, qtype};
+use std::borrow::Cow;
+use std::fmt;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Element of a numeric type in a form common to all numeric types.
+#[derive(Clone, Copy)]
+enum Number {
+    Null,
+    Inf,
+    NegInf,
+    Integer(i64),
+    Float(f64),
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Traits
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Element type of a numeric `KData`.
+trait Numeric: Clone + 'static {
+    /// Convert the element into the common form.
+    fn to_number(&self) -> Number;
+    /// Convert the common form into the element. Returns `None` if the value cannot be represented.
+    fn from_number(number: Number) -> Option<Self>;
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Macros
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Implement `Numeric` for a signed integer type which has a null and infinities.
+macro_rules! signed_numeric {
+    ($type: ty, $null: expr, $inf: expr) => {
+        impl Numeric for $type {
+            fn to_number(&self) -> Number {
+                match *self {
+                    value if value == $null => Number::Null,
+                    value if value == $inf => Number::Inf,
+                    value if value == -$inf => Number::NegInf,
+                    value => Number::Integer(value as i64),
+                }
+            }
+
+            fn from_number(number: Number) -> Option<Self> {
+                to_integer(number, $null as i64, $inf as i64).map(|value| value as $type)
+            }
+        }
+    };
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Number::Null => f.write_str("0N"),
+            Number::Inf => f.write_str("0W"),
+            Number::NegInf => f.write_str("-0W"),
+            Number::Integer(value) => write!(f, "{}", value),
+            // Debug format writes a large float in the exponent form.
+            Number::Float(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+impl Numeric for bool {
+    fn to_number(&self) -> Number {
+        Number::Integer(*self as i64)
+    }
+
+    fn from_number(number: Number) -> Option<Self> {
+        to_unsigned(number, 1).map(|value| value != 0)
+    }
+}
+
+impl Numeric for u8 {
+    fn to_number(&self) -> Number {
+        Number::Integer(*self as i64)
+    }
+
+    fn from_number(number: Number) -> Option<Self> {
+        to_unsigned(number, u8::MAX as i64).map(|value| value as u8)
+    }
+}
+
+signed_numeric!(i16, qnull_base::H, qinf_base::H);
+signed_numeric!(i32, qnull_base::I, qinf_base::I);
+signed_numeric!(i64, qnull_base::J, qinf_base::J);
+
+impl Numeric for f32 {
+    fn to_number(&self) -> Number {
+        (*self as f64).to_number()
+    }
+
+    fn from_number(number: Number) -> Option<Self> {
+        match number {
+            Number::Float(value) if value.abs() > f32::MAX as f64 => None,
+            number => f64::from_number(number).map(|value| value as f32),
+        }
+    }
+}
+
+impl Numeric for f64 {
+    fn to_number(&self) -> Number {
+        match *self {
+            value if value.is_nan() => Number::Null,
+            value if value == f64::INFINITY => Number::Inf,
+            value if value == f64::NEG_INFINITY => Number::NegInf,
+            value => Number::Float(value),
+        }
+    }
+
+    fn from_number(number: Number) -> Option<Self> {
+        Some(match number {
+            Number::Null => qnull_base::F,
+            Number::Inf => qinf_base::F,
+            Number::NegInf => -qinf_base::F,
+            Number::Integer(value) => value as f64,
+            Number::Float(value) => value,
+        })
+    }
+}
+
+impl<'a> KVal<'a> {
+    /// Cast a numeric atom or list into another numeric type. An atom stays an atom and a list stays a list.
+    /// - Bool, byte, short, int, long, real and float are cast into each other. A value which does not fit in
+    ///   the target type is an error and a float is rounded to the nearest integer when cast into an integral type.
+    /// - Nulls and infinities are mapped to those of the target type. Bool and byte have neither of them and
+    ///   therefore casting them into bool or byte is an error.
+    /// - Temporal types are cast only into their underlying type and back, i.e., timestamp and timespan into long,
+    ///   month, date, minute, second and time into int, and datetime into float. The values are kept as they are.
+    /// - Casting into the same type returns the value as it is.
+    ///
+    /// Large longs cast into float and ints or longs cast into real may lose precision.
+    /// # Parameters
+    /// - `target`: Type of the result. Either of the atom type and the list type is accepted, e.g.,
+    ///   `qtype::LONG_ATOM` or `qtype::LONG_LIST`.
+    /// # Errors
+    /// - [`KError::InvalidCast`](enum.KError.html#variant.InvalidCast) if the types cannot be converted.
+    /// - [`KError::OutOfRange`](enum.KError.html#variant.OutOfRange) carrying the first element which cannot be
+    ///   represented in the target type.
+    /// # Note
+    /// Unlike `$` in q, an infinity is cast into the infinity of the target type, e.g., `0Wi` into `0W` rather than
+    ///  `2147483647`.
+    /// # Example
+    /// ```
+    /// use kdbplus::qtype;
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let ints = KVal::Int(KData::List(Cow::Owned(vec![1, i32::MIN, i32::MAX])));
+    /// let longs = ints.cast(qtype::LONG_LIST).unwrap();
+    /// assert_eq!(longs, KVal::Long(KData::List(Cow::Owned(vec![1, i64::MIN, i64::MAX]))));
+    ///
+    /// let large = KVal::Long(KData::Atom(Cow::Owned(1 << 40)));
+    /// assert_eq!(large.cast(qtype::INT_ATOM).unwrap_err().to_string(), "value 1099511627776 out of range of int atom");
+    /// ```
+    /// ```no_run
+    /// use kdbplus::qtype;
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn sum_as_long(list: K) -> K{
+    ///   match KVal::from_raw(list).cast(qtype::LONG_LIST){
+    ///     Ok(KVal::Long(KData::List(longs))) => new_long(longs.iter().sum()),
+    ///     Ok(_) => new_error("not a list\0"),
+    ///     Err(error) => error.to_k()
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)sum_as_long: `libapi_examples 2: (`sum_as_long; 1);
+    /// q)sum_as_long 1 2 3i
+    /// 6
+    /// q)sum_as_long 1.5 2.5
+    /// 5
+    /// q)sum_as_long `a`b
+    /// 'invalid cast from symbol list to long list
+    /// ```
+    pub fn cast(self, target: i8) -> Result<KVal<'a>, KError> {
+        let target = target.wrapping_abs();
+        if self.qtype().wrapping_abs() == target {
+            return Ok(self);
+        }
+        // The result of an atom is an atom.
+        let target_name = qtype_name(if self.qtype() < 0 {
+            target.wrapping_neg()
+        } else {
+            target
+        });
+        let source_name = self.type_name();
+        match (self, target) {
+            // Temporal types and their underlying types share the raw values.
+            (KVal::Timestamp(data) | KVal::Timespan(data), qtype::LONG_LIST) => {
+                Ok(KVal::Long(data))
+            }
+            (KVal::Long(data), qtype::TIMESTAMP_LIST) => Ok(KVal::Timestamp(data)),
+            (KVal::Long(data), qtype::TIMESPAN_LIST) => Ok(KVal::Timespan(data)),
+            (
+                KVal::Month(data)
+                | KVal::Date(data)
+                | KVal::Minute(data)
+                | KVal::Second(data)
+                | KVal::Time(data),
+                qtype::INT_LIST,
+            ) => Ok(KVal::Int(data)),
+            (KVal::Int(data), qtype::MONTH_LIST) => Ok(KVal::Month(data)),
+            (KVal::Int(data), qtype::DATE_LIST) => Ok(KVal::Date(data)),
+            (KVal::Int(data), qtype::MINUTE_LIST) => Ok(KVal::Minute(data)),
+            (KVal::Int(data), qtype::SECOND_LIST) => Ok(KVal::Second(data)),
+            (KVal::Int(data), qtype::TIME_LIST) => Ok(KVal::Time(data)),
+            (KVal::Datetime(data), qtype::FLOAT_LIST) => Ok(KVal::Float(data)),
+            (KVal::Float(data), qtype::DATETIME_LIST) => Ok(KVal::Datetime(data)),
+            (KVal::Bool(data), target) => cast_numeric(&data, target, (source_name, target_name)),
+            (KVal::Byte(data), target) => cast_numeric(&data, target, (source_name, target_name)),
+            (KVal::Short(data), target) => cast_numeric(&data, target, (source_name, target_name)),
+            (KVal::Int(data), target) => cast_numeric(&data, target, (source_name, target_name)),
+            (KVal::Long(data), target) => cast_numeric(&data, target, (source_name, target_name)),
+            (KVal::Real(data), target) => cast_numeric(&data, target, (source_name, target_name)),
+            (KVal::Float(data), target) => cast_numeric(&data, target, (source_name, target_name)),
+            _ => Err(KError::invalid_cast(source_name, target_name)),
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Cast numeric data into a numeric type specified by a list type. `names` are the names of the source type and the
+///  target type reported in an error.
+fn cast_numeric<S: Numeric>(
+    data: &KData<S>,
+    target: i8,
+    (source_name, target_name): (&'static str, &'static str),
+) -> Result<KVal<'static>, KError> {
+    match target {
+        qtype::BOOL_LIST => cast_data(data, target_name).map(KVal::Bool),
+        qtype::BYTE_LIST => cast_data(data, target_name).map(KVal::Byte),
+        qtype::SHORT_LIST => cast_data(data, target_name).map(KVal::Short),
+        qtype::INT_LIST => cast_data(data, target_name).map(KVal::Int),
+        qtype::LONG_LIST => cast_data(data, target_name).map(KVal::Long),
+        qtype::REAL_LIST => cast_data(data, target_name).map(KVal::Real),
+        qtype::FLOAT_LIST => cast_data(data, target_name).map(KVal::Float),
+        _ => Err(KError::invalid_cast(source_name, target_name)),
+    }
+}
+
+/// Cast each element of numeric data. The first element which cannot be represented is reported in an error.
+fn cast_data<S: Numeric, T: Numeric>(
+    data: &KData<S>,
+    target_name: &'static str,
+) -> Result<KData<'static, T>, KError> {
+    let cast = |element: &S| {
+        let number = element.to_number();
+        T::from_number(number).ok_or_else(|| KError::out_of_range(number.to_string(), target_name))
+    };
+    match data {
+        KData::Atom(atom) => cast(atom).map(|atom| KData::Atom(Cow::Owned(atom))),
+        KData::List(list) => list
+            .iter()
+            .map(cast)
+            .collect::<Result<Vec<_>, _>>()
+            .map(|list| KData::List(Cow::Owned(list))),
+    }
+}
+
+/// Convert a number into an integer of a type whose null is `null` and infinity is `inf`. A float is rounded to the
+///  nearest integer.
+fn to_integer(number: Number, null: i64, inf: i64) -> Option<i64> {
+    match number {
+        Number::Null => Some(null),
+        Number::Inf => Some(inf),
+        Number::NegInf => Some(-inf),
+        Number::Integer(value) if (-inf..=inf).contains(&value) => Some(value),
+        // The rounded value must be a finite value of the type other than the null.
+        Number::Float(value) if value.round().abs() < inf as f64 => Some(value.round() as i64),
+        _ => None,
+    }
+}
+
+/// Convert a number into an integer between 0 and `max` of a type which has neither null nor infinity.
+fn to_unsigned(number: Number, max: i64) -> Option<i64> {
+    match number {
+        Number::Integer(value) if (0..=max).contains(&value) => Some(value),
+        Number::Float(value) if (0.0..=max as f64).contains(&value.round()) => {
+            Some(value.round() as i64)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,214 @@
+//! Conversion between [`KDict`](struct.KDict.html) and the maps of the standard library, and conversion of an atom
+//!  into a Rust primitive with `TryFrom`.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::compound::collapse_atoms;
+use super::errors::{INVALID_UTF8, TYPE};
+use super::kval::NULL_IN_SYMBOL;
+use super::{KData, KDict, KVal};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl<'a> KDict<'a> {
+    /// Convert the dictionary into a `HashMap`. Each key and value is converted with `TryFrom<KVal>`, e.g., into
+    ///  `String` for a symbol key or kept as `KVal` for a value of any type. The first value is taken if a key
+    ///  appears more than once, in the same manner as a lookup in q.
+    /// # Errors
+    /// - `"type"` if a key or a value cannot be converted.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::collections::HashMap;
+    ///
+    /// let config = KDict::new(kval!(["host", "port"]), kval!(["feed", 5001]));
+    /// let map = config.to_hashmap::<String, KVal>().unwrap();
+    /// assert_eq!(map["port"], kval!(5001));
+    ///
+    /// let limits = KDict::new(kval!(["a", "b"]), kval!([100, 200]));
+    /// let map = limits.to_hashmap::<String, i64>().unwrap();
+    /// assert_eq!(map, HashMap::from([("a".to_string(), 100), ("b".to_string(), 200)]));
+    /// ```
+    pub fn to_hashmap<K2, V2>(&self) -> Result<HashMap<K2, V2>, &'static str>
+    where
+        K2: TryFrom<KVal<'a>> + Eq + Hash,
+        V2: TryFrom<KVal<'a>>,
+    {
+        let mut map = HashMap::with_capacity(self.len());
+        for (key, value) in self.clone() {
+            map.entry(K2::try_from(key).map_err(|_| TYPE)?)
+                .or_insert(V2::try_from(value).map_err(|_| TYPE)?);
+        }
+        Ok(map)
+    }
+
+    /// Convert the dictionary into a `BTreeMap` in the same manner as [`to_hashmap`](#method.to_hashmap).
+    /// # Errors
+    /// - `"type"` if a key or a value cannot be converted.
+    pub fn to_btreemap<K2, V2>(&self) -> Result<BTreeMap<K2, V2>, &'static str>
+    where
+        K2: TryFrom<KVal<'a>> + Ord,
+        V2: TryFrom<KVal<'a>>,
+    {
+        let mut map = BTreeMap::new();
+        for (key, value) in self.clone() {
+            map.entry(K2::try_from(key).map_err(|_| TYPE)?)
+                .or_insert(V2::try_from(value).map_err(|_| TYPE)?);
+        }
+        Ok(map)
+    }
+}
+
+impl<'a> FromIterator<(String, KVal<'a>)> for KDict<'a> {
+    /// Build a dictionary with symbol keys in the order of the pairs. Values which are atoms of the same type make a
+    ///  simple list and any other values make a compound list. Keys are not checked, so a key containing a null
+    ///  character is reported when the dictionary is converted into a q object.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let limits = (1..=3_i64).map(|i| (format!("s{}", i), kval!((i * 100)))).collect::<KDict>();
+    /// assert_eq!(limits, KDict::new(kval!(["s1", "s2", "s3"]), kval!([100, 200, 300])));
+    /// ```
+    fn from_iter<I: IntoIterator<Item = (String, KVal<'a>)>>(iter: I) -> Self {
+        let (keys, values): (Vec<String>, Vec<KVal<'a>>) = iter.into_iter().unzip();
+        KDict::new(
+            KVal::Symbol(KData::List(Cow::Owned(keys))),
+            collapse_atoms(values),
+        )
+    }
+}
+
+impl<'a> TryFrom<HashMap<String, KVal<'a>>> for KDict<'a> {
+    type Error = &'static str;
+
+    /// Build a dictionary with symbol keys sorted in ascending order, so that the same map always results in the same
+    ///  dictionary. Values are collected in the same manner as `FromIterator`.
+    /// # Errors
+    /// - `"symbol contains a null character"` if a key cannot be a symbol.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::collections::HashMap;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn sort_by_key(dictionary: K) -> K{
+    ///   let map = match KDict::from_raw(dictionary).to_hashmap::<String, KVal>(){
+    ///     Ok(map) => map,
+    ///     Err(error) => return new_error(error)
+    ///   };
+    ///   match KDict::try_from(map){
+    ///     Ok(sorted) => sorted.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)sort_by_key: `libapi_examples 2: (`sort_by_key; 1);
+    /// q)sort_by_key `b`a`c!(1; "x"; 2)
+    /// a| "x"
+    /// b| 1
+    /// c| 2
+    /// ```
+    fn try_from(map: HashMap<String, KVal<'a>>) -> Result<Self, Self::Error> {
+        let mut pairs = map.into_iter().collect::<Vec<_>>();
+        pairs.sort_unstable_by(|(left, _), (right, _)| left.cmp(right));
+        from_pairs(pairs)
+    }
+}
+
+impl<'a> TryFrom<BTreeMap<String, KVal<'a>>> for KDict<'a> {
+    type Error = &'static str;
+
+    /// Build a dictionary with symbol keys in the order of the map.
+    /// # Errors
+    /// - `"symbol contains a null character"` if a key cannot be a symbol.
+    fn try_from(map: BTreeMap<String, KVal<'a>>) -> Result<Self, Self::Error> {
+        from_pairs(map)
+    }
+}
+
+impl TryFrom<KVal<'_>> for String {
+    type Error = &'static str;
+
+    /// Take the text of a symbol atom or a string.
+    /// # Errors
+    /// - `"not a symbol or a string"` if the value is neither a symbol atom nor a string.
+    /// - `"invalid UTF-8"` if the string is not a valid UTF-8 sequence.
+    fn try_from(value: KVal<'_>) -> Result<Self, Self::Error> {
+        match value {
+            KVal::Symbol(KData::Atom(symbol)) => Ok(symbol.into_owned()),
+            KVal::String(string) => {
+                String::from_utf8(string.into_owned()).map_err(|_| INVALID_UTF8)
+            }
+            _ => Err("not a symbol or a string\0"),
+        }
+    }
+}
+
+impl TryFrom<KVal<'_>> for bool {
+    type Error = &'static str;
+
+    /// Take the value of a bool atom.
+    /// # Errors
+    /// - `"not a bool"` if the value is not a bool atom.
+    fn try_from(value: KVal<'_>) -> Result<Self, Self::Error> {
+        match value {
+            KVal::Bool(KData::Atom(boolean)) => Ok(*boolean),
+            _ => Err("not a bool\0"),
+        }
+    }
+}
+
+impl TryFrom<KVal<'_>> for i64 {
+    type Error = &'static str;
+
+    /// Take the value of a long atom.
+    /// # Errors
+    /// - `"not a long"` if the value is not a long atom.
+    fn try_from(value: KVal<'_>) -> Result<Self, Self::Error> {
+        match value {
+            KVal::Long(KData::Atom(long)) => Ok(*long),
+            _ => Err("not a long\0"),
+        }
+    }
+}
+
+impl TryFrom<KVal<'_>> for f64 {
+    type Error = &'static str;
+
+    /// Take the value of a float atom.
+    /// # Errors
+    /// - `"not a float"` if the value is not a float atom.
+    fn try_from(value: KVal<'_>) -> Result<Self, Self::Error> {
+        match value {
+            KVal::Float(KData::Atom(float)) => Ok(*float),
+            _ => Err("not a float\0"),
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Build a dictionary from pairs whose keys are checked to be symbols.
+fn from_pairs<'a>(
+    pairs: impl IntoIterator<Item = (String, KVal<'a>)>,
+) -> Result<KDict<'a>, &'static str> {
+    let pairs = pairs.into_iter().collect::<Vec<_>>();
+    match pairs.iter().any(|(key, _)| key.contains('\0')) {
+        true => Err(NULL_IN_SYMBOL),
+        false => Ok(pairs.into_iter().collect()),
+    }
+}
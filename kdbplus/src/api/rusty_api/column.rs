@@ -0,0 +1,148 @@
+//! Extraction of a table column as a slice of Rust values with
+//!  [`KTable::column_as`](struct.KTable.html#method.column_as).
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::kval::qtype_name;
+use super::{KData, KError, KTable, KVal};
+use std::borrow::Cow;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Traits
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Element type of a column extracted by [`KTable::column_as`](struct.KTable.html#method.column_as).
+///
+/// | Rust type | column                |
+/// |-----------|-----------------------|
+/// | `bool`    | bool list             |
+/// | `u8`      | byte list             |
+/// | `i16`     | short list            |
+/// | `i32`     | int list              |
+/// | `i64`     | long list             |
+/// | `f32`     | real list             |
+/// | `f64`     | float list            |
+/// | `String`  | symbol list           |
+///
+/// A compound list whose elements are all atoms of the type is also taken, and `String` takes a compound list of
+///  strings which are valid UTF-8.
+pub trait ColumnElement: Clone {
+    /// Name of the column type in an error, e.g., `"long list"`.
+    const EXPECTED: &'static str;
+
+    /// Borrow the elements of a simple list column, or collect the elements of a compound list column. Returns
+    ///  `None` if the column is not of the type.
+    fn from_column<'b>(column: &'b KVal) -> Option<Cow<'b, [Self]>>;
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Macros
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Implement `ColumnElement` for the element type of a variant of `KVal`.
+macro_rules! element {
+    ($type: ty, $variant: path, $expected: expr) => {
+        impl ColumnElement for $type {
+            const EXPECTED: &'static str = $expected;
+
+            fn from_column<'b>(column: &'b KVal) -> Option<Cow<'b, [Self]>> {
+                match column {
+                    $variant(KData::List(list)) => Some(Cow::Borrowed(list.as_ref())),
+                    KVal::CompoundList(list) => list
+                        .iter()
+                        .map(|element| match element {
+                            $variant(KData::Atom(atom)) => Some(atom.as_ref().clone()),
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .map(Cow::Owned),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+element!(bool, KVal::Bool, "bool list");
+element!(u8, KVal::Byte, "byte list");
+element!(i16, KVal::Short, "short list");
+element!(i32, KVal::Int, "int list");
+element!(i64, KVal::Long, "long list");
+element!(f32, KVal::Real, "real list");
+element!(f64, KVal::Float, "float list");
+
+impl ColumnElement for String {
+    const EXPECTED: &'static str = "symbol list";
+
+    fn from_column<'b>(column: &'b KVal) -> Option<Cow<'b, [Self]>> {
+        match column {
+            KVal::Symbol(KData::List(list)) => Some(Cow::Borrowed(list.as_ref())),
+            KVal::CompoundList(list) => list
+                .iter()
+                .map(|element| match element {
+                    KVal::String(string) => String::from_utf8(string.to_vec()).ok(),
+                    KVal::Symbol(KData::Atom(symbol)) => Some(symbol.to_string()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(Cow::Owned),
+            _ => None,
+        }
+    }
+}
+
+impl KTable<'_> {
+    /// Get a column as a slice of Rust values, e.g., `i64` for a long column (see
+    ///  [`ColumnElement`](trait.ColumnElement.html)). A simple list column is borrowed without copying, including
+    ///  a column of a table built by [`from_raw`](#method.from_raw) which borrows q memory. A compound list column
+    ///  is collected into a vector.
+    /// # Errors
+    /// - `KError::MissingColumns` naming the column if it does not exist.
+    /// - `KError::InvalidOperation` naming the type of the column if it is not of `T`, or if the table is a keyed
+    ///   table.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn total_size(table: K) -> K{
+    ///   match KTable::from_raw(table).column_as::<i64>("size"){
+    ///     Ok(sizes) => new_long(sizes.iter().sum()),
+    ///     Err(error) => error.to_k()
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)total_size: `libapi_examples 2: (`total_size; 1);
+    /// q)total_size ([] sym: `a`b; size: 100 250)
+    /// 350
+    /// q)total_size ([] sym: `a`b; size: 100 250i)
+    /// 'invalid operation column_as on int list. expected: long list
+    /// ```
+    pub fn column_as<T: ColumnElement>(&self, name: &str) -> Result<Cow<'_, [T]>, KError> {
+        let columns = match self.dict.values.as_ref() {
+            KVal::CompoundList(columns) => columns,
+            _ => {
+                return Err(KError::invalid_operation(
+                    "column_as",
+                    "dictionary",
+                    Some("table"),
+                ))
+            }
+        };
+        let column = match self.column_index(name) {
+            Some(index) => &columns[index],
+            None => return Err(KError::MissingColumns(vec![name.to_string()])),
+        };
+        T::from_column(column).ok_or_else(|| {
+            KError::invalid_operation("column_as", qtype_name(column.qtype()), Some(T::EXPECTED))
+        })
+    }
+}
@@ -0,0 +1,180 @@
+//! Write-back of a modified `KVal` into the buffers of the q object it was built from.
+//!
+//! [`KVal::from_raw`](enum.KVal.html#method.from_raw) borrows the buffer of a simple list and the first modification
+//!  copies the list into Rust memory, so that the q object is never changed behind the back of q. `commit` copies the
+//!  elements back into the original object once all modifications are done, without allocating a new q object.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::super::{KUtility, G, K, S};
+use super::errors::{ATTRIBUTE, LENGTH, NOT_A_LIST, TYPE};
+use super::kval::{as_slice, enumerate_str, NULL_IN_SYMBOL};
+use super::{KData, KDict, KVal};
+use crate::{qattribute, qtype};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl KVal<'_> {
+    /// Copy the elements of the value into the buffers of an existing q object of the same shape in place. The target
+    ///  is typically the object the value was built from by [`from_raw`](#method.from_raw), which is left untouched
+    ///  by modifications of the value as a borrowed list is copied before it is modified.
+    /// - Simple lists and strings are copied element by element. Symbols are interned.
+    /// - Compound lists, dictionaries and tables are committed element by element, e.g., column by column.
+    /// - Elements which still borrow the buffer of the target are skipped.
+    ///
+    /// Nothing is written unless the whole value fits the target. The reference count of the target is not
+    ///  changed, and every q variable sharing the object observes the modification.
+    /// # Errors
+    /// - `"not a list"` if the value or the target, or any of their elements, is not a list, a dictionary or a table.
+    /// - `"type"` if the types of the value and the target differ.
+    /// - `"length"` if the lengths of the value and the target differ.
+    /// - `"list has an attribute"` if the target or any of its elements has an attribute, which the new elements
+    ///   could violate.
+    /// - `"symbol contains a null character"` if a symbol cannot be interned.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn double_in_place(list: K) -> K{
+    ///   let mut value = KVal::from_raw(list);
+    ///   if let KVal::Long(data) = &mut value{
+    ///     data.map_in_place(|long| *long *= 2);
+    ///   }
+    ///   match value.commit(list){
+    ///     Ok(()) => KNULL,
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)double_in_place: `libapi_examples 2: (`double_in_place; 1);
+    /// q)list: 1 2 3
+    /// q)double_in_place list
+    /// q)list
+    /// 2 4 6
+    /// ```
+    pub fn commit(&self, target: K) -> Result<(), &'static str> {
+        check_target(self, target)?;
+        write_target(self, target);
+        Ok(())
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Check that the value can be written into the target without writing anything.
+fn check_target(value: &KVal, target: K) -> Result<(), &'static str> {
+    let expected = match value {
+        KVal::Table(_) => qtype::TABLE,
+        KVal::Dictionary(_) => qtype::DICTIONARY,
+        _ if (0..qtype::TABLE).contains(&value.qtype()) => value.qtype(),
+        _ => return Err(NOT_A_LIST),
+    };
+    match target.is_null() {
+        true => return Err(NOT_A_LIST),
+        false => match target.get_type() {
+            // Keys of a sorted dictionary carry the sorted attribute.
+            qtype::SORTED_DICTIONARY => return Err(ATTRIBUTE),
+            target_type if !(0..=qtype::DICTIONARY).contains(&target_type) => {
+                return Err(NOT_A_LIST)
+            }
+            target_type if target_type != expected => return Err(TYPE),
+            _ if target.get_attribute() != qattribute::NONE => return Err(ATTRIBUTE),
+            _ => {}
+        },
+    }
+    match value {
+        KVal::Table(table) => check_dictionary(&table.dict, unsafe { (*target).value.table }),
+        KVal::Dictionary(dictionary) => check_dictionary(dictionary, target),
+        _ if value.len() != target.len() as usize => Err(LENGTH),
+        KVal::CompoundList(list) => list
+            .iter()
+            .zip(as_slice::<K>(target))
+            .try_for_each(|(element, target)| check_target(element, *target)),
+        KVal::Symbol(KData::List(symbols))
+            if symbols.iter().any(|symbol| symbol.contains('\0')) =>
+        {
+            Err(NULL_IN_SYMBOL)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check the keys and the values of a dictionary against the pair of a q dictionary.
+fn check_dictionary(dictionary: &KDict, target: K) -> Result<(), &'static str> {
+    let pair = as_slice::<K>(target);
+    check_target(&dictionary.keys, pair[0])?;
+    check_target(&dictionary.values, pair[1])
+}
+
+/// Write the value into the target which passed `check_target`.
+fn write_target(value: &KVal, target: K) {
+    match value {
+        KVal::CompoundList(list) => list
+            .iter()
+            .zip(as_slice::<K>(target))
+            .for_each(|(element, target)| write_target(element, *target)),
+        KVal::Bool(KData::List(list)) => {
+            let buffer = target.as_mut_slice::<G>();
+            buffer
+                .iter_mut()
+                .zip(list.iter())
+                .for_each(|(byte, boolean)| *byte = *boolean as G);
+        }
+        KVal::Guid(KData::List(list)) => write_elements(list, target),
+        KVal::Byte(KData::List(list)) => write_elements(list, target),
+        KVal::Short(KData::List(list)) => write_elements(list, target),
+        KVal::Int(KData::List(list))
+        | KVal::Month(KData::List(list))
+        | KVal::Date(KData::List(list))
+        | KVal::Minute(KData::List(list))
+        | KVal::Second(KData::List(list))
+        | KVal::Time(KData::List(list)) => write_elements(list, target),
+        KVal::Long(KData::List(list))
+        | KVal::Timestamp(KData::List(list))
+        | KVal::Timespan(KData::List(list))
+        | KVal::Enum(KData::List(list), _) => write_elements(list, target),
+        KVal::Real(KData::List(list)) => write_elements(list, target),
+        KVal::Float(KData::List(list)) | KVal::Datetime(KData::List(list)) => {
+            write_elements(list, target)
+        }
+        KVal::Symbol(KData::List(list)) => {
+            let buffer = target.as_mut_slice::<S>();
+            buffer
+                .iter_mut()
+                .zip(list.iter())
+                .for_each(|(slot, symbol)| {
+                    *slot = enumerate_str(symbol).unwrap_or_else(|_| {
+                        unreachable!("symbols were checked not to contain a null character")
+                    })
+                });
+        }
+        KVal::String(string) => write_elements(string, target),
+        KVal::Table(table) => write_dictionary(&table.dict, unsafe { (*target).value.table }),
+        KVal::Dictionary(dictionary) => write_dictionary(dictionary, target),
+        _ => unreachable!("value was checked to be a list"),
+    }
+}
+
+/// Write the keys and the values of a dictionary into the pair of a q dictionary.
+fn write_dictionary(dictionary: &KDict, target: K) {
+    let pair = as_slice::<K>(target);
+    write_target(&dictionary.keys, pair[0]);
+    write_target(&dictionary.values, pair[1]);
+}
+
+/// Copy the elements into the buffer of the target unless they still borrow the buffer.
+fn write_elements<T: Copy>(list: &[T], target: K) {
+    let buffer = unsafe { (*target).value.list.G0.as_mut_ptr() as *mut T };
+    if !std::ptr::eq(list.as_ptr(), buffer) {
+        unsafe { std::ptr::copy_nonoverlapping(list.as_ptr(), buffer, list.len()) };
+    }
+}
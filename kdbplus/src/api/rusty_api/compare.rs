@@ -0,0 +1,126 @@
+//! Comparison of `KVal` following q null semantics.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::{KData, KVal};
+use std::cmp::Ordering;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Macros
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Implement `PartialEq`, `Eq` and `PartialOrd` for `KData` of an element type. An atom and a list are neither equal
+///  nor ordered, and lists are ordered lexicographically.
+macro_rules! kdata_compare {
+    ($element: ty, $eq: expr, $cmp: expr) => {
+        impl PartialEq for KData<'_, $element> {
+            fn eq(&self, other: &Self) -> bool {
+                match (self, other) {
+                    (KData::Atom(left), KData::Atom(right)) => $eq(left.as_ref(), right.as_ref()),
+                    (KData::List(left), KData::List(right)) => {
+                        left.len() == right.len()
+                            && left
+                                .iter()
+                                .zip(right.iter())
+                                .all(|(left, right)| $eq(left, right))
+                    }
+                    _ => false,
+                }
+            }
+        }
+
+        impl Eq for KData<'_, $element> {}
+
+        impl PartialOrd for KData<'_, $element> {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                match (self, other) {
+                    (KData::Atom(left), KData::Atom(right)) => {
+                        Some($cmp(left.as_ref(), right.as_ref()))
+                    }
+                    (KData::List(left), KData::List(right)) => Some(
+                        left.iter()
+                            .zip(right.iter())
+                            .map(|(left, right)| $cmp(left, right))
+                            .find(|ordering| *ordering != Ordering::Equal)
+                            .unwrap_or_else(|| left.len().cmp(&right.len())),
+                    ),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+kdata_compare!(bool, bool::eq, bool::cmp);
+kdata_compare!([u8; 16], <[u8; 16]>::eq, <[u8; 16]>::cmp);
+kdata_compare!(u8, u8::eq, u8::cmp);
+kdata_compare!(i16, i16::eq, i16::cmp);
+kdata_compare!(i32, i32::eq, i32::cmp);
+kdata_compare!(i64, i64::eq, i64::cmp);
+kdata_compare!(f32, float_eq, float_cmp);
+kdata_compare!(f64, float_eq, float_cmp);
+kdata_compare!(String, String::eq, String::cmp);
+
+// Every value is equal to itself as float nulls are equal to each other.
+impl Eq for KVal<'_> {}
+
+impl PartialOrd for KVal<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (KVal::CompoundList(left), KVal::CompoundList(right)) => left.partial_cmp(right),
+            (KVal::Bool(left), KVal::Bool(right)) => left.partial_cmp(right),
+            (KVal::Guid(left), KVal::Guid(right)) => left.partial_cmp(right),
+            (KVal::Byte(left), KVal::Byte(right)) => left.partial_cmp(right),
+            (KVal::Short(left), KVal::Short(right)) => left.partial_cmp(right),
+            (KVal::Int(left), KVal::Int(right)) => left.partial_cmp(right),
+            (KVal::Long(left), KVal::Long(right)) => left.partial_cmp(right),
+            (KVal::Real(left), KVal::Real(right)) => left.partial_cmp(right),
+            (KVal::Float(left), KVal::Float(right)) => left.partial_cmp(right),
+            (KVal::Char(left), KVal::Char(right)) => left.partial_cmp(right),
+            (KVal::Symbol(left), KVal::Symbol(right)) => left.partial_cmp(right),
+            (KVal::String(left), KVal::String(right)) => left.partial_cmp(right),
+            (KVal::Timestamp(left), KVal::Timestamp(right)) => left.partial_cmp(right),
+            (KVal::Month(left), KVal::Month(right)) => left.partial_cmp(right),
+            (KVal::Date(left), KVal::Date(right)) => left.partial_cmp(right),
+            (KVal::Datetime(left), KVal::Datetime(right)) => left.partial_cmp(right),
+            (KVal::Timespan(left), KVal::Timespan(right)) => left.partial_cmp(right),
+            (KVal::Minute(left), KVal::Minute(right)) => left.partial_cmp(right),
+            (KVal::Second(left), KVal::Second(right)) => left.partial_cmp(right),
+            (KVal::Time(left), KVal::Time(right)) => left.partial_cmp(right),
+            (KVal::Enum(left, left_source), KVal::Enum(right, right_source))
+                if left_source == right_source =>
+            {
+                left.partial_cmp(right)
+            }
+            // Tables, dictionaries, errors and general nulls are not ordered.
+            _ => (self == other).then_some(Ordering::Equal),
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Equality of floats where two nulls (`NaN`) are equal.
+fn float_eq<F: PartialOrd + Copy>(left: &F, right: &F) -> bool {
+    float_cmp(left, right) == Ordering::Equal
+}
+
+/// Order of floats where a null (`NaN`) is less than any other value.
+fn float_cmp<F: PartialOrd + Copy>(left: &F, right: &F) -> Ordering {
+    // `NaN` is the only value which is not equal to itself.
+    #[allow(clippy::eq_op)]
+    match (left != left, right != right) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => left.partial_cmp(right).unwrap(),
+    }
+}
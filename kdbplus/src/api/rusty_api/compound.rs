@@ -0,0 +1,229 @@
+//! Conversion between a simple list and a compound list of atoms.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::errors::{EMPTY_LIST, ENUM_SOURCE_MISMATCH, MIXED_TYPES, NOT_AN_ATOM, NOT_A_LIST};
+use super::kval::char_to_byte;
+use super::{KData, KVal};
+use crate::qtype;
+use std::borrow::Cow;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl<'a> KVal<'a> {
+    /// Expand a simple list into a compound list of atoms in the same manner as [`iter`](#method.iter), i.e., a
+    ///  string into chars and an enum list into enum atoms carrying the same source. The atoms borrow the elements
+    ///  of a borrowed list and take over the elements of an owned list, so no element is copied.
+    ///
+    /// Any other value including a compound list and an atom is returned as it is.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let list = KVal::Long(KData::List(Cow::Owned(vec![1, 2])));
+    /// assert_eq!(list.to_compound_list(), KVal::CompoundList(vec![
+    ///   KVal::Long(KData::Atom(Cow::Owned(1))),
+    ///   KVal::Long(KData::Atom(Cow::Owned(2)))
+    /// ]));
+    /// ```
+    pub fn to_compound_list(self) -> KVal<'a> {
+        match self {
+            KVal::Bool(KData::List(list)) => expand(list, KVal::Bool),
+            KVal::Guid(KData::List(list)) => expand(list, KVal::Guid),
+            KVal::Byte(KData::List(list)) => expand(list, KVal::Byte),
+            KVal::Short(KData::List(list)) => expand(list, KVal::Short),
+            KVal::Int(KData::List(list)) => expand(list, KVal::Int),
+            KVal::Long(KData::List(list)) => expand(list, KVal::Long),
+            KVal::Real(KData::List(list)) => expand(list, KVal::Real),
+            KVal::Float(KData::List(list)) => expand(list, KVal::Float),
+            KVal::Symbol(KData::List(list)) => expand(list, KVal::Symbol),
+            KVal::String(string) => KVal::CompoundList(
+                string
+                    .iter()
+                    .map(|byte| KVal::Char(*byte as char))
+                    .collect(),
+            ),
+            KVal::Timestamp(KData::List(list)) => expand(list, KVal::Timestamp),
+            KVal::Month(KData::List(list)) => expand(list, KVal::Month),
+            KVal::Date(KData::List(list)) => expand(list, KVal::Date),
+            KVal::Datetime(KData::List(list)) => expand(list, KVal::Datetime),
+            KVal::Timespan(KData::List(list)) => expand(list, KVal::Timespan),
+            KVal::Minute(KData::List(list)) => expand(list, KVal::Minute),
+            KVal::Second(KData::List(list)) => expand(list, KVal::Second),
+            KVal::Time(KData::List(list)) => expand(list, KVal::Time),
+            KVal::Enum(KData::List(list), source) => {
+                expand(list, |index| KVal::Enum(index, source.clone()))
+            }
+            other => other,
+        }
+    }
+
+    /// Collapse a compound list of atoms of the same type into the simple list of the type. Chars are collapsed into
+    ///  a string of one byte each and enum atoms into an enum list of their common source. A simple list is returned
+    ///  as it is.
+    /// # Errors
+    /// - `"not a list"` if the value is neither a compound list nor a simple list.
+    /// - `"empty list"` if the compound list is empty, as the type of the result cannot be determined.
+    /// - `"not an atom"` if an element is not an atom, e.g., a list, a general null or an error.
+    /// - `"mixed types"` if the elements have different types.
+    /// - `"enum sources differ"` if enum atoms have different sources.
+    /// - `"char out of byte range"` if a char is greater than `'\u{ff}'`.
+    ///
+    /// The error messages are null-terminated and can be passed to `new_error` as they are.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let list = (1..=3).map(|i| KVal::Long(KData::Atom(Cow::Owned(i)))).collect::<KVal>();
+    /// assert_eq!(list.to_simple_list().unwrap(), KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3]))));
+    ///
+    /// let mixed = KVal::CompoundList(vec![KVal::Long(KData::Atom(Cow::Owned(1))), KVal::Char('a')]);
+    /// assert_eq!(mixed.to_simple_list().unwrap_err(), "mixed types\0");
+    /// ```
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn collapse(list: K) -> K{
+    ///   match KVal::from_raw(list).to_simple_list(){
+    ///     Ok(simple) => simple.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)collapse: `libapi_examples 2: (`collapse; 1);
+    /// q)collapse (1; 2; 3)
+    /// 1 2 3
+    /// q)collapse ("a"; "b")
+    /// "ab"
+    /// q)collapse (1; `a)
+    /// 'mixed types
+    /// ```
+    pub fn to_simple_list(self) -> Result<KVal<'a>, &'static str> {
+        let list = match self {
+            KVal::CompoundList(list) => list,
+            simple if (1..=qtype::ENUM_LIST).contains(&simple.qtype()) => return Ok(simple),
+            _ => return Err(NOT_A_LIST),
+        };
+        let atom_type = list.first().ok_or(EMPTY_LIST)?.qtype();
+        for element in &list {
+            match element.qtype() {
+                qtype::ERROR | 0.. => return Err(NOT_AN_ATOM),
+                element_type if element_type != atom_type => return Err(MIXED_TYPES),
+                _ => {}
+            }
+        }
+
+        /// Collect the atoms of a variant into a list of the same variant.
+        macro_rules! collapse {
+            ($variant: ident) => {
+                KVal::$variant(KData::List(Cow::Owned(
+                    list.into_iter()
+                        .map(|element| match element {
+                            KVal::$variant(KData::Atom(atom)) => atom.into_owned(),
+                            _ => unreachable!("elements were checked to be atoms of the same type"),
+                        })
+                        .collect(),
+                )))
+            };
+        }
+
+        Ok(match &list[0] {
+            KVal::Bool(_) => collapse!(Bool),
+            KVal::Guid(_) => collapse!(Guid),
+            KVal::Byte(_) => collapse!(Byte),
+            KVal::Short(_) => collapse!(Short),
+            KVal::Int(_) => collapse!(Int),
+            KVal::Long(_) => collapse!(Long),
+            KVal::Real(_) => collapse!(Real),
+            KVal::Float(_) => collapse!(Float),
+            KVal::Char(_) => KVal::String(Cow::Owned(
+                list.into_iter()
+                    .map(|element| match element {
+                        KVal::Char(character) => char_to_byte(character),
+                        _ => unreachable!("elements were checked to be chars"),
+                    })
+                    .collect::<Result<_, _>>()?,
+            )),
+            KVal::Symbol(_) => collapse!(Symbol),
+            KVal::Timestamp(_) => collapse!(Timestamp),
+            KVal::Month(_) => collapse!(Month),
+            KVal::Date(_) => collapse!(Date),
+            KVal::Datetime(_) => collapse!(Datetime),
+            KVal::Timespan(_) => collapse!(Timespan),
+            KVal::Minute(_) => collapse!(Minute),
+            KVal::Second(_) => collapse!(Second),
+            KVal::Time(_) => collapse!(Time),
+            KVal::Enum(_, source) => {
+                let source = source.clone();
+                let mut indices = Vec::with_capacity(list.len());
+                for element in list {
+                    match element {
+                        KVal::Enum(KData::Atom(index), element_source) => {
+                            if element_source != source {
+                                return Err(ENUM_SOURCE_MISMATCH);
+                            }
+                            indices.push(index.into_owned());
+                        }
+                        _ => unreachable!("elements were checked to be enum atoms"),
+                    }
+                }
+                KVal::Enum(KData::List(Cow::Owned(indices)), source)
+            }
+            _ => unreachable!("elements were checked to be atoms"),
+        })
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Build a simple list from elements if [`to_simple_list`](enum.KVal.html#method.to_simple_list) accepts them, i.e.,
+///  they are atoms of the same type. A compound list of the elements is built otherwise.
+pub(super) fn collapse_atoms(elements: Vec<KVal>) -> KVal {
+    let atom_type = elements.first().map_or(0, KVal::qtype);
+    let same_source = match elements.first() {
+        Some(KVal::Enum(_, source)) => elements.iter().all(|element| match element {
+            KVal::Enum(_, element_source) => element_source == source,
+            _ => false,
+        }),
+        _ => true,
+    };
+    let compound = KVal::CompoundList(elements);
+    if (qtype::ERROR + 1..0).contains(&atom_type)
+        && same_source
+        && compound.iter().all(|element| element.qtype() == atom_type)
+    {
+        compound
+            .to_simple_list()
+            .unwrap_or_else(|_| unreachable!("elements were checked to be atoms of the same type"))
+    } else {
+        compound
+    }
+}
+
+/// Build a compound list of atoms from the elements of a simple list without copying them.
+fn expand<'a, T: Clone>(
+    list: Cow<'a, [T]>,
+    variant: impl Fn(KData<'a, T>) -> KVal<'a>,
+) -> KVal<'a> {
+    KVal::CompoundList(match list {
+        Cow::Borrowed(list) => list
+            .iter()
+            .map(|element| variant(KData::Atom(Cow::Borrowed(element))))
+            .collect(),
+        Cow::Owned(list) => list
+            .into_iter()
+            .map(|element| variant(KData::Atom(Cow::Owned(element))))
+            .collect(),
+    })
+}
@@ -0,0 +1,303 @@
+//! Deserialization of `KVal` with `serde`, e.g., to build q objects from a JSON configuration. Available only when
+//!  `serde` feature is enabled.
+//!
+//! A value is built from the data model of `serde` in a similar manner to `.j.k` of q:
+//! - A boolean is a bool atom, an integer is a long atom and any other number is a float atom. An integer beyond the
+//!   range of long is a float atom.
+//! - A string is a string, or a symbol atom with [`StringFormat::Symbol`].
+//! - None and a unit, i.e., `null` in JSON, are a general null.
+//! - An object is a dictionary with symbol keys.
+//! - An array of objects with the same keys in the same order is a table.
+//! - An array of atoms of the same type is a simple list. An array of integers and other numbers is a float list and
+//!   `null` in an array of numbers is the float null. `null` in an array of symbols is the null symbol.
+//! - Any other array is a compound list.
+//!
+//! The values of a dictionary and the columns of a table follow the same rules as an array.
+//!
+//! ```
+//! use kdbplus::api::rusty_api::*;
+//! use std::borrow::Cow;
+//!
+//! let config = KVal::from_json(r#"{"port": 5001, "weights": [1, 0.5], "tables": ["trade", "quote"]}"#).unwrap();
+//! let config = match config {
+//!   KVal::Dictionary(config) => config,
+//!   _ => unreachable!()
+//! };
+//! assert_eq!(config.get("port"), Some(KVal::Long(KData::Atom(Cow::Borrowed(&5001)))));
+//! assert_eq!(config.get("weights"), Some(KVal::Float(KData::List(Cow::Owned(vec![1.0, 0.5])))));
+//! ```
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::compound::collapse_atoms;
+use super::{KData, KDict, KTable, KVal};
+use crate::qnull_base;
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::fmt;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Representation of strings in deserialization.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StringFormat {
+    /// A string is a string, i.e., a char list, in the same manner as `.j.k`.
+    #[default]
+    String,
+    /// A string is a symbol atom, and an array of strings is a symbol list.
+    Symbol,
+}
+
+/// Seed to deserialize `KVal` with a [`StringFormat`], e.g., from a format other than JSON.
+///  `KVal` itself is deserialized with [`StringFormat::String`].
+/// # Example
+/// ```
+/// use kdbplus::api::rusty_api::*;
+/// use serde::de::DeserializeSeed;
+/// use std::borrow::Cow;
+///
+/// let mut json = serde_json::Deserializer::from_str(r#"["a", "b"]"#);
+/// let symbols = DeserializeWith::new(StringFormat::Symbol).deserialize(&mut json).unwrap();
+/// assert_eq!(symbols, KVal::Symbol(KData::List(Cow::Owned(vec!["a".to_string(), "b".to_string()]))));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DeserializeWith {
+    /// Representation of strings.
+    strings: StringFormat,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl KVal<'static> {
+    /// Build a value from a JSON document. Strings are converted into strings. See [`from_json_with`](#method.from_json_with)
+    ///  to convert them into symbols.
+    ///
+    /// Available only when `serde` feature is enabled.
+    /// # Errors
+    /// A message of the JSON parser if the document is not a valid JSON.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn load_config(path: K) -> K{
+    ///   let json = match path.get_str().map(std::fs::read_to_string){
+    ///     Ok(Ok(json)) => json,
+    ///     _ => return new_error("cannot read the file\0")
+    ///   };
+    ///   match KVal::from_json(&json){
+    ///     Ok(config) => config.to_k(),
+    ///     Err(error) => new_error_chained("invalid config", &error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)load_config: `libapi_examples 2: (`load_config; 1);
+    /// q)`:config.json 0: enlist "{\"port\": 5001, \"tables\": [\"trade\", \"quote\"]}";
+    /// q)load_config "config.json"
+    /// port  | 5001
+    /// tables| ("trade";"quote")
+    /// ```
+    pub fn from_json(json: &str) -> Result<KVal<'static>, String> {
+        KVal::from_json_with(json, StringFormat::String)
+    }
+
+    /// Build a value from a JSON document converting strings as specified.
+    /// # Errors
+    /// A message of the JSON parser if the document is not a valid JSON.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let tables = KVal::from_json_with(r#"["trade", "quote"]"#, StringFormat::Symbol).unwrap();
+    /// assert_eq!(tables, KVal::Symbol(KData::List(Cow::Owned(vec!["trade".to_string(), "quote".to_string()]))));
+    /// ```
+    pub fn from_json_with(json: &str, strings: StringFormat) -> Result<KVal<'static>, String> {
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        let value = DeserializeWith::new(strings)
+            .deserialize(&mut deserializer)
+            .and_then(|value| deserializer.end().map(|_| value));
+        value.map_err(|error| error.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for KVal<'static> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        DeserializeWith::default().deserialize(deserializer)
+    }
+}
+
+impl DeserializeWith {
+    /// Build a seed converting strings as specified.
+    pub fn new(strings: StringFormat) -> Self {
+        DeserializeWith { strings }
+    }
+
+    /// Build a string or a symbol atom.
+    fn text(&self, text: String) -> KVal<'static> {
+        match self.strings {
+            StringFormat::String => KVal::String(Cow::Owned(text.into_bytes())),
+            StringFormat::Symbol => KVal::Symbol(KData::Atom(Cow::Owned(text))),
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for DeserializeWith {
+    type Value = KVal<'static>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DeserializeWith {
+    type Value = KVal<'static>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Self::Value, E> {
+        Ok(KVal::Bool(KData::Atom(Cow::Owned(value))))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E> {
+        Ok(KVal::Long(KData::Atom(Cow::Owned(value))))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(match i64::try_from(value) {
+            Ok(value) => KVal::Long(KData::Atom(Cow::Owned(value))),
+            Err(_) => KVal::Float(KData::Atom(Cow::Owned(value as f64))),
+        })
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E> {
+        Ok(KVal::Float(KData::Atom(Cow::Owned(value))))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(self.text(value.to_string()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Self::Value, E> {
+        Ok(self.text(value))
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E> {
+        Ok(KVal::Null)
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(KVal::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut elements = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(element) = seq.next_element_seed(self)? {
+            elements.push(element);
+        }
+        Ok(into_table(elements).unwrap_or_else(into_list))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut keys = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        let mut values = Vec::with_capacity(keys.capacity());
+        while let Some(key) = map.next_key::<String>()? {
+            keys.push(key);
+            values.push(map.next_value_seed(self)?);
+        }
+        Ok(KVal::Dictionary(KDict::new(
+            KVal::Symbol(KData::List(Cow::Owned(keys))),
+            into_list(values),
+        )))
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Build a simple list from atoms of the same type, promoting integers to floats among floats and filling general
+///  nulls with the null of the type of the other elements. A compound list is built otherwise.
+fn into_list(elements: Vec<KVal<'static>>) -> KVal<'static> {
+    let has_null = elements.contains(&KVal::Null);
+    let non_null = || elements.iter().filter(|element| **element != KVal::Null);
+    let numbers = non_null().all(|element| {
+        matches!(
+            element,
+            KVal::Long(KData::Atom(_)) | KVal::Float(KData::Atom(_))
+        )
+    });
+    let has_float = non_null().any(|element| matches!(element, KVal::Float(_)));
+    let symbols = non_null().all(|element| matches!(element, KVal::Symbol(KData::Atom(_))));
+    let has_value = non_null().next().is_some();
+
+    if has_value && numbers && (has_float || has_null) {
+        let floats = elements.iter().map(|element| match element {
+            KVal::Float(KData::Atom(float)) => **float,
+            KVal::Long(KData::Atom(long)) => **long as f64,
+            _ => qnull_base::F,
+        });
+        return KVal::Float(KData::List(Cow::Owned(floats.collect())));
+    }
+    let elements = if has_value && symbols && has_null {
+        elements
+            .into_iter()
+            .map(|element| match element {
+                KVal::Null => KVal::Symbol(KData::Atom(Cow::Owned(String::new()))),
+                symbol => symbol,
+            })
+            .collect()
+    } else {
+        elements
+    };
+    collapse_atoms(elements)
+}
+
+/// Build a table from dictionaries with the same symbol keys. `Err` gives the elements back if they are not such
+///  dictionaries or there is no element.
+fn into_table(elements: Vec<KVal<'static>>) -> Result<KVal<'static>, Vec<KVal<'static>>> {
+    let names = match elements.first() {
+        Some(KVal::Dictionary(KDict { keys, .. })) if !keys.is_empty() => keys.as_ref().clone(),
+        _ => return Err(elements),
+    };
+    let same_keys = elements.iter().all(|element| match element {
+        KVal::Dictionary(KDict { keys, .. }) => **keys == names,
+        _ => false,
+    });
+    if !same_keys {
+        return Err(elements);
+    }
+    let mut columns = vec![Vec::with_capacity(elements.len()); names.len()];
+    for element in elements {
+        let values = match element {
+            KVal::Dictionary(KDict { values, .. }) => values.to_compound_list(),
+            _ => unreachable!("elements were checked to be dictionaries"),
+        };
+        let values = match values {
+            KVal::CompoundList(values) => values,
+            _ => unreachable!("values of a dictionary are a list"),
+        };
+        for (column, value) in columns.iter_mut().zip(values) {
+            column.push(value);
+        }
+    }
+    Ok(KVal::Table(KTable::new(KDict::new(
+        names,
+        KVal::CompoundList(columns.into_iter().map(into_list).collect()),
+    ))))
+}
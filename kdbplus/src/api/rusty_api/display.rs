@@ -0,0 +1,886 @@
+//! Display of `KVal` in the same manner as q console.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::super::{decrement_reference_count, native, KNULL};
+use super::temporal::{
+    format_date, format_datetime, format_minute, format_month, format_second, format_time,
+    format_timespan, format_timestamp,
+};
+use super::{KData, KDict, KTable, KVal};
+use crate::{qinf_base, qnull_base};
+use std::fmt;
+use std::sync::OnceLock;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Maximum number of rows of a table or a dictionary to display.
+const MAX_ROWS: usize = 20;
+
+/// Default console size of q, i.e., `\c 25 80`.
+const DEFAULT_CONSOLE_SIZE: (usize, usize) = (25, 80);
+
+/// Null-terminated q expression returning the console size. `system` signals an error in a process without a
+///  console, which is trapped into the default size.
+const CONSOLE_SIZE_QUERY: &str = "@[system; \"c\"; 25 80i]\0";
+
+/// Milliseconds in a day, i.e., the unit of the fraction of a datetime.
+const ONE_DAY_MILLIS: f64 = 86_400_000.0;
+
+/// Console size read from q.
+static CONSOLE_SIZE: OnceLock<(usize, usize)> = OnceLock::new();
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Single line form of a value used for an element of a compound list or a cell of a table.
+struct Compact<'a, 'b>(&'b KVal<'a>);
+
+/// q expression of a value which is evaluated into the same value.
+struct Literal<'a, 'b>(&'b KVal<'a>);
+
+/// Column of a table to display.
+struct Column {
+    /// Column name.
+    name: String,
+    /// Displayed cells.
+    cells: Vec<String>,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Public Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Get the console size of q as `(rows, columns)`, i.e., the result of `\c`. The size is read from q on the first
+///  call and cached for the rest of the process. The default size `(25, 80)` is returned if q fails to return it.
+/// # Example
+/// ```no_run
+/// use kdbplus::api::*;
+/// use kdbplus::api::rusty_api::*;
+///
+/// #[no_mangle]
+/// pub extern "C" fn show_table(table: K) -> K{
+///   match KVal::from_raw(table){
+///     KVal::Table(table) => {
+///       let (rows, columns) = console_size();
+///       println!("{}", table.render(rows.saturating_sub(5), columns));
+///       KNULL
+///     }
+///     _ => new_error("not a table\0")
+///   }
+/// }
+/// ```
+pub fn console_size() -> (usize, usize) {
+    *CONSOLE_SIZE.get_or_init(|| {
+        let size = unsafe { native::k(0, CONSOLE_SIZE_QUERY.as_ptr() as *const _, KNULL) };
+        let result = match KVal::from_raw(size) {
+            KVal::Int(KData::List(size))
+                if size.len() == 2 && size.iter().all(|side| *side > 0) =>
+            {
+                (size[0] as usize, size[1] as usize)
+            }
+            _ => DEFAULT_CONSOLE_SIZE,
+        };
+        if !size.is_null() {
+            decrement_reference_count(size);
+        }
+        result
+    })
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Display a value in the same manner as q console.
+/// - Atoms and lists carry the type suffix where q needs it to distinguish the type, e.g., `1 2 3i`, `1f` and `0Np`.
+/// - Temporal values are displayed as calendar dates and clock times counted from the kdb+ epoch `2000.01.01`.
+/// - Nulls and infinities are displayed as `0N`, `0n`, `0W` and `0w` with the suffix of the type if necessary.
+/// - Tables and dictionaries are displayed as aligned rows. Only the first 20 rows are displayed and the rest is
+///   abbreviated with `..`. Nulls are displayed as blanks in these rows.
+/// # Example
+/// ```
+/// use kdbplus::api::rusty_api::*;
+/// use std::borrow::Cow;
+///
+/// let dates = KVal::Date(KData::List(Cow::Owned(vec![0, 8036, QDate::NULL.0])));
+/// assert_eq!(dates.to_string(), "2000.01.01 2022.01.01 0N");
+///
+/// let long = KVal::Long(KData::Atom(Cow::Owned(QTimestamp::NULL.0)));
+/// assert_eq!(long.to_string(), "0N");
+///
+/// let timestamp = KVal::Timestamp(KData::Atom(Cow::Owned(QTimestamp::NULL.0)));
+/// assert_eq!(timestamp.to_string(), "0Np");
+///
+/// let table = KVal::Table(KTable::new(KDict::new(
+///   KVal::Symbol(KData::List(Cow::Owned(vec!["sym".to_string(), "price".to_string()]))),
+///   KVal::CompoundList(vec![
+///     KVal::Symbol(KData::List(Cow::Owned(vec!["a".to_string(), "b".to_string()]))),
+///     KVal::Float(KData::List(Cow::Owned(vec![1.5, f64::NAN])))
+///   ])
+/// )));
+/// assert_eq!(table.to_string(), "sym price\n---------\na   1.5\nb");
+/// ```
+impl fmt::Display for KVal<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines = match self {
+            KVal::Table(table) => table_lines(table, MAX_ROWS),
+            KVal::Dictionary(dictionary) => dictionary_lines(dictionary),
+            _ => None,
+        };
+        match lines {
+            Some(lines) => f.write_str(&lines.join("\n")),
+            None => write_compact(f, self),
+        }
+    }
+}
+
+impl KTable<'_> {
+    /// Render the table in the same manner as q console of the given size.
+    /// - Only the first `max_rows` rows are rendered and the rest is abbreviated with a line of `..`.
+    /// - A line longer than `max_width` characters is cut and ends with `..`, so that every line fits in the width.
+    ///
+    /// Nulls are rendered as blanks. A malformed table is rendered in the form of q expression.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let table = KTable::new(KDict::new(
+    ///   KVal::Symbol(KData::List(Cow::Owned(vec!["sym".to_string(), "price".to_string()]))),
+    ///   KVal::CompoundList(vec![
+    ///     KVal::Symbol(KData::List(Cow::Owned(vec!["a".to_string(), "b".to_string(), "c".to_string()]))),
+    ///     KVal::Float(KData::List(Cow::Owned(vec![1.5, f64::NAN, 102.25])))
+    ///   ])
+    /// ));
+    /// assert_eq!(table.render(2, 8), "sym pr..\n------..\na   1.5\nb\n..");
+    /// ```
+    pub fn render(&self, max_rows: usize, max_width: usize) -> String {
+        let lines = table_lines(self, max_rows).unwrap_or_else(|| {
+            vec![format!(
+                "+{}",
+                Compact(&KVal::Dictionary(self.dict.clone()))
+            )]
+        });
+        lines
+            .iter()
+            .map(|line| fit(line, max_width))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl KVal<'_> {
+    /// Build a q expression which `value` evaluates into the same value, e.g., for golden tests and logging. Unlike
+    ///  [`Display`](#impl-Display-for-KVal%3C'_%3E) which may round values, the expression is exact:
+    /// - Floats and reals are written with the shortest digits read back into the same value.
+    /// - A datetime which is not a whole millisecond is written as a cast of its float, e.g., `` `datetime$1e-9 ``.
+    /// - Guids and symbols which are not q names are written as casts of strings, e.g., `` `$("a b";"c") ``.
+    /// - A dictionary is written as `(keys)!values` and a table as `+(keys)!values`, i.e., flip of a dictionary.
+    ///
+    /// An error is written as `'error` which signals the error on evaluation. An enum is evaluated into the same
+    ///  value only if the source exists in the q process.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let list = KVal::CompoundList(vec![
+    ///   KVal::Int(KData::Atom(Cow::Owned(1))),
+    ///   KVal::Symbol(KData::Atom(Cow::Owned("x".to_string()))),
+    ///   KVal::Float(KData::Atom(Cow::Owned(0.1)))
+    /// ]);
+    /// assert_eq!(list.to_q_literal(), "(1i;`x;0.1)");
+    ///
+    /// let table = KVal::Table(KTable::new(KDict::new(
+    ///   KVal::Symbol(KData::List(Cow::Owned(vec!["sym".to_string(), "price".to_string()]))),
+    ///   KVal::CompoundList(vec![
+    ///     KVal::Symbol(KData::List(Cow::Owned(vec!["a".to_string(), "b c".to_string()]))),
+    ///     KVal::Float(KData::List(Cow::Owned(vec![1.0, 2.0])))
+    ///   ])
+    /// )));
+    /// assert_eq!(table.to_q_literal(), "+(`sym`price)!(`$(\"a\";\"b c\");1 2f)");
+    /// ```
+    pub fn to_q_literal(&self) -> String {
+        Literal(self).to_string()
+    }
+}
+
+impl fmt::Display for Compact<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_compact(f, self.0)
+    }
+}
+
+impl fmt::Display for Literal<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_literal(f, self.0)
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Atom and List %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Write a value in a single line. Tables and dictionaries are written in the form of q expression.
+fn write_compact(f: &mut fmt::Formatter, value: &KVal) -> fmt::Result {
+    match value {
+        KVal::CompoundList(list) => match list.as_slice() {
+            [] => f.write_str("()"),
+            [element] => write!(f, ",{}", Compact(element)),
+            list => {
+                f.write_str("(")?;
+                for (i, element) in list.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(";")?;
+                    }
+                    write_compact(f, element)?;
+                }
+                f.write_str(")")
+            }
+        },
+        KVal::Bool(data) => write_simple(f, data, "boolean", "", "", bool_text, |_| "b"),
+        KVal::Guid(data) => write_simple(f, data, "guid", " ", "", guid_text, |_| ""),
+        KVal::Byte(data) => write_simple(f, data, "byte", "", "0x", byte_text, |_| ""),
+        KVal::Short(data) => write_simple(f, data, "short", " ", "", short_text, |_| "h"),
+        KVal::Int(data) => write_simple(f, data, "int", " ", "", int_text, |_| "i"),
+        KVal::Long(data) => write_simple(f, data, "long", " ", "", long_text, |_| ""),
+        KVal::Real(data) => write_simple(f, data, "real", " ", "", real_text, |_| "e"),
+        KVal::Float(data) => write_simple(f, data, "float", " ", "", float_text, float_suffix),
+        KVal::Char(character) => write!(f, "\"{}\"", escape(*character)),
+        KVal::Symbol(data) => write_simple(
+            f,
+            data,
+            "symbol",
+            "",
+            "",
+            |symbol| format!("`{}", symbol),
+            |_| "",
+        ),
+        KVal::String(string) => {
+            if string.len() == 1 {
+                f.write_str(",")?;
+            }
+            write!(f, "\"{}\"", escape_bytes(string))
+        }
+        KVal::Timestamp(data) => {
+            write_simple(f, data, "timestamp", " ", "", timestamp_text, |texts| {
+                border_suffix(texts, "p")
+            })
+        }
+        KVal::Month(data) => write_simple(f, data, "month", " ", "", month_text, |_| "m"),
+        KVal::Date(data) => write_simple(f, data, "date", " ", "", date_text, |texts| {
+            border_suffix(texts, "d")
+        }),
+        KVal::Datetime(data) => {
+            write_simple(f, data, "datetime", " ", "", datetime_text, |texts| {
+                border_suffix(texts, "z")
+            })
+        }
+        KVal::Timespan(data) => {
+            write_simple(f, data, "timespan", " ", "", timespan_text, |texts| {
+                border_suffix(texts, "n")
+            })
+        }
+        KVal::Minute(data) => write_simple(f, data, "minute", " ", "", minute_text, |texts| {
+            border_suffix(texts, "u")
+        }),
+        KVal::Second(data) => write_simple(f, data, "second", " ", "", second_text, |texts| {
+            border_suffix(texts, "v")
+        }),
+        KVal::Time(data) => write_simple(f, data, "time", " ", "", time_text, |texts| {
+            border_suffix(texts, "t")
+        }),
+        KVal::Enum(data, source) => {
+            write!(f, "`{}!", source.as_deref().unwrap_or(""))?;
+            write_simple(f, data, "long", " ", "", long_text, |_| "")
+        }
+        KVal::Table(table) => {
+            f.write_str("+")?;
+            write_compact_dictionary(f, &table.dict)
+        }
+        KVal::Dictionary(dictionary) => write_compact_dictionary(f, dictionary),
+        KVal::Error(error) => write!(f, "'{}", error),
+        KVal::Null => f.write_str("::"),
+    }
+}
+
+/// Write a dictionary as `keys!values`. A table or a dictionary on either side is enclosed by parentheses. A sorted
+///  dictionary is prefixed with `` `s# ``.
+fn write_compact_dictionary(f: &mut fmt::Formatter, dictionary: &KDict) -> fmt::Result {
+    if dictionary.keeps_sorted() {
+        f.write_str("`s#")?;
+    }
+    for (i, side) in [&dictionary.keys, &dictionary.values]
+        .into_iter()
+        .enumerate()
+    {
+        if i != 0 {
+            f.write_str("!")?;
+        }
+        match side.as_ref() {
+            KVal::Table(_) | KVal::Dictionary(_) => write!(f, "({})", Compact(side))?,
+            _ => write_compact(f, side)?,
+        }
+    }
+    Ok(())
+}
+
+/// Write an atom or a simple list.
+/// # Parameters
+/// - `name`: Type name used for an empty list, e.g., `` `long$() ``.
+/// - `separator`: Separator of elements.
+/// - `prefix`: Prefix put before the first element.
+/// - `element`: Text of an element without a type suffix.
+/// - `suffix`: Type suffix decided from texts of all elements.
+fn write_simple<T: Clone>(
+    f: &mut fmt::Formatter,
+    data: &KData<T>,
+    name: &str,
+    separator: &str,
+    prefix: &str,
+    element: fn(&T) -> String,
+    suffix: fn(&[String]) -> &'static str,
+) -> fmt::Result {
+    let texts = match data {
+        KData::Atom(atom) => vec![element(atom)],
+        KData::List(list) if list.is_empty() => return write!(f, "`{}$()", name),
+        KData::List(list) => {
+            if list.len() == 1 {
+                f.write_str(",")?;
+            }
+            list.iter().map(element).collect::<Vec<_>>()
+        }
+    };
+    write!(f, "{}{}{}", prefix, texts.join(separator), suffix(&texts))
+}
+
+/// Suffix of a float list which is necessary only when all elements look like integers.
+fn float_suffix(texts: &[String]) -> &'static str {
+    if texts.iter().any(|text| text.contains(['.', 'e', 'n', 'w'])) {
+        ""
+    } else {
+        "f"
+    }
+}
+
+/// Suffix of a temporal list which is necessary only when all elements are nulls or infinities.
+fn border_suffix(texts: &[String], suffix: &'static str) -> &'static str {
+    if texts.iter().all(|text| text.ends_with(['N', 'W', 'w'])) {
+        suffix
+    } else {
+        ""
+    }
+}
+
+//%% Literal %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Write a value as a q expression evaluated into the same value. Values whose compact form is exact are written
+///  in the compact form.
+fn write_literal(f: &mut fmt::Formatter, value: &KVal) -> fmt::Result {
+    match value {
+        KVal::CompoundList(list) => match list.as_slice() {
+            [] => f.write_str("()"),
+            // q collapses atoms of the same type into a simple list. A trailing `::` keeps the list general.
+            [first, ..]
+                if first.qtype() < 0
+                    && list.iter().all(|element| element.qtype() == first.qtype()) =>
+            {
+                f.write_str("-1_(")?;
+                for element in list {
+                    write!(f, "{};", Literal(element))?;
+                }
+                f.write_str("::)")
+            }
+            [element] => write!(f, ",{}", Literal(element)),
+            list => {
+                f.write_str("(")?;
+                for (i, element) in list.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(";")?;
+                    }
+                    write_literal(f, element)?;
+                }
+                f.write_str(")")
+            }
+        },
+        KVal::Guid(data) => write_cast(f, data, "guid", "\"G\"$", guid_text),
+        KVal::Real(data) => write_simple(f, data, "real", " ", "", real_literal, |_| "e"),
+        KVal::Float(data) => write_simple(f, data, "float", " ", "", float_literal, float_suffix),
+        KVal::Symbol(data) if !symbol_names(data) => {
+            write_cast(f, data, "symbol", "`$", String::clone)
+        }
+        KVal::Datetime(data) if !whole_millis(data) => {
+            f.write_str("`datetime$")?;
+            write_simple(f, data, "float", " ", "", float_literal, float_suffix)
+        }
+        KVal::Table(table) => {
+            f.write_str("+")?;
+            write_literal_dictionary(f, &table.dict)
+        }
+        KVal::Dictionary(dictionary) => write_literal_dictionary(f, dictionary),
+        exact => write_compact(f, exact),
+    }
+}
+
+/// Write a dictionary as `(keys)!values`. Keys are always enclosed by parentheses, so that `!` is not taken as
+///  enumeration or applied to a part of them. A sorted dictionary is prefixed with `` `s# ``.
+fn write_literal_dictionary(f: &mut fmt::Formatter, dictionary: &KDict) -> fmt::Result {
+    if dictionary.keeps_sorted() {
+        f.write_str("`s#")?;
+    }
+    write!(f, "({})!", Literal(&dictionary.keys))?;
+    match dictionary.values.as_ref() {
+        KVal::Table(_) | KVal::Dictionary(_) => write!(f, "({})", Literal(&dictionary.values)),
+        values => write_literal(f, values),
+    }
+}
+
+/// Write an atom or a simple list as a cast of strings, e.g., `` `$("a";"b") ``.
+/// # Parameters
+/// - `name`: Type name used for an empty list.
+/// - `cast`: Cast put before the strings.
+/// - `element`: Text of an element.
+fn write_cast<T: Clone>(
+    f: &mut fmt::Formatter,
+    data: &KData<T>,
+    name: &str,
+    cast: &str,
+    element: fn(&T) -> String,
+) -> fmt::Result {
+    let string = |value: &T| {
+        format!(
+            "\"{}\"",
+            element(value).chars().map(escape).collect::<String>()
+        )
+    };
+    match data {
+        KData::Atom(atom) => write!(f, "{}{}", cast, string(atom)),
+        KData::List(list) => match list.as_ref() {
+            [] => write!(f, "`{}$()", name),
+            [element] => write!(f, ",{}{}", cast, string(element)),
+            list => write!(
+                f,
+                "{}({})",
+                cast,
+                list.iter().map(string).collect::<Vec<_>>().join(";")
+            ),
+        },
+    }
+}
+
+/// Check if all symbols are written as `` `name ``, i.e., made of alphanumerics, `_` and `.`.
+fn symbol_names(data: &KData<String>) -> bool {
+    let name = |symbol: &String| {
+        symbol
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || matches!(character, '_' | '.'))
+    };
+    match data {
+        KData::Atom(atom) => name(atom),
+        KData::List(list) => list.iter().all(name),
+    }
+}
+
+/// Check if all datetimes are whole milliseconds, nulls or infinities, i.e., the text form is exact.
+fn whole_millis(data: &KData<f64>) -> bool {
+    let whole =
+        |days: &f64| !days.is_finite() || (days * ONE_DAY_MILLIS).round() / ONE_DAY_MILLIS == *days;
+    match data {
+        KData::Atom(atom) => whole(atom),
+        KData::List(list) => list.iter().all(whole),
+    }
+}
+
+/// Shortest text of a real read back into the same value.
+fn real_literal(value: &f32) -> String {
+    match *value {
+        real if real.is_nan() => "0N".to_string(),
+        real if real.is_infinite() => float_or_infinity(real as f64),
+        real => whole_number(format!("{:?}", real)),
+    }
+}
+
+/// Shortest text of a float read back into the same value.
+fn float_literal(value: &f64) -> String {
+    match *value {
+        float if float.is_nan() => "0n".to_string(),
+        float if float.is_infinite() => float_or_infinity(float),
+        float => whole_number(format!("{:?}", float)),
+    }
+}
+
+/// Remove `.0` of a whole number, e.g., `1.0`, so that a list is written as `1 2f`.
+fn whole_number(text: String) -> String {
+    match text.strip_suffix(".0") {
+        Some(whole) => whole.to_string(),
+        None => text,
+    }
+}
+
+//%% Element %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Text of a null or an infinity of an integral type.
+fn border(value: i64, null: i64, inf: i64) -> Option<&'static str> {
+    if value == null {
+        Some("0N")
+    } else if value == inf {
+        Some("0W")
+    } else if value == -inf {
+        Some("-0W")
+    } else {
+        None
+    }
+}
+
+fn bool_text(value: &bool) -> String {
+    (*value as u8).to_string()
+}
+
+fn byte_text(value: &u8) -> String {
+    format!("{:02x}", value)
+}
+
+pub(super) fn guid_text(value: &[u8; 16]) -> String {
+    let hex = value
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn short_text(value: &i16) -> String {
+    border(*value as i64, qnull_base::H as i64, qinf_base::H as i64)
+        .map_or_else(|| value.to_string(), String::from)
+}
+
+fn int_text(value: &i32) -> String {
+    border(*value as i64, qnull_base::I as i64, qinf_base::I as i64)
+        .map_or_else(|| value.to_string(), String::from)
+}
+
+fn long_text(value: &i64) -> String {
+    border(*value, qnull_base::J, qinf_base::J).map_or_else(|| value.to_string(), String::from)
+}
+
+fn real_text(value: &f32) -> String {
+    match *value as f64 {
+        real if real.is_nan() => "0N".to_string(),
+        real => float_or_infinity(real),
+    }
+}
+
+fn float_text(value: &f64) -> String {
+    match *value {
+        float if float.is_nan() => "0n".to_string(),
+        float => float_or_infinity(float),
+    }
+}
+
+fn timestamp_text(value: &i64) -> String {
+    border(*value, qnull_base::J, qinf_base::J)
+        .map_or_else(|| format_timestamp(*value), String::from)
+}
+
+fn month_text(value: &i32) -> String {
+    border(*value as i64, qnull_base::I as i64, qinf_base::I as i64)
+        .map_or_else(|| format_month(*value), String::from)
+}
+
+fn date_text(value: &i32) -> String {
+    border(*value as i64, qnull_base::I as i64, qinf_base::I as i64)
+        .map_or_else(|| format_date(*value), String::from)
+}
+
+fn datetime_text(value: &f64) -> String {
+    match *value {
+        days if days.is_nan() => "0N".to_string(),
+        days if days == qinf_base::F => "0w".to_string(),
+        days if days == -qinf_base::F => "-0w".to_string(),
+        days => format_datetime(days),
+    }
+}
+
+fn timespan_text(value: &i64) -> String {
+    border(*value, qnull_base::J, qinf_base::J)
+        .map_or_else(|| format_timespan(*value), String::from)
+}
+
+fn minute_text(value: &i32) -> String {
+    border(*value as i64, qnull_base::I as i64, qinf_base::I as i64)
+        .map_or_else(|| format_minute(*value), String::from)
+}
+
+fn second_text(value: &i32) -> String {
+    border(*value as i64, qnull_base::I as i64, qinf_base::I as i64)
+        .map_or_else(|| format_second(*value), String::from)
+}
+
+fn time_text(value: &i32) -> String {
+    border(*value as i64, qnull_base::I as i64, qinf_base::I as i64)
+        .map_or_else(|| format_time(*value), String::from)
+}
+
+/// Text of a non-null float with 7 significant digits in the same manner as `%g` of C.
+fn float_or_infinity(value: f64) -> String {
+    if value.is_infinite() {
+        return if value > 0.0 { "0w" } else { "-0w" }.to_string();
+    }
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    let scientific = format!("{:.6e}", value);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent = exponent.parse::<i32>().unwrap();
+    if (-4..7).contains(&exponent) {
+        trim_fraction(&format!("{:.*}", (6 - exponent) as usize, value)).to_string()
+    } else {
+        format!(
+            "{}e{}{:02}",
+            trim_fraction(mantissa),
+            if exponent < 0 { '-' } else { '+' },
+            exponent.unsigned_abs()
+        )
+    }
+}
+
+/// Remove trailing zeros of a fraction and the decimal point left alone.
+fn trim_fraction(text: &str) -> &str {
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        text
+    }
+}
+
+/// Escape a character in a q string.
+fn escape(character: char) -> String {
+    match character {
+        '"' => "\\\"".to_string(),
+        '\\' => "\\\\".to_string(),
+        '\n' => "\\n".to_string(),
+        '\r' => "\\r".to_string(),
+        '\t' => "\\t".to_string(),
+        control if control.is_ascii_control() => format!("\\{:03o}", control as u8),
+        character => character.to_string(),
+    }
+}
+
+/// Escape the bytes of a string. A valid UTF-8 sequence is written as characters and any other byte as an octal
+///  escape, e.g., `\377`, so that q reads the text back into the same bytes.
+fn escape_bytes(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        text.extend(chunk.valid().chars().map(escape));
+        chunk
+            .invalid()
+            .iter()
+            .for_each(|byte| text.push_str(&format!("\\{:03o}", byte)));
+    }
+    text
+}
+
+/// Text of an element in a table or a dictionary. Nulls are blank and no type suffix is added.
+fn cell_text(value: &KVal) -> String {
+    let text = match value {
+        KVal::Bool(KData::Atom(atom)) => bool_text(atom),
+        KVal::Guid(KData::Atom(atom)) => guid_text(atom),
+        KVal::Byte(KData::Atom(atom)) => byte_text(atom),
+        KVal::Short(KData::Atom(atom)) => short_text(atom),
+        KVal::Int(KData::Atom(atom)) => int_text(atom),
+        KVal::Long(KData::Atom(atom)) | KVal::Enum(KData::Atom(atom), _) => long_text(atom),
+        KVal::Real(KData::Atom(atom)) => real_text(atom),
+        KVal::Float(KData::Atom(atom)) => float_text(atom),
+        KVal::Char(character) => character.to_string(),
+        KVal::Symbol(KData::Atom(atom)) => atom.to_string(),
+        KVal::Timestamp(KData::Atom(atom)) => timestamp_text(atom),
+        KVal::Month(KData::Atom(atom)) => month_text(atom),
+        KVal::Date(KData::Atom(atom)) => date_text(atom),
+        KVal::Datetime(KData::Atom(atom)) => datetime_text(atom),
+        KVal::Timespan(KData::Atom(atom)) => timespan_text(atom),
+        KVal::Minute(KData::Atom(atom)) => minute_text(atom),
+        KVal::Second(KData::Atom(atom)) => second_text(atom),
+        KVal::Time(KData::Atom(atom)) => time_text(atom),
+        other => Compact(other).to_string(),
+    };
+    match text.as_str() {
+        "0N" | "0n" => String::new(),
+        _ => text,
+    }
+}
+
+//%% Table and Dictionary %%//vvvvvvvvvvvvvvvvvvvvvv/
+
+/// Lines of a table up to `max_rows` rows. `None` is returned if the table is malformed.
+fn table_lines(table: &KTable, max_rows: usize) -> Option<Vec<String>> {
+    let rows = table.len();
+    let columns = columns(table, rows, max_rows)?;
+    Some(grid(&[], &columns, rows, max_rows))
+}
+
+/// Lines of a dictionary including a keyed table. `None` is returned if the dictionary is malformed.
+fn dictionary_lines(dictionary: &KDict) -> Option<Vec<String>> {
+    if let (KVal::Table(keys), KVal::Table(values)) =
+        (dictionary.keys.as_ref(), dictionary.values.as_ref())
+    {
+        let rows = keys.len();
+        if values.len() != rows {
+            return None;
+        }
+        return Some(grid(
+            &columns(keys, rows, MAX_ROWS)?,
+            &columns(values, rows, MAX_ROWS)?,
+            rows,
+            MAX_ROWS,
+        ));
+    }
+    let rows = dictionary.len();
+    if dictionary.values.len() != rows {
+        return None;
+    }
+    let shown = rows.min(MAX_ROWS);
+    let keys = (0..shown)
+        .map(|i| dictionary.keys.get(i).as_ref().map(cell_text))
+        .collect::<Option<Vec<_>>>()?;
+    let values = (0..shown)
+        .map(|i| dictionary.values.get(i).as_ref().map(cell_text))
+        .collect::<Option<Vec<_>>>()?;
+    let width = keys
+        .iter()
+        .map(|key| key.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines = keys
+        .iter()
+        .zip(values.iter())
+        .map(|(key, value)| {
+            format!("{:<width$}| {}", key, value, width = width)
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    if rows > shown {
+        lines.push("..".to_string());
+    }
+    Some(lines)
+}
+
+/// Displayed columns of a table up to `max_rows` rows.
+fn columns(table: &KTable, rows: usize, max_rows: usize) -> Option<Vec<Column>> {
+    let names = match table.dict.keys.as_ref() {
+        KVal::Symbol(KData::List(names)) => names,
+        _ => return None,
+    };
+    let columns = match table.dict.values.as_ref() {
+        KVal::CompoundList(columns) if columns.len() == names.len() => columns,
+        _ => return None,
+    };
+    names
+        .iter()
+        .zip(columns.iter())
+        .map(|(name, column)| {
+            if column.len() != rows {
+                return None;
+            }
+            let cells = (0..rows.min(max_rows))
+                .map(|i| column.get(i).as_ref().map(cell_text))
+                .collect::<Option<Vec<_>>>()?;
+            Some(Column {
+                name: name.clone(),
+                cells,
+            })
+        })
+        .collect()
+}
+
+/// Lines of a table made of a header, a rule and up to `max_rows` rows. Key columns are separated from the other
+///  columns by `|`.
+fn grid(
+    key_columns: &[Column],
+    value_columns: &[Column],
+    rows: usize,
+    max_rows: usize,
+) -> Vec<String> {
+    let key_widths = widths(key_columns);
+    let value_widths = widths(value_columns);
+    // `None` stands for the header.
+    let line = |row: Option<usize>| {
+        let values = pad(value_columns, &value_widths, row);
+        let line = if key_columns.is_empty() {
+            values
+        } else {
+            format!("{}| {}", pad(key_columns, &key_widths, row), values)
+        };
+        line.trim_end().to_string()
+    };
+    let rule = |widths: &[usize]| {
+        "-".repeat(widths.iter().sum::<usize>() + widths.len().saturating_sub(1))
+    };
+    let mut lines = vec![line(None)];
+    lines.push(if key_columns.is_empty() {
+        rule(&value_widths)
+    } else {
+        format!("{}| {}", rule(&key_widths), rule(&value_widths))
+    });
+    lines.extend((0..rows.min(max_rows)).map(|i| line(Some(i))));
+    if rows > max_rows {
+        lines.push("..".to_string());
+    }
+    lines
+}
+
+/// Width of each column, i.e., the longest of the name and the cells.
+fn widths(columns: &[Column]) -> Vec<usize> {
+    columns
+        .iter()
+        .map(|column| {
+            column
+                .cells
+                .iter()
+                .map(|cell| cell.chars().count())
+                .chain(Some(column.name.chars().count()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Join names of columns or cells of a row padded to the widths with a space.
+fn pad(columns: &[Column], widths: &[usize], row: Option<usize>) -> String {
+    columns
+        .iter()
+        .zip(widths.iter())
+        .map(|(column, width)| {
+            let text = row.map_or(&column.name, |i| &column.cells[i]);
+            format!("{:<width$}", text, width = width)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Cut a line longer than `width` characters so that it ends with `..` within the width.
+fn fit(line: &str, width: usize) -> String {
+    if line.chars().count() <= width {
+        return line.to_string();
+    }
+    let mut fitted = line
+        .chars()
+        .take(width.saturating_sub(2))
+        .collect::<String>();
+    fitted.push_str(&".."[..width.min(2)]);
+    fitted
+}
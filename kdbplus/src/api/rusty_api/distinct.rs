@@ -0,0 +1,176 @@
+//! Removal of duplicates from a list of `KVal` in the manner of `distinct` in q.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::super::{KUtility, K};
+use super::errors::NOT_A_LIST;
+use super::hash::{element_hash, HashElement};
+use super::sort::QOrd;
+use super::{KData, KVal};
+use crate::qattribute;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Deref;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// List without duplicates built by [`KVal::distinct`](enum.KVal.html#method.distinct). A simple list is built into
+///  a q list with the unique attribute `u#`, so that q can look it up by a hash table. The list is read through
+///  `Deref` and cannot be modified, as a modification could introduce a duplicate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistinctList<'a>(KVal<'a>);
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl<'a> KVal<'a> {
+    /// Remove duplicates from a list in the same manner as `distinct` in q. The first occurrence of each element is
+    ///  kept in its original order.
+    /// - Bool, GUID, byte, short, int, long, real, float, symbol, temporal and enum lists are compared by value.
+    ///   Every float null is equal to each other and so are `0.0` and `-0.0`.
+    /// - String is compared by bytes in the same manner as q.
+    /// - Compound list is compared by `==` of `KVal`.
+    ///
+    /// A list borrowing q memory is copied only if it has a duplicate.
+    /// # Errors
+    /// - `"not a list"` if the value is an atom, a table, a dictionary, an error or a general null.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let prices = KVal::Float(KData::List(Cow::Owned(vec![1.5, f64::NAN, 1.5, -f64::NAN])));
+    /// let distinct = prices.distinct().unwrap();
+    /// assert_eq!(*distinct, KVal::Float(KData::List(Cow::Owned(vec![1.5, f64::NAN]))));
+    /// ```
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn distinct_of(list: K) -> K{
+    ///   match KVal::from_raw(list).distinct(){
+    ///     Ok(distinct) => distinct.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)distinct_of: `libapi_examples 2: (`distinct_of; 1);
+    /// q)distinct_of `b`a`b`c`a
+    /// `u#`b`a`c
+    /// q)distinct_of (1; `a; 1; "ab"; "ab")
+    /// 1
+    /// `a
+    /// "ab"
+    /// ```
+    pub fn distinct(self) -> Result<DistinctList<'a>, &'static str> {
+        let distinct = match self {
+            KVal::Bool(data) => distinct_data(data).map(KVal::Bool),
+            KVal::Guid(data) => distinct_data(data).map(KVal::Guid),
+            KVal::Byte(data) => distinct_data(data).map(KVal::Byte),
+            KVal::Short(data) => distinct_data(data).map(KVal::Short),
+            KVal::Int(data) => distinct_data(data).map(KVal::Int),
+            KVal::Long(data) => distinct_data(data).map(KVal::Long),
+            KVal::Real(data) => distinct_data(data).map(KVal::Real),
+            KVal::Float(data) => distinct_data(data).map(KVal::Float),
+            KVal::Symbol(data) => distinct_data(data).map(KVal::Symbol),
+            KVal::String(string) => Ok(KVal::String(distinct_list(string))),
+            KVal::Timestamp(data) => distinct_data(data).map(KVal::Timestamp),
+            KVal::Month(data) => distinct_data(data).map(KVal::Month),
+            KVal::Date(data) => distinct_data(data).map(KVal::Date),
+            KVal::Datetime(data) => distinct_data(data).map(KVal::Datetime),
+            KVal::Timespan(data) => distinct_data(data).map(KVal::Timespan),
+            KVal::Minute(data) => distinct_data(data).map(KVal::Minute),
+            KVal::Second(data) => distinct_data(data).map(KVal::Second),
+            KVal::Time(data) => distinct_data(data).map(KVal::Time),
+            // Enums of the same source are equal if and only if their indices are equal.
+            KVal::Enum(data, source) => distinct_data(data).map(|data| KVal::Enum(data, source)),
+            KVal::CompoundList(list) => {
+                let kept = distinct_indices(&list, |left, right| left == right);
+                Ok(KVal::CompoundList(if kept.len() == list.len() {
+                    list
+                } else {
+                    let mut kept = kept.into_iter().peekable();
+                    list.into_iter()
+                        .enumerate()
+                        .filter(|(index, _)| kept.next_if_eq(index).is_some())
+                        .map(|(_, element)| element)
+                        .collect()
+                }))
+            }
+            _ => Err(NOT_A_LIST),
+        };
+        distinct.map(DistinctList)
+    }
+}
+
+impl<'a> DistinctList<'a> {
+    /// Build a new q list with the unique attribute. A compound list is built without the attribute as q does not
+    ///  hold an attribute on it. A q error is returned if the list cannot be converted (see
+    ///  [`KVal::to_k`](enum.KVal.html#method.to_k)).
+    pub fn to_k(&self) -> K {
+        let mut list = self.0.to_k();
+        if !list.is_null() {
+            // An error object and a compound list do not take an attribute and are returned as they are.
+            let _ = list.set_attribute(qattribute::UNIQUE);
+        }
+        list
+    }
+
+    /// Take the distinct list out.
+    pub fn into_inner(self) -> KVal<'a> {
+        self.0
+    }
+}
+
+impl<'a> Deref for DistinctList<'a> {
+    type Target = KVal<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Remove duplicates from a list. A borrowed list without a duplicate is returned as it is.
+fn distinct_data<T: QOrd + HashElement + Clone>(
+    data: KData<'_, T>,
+) -> Result<KData<'_, T>, &'static str> {
+    match data {
+        KData::Atom(_) => Err(NOT_A_LIST),
+        KData::List(list) => Ok(KData::List(distinct_list(list))),
+    }
+}
+
+/// Remove duplicates from a list in the same manner as `distinct_data`.
+fn distinct_list<T: QOrd + HashElement + Clone>(list: Cow<'_, [T]>) -> Cow<'_, [T]> {
+    let kept = distinct_indices(&list, |left, right| left.q_cmp(right).is_eq());
+    if kept.len() == list.len() {
+        return list;
+    }
+    Cow::Owned(kept.into_iter().map(|index| list[index].clone()).collect())
+}
+
+/// Find the indices of the first occurrences of the elements in ascending order. Elements are bucketed by their
+///  content hash, which is equal for equal elements, and compared within a bucket by `equal`.
+fn distinct_indices<T: HashElement>(list: &[T], equal: impl Fn(&T, &T) -> bool) -> Vec<usize> {
+    let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut kept = Vec::new();
+    for (index, element) in list.iter().enumerate() {
+        let bucket = buckets.entry(element_hash(element)).or_default();
+        if !bucket.iter().any(|&first| equal(&list[first], element)) {
+            bucket.push(index);
+            kept.push(index);
+        }
+    }
+    kept
+}
@@ -0,0 +1,444 @@
+//! Insertion, replacement, removal and reordering of elements of a list of `KVal` in place.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::errors::{ENUM_SOURCE_MISMATCH, INDEX_OUT_OF_BOUNDS, NOT_AN_ATOM, NOT_A_LIST, TYPE};
+use super::kval::{atom, char_to_byte};
+use super::{KData, KVal};
+use crate::qtype;
+use std::borrow::Cow;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Reordering of the elements of a list.
+#[derive(Clone, Copy)]
+enum Reorder {
+    Reverse,
+    /// Rotate to the left by the number of elements. A negative number rotates to the right.
+    Rotate(isize),
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl<'a> KVal<'a> {
+    /// Remove the last element of the list and return it as an atom in the same manner as
+    ///  [`remove`](#method.remove). Returns `None` if the list is empty or the value is not a list.
+    ///
+    /// A list borrowing q memory is shortened without being copied.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let mut string = KVal::String(Cow::Borrowed(b"ab"));
+    /// assert_eq!(string.pop(), Some(KVal::Char('b')));
+    /// assert_eq!(string.pop(), Some(KVal::Char('a')));
+    /// assert_eq!(string.pop(), None);
+    /// assert_eq!(KVal::Char('a').pop(), None);
+    /// ```
+    pub fn pop(&mut self) -> Option<KVal<'a>> {
+        let last = self.len().checked_sub(1)?;
+        self.remove(last).ok()
+    }
+
+    /// Remove the element at the specified index and return it as an atom. The following elements are shifted
+    ///  to the left.
+    /// - Simple list yields an atom of the same type.
+    /// - String yields `Char` of the byte at the index, which is removed alone.
+    /// - Compound list yields the element as it is.
+    /// - Enum list yields an enum atom carrying the same source.
+    ///
+    /// A list borrowing q memory is copied once into an owned list unless the last element is removed.
+    /// # Errors
+    /// - `"not a list"` if the value is an atom, a table, a dictionary, an error or a general null.
+    /// - `"index out of bounds"` if the index is not less than the length.
+    ///
+    /// The value is left unchanged on error.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn take_out(list: K, index: K) -> K{
+    ///   let mut list = KVal::from_raw(list);
+    ///   match index.get_long(){
+    ///     Ok(index) if index >= 0 => match list.remove(index as usize){
+    ///       Ok(_) => list.to_k(),
+    ///       Err(error) => new_error(error)
+    ///     },
+    ///     _ => new_error("index must be a non-negative long\0")
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)take_out: `libapi_examples 2: (`take_out; 2);
+    /// q)take_out[`a`b`c; 1]
+    /// `a`c
+    /// q)take_out[1 2 3; 3]
+    /// 'index out of bounds
+    /// ```
+    pub fn remove(&mut self, index: usize) -> Result<KVal<'a>, &'static str> {
+        match self {
+            KVal::CompoundList(list) => {
+                if index < list.len() {
+                    Ok(list.remove(index))
+                } else {
+                    Err(INDEX_OUT_OF_BOUNDS)
+                }
+            }
+            KVal::Bool(data) => remove_data(data, index).map(|element| KVal::Bool(atom(element))),
+            KVal::Guid(data) => remove_data(data, index).map(|element| KVal::Guid(atom(element))),
+            KVal::Byte(data) => remove_data(data, index).map(|element| KVal::Byte(atom(element))),
+            KVal::Short(data) => remove_data(data, index).map(|element| KVal::Short(atom(element))),
+            KVal::Int(data) => remove_data(data, index).map(|element| KVal::Int(atom(element))),
+            KVal::Long(data) => remove_data(data, index).map(|element| KVal::Long(atom(element))),
+            KVal::Real(data) => remove_data(data, index).map(|element| KVal::Real(atom(element))),
+            KVal::Float(data) => remove_data(data, index).map(|element| KVal::Float(atom(element))),
+            KVal::Symbol(data) => {
+                remove_data(data, index).map(|element| KVal::Symbol(atom(element)))
+            }
+            KVal::String(string) => remove_list(string, index).map(|byte| KVal::Char(byte as char)),
+            KVal::Timestamp(data) => {
+                remove_data(data, index).map(|element| KVal::Timestamp(atom(element)))
+            }
+            KVal::Month(data) => remove_data(data, index).map(|element| KVal::Month(atom(element))),
+            KVal::Date(data) => remove_data(data, index).map(|element| KVal::Date(atom(element))),
+            KVal::Datetime(data) => {
+                remove_data(data, index).map(|element| KVal::Datetime(atom(element)))
+            }
+            KVal::Timespan(data) => {
+                remove_data(data, index).map(|element| KVal::Timespan(atom(element)))
+            }
+            KVal::Minute(data) => {
+                remove_data(data, index).map(|element| KVal::Minute(atom(element)))
+            }
+            KVal::Second(data) => {
+                remove_data(data, index).map(|element| KVal::Second(atom(element)))
+            }
+            KVal::Time(data) => remove_data(data, index).map(|element| KVal::Time(atom(element))),
+            KVal::Enum(data, source) => {
+                remove_data(data, index).map(|element| KVal::Enum(atom(element), source.clone()))
+            }
+            KVal::Char(_) | KVal::Table(_) | KVal::Dictionary(_) | KVal::Error(_) | KVal::Null => {
+                Err(NOT_A_LIST)
+            }
+        }
+    }
+
+    /// Insert an element at the specified index shifting the following elements to the right. The index equal to
+    ///  the length appends the element.
+    /// - Simple list takes an atom of the same type.
+    /// - String takes `Char` inserted as a single byte, whose code point must not exceed `0xFF`.
+    /// - Compound list takes any value.
+    /// - Enum list takes an enum atom of the same source.
+    ///
+    /// A list borrowing q memory is copied once into an owned list.
+    /// # Errors
+    /// - `"not a list"` if the value is an atom, a table, a dictionary, an error or a general null.
+    /// - `"not an atom"` if `value` is not an atom and the list is not a compound list.
+    /// - `"index out of bounds"` if the index is greater than the length.
+    /// - `"char out of byte range"` if a char inserted into a string is greater than `'\u{ff}'`.
+    /// - `"type"` if `value` has a different type from the list.
+    /// - `"enum sources differ"` if enums have different sources.
+    ///
+    /// The value is left unchanged on error.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let mut list = KVal::Long(KData::List(Cow::Owned(vec![1, 3])));
+    /// list.insert(1, KVal::Long(KData::Atom(Cow::Owned(2)))).unwrap();
+    /// list.insert(3, KVal::Long(KData::Atom(Cow::Owned(4)))).unwrap();
+    /// assert_eq!(list, KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3, 4]))));
+    ///
+    /// let error = list.insert(5, KVal::Long(KData::Atom(Cow::Owned(5)))).unwrap_err();
+    /// assert_eq!(error, "index out of bounds\0");
+    /// ```
+    pub fn insert(&mut self, index: usize, value: KVal<'a>) -> Result<(), &'static str> {
+        if !(qtype::COMPOUND_LIST..=qtype::ENUM_LIST).contains(&self.qtype()) {
+            return Err(NOT_A_LIST);
+        }
+        let is_simple = !matches!(self, KVal::CompoundList(_));
+        if is_simple && (value.qtype() >= 0 || matches!(value, KVal::Error(_))) {
+            return Err(NOT_AN_ATOM);
+        }
+        if index > self.len() {
+            return Err(INDEX_OUT_OF_BOUNDS);
+        }
+        match (self, value) {
+            (KVal::CompoundList(list), value) => list.insert(index, value),
+            (KVal::Bool(data), KVal::Bool(value)) => insert_data(data, index, value),
+            (KVal::Guid(data), KVal::Guid(value)) => insert_data(data, index, value),
+            (KVal::Byte(data), KVal::Byte(value)) => insert_data(data, index, value),
+            (KVal::Short(data), KVal::Short(value)) => insert_data(data, index, value),
+            (KVal::Int(data), KVal::Int(value)) => insert_data(data, index, value),
+            (KVal::Long(data), KVal::Long(value)) => insert_data(data, index, value),
+            (KVal::Real(data), KVal::Real(value)) => insert_data(data, index, value),
+            (KVal::Float(data), KVal::Float(value)) => insert_data(data, index, value),
+            (KVal::Symbol(data), KVal::Symbol(value)) => insert_data(data, index, value),
+            (KVal::String(string), KVal::Char(character)) => {
+                string.to_mut().insert(index, char_to_byte(character)?)
+            }
+            (KVal::Timestamp(data), KVal::Timestamp(value)) => insert_data(data, index, value),
+            (KVal::Month(data), KVal::Month(value)) => insert_data(data, index, value),
+            (KVal::Date(data), KVal::Date(value)) => insert_data(data, index, value),
+            (KVal::Datetime(data), KVal::Datetime(value)) => insert_data(data, index, value),
+            (KVal::Timespan(data), KVal::Timespan(value)) => insert_data(data, index, value),
+            (KVal::Minute(data), KVal::Minute(value)) => insert_data(data, index, value),
+            (KVal::Second(data), KVal::Second(value)) => insert_data(data, index, value),
+            (KVal::Time(data), KVal::Time(value)) => insert_data(data, index, value),
+            (KVal::Enum(data, source), KVal::Enum(value, value_source)) => {
+                if *source != value_source {
+                    return Err(ENUM_SOURCE_MISMATCH);
+                }
+                insert_data(data, index, value)
+            }
+            _ => return Err(TYPE),
+        }
+        Ok(())
+    }
+
+    /// Replace the element at the specified index in the same manner as `list[index]: value` in q. The element is
+    ///  overwritten in place without shifting the following elements. Values taken by each type of list are the same
+    ///  as [`insert`](#method.insert).
+    ///
+    /// A list borrowing q memory is copied once into an owned list.
+    /// # Errors
+    /// - `"not a list"` if the value is an atom, a table, a dictionary, an error or a general null.
+    /// - `"not an atom"` if `value` is not an atom and the list is not a compound list.
+    /// - `"index out of bounds"` if the index is not less than the length.
+    /// - `"char out of byte range"` if a char set to a string is greater than `'\u{ff}'`.
+    /// - `"type"` if `value` has a different type from the list.
+    /// - `"enum sources differ"` if enums have different sources.
+    ///
+    /// The value is left unchanged on error.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let mut list = KVal::Long(KData::List(Cow::Owned(vec![1, 0, 3])));
+    /// list.set(1, KVal::Long(KData::Atom(Cow::Owned(2)))).unwrap();
+    /// assert_eq!(list, KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3]))));
+    ///
+    /// let error = list.set(3, KVal::Long(KData::Atom(Cow::Owned(4)))).unwrap_err();
+    /// assert_eq!(error, "index out of bounds\0");
+    /// ```
+    pub fn set(&mut self, index: usize, value: KVal<'a>) -> Result<(), &'static str> {
+        if !(qtype::COMPOUND_LIST..=qtype::ENUM_LIST).contains(&self.qtype()) {
+            return Err(NOT_A_LIST);
+        }
+        let is_simple = !matches!(self, KVal::CompoundList(_));
+        if is_simple && (value.qtype() >= 0 || matches!(value, KVal::Error(_))) {
+            return Err(NOT_AN_ATOM);
+        }
+        if index >= self.len() {
+            return Err(INDEX_OUT_OF_BOUNDS);
+        }
+        match (self, value) {
+            (KVal::CompoundList(list), value) => list[index] = value,
+            (KVal::Bool(data), KVal::Bool(value)) => set_data(data, index, value),
+            (KVal::Guid(data), KVal::Guid(value)) => set_data(data, index, value),
+            (KVal::Byte(data), KVal::Byte(value)) => set_data(data, index, value),
+            (KVal::Short(data), KVal::Short(value)) => set_data(data, index, value),
+            (KVal::Int(data), KVal::Int(value)) => set_data(data, index, value),
+            (KVal::Long(data), KVal::Long(value)) => set_data(data, index, value),
+            (KVal::Real(data), KVal::Real(value)) => set_data(data, index, value),
+            (KVal::Float(data), KVal::Float(value)) => set_data(data, index, value),
+            (KVal::Symbol(data), KVal::Symbol(value)) => set_data(data, index, value),
+            (KVal::String(string), KVal::Char(character)) => {
+                string.to_mut()[index] = char_to_byte(character)?
+            }
+            (KVal::Timestamp(data), KVal::Timestamp(value)) => set_data(data, index, value),
+            (KVal::Month(data), KVal::Month(value)) => set_data(data, index, value),
+            (KVal::Date(data), KVal::Date(value)) => set_data(data, index, value),
+            (KVal::Datetime(data), KVal::Datetime(value)) => set_data(data, index, value),
+            (KVal::Timespan(data), KVal::Timespan(value)) => set_data(data, index, value),
+            (KVal::Minute(data), KVal::Minute(value)) => set_data(data, index, value),
+            (KVal::Second(data), KVal::Second(value)) => set_data(data, index, value),
+            (KVal::Time(data), KVal::Time(value)) => set_data(data, index, value),
+            (KVal::Enum(data, source), KVal::Enum(value, value_source)) => {
+                if *source != value_source {
+                    return Err(ENUM_SOURCE_MISMATCH);
+                }
+                set_data(data, index, value)
+            }
+            _ => return Err(TYPE),
+        }
+        Ok(())
+    }
+
+    /// Reverse the order of the elements of the list in the same manner as `reverse` in q. An atom is left as it
+    ///  is. A string is reversed by bytes.
+    ///
+    /// An owned list is reversed in place and a list borrowing q memory is copied once into an owned list unless it
+    ///  has less than two elements.
+    /// # Errors
+    /// - `"not a list"` if the value is a table, a dictionary, an error or a general null.
+    ///
+    /// The value is left unchanged on error.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn reversed(list: K) -> K{
+    ///   let mut list = KVal::from_raw(list);
+    ///   match list.reverse(){
+    ///     Ok(()) => list.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)reversed: `libapi_examples 2: (`reversed; 1);
+    /// q)reversed 1 2 3
+    /// 3 2 1
+    /// q)reversed "abc"
+    /// "cba"
+    /// ```
+    pub fn reverse(&mut self) -> Result<(), &'static str> {
+        self.reorder(Reorder::Reverse)
+    }
+
+    /// Rotate the elements of the list to the left by `n` in the same manner as `n rotate list` in q. A negative
+    ///  `n` rotates to the right and `n` is taken modulo the length. An atom is left as it is. A string is rotated
+    ///  by bytes, which its length counts.
+    ///
+    /// An owned list is rotated in place and a list borrowing q memory is copied once into an owned list unless the
+    ///  rotation leaves it as it is.
+    /// # Errors
+    /// - `"not a list"` if the value is a table, a dictionary, an error or a general null.
+    ///
+    /// The value is left unchanged on error.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let mut list = KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3, 4])));
+    /// list.rotate(1).unwrap();
+    /// assert_eq!(list, KVal::Long(KData::List(Cow::Owned(vec![2, 3, 4, 1]))));
+    /// list.rotate(-6).unwrap();
+    /// assert_eq!(list, KVal::Long(KData::List(Cow::Owned(vec![4, 1, 2, 3]))));
+    /// ```
+    pub fn rotate(&mut self, n: isize) -> Result<(), &'static str> {
+        self.reorder(Reorder::Rotate(n))
+    }
+
+    /// Reorder the elements of the list.
+    fn reorder(&mut self, reorder: Reorder) -> Result<(), &'static str> {
+        match self {
+            KVal::CompoundList(list) => {
+                if reorder.changes(list.len()) {
+                    reorder.apply(list)
+                }
+            }
+            KVal::Bool(data) => reorder_data(data, reorder),
+            KVal::Guid(data) => reorder_data(data, reorder),
+            KVal::Byte(data) => reorder_data(data, reorder),
+            KVal::Short(data) => reorder_data(data, reorder),
+            KVal::Int(data) => reorder_data(data, reorder),
+            KVal::Long(data) => reorder_data(data, reorder),
+            KVal::Real(data) => reorder_data(data, reorder),
+            KVal::Float(data) => reorder_data(data, reorder),
+            KVal::Char(_) => {}
+            KVal::Symbol(data) => reorder_data(data, reorder),
+            KVal::String(string) => {
+                if reorder.changes(string.len()) {
+                    reorder.apply(string.to_mut());
+                }
+            }
+            KVal::Timestamp(data) => reorder_data(data, reorder),
+            KVal::Month(data) => reorder_data(data, reorder),
+            KVal::Date(data) => reorder_data(data, reorder),
+            KVal::Datetime(data) => reorder_data(data, reorder),
+            KVal::Timespan(data) => reorder_data(data, reorder),
+            KVal::Minute(data) => reorder_data(data, reorder),
+            KVal::Second(data) => reorder_data(data, reorder),
+            KVal::Time(data) => reorder_data(data, reorder),
+            KVal::Enum(data, _) => reorder_data(data, reorder),
+            KVal::Table(_) | KVal::Dictionary(_) | KVal::Error(_) | KVal::Null => {
+                return Err(NOT_A_LIST)
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Reorder {
+    /// Check if the reordering changes a list of the length.
+    fn changes(self, length: usize) -> bool {
+        match self {
+            Reorder::Reverse => length > 1,
+            Reorder::Rotate(n) => length > 0 && n.rem_euclid(length as isize) != 0,
+        }
+    }
+
+    /// Reorder the elements.
+    fn apply<T>(self, list: &mut [T]) {
+        match self {
+            Reorder::Reverse => list.reverse(),
+            Reorder::Rotate(n) => list.rotate_left(n.rem_euclid(list.len() as isize) as usize),
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Insert an atom at the index of a list. The list, the atom and the index have been checked.
+fn insert_data<T: Clone>(data: &mut KData<'_, T>, index: usize, value: KData<'_, T>) {
+    if let (KData::List(list), KData::Atom(value)) = (data, value) {
+        list.to_mut().insert(index, value.into_owned());
+    }
+}
+
+/// Overwrite the element at the index of a list with an atom. The list, the atom and the index have been checked.
+fn set_data<T: Clone>(data: &mut KData<'_, T>, index: usize, value: KData<'_, T>) {
+    if let (KData::List(list), KData::Atom(value)) = (data, value) {
+        list.to_mut()[index] = value.into_owned();
+    }
+}
+
+/// Remove the element at the index of a list. The last element of a borrowed list is removed by shortening the
+///  slice.
+fn remove_data<T: Clone>(data: &mut KData<'_, T>, index: usize) -> Result<T, &'static str> {
+    match data {
+        KData::Atom(_) => Err(NOT_A_LIST),
+        KData::List(list) => remove_list(list, index),
+    }
+}
+
+/// Remove the element at the index of a list in the same manner as `remove_data`.
+fn remove_list<T: Clone>(list: &mut Cow<'_, [T]>, index: usize) -> Result<T, &'static str> {
+    if index >= list.len() {
+        return Err(INDEX_OUT_OF_BOUNDS);
+    }
+    if let Cow::Borrowed(slice) = *list {
+        if index + 1 == slice.len() {
+            *list = Cow::Borrowed(&slice[..index]);
+            return Ok(slice[index].clone());
+        }
+    }
+    Ok(list.to_mut().remove(index))
+}
+
+/// Reorder the elements of a list. A borrowed list is copied only if the reordering changes it.
+fn reorder_data<T: Clone>(data: &mut KData<'_, T>, reorder: Reorder) {
+    if let KData::List(list) = data {
+        if reorder.changes(list.len()) {
+            reorder.apply(list.to_mut());
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! This module provides an error of operations on `KVal`.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::super::{
+    const_S, decrement_reference_count, error_to_string, native, new_error, K, KNULL,
+};
+use super::kval::{enumerate_str, symbol_to_string};
+use crate::qtype;
+use std::error::Error as StdError;
+use std::fmt;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Error from an invalid operation on [`KVal`](enum.KVal.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KError {
+    /// Invalid operation to a wrong type.
+    InvalidOperation {
+        operator: &'static str,
+        operand_type: &'static str,
+        expected: Option<&'static str>,
+    },
+    /// Lengths of two operands do not match.
+    LengthMismatch { left: usize, right: usize },
+    /// Index is out of bounds of a value of the length.
+    IndexOutOfBounds { index: usize, length: usize },
+    /// Columns requested by name do not exist in a table.
+    MissingColumns(Vec<String>),
+    /// Length of a list exceeds the limit of a q list (see [`max_list_length`](fn.max_list_length.html)).
+    TooLong { len: usize, limit: usize },
+    /// Value of a type cannot be cast into another type.
+    InvalidCast {
+        from: &'static str,
+        to: &'static str,
+    },
+    /// Value cannot be represented in a narrower type. `value` is the offending value in q notation.
+    OutOfRange { value: String, target: &'static str },
+    /// Row of a stream cannot be added to a table. `row` is the index of the row in the stream.
+    InvalidRow { row: usize, message: String },
+    /// Error signalled by q.
+    Q { kind: QErrorKind, message: String },
+}
+
+/// Class of an error signalled by q.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QErrorKind {
+    /// Wrong type (`'type`).
+    Type,
+    /// Wrong length (`'length`).
+    Length,
+    /// Columns of tables do not match (`'mismatch`).
+    Mismatch,
+    /// Referred variable is not defined. q signals the name of the variable.
+    NotFound,
+    /// Any other error.
+    Other,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl KError {
+    /// Construct `InvalidOperation` error.
+    pub(crate) fn invalid_operation(
+        operator: &'static str,
+        operand_type: &'static str,
+        expected: Option<&'static str>,
+    ) -> Self {
+        Self::InvalidOperation {
+            operator,
+            operand_type,
+            expected,
+        }
+    }
+
+    /// Construct `LengthMismatch` error.
+    pub(crate) fn length_mismatch(left: usize, right: usize) -> Self {
+        Self::LengthMismatch { left, right }
+    }
+
+    /// Construct `IndexOutOfBounds` error.
+    pub(crate) fn index_out_of_bounds(index: usize, length: usize) -> Self {
+        Self::IndexOutOfBounds { index, length }
+    }
+
+    /// Construct `InvalidCast` error.
+    pub(crate) fn invalid_cast(from: &'static str, to: &'static str) -> Self {
+        Self::InvalidCast { from, to }
+    }
+
+    /// Construct `OutOfRange` error.
+    pub(crate) fn out_of_range(value: String, target: &'static str) -> Self {
+        Self::OutOfRange { value, target }
+    }
+
+    /// Construct `InvalidRow` error.
+    pub(crate) fn invalid_row(row: usize, message: String) -> Self {
+        Self::InvalidRow { row, message }
+    }
+
+    /// Construct `Q` error from a message signalled by q. The message is classified as `NotFound` if it is the
+    ///  name of the variable the caller referred to.
+    pub(crate) fn q(message: String, referred: Option<&str>) -> Self {
+        let kind = match message.as_str() {
+            "type" => QErrorKind::Type,
+            "length" => QErrorKind::Length,
+            "mismatch" => QErrorKind::Mismatch,
+            name if Some(name) == referred => QErrorKind::NotFound,
+            _ => QErrorKind::Other,
+        };
+        Self::Q { kind, message }
+    }
+
+    /// Wrap an error with the context in which it occurred, e.g., the name of the failed step. The result is a
+    ///  `Q` error whose message is `"context: inner"` and whose kind is the kind of `inner`, or `Other` for an error
+    ///  raised in Rust, so that the caller can still handle the original class after wrapping it any number of
+    ///  times.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let inner = KError::Q{kind: QErrorKind::Type, message: "type".to_string()};
+    /// let wrapped = KError::wrap("load trades", inner);
+    /// assert_eq!(wrapped.to_string(), "load trades: type");
+    /// assert_eq!(wrapped.kind(), Some(QErrorKind::Type));
+    ///
+    /// let twice = KError::wrap("daily job", wrapped);
+    /// assert_eq!(twice.to_string(), "daily job: load trades: type");
+    /// assert_eq!(twice.kind(), Some(QErrorKind::Type));
+    /// ```
+    pub fn wrap(context: &str, inner: KError) -> Self {
+        Self::Q {
+            kind: inner.kind().unwrap_or(QErrorKind::Other),
+            message: format!("{}: {}", context, inner),
+        }
+    }
+
+    /// Get the class of an error signalled by q. `None` is returned for an error raised in Rust.
+    pub fn kind(&self) -> Option<QErrorKind> {
+        match self {
+            Self::Q { kind, .. } => Some(*kind),
+            _ => None,
+        }
+    }
+
+    /// Build a q error carrying the message of this error. The result can be returned to q directly.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn fill_gap(primary: K, fallback: K) -> K{
+    ///   match KVal::from_raw(primary).coalesce(&KVal::from_raw(fallback)){
+    ///     Ok(filled) => filled.to_k(),
+    ///     Err(error) => error.to_k()
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)fill_gap: `libapi_examples 2: (`fill_gap; 2);
+    /// q)fill_gap[1 0N 3; 1 2]
+    /// 'length mismatch: 3 and 2
+    /// ```
+    pub fn to_k(&self) -> K {
+        error_to_k(&self.to_string())
+    }
+}
+
+impl fmt::Display for KError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidOperation {
+                operator,
+                operand_type,
+                expected,
+            } => match expected {
+                Some(expected_type) => write!(
+                    f,
+                    "invalid operation {} on {}. expected: {}",
+                    operator, operand_type, expected_type
+                ),
+                None => write!(f, "invalid operation {} on {}", operator, operand_type),
+            },
+            Self::LengthMismatch { left, right } => {
+                write!(f, "length mismatch: {} and {}", left, right)
+            }
+            Self::IndexOutOfBounds { index, length } => {
+                write!(f, "index out of bounds: {} for length {}", index, length)
+            }
+            Self::MissingColumns(columns) => write!(f, "missing columns: {}", columns.join(", ")),
+            Self::TooLong { len, limit } => write!(
+                f,
+                "too long: {} elements exceed the limit of {}",
+                len, limit
+            ),
+            Self::InvalidCast { from, to } => write!(f, "invalid cast from {} to {}", from, to),
+            Self::OutOfRange { value, target } => {
+                write!(f, "value {} out of range of {}", value, target)
+            }
+            Self::InvalidRow { row, message } => write!(f, "invalid row {}: {}", row, message),
+            Self::Q { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl StdError for KError {}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Public Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Build a q error of `"context: inner"`, e.g., to signal an error returned by q together with the step which
+///  failed. The message is formatted in the same manner as [`KError::wrap`](enum.KError.html#method.wrap) and
+///  neither input needs to be null-terminated. The message is interned as a symbol so that it lives as long as the
+///  q process.
+/// # Example
+/// ```no_run
+/// use kdbplus::api::*;
+/// use kdbplus::api::rusty_api::*;
+///
+/// #[no_mangle]
+/// pub extern "C" fn load_step(step: K, message: K) -> K{
+///   match (step.get_symbol(), message.get_str()){
+///     (Ok(step), Ok(message)) => new_error_chained(step, message),
+///     _ => new_error(errors::TYPE)
+///   }
+/// }
+/// ```
+/// ```q
+/// q)load_step: `libapi_examples 2: (`load_step; 2);
+/// q)load_step[`parse; "length"]
+/// 'parse: length
+/// ```
+pub fn new_error_chained(context: &str, inner: &str) -> K {
+    error_to_k(&format!("{}: {}", context, inner))
+}
+
+/// Build a q error of the context followed by the message of the last system error (`errno`), e.g., after a failed
+///  file operation. Unlike [`new_error_os`](../fn.new_error_os.html) the context does not need to be
+///  null-terminated.
+///
+/// Use this (or `new_error_os`, which calls `orr`) only right after a failed system call, as `errno` is not reset
+///  by a successful call and a stale message would be appended otherwise. Use
+///  [`new_error_chained`](fn.new_error_chained.html) or [`KError::to_k`](enum.KError.html#method.to_k), which call
+///  `krr`, for any other error.
+pub fn new_error_errno(context: &str) -> K {
+    match enumerate_str(context) {
+        Ok(context) => unsafe { native::orr(context as const_S) },
+        Err(error) => new_error(error),
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Build a q error from a message which is not null-terminated. The message is interned as a symbol so that it lives
+///  as long as the q process.
+pub(super) fn error_to_k(message: &str) -> K {
+    match enumerate_str(message) {
+        Ok(message) => unsafe { native::krr(message) },
+        Err(error) => new_error(error),
+    }
+}
+
+/// Take a q error which was signalled by `krr` or returned from `k` as `KError`. The error object is freed.
+/// # Parameters
+/// - `result`: `KNULL` after a signalled error or an error object returned from `k`.
+/// - `referred`: Name of the variable referred by the failed operation.
+pub(super) fn take_q_error(result: K, referred: Option<&str>) -> KError {
+    let error = if result.is_null() {
+        error_to_string(KNULL)
+    } else {
+        result
+    };
+    let message = unsafe {
+        if (*error).qtype == qtype::ERROR && !(*error).value.symbol.is_null() {
+            symbol_to_string((*error).value.symbol)
+        } else {
+            String::new()
+        }
+    };
+    decrement_reference_count(error);
+    KError::q(message, referred)
+}
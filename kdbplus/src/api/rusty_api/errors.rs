@@ -0,0 +1,90 @@
+//! This module provides null-terminated messages of errors which are raised frequently, e.g., by a failed type
+//!  check. A message can be passed to [`new_error`](../../fn.new_error.html) as it is or returned as an error of
+//!  `&'static str` which is passed to `new_error` later.
+//!
+//! `new_error` calls `krr` which only keeps the pointer to the message and returns `KNULL`. Raising these errors
+//!  therefore allocates neither a q object nor a Rust string, and the same message can be raised any number of times
+//!  without touching a reference count. Prefer them to [`KError::to_k`](../enum.KError.html#method.to_k) on a hot path
+//!  where no dynamic context is needed, as `KError::to_k` formats its message and interns it as a symbol.
+//!
+//! # Example
+//! ```no_run
+//! use kdbplus::api::*;
+//! use kdbplus::api::rusty_api::*;
+//!
+//! #[no_mangle]
+//! pub extern "C" fn only_long_list(list: K) -> K{
+//!   match KVal::from_raw(list){
+//!     KVal::Long(KData::List(_)) => increment_reference_count(list),
+//!     _ => new_error(errors::TYPE)
+//!   }
+//! }
+//! ```
+//! ```q
+//! q)only_long_list: `libapi_examples 2: (`only_long_list; 1);
+//! q)only_long_list 1 2
+//! 1 2
+//! q)only_long_list 1 2i
+//! 'type
+//! ```
+
+/// Value is an atom or other object which is not a list.
+pub const NOT_A_LIST: &str = "not a list\0";
+
+/// Value has a wrong type. This is the same message as the type error of q.
+pub const TYPE: &str = "type\0";
+
+/// Lengths of values do not match. This is the same message as the length error of q.
+pub const LENGTH: &str = "length\0";
+
+/// Index or range is out of bounds of a list.
+pub const INDEX_OUT_OF_BOUNDS: &str = "index out of bounds\0";
+
+/// Key is not found in a dictionary.
+pub const KEY_NOT_FOUND: &str = "key not found\0";
+
+/// Value cannot be cast into the target type.
+pub const INVALID_CAST: &str = "invalid cast\0";
+
+/// Value cannot be represented in the target type.
+pub const OUT_OF_RANGE: &str = "value out of range\0";
+
+/// Element of a list is not an atom.
+pub const NOT_AN_ATOM: &str = "not an atom\0";
+
+/// Elements of a list have different types.
+pub const MIXED_TYPES: &str = "mixed types\0";
+
+/// Enum atoms have different sources.
+pub const ENUM_SOURCE_MISMATCH: &str = "enum sources differ\0";
+
+/// List has no element to determine the type of the result or to repeat.
+pub const EMPTY_LIST: &str = "empty list\0";
+
+/// Type has no null, e.g., bool and byte.
+pub const NO_NULL: &str = "type has no null\0";
+
+/// Type has no infinity, e.g., GUID and symbol.
+pub const NO_INFINITY: &str = "type has no infinity\0";
+
+/// List has an attribute which a modification could violate.
+pub const ATTRIBUTE: &str = "list has an attribute\0";
+
+/// Length of a result exceeds the limit of a q list (see [`max_list_length`](../fn.max_list_length.html)).
+pub const TOO_LONG: &str = "too long\0";
+
+/// Column is not found in a table.
+pub const NO_SUCH_COLUMN: &str = "no such column\0";
+
+/// Column name appears on both sides of a join.
+pub const DUPLICATE_COLUMN: &str = "duplicate column\0";
+
+/// Tables do not have the same column names in the same order.
+pub const MISMATCH: &str = "mismatch\0";
+
+/// String read as text, e.g., into a Rust `String`, is not a valid UTF-8 sequence. Elements of a string are bytes in
+///  the same manner as q and are not required to be UTF-8 otherwise.
+pub const INVALID_UTF8: &str = "invalid UTF-8\0";
+
+/// Char cannot be held as a byte of q because its code point is greater than `0xFF`.
+pub const CHAR_OUT_OF_RANGE: &str = "char out of byte range\0";
@@ -0,0 +1,175 @@
+//! Typed wrapper of a foreign object holding a Rust value. Unlike a foreign object built by hand with
+//!  [`drop_q_object`](../fn.drop_q_object.html), the type of the embedded value is recorded and checked when the
+//!  value is borrowed back, so passing a foreign object of another type returns an error instead of reinterpreting
+//!  the memory.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::super::{new_list, KUtility, K, KNULL};
+use crate::qtype;
+use std::any::TypeId;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Tag placed at the head of every boxed `Foreign<T>` to tell it from other memory.
+const MAGIC: u64 = 0x6b64_622b_666f_7267;
+
+/// Common head of every boxed `Foreign<T>`. Placed at the beginning so that it can be read without knowing `T`.
+#[repr(C)]
+struct Header {
+    /// Always [`MAGIC`] while the value is alive.
+    magic: u64,
+    /// Type of the embedded value.
+    type_id: TypeId,
+    /// Function to drop the box with the right type.
+    drop: unsafe fn(*mut Header),
+}
+
+/// Rust value embedded in q as a foreign object (`112h`). The foreign object is a list of (destructor; pointer to
+///  this struct) and q calls the destructor to drop the value when the reference count of the object reaches 0.
+/// # Example
+/// ```no_run
+/// use kdbplus::api::*;
+/// use kdbplus::api::rusty_api::Foreign;
+///
+/// struct Counter{
+///   count: i64
+/// }
+///
+/// #[no_mangle]
+/// pub extern "C" fn new_counter(_: K) -> K{
+///   Foreign::new(Counter{count: 0})
+/// }
+///
+/// #[no_mangle]
+/// pub extern "C" fn count_up(counter: K) -> K{
+///   match Foreign::<Counter>::from_raw_mut(counter){
+///     Ok(counter) => {
+///       counter.count += 1;
+///       new_long(counter.count)
+///     },
+///     Err(error) => new_error(error)
+///   }
+/// }
+/// ```
+/// ```q
+/// q)new_counter: `libapi_examples 2: (`new_counter; 1);
+/// q)count_up: `libapi_examples 2: (`count_up; 1);
+/// q)counter: new_counter[]
+/// q)count_up counter
+/// 1
+/// q)count_up counter
+/// 2
+/// q)count_up (::)
+/// 'not a foreign object
+/// ```
+#[repr(C)]
+pub struct Foreign<T: 'static> {
+    header: Header,
+    value: T,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl<T: 'static> Foreign<T> {
+    /// Move a value into a new foreign object.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(value: T) -> K {
+        let boxed = Box::new(Foreign {
+            header: Header {
+                magic: MAGIC,
+                type_id: TypeId::of::<T>(),
+                drop: drop_boxed::<T>,
+            },
+            value,
+        });
+        let mut foreign = new_list(qtype::COMPOUND_LIST, 2);
+        let foreign_slice = foreign.as_mut_slice::<K>();
+        foreign_slice[0] = drop_foreign as K;
+        foreign_slice[1] = Box::into_raw(boxed) as K;
+        // Set as foreign object.
+        foreign.set_type(qtype::FOREIGN);
+        foreign
+    }
+
+    /// Borrow the value embedded in a foreign object.
+    /// # Errors
+    /// - `obj` is not a foreign object built by [`new`](#method.new).
+    /// - The embedded value is not of type `T`.
+    /// - The value was already dropped.
+    /// # Note
+    /// The returned reference must not outlive the foreign object.
+    pub fn from_raw<'a>(obj: K) -> Result<&'a T, &'static str> {
+        payload::<T>(obj).map(|foreign| unsafe { &(*foreign).value })
+    }
+
+    /// Borrow the value embedded in a foreign object mutably.
+    /// # Errors
+    /// Same as [`from_raw`](#method.from_raw).
+    /// # Note
+    /// The returned reference must not outlive the foreign object and only one reference may be alive at a time.
+    pub fn from_raw_mut<'a>(obj: K) -> Result<&'a mut T, &'static str> {
+        payload::<T>(obj).map(|foreign| unsafe { &mut (*foreign).value })
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Validate `obj` and retrieve the pointer to the boxed `Foreign<T>`.
+fn payload<T: 'static>(obj: K) -> Result<*mut Foreign<T>, &'static str> {
+    let header = header(obj)?;
+    // `Header` is at the head of every `Foreign<T>`.
+    if unsafe { (*header).type_id } != TypeId::of::<T>() {
+        return Err("foreign object holds another type\0");
+    }
+    Ok(header as *mut Foreign<T>)
+}
+
+/// Validate that `obj` is a live foreign object built by `Foreign` and retrieve the header of the payload.
+fn header(obj: K) -> Result<*mut Header, &'static str> {
+    if obj.is_null() || obj.get_type() != qtype::FOREIGN || unsafe { (*obj).value.list.n } != 2 {
+        return Err("not a foreign object\0");
+    }
+    let obj_slice = obj.as_mut_slice::<K>();
+    if obj_slice[1].is_null() {
+        return Err("foreign object was already dropped\0");
+    }
+    if obj_slice[0] != drop_foreign as K {
+        return Err("foreign object was not built by Foreign\0");
+    }
+    let header = obj_slice[1] as *mut Header;
+    if unsafe { (*header).magic } != MAGIC {
+        return Err("foreign object was not built by Foreign\0");
+    }
+    Ok(header)
+}
+
+/// Destructor of a foreign object called by q. Passed as the first element of the foreign object.
+///  An object which is not a live foreign object built by `Foreign` is left untouched.
+extern "C" fn drop_foreign(obj: K) -> K {
+    match header(obj) {
+        Ok(header) => {
+            unsafe { ((*header).drop)(header) };
+            // Fill the list with null.
+            obj.as_mut_slice::<K>().copy_from_slice(&[KNULL, KNULL]);
+        }
+        Err(error) => eprintln!(
+            "drop of foreign object was skipped: {}",
+            error.trim_end_matches('\0')
+        ),
+    }
+    obj
+}
+
+/// Drop a boxed `Foreign<T>` pointed by its header.
+unsafe fn drop_boxed<T: 'static>(header: *mut Header) {
+    drop(Box::from_raw(header as *mut Foreign<T>));
+}
@@ -0,0 +1,299 @@
+//! Structural hash of `KVal` which is stable across runs, processes and platforms.
+//!
+//! The hash is computed directly over the typed data: the type indicator, the length of a list and the bytes of each
+//!  element in little endian, recursing into compound lists, dictionaries and tables. No intermediate buffer is
+//!  built. The layout of the hashed data is versioned by [`CONTENT_HASH_VERSION`] and any change to the layout or to
+//!  the mixing function bumps the version, so that a hash persisted with the version is never silently compared
+//!  with a hash of another layout.
+//!
+//! The hash is not cryptographic. It is meant for keys of a cache, not for integrity against an adversary.
+//!
+//! `Hash` of `KVal` and `KData` feeds the same hash into the hasher of a collection, so that they can be keys of
+//!  `HashMap` and `HashSet`.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::{KData, KVal};
+use std::hash::{Hash, Hasher};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Version of the layout of [`KVal::content_hash`](enum.KVal.html#method.content_hash) and
+///  [`KVal::content_hash128`](enum.KVal.html#method.content_hash128). Store it together with a persisted hash.
+pub const CONTENT_HASH_VERSION: u32 = 1;
+
+/// Multipliers of the two lanes (odd 64-bit constants from the fractional part of the golden ratio and of pi).
+const PRIME_0: u64 = 0x9e37_79b9_7f4a_7c15;
+const PRIME_1: u64 = 0x243f_6a88_85a3_08d3;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// State of the hash made of two independent 64-bit lanes.
+pub(super) struct ContentHasher {
+    lanes: [u64; 2],
+    /// Number of words written.
+    count: u64,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Traits
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Element type of `KData` which can be written to `ContentHasher`.
+pub(super) trait HashElement {
+    fn write_to(&self, hasher: &mut ContentHasher);
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl ContentHasher {
+    fn new() -> Self {
+        let mut hasher = ContentHasher {
+            lanes: [PRIME_1, PRIME_0],
+            count: 0,
+        };
+        hasher.write_u64(CONTENT_HASH_VERSION as u64);
+        hasher
+    }
+
+    /// Absorb a word into both lanes.
+    fn write_u64(&mut self, word: u64) {
+        self.lanes[0] = (self.lanes[0] ^ word).wrapping_mul(PRIME_0).rotate_left(31);
+        self.lanes[1] = (self.lanes[1].rotate_left(27) ^ word).wrapping_mul(PRIME_1);
+        self.count += 1;
+    }
+
+    /// Absorb the length and the bytes packed into little-endian words. The last word is padded with zeros.
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_u64(bytes.len() as u64);
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.write_u64(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        let remainder = chunks.remainder();
+        if !remainder.is_empty() {
+            let mut word = [0_u8; 8];
+            word[..remainder.len()].copy_from_slice(remainder);
+            self.write_u64(u64::from_le_bytes(word));
+        }
+    }
+
+    /// Finalize both lanes. The first lane is the 64-bit hash.
+    fn finish(&self) -> [u64; 2] {
+        let first = fmix64(self.lanes[0] ^ self.count);
+        let second = fmix64(self.lanes[1] ^ first);
+        [first, second]
+    }
+}
+
+impl HashElement for bool {
+    fn write_to(&self, hasher: &mut ContentHasher) {
+        hasher.write_u64(*self as u64);
+    }
+}
+
+impl HashElement for u8 {
+    fn write_to(&self, hasher: &mut ContentHasher) {
+        hasher.write_u64(*self as u64);
+    }
+}
+
+impl HashElement for [u8; 16] {
+    fn write_to(&self, hasher: &mut ContentHasher) {
+        hasher.write_u64(u64::from_le_bytes(self[..8].try_into().unwrap()));
+        hasher.write_u64(u64::from_le_bytes(self[8..].try_into().unwrap()));
+    }
+}
+
+impl HashElement for i16 {
+    fn write_to(&self, hasher: &mut ContentHasher) {
+        hasher.write_u64(*self as u16 as u64);
+    }
+}
+
+impl HashElement for i32 {
+    fn write_to(&self, hasher: &mut ContentHasher) {
+        hasher.write_u64(*self as u32 as u64);
+    }
+}
+
+impl HashElement for i64 {
+    fn write_to(&self, hasher: &mut ContentHasher) {
+        hasher.write_u64(*self as u64);
+    }
+}
+
+impl HashElement for f32 {
+    fn write_to(&self, hasher: &mut ContentHasher) {
+        // Every null is hashed as the q null. `-0.0` matches the pattern `0.0` and is hashed alike as they are equal.
+        let bits = match *self {
+            value if value.is_nan() => f32::NAN.to_bits(),
+            0.0 => 0,
+            value => value.to_bits(),
+        };
+        hasher.write_u64(bits as u64);
+    }
+}
+
+impl HashElement for f64 {
+    fn write_to(&self, hasher: &mut ContentHasher) {
+        let bits = match *self {
+            value if value.is_nan() => f64::NAN.to_bits(),
+            0.0 => 0,
+            value => value.to_bits(),
+        };
+        hasher.write_u64(bits);
+    }
+}
+
+impl HashElement for String {
+    fn write_to(&self, hasher: &mut ContentHasher) {
+        hasher.write_bytes(self.as_bytes());
+    }
+}
+
+impl<T: HashElement + Clone> HashElement for KData<'_, T> {
+    fn write_to(&self, hasher: &mut ContentHasher) {
+        match self {
+            KData::Atom(atom) => atom.write_to(hasher),
+            KData::List(list) => {
+                hasher.write_u64(list.len() as u64);
+                list.iter().for_each(|element| element.write_to(hasher));
+            }
+        }
+    }
+}
+
+impl HashElement for KVal<'_> {
+    fn write_to(&self, hasher: &mut ContentHasher) {
+        // The type tells an atom from a list and a temporal type from its underlying type.
+        hasher.write_u64(self.qtype() as u8 as u64);
+        match self {
+            KVal::CompoundList(list) => {
+                hasher.write_u64(list.len() as u64);
+                list.iter().for_each(|element| element.write_to(hasher));
+            }
+            KVal::Bool(data) => data.write_to(hasher),
+            KVal::Guid(data) => data.write_to(hasher),
+            KVal::Byte(data) => data.write_to(hasher),
+            KVal::Short(data) => data.write_to(hasher),
+            KVal::Int(data)
+            | KVal::Month(data)
+            | KVal::Date(data)
+            | KVal::Minute(data)
+            | KVal::Second(data)
+            | KVal::Time(data) => data.write_to(hasher),
+            KVal::Long(data) | KVal::Timestamp(data) | KVal::Timespan(data) => {
+                data.write_to(hasher)
+            }
+            KVal::Real(data) => data.write_to(hasher),
+            KVal::Float(data) | KVal::Datetime(data) => data.write_to(hasher),
+            KVal::Char(character) => hasher.write_u64(*character as u64),
+            KVal::Symbol(data) => data.write_to(hasher),
+            KVal::String(string) => hasher.write_bytes(string),
+            KVal::Enum(data, source) => {
+                data.write_to(hasher);
+                match source {
+                    Some(source) => {
+                        hasher.write_u64(1);
+                        hasher.write_bytes(source.as_bytes());
+                    }
+                    None => hasher.write_u64(0),
+                }
+            }
+            KVal::Table(table) => {
+                table.dict.keys.write_to(hasher);
+                table.dict.values.write_to(hasher);
+            }
+            KVal::Dictionary(dictionary) => {
+                dictionary.keys.write_to(hasher);
+                dictionary.values.write_to(hasher);
+            }
+            KVal::Error(message) => hasher.write_bytes(message.as_bytes()),
+            KVal::Null => {}
+        }
+    }
+}
+
+impl<T: HashElement + Clone> Hash for KData<'_, T>
+where
+    Self: Eq,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(element_hash(self));
+    }
+}
+
+impl Hash for KVal<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.content_hash());
+    }
+}
+
+impl KVal<'_> {
+    /// Compute a 64-bit structural hash of the value which is stable across runs and platforms for the same
+    ///  [`CONTENT_HASH_VERSION`](constant.CONTENT_HASH_VERSION.html).
+    /// - Values which are equal by `==` have the same hash regardless of whether they borrow q memory. In
+    ///   particular every float null is hashed alike and so are `0.0` and `-0.0`.
+    /// - Values of different types have different hashes even if their elements have the same bits, e.g., an int
+    ///   list and a date list.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let prices = [1.5, f64::NAN, 3.5];
+    /// let borrowed = KVal::Float(KData::List(Cow::Borrowed(&prices)));
+    /// let owned = KVal::Float(KData::List(Cow::Owned(vec![1.5, -f64::NAN, 3.5])));
+    /// assert_eq!(borrowed.content_hash(), owned.content_hash());
+    ///
+    /// let dates = KVal::Date(KData::List(Cow::Owned(vec![1, 2])));
+    /// let ints = KVal::Int(KData::List(Cow::Owned(vec![1, 2])));
+    /// assert_ne!(dates.content_hash(), ints.content_hash());
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        self.content_hashes()[0]
+    }
+
+    /// Compute a 128-bit structural hash of the value. The low 64 bits equal
+    ///  [`content_hash`](#method.content_hash). Prefer this when many values share a cache and a collision is costly.
+    pub fn content_hash128(&self) -> u128 {
+        let [low, high] = self.content_hashes();
+        (high as u128) << 64 | low as u128
+    }
+
+    /// Compute the two lanes of the hash.
+    fn content_hashes(&self) -> [u64; 2] {
+        let mut hasher = ContentHasher::new();
+        self.write_to(&mut hasher);
+        hasher.finish()
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Compute the 64-bit hash of an element in the same manner as [`KVal::content_hash`].
+pub(super) fn element_hash<T: HashElement>(element: &T) -> u64 {
+    let mut hasher = ContentHasher::new();
+    element.write_to(&mut hasher);
+    hasher.finish()[0]
+}
+
+/// Finalizer of MurmurHash3 spreading every bit of the input over the output.
+fn fmix64(mut value: u64) -> u64 {
+    value ^= value >> 33;
+    value = value.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    value ^= value >> 33;
+    value = value.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    value ^ value >> 33
+}
@@ -0,0 +1,144 @@
+//! Insertion of rows built in Rust into tables of the q process.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::super::{decrement_reference_count, native, KUtility, K, KNULL};
+use super::error::take_q_error;
+use super::kval::enumerate_str;
+use super::{KDict, KError, KTable};
+use crate::qtype;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Public Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Insert rows into a global table of the q process with `insert`. The rows are converted into a q table once and
+///  passed to q without copying again. Returns the number of inserted rows.
+/// # Errors
+/// - `table_name` contains a null character.
+/// - `rows` cannot be converted into a q table.
+/// - q signals an error. The error is classified with [`QErrorKind`](enum.QErrorKind.html), e.g., `NotFound` if the
+///   table does not exist and `Type` or `Mismatch` if the rows do not match the schema of the table.
+/// # Example
+/// ```no_run
+/// use kdbplus::api::*;
+/// use kdbplus::api::rusty_api::*;
+/// use std::borrow::Cow;
+///
+/// #[no_mangle]
+/// pub extern "C" fn insert_ticks(_: K) -> K{
+///   let rows = KTable::new(KDict::new(
+///     KVal::Symbol(KData::List(Cow::Owned(vec!["sym".to_string(), "price".to_string()]))),
+///     KVal::CompoundList(vec![
+///       KVal::Symbol(KData::List(Cow::Owned(vec!["a".to_string(), "b".to_string()]))),
+///       KVal::Float(KData::List(Cow::Owned(vec![1.5, 2.5])))
+///     ])
+///   ));
+///   match insert_into("ticks", rows){
+///     Ok(inserted) => new_long(inserted),
+///     Err(error) => error.to_k()
+///   }
+/// }
+/// ```
+/// ```q
+/// q)insert_ticks: `libapi_examples 2: (`insert_ticks; 1);
+/// q)ticks: ([] sym: `symbol$(); price: `float$())
+/// q)insert_ticks[]
+/// 2
+/// q)count ticks
+/// 2
+/// ```
+pub fn insert_into(table_name: &str, rows: KTable) -> Result<i64, KError> {
+    let expected = rows.len() as i64;
+    let result = call("insert\0", table_name, rows.to_k())?;
+    // `insert` returns indices of the inserted rows.
+    let inserted = match result.get_type() {
+        qtype::LONG_LIST | qtype::INT_LIST => result.len(),
+        _ => expected,
+    };
+    decrement_reference_count(result);
+    Ok(inserted)
+}
+
+/// Upsert rows into a global keyed table of the q process with `upsert`. Rows whose keys exist replace the existing
+///  rows and the others are appended. Returns the number of upserted rows.
+/// # Parameters
+/// - `table_name`: Name of the keyed table.
+/// - `rows`: Keyed table, i.e., a dictionary of a table of keys and a table of values.
+/// # Errors
+/// Same as [`insert_into`](fn.insert_into.html).
+/// # Example
+/// ```no_run
+/// use kdbplus::api::*;
+/// use kdbplus::api::rusty_api::*;
+/// use std::borrow::Cow;
+///
+/// #[no_mangle]
+/// pub extern "C" fn upsert_quotes(_: K) -> K{
+///   let keys = KTable::new(KDict::new(
+///     KVal::Symbol(KData::List(Cow::Owned(vec!["sym".to_string()]))),
+///     KVal::CompoundList(vec![KVal::Symbol(KData::List(Cow::Owned(vec!["a".to_string()])))])
+///   ));
+///   let values = KTable::new(KDict::new(
+///     KVal::Symbol(KData::List(Cow::Owned(vec!["bid".to_string()]))),
+///     KVal::CompoundList(vec![KVal::Float(KData::List(Cow::Owned(vec![99.5])))])
+///   ));
+///   match upsert_keyed("quotes", KDict::new(KVal::Table(keys), KVal::Table(values))){
+///     Ok(upserted) => new_long(upserted),
+///     Err(error) => error.to_k()
+///   }
+/// }
+/// ```
+/// ```q
+/// q)upsert_quotes: `libapi_examples 2: (`upsert_quotes; 1);
+/// q)quotes: ([sym: `a`b] bid: 1.5 2.5)
+/// q)upsert_quotes[]
+/// 1
+/// q)quotes
+/// sym| bid
+/// ---| ----
+/// a  | 99.5
+/// b  | 2.5
+/// ```
+pub fn upsert_keyed(table_name: &str, rows: KDict) -> Result<i64, KError> {
+    let upserted = rows.len() as i64;
+    // `upsert` returns the name of the table.
+    decrement_reference_count(call("upsert\0", table_name, rows.to_k())?);
+    Ok(upserted)
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Call `insert` or `upsert` with the name of a table and rows. `function` must be null-terminated and `rows` is
+///  consumed.
+fn call(function: &'static str, table_name: &str, rows: K) -> Result<K, KError> {
+    if rows.is_null() {
+        return Err(take_q_error(KNULL, None));
+    }
+    let name = match enumerate_str(table_name) {
+        Ok(name) => name,
+        Err(error) => {
+            decrement_reference_count(rows);
+            return Err(KError::q(error.trim_end_matches('\0').to_string(), None));
+        }
+    };
+    // `k` consumes the arguments and returns an error object if the call fails.
+    let result = unsafe {
+        native::k(
+            0,
+            function.as_ptr() as *const _,
+            native::ks(name),
+            rows,
+            KNULL,
+        )
+    };
+    if result.is_null() || result.get_type() == qtype::ERROR {
+        Err(take_q_error(result, Some(table_name)))
+    } else {
+        Ok(result)
+    }
+}
@@ -0,0 +1,1157 @@
+//! Join of `KVal` in the manner of `,` in q, either into a new value or in place, and join of `KTable` in the manner
+//!  of `,`, `uj`, `lj`, `ij` and `upsert` in q.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::errors::{
+    DUPLICATE_COLUMN, ENUM_SOURCE_MISMATCH, LENGTH, MISMATCH, NOT_AN_ATOM, NO_SUCH_COLUMN,
+    TOO_LONG, TYPE,
+};
+use super::ktable::{from_parts, pick_rows};
+use super::kval::char_to_byte;
+use super::limit::q_length;
+use super::null::prototype;
+use super::{KData, KError, KKeyedTable, KTable, KVal};
+use crate::qtype;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem::discriminant;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Rows of a keyed table by their keys, kept in [`KKeyedTable`] across lookups and upserts.
+#[derive(Clone, Debug)]
+pub(super) struct KeyIndex {
+    /// First row of each combination of keys.
+    rows: HashMap<Vec<KVal<'static>>, usize>,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl<'a> KVal<'a> {
+    /// Join two values into a list in the same manner as `base,other` in q.
+    /// - Atoms and lists of the same type result in a simple list of the type. A char joins a string as a single
+    ///   byte.
+    /// - Enums result in an enum list only if they have the same source.
+    /// - Any other combination, including a compound list and values of different types, results in a compound
+    ///   list of the elements of both sides. A general null is an element.
+    ///
+    /// An owned list of `base` is extended in place and a borrowed list is copied once into a buffer large enough
+    ///  for both sides. The elements of `other` are moved if it owns them.
+    /// # Errors
+    /// - Either of the values is a table, a dictionary or an error.
+    /// - The length of the result exceeds [`max_list_length`](fn.max_list_length.html). The error is returned
+    ///   before anything is allocated.
+    /// - A char joined to a string or a char is greater than `'\u{ff}'`.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let longs = KVal::Long(KData::List(Cow::Owned(vec![1, 2])));
+    /// let joined = KVal::join(longs, KVal::Long(KData::Atom(Cow::Owned(3)))).unwrap();
+    /// assert_eq!(joined, KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3]))));
+    ///
+    /// let mixed = KVal::join(joined, KVal::Char('a')).unwrap();
+    /// assert_eq!(mixed.qtype(), 0);
+    /// assert_eq!(mixed.len(), 4);
+    /// ```
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn joined(base: K, other: K) -> K{
+    ///   match KVal::join(KVal::from_raw(base), KVal::from_raw(other)){
+    ///     Ok(joined) => joined.to_k(),
+    ///     Err(error) => error.to_k()
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)joined: `libapi_examples 2: (`joined; 2);
+    /// q)joined[1 2; 3]
+    /// 1 2 3
+    /// q)joined["ab"; "c"]
+    /// "abc"
+    /// q)joined[1 2; `a]
+    /// 1
+    /// 2
+    /// `a
+    /// ```
+    pub fn join(base: Self, other: Self) -> Result<Self, KError> {
+        let length = base.join_length()? + other.join_length()?;
+        q_length(length)?;
+        Ok(match (base, other) {
+            (KVal::Bool(base), KVal::Bool(other)) => KVal::Bool(join_data(base, other, length)),
+            (KVal::Guid(base), KVal::Guid(other)) => KVal::Guid(join_data(base, other, length)),
+            (KVal::Byte(base), KVal::Byte(other)) => KVal::Byte(join_data(base, other, length)),
+            (KVal::Short(base), KVal::Short(other)) => KVal::Short(join_data(base, other, length)),
+            (KVal::Int(base), KVal::Int(other)) => KVal::Int(join_data(base, other, length)),
+            (KVal::Long(base), KVal::Long(other)) => KVal::Long(join_data(base, other, length)),
+            (KVal::Real(base), KVal::Real(other)) => KVal::Real(join_data(base, other, length)),
+            (KVal::Float(base), KVal::Float(other)) => KVal::Float(join_data(base, other, length)),
+            (KVal::Symbol(base), KVal::Symbol(other)) => {
+                KVal::Symbol(join_data(base, other, length))
+            }
+            (
+                base @ (KVal::String(_) | KVal::Char(_)),
+                other @ (KVal::String(_) | KVal::Char(_)),
+            ) => {
+                let mut string = match base {
+                    KVal::String(Cow::Owned(string)) => string,
+                    base => {
+                        let mut string = Vec::with_capacity(length);
+                        push_chars(&mut string, &base).map_err(|_| char_out_of_range(&base))?;
+                        string
+                    }
+                };
+                push_chars(&mut string, &other).map_err(|_| char_out_of_range(&other))?;
+                KVal::String(Cow::Owned(string))
+            }
+            (KVal::Timestamp(base), KVal::Timestamp(other)) => {
+                KVal::Timestamp(join_data(base, other, length))
+            }
+            (KVal::Month(base), KVal::Month(other)) => KVal::Month(join_data(base, other, length)),
+            (KVal::Date(base), KVal::Date(other)) => KVal::Date(join_data(base, other, length)),
+            (KVal::Datetime(base), KVal::Datetime(other)) => {
+                KVal::Datetime(join_data(base, other, length))
+            }
+            (KVal::Timespan(base), KVal::Timespan(other)) => {
+                KVal::Timespan(join_data(base, other, length))
+            }
+            (KVal::Minute(base), KVal::Minute(other)) => {
+                KVal::Minute(join_data(base, other, length))
+            }
+            (KVal::Second(base), KVal::Second(other)) => {
+                KVal::Second(join_data(base, other, length))
+            }
+            (KVal::Time(base), KVal::Time(other)) => KVal::Time(join_data(base, other, length)),
+            (KVal::Enum(base, base_source), KVal::Enum(other, other_source))
+                if base_source == other_source =>
+            {
+                KVal::Enum(join_data(base, other, length), base_source)
+            }
+            (base, other) => {
+                let mut elements = base.into_elements();
+                elements.reserve(length - elements.len());
+                elements.extend(other.into_elements());
+                KVal::CompoundList(elements)
+            }
+        })
+    }
+
+    /// Join a value to a borrowed value in the same manner as [`join`](#method.join) without consuming `other`, so
+    ///  that the same value can be joined to many bases. The elements of `other` are copied once into the result and
+    ///  `other` is left as it is.
+    /// # Errors
+    /// Same as [`join`](#method.join).
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let suffix = KVal::Long(KData::List(Cow::Owned(vec![8, 9])));
+    /// let mut accumulator = KVal::Long(KData::List(Cow::Owned(vec![1])));
+    /// for _ in 0..2 {
+    ///   accumulator = KVal::join_ref(accumulator, &suffix).unwrap();
+    /// }
+    /// assert_eq!(accumulator, KVal::Long(KData::List(Cow::Owned(vec![1, 8, 9, 8, 9]))));
+    /// assert_eq!(suffix.len(), 2);
+    /// ```
+    pub fn join_ref(base: Self, other: &KVal) -> Result<Self, KError> {
+        let length = base.join_length()? + other.join_length()?;
+        q_length(length)?;
+        Ok(match (base, other) {
+            (KVal::Bool(base), KVal::Bool(other)) => {
+                KVal::Bool(join_data(base, view(other), length))
+            }
+            (KVal::Guid(base), KVal::Guid(other)) => {
+                KVal::Guid(join_data(base, view(other), length))
+            }
+            (KVal::Byte(base), KVal::Byte(other)) => {
+                KVal::Byte(join_data(base, view(other), length))
+            }
+            (KVal::Short(base), KVal::Short(other)) => {
+                KVal::Short(join_data(base, view(other), length))
+            }
+            (KVal::Int(base), KVal::Int(other)) => KVal::Int(join_data(base, view(other), length)),
+            (KVal::Long(base), KVal::Long(other)) => {
+                KVal::Long(join_data(base, view(other), length))
+            }
+            (KVal::Real(base), KVal::Real(other)) => {
+                KVal::Real(join_data(base, view(other), length))
+            }
+            (KVal::Float(base), KVal::Float(other)) => {
+                KVal::Float(join_data(base, view(other), length))
+            }
+            (KVal::Symbol(base), KVal::Symbol(other)) => {
+                KVal::Symbol(join_data(base, view(other), length))
+            }
+            (
+                base @ (KVal::String(_) | KVal::Char(_)),
+                other @ (KVal::String(_) | KVal::Char(_)),
+            ) => {
+                let mut string = match base {
+                    KVal::String(Cow::Owned(string)) => string,
+                    base => {
+                        let mut string = Vec::with_capacity(length);
+                        push_chars(&mut string, &base).map_err(|_| char_out_of_range(&base))?;
+                        string
+                    }
+                };
+                push_chars(&mut string, other).map_err(|_| char_out_of_range(other))?;
+                KVal::String(Cow::Owned(string))
+            }
+            (KVal::Timestamp(base), KVal::Timestamp(other)) => {
+                KVal::Timestamp(join_data(base, view(other), length))
+            }
+            (KVal::Month(base), KVal::Month(other)) => {
+                KVal::Month(join_data(base, view(other), length))
+            }
+            (KVal::Date(base), KVal::Date(other)) => {
+                KVal::Date(join_data(base, view(other), length))
+            }
+            (KVal::Datetime(base), KVal::Datetime(other)) => {
+                KVal::Datetime(join_data(base, view(other), length))
+            }
+            (KVal::Timespan(base), KVal::Timespan(other)) => {
+                KVal::Timespan(join_data(base, view(other), length))
+            }
+            (KVal::Minute(base), KVal::Minute(other)) => {
+                KVal::Minute(join_data(base, view(other), length))
+            }
+            (KVal::Second(base), KVal::Second(other)) => {
+                KVal::Second(join_data(base, view(other), length))
+            }
+            (KVal::Time(base), KVal::Time(other)) => {
+                KVal::Time(join_data(base, view(other), length))
+            }
+            (KVal::Enum(base, base_source), KVal::Enum(other, other_source))
+                if base_source == *other_source =>
+            {
+                KVal::Enum(join_data(base, view(other), length), base_source)
+            }
+            (base, other) => {
+                let mut elements = base.into_elements();
+                elements.reserve(length - elements.len());
+                match other {
+                    KVal::CompoundList(list) => {
+                        elements.extend(list.iter().map(|element| element.deep_clone()))
+                    }
+                    KVal::Null => elements.push(KVal::Null),
+                    other => elements.extend(other.iter().map(|element| element.deep_clone())),
+                }
+                KVal::CompoundList(elements)
+            }
+        })
+    }
+
+    /// Join any number of values into a list in one pass, in the same manner as `raze` in q or as joining the parts
+    ///  one by one with [`join`](#method.join).
+    /// - Parts of the same type, chars and strings, or enums of the same source result in a simple list.
+    /// - Any other combination results in a compound list of the elements of all parts. No part results in an empty
+    ///   compound list.
+    ///
+    /// The total length is computed first and the result is allocated once. An owned list of the first part is
+    ///  extended in place and the elements of owned parts are moved.
+    /// # Errors
+    /// - `"type"` if a part is a table, a dictionary or an error.
+    /// - `"too long"` if the length of the result exceeds [`max_list_length`](fn.max_list_length.html). The error is
+    ///   returned before anything is allocated.
+    /// - `"char out of byte range"` if a char joined to a string or a char is greater than `'\u{ff}'`.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let parts = (0..3).map(|i| KVal::Long(KData::List(Cow::Owned(vec![i; 2])))).collect();
+    /// let razed = KVal::concat(parts).unwrap();
+    /// assert_eq!(razed, KVal::Long(KData::List(Cow::Owned(vec![0, 0, 1, 1, 2, 2]))));
+    ///
+    /// let mixed = KVal::concat(vec![razed, KVal::Char('a')]).unwrap();
+    /// assert_eq!(mixed.qtype(), 0);
+    /// assert_eq!(mixed.len(), 7);
+    /// ```
+    pub fn concat(parts: Vec<KVal<'a>>) -> Result<KVal<'a>, &'static str> {
+        let mut length = 0;
+        for part in &parts {
+            length += part.join_length().map_err(|_| TYPE)?;
+        }
+        q_length(length).map_err(|_| TOO_LONG)?;
+        let mut parts = parts.into_iter();
+        let first = match parts.next() {
+            Some(first) => first,
+            None => return Ok(KVal::CompoundList(Vec::new())),
+        };
+        if parts
+            .as_slice()
+            .iter()
+            .all(|part| joins_simply(&first, part))
+        {
+            let mut list = first.into_list(length)?;
+            for part in parts {
+                // Parts were checked to join into a simple list, which fails only for a char out of the byte range.
+                list.append(part)?;
+            }
+            Ok(list)
+        } else {
+            let mut elements = first.into_elements();
+            elements.reserve(length - elements.len());
+            parts.for_each(|part| elements.extend(part.into_elements()));
+            Ok(KVal::CompoundList(elements))
+        }
+    }
+
+    /// Push an atom to the end of the list in place. An atom of `self` becomes a list of two elements and a char
+    ///  becomes a string, to which a char is pushed as a single byte. A compound list takes any value as an element.
+    ///
+    /// An owned list is extended without copying its elements, and a list borrowing q memory is copied once into
+    ///  an owned vector on the first push.
+    /// # Errors
+    /// - `"not an atom"` if `atom` is not an atom and `self` is not a compound list.
+    /// - `"type"` if `atom` has a different type from `self`, or `self` is neither an atom nor a list.
+    /// - `"enum sources differ"` if enums have different sources.
+    /// - `"char out of byte range"` if a char pushed to a string or a char is greater than `'\u{ff}'`.
+    ///
+    /// `self` is left unchanged on error.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let mut list = KVal::Long(KData::Atom(Cow::Owned(1)));
+    /// for long in 2..=4{
+    ///   list.push(KVal::Long(KData::Atom(Cow::Owned(long)))).unwrap();
+    /// }
+    /// assert_eq!(list, KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3, 4]))));
+    ///
+    /// let error = list.push(KVal::Char('a')).unwrap_err();
+    /// assert_eq!(error, "type\0");
+    /// ```
+    pub fn push(&mut self, atom: KVal<'a>) -> Result<(), &'static str> {
+        if let KVal::CompoundList(list) = self {
+            list.push(atom);
+            return Ok(());
+        }
+        match atom.qtype() {
+            qtype if qtype < 0 && !matches!(atom, KVal::Error(_)) => self.append(atom),
+            _ => Err(NOT_AN_ATOM),
+        }
+    }
+
+    /// Append an atom or the elements of a list to the end of the list in place in the same manner as
+    ///  [`join`](#method.join), except that values of different types are not joined into a compound list. A
+    ///  compound list takes the elements of `other`.
+    ///
+    /// The buffer of `self` is reused in the same manner as [`push`](#method.push).
+    /// # Errors
+    /// - `"type"` if `other` has a different type from `self`, or either of them is a table, a dictionary, an error
+    ///   or a general null.
+    /// - `"enum sources differ"` if enums have different sources.
+    /// - `"char out of byte range"` if a char appended to a string or a char is greater than `'\u{ff}'`.
+    ///
+    /// `self` is left unchanged on error.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn append_to(list: K, other: K) -> K{
+    ///   let mut list = KVal::from_raw(list);
+    ///   match list.append(KVal::from_raw(other)){
+    ///     Ok(()) => list.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)append_to: `libapi_examples 2: (`append_to; 2);
+    /// q)append_to[1 2; 3 4]
+    /// 1 2 3 4
+    /// q)append_to[1 2; 3i]
+    /// 'type
+    /// ```
+    pub fn append(&mut self, other: KVal<'a>) -> Result<(), &'static str> {
+        if let KVal::Char(character) = *self {
+            let byte = char_to_byte(character)?;
+            match other {
+                KVal::Char(other) => {
+                    *self = KVal::String(Cow::Owned(vec![byte, char_to_byte(other)?]));
+                    return Ok(());
+                }
+                KVal::String(_) => *self = KVal::String(Cow::Owned(vec![byte])),
+                _ => return Err(TYPE),
+            }
+        }
+        match (self, other) {
+            (_, KVal::Table(_) | KVal::Dictionary(_) | KVal::Error(_) | KVal::Null) => {
+                return Err(TYPE)
+            }
+            (KVal::CompoundList(list), other) => list.extend(other.into_elements()),
+            (KVal::Bool(data), KVal::Bool(other)) => append_data(data, other),
+            (KVal::Guid(data), KVal::Guid(other)) => append_data(data, other),
+            (KVal::Byte(data), KVal::Byte(other)) => append_data(data, other),
+            (KVal::Short(data), KVal::Short(other)) => append_data(data, other),
+            (KVal::Int(data), KVal::Int(other)) => append_data(data, other),
+            (KVal::Long(data), KVal::Long(other)) => append_data(data, other),
+            (KVal::Real(data), KVal::Real(other)) => append_data(data, other),
+            (KVal::Float(data), KVal::Float(other)) => append_data(data, other),
+            (KVal::Symbol(data), KVal::Symbol(other)) => append_data(data, other),
+            (KVal::String(string), KVal::String(other)) => {
+                string.to_mut().extend_from_slice(&other)
+            }
+            (KVal::String(string), KVal::Char(character)) => {
+                string.to_mut().push(char_to_byte(character)?)
+            }
+            (KVal::Timestamp(data), KVal::Timestamp(other)) => append_data(data, other),
+            (KVal::Month(data), KVal::Month(other)) => append_data(data, other),
+            (KVal::Date(data), KVal::Date(other)) => append_data(data, other),
+            (KVal::Datetime(data), KVal::Datetime(other)) => append_data(data, other),
+            (KVal::Timespan(data), KVal::Timespan(other)) => append_data(data, other),
+            (KVal::Minute(data), KVal::Minute(other)) => append_data(data, other),
+            (KVal::Second(data), KVal::Second(other)) => append_data(data, other),
+            (KVal::Time(data), KVal::Time(other)) => append_data(data, other),
+            (KVal::Enum(data, source), KVal::Enum(other, other_source)) => {
+                if *source != other_source {
+                    return Err(ENUM_SOURCE_MISMATCH);
+                }
+                append_data(data, other)
+            }
+            _ => return Err(TYPE),
+        }
+        Ok(())
+    }
+
+    /// Number of elements the value contributes to a join.
+    fn join_length(&self) -> Result<usize, KError> {
+        match self {
+            KVal::Table(_) | KVal::Dictionary(_) | KVal::Error(_) => Err(
+                KError::invalid_operation("join", self.type_name(), Some("atom or list")),
+            ),
+            KVal::Null => Ok(1),
+            value => Ok(value.len()),
+        }
+    }
+
+    /// Convert an atom or a simple list into an owned list with room for `length` elements. A char becomes a
+    ///  string and any other value is returned as it is.
+    fn into_list(self, length: usize) -> Result<KVal<'a>, &'static str> {
+        Ok(match self {
+            KVal::Bool(data) => KVal::Bool(KData::List(Cow::Owned(reserve_data(data, length)))),
+            KVal::Guid(data) => KVal::Guid(KData::List(Cow::Owned(reserve_data(data, length)))),
+            KVal::Byte(data) => KVal::Byte(KData::List(Cow::Owned(reserve_data(data, length)))),
+            KVal::Short(data) => KVal::Short(KData::List(Cow::Owned(reserve_data(data, length)))),
+            KVal::Int(data) => KVal::Int(KData::List(Cow::Owned(reserve_data(data, length)))),
+            KVal::Long(data) => KVal::Long(KData::List(Cow::Owned(reserve_data(data, length)))),
+            KVal::Real(data) => KVal::Real(KData::List(Cow::Owned(reserve_data(data, length)))),
+            KVal::Float(data) => KVal::Float(KData::List(Cow::Owned(reserve_data(data, length)))),
+            KVal::Symbol(data) => KVal::Symbol(KData::List(Cow::Owned(reserve_data(data, length)))),
+            string @ (KVal::String(_) | KVal::Char(_)) => {
+                let mut owned = match string {
+                    KVal::String(Cow::Owned(owned)) => owned,
+                    string => {
+                        let mut owned = Vec::new();
+                        push_chars(&mut owned, &string)?;
+                        owned
+                    }
+                };
+                owned.reserve(length.saturating_sub(owned.len()));
+                KVal::String(Cow::Owned(owned))
+            }
+            KVal::Timestamp(data) => {
+                KVal::Timestamp(KData::List(Cow::Owned(reserve_data(data, length))))
+            }
+            KVal::Month(data) => KVal::Month(KData::List(Cow::Owned(reserve_data(data, length)))),
+            KVal::Date(data) => KVal::Date(KData::List(Cow::Owned(reserve_data(data, length)))),
+            KVal::Datetime(data) => {
+                KVal::Datetime(KData::List(Cow::Owned(reserve_data(data, length))))
+            }
+            KVal::Timespan(data) => {
+                KVal::Timespan(KData::List(Cow::Owned(reserve_data(data, length))))
+            }
+            KVal::Minute(data) => KVal::Minute(KData::List(Cow::Owned(reserve_data(data, length)))),
+            KVal::Second(data) => KVal::Second(KData::List(Cow::Owned(reserve_data(data, length)))),
+            KVal::Time(data) => KVal::Time(KData::List(Cow::Owned(reserve_data(data, length)))),
+            KVal::Enum(data, source) => {
+                KVal::Enum(KData::List(Cow::Owned(reserve_data(data, length))), source)
+            }
+            other => other,
+        })
+    }
+
+    /// Take the elements of a list as a vector of atoms. An atom or a general null results in itself.
+    pub(super) fn into_elements(self) -> Vec<KVal<'a>> {
+        match self.to_compound_list() {
+            KVal::CompoundList(elements) => elements,
+            atom => vec![atom],
+        }
+    }
+}
+
+impl<'a> KTable<'a> {
+    /// Append the rows of another table in the same manner as `table,other` in q. Each column is joined to the
+    ///  column of the same position with [`KVal::join`](enum.KVal.html#method.join), so a column becomes a compound
+    ///  list if the types of the pair differ.
+    /// # Errors
+    /// - `InvalidOperation` if either of the tables is a keyed table.
+    /// - `Q` of `Mismatch` if the tables do not have the same column names in the same order.
+    /// - Any error of [`KVal::join`](enum.KVal.html#method.join) on a pair of columns.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let table = KTable::from_columns(vec![("sym", kval!(["a"])), ("price", kval!([1.5]))]).unwrap();
+    /// let other = KTable::from_columns(vec![("sym", kval!(["b"])), ("price", kval!([2.5]))]).unwrap();
+    /// let appended = table.append(other).unwrap();
+    /// assert_eq!(appended.get_column("price").unwrap(), kval!([1.5, 2.5]));
+    ///
+    /// let other = KTable::from_columns(vec![("price", kval!([2.5])), ("sym", kval!(["b"]))]).unwrap();
+    /// assert_eq!(appended.append(other).unwrap_err().to_string(), "mismatch");
+    /// ```
+    pub fn append(self, other: KTable<'a>) -> Result<KTable<'a>, KError> {
+        let (names, columns) = into_parts(self, "append")?;
+        let (other_names, other_columns) = into_parts(other, "append")?;
+        if names != other_names {
+            return Err(KError::q("mismatch".to_string(), None));
+        }
+        let columns = columns
+            .into_iter()
+            .zip(other_columns)
+            .map(|(column, other)| KVal::join(column, other))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(from_parts(names, columns))
+    }
+
+    /// Append the rows of another table aligning the columns by name in the same manner as `table uj other` in q.
+    ///  The columns of the table come first, followed by the columns found only in `other`.
+    /// - A column missing on either side is filled with the null of its type. Bool and byte, which have no null,
+    ///   are filled with `0b` and `0x00`, and an enum column with the null of the same source.
+    /// - A compound column is filled with the null of its first element, e.g., an empty string for a column of
+    ///   strings, or with a general null.
+    /// - A pair of columns of different types results in a compound column.
+    /// # Errors
+    /// - `InvalidOperation` if either of the tables is a keyed table.
+    /// - Any error of [`KVal::join`](enum.KVal.html#method.join) on a pair of columns.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn union_tables(table: K, other: K) -> K{
+    ///   let (table, other) = match (KVal::from_raw(table), KVal::from_raw(other)){
+    ///     (KVal::Table(table), KVal::Table(other)) => (table, other),
+    ///     _ => return new_error("not a table\0")
+    ///   };
+    ///   match table.union(other){
+    ///     Ok(union) => union.to_k(),
+    ///     Err(error) => error.to_k()
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)union_tables: `libapi_examples 2: (`union_tables; 2);
+    /// q)union_tables[([] sym: `a`b; price: 1.5 2.5); ([] sym: enlist `c; size: enlist 100)]
+    /// sym price size
+    /// --------------
+    /// a   1.5
+    /// b   2.5
+    /// c         100
+    /// ```
+    pub fn union(self, other: KTable<'a>) -> Result<KTable<'a>, KError> {
+        let (length, other_length) = (self.len(), other.len());
+        let (mut names, columns) = into_parts(self, "union")?;
+        let (other_names, other_columns) = into_parts(other, "union")?;
+        let mut other_columns = other_names
+            .into_iter()
+            .zip(other_columns)
+            .map(Some)
+            .collect::<Vec<_>>();
+        let mut joined = Vec::with_capacity(columns.len() + other_columns.len());
+        for (name, column) in names.iter().zip(columns) {
+            let other = other_columns
+                .iter_mut()
+                .find(|pair| matches!(pair, Some((other_name, _)) if other_name == name))
+                .and_then(Option::take)
+                .map(|(_, other)| other);
+            let other = match other {
+                Some(other) => other,
+                None => nulls_like(&column, other_length),
+            };
+            joined.push(KVal::join(column, other)?);
+        }
+        for (name, other) in other_columns.into_iter().flatten() {
+            joined.push(KVal::join(nulls_like(&other, length), other)?);
+            names.push(name);
+        }
+        Ok(from_parts(names, joined))
+    }
+
+    /// Join the columns of another table by key columns in the same manner as `table lj keyed` in q, where `keyed`
+    ///  is `other` keyed by `on`. Every row of this table is kept in its order and the other columns of `other` are
+    ///  appended to it from the row whose keys equal those of the row.
+    /// - If the keys appear in more than one row of `other`, the last row is taken.
+    /// - A row without a match is filled with the nulls of the columns in the same manner as
+    ///   [`take_rows`](#method.take_rows).
+    /// - Keys are compared by `==` of `KVal`, e.g., symbols by text and enums by index and source. Enum columns of
+    ///   `other` carry their sources into the result.
+    /// # Errors
+    /// - `"no such column"` if a key column is missing on either side.
+    /// - `"duplicate column"` if a column of `other` which is not a key column exists in this table.
+    /// - `"type"` if either of the tables is a keyed table.
+    /// - `"length"` if the columns of either of the tables do not have the same length.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn left_join(table: K, other: K, on: K) -> K{
+    ///   let (table, other) = (KTable::from_raw(table), KTable::from_raw(other));
+    ///   let on = match KVal::from_raw(on){
+    ///     KVal::Symbol(KData::List(on)) => on.into_owned(),
+    ///     _ => return new_error("keys must be a symbol list\0")
+    ///   };
+    ///   match table.left_join(&other, &on.iter().map(String::as_str).collect::<Vec<_>>()){
+    ///     Ok(joined) => joined.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)left_join: `libapi_examples 2: (`left_join; 3);
+    /// q)left_join[([] sym: `a`b`c; size: 1 2 3); ([] sym: `a`c; venue: `x`y); enlist `sym]
+    /// sym size venue
+    /// --------------
+    /// a   1    x
+    /// b   2
+    /// c   3    y
+    /// ```
+    pub fn left_join<'b>(
+        &'b self,
+        other: &'b KTable,
+        on: &[&str],
+    ) -> Result<KTable<'b>, &'static str> {
+        let matched = match_rows(self, other, on)?;
+        let (names, columns) = table_parts(self)?;
+        let mut names = names.to_vec();
+        // Simple list columns borrow the elements of this table.
+        let mut columns = columns
+            .iter()
+            .map(|column| {
+                column
+                    .slice(0..column.len())
+                    .unwrap_or_else(|_| column.clone())
+            })
+            .collect::<Vec<_>>();
+        let (other_names, other_columns) = table_parts(other)?;
+        for (name, column) in other_names.iter().zip(other_columns) {
+            if !on.contains(&name.as_str()) {
+                names.push(name.clone());
+                columns.push(pick_rows(column, &matched));
+            }
+        }
+        Ok(from_parts(names, columns))
+    }
+
+    /// Join the columns of another table by key columns in the same manner as `table ij keyed` in q, where `keyed`
+    ///  is `other` keyed by `on`. Only the rows of this table whose keys are found in `other` are kept in their
+    ///  order, and the other columns of `other` are appended to them in the same manner as
+    ///  [`left_join`](#method.left_join).
+    /// # Errors
+    /// - `"no such column"` if a key column is missing on either side.
+    /// - `"duplicate column"` if a column of `other` which is not a key column exists in this table.
+    /// - `"type"` if either of the tables is a keyed table.
+    /// - `"length"` if the columns of either of the tables do not have the same length.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let trades = KTable::from_columns(vec![("sym", kval!(["a", "b", "c"])), ("size", kval!([1, 2, 3]))]).unwrap();
+    /// let venues = KTable::from_columns(vec![("sym", kval!(["c", "a"])), ("venue", kval!(["y", "x"]))]).unwrap();
+    /// let joined = trades.inner_join(&venues, &["sym"]).unwrap();
+    /// assert_eq!(joined.column_names(), vec!["sym", "size", "venue"]);
+    /// assert_eq!(joined.get_column("venue").unwrap(), kval!(["x", "y"]));
+    /// ```
+    pub fn inner_join<'b>(
+        &'b self,
+        other: &'b KTable,
+        on: &[&str],
+    ) -> Result<KTable<'b>, &'static str> {
+        let matched = match_rows(self, other, on)?;
+        let (rows, matched): (Vec<i64>, Vec<i64>) = matched
+            .into_iter()
+            .enumerate()
+            .filter(|(_, other_row)| *other_row >= 0)
+            .map(|(row, other_row)| (row as i64, other_row))
+            .unzip();
+        let (names, columns) = table_parts(self)?;
+        let mut names = names.to_vec();
+        let mut columns = columns
+            .iter()
+            .map(|column| pick_rows(column, &rows))
+            .collect::<Vec<_>>();
+        let (other_names, other_columns) = table_parts(other)?;
+        for (name, column) in other_names.iter().zip(other_columns) {
+            if !on.contains(&name.as_str()) {
+                names.push(name.clone());
+                columns.push(pick_rows(column, &matched));
+            }
+        }
+        Ok(from_parts(names, columns))
+    }
+
+    /// Upsert rows by key columns in place in the same manner as `keyed upsert rows` in q, where `keyed` is this
+    ///  table keyed by `key_cols`.
+    /// - A row whose keys equal those of an existing row replaces that row in place. If the keys appear in more
+    ///   than one existing row, the first one is replaced.
+    /// - A row with new keys is appended. A later row of `rows` with the same keys replaces it.
+    /// - Keys are compared by `==` of `KVal` in the same manner as [`left_join`](#method.left_join).
+    ///
+    /// Rows are found through a hash index of the keys built on each call, which costs O(n) over the existing rows.
+    ///  A stream of updates is upserted with [`KKeyedTable::upsert`](struct.KKeyedTable.html#method.upsert), which
+    ///  keeps the index across calls.
+    /// # Errors
+    /// - `"type"` if either of the tables is a keyed table, or a column of `rows` has a different type from the
+    ///   column of this table. A compound column takes a column of any type.
+    /// - `"mismatch"` if the tables do not have the same column names in the same order.
+    /// - `"length"` if the columns of either of the tables do not have the same length, or `key_cols` is empty.
+    /// - `"no such column"` if a key column is missing.
+    ///
+    /// Every check is done before any column is modified, so the table is left unchanged on error.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn upsert_rows(table: K, key_cols: K, rows: K) -> K{
+    ///   let mut table = KTable::from_raw(table);
+    ///   let key_cols = match KVal::from_raw(key_cols){
+    ///     KVal::Symbol(KData::List(key_cols)) => key_cols.into_owned(),
+    ///     _ => return new_error("keys must be a symbol list\0")
+    ///   };
+    ///   let key_cols = key_cols.iter().map(String::as_str).collect::<Vec<_>>();
+    ///   match table.upsert(&key_cols, KTable::from_raw(rows)){
+    ///     Ok(()) => table.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)upsert_rows: `libapi_examples 2: (`upsert_rows; 3);
+    /// q)upsert_rows[([] sym: `a`b; price: 1.5 2.5); enlist `sym; ([] sym: `b`c; price: 3.5 4.5)]
+    /// sym price
+    /// ---------
+    /// a   1.5
+    /// b   3.5
+    /// c   4.5
+    /// ```
+    pub fn upsert(&mut self, key_cols: &[&str], rows: KTable) -> Result<(), &'static str> {
+        let length = self.len();
+        let (appended, replaced) = {
+            let (names, columns) = table_parts(self)?;
+            let (row_names, row_columns) = table_parts(&rows)?;
+            if names != row_names {
+                return Err(MISMATCH);
+            }
+            self.check_lengths()?;
+            rows.check_lengths()?;
+            check_upsert(columns.iter(), row_columns)?;
+            if key_cols.is_empty() {
+                return Err(LENGTH);
+            }
+            let keys = key_columns(self, key_cols)?;
+            let new_keys = key_columns(&rows, key_cols)?;
+            let mut index = HashMap::with_capacity(length + rows.len());
+            // Rows are indexed backwards so that the first of repeated keys is kept.
+            for row in (0..length).rev() {
+                index.insert(row_keys(&keys, row), row);
+            }
+            locate_rows(&mut index, length, rows.len(), |row| {
+                row_keys(&new_keys, row)
+            })
+        };
+        let (_, row_columns) = table_parts(&rows)?;
+        let columns = match self.dict.values.as_mut() {
+            KVal::CompoundList(columns) => columns,
+            _ => unreachable!("table was checked to be a table"),
+        };
+        upsert_columns(columns, row_columns, &appended, &replaced);
+        Ok(())
+    }
+}
+
+impl KKeyedTable<'_> {
+    /// Upsert rows in place in the same manner as `keyed upsert rows` in q. `rows` is a table of the key columns
+    ///  followed by the value columns.
+    /// - A row whose keys equal those of an existing row replaces the values of that row in place. If the keys
+    ///   appear in more than one existing row, the first one is replaced.
+    /// - A row with new keys is appended. A later row of `rows` with the same keys replaces it.
+    /// - Keys are compared by `==` of `KVal` in the same manner as
+    ///   [`KTable::upsert`](struct.KTable.html#method.upsert).
+    ///
+    /// The rows are found through a hash index of the keys, which is built on the first lookup or upsert and kept in
+    ///  the keyed table, so that repeated upserts cost O(1) amortized per row. The index is built again after the keys
+    ///  are borrowed through [`keys_mut`](struct.KKeyedTable.html#method.keys_mut).
+    /// # Errors
+    /// - `"type"` if either of the tables is not a table, or a column of `rows` has a different type from the
+    ///   column of this table. A compound column takes a column of any type.
+    /// - `"mismatch"` if `rows` does not have the key columns followed by the value columns in the same order.
+    /// - `"length"` if the columns of either of the tables do not have the same length, or there is no key column.
+    ///
+    /// Every check is done before any column is modified, so the keyed table is left unchanged on error.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use kdbplus::kval;
+    ///
+    /// let table = KTable::from_columns(vec![("sym", kval!(["a", "b"])), ("price", kval!([1.5, 2.5]))]).unwrap();
+    /// let mut keyed = table.enkey(1).unwrap();
+    /// for (sym, price) in [("b", 3.5), ("c", 4.5), ("c", 5.5)] {
+    ///   let row = KTable::from_columns(vec![("sym", kval!([sym])), ("price", kval!([price]))]).unwrap();
+    ///   keyed.upsert(row).unwrap();
+    /// }
+    /// assert_eq!(keyed.keys().get_column("sym").unwrap(), kval!(["a", "b", "c"]));
+    /// assert_eq!(keyed.values().get_column("price").unwrap(), kval!([1.5, 3.5, 5.5]));
+    /// ```
+    pub fn upsert(&mut self, rows: KTable) -> Result<(), &'static str> {
+        let length = self.len();
+        let (row_names, row_columns) = table_parts(&rows)?;
+        let (key_names, key_columns) = table_parts(&self.keys)?;
+        let (value_names, value_columns) = table_parts(&self.values)?;
+        if !key_names.iter().chain(value_names).eq(row_names) {
+            return Err(MISMATCH);
+        }
+        self.keys.check_lengths()?;
+        self.values.check_lengths()?;
+        rows.check_lengths()?;
+        if self.values.len() != length {
+            return Err(LENGTH);
+        }
+        check_upsert(key_columns.iter().chain(value_columns), row_columns)?;
+        if key_columns.is_empty() {
+            return Err(LENGTH);
+        }
+        let (row_keys, row_values) = row_columns.split_at(key_columns.len());
+        self.key_index()?;
+        let index = self
+            .index
+            .get_mut()
+            .unwrap_or_else(|| unreachable!("index was built"));
+        let new_keys = row_keys.iter().collect::<Vec<_>>();
+        let (appended, replaced) = locate_rows(&mut index.rows, length, rows.len(), |row| {
+            owned_keys(&new_keys, row)
+        });
+        match (
+            self.keys.dict.values.as_mut(),
+            self.values.dict.values.as_mut(),
+        ) {
+            (KVal::CompoundList(key_columns), KVal::CompoundList(value_columns)) => {
+                upsert_columns(key_columns, row_keys, &appended, &[]);
+                upsert_columns(value_columns, row_values, &appended, &replaced);
+            }
+            _ => unreachable!("keys and values were checked to be tables"),
+        }
+        Ok(())
+    }
+
+    /// Get the index of the rows by their keys, building it if the keys were borrowed mutably since it was last built.
+    /// # Errors
+    /// - `"type"` if the keys are not a table.
+    /// - `"length"` if the key columns do not have the same length or the keys and the values do not have the same
+    ///   number of rows.
+    pub(super) fn key_index(&self) -> Result<&KeyIndex, &'static str> {
+        let (_, key_columns) = table_parts(&self.keys)?;
+        self.keys.check_lengths()?;
+        if self.values.len() != self.keys.len() {
+            return Err(LENGTH);
+        }
+        Ok(self
+            .index
+            .get_or_init(|| KeyIndex::new(key_columns, self.len())))
+    }
+}
+
+impl KeyIndex {
+    /// Index `length` rows of the key columns.
+    fn new(key_columns: &[KVal], length: usize) -> Self {
+        let keys = key_columns.iter().collect::<Vec<_>>();
+        let mut rows = HashMap::with_capacity(length);
+        // Rows are indexed backwards so that the first of repeated keys is kept.
+        for row in (0..length).rev() {
+            rows.insert(owned_keys(&keys, row), row);
+        }
+        KeyIndex { rows }
+    }
+
+    /// Find the first row of the keys.
+    pub(super) fn find(&self, keys: &[KVal]) -> Option<usize> {
+        let keys = keys.iter().map(KVal::deep_clone).collect::<Vec<_>>();
+        self.rows.get(&keys).copied()
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Join two values of the same element type into an owned list of `length` elements.
+fn join_data<'a, T: Clone>(base: KData<'a, T>, other: KData<'_, T>, length: usize) -> KData<'a, T> {
+    let mut list = reserve_data(base, length);
+    push_data(&mut list, other);
+    KData::List(Cow::Owned(list))
+}
+
+/// Take the elements of data into a vector with room for `length` elements. An owned list is reserved in place and
+///  a borrowed list is copied once.
+fn reserve_data<T: Clone>(data: KData<'_, T>, length: usize) -> Vec<T> {
+    match data {
+        KData::List(Cow::Owned(mut list)) => {
+            list.reserve(length.saturating_sub(list.len()));
+            list
+        }
+        data => {
+            let mut list = Vec::with_capacity(length);
+            push_data(&mut list, data);
+            list
+        }
+    }
+}
+
+/// Check if a part is joined to the first part of [`KVal::concat`] into a simple list in the same manner as
+///  [`KVal::join`].
+fn joins_simply(first: &KVal, part: &KVal) -> bool {
+    match (first, part) {
+        (KVal::CompoundList(_) | KVal::Null, _) | (_, KVal::CompoundList(_) | KVal::Null) => false,
+        (KVal::String(_) | KVal::Char(_), KVal::String(_) | KVal::Char(_)) => true,
+        (KVal::Enum(_, source), KVal::Enum(_, other_source)) => source == other_source,
+        (first, part) => discriminant(first) == discriminant(part),
+    }
+}
+
+/// Borrow data without copying it.
+fn view<'b, T: Clone>(data: &'b KData<'_, T>) -> KData<'b, T> {
+    match data {
+        KData::Atom(atom) => KData::Atom(Cow::Borrowed(atom.as_ref())),
+        KData::List(list) => KData::List(Cow::Borrowed(list.as_ref())),
+    }
+}
+
+/// Push an atom or the elements of a list into a vector, moving them if they are owned.
+fn push_data<T: Clone>(list: &mut Vec<T>, data: KData<'_, T>) {
+    match data {
+        KData::Atom(atom) => list.push(atom.into_owned()),
+        KData::List(Cow::Owned(other)) => list.extend(other),
+        KData::List(Cow::Borrowed(other)) => list.extend_from_slice(other),
+    }
+}
+
+/// Append an atom or the elements of a list to data in place. An atom of `data` becomes a list and a borrowed list
+///  is copied once.
+fn append_data<T: Clone>(data: &mut KData<'_, T>, other: KData<'_, T>) {
+    let mut list = match std::mem::replace(data, KData::List(Cow::Owned(Vec::new()))) {
+        KData::Atom(atom) => vec![atom.into_owned()],
+        KData::List(list) => list.into_owned(),
+    };
+    push_data(&mut list, other);
+    *data = KData::List(Cow::Owned(list));
+}
+
+/// Push a string or a char into the bytes of a string. A char is pushed as a single byte.
+fn push_chars(string: &mut Vec<u8>, value: &KVal) -> Result<(), &'static str> {
+    match value {
+        KVal::String(other) => string.extend_from_slice(other),
+        KVal::Char(character) => string.push(char_to_byte(*character)?),
+        _ => unreachable!("only a string or a char is joined to a string"),
+    }
+    Ok(())
+}
+
+/// Error of a char which cannot be joined to a string because it is not a byte.
+fn char_out_of_range(character: &KVal) -> KError {
+    KError::out_of_range(character.to_string(), "char")
+}
+
+/// Take the column names and the columns of a table.
+fn into_parts<'a>(
+    table: KTable<'a>,
+    operator: &'static str,
+) -> Result<(Vec<String>, Vec<KVal<'a>>), KError> {
+    match (*table.dict.keys, *table.dict.values) {
+        (KVal::Symbol(KData::List(names)), KVal::CompoundList(columns)) => {
+            Ok((names.into_owned(), columns))
+        }
+        _ => Err(KError::invalid_operation(
+            operator,
+            "dictionary",
+            Some("table"),
+        )),
+    }
+}
+
+/// Build a list of `length` nulls which joins a column without changing its type in the manner of `uj` in q.
+fn nulls_like(column: &KVal, length: usize) -> KVal<'static> {
+    let null = prototype(column);
+    match column {
+        KVal::CompoundList(_) => KVal::CompoundList(vec![null; length]),
+        _ if length == 0 => column
+            .slice(0..0)
+            .map(|empty| empty.deep_clone())
+            .unwrap_or(KVal::CompoundList(Vec::new())),
+        _ => KVal::concat(vec![null; length])
+            .unwrap_or_else(|_| unreachable!("atoms of the same type are joined")),
+    }
+}
+
+/// Borrow the column names and the columns of a table.
+fn table_parts<'b>(table: &'b KTable) -> Result<(&'b [String], &'b [KVal<'b>]), &'static str> {
+    match (table.dict.keys.as_ref(), table.dict.values.as_ref()) {
+        (KVal::Symbol(KData::List(names)), KVal::CompoundList(columns)) => Ok((names, columns)),
+        _ => Err(TYPE),
+    }
+}
+
+/// Find the row of `other` whose keys equal those of each row of `table`, taking the last one if the keys repeat.
+///  `-1` stands for a row without a match.
+fn match_rows(table: &KTable, other: &KTable, on: &[&str]) -> Result<Vec<i64>, &'static str> {
+    let keys = key_columns(table, on)?;
+    let other_keys = key_columns(other, on)?;
+    table.check_lengths()?;
+    other.check_lengths()?;
+    let names = table.column_names();
+    if other
+        .column_names()
+        .iter()
+        .any(|name| !on.contains(name) && names.contains(name))
+    {
+        return Err(DUPLICATE_COLUMN);
+    }
+    let mut index = HashMap::with_capacity(other.len());
+    for row in 0..other.len() {
+        index.insert(row_keys(&other_keys, row), row as i64);
+    }
+    Ok((0..table.len())
+        .map(|row| index.get(&row_keys(&keys, row)).copied().unwrap_or(-1))
+        .collect())
+}
+
+/// Get the key columns of a table in the order of `on`.
+fn key_columns<'b>(table: &'b KTable, on: &[&str]) -> Result<Vec<&'b KVal<'b>>, &'static str> {
+    let columns = match table.dict.values.as_ref() {
+        KVal::CompoundList(columns) => columns,
+        _ => return Err(TYPE),
+    };
+    on.iter()
+        .map(|name| {
+            table
+                .column_index(name)
+                .map(|index| &columns[index])
+                .ok_or(NO_SUCH_COLUMN)
+        })
+        .collect()
+}
+
+/// Get the keys of a row borrowing the elements of the key columns. The key columns must have been checked to have
+///  at least `row + 1` elements.
+fn row_keys<'b>(keys: &[&'b KVal<'b>], row: usize) -> Vec<KVal<'b>> {
+    keys.iter()
+        .map(|column| {
+            column
+                .get(row)
+                .unwrap_or_else(|| unreachable!("columns have the same length"))
+        })
+        .collect()
+}
+
+/// Get the keys of a row copying the elements of the key columns, so that they outlive the columns.
+fn owned_keys(keys: &[&KVal], row: usize) -> Vec<KVal<'static>> {
+    row_keys(keys, row).iter().map(KVal::deep_clone).collect()
+}
+
+/// Check that the columns of rows can be upserted into the columns of a table, so that no column is modified before
+///  another one fails. Both tables must have been checked to have columns of the same length.
+fn check_upsert<'b>(
+    columns: impl Iterator<Item = &'b KVal<'b>>,
+    row_columns: &[KVal],
+) -> Result<(), &'static str> {
+    let mut row_columns = row_columns.iter();
+    for column in columns {
+        let row_column = row_columns.next().ok_or(MISMATCH)?;
+        if !upserts_into(column, row_column) {
+            return Err(TYPE);
+        }
+    }
+    match row_columns.next() {
+        Some(_) => Err(MISMATCH),
+        None => Ok(()),
+    }
+}
+
+/// Check if a column of rows can be upserted into a column of a table without changing its type.
+fn upserts_into(column: &KVal, row_column: &KVal) -> bool {
+    let is_list = |value: &KVal| (qtype::COMPOUND_LIST..=qtype::ENUM_LIST).contains(&value.qtype());
+    if !is_list(column) || !is_list(row_column) {
+        return false;
+    }
+    match (column, row_column) {
+        (KVal::CompoundList(_), _) => true,
+        (KVal::Enum(_, source), KVal::Enum(_, row_source)) => source == row_source,
+        _ => column.qtype() == row_column.qtype(),
+    }
+}
+
+/// Find the row into which each of `rows` rows is upserted through an index of the keys of `length` existing rows,
+///  adding the keys of new rows to the index. Returns the rows to append and pairs of the row to replace and the row
+///  replacing it.
+fn locate_rows<T: Eq + Hash>(
+    index: &mut HashMap<T, usize>,
+    length: usize,
+    rows: usize,
+    keys: impl Fn(usize) -> T,
+) -> (Vec<i64>, Vec<(usize, usize)>) {
+    let mut appended = Vec::new();
+    let mut replaced = Vec::new();
+    for row in 0..rows {
+        let next = length + appended.len();
+        match *index.entry(keys(row)).or_insert(next) {
+            target if target == next => appended.push(row as i64),
+            target => replaced.push((target, row)),
+        }
+    }
+    (appended, replaced)
+}
+
+/// Append the rows `appended` of the row columns to the columns and replace each target row with a row of the row
+///  columns. The columns must have been checked with `check_upsert`.
+fn upsert_columns(
+    columns: &mut [KVal],
+    row_columns: &[KVal],
+    appended: &[i64],
+    replaced: &[(usize, usize)],
+) {
+    for (column, row_column) in columns.iter_mut().zip(row_columns) {
+        column
+            .append(pick_rows(row_column, appended).deep_clone())
+            .unwrap_or_else(|_| unreachable!("columns were checked to take the rows"));
+        for (target, row) in replaced {
+            let cell = row_column
+                .get(*row)
+                .unwrap_or_else(|| unreachable!("columns were checked to have the same length"));
+            column
+                .set(*target, cell.deep_clone())
+                .unwrap_or_else(|_| unreachable!("columns were checked to take the rows"));
+        }
+    }
+}
@@ -0,0 +1,1026 @@
+//! Rust representation of a q dictionary.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::super::{decrement_reference_count, new_dictionary, KUtility, K, KNULL};
+use super::compound::collapse_atoms;
+use super::errors::{KEY_NOT_FOUND, NOT_A_LIST, TYPE};
+use super::kval::as_slice;
+use super::rows::empty_column;
+use super::{KData, KTable, KVal, KValIter};
+use crate::{qattribute, qtype};
+use std::cell::OnceCell;
+use std::cmp::Ordering;
+use std::iter::Zip;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Rust representation of a q dictionary. A keyed table is a dictionary whose keys and values are tables.
+///
+/// A sorted dictionary of q, e.g., `` `s#`a`b!1 2``, is marked as sorted (see [`is_sorted`](#method.is_sorted)) and
+///  is built into a sorted dictionary again by [`to_k`](#method.to_k), so that q keeps looking it up as a step
+///  function.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KDict<'a> {
+    /// Keys of the dictionary.
+    pub keys: Box<KVal<'a>>,
+    /// Values of the dictionary.
+    pub values: Box<KVal<'a>>,
+    /// Whether the dictionary is a sorted dictionary.
+    sorted: bool,
+}
+
+/// Rust representation of a q dictionary whose values are converted on demand. Converting a dictionary eagerly
+///  converts all of its values, which is wasted work for a dictionary holding large tables, e.g., a snapshot of a
+///  namespace, when only one entry is read. Keys are converted when the dictionary is built and each value is
+///  converted once when it is first read.
+/// # Note
+/// Only values held in a compound list are deferred. Values held in a simple list, e.g., those of `` `a`b!1 2``,
+///  are converted together when the dictionary is built as that is as cheap as converting one of them.
+#[derive(Debug)]
+pub struct LazyDict<'a> {
+    /// Keys of the dictionary.
+    pub keys: KVal<'a>,
+    /// Values of the dictionary.
+    values: LazyValues<'a>,
+    /// Whether the dictionary is a sorted dictionary.
+    sorted: bool,
+}
+
+/// Values of [`LazyDict`](struct.LazyDict.html).
+#[derive(Debug)]
+enum LazyValues<'a> {
+    /// Elements of a compound list and their conversion.
+    Compound(&'a [K], Vec<OnceCell<KVal<'a>>>),
+    /// Simple list converted eagerly and its atoms.
+    Simple(KVal<'a>, Vec<OnceCell<KVal<'a>>>),
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl<'a> KDict<'a> {
+    /// Build a dictionary from keys and values.
+    pub fn new(keys: KVal<'a>, values: KVal<'a>) -> Self {
+        KDict {
+            keys: Box::new(keys),
+            values: Box::new(values),
+            sorted: false,
+        }
+    }
+
+    /// Build `KDict` from a q dictionary. The reference count of the q object is not changed. A sorted dictionary is
+    ///  marked as sorted.
+    /// # Note
+    /// `dictionary` must be a q dictionary.
+    pub fn from_raw(dictionary: K) -> Self {
+        let pair = as_slice::<K>(dictionary);
+        let mut built = KDict::new(KVal::from_raw(pair[0]), KVal::from_raw(pair[1]));
+        built.sorted = dictionary.get_type() == qtype::SORTED_DICTIONARY;
+        built
+    }
+
+    /// Build a new q dictionary. A q error is returned if the keys or the values cannot be converted.
+    ///
+    /// A dictionary marked as sorted is built into a sorted dictionary whose keys carry the sorted attribute `s#`
+    ///  as long as the keys are still a simple list in ascending order. Otherwise a plain dictionary is built in the
+    ///  same manner as q drops the attribute from a list whose order is broken.
+    pub fn to_k(&self) -> K {
+        self.build(self.keeps_sorted())
+    }
+
+    /// Check if the dictionary is a sorted dictionary, which is the case for a dictionary built from a sorted
+    ///  dictionary of q or by [`into_sorted`](#method.into_sorted).
+    pub fn is_sorted(&self) -> bool {
+        self.sorted
+    }
+
+    /// Sort the keys in ascending order in the same manner as [`KVal::sort`](enum.KVal.html#method.sort) and
+    ///  reorder the values together with them. The dictionary is marked as sorted, so that it is built into a sorted
+    ///  dictionary of q by [`to_k`](#method.to_k). The order of equal keys is kept.
+    ///
+    /// Keys and values which are already in order are left as they are without being copied.
+    /// # Errors
+    /// - `"not a list"` if the keys are not a simple list, e.g., of a keyed table.
+    /// - `"type"` if the keys are a compound list or an enum list.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn step_function(dictionary: K) -> K{
+    ///   match KDict::from_raw(dictionary).into_sorted(){
+    ///     Ok(sorted) => sorted.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)step_function: `libapi_examples 2: (`step_function; 1);
+    /// q)rates: step_function 10 0 5!0.03 0.01 0.02
+    /// q)rates 7
+    /// 0.02
+    /// q)attr key rates
+    /// `s
+    /// ```
+    pub fn into_sorted(mut self) -> Result<KDict<'a>, &'static str> {
+        match self.keys.as_ref() {
+            KVal::CompoundList(_) | KVal::Enum(_, _) => return Err(TYPE),
+            keys if !(1..qtype::ENUM_LIST).contains(&keys.qtype()) => return Err(NOT_A_LIST),
+            _ => {}
+        }
+        let keys = self.keys.iter().collect::<Vec<_>>();
+        let mut order = (0..keys.len()).collect::<Vec<_>>();
+        order.sort_by(|left, right| {
+            keys[*left]
+                .partial_cmp(&keys[*right])
+                .unwrap_or(Ordering::Equal)
+        });
+        drop(keys);
+        if order
+            .iter()
+            .enumerate()
+            .any(|(index, element)| index != *element)
+        {
+            *self.keys = reorder(*self.keys, &order);
+            *self.values = reorder(*self.values, &order);
+        }
+        self.sorted = true;
+        Ok(self)
+    }
+
+    /// Get the number of keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Iterate over pairs of a key and its value in the same manner as [`KVal::iter`](enum.KVal.html#method.iter),
+    ///  i.e., a simple list yields atoms borrowing its elements and a compound list yields clones of its elements.
+    ///  A keyed table yields nothing.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let config = KDict::new(kval!(["host", "port"]), kval!(["feed", 5001]));
+    /// let mut pairs = config.iter();
+    /// assert_eq!(pairs.next(), Some((kval!("host"), kval!("feed"))));
+    /// assert_eq!(pairs.next(), Some((kval!("port"), kval!(5001))));
+    /// assert_eq!(pairs.next(), None);
+    /// ```
+    pub fn iter(&self) -> Zip<KValIter<'_>, KValIter<'_>> {
+        self.keys_iter().zip(self.values_iter())
+    }
+
+    /// Iterate over the keys in the same manner as [`KVal::iter`](enum.KVal.html#method.iter). A keyed table yields
+    ///  nothing.
+    pub fn keys_iter(&self) -> KValIter<'_> {
+        self.keys.iter()
+    }
+
+    /// Iterate over the values in the same manner as [`KVal::iter`](enum.KVal.html#method.iter). A keyed table
+    ///  yields nothing.
+    pub fn values_iter(&self) -> KValIter<'_> {
+        self.values.iter()
+    }
+
+    /// Check if a symbol or string key exists in the same manner as [`get`](#method.get) without touching the values.
+    ///  Symbols are compared as `&str` without building an atom for each key.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn has_option(options: K, name: K) -> K{
+    ///   match (KVal::from_raw(options), name.get_symbol()){
+    ///     (KVal::Dictionary(options), Ok(name)) => new_bool(options.contains_key(name) as i32),
+    ///     _ => new_error("expected a dictionary and a symbol\0")
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)has_option: `libapi_examples 2: (`has_option; 2);
+    /// q)has_option[`verbose`depth!(1b; 3); `depth]
+    /// 1b
+    /// q)has_option[`verbose`depth!(1b; 3); `limit]
+    /// 0b
+    /// ```
+    pub fn contains_key(&self, key: &str) -> bool {
+        text_position(&self.keys, key).is_some()
+    }
+
+    /// Find the index of a key of any type compared by `==` without touching the values. The first index is returned
+    ///  if the key appears more than once, which is the key found by a lookup in q. A symbol key in a symbol list is
+    ///  compared as `&str` without building an atom for each key.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let sides = KDict::new(kval!(["buy", "sell", "buy"]), kval!([1, -1, 2]));
+    /// assert_eq!(sides.position(&kval!("buy")), Some(0));
+    /// assert_eq!(sides.position(&kval!("sell")), Some(1));
+    /// assert_eq!(sides.position(&kval!(1)), None);
+    /// ```
+    pub fn position(&self, key: &KVal) -> Option<usize> {
+        position(&self.keys, key)
+    }
+
+    /// Get the value of a symbol or string key. `None` is returned if the key is not found or the keys are neither a
+    ///  symbol list nor a compound list, e.g., of strings. The first match is returned if the key appears more than
+    ///  once.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let config = KDict::new(kval!(["host", "port"]), kval!(["feed", 5001]));
+    /// assert_eq!(config.get("port"), Some(kval!(5001)));
+    /// assert_eq!(config.get("user"), None);
+    /// ```
+    pub fn get(&self, key: &str) -> Option<KVal<'_>> {
+        text_position(&self.keys, key).and_then(|index| self.values.get(index))
+    }
+
+    /// Get the value of a key of any type compared by `==`. The first match is returned if the key appears more
+    ///  than once.
+    pub fn get_by(&self, key: &KVal) -> Option<KVal<'_>> {
+        position(&self.keys, key).and_then(|index| self.values.get(index))
+    }
+
+    /// Get the value at the index. `None` is returned if the index is out of bounds.
+    pub fn value_at(&self, index: usize) -> Option<KVal<'_>> {
+        self.values.get(index)
+    }
+
+    /// Get a value nested in dictionaries of dictionaries in the same manner as `` cfg[`conn; `host] `` in q. Each
+    ///  element of the path is a symbol or string key of a dictionary found in the same manner as
+    ///  [`get`](#method.get), or an index of a list if it parses as an integer. Intermediate values are borrowed and
+    ///  only the value at the end of the path is cloned.
+    ///
+    /// `None` is returned if the path is empty, any step is missing or an intermediate value is neither a
+    ///  dictionary nor a compound list, e.g., an atom or a simple list.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let config = KDict::new(
+    ///   kval!(["conn", "feeds"]),
+    ///   kval!([{"host" => "feed", "port" => 5001}, ["trades", "quotes"]])
+    /// );
+    /// assert_eq!(config.get_in(&["conn", "port"]), Some(kval!(5001)));
+    /// assert_eq!(config.get_in(&["feeds", "1"]), Some(kval!("quotes")));
+    /// assert_eq!(config.get_in(&["conn", "user"]), None);
+    /// assert_eq!(config.get_in(&["feeds", "port"]), None);
+    /// ```
+    pub fn get_in(&self, path: &[&str]) -> Option<KVal<'_>> {
+        let (first, rest) = path.split_first()?;
+        match rest.split_last() {
+            None => self.get(first),
+            Some((last, middle)) => {
+                let node = middle
+                    .iter()
+                    .try_fold(child(self, first)?, |node, step| descend(node, step))?;
+                lookup(node, last)
+            }
+        }
+    }
+
+    /// Get a value nested in dictionaries of dictionaries in the same manner as [`get_in`](#method.get_in) and
+    ///  convert it with `TryFrom<KVal>`. `default` is returned if the path does not exist or the value cannot be
+    ///  converted.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn connection_of(config: K) -> K{
+    ///   let config = KDict::from_raw(config);
+    ///   let host = config.get_in_or(&["conn", "host"], String::from("localhost"));
+    ///   let port = config.get_in_or(&["conn", "port"], 5000_i64);
+    ///   new_string(&format!("{}:{}", host, port))
+    /// }
+    /// ```
+    /// ```q
+    /// q)connection_of: `libapi_examples 2: (`connection_of; 1);
+    /// q)connection_of `conn`mode!(`host`port!(`feed; 5001); `live)
+    /// "feed:5001"
+    /// q)connection_of `conn`mode!(`host`port!(`feed; "5001"); `live)
+    /// "feed:5000"
+    /// q)connection_of enlist[`mode]!enlist `live
+    /// "localhost:5000"
+    /// ```
+    pub fn get_in_or<'b, T: TryFrom<KVal<'b>>>(&'b self, path: &[&str], default: T) -> T {
+        self.get_in(path)
+            .and_then(|value| T::try_from(value).ok())
+            .unwrap_or(default)
+    }
+
+    /// Get a long atom of a symbol or string key found in the same manner as [`get`](#method.get).
+    /// # Errors
+    /// - `"key not found"` if the key is not found.
+    /// - `"not a long"` if the value is not a long atom.
+    pub fn get_long(&self, key: &str) -> Result<i64, &'static str> {
+        match self.entry(key)? {
+            KVal::Long(KData::Atom(long)) => Ok(*long),
+            _ => Err("not a long\0"),
+        }
+    }
+
+    /// Get a float atom of a symbol or string key found in the same manner as [`get`](#method.get).
+    /// # Errors
+    /// - `"key not found"` if the key is not found.
+    /// - `"not a float"` if the value is not a float atom.
+    pub fn get_float(&self, key: &str) -> Result<f64, &'static str> {
+        match self.entry(key)? {
+            KVal::Float(KData::Atom(float)) => Ok(*float),
+            _ => Err("not a float\0"),
+        }
+    }
+
+    /// Get a symbol atom of a symbol or string key found in the same manner as [`get`](#method.get).
+    /// # Errors
+    /// - `"key not found"` if the key is not found.
+    /// - `"not a symbol"` if the value is not a symbol atom.
+    pub fn get_symbol(&self, key: &str) -> Result<String, &'static str> {
+        match self.entry(key)? {
+            KVal::Symbol(KData::Atom(symbol)) => Ok(symbol.into_owned()),
+            _ => Err("not a symbol\0"),
+        }
+    }
+
+    /// Get a table of a symbol or string key found in the same manner as [`get`](#method.get).
+    /// # Errors
+    /// - `"key not found"` if the key is not found.
+    /// - `"not a table"` if the value is not a table.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn describe_feed(config: K) -> K{
+    ///   let config = match KVal::from_raw(config){
+    ///     KVal::Dictionary(config) => config,
+    ///     _ => return new_error("config must be a dictionary\0")
+    ///   };
+    ///   let describe = || -> Result<String, &'static str>{
+    ///     Ok(format!(
+    ///       "{}:{} every {}s for {} instruments",
+    ///       config.get_symbol("host")?,
+    ///       config.get_long("port")?,
+    ///       config.get_float("interval")?,
+    ///       config.get_table("instruments")?.len()
+    ///     ))
+    ///   };
+    ///   match describe(){
+    ///     Ok(description) => new_string(&description),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)describe_feed: `libapi_examples 2: (`describe_feed; 1);
+    /// q)describe_feed `host`port`interval`instruments!(`feed; 5001; 0.5; ([] sym: `a`b))
+    /// "feed:5001 every 0.5s for 2 instruments"
+    /// q)describe_feed `host`interval!(`feed; 0.5)
+    /// 'key not found
+    /// ```
+    pub fn get_table(&self, key: &str) -> Result<KTable<'_>, &'static str> {
+        match self.entry(key)? {
+            KVal::Table(table) => Ok(table),
+            _ => Err("not a table\0"),
+        }
+    }
+
+    /// Check if the dictionary has no key.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copy the dictionary into a new dictionary which owns all of its data. See
+    ///  [`KVal::deep_clone`](enum.KVal.html#method.deep_clone).
+    pub fn deep_clone(&self) -> KDict<'static> {
+        KDict {
+            keys: Box::new(self.keys.deep_clone()),
+            values: Box::new(self.values.deep_clone()),
+            sorted: self.sorted,
+        }
+    }
+
+    /// Insert a key and its value at the end of the dictionary unless the key already exists. A simple list of keys
+    ///  or values becomes a compound list when the new element does not have its type, in the same manner as `,` in
+    ///  q. Returns the value which is not inserted if the key already exists, in which case the dictionary is left
+    ///  unchanged. See [`upsert`](#method.upsert) to replace the existing value.
+    /// # Errors
+    /// - `"not a list"` if the keys or the values are not lists, e.g., of a keyed table.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let mut limits = KDict::new(kval!(["a"]), kval!([100]));
+    /// assert_eq!(limits.insert(kval!("b"), kval!(200)), Ok(None));
+    /// assert_eq!(limits.insert(kval!("b"), kval!(300)), Ok(Some(kval!(300))));
+    /// assert_eq!(limits.insert(kval!("c"), kval!("none")), Ok(None));
+    /// assert_eq!(limits, KDict::new(kval!(["a", "b", "c"]), kval!([100, 200, "none"])));
+    /// ```
+    pub fn insert(
+        &mut self,
+        key: KVal<'a>,
+        value: KVal<'a>,
+    ) -> Result<Option<KVal<'a>>, &'static str> {
+        self.check_lists()?;
+        if position(&self.keys, &key).is_some() {
+            return Ok(Some(value));
+        }
+        let length = self.len();
+        place(&mut self.keys, length, key);
+        place(&mut self.values, length, value);
+        self.check_length();
+        Ok(None)
+    }
+
+    /// Replace the value of a key if the key already exists, or insert the key and the value at the end of the
+    ///  dictionary in the same manner as [`insert`](#method.insert) otherwise. This is what `,` does with two
+    ///  dictionaries in q. Returns the value which is replaced.
+    /// # Errors
+    /// - `"not a list"` if the keys or the values are not lists, e.g., of a keyed table.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn upsert_all(dictionary: K, keys: K, values: K) -> K{
+    ///   let mut dictionary = KDict::from_raw(dictionary);
+    ///   let (keys, values) = (KVal::from_raw(keys), KVal::from_raw(values));
+    ///   for (key, value) in keys.iter().zip(values.iter()){
+    ///     if let Err(error) = dictionary.upsert(key, value){
+    ///       return new_error(error);
+    ///     }
+    ///   }
+    ///   dictionary.to_k()
+    /// }
+    /// ```
+    /// ```q
+    /// q)upsert_all: `libapi_examples 2: (`upsert_all; 3);
+    /// q)upsert_all[`a`b!1 2; `b`c; (20; "x")]
+    /// a| 1
+    /// b| 20
+    /// c| "x"
+    /// ```
+    pub fn upsert(
+        &mut self,
+        key: KVal<'a>,
+        value: KVal<'a>,
+    ) -> Result<Option<KVal<'a>>, &'static str> {
+        self.check_lists()?;
+        match position(&self.keys, &key) {
+            Some(index) => {
+                let previous = self.values.remove(index)?;
+                place(&mut self.values, index, value);
+                self.check_length();
+                Ok(Some(previous))
+            }
+            None => self.insert(key, value),
+        }
+    }
+
+    /// Join two dictionaries in the same manner as `,` in q. The value of `other` is taken for a key in both of them.
+    ///  Keys only in `self` keep their order and keys only in `other` follow them in the order of `other`. A simple
+    ///  list of keys or values becomes a compound list when the elements of `other` do not have its type.
+    /// # Errors
+    /// - `"not a list"` if the keys or the values of either dictionary are not lists, e.g., of a keyed table.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let defaults = KDict::new(kval!(["host", "port"]), kval!(["localhost", 5000]));
+    /// let overrides = KDict::new(kval!(["port", "user"]), kval!([5001, "feed"]));
+    /// assert_eq!(
+    ///   defaults.merge(overrides),
+    ///   Ok(KDict::new(kval!(["host", "port", "user"]), kval!(["localhost", 5001, "feed"])))
+    /// );
+    /// ```
+    pub fn merge(self, other: KDict<'a>) -> Result<KDict<'a>, &'static str> {
+        self.merge_with(other, |_, right| right)
+    }
+
+    /// Join two dictionaries in the same manner as [`merge`](#method.merge) except that the value of a key in both
+    ///  of them is built by `f` from the value of `self` and that of `other`.
+    /// # Errors
+    /// - `"not a list"` if the keys or the values of either dictionary are not lists, e.g., of a keyed table.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn fill_join(left: K, right: K) -> K{
+    ///   let fill = |left: KVal<'static>, right: KVal<'static>| if right.is_null_atom(){ left } else{ right };
+    ///   match KDict::from_raw(left).merge_with(KDict::from_raw(right), fill){
+    ///     Ok(merged) => merged.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)fill_join: `libapi_examples 2: (`fill_join; 2);
+    /// q)fill_join[`a`b!1 2; `b`c!0N 3]
+    /// a| 1
+    /// b| 2
+    /// c| 3
+    /// ```
+    pub fn merge_with(
+        mut self,
+        other: KDict<'a>,
+        f: impl Fn(KVal<'a>, KVal<'a>) -> KVal<'a>,
+    ) -> Result<KDict<'a>, &'static str> {
+        self.check_lists()?;
+        other.check_lists()?;
+        let keys = other.keys.into_elements();
+        let values = other.values.into_elements();
+        for (key, value) in keys.into_iter().zip(values) {
+            match position(&self.keys, &key) {
+                Some(index) => {
+                    let left = self.values.remove(index)?;
+                    place(&mut self.values, index, f(left, value));
+                }
+                None => {
+                    let length = self.len();
+                    place(&mut self.keys, length, key);
+                    place(&mut self.values, length, value);
+                }
+            }
+        }
+        self.check_length();
+        Ok(self)
+    }
+
+    /// Build a dictionary of the pairs for which `pred` returns true in the order of the keys. Simple lists of keys
+    ///  and values stay simple lists of the same types even if no pair is kept. Keys and values which are not lists,
+    ///  e.g., of a keyed table, are kept as they are.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let config = KDict::new(kval!(["db_host", "db_port", "log_level"]), kval!(["feed", 5001, "info"]));
+    /// let db = config.filter(|key, _| matches!(key, KVal::Symbol(KData::Atom(key)) if key.starts_with("db_")));
+    /// assert_eq!(db, KDict::new(kval!(["db_host", "db_port"]), kval!(["feed", 5001])));
+    /// ```
+    pub fn filter(&self, pred: impl Fn(&KVal, &KVal) -> bool) -> KDict<'_> {
+        if self.check_lists().is_err() {
+            return self.clone();
+        }
+        let keep = self
+            .iter()
+            .map(|(key, value)| pred(&key, &value))
+            .collect::<Vec<_>>();
+        KDict {
+            keys: Box::new(select(&self.keys, &keep)),
+            values: Box::new(select(&self.values, &keep)),
+            sorted: self.sorted,
+        }
+    }
+
+    /// Map each value with `f` keeping the keys and their order. The mapped values make a simple list if they are
+    ///  atoms of the same type and a compound list otherwise, e.g., if `f` turns some of the long atoms into symbols.
+    /// # Errors
+    /// - `"not a list"` if the keys or the values are not lists, e.g., of a keyed table.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::kval;
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn double_prefixed(dictionary: K, prefix: K) -> K{
+    ///   let prefix = match prefix.get_symbol(){
+    ///     Ok(prefix) => prefix,
+    ///     Err(error) => return new_error(error)
+    ///   };
+    ///   let dictionary = KDict::from_raw(dictionary);
+    ///   let selected = dictionary.filter(|key, _| matches!(key, KVal::Symbol(KData::Atom(key)) if key.starts_with(prefix)));
+    ///   let doubled = selected.map_values(|value| match value{
+    ///     KVal::Long(KData::Atom(long)) => kval!(*long * 2),
+    ///     KVal::Float(KData::Atom(float)) => kval!(*float * 2.0),
+    ///     other => other
+    ///   });
+    ///   match doubled{
+    ///     Ok(doubled) => doubled.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)double_prefixed: `libapi_examples 2: (`double_prefixed; 2);
+    /// q)double_prefixed[`px_bid`px_ask`qty`px_src!(1.5; 2.5; 100; `feed); `px]
+    /// px_bid| 3f
+    /// px_ask| 5f
+    /// px_src| `feed
+    /// ```
+    pub fn map_values(self, f: impl Fn(KVal<'a>) -> KVal<'a>) -> Result<KDict<'a>, &'static str> {
+        self.check_lists()?;
+        let values = match self.values.is_empty() {
+            true => *self.values,
+            false => collapse_atoms(self.values.into_elements().into_iter().map(f).collect()),
+        };
+        Ok(KDict {
+            keys: self.keys,
+            values: Box::new(values),
+            sorted: self.sorted,
+        })
+    }
+
+    /// Remove a key and its value from the dictionary and return the value. The first match is removed if the key
+    ///  appears more than once. Returns `None` if the key is not found or the keys or the values are not lists.
+    pub fn remove(&mut self, key: &KVal) -> Option<KVal<'a>> {
+        self.check_lists().ok()?;
+        let index = position(&self.keys, key)?;
+        self.keys.remove(index).ok()?;
+        let value = self.values.remove(index).ok();
+        self.check_length();
+        value
+    }
+
+    /// Check that the keys and the values are lists which can be modified element by element.
+    fn check_lists(&self) -> Result<(), &'static str> {
+        match [&self.keys, &self.values]
+            .iter()
+            .all(|list| (qtype::COMPOUND_LIST..=qtype::ENUM_LIST).contains(&list.qtype()))
+        {
+            true => Ok(()),
+            false => Err(NOT_A_LIST),
+        }
+    }
+
+    /// Assert that there are as many values as keys after a modification.
+    fn check_length(&self) {
+        debug_assert_eq!(
+            self.keys.len(),
+            self.values.len(),
+            "keys and values of a dictionary must have the same length"
+        );
+    }
+
+    /// Check if the dictionary is built into a sorted dictionary, i.e., it is marked as sorted and the keys are still
+    ///  a simple list in ascending order.
+    pub(super) fn keeps_sorted(&self) -> bool {
+        self.sorted
+            && (1..qtype::ENUM_LIST).contains(&self.keys.qtype())
+            && is_ascending(&self.keys)
+    }
+
+    /// Build a new q dictionary, which is a sorted dictionary if `sorted` is true and the sorted attribute can be set
+    ///  on the keys. The keys and the values are built anew and handed to `new_dictionary`, which takes them over
+    ///  without incrementing their reference counts.
+    pub(super) fn build(&self, sorted: bool) -> K {
+        let mut keys = self.keys.to_k();
+        if keys.is_null() {
+            return KNULL;
+        }
+        let values = self.values.to_k();
+        if values.is_null() {
+            decrement_reference_count(keys);
+            return KNULL;
+        }
+        let sorted = sorted && keys.set_attribute(qattribute::SORTED).is_ok();
+        let dictionary = new_dictionary(keys, values);
+        if sorted && !dictionary.is_null() && dictionary.get_type() == qtype::DICTIONARY {
+            unsafe { (*dictionary).qtype = qtype::SORTED_DICTIONARY };
+        }
+        dictionary
+    }
+
+    /// Get the value of a symbol or string key which must exist.
+    fn entry(&self, key: &str) -> Result<KVal<'_>, &'static str> {
+        self.get(key).ok_or(KEY_NOT_FOUND)
+    }
+}
+
+impl<'a> IntoIterator for KDict<'a> {
+    type Item = (KVal<'a>, KVal<'a>);
+    type IntoIter = Zip<std::vec::IntoIter<KVal<'a>>, std::vec::IntoIter<KVal<'a>>>;
+
+    /// Consume the dictionary into pairs of a key and its value. The elements of a simple list are taken over
+    ///  without being copied. A keyed table yields nothing.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let limits = KDict::new(kval!(["a", "b"]), kval!([100, 200]));
+    /// let mut total = 0;
+    /// for (_, limit) in limits{
+    ///   if let KVal::Long(KData::Atom(limit)) = limit{
+    ///     total += *limit;
+    ///   }
+    /// }
+    /// assert_eq!(total, 300);
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        let (keys, values) = match (*self.keys, *self.values) {
+            (KVal::Table(_), _) | (_, KVal::Table(_)) => (Vec::new(), Vec::new()),
+            (keys, values) => (keys.into_elements(), values.into_elements()),
+        };
+        keys.into_iter().zip(values)
+    }
+}
+
+impl<'b, 'a: 'b> IntoIterator for &'b KDict<'a> {
+    type Item = (KVal<'b>, KVal<'b>);
+    type IntoIter = Zip<KValIter<'b>, KValIter<'b>>;
+
+    /// Same as [`KDict::iter`](struct.KDict.html#method.iter).
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> LazyDict<'a> {
+    /// Build `LazyDict` from a q dictionary converting only the keys. The reference count of the q object is not
+    ///  changed.
+    /// # Note
+    /// `dictionary` must be a q dictionary.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn entry_of(namespace: K, name: K) -> K{
+    ///   let namespace = LazyDict::from_raw(namespace);
+    ///   match namespace.get(name.get_symbol().unwrap_or("")){
+    ///     Some(value) => value.to_k(),
+    ///     None => new_error("no such entry\0")
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)entry_of: `libapi_examples 2: (`entry_of; 2);
+    /// q)snapshot: `trades`quotes`count!(([] price: 1 2f); ([] bid: 3 4f); 2)
+    /// q)entry_of[snapshot; `quotes]
+    /// bid
+    /// ---
+    /// 3
+    /// 4
+    /// ```
+    pub fn from_raw(dictionary: K) -> Self {
+        let pair = as_slice::<K>(dictionary);
+        let keys = KVal::from_raw(pair[0]);
+        let values = if pair[1].get_type() == qtype::COMPOUND_LIST {
+            let elements = as_slice::<K>(pair[1]);
+            LazyValues::Compound(elements, cells(elements.len()))
+        } else {
+            let values = KVal::from_raw(pair[1]);
+            let length = values.len();
+            LazyValues::Simple(values, cells(length))
+        };
+        LazyDict {
+            keys,
+            values,
+            sorted: dictionary.get_type() == qtype::SORTED_DICTIONARY,
+        }
+    }
+
+    /// Check if a symbol or string key exists without converting any value. See
+    ///  [`KDict::contains_key`](struct.KDict.html#method.contains_key).
+    pub fn contains_key(&self, key: &str) -> bool {
+        text_position(&self.keys, key).is_some()
+    }
+
+    /// Get the value of a symbol or string key converting it if it has not been read. `None` is returned if the key
+    ///  is not found or the keys are neither a symbol list nor a compound list, e.g., of strings. The first match is
+    ///  returned if the key appears more than once.
+    pub fn get(&self, key: &str) -> Option<&KVal<'a>> {
+        text_position(&self.keys, key).and_then(|index| self.values.get(index))
+    }
+
+    /// Get the value of a key of any type compared by `==` converting it if it has not been read. The first match
+    ///  is returned if the key appears more than once.
+    pub fn get_by(&self, key: &KVal) -> Option<&KVal<'a>> {
+        position(&self.keys, key).and_then(|index| self.values.get(index))
+    }
+
+    /// Get the value at the index converting it if it has not been read. `None` is returned if the index is out of
+    ///  bounds.
+    pub fn value_at(&self, index: usize) -> Option<&KVal<'a>> {
+        self.values.get(index)
+    }
+
+    /// Get the number of keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Check if the dictionary has no key.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the number of values which have been converted from the compound list of values.
+    pub fn converted(&self) -> usize {
+        match &self.values {
+            LazyValues::Compound(_, cells) => {
+                cells.iter().filter(|cell| cell.get().is_some()).count()
+            }
+            LazyValues::Simple(values, _) => values.len(),
+        }
+    }
+
+    /// Get the length of the value at the index without converting it.
+    pub(super) fn value_len(&self, index: usize) -> Option<usize> {
+        match &self.values {
+            LazyValues::Compound(elements, _) => {
+                elements.get(index).map(|element| element.len() as usize)
+            }
+            LazyValues::Simple(values, _) => values.get(index).map(|atom| atom.len()),
+        }
+    }
+
+    /// Convert the remaining values and build `KDict`.
+    pub fn into_dict(self) -> KDict<'a> {
+        let values = match self.values {
+            LazyValues::Compound(elements, cells) => KVal::CompoundList(
+                elements
+                    .iter()
+                    .zip(cells)
+                    .map(|(element, cell)| {
+                        cell.into_inner()
+                            .unwrap_or_else(|| KVal::from_raw(*element))
+                    })
+                    .collect(),
+            ),
+            LazyValues::Simple(values, _) => values,
+        };
+        let mut dictionary = KDict::new(self.keys, values);
+        dictionary.sorted = self.sorted;
+        dictionary
+    }
+}
+
+impl<'a> LazyValues<'a> {
+    /// Get the value at the index converting it once.
+    fn get(&self, index: usize) -> Option<&KVal<'a>> {
+        match self {
+            LazyValues::Compound(elements, cells) => {
+                let element = *elements.get(index)?;
+                Some(cells[index].get_or_init(|| KVal::from_raw(element)))
+            }
+            LazyValues::Simple(values, cells) => {
+                let atom = values.get(index)?;
+                Some(cells[index].get_or_init(|| atom.deep_clone()))
+            }
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Find the index of a key.
+fn position(keys: &KVal, key: &KVal) -> Option<usize> {
+    match (keys, key) {
+        (KVal::Symbol(KData::List(symbols)), KVal::Symbol(KData::Atom(symbol))) => symbols
+            .iter()
+            .position(|element| element.as_str() == symbol.as_str()),
+        _ => keys.iter().position(|element| element == *key),
+    }
+}
+
+/// Find the index of a symbol or string key without building an atom for each key.
+fn text_position(keys: &KVal, key: &str) -> Option<usize> {
+    match keys {
+        KVal::Symbol(KData::List(symbols)) => symbols.iter().position(|symbol| symbol == key),
+        KVal::CompoundList(elements) => elements.iter().position(|element| match element {
+            KVal::String(string) => string.as_ref() == key.as_bytes(),
+            KVal::Symbol(KData::Atom(symbol)) => symbol.as_str() == key,
+            _ => false,
+        }),
+        _ => None,
+    }
+}
+
+/// Borrow the value of a symbol or string key held in a compound list of values.
+fn child<'b, 'a>(dictionary: &'b KDict<'a>, key: &str) -> Option<&'b KVal<'a>> {
+    match dictionary.values.as_ref() {
+        KVal::CompoundList(values) => {
+            text_position(&dictionary.keys, key).and_then(|index| values.get(index))
+        }
+        _ => None,
+    }
+}
+
+/// Borrow the element of a dictionary or a compound list at a step of a path.
+fn descend<'b, 'a>(node: &'b KVal<'a>, step: &str) -> Option<&'b KVal<'a>> {
+    match node {
+        KVal::Dictionary(dictionary) => child(dictionary, step),
+        KVal::CompoundList(list) => step.parse::<usize>().ok().and_then(|index| list.get(index)),
+        _ => None,
+    }
+}
+
+/// Get the element of a dictionary or a list at the last step of a path.
+fn lookup<'b>(node: &'b KVal, step: &str) -> Option<KVal<'b>> {
+    match node {
+        KVal::Dictionary(dictionary) => dictionary.get(step),
+        _ if (0..qtype::TABLE).contains(&node.qtype()) => {
+            step.parse::<usize>().ok().and_then(|index| node.get(index))
+        }
+        _ => None,
+    }
+}
+
+/// Insert an element into a list of keys or values, expanding a simple list into a compound list if the element
+///  does not fit it. An element inserted into an empty compound list, e.g., of `()!()`, makes a simple list of it.
+fn place<'a>(list: &mut KVal<'a>, index: usize, element: KVal<'a>) {
+    match list {
+        KVal::CompoundList(elements) if elements.is_empty() => {
+            *list = collapse_atoms(vec![element])
+        }
+        KVal::CompoundList(elements) => elements.insert(index, element),
+        _ if fits(list, &element) => list
+            .insert(index, element)
+            .unwrap_or_else(|_| unreachable!("element was checked to fit the list")),
+        _ => {
+            *list = std::mem::replace(list, KVal::Null).to_compound_list();
+            place(list, index, element)
+        }
+    }
+}
+
+/// Check if an element can be inserted into a simple list without changing the type of the list.
+pub(super) fn fits(list: &KVal, element: &KVal) -> bool {
+    match (list, element) {
+        (KVal::Enum(_, source), KVal::Enum(KData::Atom(_), element_source)) => {
+            source == element_source
+        }
+        _ => element.qtype() == -list.qtype(),
+    }
+}
+
+/// Check if the atoms of a list are in ascending order.
+fn is_ascending(list: &KVal) -> bool {
+    let atoms = list.iter().collect::<Vec<_>>();
+    atoms
+        .windows(2)
+        .all(|pair| pair[0].partial_cmp(&pair[1]) != Some(Ordering::Greater))
+}
+
+/// Rearrange the elements of a list of keys or values in the order of the indices. A compound list stays a compound
+///  list.
+fn reorder<'a>(list: KVal<'a>, order: &[usize]) -> KVal<'a> {
+    let is_compound = matches!(list, KVal::CompoundList(_));
+    let mut elements = list
+        .into_elements()
+        .into_iter()
+        .map(Some)
+        .collect::<Vec<_>>();
+    let reordered = order
+        .iter()
+        .map(|index| {
+            elements[*index]
+                .take()
+                .unwrap_or_else(|| unreachable!("each index appears once"))
+        })
+        .collect::<Vec<_>>();
+    match is_compound {
+        true => KVal::CompoundList(reordered),
+        false => collapse_atoms(reordered),
+    }
+}
+
+/// Take the elements of a list of keys or values which are marked to be kept. A compound list stays a compound list
+///  and a simple list stays a simple list of the same type.
+fn select<'b>(list: &'b KVal, keep: &[bool]) -> KVal<'b> {
+    let elements = list
+        .iter()
+        .zip(keep)
+        .filter_map(|(element, keep)| keep.then_some(element))
+        .collect::<Vec<_>>();
+    match list {
+        KVal::CompoundList(_) => KVal::CompoundList(elements),
+        _ if elements.is_empty() => {
+            empty_column(list.qtype()).unwrap_or_else(|| KVal::CompoundList(Vec::new()))
+        }
+        _ => collapse_atoms(elements),
+    }
+}
+
+/// Build cells for values which have not been converted.
+fn cells<'a>(length: usize) -> Vec<OnceCell<KVal<'a>>> {
+    (0..length).map(|_| OnceCell::new()).collect()
+}
@@ -0,0 +1,1312 @@
+//! Rust representation of a q table and a q keyed table.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::super::{decrement_reference_count, flip, new_dictionary, KUtility, K, KNULL, S};
+use super::compound::collapse_atoms;
+use super::errors::{INDEX_OUT_OF_BOUNDS, LENGTH, NO_SUCH_COLUMN, TYPE};
+use super::join::KeyIndex;
+use super::kval::{as_slice, qtype_name, symbol_to_string};
+use super::null::prototype;
+use super::rows::empty_column;
+use super::{KData, KDict, KError, KVal, LazyDict};
+use crate::{qnull_base, qtype};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::OnceLock;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Rust representation of a q table, i.e., a flipped dictionary of a symbol list and a compound list of columns.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KTable<'a> {
+    /// Underlying dictionary of column names and columns.
+    pub dict: KDict<'a>,
+}
+
+/// Rust representation of a q table whose columns are converted on demand. Converting a wide table eagerly copies
+///  every symbol column into Rust strings even if a function reads only a few columns. Column names are converted
+///  when the table is built and each column is converted once when it is first read.
+#[derive(Debug)]
+pub struct LazyTable<'a> {
+    /// Underlying dictionary of column names and columns.
+    pub dict: LazyDict<'a>,
+}
+
+/// Rust representation of a q keyed table, i.e., a dictionary mapping a table of key columns to a table of value
+///  columns with the same number of rows.
+///
+/// [`KVal::from_raw`](enum.KVal.html#method.from_raw) builds a keyed table into `KVal::Dictionary` of two tables,
+///  which is converted into `KKeyedTable` with `TryFrom`.
+///
+/// The tables are borrowed through [`keys`](#method.keys) and [`values`](#method.values), so that the index of the
+///  rows by their keys, which [`lookup`](#method.lookup) and [`upsert`](#method.upsert) share, is dropped whenever
+///  the keys are borrowed mutably.
+#[derive(Clone, Debug)]
+pub struct KKeyedTable<'a> {
+    /// Table of key columns.
+    pub(super) keys: KTable<'a>,
+    /// Table of value columns.
+    pub(super) values: KTable<'a>,
+    /// Index of the rows by their keys, built on the first lookup or upsert and kept until the keys are borrowed
+    ///  mutably.
+    pub(super) index: OnceLock<KeyIndex>,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl<'a> KTable<'a> {
+    /// Build a table from a dictionary of column names and columns.
+    /// # Panics
+    /// In a debug build, if the columns have different lengths.
+    pub fn new(dict: KDict<'a>) -> Self {
+        if let KVal::CompoundList(columns) = dict.values.as_ref() {
+            debug_assert!(
+                columns
+                    .windows(2)
+                    .all(|pair| pair[0].len() == pair[1].len()),
+                "columns of a table must have the same length"
+            );
+        }
+        KTable { dict }
+    }
+
+    /// Build a table from pairs of a column name and a column in the order of the columns. The names make a symbol
+    ///  list and the columns a compound list even if there is only one column.
+    /// # Errors
+    /// - `InvalidOperation` wrapped with the name of the first column which is not a list.
+    /// - `LengthMismatch` wrapped with the name of the first column whose length differs from that of the first
+    ///   column.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let table = KTable::from_columns(vec![("sym", kval!(["a", "b"])), ("price", kval!([1.5, 2.5]))]).unwrap();
+    /// assert_eq!(table.len(), 2);
+    /// assert_eq!(table.column_names(), vec!["sym", "price"]);
+    ///
+    /// let error = KTable::from_columns(vec![("sym", kval!(["a", "b"])), ("size", kval!([100]))]).unwrap_err();
+    /// assert_eq!(error.to_string(), "size: length mismatch: 2 and 1");
+    /// ```
+    pub fn from_columns(columns: Vec<(&str, KVal<'a>)>) -> Result<Self, KError> {
+        let length = columns.first().map_or(0, |(_, column)| column.len());
+        for (name, column) in columns.iter() {
+            if !(0..qtype::TABLE).contains(&column.qtype()) {
+                let error = KError::invalid_operation(
+                    "from_columns",
+                    qtype_name(column.qtype()),
+                    Some("list"),
+                );
+                return Err(KError::wrap(name, error));
+            }
+            if column.len() != length {
+                return Err(KError::wrap(
+                    name,
+                    KError::length_mismatch(length, column.len()),
+                ));
+            }
+        }
+        let (names, columns): (Vec<String>, Vec<KVal<'a>>) = columns
+            .into_iter()
+            .map(|(name, column)| (name.to_string(), column))
+            .unzip();
+        Ok(KTable::new(KDict::new(
+            KVal::Symbol(KData::List(Cow::Owned(names))),
+            KVal::CompoundList(columns),
+        )))
+    }
+
+    /// Build a table without rows from pairs of a column name and a list type, e.g., `qtype::FLOAT_LIST`.
+    /// # Errors
+    /// `InvalidOperation` if the schema has an enum type or a type which is not a list type.
+    /// # Example
+    /// ```
+    /// use kdbplus::qtype;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let table = KTable::empty(&[("sym", qtype::SYMBOL_LIST), ("price", qtype::FLOAT_LIST)]).unwrap();
+    /// assert!(table.is_empty());
+    /// assert_eq!(table.get_column("price").unwrap().qtype(), qtype::FLOAT_LIST);
+    /// ```
+    pub fn empty(schema: &[(&str, i8)]) -> Result<KTable<'static>, KError> {
+        schema
+            .iter()
+            .map(|(name, qtype)| match empty_column(*qtype) {
+                Some(column) => Ok((*name, column)),
+                None => Err(KError::invalid_operation(
+                    "empty",
+                    qtype_name(*qtype),
+                    Some("simple list type except enum or compound list type"),
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(KTable::from_columns)
+    }
+
+    /// Build `KTable` from a q table. The reference count of the q object is not changed.
+    /// # Note
+    /// `table` must be a q table.
+    // `table` is a q object passed by q, which keeps it alive during the call, in the same manner as
+    //  `KVal::from_raw`.
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn from_raw(table: K) -> Self {
+        KTable::new(KDict::from_raw(unsafe { (*table).value.table }))
+    }
+
+    /// Build `KTable` from a q table in the same manner as [`from_raw`](#method.from_raw) checking the object first
+    ///  in every build, so that a function can reject an object which is not a table with a q error instead of
+    ///  reading it as a table.
+    /// # Errors
+    /// - `KError::InvalidOperation` naming the type of `table` if it is not a table, or naming `"table"` if it is
+    ///   not a flipped dictionary of a symbol list and a compound list of lists.
+    /// - `KError::LengthMismatch` if the number of columns does not match the number of names or the columns do not
+    ///   have the same length.
+    /// # Note
+    /// The underlying dictionary is read through the pointer held by `table`, which is checked only not to be null.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn count_rows(table: K) -> K{
+    ///   match KTable::try_from_raw(table){
+    ///     Ok(table) => new_long(table.len() as i64),
+    ///     Err(error) => error.to_k()
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)count_rows: `libapi_examples 2: (`count_rows; 1);
+    /// q)count_rows ([] x: til 3)
+    /// 3
+    /// q)count_rows til 3
+    /// 'invalid operation try_from_raw on long list. expected: table
+    /// ```
+    // `table` is a q object passed by q, which keeps it alive during the call, in the same manner as
+    //  `KVal::from_raw`.
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn try_from_raw(table: K) -> Result<Self, KError> {
+        let malformed = || {
+            KError::invalid_operation(
+                "try_from_raw",
+                "table",
+                Some("flipped dictionary of symbol list and lists"),
+            )
+        };
+        let qtype = if table.is_null() {
+            qtype::NULL
+        } else {
+            table.get_type()
+        };
+        if qtype != qtype::TABLE {
+            return Err(KError::invalid_operation(
+                "try_from_raw",
+                qtype_name(qtype),
+                Some("table"),
+            ));
+        }
+        let dictionary = unsafe { (*table).value.table };
+        if dictionary.is_null() || dictionary.get_type() != qtype::DICTIONARY {
+            return Err(malformed());
+        }
+        let pair = as_slice::<K>(dictionary);
+        if pair.iter().any(|k| k.is_null())
+            || pair[0].get_type() != qtype::SYMBOL_LIST
+            || pair[1].get_type() != qtype::COMPOUND_LIST
+        {
+            return Err(malformed());
+        }
+        let (names, columns) = (as_slice::<S>(pair[0]), as_slice::<K>(pair[1]));
+        if names.len() != columns.len() {
+            return Err(KError::LengthMismatch {
+                left: names.len(),
+                right: columns.len(),
+            });
+        }
+        if columns.iter().any(|column| {
+            column.is_null()
+                || !(qtype::COMPOUND_LIST..=qtype::ENUM_LIST).contains(&column.get_type())
+        }) {
+            return Err(malformed());
+        }
+        // A column is always a list.
+        let lengths = columns
+            .iter()
+            .map(|column| unsafe { (**column).value.list.n } as usize)
+            .collect::<Vec<_>>();
+        match lengths.iter().find(|length| **length != lengths[0]) {
+            Some(length) => Err(KError::LengthMismatch {
+                left: lengths[0],
+                right: *length,
+            }),
+            None => Ok(KTable::from_raw(table)),
+        }
+    }
+
+    /// Build a new q table. A q error is returned if the underlying dictionary cannot be converted or
+    ///  it does not form a table. The dictionary is never built as a sorted dictionary, which `flip` rejects, even if
+    ///  it is marked as sorted.
+    pub fn to_k(&self) -> K {
+        // `flip` takes over the dictionary and frees it if it does not form a table.
+        let dictionary = self.dict.build(false);
+        if dictionary.is_null() {
+            return KNULL;
+        }
+        flip(dictionary)
+    }
+
+    /// Get the number of rows. A table without columns has no row, and neither does a keyed table, whose values are
+    ///  not a compound list of columns.
+    pub fn len(&self) -> usize {
+        match self.dict.values.as_ref() {
+            KVal::CompoundList(columns) => columns.first().map_or(0, KVal::len),
+            _ => 0,
+        }
+    }
+
+    /// Check if the table has no row.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check that every column has as many rows as the table, so that each row can be got from every column.
+    /// # Errors
+    /// - `"length"` if a column has a different length.
+    pub(super) fn check_lengths(&self) -> Result<(), &'static str> {
+        let length = self.len();
+        match self.dict.values.as_ref() {
+            KVal::CompoundList(columns) if columns.iter().any(|column| column.len() != length) => {
+                Err(LENGTH)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Get the column of the name. `None` is returned if the column does not exist.
+    pub fn get_column(&self, name: &str) -> Option<KVal<'_>> {
+        self.dict.get(name)
+    }
+
+    /// Get the names of the columns borrowing the symbols of the table. An empty vector is returned if the column
+    ///  names are not a symbol list.
+    pub fn column_names(&self) -> Vec<&str> {
+        match self.dict.keys.as_ref() {
+            KVal::Symbol(KData::List(names)) => names.iter().map(String::as_str).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Get the index of the column of the name. The first match is returned if the name appears more than once.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        match self.dict.keys.as_ref() {
+            KVal::Symbol(KData::List(names)) => names.iter().position(|column| column == name),
+            _ => None,
+        }
+    }
+
+    /// Get the column of the name in the same manner as [`get_column`](#method.get_column) failing with the name of
+    ///  the column if it does not exist.
+    /// # Parameters
+    /// - `name`: Name of the column.
+    /// - `enum_source`: Name of the source of an enum column, which is not known for a table passed from q. It is
+    ///   ignored for a column of another type.
+    /// # Errors
+    /// - `KError::MissingColumns` naming the column if it does not exist.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn pick_column(table: K, name: K) -> K{
+    ///   let table = KTable::from_raw(table);
+    ///   match table.get_column_by_name(name.get_symbol().unwrap_or(""), Some("sym")){
+    ///     Ok(column) => column.to_k(),
+    ///     Err(error) => error.to_k()
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)pick_column: `libapi_examples 2: (`pick_column; 2);
+    /// q)sym: `a`b
+    /// q)pick_column[([] sym: `sym$`a`b`a; price: 1.5 2.5 3.5); `sym]
+    /// `sym$`a`b`a
+    /// q)pick_column[([] sym: `sym$`a`b`a; price: 1.5 2.5 3.5); `size]
+    /// 'missing columns: size
+    /// ```
+    pub fn get_column_by_name(
+        &self,
+        name: &str,
+        enum_source: Option<&str>,
+    ) -> Result<KVal<'_>, KError> {
+        match (self.get_column(name), enum_source) {
+            (Some(KVal::Enum(data, _)), Some(source)) => {
+                Ok(KVal::Enum(data, Some(source.to_string())))
+            }
+            (Some(column), _) => Ok(column),
+            (None, _) => Err(KError::MissingColumns(vec![name.to_string()])),
+        }
+    }
+
+    /// Get the element of a column at a row without building the row or the column.
+    /// - Simple list column returns the atom of the same type borrowing the element.
+    /// - String column returns `Char`.
+    /// - Compound list column returns a clone of the element, e.g., a string.
+    /// - Enum list column returns the enum atom of `enum_source`, which is not known for a table passed from q.
+    ///   The source of the column is kept if `enum_source` is `None`.
+    /// # Errors
+    /// - `"no such column"` if the column does not exist.
+    /// - `"index out of bounds"` if the row is negative or not less than the number of rows.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn cell(table: K, row: K, column: K) -> K{
+    ///   let table = KTable::from_raw(table);
+    ///   match table.get_cell(row.get_long().unwrap_or(-1), column.get_symbol().unwrap_or(""), Some("sym")){
+    ///     Ok(cell) => cell.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)cell: `libapi_examples 2: (`cell; 3);
+    /// q)sym: `a`b
+    /// q)table: ([] sym: `sym$`a`b; id: "xy"; note: ("first"; "second"))
+    /// q)cell[table; 1; `sym]
+    /// `sym$`b
+    /// q)cell[table; 1; `note]
+    /// "second"
+    /// q)cell[table; 2; `id]
+    /// 'index out of bounds
+    /// q)cell[table; 0; `size]
+    /// 'no such column
+    /// ```
+    pub fn get_cell(
+        &self,
+        row: i64,
+        column: &str,
+        enum_source: Option<&str>,
+    ) -> Result<KVal<'_>, &'static str> {
+        let column = match (self.column_index(column), self.dict.values.as_ref()) {
+            (Some(index), KVal::CompoundList(columns)) => &columns[index],
+            _ => return Err(NO_SUCH_COLUMN),
+        };
+        let cell = usize::try_from(row)
+            .ok()
+            .and_then(|row| column.get(row))
+            .ok_or(INDEX_OUT_OF_BOUNDS)?;
+        match (cell, enum_source) {
+            (KVal::Enum(data, _), Some(source)) => Ok(KVal::Enum(data, Some(source.to_string()))),
+            (cell, _) => Ok(cell),
+        }
+    }
+
+    /// Get a row in the same manner as [`get_row_with_sources`](#method.get_row_with_sources) giving sources to
+    ///  enum columns in the order the enum columns appear in the table. The source of a column is kept if its entry
+    ///  is `None` or missing.
+    /// # Errors
+    /// - `"index out of bounds"` if the index is negative or not less than the number of rows.
+    /// - `"length"` if the columns do not have the same length.
+    /// - `"type"` if the table is a keyed table.
+    #[deprecated(
+        note = "use `get_row_with_sources` which looks up sources by column name instead of by position"
+    )]
+    pub fn get_row(
+        &self,
+        index: i64,
+        enum_sources: &[Option<&str>],
+    ) -> Result<KVal<'_>, &'static str> {
+        let mut enum_sources = enum_sources.iter();
+        build_row(self, index, |_| enum_sources.next().copied().flatten())
+    }
+
+    /// Get a row as a dictionary from column names to cells, in the same manner as `table i` in q. The column names
+    ///  are borrowed and each cell is taken in the same manner as [`get_cell`](#method.get_cell), so only the cells
+    ///  of the row are copied.
+    /// # Parameters
+    /// - `index`: Index of the row.
+    /// - `sources`: Names of the sources of enum columns keyed by column name. The source of a column is kept if the
+    ///   column is not in the map.
+    /// # Errors
+    /// - `"index out of bounds"` if the index is negative or not less than the number of rows.
+    /// - `"length"` if the columns do not have the same length.
+    /// - `"type"` if the table is a keyed table.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::collections::HashMap;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn row_at(table: K, index: K) -> K{
+    ///   let table = match KTable::try_from_raw(table){
+    ///     Ok(table) => table,
+    ///     Err(error) => return error.to_k()
+    ///   };
+    ///   let sources = HashMap::from([("sym", "sym")]);
+    ///   match table.get_row_with_sources(index.get_long().unwrap_or(-1), &sources){
+    ///     Ok(row) => row.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)row_at: `libapi_examples 2: (`row_at; 2);
+    /// q)sym: `a`b
+    /// q)row_at[([] sym: `sym$`a`b; price: 1.5 2.5; note: ("first"; "second")); 1]
+    /// sym  | `sym$`b
+    /// price| 2.5
+    /// note | "second"
+    /// q)row_at[([] sym: `sym$`a`b; price: 1.5 2.5; note: ("first"; "second")); 2]
+    /// 'index out of bounds
+    /// q)row_at[`a`b; 0]
+    /// 'invalid operation try_from_raw on symbol list. expected: table
+    /// ```
+    pub fn get_row_with_sources(
+        &self,
+        index: i64,
+        sources: &HashMap<&str, &str>,
+    ) -> Result<KVal<'_>, &'static str> {
+        build_row(self, index, |name| sources.get(name).copied())
+    }
+
+    /// Get the column of the name in the same manner as [`get_column_by_name`](#method.get_column_by_name) looking
+    ///  up the source of an enum column by column name. The source of the column is kept if it is not in the map.
+    /// # Errors
+    /// - `KError::MissingColumns` naming the column if it does not exist.
+    pub fn get_column_with_sources(
+        &self,
+        name: &str,
+        sources: &HashMap<&str, &str>,
+    ) -> Result<KVal<'_>, KError> {
+        self.get_column_by_name(name, sources.get(name).copied())
+    }
+
+    /// Iterate over rows in parallel with `rayon`. Each row is a vector of the elements of the columns in the order
+    ///  of the columns, borrowing the elements in the same manner as [`KVal::get`](enum.KVal.html#method.get).
+    ///
+    /// Available only when `parallel` feature is enabled.
+    /// # Note
+    /// Do not call a function of the q C API inside the iteration as it is not thread-safe. Convert rows to q
+    ///  objects after collecting them on the main thread.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use rayon::prelude::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let table = KTable::new(KDict::new(
+    ///   KVal::Symbol(KData::List(Cow::Owned(vec!["price".to_string(), "size".to_string()]))),
+    ///   KVal::CompoundList(vec![
+    ///     KVal::Float(KData::List(Cow::Owned(vec![1.5, 2.5, 3.5]))),
+    ///     KVal::Long(KData::List(Cow::Owned(vec![100, 200, 300])))
+    ///   ])
+    /// ));
+    /// let notional = table.par_rows().map(|row| match (&row[0], &row[1]){
+    ///   (KVal::Float(KData::Atom(price)), KVal::Long(KData::Atom(size))) => **price * **size as f64,
+    ///   _ => unreachable!()
+    /// }).sum::<f64>();
+    /// assert_eq!(notional, 1700.0);
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn par_rows(&self) -> impl IndexedParallelIterator<Item = Vec<KVal<'_>>> {
+        let columns = match self.dict.values.as_ref() {
+            KVal::CompoundList(columns) => columns.as_slice(),
+            _ => &[],
+        };
+        (0..self.len()).into_par_iter().map(move |row| {
+            columns
+                .iter()
+                .map(|column| column.get(row).unwrap_or(KVal::Null))
+                .collect()
+        })
+    }
+
+    /// Copy the table into a new table which owns all of its data. See
+    ///  [`KVal::deep_clone`](enum.KVal.html#method.deep_clone).
+    pub fn deep_clone(&self) -> KTable<'static> {
+        KTable::new(self.dict.deep_clone())
+    }
+
+    /// Get a table of the specified columns and rows. Simple list columns of the result borrow the elements of this
+    ///  table, so that only the requested part is touched when the result is converted to another format.
+    /// # Parameters
+    /// - `columns`: Names of the columns in the order of the result. All columns are selected if `None`.
+    /// - `rows`: Range of row indices. All rows are selected if `None`.
+    /// # Errors
+    /// - Some of `columns` do not exist. The error names all missing columns.
+    /// - `rows` is out of bounds of the table.
+    /// - The table is not a dictionary of a symbol list and a compound list.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let table = KTable::new(KDict::new(
+    ///   KVal::Symbol(KData::List(Cow::Owned(vec!["sym".to_string(), "price".to_string(), "size".to_string()]))),
+    ///   KVal::CompoundList(vec![
+    ///     KVal::Symbol(KData::List(Cow::Owned(vec!["a".to_string(), "b".to_string(), "c".to_string()]))),
+    ///     KVal::Float(KData::List(Cow::Owned(vec![1.5, 2.5, 3.5]))),
+    ///     KVal::Long(KData::List(Cow::Owned(vec![100, 200, 300])))
+    ///   ])
+    /// ));
+    /// let projected = table.project(Some(&["size", "sym"]), Some(1..3)).unwrap();
+    /// assert_eq!(projected.len(), 2);
+    /// assert_eq!(projected.dict.len(), 2);
+    ///
+    /// let error = table.project(Some(&["sym", "bid", "ask"]), None).unwrap_err();
+    /// assert_eq!(error.to_string(), "missing columns: bid, ask");
+    /// ```
+    pub fn project(
+        &self,
+        columns: Option<&[&str]>,
+        rows: Option<Range<i64>>,
+    ) -> Result<KTable<'_>, KError> {
+        let (names, values) = match (self.dict.keys.as_ref(), self.dict.values.as_ref()) {
+            (KVal::Symbol(KData::List(names)), KVal::CompoundList(values)) => (names, values),
+            _ => {
+                return Err(KError::invalid_operation(
+                    "project",
+                    "dictionary",
+                    Some("table"),
+                ))
+            }
+        };
+        let indices = match columns {
+            Some(columns) => {
+                let (found, missing): (Vec<_>, Vec<_>) = columns
+                    .iter()
+                    .map(|column| (column, names.iter().position(|name| name == column)))
+                    .partition(|(_, index)| index.is_some());
+                if !missing.is_empty() {
+                    return Err(KError::MissingColumns(
+                        missing
+                            .into_iter()
+                            .map(|(column, _)| column.to_string())
+                            .collect(),
+                    ));
+                }
+                found.into_iter().filter_map(|(_, index)| index).collect()
+            }
+            None => (0..names.len()).collect::<Vec<_>>(),
+        };
+        let length = self.len();
+        let range = match rows {
+            Some(rows) if rows.start < 0 || rows.start > rows.end => {
+                return Err(KError::index_out_of_bounds(
+                    rows.start.max(0) as usize,
+                    length,
+                ))
+            }
+            Some(rows) if rows.end as usize > length => {
+                return Err(KError::index_out_of_bounds(rows.end as usize, length))
+            }
+            Some(rows) => rows.start as usize..rows.end as usize,
+            None => 0..length,
+        };
+        let columns = indices
+            .iter()
+            .map(|index| {
+                values[*index]
+                    .slice(range.clone())
+                    .map_err(|_| KError::index_out_of_bounds(range.end, values[*index].len()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(KTable::new(KDict::new(
+            KVal::Symbol(KData::List(Cow::Owned(
+                indices.iter().map(|index| names[*index].clone()).collect(),
+            ))),
+            KVal::CompoundList(columns),
+        )))
+    }
+
+    /// Get a table of the rows in the specified range in the same manner as [`project`](#method.project) with all
+    ///  columns, e.g., a page of a table. Simple list columns borrow the elements of this table and enum columns
+    ///  carry the same source.
+    /// # Errors
+    /// - `"index out of bounds"` if the range is out of bounds of the table or decreasing.
+    /// - `"type"` if the table is a keyed table.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn page(table: K, start: K, size: K) -> K{
+    ///   match (start.get_long(), size.get_long()){
+    ///     (Ok(start), Ok(size)) if start >= 0 && size >= 0 => {
+    ///       let table = KTable::from_raw(table);
+    ///       let end = table.len().min((start + size) as usize);
+    ///       match table.get_rows(start as usize .. end){
+    ///         Ok(page) => page.to_k(),
+    ///         Err(error) => new_error(error)
+    ///       }
+    ///     },
+    ///     _ => new_error("start and size must be non-negative longs\0")
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)page: `libapi_examples 2: (`page; 3);
+    /// q)page[([] sym: `a`b`c; price: 1.5 2.5 3.5); 1; 5]
+    /// sym price
+    /// ---------
+    /// b   2.5
+    /// c   3.5
+    /// ```
+    pub fn get_rows(&self, range: Range<usize>) -> Result<KTable<'_>, &'static str> {
+        if range.start > range.end || range.end > self.len() {
+            return Err(INDEX_OUT_OF_BOUNDS);
+        }
+        self.project(None, Some(range.start as i64..range.end as i64))
+            .map_err(|_| TYPE)
+    }
+
+    /// Get a table of the rows at the specified indices in the same manner as `table indices` in q. A row may be
+    ///  picked more than once, and an index out of bounds, including a negative index, picks a row of nulls. Bool
+    ///  and byte columns, which have no null, are filled with `0b` and `0x00`.
+    /// # Errors
+    /// - `"type"` if the table is a keyed table.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let table = KTable::from_columns(vec![("sym", kval!(["a", "b", "c"])), ("size", kval!([1, 2, 3]))]).unwrap();
+    /// let picked = table.take_rows(&[2, 0, 5]).unwrap();
+    /// assert_eq!(picked.get_column("sym").unwrap(), kval!(["c", "a", ""]));
+    /// ```
+    pub fn take_rows(&self, indices: &[i64]) -> Result<KTable<'_>, &'static str> {
+        match (self.dict.keys.as_ref(), self.dict.values.as_ref()) {
+            (KVal::Symbol(KData::List(names)), KVal::CompoundList(columns)) => {
+                Ok(KTable::new(KDict::new(
+                    KVal::Symbol(KData::List(Cow::Borrowed(names.as_ref()))),
+                    KVal::CompoundList(
+                        columns
+                            .iter()
+                            .map(|column| pick_rows(column, indices))
+                            .collect(),
+                    ),
+                )))
+            }
+            _ => Err(TYPE),
+        }
+    }
+
+    /// Get a table of the rows at the specified indices in the same manner as `table where ...` in q, e.g., indices
+    ///  computed over a column without building any row. Unlike [`take_rows`](#method.take_rows), every index must be
+    ///  within the table.
+    /// # Errors
+    /// - `"index out of bounds"` if any index is negative or not less than the number of rows.
+    /// - `"type"` if the table is a keyed table.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn price_above(table: K, threshold: K) -> K{
+    ///   let table = KTable::from_raw(table);
+    ///   let (prices, threshold) = match (table.get_column("price"), threshold.get_float()){
+    ///     (Some(KVal::Float(prices)), Ok(threshold)) => (prices, threshold),
+    ///     _ => return new_error("expected a float price column and a float\0")
+    ///   };
+    ///   let indices = prices.iter().enumerate()
+    ///     .filter_map(|(i, price)| (*price > threshold).then_some(i as i64))
+    ///     .collect::<Vec<_>>();
+    ///   match table.where_indices(&indices){
+    ///     Ok(selected) => selected.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)price_above: `libapi_examples 2: (`price_above; 2);
+    /// q)price_above[([] sym: `a`b`c; price: 1.5 2.5 3.5); 2f]
+    /// sym price
+    /// ---------
+    /// b   2.5
+    /// c   3.5
+    /// ```
+    pub fn where_indices(&self, indices: &[i64]) -> Result<KTable<'_>, &'static str> {
+        let rows = 0..self.len() as i64;
+        match indices.iter().all(|index| rows.contains(index)) {
+            true => self.take_rows(indices),
+            false => Err(INDEX_OUT_OF_BOUNDS),
+        }
+    }
+
+    /// Get a table of the rows for which the predicate returns `true`. Each row is passed as a dictionary of the
+    ///  column names and the elements of the row in the same manner as `table i` in q, which borrows the elements
+    ///  of simple columns. Use [`where_indices`](#method.where_indices) to select rows by a column without building
+    ///  a dictionary per row.
+    /// # Errors
+    /// - `"type"` if the table is a keyed table.
+    /// - `"length"` if the columns do not have the same length.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let table = KTable::from_columns(vec![
+    ///   ("sym", kval!(["a", "b", "c"])),
+    ///   ("price", kval!([1.5, 2.5, 3.5]))
+    /// ]).unwrap();
+    /// let selected = table.filter(|row| match row{
+    ///   KVal::Dictionary(row) => row.get_float("price") > Ok(2.0),
+    ///   _ => false
+    /// }).unwrap();
+    /// assert_eq!(selected.get_column("sym").unwrap(), kval!(["b", "c"]));
+    /// ```
+    pub fn filter(&self, predicate: impl Fn(&KVal) -> bool) -> Result<KTable<'_>, &'static str> {
+        let (names, columns) = match (self.dict.keys.as_ref(), self.dict.values.as_ref()) {
+            (KVal::Symbol(KData::List(names)), KVal::CompoundList(columns)) => (names, columns),
+            _ => return Err(TYPE),
+        };
+        self.check_lengths()?;
+        let rows = (0..self.len())
+            .filter(|row| {
+                let cells = columns
+                    .iter()
+                    .map(|column| {
+                        column
+                            .get(*row)
+                            .unwrap_or_else(|| unreachable!("columns have the same length"))
+                    })
+                    .collect();
+                predicate(&KVal::Dictionary(KDict::new(
+                    KVal::Symbol(KData::List(Cow::Borrowed(names.as_ref()))),
+                    collapse_atoms(cells),
+                )))
+            })
+            .map(|row| row as i64)
+            .collect::<Vec<_>>();
+        self.take_rows(&rows)
+    }
+
+    /// Turn the table into a keyed table whose key columns are the first `n` columns in the same manner as
+    ///  `n!table` in q.
+    /// # Errors
+    /// - `"length"` if `n` is 0 or not less than the number of columns.
+    /// - `"type"` if the table is a keyed table.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let table = KTable::from_columns(vec![("sym", kval!(["a", "b"])), ("price", kval!([1.5, 2.5]))]).unwrap();
+    /// let keyed = table.clone().enkey(1).unwrap();
+    /// assert_eq!(keyed.keys().column_names(), vec!["sym"]);
+    /// assert_eq!(keyed.lookup(&kval!("b")), Some(1));
+    /// assert_eq!(keyed.unkey(), table);
+    /// ```
+    pub fn enkey(self, n: usize) -> Result<KKeyedTable<'a>, &'static str> {
+        if !matches!(
+            (self.dict.keys.as_ref(), self.dict.values.as_ref()),
+            (KVal::Symbol(KData::List(_)), KVal::CompoundList(_))
+        ) {
+            return Err(TYPE);
+        }
+        let (mut names, mut columns) = into_parts(self);
+        if n == 0 || n >= columns.len() {
+            return Err(LENGTH);
+        }
+        let value_names = names.split_off(n);
+        let value_columns = columns.split_off(n);
+        Ok(KKeyedTable::new(
+            from_parts(names, columns),
+            from_parts(value_names, value_columns),
+        ))
+    }
+}
+
+impl<'a> KKeyedTable<'a> {
+    /// Build a keyed table from a table of key columns and a table of value columns.
+    /// # Panics
+    /// In a debug build, if the tables have different numbers of rows.
+    pub fn new(keys: KTable<'a>, values: KTable<'a>) -> Self {
+        debug_assert_eq!(
+            keys.len(),
+            values.len(),
+            "keys and values of a keyed table must have the same number of rows"
+        );
+        KKeyedTable {
+            keys,
+            values,
+            index: OnceLock::new(),
+        }
+    }
+
+    /// Borrow the table of key columns.
+    pub fn keys(&self) -> &KTable<'a> {
+        &self.keys
+    }
+
+    /// Borrow the table of value columns.
+    pub fn values(&self) -> &KTable<'a> {
+        &self.values
+    }
+
+    /// Borrow the table of key columns mutably. The index of the rows by their keys is dropped and built again on
+    ///  the next [`lookup`](#method.lookup) or [`upsert`](#method.upsert).
+    pub fn keys_mut(&mut self) -> &mut KTable<'a> {
+        self.index = OnceLock::new();
+        &mut self.keys
+    }
+
+    /// Borrow the table of value columns mutably. The keys of the rows must be kept in the same number of rows.
+    pub fn values_mut(&mut self) -> &mut KTable<'a> {
+        &mut self.values
+    }
+
+    /// Build `KKeyedTable` from a q keyed table. The reference count of the q object is not changed.
+    /// # Note
+    /// `keyed_table` must be a q keyed table.
+    pub fn from_raw(keyed_table: K) -> Self {
+        let pair = as_slice::<K>(keyed_table);
+        KKeyedTable::new(KTable::from_raw(pair[0]), KTable::from_raw(pair[1]))
+    }
+
+    /// Build a new q keyed table. A q error is returned if either of the tables cannot be converted (see
+    ///  [`KTable::to_k`](struct.KTable.html#method.to_k)).
+    pub fn to_k(&self) -> K {
+        let keys = self.keys.to_k();
+        if keys.is_null() {
+            return KNULL;
+        }
+        let values = self.values.to_k();
+        if values.is_null() {
+            decrement_reference_count(keys);
+            return KNULL;
+        }
+        new_dictionary(keys, values)
+    }
+
+    /// Get the number of rows.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Check if the keyed table has no row.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Turn the keyed table into a table of the key columns followed by the value columns in the same manner as
+    ///  `0!keyed` in q.
+    pub fn unkey(self) -> KTable<'a> {
+        let (mut names, mut columns) = into_parts(self.keys);
+        let (value_names, value_columns) = into_parts(self.values);
+        names.extend(value_names);
+        columns.extend(value_columns);
+        from_parts(names, columns)
+    }
+
+    /// Find the first row whose keys equal the specified keys in the same manner as `(key keyed)?key` in q. Keys are
+    ///  compared by `==` of `KVal`.
+    ///
+    /// The rows are found through a hash index of the keys, which is built on the first lookup or upsert and kept in
+    ///  the keyed table, so that a lookup costs O(1). `None` is returned if the key columns do not have the same
+    ///  length or the keys and the values do not have the same number of rows.
+    /// # Parameters
+    /// - `key`: Keys of a row as a dictionary from the names of the key columns, as a list in the order of the key
+    ///   columns, or as an atom for a keyed table of one key column.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn find_key(keyed: K, key: K) -> K{
+    ///   match KKeyedTable::from_raw(keyed).lookup(&KVal::from_raw(key)){
+    ///     Some(row) => new_long(row as i64),
+    ///     None => new_long(-1)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)find_key: `libapi_examples 2: (`find_key; 2);
+    /// q)find_key[([sym: `a`b; id: 1 2] price: 1.5 2.5); (`b; 2)]
+    /// 1
+    /// q)find_key[([sym: `a`b; id: 1 2] price: 1.5 2.5); `sym`id!(`a; 2)]
+    /// -1
+    /// ```
+    pub fn lookup(&self, key: &KVal) -> Option<usize> {
+        let (names, columns) = match (self.keys.dict.keys.as_ref(), self.keys.dict.values.as_ref())
+        {
+            (KVal::Symbol(KData::List(names)), KVal::CompoundList(columns)) => (names, columns),
+            _ => return None,
+        };
+        let key = match key {
+            KVal::Dictionary(dictionary) => names
+                .iter()
+                .map(|name| dictionary.get(name))
+                .collect::<Option<Vec<_>>>()?,
+            key => key.iter().collect(),
+        };
+        if key.len() != columns.len() {
+            return None;
+        }
+        self.key_index().ok()?.find(&key)
+    }
+}
+
+impl<'a> TryFrom<KVal<'a>> for KKeyedTable<'a> {
+    type Error = &'static str;
+
+    /// Take a dictionary of two tables as a keyed table.
+    /// # Errors
+    /// - `"type"` if the value is not a dictionary whose keys and values are tables.
+    fn try_from(value: KVal<'a>) -> Result<Self, Self::Error> {
+        match value {
+            KVal::Dictionary(KDict { keys, values, .. }) => match (*keys, *values) {
+                (KVal::Table(keys), KVal::Table(values)) => Ok(KKeyedTable::new(keys, values)),
+                _ => Err(TYPE),
+            },
+            _ => Err(TYPE),
+        }
+    }
+}
+
+impl PartialEq for KKeyedTable<'_> {
+    /// Compare the keys and the values. The index of the rows is not compared.
+    fn eq(&self, other: &Self) -> bool {
+        self.keys == other.keys && self.values == other.values
+    }
+}
+
+impl<'a> From<KKeyedTable<'a>> for KVal<'a> {
+    /// Build a dictionary of two tables in the same shape as [`KVal::from_raw`](enum.KVal.html#method.from_raw)
+    ///  builds a keyed table.
+    fn from(keyed: KKeyedTable<'a>) -> Self {
+        KVal::Dictionary(KDict::new(
+            KVal::Table(keyed.keys),
+            KVal::Table(keyed.values),
+        ))
+    }
+}
+
+impl<'a> LazyTable<'a> {
+    /// Build `LazyTable` from a q table converting only the column names. The reference count of the q object is
+    ///  not changed.
+    /// # Note
+    /// `table` must be a q table.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn lazy_column(table: K, name: K) -> K{
+    ///   let table = LazyTable::from_raw(table);
+    ///   match table.get_column(name.get_symbol().unwrap_or("")){
+    ///     Some(column) => column.to_k(),
+    ///     None => new_error("no such column\0")
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)lazy_column: `libapi_examples 2: (`lazy_column; 2);
+    /// q)lazy_column[([] sym: `a`b; price: 1.5 2.5); `price]
+    /// 1.5 2.5
+    /// ```
+    // `table` is a q object passed by q, which keeps it alive during the call, in the same manner as
+    //  `KVal::from_raw`.
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn from_raw(table: K) -> Self {
+        LazyTable {
+            dict: LazyDict::from_raw(unsafe { (*table).value.table }),
+        }
+    }
+
+    /// Get the number of rows without converting any column. A table without columns has no row.
+    pub fn len(&self) -> usize {
+        self.dict.value_len(0).unwrap_or(0)
+    }
+
+    /// Check if the table has no row.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the column of the name converting it if it has not been read. `None` is returned if the column does not
+    ///  exist.
+    pub fn get_column(&self, name: &str) -> Option<&KVal<'a>> {
+        self.dict.get(name)
+    }
+
+    /// Get the column at the index converting it if it has not been read. `None` is returned if the index is out of
+    ///  bounds.
+    pub fn column_at(&self, index: usize) -> Option<&KVal<'a>> {
+        self.dict.value_at(index)
+    }
+
+    /// Get the number of columns which have been converted.
+    pub fn converted(&self) -> usize {
+        self.dict.converted()
+    }
+
+    /// Convert the remaining columns and build `KTable`.
+    pub fn into_table(self) -> KTable<'a> {
+        KTable::new(self.dict.into_dict())
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Public Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Get the number of rows of a q table or a keyed table without building `KTable`. Only the length of the first
+///  column is read, so that a function can reject a large table before converting it.
+/// # Errors
+/// `k` is neither a table nor a keyed table.
+/// # Example
+/// ```no_run
+/// use kdbplus::api::*;
+/// use kdbplus::api::rusty_api::*;
+///
+/// #[no_mangle]
+/// pub extern "C" fn small_table_only(table: K) -> K{
+///   match peek_table_rows(table){
+///     Ok(rows) if rows > 10_000_000 => new_error("table is too large\0"),
+///     Ok(_) => increment_reference_count(table),
+///     Err(error) => error.to_k()
+///   }
+/// }
+/// ```
+/// ```q
+/// q)small_table_only: `libapi_examples 2: (`small_table_only; 1);
+/// q)small_table_only ([] x: til 3)
+/// x
+/// -
+/// 0
+/// 1
+/// 2
+/// q)small_table_only til 3
+/// 'invalid operation peek_table_rows on long list. expected: table
+/// ```
+pub fn peek_table_rows(k: K) -> Result<i64, KError> {
+    let tables = tables(k, "peek_table_rows")?;
+    let (_, columns) = table_parts(tables[0]);
+    // A column is always a list.
+    Ok(columns
+        .first()
+        .map_or(0, |column| unsafe { (**column).value.list.n }))
+}
+
+/// Get the names and the type codes of the columns of a q table or a keyed table without building `KTable`. Key
+///  columns of a keyed table come first.
+/// # Errors
+/// `k` is neither a table nor a keyed table.
+/// # Example
+/// ```no_run
+/// use kdbplus::api::*;
+/// use kdbplus::api::rusty_api::*;
+/// use std::borrow::Cow;
+///
+/// #[no_mangle]
+/// pub extern "C" fn column_types(table: K) -> K{
+///   match peek_table_schema(table){
+///     Ok(schema) => {
+///       let types = schema.iter().map(|(_, qtype)| *qtype as i16).collect::<Vec<_>>();
+///       KVal::Short(KData::List(Cow::Owned(types))).to_k()
+///     },
+///     Err(error) => error.to_k()
+///   }
+/// }
+/// ```
+/// ```q
+/// q)column_types: `libapi_examples 2: (`column_types; 1);
+/// q)column_types ([sym: `a`b] price: 1.5 2.5; size: 100 200)
+/// 11 9 7h
+/// ```
+pub fn peek_table_schema(k: K) -> Result<Vec<(String, i8)>, KError> {
+    Ok(tables(k, "peek_table_schema")?
+        .into_iter()
+        .flat_map(|table| {
+            let (names, columns) = table_parts(table);
+            names
+                .iter()
+                .zip(columns.iter())
+                .map(|(name, column)| (symbol_to_string(*name), column.get_type()))
+        })
+        .collect())
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Build a row of a table as a dictionary from column names to cells. `source_of` is called with the name of each
+///  enum column in order and the source of the column is kept if it returns `None`.
+fn build_row<'t, 'b>(
+    table: &'t KTable,
+    index: i64,
+    mut source_of: impl FnMut(&str) -> Option<&'b str>,
+) -> Result<KVal<'t>, &'static str> {
+    let (names, columns) = match (table.dict.keys.as_ref(), table.dict.values.as_ref()) {
+        (KVal::Symbol(KData::List(names)), KVal::CompoundList(columns)) => (names, columns),
+        _ => return Err(TYPE),
+    };
+    let index = usize::try_from(index)
+        .ok()
+        .filter(|index| *index < table.len())
+        .ok_or(INDEX_OUT_OF_BOUNDS)?;
+    let cells = names
+        .iter()
+        .zip(columns.iter())
+        .map(|(name, column)| match column.get(index)? {
+            KVal::Enum(data, source) => match source_of(name) {
+                Some(name) => Some(KVal::Enum(data, Some(name.to_string()))),
+                None => Some(KVal::Enum(data, source)),
+            },
+            cell => Some(cell),
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or(LENGTH)?;
+    Ok(KVal::Dictionary(KDict::new(
+        KVal::Symbol(KData::List(Cow::Borrowed(names.as_ref()))),
+        collapse_atoms(cells),
+    )))
+}
+
+/// Get the tables forming a table (itself) or a keyed table (keys and values).
+fn tables(k: K, operator: &'static str) -> Result<Vec<K>, KError> {
+    let qtype = if k.is_null() {
+        qtype::NULL
+    } else {
+        k.get_type()
+    };
+    match qtype {
+        qtype::TABLE => Ok(vec![k]),
+        qtype::DICTIONARY | qtype::SORTED_DICTIONARY => {
+            let pair = as_slice::<K>(k);
+            if pair[0].get_type() == qtype::TABLE && pair[1].get_type() == qtype::TABLE {
+                Ok(pair.to_vec())
+            } else {
+                Err(KError::invalid_operation(
+                    operator,
+                    "dictionary",
+                    Some("table"),
+                ))
+            }
+        }
+        _ => Err(KError::invalid_operation(
+            operator,
+            qtype_name(qtype),
+            Some("table"),
+        )),
+    }
+}
+
+/// Borrow the column names and the columns of a table.
+fn table_parts<'a>(table: K) -> (&'a [S], &'a [K]) {
+    let pair = as_slice::<K>(unsafe { (*table).value.table });
+    (as_slice::<S>(pair[0]), as_slice::<K>(pair[1]))
+}
+
+/// Pick the elements of a column at the indices. An index out of bounds picks the null of the column in the manner
+///  of `prototype`.
+pub(super) fn pick_rows<'b>(column: &'b KVal, indices: &[i64]) -> KVal<'b> {
+    macro_rules! pick {
+        ($data: expr, $variant: path, $null: expr) => {
+            $variant(pick_data($data, indices, $null))
+        };
+    }
+    match column {
+        KVal::CompoundList(list) => {
+            let null = prototype(column);
+            KVal::CompoundList(
+                indices
+                    .iter()
+                    .map(|index| match position(*index, list.len()) {
+                        Some(index) => list[index].clone(),
+                        None => null.clone(),
+                    })
+                    .collect(),
+            )
+        }
+        KVal::Bool(data) => pick!(data, KVal::Bool, false),
+        KVal::Guid(data) => pick!(data, KVal::Guid, qnull_base::U),
+        KVal::Byte(data) => pick!(data, KVal::Byte, 0),
+        KVal::Short(data) => pick!(data, KVal::Short, qnull_base::H),
+        KVal::Int(data) => pick!(data, KVal::Int, qnull_base::I),
+        KVal::Long(data) => pick!(data, KVal::Long, qnull_base::J),
+        KVal::Real(data) => pick!(data, KVal::Real, qnull_base::E),
+        KVal::Float(data) => pick!(data, KVal::Float, qnull_base::F),
+        KVal::Symbol(data) => pick!(data, KVal::Symbol, String::new()),
+        KVal::String(string) => KVal::String(Cow::Owned(
+            indices
+                .iter()
+                .map(|index| match position(*index, string.len()) {
+                    Some(index) => string[index],
+                    None => qnull_base::C as u8,
+                })
+                .collect(),
+        )),
+        KVal::Timestamp(data) => pick!(data, KVal::Timestamp, qnull_base::J),
+        KVal::Month(data) => pick!(data, KVal::Month, qnull_base::I),
+        KVal::Date(data) => pick!(data, KVal::Date, qnull_base::I),
+        KVal::Datetime(data) => pick!(data, KVal::Datetime, qnull_base::F),
+        KVal::Timespan(data) => pick!(data, KVal::Timespan, qnull_base::J),
+        KVal::Minute(data) => pick!(data, KVal::Minute, qnull_base::I),
+        KVal::Second(data) => pick!(data, KVal::Second, qnull_base::I),
+        KVal::Time(data) => pick!(data, KVal::Time, qnull_base::I),
+        KVal::Enum(data, source) => {
+            KVal::Enum(pick_data(data, indices, qnull_base::J), source.clone())
+        }
+        other => other.clone(),
+    }
+}
+
+/// Pick the elements of data at the indices into an owned list, substituting `null` for an index out of bounds.
+fn pick_data<T: Clone>(data: &KData<'_, T>, indices: &[i64], null: T) -> KData<'static, T> {
+    let list = data.as_slice();
+    KData::List(Cow::Owned(
+        indices
+            .iter()
+            .map(|index| match position(*index, list.len()) {
+                Some(index) => list[index].clone(),
+                None => null.clone(),
+            })
+            .collect(),
+    ))
+}
+
+/// Convert an index into a position in a list of the length. Returns `None` if the index is out of bounds.
+fn position(index: i64, length: usize) -> Option<usize> {
+    usize::try_from(index).ok().filter(|index| *index < length)
+}
+
+/// Take the column names and the columns of a table. A table which is not a dictionary of a symbol list and a compound
+///  list has no column.
+fn into_parts(table: KTable<'_>) -> (Vec<String>, Vec<KVal<'_>>) {
+    match (*table.dict.keys, *table.dict.values) {
+        (KVal::Symbol(KData::List(names)), KVal::CompoundList(columns)) => {
+            (names.into_owned(), columns)
+        }
+        _ => (Vec::new(), Vec::new()),
+    }
+}
+
+/// Build a table from column names and columns of the same length.
+pub(super) fn from_parts<'a>(names: Vec<String>, columns: Vec<KVal<'a>>) -> KTable<'a> {
+    KTable::new(KDict::new(
+        KVal::Symbol(KData::List(Cow::Owned(names))),
+        KVal::CompoundList(columns),
+    ))
+}
@@ -0,0 +1,1179 @@
+//! Rust representation of a q object. [`KVal`](enum.KVal.html) is built from a raw `K` object with
+//!  [`from_raw`](enum.KVal.html#method.from_raw) and converted back with [`to_k`](enum.KVal.html#method.to_k).
+//!
+//! Simple lists built by `from_raw` borrow the underlying buffer of the `K` object instead of copying it. Therefore
+//!  the `K` object must be alive while the `KVal` is used. A modification of a borrowed list copies it first and
+//!  never changes the `K` object. Return a new object with `to_k` or write the modification back into the original
+//!  object with [`commit`](enum.KVal.html#method.commit).
+//!
+//! # Example
+//! ```no_run
+//! use kdbplus::api::*;
+//! use kdbplus::api::rusty_api::*;
+//! use std::borrow::Cow;
+//!
+//! #[no_mangle]
+//! pub extern "C" fn double_it(list: K) -> K{
+//!   match KVal::from_raw(list){
+//!     KVal::Long(KData::List(list)) => {
+//!       let doubled = list.iter().map(|long| long * 2).collect::<Vec<_>>();
+//!       KVal::Long(KData::List(Cow::Owned(doubled))).to_k()
+//!     },
+//!     _ => new_error("not a long list\0")
+//!   }
+//! }
+//! ```
+//! ```q
+//! q)double_it: `libapi_examples 2: (`double_it; 1);
+//! q)double_it 1 2 3
+//! 2 4 6
+//! ```
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::super::{
+    decrement_reference_count, enumerate_n, native, new_bool, new_byte_wrapping, new_date,
+    new_datetime, new_enum, new_error, new_float, new_guid, new_int, new_list, new_long,
+    new_minute, new_month, new_null, new_real32, new_second, new_short_wrapping, new_time,
+    new_timespan, new_timestamp, KUtility, C, G, I, K, KNULL, S,
+};
+use super::error::error_to_k;
+use super::errors;
+use super::limit::q_length;
+#[cfg(feature = "parallel")]
+use super::parallel;
+use super::{KDict, KTable, LazyDict, LazyTable};
+use crate::qtype;
+use std::borrow::Cow;
+use std::ffi::{CStr, CString};
+use std::iter::FusedIterator;
+use std::ops::Range;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Error of a symbol which cannot be interned.
+pub(super) const NULL_IN_SYMBOL: &str = "symbol contains a null character\0";
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Value of a q object which can be either an atom or a simple list.
+#[derive(Clone, Debug)]
+pub enum KData<'a, T>
+where
+    T: Clone,
+{
+    /// Atom.
+    Atom(Cow<'a, T>),
+    /// Simple list.
+    List(Cow<'a, [T]>),
+}
+
+/// Rust representation of a q object.
+/// # Note
+/// - Temporal values are held as the raw values stored in q, e.g., nanoseconds since `2000.01.01D00:00:00` for timestamp.
+/// - Bool list is converted into `bool` element by element instead of borrowing the byte buffer of q.
+/// - Symbol is converted into an owned `String`.
+/// - Char is a byte in q. `Char` holds the char whose code point equals to the byte.
+/// - String is held as the bytes of the char list, which need not be a valid UTF-8 sequence. An element of a string
+///   is a byte in the same manner as q, and a char of a string element is the char whose code point equals to the
+///   byte.
+/// - Real is held as `f32` as stored in q. It is never converted through `f64`.
+/// # Comparison
+/// `KVal` is compared in the manner of q rather than of IEEE754:
+/// - Two float or real nulls (`NaN`) are equal and a null is less than any other value including the negative
+///   infinity.
+/// - GUIDs are compared byte by byte and symbols as strings.
+/// - Compound lists, dictionaries and tables are compared element by element.
+/// - Enums are equal only if both the indices and the sources are equal.
+/// - An atom is never equal to a list and values of different types are never equal nor ordered. Values other
+///   than atoms and lists of the same type, and compound lists of them, have no order.
+/// # Hash
+/// `KVal` implements `Eq` and `Hash` consistently with the comparison above and can be a key of `HashMap`.
+/// - Float and real atoms are hashed by their bits, except that every null is hashed alike and so are `0.0` and
+///   `-0.0`.
+/// - A temporal value is hashed apart from its underlying type and an enum together with its source.
+/// - Lists, dictionaries and tables are hashed element by element. Prefer atoms as keys where possible.
+///
+/// ```
+/// use kdbplus::api::rusty_api::*;
+/// use std::borrow::Cow;
+/// use std::collections::HashMap;
+///
+/// let mut counts = HashMap::new();
+/// for price in [1.5, f64::NAN, 1.5, -f64::NAN]{
+///   *counts.entry(KVal::Float(KData::Atom(Cow::Owned(price)))).or_insert(0) += 1;
+/// }
+/// assert_eq!(counts[&KVal::Float(KData::Atom(Cow::Owned(f64::NAN)))], 2);
+/// assert_eq!(counts[&KVal::Float(KData::Atom(Cow::Owned(1.5)))], 2);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum KVal<'a> {
+    /// Compound list.
+    CompoundList(Vec<KVal<'a>>),
+    /// Bool atom or list.
+    Bool(KData<'a, bool>),
+    /// GUID atom or list.
+    Guid(KData<'a, [u8; 16]>),
+    /// Byte atom or list.
+    Byte(KData<'a, u8>),
+    /// Short atom or list.
+    Short(KData<'a, i16>),
+    /// Int atom or list.
+    Int(KData<'a, i32>),
+    /// Long atom or list.
+    Long(KData<'a, i64>),
+    /// Real atom or list.
+    Real(KData<'a, f32>),
+    /// Float atom or list.
+    Float(KData<'a, f64>),
+    /// Char atom.
+    Char(char),
+    /// Symbol atom or list.
+    Symbol(KData<'a, String>),
+    /// String (char list) held as bytes.
+    String(Cow<'a, [u8]>),
+    /// Timestamp atom or list.
+    Timestamp(KData<'a, i64>),
+    /// Month atom or list.
+    Month(KData<'a, i32>),
+    /// Date atom or list.
+    Date(KData<'a, i32>),
+    /// Datetime atom or list.
+    Datetime(KData<'a, f64>),
+    /// Timespan atom or list.
+    Timespan(KData<'a, i64>),
+    /// Minute atom or list.
+    Minute(KData<'a, i32>),
+    /// Second atom or list.
+    Second(KData<'a, i32>),
+    /// Time atom or list.
+    Time(KData<'a, i32>),
+    /// Enum atom or list with the name of its source if known. The source of an enum passed from q is not known.
+    Enum(KData<'a, i64>, Option<String>),
+    /// Table.
+    Table(KTable<'a>),
+    /// Dictionary including keyed table.
+    Dictionary(KDict<'a>),
+    /// Error.
+    Error(Cow<'a, str>),
+    /// General null `(::)`. It is converted into a new general null of type 101h by `to_k`, so that a compound list
+    ///  holding `::` such as ``(1; ::; `a)`` makes a round trip. General nulls are equal to each other.
+    Null,
+}
+
+/// Iterator over elements of [`KVal`](enum.KVal.html) created by [`KVal::iter`](enum.KVal.html#method.iter).
+#[derive(Clone, Debug)]
+pub struct KValIter<'a> {
+    /// Iterated value.
+    value: &'a KVal<'a>,
+    /// Index of the next element from the front.
+    front: usize,
+    /// Index after the next element from the back.
+    back: usize,
+}
+
+/// Rust representation of a q object built by [`KVal::from_raw_lazy`](enum.KVal.html#method.from_raw_lazy). A table
+///  or a dictionary defers the conversion of its columns or values until they are read, and any other object is
+///  converted eagerly.
+#[derive(Debug)]
+pub enum LazyKVal<'a> {
+    /// Table whose columns are converted on demand.
+    Table(LazyTable<'a>),
+    /// Dictionary including keyed table whose values are converted on demand.
+    Dictionary(LazyDict<'a>),
+    /// Any other object.
+    Value(KVal<'a>),
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% KVal %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl<'a> KVal<'a> {
+    /// Build `KVal` from a q object. The reference count of the q object is not changed.
+    /// # Parameters
+    /// - `k`: q object. `KNULL` is regarded as a general null.
+    /// # Note
+    /// - Function, foreign object and other types which cannot be represented are converted into `KVal::Error`.
+    /// - Only `(::)` among objects of type 101h is converted into `KVal::Null`. The other objects of the type are unary
+    ///   primitives, e.g., `neg`, and therefore converted into `KVal::Error`.
+    /// - A keyed table is converted into `KVal::Dictionary` of two tables, which is converted into
+    ///   [`KKeyedTable`](struct.KKeyedTable.html) with `TryFrom`.
+    /// - Changes to the value are not propagated to the q object. Use [`commit`](#method.commit) to write them back.
+    // `k` is a q object passed by q, which keeps it alive during the call, in the same manner as any other function
+    //  of `api` taking `K` reads it without being `unsafe`.
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    pub fn from_raw(k: K) -> KVal<'a> {
+        if k.is_null() {
+            return KVal::Null;
+        }
+        match k.get_type() {
+            qtype::BOOL_ATOM => {
+                KVal::Bool(KData::Atom(Cow::Owned(unsafe { (*k).value.byte } != 0)))
+            }
+            qtype::GUID_ATOM => KVal::Guid(KData::Atom(Cow::Owned(k.get_guid().unwrap()))),
+            qtype::BYTE_ATOM => KVal::Byte(atom(unsafe { (*k).value.byte })),
+            qtype::SHORT_ATOM => KVal::Short(atom(unsafe { (*k).value.short })),
+            qtype::INT_ATOM => KVal::Int(atom(unsafe { (*k).value.int })),
+            qtype::LONG_ATOM => KVal::Long(atom(unsafe { (*k).value.long })),
+            qtype::REAL_ATOM => KVal::Real(atom(unsafe { (*k).value.real })),
+            qtype::FLOAT_ATOM => KVal::Float(atom(unsafe { (*k).value.float })),
+            qtype::CHAR => KVal::Char(unsafe { (*k).value.byte } as char),
+            qtype::SYMBOL_ATOM => {
+                KVal::Symbol(atom(symbol_to_string(unsafe { (*k).value.symbol })))
+            }
+            qtype::TIMESTAMP_ATOM => KVal::Timestamp(atom(unsafe { (*k).value.long })),
+            qtype::MONTH_ATOM => KVal::Month(atom(unsafe { (*k).value.int })),
+            qtype::DATE_ATOM => KVal::Date(atom(unsafe { (*k).value.int })),
+            qtype::DATETIME_ATOM => KVal::Datetime(atom(unsafe { (*k).value.float })),
+            qtype::TIMESPAN_ATOM => KVal::Timespan(atom(unsafe { (*k).value.long })),
+            qtype::MINUTE_ATOM => KVal::Minute(atom(unsafe { (*k).value.int })),
+            qtype::SECOND_ATOM => KVal::Second(atom(unsafe { (*k).value.int })),
+            qtype::TIME_ATOM => KVal::Time(atom(unsafe { (*k).value.int })),
+            qtype::ENUM_ATOM => KVal::Enum(atom(unsafe { (*k).value.long }), None),
+            qtype::COMPOUND_LIST => KVal::CompoundList(
+                as_slice::<K>(k)
+                    .iter()
+                    .map(|element| KVal::from_raw(*element))
+                    .collect(),
+            ),
+            qtype::BOOL_LIST => KVal::Bool(KData::List(Cow::Owned(
+                as_slice::<G>(k).iter().map(|byte| *byte != 0).collect(),
+            ))),
+            qtype::GUID_LIST => KVal::Guid(list(k)),
+            qtype::BYTE_LIST => KVal::Byte(list(k)),
+            qtype::SHORT_LIST => KVal::Short(list(k)),
+            qtype::INT_LIST => KVal::Int(list(k)),
+            qtype::LONG_LIST => KVal::Long(list(k)),
+            qtype::REAL_LIST => KVal::Real(list(k)),
+            qtype::FLOAT_LIST => KVal::Float(list(k)),
+            qtype::STRING => KVal::String(Cow::Borrowed(as_slice::<G>(k))),
+            qtype::SYMBOL_LIST => KVal::Symbol(KData::List(Cow::Owned(
+                as_slice::<S>(k)
+                    .iter()
+                    .map(|symbol| symbol_to_string(*symbol))
+                    .collect(),
+            ))),
+            qtype::TIMESTAMP_LIST => KVal::Timestamp(list(k)),
+            qtype::MONTH_LIST => KVal::Month(list(k)),
+            qtype::DATE_LIST => KVal::Date(list(k)),
+            qtype::DATETIME_LIST => KVal::Datetime(list(k)),
+            qtype::TIMESPAN_LIST => KVal::Timespan(list(k)),
+            qtype::MINUTE_LIST => KVal::Minute(list(k)),
+            qtype::SECOND_LIST => KVal::Second(list(k)),
+            qtype::TIME_LIST => KVal::Time(list(k)),
+            qtype::ENUM_LIST => KVal::Enum(list(k), None),
+            qtype::TABLE => KVal::Table(KTable::from_raw(k)),
+            qtype::DICTIONARY | qtype::SORTED_DICTIONARY => KVal::Dictionary(KDict::from_raw(k)),
+            qtype::ERROR => {
+                // The message is held as a symbol. It is null if the object was caught from `KNULL`.
+                let message = unsafe { (*k).value.symbol };
+                if message.is_null() {
+                    KVal::Null
+                } else {
+                    KVal::Error(Cow::Owned(symbol_to_string(message)))
+                }
+            }
+            qtype::NULL if unsafe { (*k).value.byte } == 0 => KVal::Null,
+            other => KVal::Error(Cow::Owned(format!("unsupported type: {}h", other))),
+        }
+    }
+
+    /// Build a value from a q object in the same manner as [`from_raw`](#method.from_raw) except that the columns of
+    ///  a table and the values of a dictionary are converted when they are first read. This avoids copying a wide
+    ///  table when a function reads only a few of its columns. The reference count of the q object is not changed.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn row_count(table: K) -> K{
+    ///   match KVal::from_raw_lazy(table){
+    ///     LazyKVal::Table(table) => new_long(table.len() as i64),
+    ///     _ => new_error("not a table\0")
+    ///   }
+    /// }
+    /// ```
+    pub fn from_raw_lazy(k: K) -> LazyKVal<'a> {
+        if k.is_null() {
+            return LazyKVal::Value(KVal::Null);
+        }
+        match k.get_type() {
+            qtype::TABLE => LazyKVal::Table(LazyTable::from_raw(k)),
+            qtype::DICTIONARY | qtype::SORTED_DICTIONARY => {
+                LazyKVal::Dictionary(LazyDict::from_raw(k))
+            }
+            _ => LazyKVal::Value(KVal::from_raw(k)),
+        }
+    }
+
+    /// Build a new q object from `KVal`. The returned object has a reference count of 1 and is owned by the caller.
+    /// # Ownership
+    /// Every element of a compound list, a dictionary and a table is built anew even if it borrows the memory of a
+    ///  q object or the same value appears more than once. The returned object therefore never shares a child with
+    ///  another object and no reference count of the borrowed objects is changed.
+    /// # Note
+    /// A value which cannot be represented in q results in a q error (see [`new_error`](../fn.new_error.html)):
+    /// - `Char` whose code point is greater than `0xFF`.
+    /// - `Symbol` containing a null character.
+    /// - `Enum` without its source.
+    /// - List whose length exceeds [`max_list_length`](fn.max_list_length.html). Nothing is allocated in this case.
+    pub fn to_k(&self) -> K {
+        match self {
+            KVal::CompoundList(list) => compound_to_k(list),
+            KVal::Bool(KData::List(list)) => bool_list_to_k(list),
+            KVal::Guid(KData::List(list)) => simple_list_to_k(qtype::GUID_LIST, list),
+            KVal::Byte(KData::List(list)) => simple_list_to_k(qtype::BYTE_LIST, list),
+            KVal::Short(KData::List(list)) => simple_list_to_k(qtype::SHORT_LIST, list),
+            KVal::Int(KData::List(list)) => simple_list_to_k(qtype::INT_LIST, list),
+            KVal::Long(KData::List(list)) => simple_list_to_k(qtype::LONG_LIST, list),
+            KVal::Real(KData::List(list)) => simple_list_to_k(qtype::REAL_LIST, list),
+            KVal::Float(KData::List(list)) => simple_list_to_k(qtype::FLOAT_LIST, list),
+            KVal::Symbol(KData::List(list)) => symbol_list_to_k(list),
+            KVal::String(string) => simple_list_to_k(qtype::STRING, string),
+            KVal::Timestamp(KData::List(list)) => simple_list_to_k(qtype::TIMESTAMP_LIST, list),
+            KVal::Month(KData::List(list)) => simple_list_to_k(qtype::MONTH_LIST, list),
+            KVal::Date(KData::List(list)) => simple_list_to_k(qtype::DATE_LIST, list),
+            KVal::Datetime(KData::List(list)) => simple_list_to_k(qtype::DATETIME_LIST, list),
+            KVal::Timespan(KData::List(list)) => simple_list_to_k(qtype::TIMESPAN_LIST, list),
+            KVal::Minute(KData::List(list)) => simple_list_to_k(qtype::MINUTE_LIST, list),
+            KVal::Second(KData::List(list)) => simple_list_to_k(qtype::SECOND_LIST, list),
+            KVal::Time(KData::List(list)) => simple_list_to_k(qtype::TIME_LIST, list),
+            KVal::Enum(KData::List(list), source) => enum_list_to_k(list, source.as_deref()),
+            KVal::Table(table) => table.to_k(),
+            KVal::Dictionary(dictionary) => dictionary.to_k(),
+            KVal::Error(message) => error_to_k(message),
+            KVal::Null => new_null(),
+            atom => atom_to_k(atom),
+        }
+    }
+
+    /// Get the number of elements. An atom, an error and a general null have a length of 1, a table has the number of
+    ///  rows and a dictionary has the number of keys.
+    pub fn len(&self) -> usize {
+        match self {
+            KVal::CompoundList(list) => list.len(),
+            KVal::Bool(data) => data.len(),
+            KVal::Guid(data) => data.len(),
+            KVal::Byte(data) => data.len(),
+            KVal::Short(data) => data.len(),
+            KVal::Int(data) => data.len(),
+            KVal::Long(data) => data.len(),
+            KVal::Real(data) => data.len(),
+            KVal::Float(data) => data.len(),
+            KVal::Char(_) => 1,
+            KVal::Symbol(data) => data.len(),
+            KVal::String(string) => string.len(),
+            KVal::Timestamp(data) => data.len(),
+            KVal::Month(data) => data.len(),
+            KVal::Date(data) => data.len(),
+            KVal::Datetime(data) => data.len(),
+            KVal::Timespan(data) => data.len(),
+            KVal::Minute(data) => data.len(),
+            KVal::Second(data) => data.len(),
+            KVal::Time(data) => data.len(),
+            KVal::Enum(data, _) => data.len(),
+            KVal::Table(table) => table.len(),
+            KVal::Dictionary(dictionary) => dictionary.len(),
+            KVal::Error(_) | KVal::Null => 1,
+        }
+    }
+
+    /// Check if the value has no element.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the q type code of the object which [`to_k`](#method.to_k) builds, e.g., `qtype::LONG_LIST` for a long
+    ///  list. A dictionary including a keyed table is `qtype::DICTIONARY`, an error is `qtype::ERROR` and a general null
+    ///  is `qtype::NULL`.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use kdbplus::qtype;
+    /// use std::borrow::Cow;
+    ///
+    /// let list = KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3])));
+    /// assert_eq!(list.qtype(), qtype::LONG_LIST);
+    /// assert_eq!(KVal::Char('q').qtype(), qtype::CHAR);
+    /// ```
+    pub fn qtype(&self) -> i8 {
+        macro_rules! atom_or_list {
+            ($data: expr, $list: expr) => {
+                match $data {
+                    KData::Atom(_) => -$list,
+                    KData::List(_) => $list,
+                }
+            };
+        }
+        match self {
+            KVal::CompoundList(_) => qtype::COMPOUND_LIST,
+            KVal::Bool(data) => atom_or_list!(data, qtype::BOOL_LIST),
+            KVal::Guid(data) => atom_or_list!(data, qtype::GUID_LIST),
+            KVal::Byte(data) => atom_or_list!(data, qtype::BYTE_LIST),
+            KVal::Short(data) => atom_or_list!(data, qtype::SHORT_LIST),
+            KVal::Int(data) => atom_or_list!(data, qtype::INT_LIST),
+            KVal::Long(data) => atom_or_list!(data, qtype::LONG_LIST),
+            KVal::Real(data) => atom_or_list!(data, qtype::REAL_LIST),
+            KVal::Float(data) => atom_or_list!(data, qtype::FLOAT_LIST),
+            KVal::Char(_) => qtype::CHAR,
+            KVal::Symbol(data) => atom_or_list!(data, qtype::SYMBOL_LIST),
+            KVal::String(_) => qtype::STRING,
+            KVal::Timestamp(data) => atom_or_list!(data, qtype::TIMESTAMP_LIST),
+            KVal::Month(data) => atom_or_list!(data, qtype::MONTH_LIST),
+            KVal::Date(data) => atom_or_list!(data, qtype::DATE_LIST),
+            KVal::Datetime(data) => atom_or_list!(data, qtype::DATETIME_LIST),
+            KVal::Timespan(data) => atom_or_list!(data, qtype::TIMESPAN_LIST),
+            KVal::Minute(data) => atom_or_list!(data, qtype::MINUTE_LIST),
+            KVal::Second(data) => atom_or_list!(data, qtype::SECOND_LIST),
+            KVal::Time(data) => atom_or_list!(data, qtype::TIME_LIST),
+            KVal::Enum(data, _) => atom_or_list!(data, qtype::ENUM_LIST),
+            KVal::Table(_) => qtype::TABLE,
+            KVal::Dictionary(_) => qtype::DICTIONARY,
+            KVal::Error(_) => qtype::ERROR,
+            KVal::Null => qtype::NULL,
+        }
+    }
+
+    /// Get a name of the q type of the value, e.g., "long list" or "symbol atom". A char atom is "char" and a char
+    ///  list is "string".
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let atom = KVal::Symbol(KData::Atom(Cow::Owned("kdb".to_string())));
+    /// assert_eq!(atom.type_name(), "symbol atom");
+    /// ```
+    pub fn type_name(&self) -> &'static str {
+        qtype_name(self.qtype())
+    }
+
+    /// Get an element at the specified index.
+    /// - Simple list returns the atom of the same type borrowing the element.
+    /// - String returns `Char` of the byte at the index.
+    /// - Compound list returns a clone of the element.
+    /// - Enum list returns the enum atom carrying the same source.
+    /// - Atom returns a clone of itself for the index 0.
+    ///
+    /// `None` is returned if the index is out of bounds or the value is a table, a dictionary, an error or
+    ///  a general null.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn pick(list: K, index: K) -> K{
+    ///   match index.get_long(){
+    ///     Ok(index) if index >= 0 => match KVal::from_raw(list).get(index as usize){
+    ///       Some(element) => element.to_k(),
+    ///       None => new_error("index out of bounds\0")
+    ///     },
+    ///     _ => new_error("index must be a non-negative long\0")
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)pick: `libapi_examples 2: (`pick; 2);
+    /// q)pick[`a`b`c; 1]
+    /// `b
+    /// q)pick["text"; 2]
+    /// "x"
+    /// q)pick[1 2 3; 3]
+    /// 'index out of bounds
+    /// ```
+    pub fn get(&self, index: usize) -> Option<KVal<'_>> {
+        match self {
+            KVal::CompoundList(list) => list.get(index).cloned(),
+            KVal::Bool(data) => data.get(index).map(KVal::Bool),
+            KVal::Guid(data) => data.get(index).map(KVal::Guid),
+            KVal::Byte(data) => data.get(index).map(KVal::Byte),
+            KVal::Short(data) => data.get(index).map(KVal::Short),
+            KVal::Int(data) => data.get(index).map(KVal::Int),
+            KVal::Long(data) => data.get(index).map(KVal::Long),
+            KVal::Real(data) => data.get(index).map(KVal::Real),
+            KVal::Float(data) => data.get(index).map(KVal::Float),
+            KVal::Char(character) => (index == 0).then_some(KVal::Char(*character)),
+            KVal::Symbol(data) => data.get(index).map(KVal::Symbol),
+            KVal::String(string) => string.get(index).map(|byte| KVal::Char(*byte as char)),
+            KVal::Timestamp(data) => data.get(index).map(KVal::Timestamp),
+            KVal::Month(data) => data.get(index).map(KVal::Month),
+            KVal::Date(data) => data.get(index).map(KVal::Date),
+            KVal::Datetime(data) => data.get(index).map(KVal::Datetime),
+            KVal::Timespan(data) => data.get(index).map(KVal::Timespan),
+            KVal::Minute(data) => data.get(index).map(KVal::Minute),
+            KVal::Second(data) => data.get(index).map(KVal::Second),
+            KVal::Time(data) => data.get(index).map(KVal::Time),
+            KVal::Enum(data, source) => data
+                .get(index)
+                .map(|element| KVal::Enum(element, source.clone())),
+            KVal::Table(_) | KVal::Dictionary(_) | KVal::Error(_) | KVal::Null => None,
+        }
+    }
+
+    /// Iterate over elements in the same manner as [`get`](#method.get).
+    /// - Simple list yields atoms of the same type borrowing the elements.
+    /// - String yields `Char` of each byte.
+    /// - Compound list yields clones of the elements.
+    /// - Enum list yields enum atoms carrying the same source.
+    /// - Atom yields a clone of itself once.
+    ///
+    /// Table, dictionary, error and general null yield nothing.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let list = KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3])));
+    /// let iter = list.iter();
+    /// assert_eq!(iter.len(), 3);
+    /// let sum = iter.map(|element| match element{
+    ///   KVal::Long(KData::Atom(long)) => *long,
+    ///   _ => unreachable!()
+    /// }).sum::<i64>();
+    /// assert_eq!(sum, 6);
+    ///
+    /// let error = KVal::Error(Cow::Borrowed("type"));
+    /// assert_eq!(error.iter().count(), 0);
+    /// ```
+    pub fn iter(&self) -> KValIter<'_> {
+        let back = match self {
+            KVal::Table(_) | KVal::Dictionary(_) | KVal::Error(_) | KVal::Null => 0,
+            _ => self.len(),
+        };
+        KValIter {
+            value: self,
+            front: 0,
+            back,
+        }
+    }
+
+    /// Get a sub-list in the specified range of indices.
+    /// - Simple list borrows the elements without copying them.
+    /// - String borrows the bytes in the range.
+    /// - Compound list returns a vector of clones of the elements.
+    /// - Enum list carries the same source.
+    /// # Errors
+    /// - The value is an atom, a table, a dictionary, an error or a general null.
+    /// - The range is out of bounds or decreasing.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn window(list: K, start: K, end: K) -> K{
+    ///   match (start.get_long(), end.get_long()){
+    ///     (Ok(start), Ok(end)) if start >= 0 && end >= 0 => {
+    ///       match KVal::from_raw(list).slice(start as usize .. end as usize){
+    ///         Ok(window) => window.to_k(),
+    ///         Err(error) => new_error(error)
+    ///       }
+    ///     },
+    ///     _ => new_error("indices must be non-negative longs\0")
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)window: `libapi_examples 2: (`window; 3);
+    /// q)window[til 10; 3; 6]
+    /// 3 4 5
+    /// q)window["kdbplus"; 3; 7]
+    /// "plus"
+    /// q)window[til 10; 8; 11]
+    /// 'index out of bounds
+    /// ```
+    pub fn slice(&self, range: Range<usize>) -> Result<KVal<'_>, &'static str> {
+        match self {
+            KVal::CompoundList(list) => list
+                .get(range)
+                .map(|list| KVal::CompoundList(list.to_vec()))
+                .ok_or(errors::INDEX_OUT_OF_BOUNDS),
+            KVal::Bool(data) => data.slice(range).map(KVal::Bool),
+            KVal::Guid(data) => data.slice(range).map(KVal::Guid),
+            KVal::Byte(data) => data.slice(range).map(KVal::Byte),
+            KVal::Short(data) => data.slice(range).map(KVal::Short),
+            KVal::Int(data) => data.slice(range).map(KVal::Int),
+            KVal::Long(data) => data.slice(range).map(KVal::Long),
+            KVal::Real(data) => data.slice(range).map(KVal::Real),
+            KVal::Float(data) => data.slice(range).map(KVal::Float),
+            KVal::Symbol(data) => data.slice(range).map(KVal::Symbol),
+            KVal::String(string) => string
+                .get(range)
+                .map(|string| KVal::String(Cow::Borrowed(string)))
+                .ok_or(errors::INDEX_OUT_OF_BOUNDS),
+            KVal::Timestamp(data) => data.slice(range).map(KVal::Timestamp),
+            KVal::Month(data) => data.slice(range).map(KVal::Month),
+            KVal::Date(data) => data.slice(range).map(KVal::Date),
+            KVal::Datetime(data) => data.slice(range).map(KVal::Datetime),
+            KVal::Timespan(data) => data.slice(range).map(KVal::Timespan),
+            KVal::Minute(data) => data.slice(range).map(KVal::Minute),
+            KVal::Second(data) => data.slice(range).map(KVal::Second),
+            KVal::Time(data) => data.slice(range).map(KVal::Time),
+            KVal::Enum(data, source) => data
+                .slice(range)
+                .map(|data| KVal::Enum(data, source.clone())),
+            KVal::Char(_) | KVal::Table(_) | KVal::Dictionary(_) | KVal::Error(_) | KVal::Null => {
+                Err(errors::NOT_A_LIST)
+            }
+        }
+    }
+
+    /// Copy the value into a new value which owns all of its data. Unlike `clone`, which keeps borrowing the buffer
+    ///  of a q object, the result holds no reference to the q object and therefore outlives it and can be sent to
+    ///  another thread. Elements of compound lists, dictionaries and tables are copied recursively.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn doubled(list: K) -> K{
+    ///   let mut copy = KVal::from_raw(list).deep_clone();
+    ///   match &mut copy{
+    ///     KVal::Long(KData::List(longs)) => longs.to_mut().iter_mut().for_each(|long| *long *= 2),
+    ///     _ => return new_error("not a long list\0")
+    ///   }
+    ///   copy.to_k()
+    /// }
+    /// ```
+    /// ```q
+    /// q)doubled: `libapi_examples 2: (`doubled; 1);
+    /// q)list: 1 2 3
+    /// q)doubled list
+    /// 2 4 6
+    /// q)list
+    /// 1 2 3
+    /// ```
+    pub fn deep_clone(&self) -> KVal<'static> {
+        match self {
+            KVal::CompoundList(list) => {
+                KVal::CompoundList(list.iter().map(KVal::deep_clone).collect())
+            }
+            KVal::Bool(data) => KVal::Bool(data.deep_clone()),
+            KVal::Guid(data) => KVal::Guid(data.deep_clone()),
+            KVal::Byte(data) => KVal::Byte(data.deep_clone()),
+            KVal::Short(data) => KVal::Short(data.deep_clone()),
+            KVal::Int(data) => KVal::Int(data.deep_clone()),
+            KVal::Long(data) => KVal::Long(data.deep_clone()),
+            KVal::Real(data) => KVal::Real(data.deep_clone()),
+            KVal::Float(data) => KVal::Float(data.deep_clone()),
+            KVal::Char(character) => KVal::Char(*character),
+            KVal::Symbol(data) => KVal::Symbol(data.deep_clone()),
+            KVal::String(string) => KVal::String(Cow::Owned(string.to_vec())),
+            KVal::Timestamp(data) => KVal::Timestamp(data.deep_clone()),
+            KVal::Month(data) => KVal::Month(data.deep_clone()),
+            KVal::Date(data) => KVal::Date(data.deep_clone()),
+            KVal::Datetime(data) => KVal::Datetime(data.deep_clone()),
+            KVal::Timespan(data) => KVal::Timespan(data.deep_clone()),
+            KVal::Minute(data) => KVal::Minute(data.deep_clone()),
+            KVal::Second(data) => KVal::Second(data.deep_clone()),
+            KVal::Time(data) => KVal::Time(data.deep_clone()),
+            KVal::Enum(data, source) => KVal::Enum(data.deep_clone(), source.clone()),
+            KVal::Table(table) => KVal::Table(table.deep_clone()),
+            KVal::Dictionary(dictionary) => KVal::Dictionary(dictionary.deep_clone()),
+            KVal::Error(error) => KVal::Error(Cow::Owned(error.to_string())),
+            KVal::Null => KVal::Null,
+        }
+    }
+}
+
+impl<'a> FromIterator<KVal<'a>> for KVal<'a> {
+    /// Collect values into a compound list.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let list = (0..5).map(|i| KVal::Long(KData::Atom(Cow::Owned(i)))).collect::<KVal>();
+    /// assert!(matches!(list, KVal::CompoundList(_)));
+    /// assert_eq!(list.len(), 5);
+    /// ```
+    fn from_iter<I: IntoIterator<Item = KVal<'a>>>(iter: I) -> Self {
+        KVal::CompoundList(iter.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for KVal<'a> {
+    type Item = KVal<'a>;
+    type IntoIter = std::vec::IntoIter<KVal<'a>>;
+
+    /// Consume a compound list into its elements. Any other value yields itself once. Use
+    ///  [`iter`](#method.iter) to iterate over elements of a simple list.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let list = KVal::CompoundList(vec![KVal::Char('a'), KVal::Null]);
+    /// assert_eq!(list.into_iter().count(), 2);
+    ///
+    /// let atom = KVal::Long(KData::Atom(Cow::Owned(42)));
+    /// assert_eq!(atom.into_iter().count(), 1);
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            KVal::CompoundList(list) => list.into_iter(),
+            value => vec![value].into_iter(),
+        }
+    }
+}
+
+impl<'b, 'a: 'b> IntoIterator for &'b KVal<'a> {
+    type Item = KVal<'b>;
+    type IntoIter = KValIter<'b>;
+
+    /// Same as [`KVal::iter`](enum.KVal.html#method.iter).
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+//%% KValIter %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl<'a> Iterator for KValIter<'a> {
+    type Item = KVal<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        let element = self.value.get(self.front);
+        self.front += 1;
+        element
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.back - self.front, Some(self.back - self.front))
+    }
+}
+
+impl<'a> DoubleEndedIterator for KValIter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        self.value.get(self.back)
+    }
+}
+
+impl<'a> ExactSizeIterator for KValIter<'a> {}
+
+impl<'a> FusedIterator for KValIter<'a> {}
+
+//%% LazyKVal %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl<'a> LazyKVal<'a> {
+    /// Convert the remaining columns or values and build `KVal`.
+    pub fn into_kval(self) -> KVal<'a> {
+        match self {
+            LazyKVal::Table(table) => KVal::Table(table.into_table()),
+            LazyKVal::Dictionary(dictionary) => KVal::Dictionary(dictionary.into_dict()),
+            LazyKVal::Value(value) => value,
+        }
+    }
+}
+
+//%% KData %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+impl<'a, T> KData<'a, T>
+where
+    T: Clone,
+{
+    /// Get the number of elements. An atom has a length of 1.
+    pub fn len(&self) -> usize {
+        match self {
+            KData::Atom(_) => 1,
+            KData::List(list) => list.len(),
+        }
+    }
+
+    /// Check if the value has no element.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get an atom borrowing the element at the specified index. An atom returns itself for the index 0.
+    pub fn get(&self, index: usize) -> Option<KData<'_, T>> {
+        match self {
+            KData::Atom(atom) => (index == 0).then(|| KData::Atom(Cow::Borrowed(atom.as_ref()))),
+            KData::List(list) => list
+                .get(index)
+                .map(|element| KData::Atom(Cow::Borrowed(element))),
+        }
+    }
+
+    /// Get a list borrowing the elements in the specified range of indices.
+    /// # Errors
+    /// - The value is an atom.
+    /// - The range is out of bounds or decreasing.
+    pub fn slice(&self, range: Range<usize>) -> Result<KData<'_, T>, &'static str> {
+        match self {
+            KData::Atom(_) => Err(errors::NOT_A_LIST),
+            KData::List(list) => list
+                .get(range)
+                .map(|list| KData::List(Cow::Borrowed(list)))
+                .ok_or(errors::INDEX_OUT_OF_BOUNDS),
+        }
+    }
+
+    /// Borrow the elements as a slice. An atom is a slice of one element.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// fn total(data: &KData<i64>) -> i64{
+    ///   data.as_slice().iter().sum()
+    /// }
+    ///
+    /// assert_eq!(total(&KData::Atom(Cow::Owned(3))), 3);
+    /// assert_eq!(total(&KData::List(Cow::Owned(vec![1, 2, 3]))), 6);
+    /// ```
+    pub fn as_slice(&self) -> &[T] {
+        match self {
+            KData::Atom(atom) => std::slice::from_ref(atom.as_ref()),
+            KData::List(list) => list.as_ref(),
+        }
+    }
+
+    /// Take the elements as a vector. An atom is a vector of one element. A list owning its data is not copied.
+    pub fn to_vec(self) -> Vec<T> {
+        match self {
+            KData::Atom(atom) => vec![atom.into_owned()],
+            KData::List(list) => list.into_owned(),
+        }
+    }
+
+    /// Get the first element. An atom returns itself and an empty list returns `None`.
+    pub fn first(&self) -> Option<&T> {
+        self.as_slice().first()
+    }
+
+    /// Get the last element. An atom returns itself and an empty list returns `None`.
+    pub fn last(&self) -> Option<&T> {
+        self.as_slice().last()
+    }
+
+    /// Iterate over the elements. An atom yields itself.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    /// Transform each element into a new atom or list of the same shape which owns its data.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let sizes = KData::List(Cow::Owned(vec![100_i64, 250]));
+    /// let lots = sizes.map(|size| size as f64 / 100.0);
+    /// assert_eq!(lots, KData::List(Cow::Owned(vec![1.0, 2.5])));
+    /// ```
+    pub fn map<U: Clone>(self, mut f: impl FnMut(T) -> U) -> KData<'static, U> {
+        match self {
+            KData::Atom(atom) => KData::Atom(Cow::Owned(f(atom.into_owned()))),
+            KData::List(list) => {
+                KData::List(Cow::Owned(list.into_owned().into_iter().map(f).collect()))
+            }
+        }
+    }
+
+    /// Modify each element in place. Data owned by the value is modified without reallocation while data borrowed
+    ///  from q is copied once before modification, so that the q object is left as it is.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn scale(prices: K) -> K{
+    ///   match KVal::from_raw(prices){
+    ///     KVal::Float(mut prices) => {
+    ///       prices.map_in_place(|price| *price *= 2.0);
+    ///       KVal::Float(prices).to_k()
+    ///     },
+    ///     _ => new_error("type\0")
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)scale: `libapi_examples 2: (`scale; 1);
+    /// q)scale 1.5 0n 3
+    /// 3 0n 6
+    /// ```
+    pub fn map_in_place(&mut self, mut f: impl FnMut(&mut T)) {
+        match self {
+            KData::Atom(atom) => f(atom.to_mut()),
+            KData::List(list) => list.to_mut().iter_mut().for_each(f),
+        }
+    }
+}
+
+impl<T> KData<'_, T>
+where
+    T: Clone + 'static,
+{
+    /// Copy the atom or the list into a new value which owns the data.
+    pub fn deep_clone(&self) -> KData<'static, T> {
+        match self {
+            KData::Atom(atom) => KData::Atom(Cow::Owned(atom.as_ref().clone())),
+            KData::List(list) => KData::List(Cow::Owned(list.to_vec())),
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Borrow the underlying buffer of a q list.
+pub(super) fn as_slice<'a, T>(k: K) -> &'a [T] {
+    unsafe {
+        std::slice::from_raw_parts(
+            (*k).value.list.G0.as_ptr() as *const T,
+            (*k).value.list.n as usize,
+        )
+    }
+}
+
+/// Wrap an atom value.
+pub(super) fn atom<'a, T: Clone>(value: T) -> KData<'a, T> {
+    KData::Atom(Cow::Owned(value))
+}
+
+/// Convert a char into the byte of q whose code point equals to the char.
+pub(super) fn char_to_byte(character: char) -> Result<u8, &'static str> {
+    u8::try_from(character as u32).map_err(|_| errors::CHAR_OUT_OF_RANGE)
+}
+
+/// Borrow a simple list.
+fn list<'a, T: Clone>(k: K) -> KData<'a, T> {
+    KData::List(Cow::Borrowed(as_slice::<T>(k)))
+}
+
+/// Build a new q atom. `value` must be an atom, a char or an enum atom.
+fn atom_to_k(value: &KVal) -> K {
+    match value {
+        KVal::Bool(KData::Atom(atom)) => new_bool(**atom as I),
+        KVal::Guid(KData::Atom(atom)) => new_guid(**atom),
+        KVal::Byte(KData::Atom(atom)) => new_byte_wrapping(**atom as I),
+        KVal::Short(KData::Atom(atom)) => new_short_wrapping(**atom as I),
+        KVal::Int(KData::Atom(atom)) => new_int(**atom),
+        KVal::Long(KData::Atom(atom)) => new_long(**atom),
+        KVal::Real(KData::Atom(atom)) => new_real32(**atom),
+        KVal::Float(KData::Atom(atom)) => new_float(**atom),
+        KVal::Char(character) => match char_to_byte(*character) {
+            Ok(byte) => unsafe { native::kc(byte as I) },
+            Err(error) => new_error(error),
+        },
+        KVal::Symbol(KData::Atom(atom)) => match enumerate_str(atom) {
+            Ok(symbol) => {
+                let k = unsafe { native::ka(qtype::SYMBOL_ATOM as I) };
+                unsafe { (*k).value.symbol = symbol };
+                k
+            }
+            Err(error) => new_error(error),
+        },
+        KVal::Timestamp(KData::Atom(atom)) => new_timestamp(**atom),
+        KVal::Month(KData::Atom(atom)) => new_month(**atom),
+        KVal::Date(KData::Atom(atom)) => new_date(**atom),
+        KVal::Datetime(KData::Atom(atom)) => new_datetime(**atom),
+        KVal::Timespan(KData::Atom(atom)) => new_timespan(**atom),
+        KVal::Minute(KData::Atom(atom)) => new_minute(**atom),
+        KVal::Second(KData::Atom(atom)) => new_second(**atom),
+        KVal::Time(KData::Atom(atom)) => new_time(**atom),
+        KVal::Enum(KData::Atom(atom), Some(source)) => new_enum(source, **atom),
+        KVal::Enum(KData::Atom(_), None) => new_error("enum requires a source\0"),
+        _ => unreachable!("only an atom is built by atom_to_k"),
+    }
+}
+
+/// Copy a slice into a new simple list of the specified type. A q error is returned if the slice is too long. A long
+///  slice is copied in parallel when `parallel` feature is enabled.
+fn simple_list_to_k<T: Copy + Send + Sync>(qtype: i8, list: &[T]) -> K {
+    let k = match q_length(list.len()) {
+        Ok(length) => new_list(qtype, length),
+        Err(error) => return error.to_k(),
+    };
+    #[cfg(feature = "parallel")]
+    if parallel::is_long(list.len()) {
+        parallel::copy(k.as_mut_slice::<T>(), list);
+        return k;
+    }
+    k.as_mut_slice::<T>().copy_from_slice(list);
+    k
+}
+
+/// Build a new bool list. `bool` is copied element by element as q holds a bool in a byte.
+fn bool_list_to_k(list: &[bool]) -> K {
+    let k = match q_length(list.len()) {
+        Ok(length) => new_list(qtype::BOOL_LIST, length),
+        Err(error) => return error.to_k(),
+    };
+    #[cfg(feature = "parallel")]
+    if parallel::is_long(list.len()) {
+        parallel::copy_bools(k.as_mut_slice::<G>(), list);
+        return k;
+    }
+    k.as_mut_slice::<G>()
+        .iter_mut()
+        .zip(list.iter())
+        .for_each(|(byte, boolean)| *byte = *boolean as G);
+    k
+}
+
+/// Build a new symbol list interning each symbol. Symbols of a long list are interned in parallel when `parallel`
+///  feature is enabled.
+fn symbol_list_to_k(list: &[String]) -> K {
+    let k = match q_length(list.len()) {
+        Ok(length) => new_list(qtype::SYMBOL_LIST, length),
+        Err(error) => return error.to_k(),
+    };
+    #[cfg(feature = "parallel")]
+    if parallel::is_long(list.len()) {
+        return match parallel::intern(k.as_mut_slice::<S>(), list) {
+            Ok(()) => k,
+            Err(error) => {
+                decrement_reference_count(k);
+                new_error(error)
+            }
+        };
+    }
+    for (slot, symbol) in k.as_mut_slice::<S>().iter_mut().zip(list.iter()) {
+        match enumerate_str(symbol) {
+            Ok(symbol) => *slot = symbol,
+            Err(error) => {
+                // q does not free the symbols held by a symbol list.
+                decrement_reference_count(k);
+                return new_error(error);
+            }
+        }
+    }
+    k
+}
+
+/// Build a new enum list by casting the indices to the source.
+fn enum_list_to_k(list: &[i64], source: Option<&str>) -> K {
+    let source = match source {
+        Some(source) => source,
+        None => return new_error("enum requires a source\0"),
+    };
+    let indices = simple_list_to_k(qtype::LONG_LIST, list);
+    if indices.is_null() {
+        // Error was signalled.
+        return KNULL;
+    }
+    // Cast the indices to the source in the same manner as `new_enum`.
+    match CString::new(format!("{{`{}${} x}}", source, source)) {
+        Ok(function) => unsafe { native::k(0, function.as_ptr(), indices, KNULL) },
+        Err(_) => {
+            decrement_reference_count(indices);
+            new_error("enum source contains a null character\0")
+        }
+    }
+}
+
+/// Build a new compound list building each element anew. The converted elements are freed if an element cannot
+///  be converted.
+fn compound_to_k(list: &[KVal]) -> K {
+    let length = match q_length(list.len()) {
+        Ok(length) => length,
+        Err(error) => return error.to_k(),
+    };
+    let mut elements = Vec::with_capacity(list.len());
+    for element in list {
+        let element = element.to_k();
+        if element.is_null() {
+            // Error. Free the converted elements.
+            for element in elements {
+                decrement_reference_count(element);
+            }
+            return KNULL;
+        }
+        elements.push(element);
+    }
+    let compound = new_list(qtype::COMPOUND_LIST, length);
+    compound.as_mut_slice::<K>().copy_from_slice(&elements);
+    compound
+}
+
+/// Convert q symbol into `String`.
+pub(super) fn symbol_to_string(symbol: S) -> String {
+    unsafe { CStr::from_ptr(symbol as *const C) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Get a name of a q type code in the same manner as `KVal::type_name`.
+pub(super) fn qtype_name(qtype: i8) -> &'static str {
+    match qtype {
+        qtype::ERROR => "error",
+        qtype::ENUM_ATOM => "enum atom",
+        qtype::TIME_ATOM => "time atom",
+        qtype::SECOND_ATOM => "second atom",
+        qtype::MINUTE_ATOM => "minute atom",
+        qtype::TIMESPAN_ATOM => "timespan atom",
+        qtype::DATETIME_ATOM => "datetime atom",
+        qtype::DATE_ATOM => "date atom",
+        qtype::MONTH_ATOM => "month atom",
+        qtype::TIMESTAMP_ATOM => "timestamp atom",
+        qtype::SYMBOL_ATOM => "symbol atom",
+        qtype::CHAR => "char",
+        qtype::FLOAT_ATOM => "float atom",
+        qtype::REAL_ATOM => "real atom",
+        qtype::LONG_ATOM => "long atom",
+        qtype::INT_ATOM => "int atom",
+        qtype::SHORT_ATOM => "short atom",
+        qtype::BYTE_ATOM => "byte atom",
+        qtype::GUID_ATOM => "guid atom",
+        qtype::BOOL_ATOM => "bool atom",
+        qtype::COMPOUND_LIST => "compound list",
+        qtype::BOOL_LIST => "bool list",
+        qtype::GUID_LIST => "guid list",
+        qtype::BYTE_LIST => "byte list",
+        qtype::SHORT_LIST => "short list",
+        qtype::INT_LIST => "int list",
+        qtype::LONG_LIST => "long list",
+        qtype::REAL_LIST => "real list",
+        qtype::FLOAT_LIST => "float list",
+        qtype::STRING => "string",
+        qtype::SYMBOL_LIST => "symbol list",
+        qtype::TIMESTAMP_LIST => "timestamp list",
+        qtype::MONTH_LIST => "month list",
+        qtype::DATE_LIST => "date list",
+        qtype::DATETIME_LIST => "datetime list",
+        qtype::TIMESPAN_LIST => "timespan list",
+        qtype::MINUTE_LIST => "minute list",
+        qtype::SECOND_LIST => "second list",
+        qtype::TIME_LIST => "time list",
+        qtype::ENUM_LIST => "enum list",
+        qtype::TABLE => "table",
+        qtype::DICTIONARY | qtype::SORTED_DICTIONARY => "dictionary",
+        qtype::NULL => "general null",
+        qtype::FOREIGN => "foreign",
+        _ => "unsupported type",
+    }
+}
+
+/// Intern a string as a q symbol.
+pub(super) fn enumerate_str(symbol: &str) -> Result<S, &'static str> {
+    if symbol.as_bytes().contains(&0) {
+        Err(NULL_IN_SYMBOL)
+    } else {
+        // `sn` copies the first `n` bytes, which makes a null terminator unnecessary.
+        Ok(enumerate_n(symbol.as_ptr() as S, symbol.len() as I))
+    }
+}
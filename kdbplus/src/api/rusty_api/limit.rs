@@ -0,0 +1,68 @@
+//! Limit of the length of a q list built from `KVal`.
+//!
+//! kdb+ 3.0 and later, which the layout of [`k0`](../struct.k0.html) requires, holds the length of a list as a
+//!  64-bit `J`. A Rust vector is therefore never too long for q on a 64-bit platform by itself, but a process may
+//!  want a lower limit, e.g., to reject a runaway result before q tries to allocate it. Every conversion of a Rust
+//!  length into a q length checks the limit and fails with
+//!  [`KError::TooLong`](enum.KError.html#variant.TooLong) instead of building the list.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::super::J;
+use super::KError;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Largest length which q can hold, i.e., the maximum of `J` within the address space of the platform.
+const Q_MAX_LENGTH: usize = if (J::MAX as u64) < usize::MAX as u64 {
+    J::MAX as usize
+} else {
+    usize::MAX
+};
+
+/// Current limit of the length of a list.
+static MAX_LIST_LENGTH: AtomicUsize = AtomicUsize::new(Q_MAX_LENGTH);
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Public Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Get the maximum number of elements of a q list built from `KVal`. Defaults to the largest length q can hold.
+pub fn max_list_length() -> usize {
+    MAX_LIST_LENGTH.load(Ordering::Relaxed)
+}
+
+/// Set the maximum number of elements of a q list built from `KVal`. The limit is shared by the whole process and
+///  is capped at the largest length q can hold. Returns the previous limit.
+/// # Example
+/// ```
+/// use kdbplus::api::rusty_api::*;
+/// use std::borrow::Cow;
+///
+/// let previous = set_max_list_length(2);
+/// let list = KVal::Long(KData::List(Cow::Owned(vec![1, 2])));
+/// let error = KVal::join(list, KVal::Long(KData::Atom(Cow::Owned(3)))).unwrap_err();
+/// assert_eq!(error, KError::TooLong{len: 3, limit: 2});
+/// set_max_list_length(previous);
+/// ```
+pub fn set_max_list_length(limit: usize) -> usize {
+    MAX_LIST_LENGTH.swap(limit.min(Q_MAX_LENGTH), Ordering::Relaxed)
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Convert a Rust length into a q length if it does not exceed the limit.
+pub(super) fn q_length(len: usize) -> Result<J, KError> {
+    let limit = max_list_length();
+    if len > limit {
+        return Err(KError::TooLong { len, limit });
+    }
+    Ok(len as J)
+}
@@ -0,0 +1,95 @@
+//! Support of [`kval!`](../../../macro.kval.html). Items in this module are used by the expansion of the macro and
+//!  are not intended to be used directly.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::compound::collapse_atoms;
+use super::{KData, KDict, KVal};
+use std::borrow::Cow;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Traits
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Rust value which is an atom of `kval!`.
+pub trait KLiteral {
+    /// Build an atom of the value.
+    fn into_kval(self) -> KVal<'static>;
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Macros
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Implement `KLiteral` for a type which is held by an atom of a variant as it is.
+macro_rules! literal {
+    ($type: ty, $variant: ident) => {
+        impl KLiteral for $type {
+            fn into_kval(self) -> KVal<'static> {
+                KVal::$variant(KData::Atom(Cow::Owned(self)))
+            }
+        }
+    };
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+literal!(bool, Bool);
+literal!(u8, Byte);
+literal!(i16, Short);
+literal!(i32, Int);
+literal!(i64, Long);
+literal!(f32, Real);
+literal!(f64, Float);
+literal!(String, Symbol);
+
+impl KLiteral for char {
+    fn into_kval(self) -> KVal<'static> {
+        KVal::Char(self)
+    }
+}
+
+impl KLiteral for &str {
+    fn into_kval(self) -> KVal<'static> {
+        KVal::Symbol(KData::Atom(Cow::Owned(self.to_string())))
+    }
+}
+
+impl KLiteral for KVal<'static> {
+    fn into_kval(self) -> KVal<'static> {
+        self
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Public Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Build an atom of a literal. An integer without a suffix, which Rust takes as `i32`, is a long atom in the same
+///  manner as q.
+pub fn atom<T: KLiteral>(value: T, text: &str) -> KVal<'static> {
+    match value.into_kval() {
+        KVal::Int(KData::Atom(int)) if !text.contains(['i', 'u']) => {
+            KVal::Long(KData::Atom(Cow::Owned(*int as i64)))
+        }
+        atom => atom,
+    }
+}
+
+/// Build a simple list if the elements are atoms of the same type and a compound list otherwise.
+pub fn list(elements: Vec<KVal<'static>>) -> KVal<'static> {
+    collapse_atoms(elements)
+}
+
+/// Build a dictionary with symbol keys.
+pub fn dict(keys: Vec<&str>, values: Vec<KVal<'static>>) -> KVal<'static> {
+    let keys = keys.into_iter().map(str::to_string).collect();
+    KVal::Dictionary(KDict::new(
+        KVal::Symbol(KData::List(Cow::Owned(keys))),
+        collapse_atoms(values),
+    ))
+}
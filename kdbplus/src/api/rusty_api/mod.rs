@@ -0,0 +1,102 @@
+//! This `rusty_api` module provides Rust-native types on top of the raw `K` object of the `api` module. While the functions
+//!  of the `api` module mirror the C API, the types here aim to make it hard to misuse values carried by a q object, e.g.,
+//!  by passing a timestamp where a timespan is expected or by borrowing a foreign object as a wrong Rust type.
+//!
+//! The central type is [`KVal`](enum.KVal.html) which represents a q object as a Rust enum. A `KVal` built from a `K`
+//!  object passed from q borrows the buffer of simple lists, so that a function can read large lists without copying them.
+//!
+//! *Notes:*
+//!
+//! - Conversion to/from `chrono` types is available only when `chrono` feature is enabled (enabled together with `ipc` feature).
+//! - Parallel iteration, e.g., [`KTable::par_rows`](struct.KTable.html#method.par_rows), and parallel copy of long lists
+//!   in [`KVal::to_k`](enum.KVal.html#method.to_k) (see [`set_parallel_threshold`](fn.set_parallel_threshold.html))
+//!   are available only when `parallel` feature is enabled. Without the feature the module does not depend on `rayon`
+//!   and never spawns a thread pool inside q.
+//! - Conversion into Apache Arrow arrays, e.g., [`symbols_to_arrow`](fn.symbols_to_arrow.html), and between tables and
+//!   Arrow record batches with [`KTable::to_arrow`](struct.KTable.html#method.to_arrow) and
+//!   [`KTable::from_arrow`](struct.KTable.html#method.from_arrow) are available only when `arrow` feature is enabled.
+//! - Mapping of Rust structs into table rows with `#[derive(QRow)]` (see [`QRow`](trait.QRow.html)) is available only
+//!   when `derive` feature is enabled.
+//! - Serialization of `KVal` with `serde`, e.g., into JSON, and deserialization, e.g., with
+//!   [`KVal::from_json`](enum.KVal.html#method.from_json), are available only when `serde` feature is enabled. See
+//!   [`TemporalFormat`](enum.TemporalFormat.html) for the representation of temporal values.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+mod aggregate;
+#[cfg(feature = "arrow")]
+mod arrow;
+mod calendar;
+mod cast;
+mod collection;
+mod column;
+mod commit;
+mod compare;
+mod compound;
+#[cfg(feature = "serde")]
+mod deserialize;
+mod display;
+mod distinct;
+mod edit;
+mod error;
+pub mod errors;
+mod foreign;
+mod hash;
+mod insert;
+mod join;
+mod kdict;
+mod ktable;
+mod kval;
+mod limit;
+#[doc(hidden)]
+pub mod literal;
+mod null;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod parse;
+#[cfg(feature = "derive")]
+mod qrow;
+mod rows;
+mod search;
+#[cfg(feature = "serde")]
+mod serialize;
+mod sort;
+mod split;
+mod strings;
+mod take;
+mod temporal;
+mod tracked;
+
+pub use aggregate::Numeric;
+#[cfg(feature = "arrow")]
+pub use arrow::*;
+pub use calendar::*;
+pub use column::ColumnElement;
+#[cfg(feature = "serde")]
+pub use deserialize::{DeserializeWith, StringFormat};
+pub use display::console_size;
+pub use distinct::DistinctList;
+pub use error::*;
+pub use foreign::*;
+pub use hash::CONTENT_HASH_VERSION;
+pub use insert::*;
+#[cfg(feature = "derive")]
+pub use kdbplus_derive::QRow;
+pub use kdict::*;
+pub use ktable::*;
+pub use kval::*;
+pub use limit::*;
+pub use null::Nullable;
+#[cfg(feature = "parallel")]
+pub use parallel::{parallel_threshold, set_parallel_threshold};
+pub use parse::ParseError;
+#[cfg(feature = "derive")]
+pub use qrow::{QField, QRow};
+pub use rows::{RowChunks, RowValues};
+#[cfg(feature = "serde")]
+pub use serialize::{SerializeWith, TemporalFormat};
+pub use sort::{QOrd, SortedList, SortedTable};
+pub use temporal::*;
+pub use tracked::TrackedKVal;
@@ -0,0 +1,484 @@
+//! Operations regarding q nulls of `KVal`.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::errors::{NO_INFINITY, NO_NULL};
+use super::{KData, KError, KVal};
+use crate::{qinf_base, qninf_base, qnull_base, qtype};
+use std::borrow::Cow;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Traits
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Element type of `KData` which may have a q null or infinities. Implemented for every element type of `KData`.
+///  Types without null (bool and byte) and without infinities (bool, byte, GUID and symbol) always return `false`.
+///
+/// Temporal types are tested with the values of their underlying types, e.g., a date with those of int.
+pub trait Nullable: Clone {
+    /// Check if the value is the q null of the type, e.g., `i32::MIN` for int, any `NaN` for float, all-zero GUID
+    ///  and an empty symbol.
+    fn is_null_value(&self) -> bool;
+    /// Check if the value is the positive infinity of the type.
+    fn is_inf_value(&self) -> bool {
+        false
+    }
+    /// Check if the value is the negative infinity of the type.
+    fn is_ninf_value(&self) -> bool {
+        false
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Macros
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Implement `Nullable` for a type with a null and infinities. A float type takes any `NaN` as the null.
+macro_rules! nullable {
+    ($type: ty, NaN, $inf: expr, $ninf: expr) => {
+        nullable!(@impl $type, |value: &$type| value.is_nan(), $inf, $ninf);
+    };
+    ($type: ty, $null: expr, $inf: expr, $ninf: expr) => {
+        nullable!(@impl $type, |value: &$type| *value == $null, $inf, $ninf);
+    };
+    (@impl $type: ty, $is_null: expr, $inf: expr, $ninf: expr) => {
+        impl Nullable for $type {
+            fn is_null_value(&self) -> bool {
+                $is_null(self)
+            }
+            fn is_inf_value(&self) -> bool {
+                *self == $inf
+            }
+            fn is_ninf_value(&self) -> bool {
+                *self == $ninf
+            }
+        }
+    };
+}
+
+/// Apply a method of `Nullable` to the value of an atom. Anything other than an atom of `KData` results in `false`.
+macro_rules! test_atom {
+    ($value: expr, $test: ident) => {
+        match $value {
+            KVal::Bool(KData::Atom(atom)) => atom.$test(),
+            KVal::Guid(KData::Atom(atom)) => atom.$test(),
+            KVal::Byte(KData::Atom(atom)) => atom.$test(),
+            KVal::Short(KData::Atom(atom)) => atom.$test(),
+            KVal::Int(KData::Atom(atom))
+            | KVal::Month(KData::Atom(atom))
+            | KVal::Date(KData::Atom(atom))
+            | KVal::Minute(KData::Atom(atom))
+            | KVal::Second(KData::Atom(atom))
+            | KVal::Time(KData::Atom(atom)) => atom.$test(),
+            KVal::Long(KData::Atom(atom))
+            | KVal::Timestamp(KData::Atom(atom))
+            | KVal::Timespan(KData::Atom(atom))
+            | KVal::Enum(KData::Atom(atom), _) => atom.$test(),
+            KVal::Real(KData::Atom(atom)) => atom.$test(),
+            KVal::Float(KData::Atom(atom)) | KVal::Datetime(KData::Atom(atom)) => atom.$test(),
+            KVal::Symbol(KData::Atom(atom)) => atom.$test(),
+            _ => false,
+        }
+    };
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl Nullable for bool {
+    fn is_null_value(&self) -> bool {
+        false
+    }
+}
+
+impl Nullable for u8 {
+    fn is_null_value(&self) -> bool {
+        false
+    }
+}
+
+impl Nullable for [u8; 16] {
+    fn is_null_value(&self) -> bool {
+        *self == qnull_base::U
+    }
+}
+
+nullable!(i16, qnull_base::H, qinf_base::H, qninf_base::H);
+nullable!(i32, qnull_base::I, qinf_base::I, qninf_base::I);
+nullable!(i64, qnull_base::J, qinf_base::J, qninf_base::J);
+nullable!(f32, NaN, qinf_base::E, qninf_base::E);
+nullable!(f64, NaN, qinf_base::F, qninf_base::F);
+
+impl Nullable for String {
+    fn is_null_value(&self) -> bool {
+        self.is_empty()
+    }
+}
+
+impl<T: Nullable> KData<'_, T> {
+    /// Flag each element which is the q null of the type. An atom results in a single flag.
+    /// # Example
+    /// ```
+    /// use kdbplus::qnull_base;
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let dates = KData::List(Cow::Owned(vec![0, qnull_base::I, 7]));
+    /// assert_eq!(dates.null_mask(), vec![false, true, false]);
+    ///
+    /// let symbols = KData::List(Cow::Owned(vec!["a".to_string(), String::new()]));
+    /// assert_eq!(symbols.null_mask(), vec![false, true]);
+    /// ```
+    pub fn null_mask(&self) -> Vec<bool> {
+        self.mask(T::is_null_value)
+    }
+
+    /// Flag each element which is the positive infinity of the type. An atom results in a single flag.
+    pub fn inf_mask(&self) -> Vec<bool> {
+        self.mask(T::is_inf_value)
+    }
+
+    /// Flag each element which is the negative infinity of the type. An atom results in a single flag.
+    pub fn ninf_mask(&self) -> Vec<bool> {
+        self.mask(T::is_ninf_value)
+    }
+
+    /// Apply a test to each element.
+    fn mask(&self, test: impl Fn(&T) -> bool) -> Vec<bool> {
+        match self {
+            KData::Atom(atom) => vec![test(atom)],
+            KData::List(list) => list.iter().map(test).collect(),
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl KVal<'static> {
+    /// Build an atom of the q null of a type, e.g., `0Nj` for long and `` ` `` for symbol.
+    /// # Parameters
+    /// - `qtype`: Type of the null. Either of the atom type and the list type is accepted, e.g.,
+    ///   `qtype::LONG_ATOM` or `qtype::LONG_LIST`. A char list type (`qtype::STRING`) results in the null char.
+    /// # Errors
+    /// `"type has no null"` if the type is not GUID, short, int, long, real, float, char, symbol or a temporal type.
+    /// # Example
+    /// ```
+    /// use kdbplus::{qnull_base, qtype};
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let null = KVal::null_of(qtype::TIMESTAMP_ATOM).unwrap();
+    /// assert_eq!(null, KVal::Timestamp(KData::Atom(Cow::Owned(qnull_base::J))));
+    /// assert_eq!(KVal::null_of(qtype::BOOL_ATOM).unwrap_err(), "type has no null\0");
+    /// ```
+    pub fn null_of(qtype: i8) -> Result<KVal<'static>, &'static str> {
+        match qtype.wrapping_abs() {
+            qtype::GUID_LIST => Ok(KVal::Guid(KData::Atom(Cow::Owned(qnull_base::U)))),
+            qtype::STRING => Ok(KVal::Char(qnull_base::C)),
+            qtype::SYMBOL_LIST => Ok(KVal::Symbol(KData::Atom(Cow::Owned(
+                qnull_base::S.to_string(),
+            )))),
+            qtype => border_of(
+                qtype,
+                qnull_base::H,
+                qnull_base::I,
+                qnull_base::J,
+                qnull_base::E,
+                qnull_base::F,
+            )
+            .ok_or(NO_NULL),
+        }
+    }
+
+    /// Build an atom of the positive infinity of a type, e.g., `0Wj` for long.
+    /// # Parameters
+    /// - `qtype`: Type of the infinity. Either of the atom type and the list type is accepted.
+    /// # Errors
+    /// `"type has no infinity"` if the type is not short, int, long, real, float or a temporal type.
+    /// # Example
+    /// ```
+    /// use kdbplus::{qinf_base, qtype};
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let inf = KVal::inf_of(qtype::DATE_LIST).unwrap();
+    /// assert_eq!(inf, KVal::Date(KData::Atom(Cow::Owned(qinf_base::I))));
+    /// assert_eq!(KVal::inf_of(qtype::SYMBOL_ATOM).unwrap_err(), "type has no infinity\0");
+    /// ```
+    pub fn inf_of(qtype: i8) -> Result<KVal<'static>, &'static str> {
+        border_of(
+            qtype.wrapping_abs(),
+            qinf_base::H,
+            qinf_base::I,
+            qinf_base::J,
+            qinf_base::E,
+            qinf_base::F,
+        )
+        .ok_or(NO_INFINITY)
+    }
+
+    /// Build an atom of the negative infinity of a type, e.g., `-0Wj` for long.
+    /// # Parameters
+    /// - `qtype`: Type of the infinity. Either of the atom type and the list type is accepted.
+    /// # Errors
+    /// `"type has no infinity"` if the type is not short, int, long, real, float or a temporal type.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn borders_of(qtype: K) -> K{
+    ///   let borders = match qtype.get_short(){
+    ///     Ok(qtype) => [KVal::null_of(qtype as i8), KVal::inf_of(qtype as i8), KVal::ninf_of(qtype as i8)],
+    ///     Err(_) => return new_error("type must be a short\0")
+    ///   };
+    ///   match borders.into_iter().collect::<Result<KVal, _>>(){
+    ///     Ok(borders) => borders.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)borders_of: `libapi_examples 2: (`borders_of; 1);
+    /// q)borders_of -19h
+    /// 0Nt
+    /// 0Wt
+    /// -0Wt
+    /// q)borders_of -11h
+    /// 'type has no infinity
+    /// ```
+    pub fn ninf_of(qtype: i8) -> Result<KVal<'static>, &'static str> {
+        border_of(
+            qtype.wrapping_abs(),
+            qninf_base::H,
+            qninf_base::I,
+            qninf_base::J,
+            qninf_base::E,
+            qninf_base::F,
+        )
+        .ok_or(NO_INFINITY)
+    }
+}
+
+impl<'a> KVal<'a> {
+    /// Check if the value is a null atom of its type, e.g., `0Nj`, `0n`, `0Ng`, `` ` `` or `" "`. The general null
+    ///  `(::)` is also a null as `null (::)` is true in q. A list is never a null atom.
+    /// # Example
+    /// ```
+    /// use kdbplus::qnull_base;
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// assert!(KVal::Timespan(KData::Atom(Cow::Owned(qnull_base::J))).is_null_atom());
+    /// assert!(KVal::Real(KData::Atom(Cow::Owned(f32::NAN))).is_null_atom());
+    /// assert!(!KVal::Long(KData::List(Cow::Owned(vec![qnull_base::J]))).is_null_atom());
+    /// ```
+    pub fn is_null_atom(&self) -> bool {
+        match self {
+            KVal::Char(character) => *character == qnull_base::C,
+            KVal::Null => true,
+            value => test_atom!(value, is_null_value),
+        }
+    }
+
+    /// Check if the value is an atom of the positive infinity of its type, e.g., `0Wj`, `0w` or `0Wd`.
+    pub fn is_inf_atom(&self) -> bool {
+        test_atom!(self, is_inf_value)
+    }
+
+    /// Check if the value is an atom of the negative infinity of its type, e.g., `-0Wj`, `-0w` or `-0Wd`.
+    pub fn is_ninf_atom(&self) -> bool {
+        test_atom!(self, is_ninf_value)
+    }
+
+    /// Replace nulls with elements of `fallback` in the same manner as `fallback^self` in q.
+    /// - Two lists must have the same length.
+    /// - An atom fallback fills every null of a list.
+    /// - A list fallback for an atom results in a list of the length of the fallback.
+    ///
+    /// Supported types are bool, GUID, byte, short, int, long, real, float, symbol and temporal types. Both values
+    ///  must have the same type. Bool and byte have no null and therefore `self` is returned as it is.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let primary = KVal::Float(KData::List(Cow::Owned(vec![1.0, f64::NAN, 3.0, f64::NAN])));
+    /// let fallback = KVal::Float(KData::List(Cow::Owned(vec![9.0, 8.0, 7.0, 6.0])));
+    /// match primary.coalesce(&fallback).unwrap(){
+    ///   KVal::Float(KData::List(filled)) => assert_eq!(filled.as_ref(), &[1.0, 8.0, 3.0, 6.0]),
+    ///   _ => unreachable!()
+    /// }
+    /// ```
+    /// ```q
+    /// q)9 8 7 6f^1 0n 3 0n
+    /// 1 8 3 6f
+    /// ```
+    pub fn coalesce(self, fallback: &KVal) -> Result<KVal<'a>, KError> {
+        match (self, fallback) {
+            (KVal::Bool(data), KVal::Bool(fill)) => coalesce_data(data, fill).map(KVal::Bool),
+            (KVal::Guid(data), KVal::Guid(fill)) => coalesce_data(data, fill).map(KVal::Guid),
+            (KVal::Byte(data), KVal::Byte(fill)) => coalesce_data(data, fill).map(KVal::Byte),
+            (KVal::Short(data), KVal::Short(fill)) => coalesce_data(data, fill).map(KVal::Short),
+            (KVal::Int(data), KVal::Int(fill)) => coalesce_data(data, fill).map(KVal::Int),
+            (KVal::Long(data), KVal::Long(fill)) => coalesce_data(data, fill).map(KVal::Long),
+            (KVal::Real(data), KVal::Real(fill)) => coalesce_data(data, fill).map(KVal::Real),
+            (KVal::Float(data), KVal::Float(fill)) => coalesce_data(data, fill).map(KVal::Float),
+            (KVal::Symbol(data), KVal::Symbol(fill)) => coalesce_data(data, fill).map(KVal::Symbol),
+            (KVal::Timestamp(data), KVal::Timestamp(fill)) => {
+                coalesce_data(data, fill).map(KVal::Timestamp)
+            }
+            (KVal::Month(data), KVal::Month(fill)) => coalesce_data(data, fill).map(KVal::Month),
+            (KVal::Date(data), KVal::Date(fill)) => coalesce_data(data, fill).map(KVal::Date),
+            (KVal::Datetime(data), KVal::Datetime(fill)) => {
+                coalesce_data(data, fill).map(KVal::Datetime)
+            }
+            (KVal::Timespan(data), KVal::Timespan(fill)) => {
+                coalesce_data(data, fill).map(KVal::Timespan)
+            }
+            (KVal::Minute(data), KVal::Minute(fill)) => coalesce_data(data, fill).map(KVal::Minute),
+            (KVal::Second(data), KVal::Second(fill)) => coalesce_data(data, fill).map(KVal::Second),
+            (KVal::Time(data), KVal::Time(fill)) => coalesce_data(data, fill).map(KVal::Time),
+            (
+                value @ (KVal::Bool(_)
+                | KVal::Guid(_)
+                | KVal::Byte(_)
+                | KVal::Short(_)
+                | KVal::Int(_)
+                | KVal::Long(_)
+                | KVal::Real(_)
+                | KVal::Float(_)
+                | KVal::Symbol(_)
+                | KVal::Timestamp(_)
+                | KVal::Month(_)
+                | KVal::Date(_)
+                | KVal::Datetime(_)
+                | KVal::Timespan(_)
+                | KVal::Minute(_)
+                | KVal::Second(_)
+                | KVal::Time(_)),
+                _,
+            ) => Err(KError::invalid_operation(
+                "coalesce",
+                fallback.type_name(),
+                Some(value.type_name()),
+            )),
+            (value, _) => Err(KError::invalid_operation(
+                "coalesce",
+                value.type_name(),
+                None,
+            )),
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Build the atom which q picks for an index out of bounds of a list, e.g., the null of a simple list. Bool and byte,
+///  which have no null, result in `0b` and `0x00`. A compound list results in an empty string if its first element
+///  is a string, the null of its first element if it is an atom, or a general null otherwise.
+pub(super) fn prototype(list: &KVal) -> KVal<'static> {
+    match list {
+        KVal::Enum(_, source) => KVal::Enum(KData::Atom(Cow::Owned(qnull_base::J)), source.clone()),
+        KVal::CompoundList(elements) => match elements.first() {
+            Some(KVal::String(_)) => KVal::String(Cow::Owned(Vec::new())),
+            Some(element) if element.qtype() < 0 => prototype(element),
+            _ => KVal::Null,
+        },
+        _ => KVal::null_of(list.qtype()).unwrap_or_else(|_| match list.qtype().wrapping_abs() {
+            qtype::BOOL_LIST => KVal::Bool(KData::Atom(Cow::Owned(false))),
+            qtype::BYTE_LIST => KVal::Byte(KData::Atom(Cow::Owned(0))),
+            _ => KVal::Null,
+        }),
+    }
+}
+
+/// Build an atom of the type specified by a list type from a border value (null or infinity) of each underlying
+///  type. Returns `None` if the type is not numeric or temporal or has no border.
+fn border_of(
+    list_type: i8,
+    short: i16,
+    int: i32,
+    long: i64,
+    real: f32,
+    float: f64,
+) -> Option<KVal<'static>> {
+    Some(match list_type {
+        qtype::SHORT_LIST => KVal::Short(KData::Atom(Cow::Owned(short))),
+        qtype::INT_LIST => KVal::Int(KData::Atom(Cow::Owned(int))),
+        qtype::LONG_LIST => KVal::Long(KData::Atom(Cow::Owned(long))),
+        qtype::REAL_LIST => KVal::Real(KData::Atom(Cow::Owned(real))),
+        qtype::FLOAT_LIST => KVal::Float(KData::Atom(Cow::Owned(float))),
+        qtype::TIMESTAMP_LIST => KVal::Timestamp(KData::Atom(Cow::Owned(long))),
+        qtype::MONTH_LIST => KVal::Month(KData::Atom(Cow::Owned(int))),
+        qtype::DATE_LIST => KVal::Date(KData::Atom(Cow::Owned(int))),
+        qtype::DATETIME_LIST => KVal::Datetime(KData::Atom(Cow::Owned(float))),
+        qtype::TIMESPAN_LIST => KVal::Timespan(KData::Atom(Cow::Owned(long))),
+        qtype::MINUTE_LIST => KVal::Minute(KData::Atom(Cow::Owned(int))),
+        qtype::SECOND_LIST => KVal::Second(KData::Atom(Cow::Owned(int))),
+        qtype::TIME_LIST => KVal::Time(KData::Atom(Cow::Owned(int))),
+        _ => return None,
+    })
+}
+
+/// Replace nulls of `data` with elements of `fill`. A list without null is returned as it is.
+fn coalesce_data<'a, T: Nullable>(
+    data: KData<'a, T>,
+    fill: &KData<'_, T>,
+) -> Result<KData<'a, T>, KError> {
+    match (data, fill) {
+        (KData::Atom(atom), KData::Atom(fill)) => {
+            if atom.is_null_value() {
+                Ok(KData::Atom(Cow::Owned(fill.as_ref().clone())))
+            } else {
+                Ok(KData::Atom(atom))
+            }
+        }
+        (KData::Atom(atom), KData::List(fill)) => Ok(KData::List(Cow::Owned(
+            fill.iter()
+                .map(|element| match atom.is_null_value() {
+                    true => element.clone(),
+                    false => atom.as_ref().clone(),
+                })
+                .collect(),
+        ))),
+        (KData::List(list), KData::Atom(fill)) => {
+            if !list.iter().any(Nullable::is_null_value) {
+                return Ok(KData::List(list));
+            }
+            Ok(KData::List(Cow::Owned(
+                list.iter()
+                    .map(|element| match element.is_null_value() {
+                        true => fill.as_ref().clone(),
+                        false => element.clone(),
+                    })
+                    .collect(),
+            )))
+        }
+        (KData::List(list), KData::List(fill)) => {
+            if list.len() != fill.len() {
+                return Err(KError::length_mismatch(list.len(), fill.len()));
+            }
+            if !list.iter().any(Nullable::is_null_value) {
+                return Ok(KData::List(list));
+            }
+            Ok(KData::List(Cow::Owned(
+                list.iter()
+                    .zip(fill.iter())
+                    .map(|(element, fill)| match element.is_null_value() {
+                        true => fill.clone(),
+                        false => element.clone(),
+                    })
+                    .collect(),
+            )))
+        }
+    }
+}
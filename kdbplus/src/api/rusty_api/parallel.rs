@@ -0,0 +1,118 @@
+//! Parallel copy of long lists into q objects built by [`KVal::to_k`](enum.KVal.html#method.to_k). Available only
+//!  when `parallel` feature is enabled.
+//!
+//! Only the elements are written in parallel. Every q object is still allocated on the calling thread, so a compound
+//!  list gains from this through its long simple lists, e.g., the columns of a table.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::super::{native, pin_symbol, G, S};
+use super::kval::{enumerate_str, NULL_IN_SYMBOL};
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Default number of elements from which a list is copied in parallel.
+const DEFAULT_PARALLEL_THRESHOLD: usize = 1 << 20;
+
+/// Number of elements copied by a task.
+const CHUNK: usize = 1 << 16;
+
+/// Current number of elements from which a list is copied in parallel.
+static PARALLEL_THRESHOLD: AtomicUsize = AtomicUsize::new(DEFAULT_PARALLEL_THRESHOLD);
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Public Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Get the number of elements from which [`KVal::to_k`](enum.KVal.html#method.to_k) copies a simple list in
+///  parallel. Defaults to 1,048,576.
+///
+/// Available only when `parallel` feature is enabled.
+pub fn parallel_threshold() -> usize {
+    PARALLEL_THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// Set the number of elements from which [`KVal::to_k`](enum.KVal.html#method.to_k) copies a simple list in
+///  parallel with `rayon`. The threshold is shared by the whole process. `usize::MAX` disables parallel copy.
+///  Returns the previous threshold.
+///
+/// Symbols of a long symbol list are interned from the threads of `rayon` with the symbol lock of q enabled by
+///  [`pin_symbol`](../fn.pin_symbol.html). The previous state of the lock is restored afterwards.
+///
+/// Available only when `parallel` feature is enabled.
+/// # Example
+/// ```
+/// use kdbplus::api::rusty_api::*;
+///
+/// // Copy lists of 10,000 elements or more in parallel.
+/// let previous = set_parallel_threshold(10_000);
+/// assert_eq!(parallel_threshold(), 10_000);
+/// set_parallel_threshold(previous);
+/// ```
+pub fn set_parallel_threshold(threshold: usize) -> usize {
+    PARALLEL_THRESHOLD.swap(threshold, Ordering::Relaxed)
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Check if a list of the length is copied in parallel.
+pub(super) fn is_long(len: usize) -> bool {
+    len >= parallel_threshold()
+}
+
+/// Copy elements into the buffer of a new list in chunks.
+pub(super) fn copy<T: Copy + Send + Sync>(buffer: &mut [T], list: &[T]) {
+    buffer
+        .par_chunks_mut(CHUNK)
+        .zip(list.par_chunks(CHUNK))
+        .for_each(|(target, source)| target.copy_from_slice(source));
+}
+
+/// Copy bools into the buffer of a new bool list in chunks.
+pub(super) fn copy_bools(buffer: &mut [G], list: &[bool]) {
+    buffer
+        .par_chunks_mut(CHUNK)
+        .zip(list.par_chunks(CHUNK))
+        .for_each(|(target, source)| {
+            target
+                .iter_mut()
+                .zip(source.iter())
+                .for_each(|(byte, boolean)| *byte = *boolean as G)
+        });
+}
+
+/// Intern symbols into the buffer of a new symbol list with the symbol lock of q enabled. No symbol is interned if
+///  any of them contains a null character.
+pub(super) fn intern(buffer: &mut [S], list: &[String]) -> Result<(), &'static str> {
+    if list.par_iter().any(|symbol| symbol.as_bytes().contains(&0)) {
+        return Err(NULL_IN_SYMBOL);
+    }
+    // A raw pointer is not `Send`. The interned symbols live as long as the q process, so their addresses are written
+    //  as integers of the same size.
+    let slots =
+        unsafe { std::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut usize, buffer.len()) };
+    let previous = pin_symbol();
+    slots
+        .par_chunks_mut(CHUNK)
+        .zip(list.par_chunks(CHUNK))
+        .for_each(|(target, source)| {
+            target
+                .iter_mut()
+                .zip(source.iter())
+                .for_each(|(slot, symbol)| {
+                    *slot = enumerate_str(symbol).unwrap_or_else(|_| {
+                        unreachable!("symbols were checked not to contain a null character")
+                    }) as usize
+                })
+        });
+    unsafe { native::setm(previous) };
+    Ok(())
+}
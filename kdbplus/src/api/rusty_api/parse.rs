@@ -0,0 +1,581 @@
+//! Parser of a subset of q literal syntax into `KVal`, e.g., to build expected values of tests from readable text
+//!  without a q process.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::compound::collapse_atoms;
+use super::temporal::{civil_from_days, days_from_civil};
+use super::{KData, KDict, KVal};
+use crate::{qinf_base, qninf_base, qnull_base};
+use std::borrow::Cow;
+use std::error::Error as StdError;
+use std::fmt;
+use std::str::FromStr;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Type suffixes accepted at the end of a number.
+const SUFFIXES: &str = "bhijefdp";
+
+/// One day in nanoseconds.
+const ONE_DAY_NANOS: i64 = 86_400_000_000_000;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Error from parsing a q literal by [`KVal::parse`](enum.KVal.html#method.parse).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Byte offset in the input where the error was found.
+    pub position: usize,
+    /// Description of the error.
+    pub message: String,
+}
+
+/// Type of a number decided from its suffix or its form.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Bool,
+    Short,
+    Int,
+    Long,
+    Real,
+    Float,
+    Date,
+    Timestamp,
+}
+
+/// Number in a simple list. The suffix is removed from the body.
+struct Number<'a> {
+    /// Byte offset of the number in the input.
+    position: usize,
+    body: &'a str,
+    suffix: Option<char>,
+}
+
+/// Recursive descent parser over the input.
+struct Parser<'a> {
+    input: &'a str,
+    /// Byte offset of the next character.
+    position: usize,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl KVal<'static> {
+    /// Parse a q literal into a value without a q process. The supported subset is:
+    /// - Numbers with optional type suffixes `b`, `h`, `i`, `j`, `e` and `f`, e.g., `1i` and `2.5e`, including nulls
+    ///   and infinities such as `0N`, `0n`, `0W` and `-0w`.
+    /// - Dates and timestamps, e.g., `2020.04.01` and `2020.04.01D12:00:00.000000001`. `0Nd` and `0Np` are their
+    ///   nulls.
+    /// - Simple lists of numbers separated by spaces, e.g., `1 2 3h`. A list of integers and floats is a float list.
+    /// - Symbols, e.g., `` `a `` and `` `a`b`c ``, and strings with escapes `\"`, `\\`, `\n`, `\r`, `\t` and
+    ///   `\ooo`. A string of one character is a char atom as in q.
+    /// - General lists `(a;b;c)`, which are simple lists if the elements are atoms of the same type, `()` and
+    ///   enlist `,x`.
+    /// - Dictionaries `keys!values`.
+    /// # Errors
+    /// [`ParseError`](struct.ParseError.html) with the byte offset of any other syntax or an invalid literal.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let config = KVal::parse("`port`hosts!(5001i;`a`b)").unwrap();
+    /// assert_eq!(config, KVal::Dictionary(KDict::new(
+    ///   KVal::Symbol(KData::List(Cow::Owned(vec!["port".to_string(), "hosts".to_string()]))),
+    ///   KVal::CompoundList(vec![
+    ///     KVal::Int(KData::Atom(Cow::Owned(5001))),
+    ///     KVal::Symbol(KData::List(Cow::Owned(vec!["a".to_string(), "b".to_string()])))
+    ///   ])
+    /// )));
+    ///
+    /// let error = KVal::parse("1 2 + 3").unwrap_err();
+    /// assert_eq!(error.to_string(), "unexpected '+' at 4");
+    /// ```
+    pub fn parse(input: &str) -> Result<KVal<'static>, ParseError> {
+        let mut parser = Parser { input, position: 0 };
+        let value = parser.expression()?;
+        parser.skip_spaces();
+        match parser.peek() {
+            None => Ok(value),
+            Some(character) => parser.error(format!("unexpected '{}'", character)),
+        }
+    }
+}
+
+impl FromStr for KVal<'static> {
+    type Err = ParseError;
+
+    /// Parse a q literal in the same manner as [`KVal::parse`](enum.KVal.html#method.parse).
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        KVal::parse(input)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.position)
+    }
+}
+
+impl StdError for ParseError {}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    /// Check the character after the next one.
+    fn peek_second(&self) -> Option<char> {
+        self.input[self.position..].chars().nth(1)
+    }
+
+    fn skip_spaces(&mut self) {
+        while let Some(character) = self.peek().filter(|character| character.is_whitespace()) {
+            self.position += character.len_utf8();
+        }
+    }
+
+    /// Build an error at the current position.
+    fn error<T>(&self, message: String) -> Result<T, ParseError> {
+        error_at(self.position, message)
+    }
+
+    /// `term` or `term!expression`.
+    fn expression(&mut self) -> Result<KVal<'static>, ParseError> {
+        self.skip_spaces();
+        let start = self.position;
+        let keys = self.term()?;
+        self.skip_spaces();
+        if self.peek() != Some('!') {
+            return Ok(keys);
+        }
+        self.position += 1;
+        let values = self.expression()?;
+        if keys.qtype() < 0 {
+            return error_at(start, "keys of a dictionary must be a list".to_string());
+        }
+        if keys.len() != values.len() {
+            return error_at(
+                start,
+                format!(
+                    "length mismatch of keys and values: {} and {}",
+                    keys.len(),
+                    values.len()
+                ),
+            );
+        }
+        Ok(KVal::Dictionary(KDict::new(keys, values)))
+    }
+
+    fn term(&mut self) -> Result<KVal<'static>, ParseError> {
+        match self.peek() {
+            None => self.error("unexpected end of input".to_string()),
+            Some('`') => Ok(self.symbols()),
+            Some('"') => self.string(),
+            Some('(') => self.general_list(),
+            Some(',') => {
+                self.position += 1;
+                self.skip_spaces();
+                Ok(collapse_atoms(vec![self.term()?]))
+            }
+            Some(character) if starts_number(character, self.peek_second()) => self.numbers(),
+            Some(character) => self.error(format!("unsupported syntax '{}'", character)),
+        }
+    }
+
+    /// `` `a `` or `` `a`b ``.
+    fn symbols(&mut self) -> KVal<'static> {
+        let mut symbols = Vec::new();
+        while self.peek() == Some('`') {
+            self.position += 1;
+            let start = self.position;
+            while let Some(character) = self.peek().filter(|character| {
+                character.is_ascii_alphanumeric() || matches!(character, '_' | '.' | ':' | '/')
+            }) {
+                self.position += character.len_utf8();
+            }
+            symbols.push(self.input[start..self.position].to_string());
+        }
+        match symbols.len() {
+            1 => KVal::Symbol(KData::Atom(Cow::Owned(symbols.remove(0)))),
+            _ => KVal::Symbol(KData::List(Cow::Owned(symbols))),
+        }
+    }
+
+    /// String enclosed by `"`. A string of one byte is a char atom.
+    fn string(&mut self) -> Result<KVal<'static>, ParseError> {
+        let start = self.position;
+        self.position += 1;
+        let mut string = Vec::new();
+        loop {
+            let escape = self.position;
+            match self.peek() {
+                None => return error_at(start, "unterminated string".to_string()),
+                Some('"') => {
+                    self.position += 1;
+                    break;
+                }
+                Some('\\') => {
+                    self.position += 1;
+                    let escaped = match self.peek() {
+                        Some(character @ ('"' | '\\')) => character as u8,
+                        Some('n') => b'\n',
+                        Some('r') => b'\r',
+                        Some('t') => b'\t',
+                        Some('0'..='7') => {
+                            let digits = self.input[self.position..].get(..3).filter(|digits| {
+                                digits.bytes().all(|byte| (b'0'..=b'7').contains(&byte))
+                            });
+                            match digits.and_then(|digits| u8::from_str_radix(digits, 8).ok()) {
+                                Some(byte) => {
+                                    self.position += 2;
+                                    byte
+                                }
+                                _ => return error_at(escape, "invalid octal escape".to_string()),
+                            }
+                        }
+                        _ => return error_at(escape, "invalid escape".to_string()),
+                    };
+                    self.position += 1;
+                    string.push(escaped);
+                }
+                Some(character) => {
+                    self.position += character.len_utf8();
+                    string.extend_from_slice(character.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+            }
+        }
+        Ok(match string.len() {
+            1 => KVal::Char(string[0] as char),
+            _ => KVal::String(Cow::Owned(string)),
+        })
+    }
+
+    /// `()`, `(x)` or `(a;b;c)`.
+    fn general_list(&mut self) -> Result<KVal<'static>, ParseError> {
+        self.position += 1;
+        self.skip_spaces();
+        if self.peek() == Some(')') {
+            self.position += 1;
+            return Ok(KVal::CompoundList(Vec::new()));
+        }
+        let mut elements = Vec::new();
+        loop {
+            elements.push(self.expression()?);
+            self.skip_spaces();
+            match self.peek() {
+                Some(';') => self.position += 1,
+                Some(')') => {
+                    self.position += 1;
+                    break;
+                }
+                _ => return self.error("expected ';' or ')'".to_string()),
+            }
+        }
+        Ok(match elements.len() {
+            1 => elements.remove(0),
+            _ => collapse_atoms(elements),
+        })
+    }
+
+    /// Number or numbers separated by spaces.
+    fn numbers(&mut self) -> Result<KVal<'static>, ParseError> {
+        let mut numbers = vec![self.number()];
+        loop {
+            let rest = &self.input[self.position..];
+            let spaces = rest.len() - rest.trim_start_matches(' ').len();
+            let mut next = rest[spaces..].chars();
+            match next.next() {
+                Some(character) if spaces > 0 && starts_number(character, next.next()) => {
+                    self.position += spaces;
+                    numbers.push(self.number());
+                }
+                _ => break,
+            }
+        }
+        build_numbers(&numbers)
+    }
+
+    /// Take a number and split its suffix.
+    fn number(&mut self) -> Number<'a> {
+        let start = self.position;
+        let mut previous = None;
+        while let Some(character) = self.peek().filter(|character| {
+            character.is_ascii_alphanumeric()
+                || matches!(character, '.' | ':')
+                || (matches!(character, '-' | '+') && (previous.is_none() || previous == Some('e')))
+        }) {
+            previous = Some(character);
+            self.position += 1;
+        }
+        let text = &self.input[start..self.position];
+        let suffix = text
+            .chars()
+            .last()
+            .filter(|suffix| text.len() > 1 && SUFFIXES.contains(*suffix))
+            .filter(|_| {
+                let before = text[..text.len() - 1].chars().last();
+                matches!(before, Some('0'..='9' | '.' | 'N' | 'W' | 'n' | 'w'))
+            });
+        Number {
+            position: start,
+            body: &text[..text.len() - suffix.map_or(0, |_| 1)],
+            suffix,
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+fn error_at<T>(position: usize, message: String) -> Result<T, ParseError> {
+    Err(ParseError { position, message })
+}
+
+/// Check if a number starts with the characters, e.g., `1`, `-1` or `.5`.
+fn starts_number(first: char, second: Option<char>) -> bool {
+    first.is_ascii_digit()
+        || (matches!(first, '-' | '.') && second.is_some_and(|second| second.is_ascii_digit()))
+}
+
+/// Check if a body is a null or an infinity without a type, which takes the type of the list.
+fn is_border(body: &str) -> bool {
+    matches!(body, "0N" | "0W" | "-0W")
+}
+
+/// Decide the type of a number from its form.
+fn kind_of(number: &Number) -> Result<Kind, ParseError> {
+    let body = number.body;
+    let kind = match number.suffix {
+        Some('b') => Kind::Bool,
+        Some('h') => Kind::Short,
+        Some('i') => Kind::Int,
+        Some('j') => Kind::Long,
+        Some('e') => Kind::Real,
+        Some('f') => Kind::Float,
+        Some('d') => Kind::Date,
+        Some('p') => Kind::Timestamp,
+        _ if is_border(body) => Kind::Long,
+        _ if matches!(body, "0n" | "0w" | "-0w") => Kind::Float,
+        _ if body.contains('D') => Kind::Timestamp,
+        _ if body.matches('.').count() == 2 => Kind::Date,
+        _ if body
+            .bytes()
+            .all(|byte| byte.is_ascii_digit() || byte == b'-') =>
+        {
+            Kind::Long
+        }
+        _ if body.contains(['.', 'e']) => Kind::Float,
+        _ => return error_at(number.position, format!("unsupported literal '{}'", body)),
+    };
+    Ok(kind)
+}
+
+/// Build an atom or a simple list of numbers. The suffix of the last number is the type of the list, otherwise the
+///  type is decided from the forms, where integers in a list of floats are floats.
+fn build_numbers(numbers: &[Number]) -> Result<KVal<'static>, ParseError> {
+    let last = &numbers[numbers.len() - 1];
+    for number in &numbers[..numbers.len() - 1] {
+        if number.suffix.is_some() && number.suffix != last.suffix {
+            return error_at(
+                number.position,
+                "type suffix must be at the end of a list".to_string(),
+            );
+        }
+    }
+    let kind = if last.suffix.is_some() {
+        kind_of(last)?
+    } else {
+        let mut kind = None;
+        for number in numbers.iter().filter(|number| !is_border(number.body)) {
+            let element = kind_of(number)?;
+            kind = match (kind, element) {
+                (None, element) => Some(element),
+                (Some(Kind::Long), Kind::Float) | (Some(Kind::Float), Kind::Long) => {
+                    Some(Kind::Float)
+                }
+                (Some(kind), element) if kind == element => Some(kind),
+                _ => return error_at(number.position, "mixed types in a list".to_string()),
+            };
+        }
+        kind.unwrap_or(Kind::Long)
+    };
+
+    macro_rules! build {
+        ($variant: ident, $element: expr) => {{
+            let mut values = Vec::with_capacity(numbers.len());
+            for number in numbers {
+                values.push($element(number)?);
+            }
+            if numbers.len() == 1 {
+                KVal::$variant(KData::Atom(Cow::Owned(values.remove(0))))
+            } else {
+                KVal::$variant(KData::List(Cow::Owned(values)))
+            }
+        }};
+    }
+
+    Ok(match kind {
+        Kind::Bool => bools(numbers)?,
+        Kind::Short => build!(Short, |number: &Number| integer(
+            number,
+            qnull_base::H,
+            qinf_base::H,
+            qninf_base::H
+        )),
+        Kind::Int => build!(Int, |number: &Number| integer(
+            number,
+            qnull_base::I,
+            qinf_base::I,
+            qninf_base::I
+        )),
+        Kind::Long => build!(Long, |number: &Number| integer(
+            number,
+            qnull_base::J,
+            qinf_base::J,
+            qninf_base::J
+        )),
+        Kind::Real => build!(Real, |number: &Number| float(number)
+            .map(|float| float as f32)),
+        Kind::Float => build!(Float, float),
+        Kind::Date => build!(Date, date),
+        Kind::Timestamp => build!(Timestamp, timestamp),
+    })
+}
+
+/// Bools of a single number made of `0` and `1`, e.g., `101b`.
+fn bools(numbers: &[Number]) -> Result<KVal<'static>, ParseError> {
+    let number = &numbers[0];
+    if numbers.len() != 1 {
+        return error_at(
+            number.position,
+            "bools must be written as one number, e.g., 101b".to_string(),
+        );
+    }
+    let mut bools = Vec::with_capacity(number.body.len());
+    for digit in number.body.chars() {
+        match digit {
+            '0' => bools.push(false),
+            '1' => bools.push(true),
+            _ => return error_at(number.position, format!("invalid bool '{}'", number.body)),
+        }
+    }
+    Ok(match bools.len() {
+        1 => KVal::Bool(KData::Atom(Cow::Owned(bools[0]))),
+        _ => KVal::Bool(KData::List(Cow::Owned(bools))),
+    })
+}
+
+/// Integer of a width given by its null and infinities.
+fn integer<T: FromStr + Copy>(number: &Number, null: T, inf: T, ninf: T) -> Result<T, ParseError> {
+    match number.body {
+        "0N" => Ok(null),
+        "0W" => Ok(inf),
+        "-0W" => Ok(ninf),
+        body => body.parse().or_else(|_| {
+            error_at(
+                number.position,
+                format!("invalid or out of range integer '{}'", body),
+            )
+        }),
+    }
+}
+
+fn float(number: &Number) -> Result<f64, ParseError> {
+    match number.body {
+        "0N" | "0n" => Ok(qnull_base::F),
+        "0W" | "0w" => Ok(qinf_base::F),
+        "-0W" | "-0w" => Ok(qninf_base::F),
+        body => body
+            .parse()
+            .or_else(|_| error_at(number.position, format!("invalid float '{}'", body))),
+    }
+}
+
+/// Days since `2000.01.01` of `yyyy.mm.dd`.
+fn date(number: &Number) -> Result<i32, ParseError> {
+    match number.body {
+        "0N" => Ok(qnull_base::I),
+        "0W" => Ok(qinf_base::I),
+        "-0W" => Ok(qninf_base::I),
+        body => parse_date(body)
+            .map(|days| days as i32)
+            .ok_or_else(|| ParseError {
+                position: number.position,
+                message: format!("invalid date '{}'", body),
+            }),
+    }
+}
+
+/// Nanoseconds since `2000.01.01D00:00:00` of `yyyy.mm.ddDhh:mm[:ss[.nnnnnnnnn]]`.
+fn timestamp(number: &Number) -> Result<i64, ParseError> {
+    let invalid = || ParseError {
+        position: number.position,
+        message: format!("invalid timestamp '{}'", number.body),
+    };
+    match number.body {
+        "0N" => Ok(qnull_base::J),
+        "0W" => Ok(qinf_base::J),
+        "-0W" => Ok(qninf_base::J),
+        body => {
+            let (date, time) = body.split_once('D').ok_or_else(invalid)?;
+            let days = parse_date(date).ok_or_else(invalid)?;
+            let nanos = parse_clock(time).ok_or_else(invalid)?;
+            days.checked_mul(ONE_DAY_NANOS)
+                .and_then(|days| days.checked_add(nanos))
+                .ok_or_else(invalid)
+        }
+    }
+}
+
+/// Days since `2000.01.01` of a valid date `yyyy.mm.dd`.
+fn parse_date(text: &str) -> Option<i64> {
+    let mut parts = text.splitn(3, '.');
+    let year = parts.next()?;
+    let (month, day) = (parts.next()?, parts.next()?);
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return None;
+    }
+    let (year, month, day) = (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+    let days = days_from_civil(year, month, day);
+    (civil_from_days(days) == (year, month, day)).then_some(days)
+}
+
+/// Nanoseconds of `hh:mm[:ss[.nnnnnnnnn]]`. An empty text is midnight.
+fn parse_clock(text: &str) -> Option<i64> {
+    if text.is_empty() {
+        return Some(0);
+    }
+    let (clock, fraction) = text.split_once('.').unwrap_or((text, ""));
+    let mut units = clock.split(':');
+    let hours = units.next()?;
+    let minutes = units.next()?;
+    let seconds = units.next().unwrap_or("00");
+    if units.next().is_some()
+        || fraction.len() > 9
+        || [hours, minutes, seconds].iter().any(|unit| unit.len() != 2)
+        || !fraction.bytes().all(|byte| byte.is_ascii_digit())
+    {
+        return None;
+    }
+    let (hours, minutes, seconds) = (
+        hours.parse::<i64>().ok()?,
+        minutes.parse::<i64>().ok()?,
+        seconds.parse::<i64>().ok()?,
+    );
+    if hours > 23 || minutes > 59 || seconds > 59 {
+        return None;
+    }
+    let fraction = format!("{:0<9}", fraction).parse::<i64>().ok()?;
+    Some(((hours * 60 + minutes) * 60 + seconds) * 1_000_000_000 + fraction)
+}
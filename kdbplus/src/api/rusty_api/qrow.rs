@@ -0,0 +1,289 @@
+//! Mapping of Rust structs into rows of a table with [`QRow`](trait.QRow.html), which is derived for a struct with
+//!  named fields. Each field is converted with [`QField`](trait.QField.html). Available only when `derive` feature is
+//!  enabled.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::compound::collapse_atoms;
+use super::errors::{INVALID_UTF8, TYPE};
+use super::kval::atom;
+use super::{KData, KDict, KTable, KVal, RowValues};
+use crate::qtype;
+use std::borrow::Cow;
+use std::convert::Infallible;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Traits
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Type of a field which is stored as a cell of a table column.
+///
+/// | Rust type | q type                                               |
+/// |-----------|------------------------------------------------------|
+/// | `bool`    | bool                                                 |
+/// | `u8`      | byte                                                 |
+/// | `i16`     | short                                                |
+/// | `i32`     | int, month, date, minute, second or time             |
+/// | `i64`     | long, timestamp or timespan                          |
+/// | `f32`     | real                                                 |
+/// | `f64`     | float or datetime                                    |
+/// | `char`    | char                                                 |
+/// | `String`  | symbol or string, i.e., a compound list of strings   |
+///
+/// The first type is the default. `Option` of a type with a q null is stored in the same manner and `None` is
+///  mapped into the null, e.g., `0Nj` for `Option<i64>` and `` ` `` or `""` for `Option<String>`.
+pub trait QField: Sized {
+    /// List types which the field can be stored as. The first one is the default.
+    const QTYPES: &'static [i8];
+
+    /// Build an atom of the value for a column of the list type, which is one of
+    ///  [`QTYPES`](#associatedconstant.QTYPES).
+    fn to_atom(&self, qtype: i8) -> KVal<'static>;
+
+    /// Take the value of an atom of a column of the list type.
+    /// # Errors
+    /// - `"type"` if the atom is not of the type of the column.
+    fn from_atom(value: &KVal, qtype: i8) -> Result<Self, &'static str>;
+
+    /// Take the value of a column of a row built as a dictionary from column names to cells.
+    /// # Errors
+    /// A message naming the column if the row is not a dictionary, the column is missing or the cell has a wrong type.
+    fn from_row_field(row: &KVal, column: &str, qtype: i8) -> Result<Self, String> {
+        let cell = match row {
+            KVal::Dictionary(row) => row.get(column),
+            _ => return Err("row must be a dictionary".to_string()),
+        };
+        match cell {
+            Some(cell) => Self::from_atom(&cell, qtype)
+                .map_err(|error| format!("column {}: {}", column, error.trim_end_matches('\0'))),
+            None => Err(format!("missing column {}", column)),
+        }
+    }
+}
+
+/// Rust struct mapped into a row of a table whose columns are its fields. Derived with `#[derive(QRow)]`, which
+///  selects the type of a column by the type of the field (see [`QField`](trait.QField.html)) or by
+///  `#[q(type = "...")]` naming a q type, e.g., `"timestamp"` for `i64` or `"string"` for `String`.
+/// # Example
+/// ```
+/// use kdbplus::api::rusty_api::*;
+///
+/// #[derive(QRow)]
+/// struct Trade{
+///   #[q(type = "timestamp")]
+///   time: i64,
+///   sym: String,
+///   price: f64,
+///   size: Option<i64>
+/// }
+///
+/// let trades = vec![Trade{time: 0, sym: "a".to_string(), price: 1.5, size: None}];
+/// let table = Trade::table_from_slice(&trades);
+/// assert_eq!(table.column_names(), vec!["time", "sym", "price", "size"]);
+/// let restored = Trade::from_table(&table).unwrap();
+/// assert_eq!(restored[0].size, None);
+/// ```
+pub trait QRow: Sized {
+    /// Get the names and the list types of the columns in the order of the fields.
+    fn schema() -> Vec<(&'static str, i8)>;
+
+    /// Build the cells of the row in the order of [`schema`](#tymethod.schema).
+    fn to_row_values(&self) -> RowValues;
+
+    /// Build a value from a row built as a dictionary from column names to cells, e.g., by
+    ///  [`to_kval_row`](#method.to_kval_row). Columns which are not fields are ignored.
+    /// # Errors
+    /// A message naming the column if the row is not a dictionary, a column is missing or a cell has a wrong type.
+    fn from_row(row: &KVal) -> Result<Self, String>;
+
+    /// Build the row as a dictionary from column names to cells.
+    fn to_kval_row(&self) -> KVal<'static> {
+        KVal::Dictionary(KDict::new(
+            KVal::Symbol(KData::List(Cow::Owned(
+                Self::schema()
+                    .into_iter()
+                    .map(|(name, _)| name.to_string())
+                    .collect(),
+            ))),
+            collapse_atoms(self.to_row_values()),
+        ))
+    }
+
+    /// Build a table whose rows are the values.
+    /// # Panics
+    /// If [`to_row_values`](#tymethod.to_row_values) does not match [`schema`](#tymethod.schema), which does not
+    ///  happen for a derived implementation.
+    fn table_from_slice(rows: &[Self]) -> KTable<'static> {
+        KTable::from_row_iter(
+            &Self::schema(),
+            rows.iter()
+                .map(|row| Ok::<_, Infallible>(row.to_row_values())),
+            rows.len().max(1),
+        )
+        .unwrap_or_else(|error| panic!("rows do not match the schema: {}", error))
+    }
+
+    /// Build the values from the rows of a table in the same manner as [`from_row`](#tymethod.from_row).
+    /// # Errors
+    /// A message naming the row and the column if a row cannot be converted, or if the table is a keyed table. If the
+    ///  columns do not have the same length, the message is `row n: length`, where `n` is the first row missing from
+    ///  a column.
+    fn from_table(table: &KTable) -> Result<Vec<Self>, String> {
+        let (names, columns) = match (table.dict.keys.as_ref(), table.dict.values.as_ref()) {
+            (KVal::Symbol(KData::List(names)), KVal::CompoundList(columns)) => (names, columns),
+            _ => return Err("expected table, got keyed table".to_string()),
+        };
+        if table.check_lengths().is_err() {
+            let row = columns.iter().map(KVal::len).min().unwrap_or_default();
+            return Err(format!("row {}: length", row));
+        }
+        (0..table.len())
+            .map(|row| {
+                let cells = columns
+                    .iter()
+                    .map(|column| {
+                        column
+                            .get(row)
+                            .unwrap_or_else(|| unreachable!("columns have the same length"))
+                    })
+                    .collect();
+                let values = KVal::Dictionary(KDict::new(
+                    KVal::Symbol(KData::List(Cow::Borrowed(names.as_ref()))),
+                    collapse_atoms(cells),
+                ));
+                Self::from_row(&values).map_err(|error| format!("row {}: {}", row, error))
+            })
+            .collect()
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Macros
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Implement `QField` for a type stored as either of the variants of `KVal` paired with list types.
+macro_rules! field {
+    ($type: ty, $(($qtype: path, $variant: path)),+) => {
+        impl QField for $type {
+            const QTYPES: &'static [i8] = &[$($qtype),+];
+
+            fn to_atom(&self, qtype: i8) -> KVal<'static> {
+                match qtype {
+                    $($qtype => $variant(atom(self.clone())),)+
+                    _ => unreachable!("list type is one of QTYPES"),
+                }
+            }
+
+            fn from_atom(value: &KVal, qtype: i8) -> Result<Self, &'static str> {
+                match (qtype, value) {
+                    $(($qtype, $variant(KData::Atom(value))) => Ok(value.as_ref().clone()),)+
+                    _ => Err(TYPE),
+                }
+            }
+        }
+    };
+}
+
+/// Implement `QField` for `Option` of types with a q null.
+macro_rules! nullable {
+    ($($type: ty),*) => {
+        $(
+            impl QField for Option<$type> {
+                const QTYPES: &'static [i8] = <$type as QField>::QTYPES;
+
+                fn to_atom(&self, qtype: i8) -> KVal<'static> {
+                    match self {
+                        Some(value) => value.to_atom(qtype),
+                        None => KVal::null_of(qtype).unwrap_or_else(|_| unreachable!("type has a null")),
+                    }
+                }
+
+                fn from_atom(value: &KVal, qtype: i8) -> Result<Self, &'static str> {
+                    let inner = <$type as QField>::from_atom(value, qtype)?;
+                    Ok((!value.is_null_atom()).then_some(inner))
+                }
+            }
+        )*
+    };
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+field!(bool, (qtype::BOOL_LIST, KVal::Bool));
+field!(u8, (qtype::BYTE_LIST, KVal::Byte));
+field!(i16, (qtype::SHORT_LIST, KVal::Short));
+field!(
+    i32,
+    (qtype::INT_LIST, KVal::Int),
+    (qtype::MONTH_LIST, KVal::Month),
+    (qtype::DATE_LIST, KVal::Date),
+    (qtype::MINUTE_LIST, KVal::Minute),
+    (qtype::SECOND_LIST, KVal::Second),
+    (qtype::TIME_LIST, KVal::Time)
+);
+field!(
+    i64,
+    (qtype::LONG_LIST, KVal::Long),
+    (qtype::TIMESTAMP_LIST, KVal::Timestamp),
+    (qtype::TIMESPAN_LIST, KVal::Timespan)
+);
+field!(f32, (qtype::REAL_LIST, KVal::Real));
+field!(
+    f64,
+    (qtype::FLOAT_LIST, KVal::Float),
+    (qtype::DATETIME_LIST, KVal::Datetime)
+);
+nullable!(i16, i32, i64, f32, f64, char);
+
+impl QField for char {
+    const QTYPES: &'static [i8] = &[qtype::STRING];
+
+    fn to_atom(&self, _: i8) -> KVal<'static> {
+        KVal::Char(*self)
+    }
+
+    fn from_atom(value: &KVal, _: i8) -> Result<Self, &'static str> {
+        match value {
+            KVal::Char(character) => Ok(*character),
+            _ => Err(TYPE),
+        }
+    }
+}
+
+impl QField for String {
+    const QTYPES: &'static [i8] = &[qtype::SYMBOL_LIST, qtype::COMPOUND_LIST];
+
+    fn to_atom(&self, qtype: i8) -> KVal<'static> {
+        match qtype {
+            qtype::SYMBOL_LIST => KVal::Symbol(atom(self.clone())),
+            _ => KVal::String(Cow::Owned(self.clone().into_bytes())),
+        }
+    }
+
+    fn from_atom(value: &KVal, qtype: i8) -> Result<Self, &'static str> {
+        match (qtype, value) {
+            (qtype::SYMBOL_LIST, KVal::Symbol(KData::Atom(symbol))) => Ok(symbol.to_string()),
+            (qtype::COMPOUND_LIST, KVal::String(string)) => {
+                String::from_utf8(string.to_vec()).map_err(|_| INVALID_UTF8)
+            }
+            _ => Err(TYPE),
+        }
+    }
+}
+
+impl QField for Option<String> {
+    const QTYPES: &'static [i8] = <String as QField>::QTYPES;
+
+    fn to_atom(&self, qtype: i8) -> KVal<'static> {
+        self.clone().unwrap_or_default().to_atom(qtype)
+    }
+
+    fn from_atom(value: &KVal, qtype: i8) -> Result<Self, &'static str> {
+        let inner = String::from_atom(value, qtype)?;
+        Ok((!inner.is_empty()).then_some(inner))
+    }
+}
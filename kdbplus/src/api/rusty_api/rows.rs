@@ -0,0 +1,377 @@
+//! Construction of a table from a stream of rows, e.g., records parsed from a file.
+//!
+//! Rows are collected into chunk tables of a fixed number of rows. [`KTable::row_chunks`] yields the chunks so that
+//!  a caller can insert each of them into q as soon as it is built and drop it, and [`KTable::from_row_iter`] appends
+//!  the chunks to one table. Either way at most one chunk is held besides the output, instead of every row being
+//!  collected before the columns are built.
+//!
+//! A single row is appended to an existing table with [`KTable::append_row`].
+//!
+//! [`KTable::append_row`]: struct.KTable.html#method.append_row
+//! [`KTable::row_chunks`]: struct.KTable.html#method.row_chunks
+//! [`KTable::from_row_iter`]: struct.KTable.html#method.from_row_iter
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::kdict::fits;
+use super::kval::qtype_name;
+use super::{KData, KDict, KError, KTable, KVal};
+use crate::qtype;
+use std::borrow::Cow;
+use std::fmt::Display;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Values of a row in the order of the columns of a schema. Each value is an atom of the type of its column, or any
+///  value for a compound list column.
+pub type RowValues = Vec<KVal<'static>>;
+
+/// Iterator over chunk tables built from a stream of rows. See [`KTable::row_chunks`](struct.KTable.html#method.row_chunks).
+pub struct RowChunks<I> {
+    /// Names of the columns.
+    names: Vec<String>,
+    /// List types of the columns.
+    types: Vec<i8>,
+    /// Stream of rows.
+    rows: I,
+    /// Maximum number of rows of a chunk.
+    chunk_rows: usize,
+    /// Index of the next row in the stream.
+    next_row: usize,
+    /// Whether the stream is exhausted or failed.
+    done: bool,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl KTable<'static> {
+    /// Build a table from a stream of rows. The rows are collected into chunks of `chunk_rows` rows with
+    ///  [`row_chunks`](#method.row_chunks) and each chunk is appended to the table and dropped, so that at most one
+    ///  chunk is held besides the table. An empty stream results in an empty table of the schema.
+    /// # Parameters
+    /// - `schema`: Names and list types of the columns, e.g., `("price", qtype::FLOAT_LIST)`, in the same form as
+    ///   [`peek_table_schema`](fn.peek_table_schema.html). `qtype::COMPOUND_LIST` accepts any value.
+    /// - `rows`: Stream of rows. An error of the stream aborts the construction.
+    /// - `chunk_rows`: Number of rows of a chunk. 0 is treated as 1.
+    /// # Errors
+    /// - `InvalidOperation` if the schema has an enum type or a type which is not a list type.
+    /// - `InvalidRow` naming the index of the row if the stream fails or a row does not match the schema.
+    /// # Example
+    /// ```
+    /// use kdbplus::qtype;
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let lines = ["a,1.5", "b,2.5", "c,x"];
+    /// let rows = lines.iter().map(|line| {
+    ///   let (sym, price) = line.split_once(',').unwrap();
+    ///   let price = price.parse::<f64>().map_err(|error| format!("price: {}", error))?;
+    ///   Ok::<RowValues, String>(vec![
+    ///     KVal::Symbol(KData::Atom(Cow::Owned(sym.to_string()))),
+    ///     KVal::Float(KData::Atom(Cow::Owned(price)))
+    ///   ])
+    /// });
+    /// let schema = [("sym", qtype::SYMBOL_LIST), ("price", qtype::FLOAT_LIST)];
+    ///
+    /// let table = KTable::from_row_iter(&schema, rows.clone().take(2), 1).unwrap();
+    /// assert_eq!(table.len(), 2);
+    ///
+    /// let error = KTable::from_row_iter(&schema, rows, 1).unwrap_err();
+    /// assert_eq!(error.to_string(), "invalid row 2: price: invalid float literal");
+    /// ```
+    pub fn from_row_iter<S, I, E>(
+        schema: &[(S, i8)],
+        rows: I,
+        chunk_rows: usize,
+    ) -> Result<KTable<'static>, KError>
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = Result<RowValues, E>>,
+        E: Display,
+    {
+        let mut chunks = KTable::row_chunks(schema, rows, chunk_rows)?;
+        let mut columns = chunks.empty_columns();
+        for chunk in &mut chunks {
+            for (column, part) in columns.iter_mut().zip(into_columns(chunk?)) {
+                column
+                    .append(part)
+                    .unwrap_or_else(|_| unreachable!("chunks have the types of the schema"));
+            }
+        }
+        Ok(chunks.table(columns))
+    }
+
+    /// Iterate over chunk tables of at most `chunk_rows` rows built from a stream of rows, e.g., to insert each
+    ///  chunk into q with [`insert_into`](fn.insert_into.html) as soon as it is built. The parameters are the same
+    ///  as [`from_row_iter`](#method.from_row_iter).
+    ///
+    /// The iterator yields an `InvalidRow` error naming the index of the row if the stream fails or a row does not
+    ///  match the schema, and ends after it. An empty stream yields no chunk.
+    /// # Errors
+    /// `InvalidOperation` if the schema has an enum type or a type which is not a list type.
+    pub fn row_chunks<S, I, E>(
+        schema: &[(S, i8)],
+        rows: I,
+        chunk_rows: usize,
+    ) -> Result<RowChunks<I::IntoIter>, KError>
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = Result<RowValues, E>>,
+        E: Display,
+    {
+        let types = schema.iter().map(|(_, qtype)| *qtype).collect::<Vec<_>>();
+        if let Some(qtype) = types.iter().find(|qtype| empty_column(**qtype).is_none()) {
+            return Err(KError::invalid_operation(
+                "row_chunks",
+                qtype_name(*qtype),
+                Some("simple list type except enum or compound list type"),
+            ));
+        }
+        Ok(RowChunks {
+            names: schema
+                .iter()
+                .map(|(name, _)| name.as_ref().to_string())
+                .collect(),
+            types,
+            rows: rows.into_iter(),
+            chunk_rows: chunk_rows.max(1),
+            next_row: 0,
+            done: false,
+        })
+    }
+}
+
+impl<'a> KTable<'a> {
+    /// Append a row to the end of the table in place. The row is either a dictionary of column names and values in
+    ///  any order or a compound list of values in the order of the columns.
+    ///
+    /// Each value is pushed to its column in the same manner as [`KVal::push`](enum.KVal.html#method.push). A simple
+    ///  list column is upgraded to a compound list if the value is not an atom of its type, e.g., a float into a long
+    ///  column. An enum column takes only an enum atom of the same source.
+    /// # Errors
+    /// - `InvalidOperation` if the table is not a dictionary of a symbol list and a compound list.
+    /// - `InvalidRow` naming the index of the new row and the offending column if the row does not have one value
+    ///   for each column or a value does not fit an enum column.
+    ///
+    /// The table is left unchanged on error.
+    /// # Example
+    /// ```
+    /// use kdbplus::kval;
+    /// use kdbplus::qtype;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// let mut table = KTable::empty(&[("sym", qtype::SYMBOL_LIST), ("price", qtype::FLOAT_LIST)]).unwrap();
+    /// table.append_row(kval!(["a", 1.5])).unwrap();
+    /// table.append_row(kval!({"price" => 2.5, "sym" => "b"})).unwrap();
+    /// assert_eq!(table.get_column("price"), Some(kval!([1.5, 2.5])));
+    ///
+    /// let error = table.append_row(kval!({"sym" => "c"})).unwrap_err();
+    /// assert_eq!(error.to_string(), "invalid row 2: missing columns: price");
+    /// ```
+    pub fn append_row(&mut self, row: KVal<'a>) -> Result<(), KError> {
+        let index = self.len();
+        let (names, columns) = match (self.dict.keys.as_ref(), self.dict.values.as_mut()) {
+            (KVal::Symbol(KData::List(names)), KVal::CompoundList(columns)) => (names, columns),
+            _ => {
+                return Err(KError::invalid_operation(
+                    "append_row",
+                    "dictionary",
+                    Some("table"),
+                ))
+            }
+        };
+        let values =
+            row_values(names, row).map_err(|message| KError::invalid_row(index, message))?;
+        for ((column, value), name) in columns.iter().zip(&values).zip(names.iter()) {
+            if matches!(column, KVal::Enum(..)) && !fits(column, value) {
+                let message = match value {
+                    KVal::Enum(KData::Atom(_), _) => {
+                        format!("enum sources differ in column {}", name)
+                    }
+                    _ => format!(
+                        "{} in column {} of {}",
+                        value.type_name(),
+                        name,
+                        column.type_name()
+                    ),
+                };
+                return Err(KError::invalid_row(index, message));
+            }
+        }
+        for (column, value) in columns.iter_mut().zip(values) {
+            if !matches!(column, KVal::CompoundList(_)) && !fits(column, &value) {
+                *column = std::mem::replace(column, KVal::Null).to_compound_list();
+            }
+            column
+                .push(value)
+                .unwrap_or_else(|_| unreachable!("value was checked to fit the column"));
+        }
+        Ok(())
+    }
+}
+
+impl<I> RowChunks<I> {
+    /// Build empty columns of the schema.
+    fn empty_columns(&self) -> Vec<KVal<'static>> {
+        self.types
+            .iter()
+            .map(|qtype| {
+                empty_column(*qtype)
+                    .unwrap_or_else(|| unreachable!("types were checked to be list types"))
+            })
+            .collect()
+    }
+
+    /// Build a table of the columns.
+    fn table(&self, columns: Vec<KVal<'static>>) -> KTable<'static> {
+        KTable::new(KDict::new(
+            KVal::Symbol(KData::List(Cow::Owned(self.names.clone()))),
+            KVal::CompoundList(columns),
+        ))
+    }
+
+    /// Push the values of a row to the columns. `columns` may be partially updated on error, which ends the
+    ///  iteration.
+    fn push_row(&self, columns: &mut [KVal<'static>], values: RowValues) -> Result<(), String> {
+        if values.len() != columns.len() {
+            return Err(format!(
+                "{} values for {} columns",
+                values.len(),
+                columns.len()
+            ));
+        }
+        for ((column, value), name) in columns.iter_mut().zip(values).zip(&self.names) {
+            let value_type = value.type_name();
+            column.push(value).map_err(|_| {
+                format!(
+                    "{} in column {} of {}",
+                    value_type,
+                    name,
+                    column.type_name()
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl<I, E> Iterator for RowChunks<I>
+where
+    I: Iterator<Item = Result<RowValues, E>>,
+    E: Display,
+{
+    type Item = Result<KTable<'static>, KError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut columns = self.empty_columns();
+        let mut count = 0;
+        while count < self.chunk_rows {
+            let result = match self.rows.next() {
+                Some(Ok(values)) => self.push_row(&mut columns, values),
+                Some(Err(error)) => Err(error.to_string()),
+                None => {
+                    self.done = true;
+                    break;
+                }
+            };
+            if let Err(message) = result {
+                self.done = true;
+                return Some(Err(KError::invalid_row(self.next_row, message)));
+            }
+            self.next_row += 1;
+            count += 1;
+        }
+        (count > 0).then(|| Ok(self.table(columns)))
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Build an empty list of a list type. `None` is returned for an enum list and a type which is not a list type.
+pub(super) fn empty_column(qtype: i8) -> Option<KVal<'static>> {
+    fn empty<T: Clone>() -> KData<'static, T> {
+        KData::List(Cow::Owned(Vec::new()))
+    }
+    Some(match qtype {
+        qtype::COMPOUND_LIST => KVal::CompoundList(Vec::new()),
+        qtype::BOOL_LIST => KVal::Bool(empty()),
+        qtype::GUID_LIST => KVal::Guid(empty()),
+        qtype::BYTE_LIST => KVal::Byte(empty()),
+        qtype::SHORT_LIST => KVal::Short(empty()),
+        qtype::INT_LIST => KVal::Int(empty()),
+        qtype::LONG_LIST => KVal::Long(empty()),
+        qtype::REAL_LIST => KVal::Real(empty()),
+        qtype::FLOAT_LIST => KVal::Float(empty()),
+        qtype::STRING => KVal::String(Cow::Owned(Vec::new())),
+        qtype::SYMBOL_LIST => KVal::Symbol(empty()),
+        qtype::TIMESTAMP_LIST => KVal::Timestamp(empty()),
+        qtype::MONTH_LIST => KVal::Month(empty()),
+        qtype::DATE_LIST => KVal::Date(empty()),
+        qtype::DATETIME_LIST => KVal::Datetime(empty()),
+        qtype::TIMESPAN_LIST => KVal::Timespan(empty()),
+        qtype::MINUTE_LIST => KVal::Minute(empty()),
+        qtype::SECOND_LIST => KVal::Second(empty()),
+        qtype::TIME_LIST => KVal::Time(empty()),
+        _ => return None,
+    })
+}
+
+/// Arrange the values of a row in the order of the columns.
+fn row_values<'a>(names: &[String], row: KVal<'a>) -> Result<Vec<KVal<'a>>, String> {
+    match row {
+        KVal::CompoundList(values) if values.len() == names.len() => Ok(values),
+        KVal::CompoundList(values) => Err(format!(
+            "{} values for {} columns",
+            values.len(),
+            names.len()
+        )),
+        KVal::Dictionary(row) => {
+            let mut values = names.iter().map(|_| None).collect::<Vec<_>>();
+            for (key, value) in row {
+                let key_type = key.type_name();
+                let key = String::try_from(key)
+                    .map_err(|_| format!("{} is not a column name", key_type))?;
+                let index = names
+                    .iter()
+                    .position(|name| *name == key)
+                    .ok_or_else(|| format!("unknown column {}", key))?;
+                if values[index].replace(value).is_some() {
+                    return Err(format!("duplicate column {}", key));
+                }
+            }
+            let missing = names
+                .iter()
+                .zip(&values)
+                .filter(|(_, value)| value.is_none())
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+            match missing.is_empty() {
+                true => Ok(values.into_iter().flatten().collect()),
+                false => Err(KError::MissingColumns(missing).to_string()),
+            }
+        }
+        other => Err(format!(
+            "{} is neither a dictionary nor a compound list",
+            other.type_name()
+        )),
+    }
+}
+
+/// Take the columns of a table.
+fn into_columns(table: KTable<'static>) -> Vec<KVal<'static>> {
+    match *table.dict.values {
+        KVal::CompoundList(columns) => columns,
+        _ => Vec::new(),
+    }
+}
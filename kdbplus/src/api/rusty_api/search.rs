@@ -0,0 +1,228 @@
+//! Search of an atom in a list of `KVal`, including binary search of a sorted list in the same manner as `bin` in q.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::errors::{NOT_A_LIST, TYPE};
+use super::sort::QOrd;
+use super::{KData, KVal};
+use crate::qtype;
+use std::borrow::Cow;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl KVal<'_> {
+    /// Find the index of the first element of the list equal to `needle`.
+    /// - An atom is searched in the list of its type, e.g., a symbol atom in a symbol list and a char in a string.
+    ///   Nulls are equal to each other, so that a float null can be found.
+    /// - A char is compared with each byte of a string in the same manner as [`get`](#method.get) takes the byte as
+    ///   a char, so that the index points at the byte. A char above `'\u{ff}'`, which is not a byte, is not found.
+    /// - Any value is searched in a compound list by `==` of `KVal`.
+    ///
+    /// `None` is returned if the element is not found, if `self` is not a list or if `needle` is not an atom of the
+    ///  type of the list.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let prices = KVal::Float(KData::List(Cow::Owned(vec![1.5, f64::NAN, 2.5])));
+    /// assert_eq!(prices.index_of(&KVal::Float(KData::Atom(Cow::Owned(f64::NAN)))), Some(1));
+    /// assert_eq!(prices.index_of(&KVal::Long(KData::Atom(Cow::Owned(1)))), None);
+    /// ```
+    pub fn index_of(&self, needle: &KVal) -> Option<usize> {
+        self.positions(needle).next()
+    }
+
+    /// Check if the list has an element equal to `needle` in the same manner as [`index_of`](#method.index_of).
+    pub fn contains(&self, needle: &KVal) -> bool {
+        self.index_of(needle).is_some()
+    }
+
+    /// Find the indices of all elements of the list equal to `needle` in the same manner as `where list=needle` in q.
+    ///  The indices are returned as a long list, which is empty under the conditions `index_of` returns `None`.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn indices_of(list: K, needle: K) -> K{
+    ///   KVal::from_raw(list).find_all(&KVal::from_raw(needle)).to_k()
+    /// }
+    /// ```
+    /// ```q
+    /// q)indices_of: `libapi_examples 2: (`indices_of; 2);
+    /// q)indices_of[`a`b`a; `a]
+    /// 0 2
+    /// q)indices_of["abc"; "d"]
+    /// `long$()
+    /// ```
+    pub fn find_all(&self, needle: &KVal) -> KVal<'static> {
+        KVal::Long(KData::List(Cow::Owned(
+            self.positions(needle).map(|index| index as i64).collect(),
+        )))
+    }
+
+    /// Find the index of the last element of a sorted list which is less than or equal to `needle` in the same manner
+    ///  as `bin` in q. Elements are compared in the order of `asc`, so that a null precedes any other value. `-1` is
+    ///  returned if `needle` precedes the first element.
+    ///
+    /// The list must be sorted in ascending order, e.g., by [`sort`](#method.sort) or with the sorted attribute
+    ///  passed from q. The result for an unsorted list is unspecified. A string is searched by bytes in the same
+    ///  manner as [`index_of`](#method.index_of).
+    /// # Errors
+    /// - `"not a list"` if the value is not a simple list or a string.
+    /// - `"type"` if `needle` is not an atom of the type of the list, e.g., a char for a string, or an enum of
+    ///   another source. A compound list is not searched.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn bin_of(list: K, needle: K) -> K{
+    ///   match KVal::from_raw(list).bin(&KVal::from_raw(needle)){
+    ///     Ok(index) => new_long(index),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)bin_of: `libapi_examples 2: (`bin_of; 2);
+    /// q)bin_of[1 3 3 5; 3]
+    /// 2
+    /// q)bin_of[1 3 3 5; 0]
+    /// -1
+    /// ```
+    pub fn bin(&self, needle: &KVal) -> Result<i64, &'static str> {
+        match (self, needle) {
+            (KVal::Bool(list), KVal::Bool(atom)) => bin(list, atom),
+            (KVal::Guid(list), KVal::Guid(atom)) => bin(list, atom),
+            (KVal::Byte(list), KVal::Byte(atom)) => bin(list, atom),
+            (KVal::Short(list), KVal::Short(atom)) => bin(list, atom),
+            (KVal::Int(list), KVal::Int(atom)) => bin(list, atom),
+            (KVal::Long(list), KVal::Long(atom)) => bin(list, atom),
+            (KVal::Real(list), KVal::Real(atom)) => bin(list, atom),
+            (KVal::Float(list), KVal::Float(atom)) => bin(list, atom),
+            (KVal::Symbol(list), KVal::Symbol(atom)) => bin(list, atom),
+            (KVal::String(string), KVal::Char(character)) => {
+                Ok(string.partition_point(|byte| *byte as u32 <= *character as u32) as i64 - 1)
+            }
+            (KVal::Timestamp(list), KVal::Timestamp(atom)) => bin(list, atom),
+            (KVal::Month(list), KVal::Month(atom)) => bin(list, atom),
+            (KVal::Date(list), KVal::Date(atom)) => bin(list, atom),
+            (KVal::Datetime(list), KVal::Datetime(atom)) => bin(list, atom),
+            (KVal::Timespan(list), KVal::Timespan(atom)) => bin(list, atom),
+            (KVal::Minute(list), KVal::Minute(atom)) => bin(list, atom),
+            (KVal::Second(list), KVal::Second(atom)) => bin(list, atom),
+            (KVal::Time(list), KVal::Time(atom)) => bin(list, atom),
+            (KVal::Enum(list, list_source), KVal::Enum(atom, atom_source))
+                if list_source == atom_source =>
+            {
+                bin(list, atom)
+            }
+            (KVal::String(_) | KVal::CompoundList(_), _) => Err(TYPE),
+            (list, _) if list.qtype() > 0 && list.qtype() < qtype::TABLE => Err(TYPE),
+            _ => Err(NOT_A_LIST),
+        }
+    }
+
+    /// Indices of elements equal to `needle` in ascending order.
+    fn positions<'b>(&'b self, needle: &'b KVal) -> Box<dyn Iterator<Item = usize> + 'b> {
+        match (self, needle) {
+            (KVal::CompoundList(list), needle) => Box::new(
+                list.iter()
+                    .enumerate()
+                    .filter(move |(_, element)| *element == needle)
+                    .map(|(index, _)| index),
+            ),
+            (KVal::Bool(list), KVal::Bool(atom)) => positions(list, atom),
+            (KVal::Guid(list), KVal::Guid(atom)) => positions(list, atom),
+            (KVal::Byte(list), KVal::Byte(atom)) => positions(list, atom),
+            (KVal::Short(list), KVal::Short(atom)) => positions(list, atom),
+            (KVal::Int(list), KVal::Int(atom)) => positions(list, atom),
+            (KVal::Long(list), KVal::Long(atom)) => positions(list, atom),
+            (KVal::Real(list), KVal::Real(atom)) => positions(list, atom),
+            (KVal::Float(list), KVal::Float(atom)) => positions(list, atom),
+            (KVal::Symbol(list), KVal::Symbol(atom)) => positions(list, atom),
+            (KVal::String(string), KVal::Char(character)) => Box::new(
+                string
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, byte)| **byte as u32 == *character as u32)
+                    .map(|(index, _)| index),
+            ),
+            (KVal::Timestamp(list), KVal::Timestamp(atom)) => positions(list, atom),
+            (KVal::Month(list), KVal::Month(atom)) => positions(list, atom),
+            (KVal::Date(list), KVal::Date(atom)) => positions(list, atom),
+            (KVal::Datetime(list), KVal::Datetime(atom)) => positions(list, atom),
+            (KVal::Timespan(list), KVal::Timespan(atom)) => positions(list, atom),
+            (KVal::Minute(list), KVal::Minute(atom)) => positions(list, atom),
+            (KVal::Second(list), KVal::Second(atom)) => positions(list, atom),
+            (KVal::Time(list), KVal::Time(atom)) => positions(list, atom),
+            (KVal::Enum(list, list_source), KVal::Enum(atom, atom_source))
+                if list_source == atom_source =>
+            {
+                positions(list, atom)
+            }
+            _ => Box::new(std::iter::empty()),
+        }
+    }
+}
+
+impl<T: QOrd + Clone> KData<'_, T> {
+    /// Search a sorted list for `needle` by binary search in the same manner as `slice::binary_search` of Rust,
+    ///  comparing elements in the order of `asc` in q, so that a null precedes any other value. An atom is searched
+    ///  as a list of one element.
+    /// - `Ok` holds the index of a matching element. Any of duplicates may match.
+    /// - `Err` holds the index where `needle` could be inserted keeping the order.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let prices = KData::List(Cow::Owned(vec![f64::NAN, 1.5, 2.5]));
+    /// assert_eq!(prices.binary_search(&2.5), Ok(2));
+    /// assert_eq!(prices.binary_search(&f64::NAN), Ok(0));
+    /// assert_eq!(prices.binary_search(&2.0), Err(2));
+    /// ```
+    pub fn binary_search(&self, needle: &T) -> Result<usize, usize> {
+        self.as_slice()
+            .binary_search_by(|element| element.q_cmp(needle))
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Indices of elements of a list equal to an atom. Nothing is found unless `list` is a list and `atom` is an atom.
+fn positions<'b, T: QOrd + Clone>(
+    list: &'b KData<'_, T>,
+    atom: &'b KData<'_, T>,
+) -> Box<dyn Iterator<Item = usize> + 'b> {
+    match (list, atom) {
+        (KData::List(list), KData::Atom(atom)) => Box::new(
+            list.iter()
+                .enumerate()
+                .filter(move |(_, element)| element.q_cmp(atom).is_eq())
+                .map(|(index, _)| index),
+        ),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+/// Index of the last element of a sorted list which is not greater than an atom, or `-1`.
+fn bin<T: QOrd + Clone>(list: &KData<T>, atom: &KData<T>) -> Result<i64, &'static str> {
+    match (list, atom) {
+        (KData::List(list), KData::Atom(atom)) => {
+            Ok(list.partition_point(|element| element.q_cmp(atom).is_le()) as i64 - 1)
+        }
+        (KData::Atom(_), _) => Err(NOT_A_LIST),
+        _ => Err(TYPE),
+    }
+}
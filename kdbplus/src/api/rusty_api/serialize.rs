@@ -0,0 +1,343 @@
+//! Serialization of `KVal` with `serde`, e.g., to log or snapshot q data as JSON or MessagePack. Available only when
+//!  `serde` feature is enabled.
+//!
+//! A value is serialized into the data model of `serde` as follows:
+//! - An atom is serialized as its natural scalar and a list as a sequence of them. A string is a string, or bytes if
+//!   it is not valid UTF-8.
+//! - A symbol is a string and a GUID is a hyphenated string.
+//! - A q null including the null symbol and the null char `" "` is serialized as none, i.e., `null` in JSON.
+//! - A temporal value is its raw value, e.g., nanoseconds since `2000.01.01D00:00:00` for a timestamp, or an ISO
+//!   8601 string. See [`TemporalFormat`].
+//! - An enum is the raw index into its source.
+//! - A dictionary is a map and a table is a sequence of row maps from the column names to the values. A keyed
+//!   table is a sequence of rows holding both the key columns and the value columns.
+//! - An error is a map of `error` to its message and a general null is a unit, i.e., `null` in JSON.
+//!
+//! ```
+//! use kdbplus::api::rusty_api::*;
+//! use std::borrow::Cow;
+//!
+//! let table = KTable::new(KDict::new(
+//!   KVal::Symbol(KData::List(Cow::Owned(vec!["sym".to_string(), "date".to_string()]))),
+//!   KVal::CompoundList(vec![
+//!     KVal::Symbol(KData::List(Cow::Owned(vec!["a".to_string(), String::new()]))),
+//!     KVal::Date(KData::List(Cow::Owned(vec![0, 31])))
+//!   ])
+//! ));
+//! let table = KVal::Table(table);
+//! assert_eq!(
+//!   serde_json::to_string(&table).unwrap(),
+//!   r#"[{"sym":"a","date":0},{"sym":null,"date":31}]"#
+//! );
+//! assert_eq!(
+//!   serde_json::to_string(&table.serialize_with(TemporalFormat::Iso)).unwrap(),
+//!   r#"[{"sym":"a","date":"2000-01-01"},{"sym":null,"date":"2000-02-01"}]"#
+//! );
+//! ```
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::display::guid_text;
+use super::temporal::{
+    format_date, format_datetime, format_minute, format_month, format_second, format_time,
+    format_timestamp,
+};
+use super::{KData, KDict, KTable, KVal, Nullable};
+use serde::ser::{Error, SerializeMap};
+use serde::{Serialize, Serializer};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Representation of temporal values in serialization.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TemporalFormat {
+    /// Raw value stored in q, e.g., days since `2000.01.01` for a date and milliseconds for a time. A datetime is a
+    ///  float of days.
+    #[default]
+    Raw,
+    /// ISO 8601 string:
+    /// - timestamp: `2000-01-01T12:00:00.000000000`
+    /// - month: `2000-01`
+    /// - date: `2000-01-01`
+    /// - datetime: `2000-01-01T12:00:00.000`
+    /// - timespan: duration in seconds, e.g., `PT3600.000000000S` or `-PT0.000000001S`
+    /// - minute, second and time: `12:00`, `12:00:00` and `12:00:00.000`
+    ///
+    /// Infinities are written in q notation, e.g., `0W`, as they have no ISO 8601 representation.
+    Iso,
+}
+
+/// `KVal` serialized with a [`TemporalFormat`]. Built by
+///  [`KVal::serialize_with`](enum.KVal.html#method.serialize_with).
+#[derive(Clone, Copy, Debug)]
+pub struct SerializeWith<'s, 'a> {
+    /// Serialized value.
+    value: &'s KVal<'a>,
+    /// Representation of temporal values.
+    temporal: TemporalFormat,
+}
+
+/// Scalar which a single element of a list is serialized as.
+enum Scalar {
+    Null,
+    Bool(bool),
+    Unsigned(u64),
+    Signed(i64),
+    Real(f32),
+    Float(f64),
+    Text(String),
+}
+
+/// Row of columns serialized as a map from the column names to the values.
+struct Row<'r, 'a> {
+    /// Names and columns.
+    columns: &'r [(&'r String, &'r KVal<'a>)],
+    /// Index of the row.
+    row: usize,
+    /// Representation of temporal values.
+    temporal: TemporalFormat,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl<'a> KVal<'a> {
+    /// Serialize the value with the specified representation of temporal values. `KVal` itself is serialized with
+    ///  [`TemporalFormat::Raw`](enum.TemporalFormat.html#variant.Raw).
+    ///
+    /// Available only when `serde` feature is enabled.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let timestamp = KVal::Timestamp(KData::Atom(Cow::Owned(43_200_000_000_000)));
+    /// assert_eq!(serde_json::to_string(&timestamp).unwrap(), "43200000000000");
+    /// assert_eq!(
+    ///   serde_json::to_string(&timestamp.serialize_with(TemporalFormat::Iso)).unwrap(),
+    ///   r#""2000-01-01T12:00:00.000000000""#
+    /// );
+    /// ```
+    pub fn serialize_with(&self, temporal: TemporalFormat) -> SerializeWith<'_, 'a> {
+        SerializeWith {
+            value: self,
+            temporal,
+        }
+    }
+}
+
+impl Serialize for KVal<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.serialize_with(TemporalFormat::Raw)
+            .serialize(serializer)
+    }
+}
+
+impl Serialize for SerializeWith<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let iso = self.temporal == TemporalFormat::Iso;
+        match self.value {
+            KVal::CompoundList(list) => serializer.collect_seq(
+                list.iter()
+                    .map(|element| element.serialize_with(self.temporal)),
+            ),
+            KVal::Bool(data) => serialize_data(data, serializer, |value| Scalar::Bool(*value)),
+            KVal::Guid(data) => {
+                serialize_data(data, serializer, |value| Scalar::Text(guid_text(value)))
+            }
+            KVal::Byte(data) => {
+                serialize_data(data, serializer, |value| Scalar::Unsigned(*value as u64))
+            }
+            KVal::Short(data) => {
+                serialize_data(data, serializer, |value| Scalar::Signed(*value as i64))
+            }
+            KVal::Int(data) => {
+                serialize_data(data, serializer, |value| Scalar::Signed(*value as i64))
+            }
+            KVal::Long(data) | KVal::Enum(data, _) => {
+                serialize_data(data, serializer, |value| Scalar::Signed(*value))
+            }
+            KVal::Real(data) => serialize_data(data, serializer, |value| Scalar::Real(*value)),
+            KVal::Float(data) => serialize_data(data, serializer, |value| Scalar::Float(*value)),
+            KVal::Char(' ') => serializer.serialize_none(),
+            KVal::Char(character) => serializer.serialize_char(*character),
+            KVal::Symbol(data) => {
+                serialize_data(data, serializer, |value| Scalar::Text(value.clone()))
+            }
+            KVal::String(string) => match std::str::from_utf8(string) {
+                Ok(text) => serializer.serialize_str(text),
+                Err(_) => serializer.serialize_bytes(string),
+            },
+            KVal::Timestamp(data) if iso => serialize_data(data, serializer, |value| {
+                iso_text(value, || {
+                    let text = format_timestamp(*value);
+                    let (date, time) = text.split_once('D').unwrap_or((&text, ""));
+                    format!("{}T{}", date.replace('.', "-"), time)
+                })
+            }),
+            KVal::Month(data) if iso => serialize_data(data, serializer, |value| {
+                iso_text(value, || format_month(*value).replace('.', "-"))
+            }),
+            KVal::Date(data) if iso => serialize_data(data, serializer, |value| {
+                iso_text(value, || format_date(*value).replace('.', "-"))
+            }),
+            KVal::Datetime(data) if iso => serialize_data(data, serializer, |value| {
+                iso_text(value, || {
+                    let text = format_datetime(*value);
+                    let (date, time) = text.split_once('T').unwrap_or((&text, ""));
+                    format!("{}T{}", date.replace('.', "-"), time)
+                })
+            }),
+            KVal::Timespan(data) if iso => serialize_data(data, serializer, |value| {
+                iso_text(value, || iso_duration(*value))
+            }),
+            KVal::Minute(data) if iso => serialize_data(data, serializer, |value| {
+                iso_text(value, || format_minute(*value))
+            }),
+            KVal::Second(data) if iso => serialize_data(data, serializer, |value| {
+                iso_text(value, || format_second(*value))
+            }),
+            KVal::Time(data) if iso => serialize_data(data, serializer, |value| {
+                iso_text(value, || format_time(*value))
+            }),
+            KVal::Timestamp(data) | KVal::Timespan(data) => {
+                serialize_data(data, serializer, |value| Scalar::Signed(*value))
+            }
+            KVal::Month(data)
+            | KVal::Date(data)
+            | KVal::Minute(data)
+            | KVal::Second(data)
+            | KVal::Time(data) => {
+                serialize_data(data, serializer, |value| Scalar::Signed(*value as i64))
+            }
+            KVal::Datetime(data) => serialize_data(data, serializer, |value| Scalar::Float(*value)),
+            KVal::Table(table) => serialize_rows(&[table], serializer, self.temporal),
+            KVal::Dictionary(KDict { keys, values, .. }) => {
+                match (keys.as_ref(), values.as_ref()) {
+                    // Keyed table.
+                    (KVal::Table(keys), KVal::Table(values)) => {
+                        serialize_rows(&[keys, values], serializer, self.temporal)
+                    }
+                    (keys, values) => {
+                        let mut map = serializer.serialize_map(Some(keys.len()))?;
+                        for index in 0..keys.len() {
+                            let key = keys.get(index).unwrap_or(KVal::Null);
+                            let value = values.get(index).unwrap_or(KVal::Null);
+                            map.serialize_entry(
+                                &key.serialize_with(self.temporal),
+                                &value.serialize_with(self.temporal),
+                            )?;
+                        }
+                        map.end()
+                    }
+                }
+            }
+            KVal::Error(message) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("error", message)?;
+                map.end()
+            }
+            KVal::Null => serializer.serialize_unit(),
+        }
+    }
+}
+
+impl Serialize for Scalar {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Scalar::Null => serializer.serialize_none(),
+            Scalar::Bool(value) => serializer.serialize_bool(*value),
+            Scalar::Unsigned(value) => serializer.serialize_u64(*value),
+            Scalar::Signed(value) => serializer.serialize_i64(*value),
+            Scalar::Real(value) => serializer.serialize_f32(*value),
+            Scalar::Float(value) => serializer.serialize_f64(*value),
+            Scalar::Text(value) => serializer.serialize_str(value),
+        }
+    }
+}
+
+impl Serialize for Row<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for (name, column) in self.columns {
+            let value = column.get(self.row).unwrap_or(KVal::Null);
+            map.serialize_entry(name.as_str(), &value.serialize_with(self.temporal))?;
+        }
+        map.end()
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Serialize an atom as a scalar or a list as a sequence of scalars. A q null is serialized as none.
+fn serialize_data<T: Nullable, S: Serializer>(
+    data: &KData<T>,
+    serializer: S,
+    scalar: impl Fn(&T) -> Scalar,
+) -> Result<S::Ok, S::Error> {
+    let scalar = |value: &T| {
+        if value.is_null_value() {
+            Scalar::Null
+        } else {
+            scalar(value)
+        }
+    };
+    match data {
+        KData::Atom(atom) => scalar(atom).serialize(serializer),
+        KData::List(list) => serializer.collect_seq(list.iter().map(scalar)),
+    }
+}
+
+/// Text of a non-null temporal value. An infinity is written in q notation.
+fn iso_text<T: Nullable>(value: &T, text: impl FnOnce() -> String) -> Scalar {
+    Scalar::Text(if value.is_inf_value() {
+        "0W".to_string()
+    } else if value.is_ninf_value() {
+        "-0W".to_string()
+    } else {
+        text()
+    })
+}
+
+/// Format a duration in nanoseconds as an ISO 8601 duration in seconds, e.g., `PT3600.000000000S`.
+fn iso_duration(nanos: i64) -> String {
+    let sign = if nanos < 0 { "-" } else { "" };
+    let nanos = nanos.unsigned_abs();
+    format!(
+        "{}PT{}.{:09}S",
+        sign,
+        nanos / 1_000_000_000,
+        nanos % 1_000_000_000
+    )
+}
+
+/// Serialize tables of the same length as a sequence of rows. Each row is a map holding the columns of all of the
+///  tables in order, e.g., the key columns and the value columns of a keyed table.
+fn serialize_rows<S: Serializer>(
+    tables: &[&KTable],
+    serializer: S,
+    temporal: TemporalFormat,
+) -> Result<S::Ok, S::Error> {
+    let mut columns = Vec::new();
+    for table in tables {
+        match (table.dict.keys.as_ref(), table.dict.values.as_ref()) {
+            (KVal::Symbol(KData::List(names)), KVal::CompoundList(values)) => {
+                columns.extend(names.iter().zip(values.iter()))
+            }
+            _ => return Err(S::Error::custom("table must have symbol column names")),
+        }
+    }
+    let rows = tables.first().map_or(0, |table| table.len());
+    serializer.collect_seq((0..rows).map(|row| Row {
+        columns: &columns,
+        row,
+        temporal,
+    }))
+}
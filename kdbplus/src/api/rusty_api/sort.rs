@@ -0,0 +1,386 @@
+//! Sort of a list of `KVal` and of the rows of `KTable` in the order of q.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::super::{KUtility, K};
+use super::errors::{NOT_A_LIST, NO_SUCH_COLUMN, TYPE};
+use super::kval::as_slice;
+use super::{KData, KTable, KVal};
+use crate::{qattribute, qtype};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::ops::Deref;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// List sorted in ascending order by [`KVal::sort`](enum.KVal.html#method.sort). It is built into a q list with the
+///  sorted attribute `s#`, so that q can search it by binary search, e.g., with `bin`. The list is read through
+///  `Deref` and cannot be modified, as a modification could break the order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SortedList<'a>(KVal<'a>);
+
+/// Table sorted by [`KTable::sort_by`](struct.KTable.html#method.sort_by). If the rows are sorted in ascending order
+///  of the first sort column, the column is built with the sorted attribute `s#` in the same manner as `xasc` in q.
+///  The table is read through `Deref` and cannot be modified, as a modification could break the order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SortedTable<'a> {
+    /// Sorted table.
+    table: KTable<'a>,
+    /// Index of the column which carries the sorted attribute.
+    sorted_column: Option<usize>,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Traits
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Element type of `KData` which q can sort, i.e., every element type. Temporal types are ordered with their
+///  underlying types.
+pub trait QOrd {
+    /// Compare two elements in the order of `asc` in q. A null precedes any other value.
+    fn q_cmp(&self, other: &Self) -> Ordering;
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Macros
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Implement `QOrd` for types whose null is the least value in `Ord`.
+macro_rules! q_ord {
+    ($($type: ty),*) => {
+        $(
+            impl QOrd for $type {
+                fn q_cmp(&self, other: &Self) -> Ordering {
+                    self.cmp(other)
+                }
+            }
+        )*
+    };
+}
+
+/// Implement `QOrd` for floating point types whose null is NaN.
+macro_rules! q_ord_float {
+    ($($type: ty),*) => {
+        $(
+            impl QOrd for $type {
+                fn q_cmp(&self, other: &Self) -> Ordering {
+                    match (self.is_nan(), other.is_nan()) {
+                        (true, true) => Ordering::Equal,
+                        (true, false) => Ordering::Less,
+                        (false, true) => Ordering::Greater,
+                        (false, false) => self.partial_cmp(other).unwrap(),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+// Integral nulls are the minimum of the types and the null symbol is the empty string.
+q_ord!(bool, u8, [u8; 16], i16, i32, i64, String);
+q_ord_float!(f32, f64);
+
+impl<'a> KVal<'a> {
+    /// Sort a simple list in ascending order in the same manner as `asc` in q. Nulls come first and the order of
+    ///  equal elements is kept. The returned list is built into a q list with the sorted attribute.
+    /// - Bool, GUID, byte, short, int, long, real, float, symbol and temporal lists are sorted by value.
+    /// - String is sorted by bytes in the same manner as q.
+    ///
+    /// An owned list is sorted in place and a list borrowing q memory is copied once unless it is already sorted.
+    /// # Errors
+    /// - `"not a list"` if the value is an atom, a table, a dictionary, an error or a general null.
+    /// - `"type"` if the value is a compound list or an enum list.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn sorted(list: K) -> K{
+    ///   match KVal::from_raw(list).sort(){
+    ///     Ok(sorted) => sorted.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)sorted: `libapi_examples 2: (`sorted; 1);
+    /// q)sorted 3 0N 1 -0W 2
+    /// `s#0N -0W 1 2 3
+    /// q)sorted[3 1 2] bin 2
+    /// 1
+    /// ```
+    pub fn sort(self) -> Result<SortedList<'a>, &'static str> {
+        self.sort_by_order(Ordering::Less).map(SortedList)
+    }
+
+    /// Sort a simple list in descending order in the same manner as `desc` in q. Nulls come last and the order of
+    ///  equal elements is kept. The types accepted and the errors are the same as [`sort`](#method.sort). The
+    ///  returned list has no attribute.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let prices = KVal::Float(KData::List(Cow::Owned(vec![1.5, f64::NAN, 3.5])));
+    /// match prices.sort_desc().unwrap(){
+    ///   KVal::Float(KData::List(sorted)) => {
+    ///     assert_eq!(&sorted[..2], &[3.5, 1.5]);
+    ///     assert!(sorted[2].is_nan());
+    ///   }
+    ///   _ => unreachable!()
+    /// }
+    /// ```
+    pub fn sort_desc(self) -> Result<KVal<'a>, &'static str> {
+        self.sort_by_order(Ordering::Greater)
+    }
+
+    /// Sort a simple list so that each element is in `order` to the next one or equal to it.
+    fn sort_by_order(self, order: Ordering) -> Result<KVal<'a>, &'static str> {
+        match self {
+            KVal::Bool(data) => sort_data(data, order).map(KVal::Bool),
+            KVal::Guid(data) => sort_data(data, order).map(KVal::Guid),
+            KVal::Byte(data) => sort_data(data, order).map(KVal::Byte),
+            KVal::Short(data) => sort_data(data, order).map(KVal::Short),
+            KVal::Int(data) => sort_data(data, order).map(KVal::Int),
+            KVal::Long(data) => sort_data(data, order).map(KVal::Long),
+            KVal::Real(data) => sort_data(data, order).map(KVal::Real),
+            KVal::Float(data) => sort_data(data, order).map(KVal::Float),
+            KVal::Symbol(data) => sort_data(data, order).map(KVal::Symbol),
+            KVal::String(string) => Ok(KVal::String(sort_list(string, order))),
+            KVal::Timestamp(data) => sort_data(data, order).map(KVal::Timestamp),
+            KVal::Month(data) => sort_data(data, order).map(KVal::Month),
+            KVal::Date(data) => sort_data(data, order).map(KVal::Date),
+            KVal::Datetime(data) => sort_data(data, order).map(KVal::Datetime),
+            KVal::Timespan(data) => sort_data(data, order).map(KVal::Timespan),
+            KVal::Minute(data) => sort_data(data, order).map(KVal::Minute),
+            KVal::Second(data) => sort_data(data, order).map(KVal::Second),
+            KVal::Time(data) => sort_data(data, order).map(KVal::Time),
+            // An enum is sorted by the values of its source, which is not available here.
+            KVal::CompoundList(_) | KVal::Enum(KData::List(_), _) => Err(TYPE),
+            _ => Err(NOT_A_LIST),
+        }
+    }
+}
+
+impl<'a> KTable<'a> {
+    /// Sort the rows by one or more columns in the same manner as `xasc` and `xdesc` in q. Rows are ordered by the
+    ///  first column and rows with equal values by the next column, and the order of rows equal in every sort column
+    ///  is kept. Each element of `columns` is a pair of a column name and whether the column is sorted in descending
+    ///  order.
+    /// - Nulls come first in ascending order and last in descending order. A float null is less than any float.
+    /// - Symbols are ordered lexicographically and a string column by bytes.
+    /// - A compound column is ordered by its elements, e.g., strings lexicographically. Elements of different types
+    ///   are ordered by type.
+    ///
+    /// Every column is copied in the new order and the first sort column carries the sorted attribute if it is
+    ///  sorted in ascending order.
+    /// # Errors
+    /// - `"no such column"` if any of `columns` does not exist.
+    /// - `"type"` if a sort column is an enum list, whose order is that of its source, or if the table is a keyed
+    ///   table.
+    /// - `"length"` if the columns do not have the same length.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn sort_table(table: K, columns: K, descending: K) -> K{
+    ///   let table = KTable::from_raw(table);
+    ///   let (columns, descending) = match (KVal::from_raw(columns), KVal::from_raw(descending)){
+    ///     (KVal::Symbol(KData::List(columns)), KVal::Bool(KData::List(descending))) => (columns, descending),
+    ///     _ => return new_error("expected a symbol list and a bool list\0")
+    ///   };
+    ///   let keys = columns.iter().map(String::as_str).zip(descending.iter().copied()).collect::<Vec<_>>();
+    ///   match table.sort_by(&keys){
+    ///     Ok(sorted) => sorted.to_k(),
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)sort_table: `libapi_examples 2: (`sort_table; 3);
+    /// q)sort_table[([] sym: `b`a`b`a; price: 1 0n 2 3f); `sym`price; 01b]
+    /// sym price
+    /// ---------
+    /// a   3
+    /// a
+    /// b   2
+    /// b   1
+    /// q)attr exec sym from sort_table[([] sym: `b`a; price: 1 2f); enlist `sym; enlist 0b]
+    /// `s
+    /// ```
+    pub fn sort_by(&self, columns: &[(&str, bool)]) -> Result<SortedTable<'_>, &'static str> {
+        let all = match self.dict.values.as_ref() {
+            KVal::CompoundList(all) => all,
+            _ => return Err(TYPE),
+        };
+        self.check_lengths()?;
+        let mut keys = Vec::with_capacity(columns.len());
+        for (name, descending) in columns {
+            let index = self.column_index(name).ok_or(NO_SUCH_COLUMN)?;
+            if matches!(all[index], KVal::Enum(..)) {
+                return Err(TYPE);
+            }
+            let order = match descending {
+                true => Ordering::Greater,
+                false => Ordering::Less,
+            };
+            keys.push((&all[index], order));
+        }
+        let mut rows = (0..self.len() as i64).collect::<Vec<_>>();
+        rows.sort_by(|left, right| {
+            keys.iter()
+                .map(|(column, order)| {
+                    ordered(
+                        compare_rows(column, *left as usize, *right as usize),
+                        *order,
+                    )
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        let sorted_column = match columns.first() {
+            Some((name, false)) => self.column_index(name),
+            _ => None,
+        };
+        Ok(SortedTable {
+            table: self.take_rows(&rows)?,
+            sorted_column,
+        })
+    }
+}
+
+impl<'a> SortedTable<'a> {
+    /// Build a new q table whose first sort column carries the sorted attribute if it is sorted in ascending order.
+    ///  A compound column is built without the attribute as q does not hold an attribute on it. A q error is
+    ///  returned if the table cannot be converted (see [`KTable::to_k`](struct.KTable.html#method.to_k)).
+    pub fn to_k(&self) -> K {
+        let table = self.table.to_k();
+        if let Some(index) = self.sorted_column {
+            if !table.is_null() && table.get_type() == qtype::TABLE {
+                let pair = as_slice::<K>(unsafe { (*table).value.table });
+                let mut column = as_slice::<K>(pair[1])[index];
+                let _ = column.set_attribute(qattribute::SORTED);
+            }
+        }
+        table
+    }
+
+    /// Take the sorted table out.
+    pub fn into_inner(self) -> KTable<'a> {
+        self.table
+    }
+}
+
+impl<'a> Deref for SortedTable<'a> {
+    type Target = KTable<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.table
+    }
+}
+
+impl<'a> SortedList<'a> {
+    /// Build a new q list with the sorted attribute. A q error is returned if the list cannot be converted (see
+    ///  [`KVal::to_k`](enum.KVal.html#method.to_k)).
+    pub fn to_k(&self) -> K {
+        let mut list = self.0.to_k();
+        if !list.is_null() {
+            // An error object does not take an attribute and is returned as it is.
+            let _ = list.set_attribute(qattribute::SORTED);
+        }
+        list
+    }
+
+    /// Take the sorted list out.
+    pub fn into_inner(self) -> KVal<'a> {
+        self.0
+    }
+}
+
+impl<'a> Deref for SortedList<'a> {
+    type Target = KVal<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Sort a list in the order. A borrowed list which is already sorted is returned as it is.
+fn sort_data<T: QOrd + Clone>(
+    data: KData<'_, T>,
+    order: Ordering,
+) -> Result<KData<'_, T>, &'static str> {
+    match data {
+        KData::Atom(_) => Err(NOT_A_LIST),
+        KData::List(list) => Ok(KData::List(sort_list(list, order))),
+    }
+}
+
+/// Sort a list in the order in the same manner as `sort_data`.
+fn sort_list<T: QOrd + Clone>(mut list: Cow<'_, [T]>, order: Ordering) -> Cow<'_, [T]> {
+    if !is_sorted(&list, order, QOrd::q_cmp) {
+        list.to_mut()
+            .sort_by(|left, right| ordered(left.q_cmp(right), order));
+    }
+    list
+}
+
+/// Check if each element is in the order to the next one or equal to it.
+fn is_sorted<T>(list: &[T], order: Ordering, compare: impl Fn(&T, &T) -> Ordering) -> bool {
+    list.windows(2)
+        .all(|pair| compare(&pair[0], &pair[1]) != order.reverse())
+}
+
+/// Turn the ascending comparison into the comparison of the order.
+fn ordered(ordering: Ordering, order: Ordering) -> Ordering {
+    match order {
+        Ordering::Greater => ordering.reverse(),
+        _ => ordering,
+    }
+}
+
+/// Compare the elements of a column at two rows in ascending order.
+fn compare_rows(column: &KVal, left: usize, right: usize) -> Ordering {
+    fn compare<T: QOrd + Clone>(data: &KData<'_, T>, left: usize, right: usize) -> Ordering {
+        let list = data.as_slice();
+        list[left].q_cmp(&list[right])
+    }
+    match column {
+        KVal::CompoundList(list) => list[left]
+            .partial_cmp(&list[right])
+            .unwrap_or_else(|| list[left].qtype().cmp(&list[right].qtype())),
+        KVal::Bool(data) => compare(data, left, right),
+        KVal::Guid(data) => compare(data, left, right),
+        KVal::Byte(data) => compare(data, left, right),
+        KVal::Short(data) => compare(data, left, right),
+        KVal::Int(data)
+        | KVal::Month(data)
+        | KVal::Date(data)
+        | KVal::Minute(data)
+        | KVal::Second(data)
+        | KVal::Time(data) => compare(data, left, right),
+        KVal::Long(data) | KVal::Timestamp(data) | KVal::Timespan(data) => {
+            compare(data, left, right)
+        }
+        KVal::Real(data) => compare(data, left, right),
+        KVal::Float(data) | KVal::Datetime(data) => compare(data, left, right),
+        KVal::Symbol(data) => compare(data, left, right),
+        KVal::String(string) => string[left].cmp(&string[right]),
+        _ => Ordering::Equal,
+    }
+}
@@ -0,0 +1,164 @@
+//! Split of a list of `KVal` into sub-lists borrowing the same memory, e.g., to stream a large column out in pieces.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::errors::{INDEX_OUT_OF_BOUNDS, NOT_A_LIST};
+use super::{KData, KVal};
+use std::borrow::Cow;
+use std::ops::Range;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Error of a chunk of no element.
+const ZERO_CHUNK_SIZE: &str = "chunk size must be positive\0";
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl<'a> KVal<'a> {
+    /// Split a list into the elements before the index `mid` and the rest. A string is split by bytes in the same
+    ///  manner as q.
+    ///
+    /// A borrowed list is split into two lists borrowing the same memory. An owned list keeps the head in place and
+    ///  moves the tail into a new list. An enum list carries its source into both lists.
+    /// # Errors
+    /// - `"not a list"` if the value is an atom, a table, a dictionary, an error or a general null.
+    /// - `"index out of bounds"` if `mid` exceeds the length of the list.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let longs = [1_i64, 2, 3];
+    /// let (head, tail) = KVal::Long(KData::List(Cow::Borrowed(&longs))).split_at(1).unwrap();
+    /// assert_eq!(head, KVal::Long(KData::List(Cow::Owned(vec![1]))));
+    /// assert_eq!(tail, KVal::Long(KData::List(Cow::Owned(vec![2, 3]))));
+    ///
+    /// let (head, tail) = KVal::String(Cow::Borrowed("héllo".as_bytes())).split_at(2).unwrap();
+    /// assert_eq!(head, KVal::String(Cow::Borrowed(b"h\xc3")));
+    /// assert_eq!(tail, KVal::String(Cow::Borrowed(b"\xa9llo")));
+    /// ```
+    pub fn split_at(self, mid: usize) -> Result<(KVal<'a>, KVal<'a>), &'static str> {
+        match self {
+            KVal::CompoundList(mut list) => {
+                if mid > list.len() {
+                    return Err(INDEX_OUT_OF_BOUNDS);
+                }
+                let tail = list.split_off(mid);
+                Ok((KVal::CompoundList(list), KVal::CompoundList(tail)))
+            }
+            KVal::Bool(data) => split_data(data, mid, KVal::Bool),
+            KVal::Guid(data) => split_data(data, mid, KVal::Guid),
+            KVal::Byte(data) => split_data(data, mid, KVal::Byte),
+            KVal::Short(data) => split_data(data, mid, KVal::Short),
+            KVal::Int(data) => split_data(data, mid, KVal::Int),
+            KVal::Long(data) => split_data(data, mid, KVal::Long),
+            KVal::Real(data) => split_data(data, mid, KVal::Real),
+            KVal::Float(data) => split_data(data, mid, KVal::Float),
+            KVal::Symbol(data) => split_data(data, mid, KVal::Symbol),
+            KVal::String(string) => {
+                let (head, tail) = split_list(string, mid)?;
+                Ok((KVal::String(head), KVal::String(tail)))
+            }
+            KVal::Timestamp(data) => split_data(data, mid, KVal::Timestamp),
+            KVal::Month(data) => split_data(data, mid, KVal::Month),
+            KVal::Date(data) => split_data(data, mid, KVal::Date),
+            KVal::Datetime(data) => split_data(data, mid, KVal::Datetime),
+            KVal::Timespan(data) => split_data(data, mid, KVal::Timespan),
+            KVal::Minute(data) => split_data(data, mid, KVal::Minute),
+            KVal::Second(data) => split_data(data, mid, KVal::Second),
+            KVal::Time(data) => split_data(data, mid, KVal::Time),
+            KVal::Enum(data, source) => {
+                split_data(data, mid, |data| KVal::Enum(data, source.clone()))
+            }
+            KVal::Char(_) | KVal::Table(_) | KVal::Dictionary(_) | KVal::Error(_) | KVal::Null => {
+                Err(NOT_A_LIST)
+            }
+        }
+    }
+
+    /// Iterate over consecutive sub-lists of `size` elements. The last sub-list has less elements if `size` does not
+    ///  divide the length, and an empty list yields nothing. A string is chunked by bytes.
+    ///
+    /// Each sub-list is got in the same manner as [`slice`](#method.slice): a simple list and a string are borrowed
+    ///  without copying, a compound list holds clones of the elements and an enum list carries its source.
+    /// # Errors
+    /// - `"not a list"` if the value is an atom, a table, a dictionary, an error or a general null.
+    /// - `"chunk size must be positive"` if `size` is 0.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let list = KVal::Long(KData::List(Cow::Owned((0..5).collect())));
+    /// let sums = list.chunks(2).unwrap().map(|chunk| match chunk{
+    ///   KVal::Long(KData::List(longs)) => longs.iter().sum::<i64>(),
+    ///   _ => unreachable!()
+    /// }).collect::<Vec<_>>();
+    /// assert_eq!(sums, vec![1, 5, 4]);
+    /// ```
+    pub fn chunks(&self, size: usize) -> Result<impl Iterator<Item = KVal<'_>>, &'static str> {
+        if size == 0 {
+            return Err(ZERO_CHUNK_SIZE);
+        }
+        // Check that the value is a list.
+        self.slice(0..0)?;
+        let len = self.len();
+        let bounds = (0..len)
+            .step_by(size)
+            .chain(std::iter::once(len))
+            .collect::<Vec<_>>();
+        let ranges = bounds
+            .windows(2)
+            .map(|bound| bound[0]..bound[1])
+            .collect::<Vec<Range<usize>>>();
+        Ok(ranges.into_iter().map(move |range| {
+            self.slice(range)
+                .unwrap_or_else(|_| unreachable!("chunks lie within the list"))
+        }))
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Split a list and wrap both halves into `KVal`.
+fn split_data<'a, T: Clone>(
+    data: KData<'a, T>,
+    mid: usize,
+    wrap: impl Fn(KData<'a, T>) -> KVal<'a>,
+) -> Result<(KVal<'a>, KVal<'a>), &'static str> {
+    match data {
+        KData::Atom(_) => Err(NOT_A_LIST),
+        KData::List(list) => {
+            let (head, tail) = split_list(list, mid)?;
+            Ok((wrap(KData::List(head)), wrap(KData::List(tail))))
+        }
+    }
+}
+
+/// Head and tail of a split list.
+type Halves<'a, T> = (Cow<'a, [T]>, Cow<'a, [T]>);
+
+/// Split a list before the element at the index `mid`. A borrowed list is split into two borrowed lists.
+fn split_list<T: Clone>(list: Cow<'_, [T]>, mid: usize) -> Result<Halves<'_, T>, &'static str> {
+    match list {
+        Cow::Borrowed(list) => {
+            let (head, tail) = list.split_at_checked(mid).ok_or(INDEX_OUT_OF_BOUNDS)?;
+            Ok((Cow::Borrowed(head), Cow::Borrowed(tail)))
+        }
+        Cow::Owned(mut list) => {
+            if mid > list.len() {
+                return Err(INDEX_OUT_OF_BOUNDS);
+            }
+            let tail = list.split_off(mid);
+            Ok((Cow::Owned(list), Cow::Owned(tail)))
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Conversion between a list of q strings and Rust strings.
+//!
+//! q holds a list of strings as a compound list of char lists. Unlike symbols, the strings are not interned and
+//!  therefore suit a column of free text.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::kval::char_to_byte;
+use super::{KData, KError, KVal};
+use std::borrow::Cow;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl<'a> KVal<'a> {
+    /// Build a compound list of strings in the representation of q.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let lines = KVal::from_string_vec(vec!["first".to_string(), String::new()]);
+    /// assert_eq!(
+    ///   lines,
+    ///   KVal::CompoundList(vec![KVal::String(Cow::Borrowed(b"first")), KVal::String(Cow::Borrowed(b""))])
+    /// );
+    /// ```
+    pub fn from_string_vec(strings: Vec<String>) -> KVal<'static> {
+        KVal::CompoundList(
+            strings
+                .into_iter()
+                .map(|string| KVal::String(Cow::Owned(string.into_bytes())))
+                .collect(),
+        )
+    }
+
+    /// Copy a list of strings into Rust strings.
+    /// - Compound list accepts strings, chars and symbols as elements. A char is a string of one byte.
+    /// - Symbol list is accepted as a list of strings.
+    /// # Errors
+    /// - `InvalidOperation` if the value is not a compound list or a symbol list, an element is not a string, a
+    ///   char or a symbol, or a string is not valid UTF-8.
+    /// - `OutOfRange` if a char is greater than `'\u{ff}'` and therefore is not a byte.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn longest_line(lines: K) -> K{
+    ///   match KVal::from_raw(lines).as_string_vec(){
+    ///     Ok(lines) => match lines.into_iter().max_by_key(|line| line.len()){
+    ///       Some(line) => new_string(&line),
+    ///       None => new_string("")
+    ///     },
+    ///     Err(error) => error.to_k()
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)longest_line: `libapi_examples 2: (`longest_line; 1);
+    /// q)longest_line ("short"; "much longer"; `sym)
+    /// "much longer"
+    /// q)longest_line 1 2
+    /// 'invalid operation as_string_vec on long list. expected: compound list or symbol list
+    /// ```
+    pub fn as_string_vec(&self) -> Result<Vec<String>, KError> {
+        self.string_elements()?
+            .into_iter()
+            .map(|string| {
+                String::from_utf8(string.into_owned()).map_err(|_| {
+                    KError::invalid_operation("as_string_vec", "string", Some("valid UTF-8"))
+                })
+            })
+            .collect()
+    }
+
+    /// Join a list of strings into one string with a separator in the same manner as `separator sv strings` in
+    ///  q. The elements are accepted in the same manner as [`as_string_vec`](#method.as_string_vec) and copied
+    ///  once into the result. Strings are joined as bytes and need not be valid UTF-8.
+    /// # Errors
+    /// Same as [`as_string_vec`](#method.as_string_vec) except that a string which is not valid UTF-8 is joined.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let lines = KVal::from_string_vec(vec!["a".to_string(), "b c".to_string()]);
+    /// assert_eq!(lines.join_strings("\n").unwrap(), KVal::String(Cow::Borrowed(b"a\nb c")));
+    /// ```
+    pub fn join_strings(&self, separator: &str) -> Result<KVal<'static>, KError> {
+        let strings = self.string_elements()?;
+        let length = strings.iter().map(|string| string.len()).sum::<usize>()
+            + separator.len() * strings.len().saturating_sub(1);
+        let mut joined = Vec::with_capacity(length);
+        for (i, string) in strings.iter().enumerate() {
+            if i != 0 {
+                joined.extend_from_slice(separator.as_bytes());
+            }
+            joined.extend_from_slice(string);
+        }
+        Ok(KVal::String(Cow::Owned(joined)))
+    }
+
+    /// Borrow the bytes of the elements of a list of strings. Only a char is copied.
+    fn string_elements(&self) -> Result<Vec<Cow<'_, [u8]>>, KError> {
+        match self {
+            KVal::CompoundList(list) => list
+                .iter()
+                .map(|element| match element {
+                    KVal::String(string) => Ok(Cow::Borrowed(string.as_ref())),
+                    KVal::Char(character) => char_to_byte(*character)
+                        .map(|byte| Cow::Owned(vec![byte]))
+                        .map_err(|_| KError::out_of_range(element.to_string(), "char")),
+                    KVal::Symbol(KData::Atom(symbol)) => Ok(Cow::Borrowed(symbol.as_bytes())),
+                    _ => Err(KError::invalid_operation(
+                        "as_string_vec",
+                        element.type_name(),
+                        Some("string, char or symbol"),
+                    )),
+                })
+                .collect(),
+            KVal::Symbol(KData::List(symbols)) => Ok(symbols
+                .iter()
+                .map(|symbol| Cow::Borrowed(symbol.as_bytes()))
+                .collect()),
+            _ => Err(KError::invalid_operation(
+                "as_string_vec",
+                self.type_name(),
+                Some("compound list or symbol list"),
+            )),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Take and drop of elements of a list of `KVal` in the manner of `#` and `_` in q.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::errors::{EMPTY_LIST, NOT_A_LIST, TOO_LONG};
+use super::limit::q_length;
+use super::{KData, KVal};
+use std::borrow::Cow;
+use std::ops::Range;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Operation on the head or the tail of a list.
+#[derive(Clone, Copy)]
+enum Cut {
+    /// Take `n` elements in the same manner as `n#list`.
+    Take(i64),
+    /// Drop `n` elements in the same manner as `n _ list`.
+    Drop(i64),
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl<'a> KVal<'a> {
+    /// Take the first `n` elements of a list in the same manner as `n#list` in q. A negative `n` takes the last
+    ///  elements. A string is taken by bytes in the same manner as q.
+    ///
+    /// Taking more elements than the list has repeats the elements cyclically as q does, e.g., `5#1 2` is
+    ///  `1 2 1 2 1` and `-5#1 2` is `2 1 2 1 2`. Otherwise the result borrows the same memory as `self` and an owned
+    ///  list is truncated in place.
+    /// # Errors
+    /// - `"not a list"` if the value is an atom, a table, a dictionary, an error or a general null.
+    /// - `"empty list"` if more than 0 elements are taken from an empty list, as there is no element to repeat.
+    /// - `"too long"` if the length of the result exceeds [`max_list_length`](fn.max_list_length.html).
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn taken(n: K, list: K) -> K{
+    ///   match n.get_long(){
+    ///     Ok(n) => match KVal::from_raw(list).take(n){
+    ///       Ok(taken) => taken.to_k(),
+    ///       Err(error) => new_error(error)
+    ///     },
+    ///     Err(error) => new_error(error)
+    ///   }
+    /// }
+    /// ```
+    /// ```q
+    /// q)taken: `libapi_examples 2: (`taken; 2);
+    /// q)taken[2; `a`b`c]
+    /// `a`b
+    /// q)taken[-5; 1 2]
+    /// 2 1 2 1 2
+    /// ```
+    pub fn take(self, n: i64) -> Result<KVal<'a>, &'static str> {
+        self.cut(Cut::Take(n))
+    }
+
+    /// Drop the first `n` elements of a list in the same manner as `n _ list` in q. A negative `n` drops the last
+    ///  elements and dropping more elements than the list has leaves an empty list. A string is dropped by bytes in
+    ///  the same manner as q.
+    ///
+    /// The result borrows the same memory as `self` and an owned list is shortened in place.
+    /// # Errors
+    /// - `"not a list"` if the value is an atom, a table, a dictionary, an error or a general null.
+    /// # Example
+    /// ```
+    /// use kdbplus::api::rusty_api::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let string = KVal::String(Cow::Borrowed(b"kdbplus"));
+    /// assert_eq!(string.clone().drop(3).unwrap(), KVal::String(Cow::Borrowed(b"plus")));
+    /// assert_eq!(string.drop(-4).unwrap(), KVal::String(Cow::Borrowed(b"kdb")));
+    /// ```
+    pub fn drop(self, n: i64) -> Result<KVal<'a>, &'static str> {
+        self.cut(Cut::Drop(n))
+    }
+
+    /// Take or drop elements of the list.
+    fn cut(self, cut: Cut) -> Result<KVal<'a>, &'static str> {
+        match self {
+            KVal::CompoundList(list) => {
+                cut_list(Cow::Owned(list), cut).map(|list| KVal::CompoundList(list.into_owned()))
+            }
+            KVal::Bool(data) => cut_data(data, cut).map(KVal::Bool),
+            KVal::Guid(data) => cut_data(data, cut).map(KVal::Guid),
+            KVal::Byte(data) => cut_data(data, cut).map(KVal::Byte),
+            KVal::Short(data) => cut_data(data, cut).map(KVal::Short),
+            KVal::Int(data) => cut_data(data, cut).map(KVal::Int),
+            KVal::Long(data) => cut_data(data, cut).map(KVal::Long),
+            KVal::Real(data) => cut_data(data, cut).map(KVal::Real),
+            KVal::Float(data) => cut_data(data, cut).map(KVal::Float),
+            KVal::Symbol(data) => cut_data(data, cut).map(KVal::Symbol),
+            KVal::String(string) => cut_list(string, cut).map(KVal::String),
+            KVal::Timestamp(data) => cut_data(data, cut).map(KVal::Timestamp),
+            KVal::Month(data) => cut_data(data, cut).map(KVal::Month),
+            KVal::Date(data) => cut_data(data, cut).map(KVal::Date),
+            KVal::Datetime(data) => cut_data(data, cut).map(KVal::Datetime),
+            KVal::Timespan(data) => cut_data(data, cut).map(KVal::Timespan),
+            KVal::Minute(data) => cut_data(data, cut).map(KVal::Minute),
+            KVal::Second(data) => cut_data(data, cut).map(KVal::Second),
+            KVal::Time(data) => cut_data(data, cut).map(KVal::Time),
+            KVal::Enum(data, source) => cut_data(data, cut).map(|data| KVal::Enum(data, source)),
+            KVal::Char(_) | KVal::Table(_) | KVal::Dictionary(_) | KVal::Error(_) | KVal::Null => {
+                Err(NOT_A_LIST)
+            }
+        }
+    }
+}
+
+impl Cut {
+    /// Signed number of elements to take or drop.
+    fn n(self) -> i64 {
+        match self {
+            Cut::Take(n) | Cut::Drop(n) => n,
+        }
+    }
+
+    /// Range of the elements kept from a list of the length. `None` is returned if the elements must be repeated,
+    ///  which happens only to `Take`.
+    fn range(self, len: usize) -> Option<Range<usize>> {
+        match self {
+            Cut::Take(n) => {
+                let count = usize::try_from(n.unsigned_abs())
+                    .ok()
+                    .filter(|count| *count <= len)?;
+                Some(if n < 0 { len - count..len } else { 0..count })
+            }
+            Cut::Drop(n) => {
+                let count = usize::try_from(n.unsigned_abs()).map_or(len, |count| count.min(len));
+                Some(if n < 0 { 0..len - count } else { count..len })
+            }
+        }
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Take or drop elements of a list.
+fn cut_data<T: Clone>(data: KData<'_, T>, cut: Cut) -> Result<KData<'_, T>, &'static str> {
+    match data {
+        KData::Atom(_) => Err(NOT_A_LIST),
+        KData::List(list) => cut_list(list, cut).map(KData::List),
+    }
+}
+
+/// Take or drop elements of a list. A borrowed list is sliced and an owned list is shortened in place unless the
+///  elements must be repeated.
+fn cut_list<T: Clone>(list: Cow<'_, [T]>, cut: Cut) -> Result<Cow<'_, [T]>, &'static str> {
+    match cut.range(list.len()) {
+        Some(range) => Ok(match list {
+            Cow::Borrowed(list) => Cow::Borrowed(&list[range]),
+            Cow::Owned(mut list) => {
+                list.truncate(range.end);
+                list.drain(..range.start);
+                Cow::Owned(list)
+            }
+        }),
+        None => repeat(&list, cut.n()).map(Cow::Owned),
+    }
+}
+
+/// Take `n` elements from a list which has less elements by repeating them. A negative `n` repeats them so that the
+///  result ends with the last element.
+fn repeat<T: Clone>(list: &[T], n: i64) -> Result<Vec<T>, &'static str> {
+    if list.is_empty() {
+        return Err(EMPTY_LIST);
+    }
+    let count = usize::try_from(n.unsigned_abs()).map_err(|_| TOO_LONG)?;
+    q_length(count).map_err(|_| TOO_LONG)?;
+    let start = if n < 0 {
+        (list.len() - count % list.len()) % list.len()
+    } else {
+        0
+    };
+    Ok(list
+        .iter()
+        .cycle()
+        .skip(start)
+        .take(count)
+        .cloned()
+        .collect())
+}
@@ -0,0 +1,673 @@
+//! Thin newtypes over the raw integers and floats backing q temporal types. Timestamp, timespan and long all
+//!  carry an `i64` in q, so passing one where another is expected compiles silently with bare integers. Wrapping
+//!  them lets the compiler catch such mix-ups while arithmetic is only provided where it is meaningful in q.
+//!
+//! ```
+//! use kdbplus::api::rusty_api::*;
+//!
+//! // 2000.01.01D12:00:00.000000000
+//! let noon = QTimestamp(43_200_000_000_000);
+//! // 0D01:00:00.000000000
+//! let hour = QTimespan(3_600_000_000_000);
+//! assert_eq!(noon + hour, QTimestamp(46_800_000_000_000));
+//! assert_eq!((noon + hour) - noon, hour);
+//! ```
+//!
+//! Adding two timestamps does not make sense and does not compile:
+//!
+//! ```compile_fail
+//! use kdbplus::api::rusty_api::*;
+//!
+//! let _ = QTimestamp(0) + QTimestamp(1);
+//! ```
+//!
+//! Neither does adding a bare long to a timestamp without stating it is a timespan:
+//!
+//! ```compile_fail
+//! use kdbplus::api::rusty_api::*;
+//!
+//! let _ = QTimestamp(0) + 1_i64;
+//! ```
+//!
+//! # Note
+//! Arithmetic wraps like q does. In particular q nulls are not propagated specially, i.e., `0Np + 1` is
+//!  `-0Wp` in q and `QTimestamp::NULL + QTimespan(1)` is `QTimestamp::NINF` here.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::{KData, KVal};
+use crate::{qinf_base, qninf_base, qnull_base};
+use std::fmt;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+#[cfg(feature = "chrono")]
+use std::convert::TryFrom;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Macros
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Define common items of a temporal newtype: conversion from/to the raw value and border values.
+macro_rules! temporal_common {
+    ($newtype: ident, $raw: ty, $null: expr, $inf: expr, $ninf: expr) => {
+        impl $newtype {
+            /// Null value of this type.
+            pub const NULL: $newtype = $newtype($null);
+            /// Infinity value of this type.
+            pub const INF: $newtype = $newtype($inf);
+            /// Negative infinity value of this type.
+            pub const NINF: $newtype = $newtype($ninf);
+
+            /// Get the underlying raw value.
+            #[inline]
+            pub fn raw(self) -> $raw {
+                self.0
+            }
+        }
+
+        impl From<$raw> for $newtype {
+            #[inline]
+            fn from(raw: $raw) -> Self {
+                $newtype(raw)
+            }
+        }
+
+        impl From<$newtype> for $raw {
+            #[inline]
+            fn from(value: $newtype) -> Self {
+                value.0
+            }
+        }
+    };
+}
+
+/// Define `is_null` for a temporal newtype backed by an integer.
+macro_rules! temporal_integer_null {
+    ($newtype: ident) => {
+        impl $newtype {
+            /// Check if the value is the q null of this type.
+            #[inline]
+            pub fn is_null(self) -> bool {
+                self == Self::NULL
+            }
+        }
+    };
+}
+
+/// Implement `point (+|-) offset = point` and `point - point = offset`.
+macro_rules! temporal_point {
+    ($point: ident, $offset: ty, $wrap: expr, $unwrap: expr) => {
+        impl Add<$offset> for $point {
+            type Output = $point;
+            #[inline]
+            fn add(self, rhs: $offset) -> $point {
+                $point(self.0.wrapping_add($unwrap(rhs)))
+            }
+        }
+
+        impl Sub<$offset> for $point {
+            type Output = $point;
+            #[inline]
+            fn sub(self, rhs: $offset) -> $point {
+                $point(self.0.wrapping_sub($unwrap(rhs)))
+            }
+        }
+
+        impl AddAssign<$offset> for $point {
+            #[inline]
+            fn add_assign(&mut self, rhs: $offset) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl SubAssign<$offset> for $point {
+            #[inline]
+            fn sub_assign(&mut self, rhs: $offset) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl Sub for $point {
+            type Output = $offset;
+            #[inline]
+            fn sub(self, rhs: $point) -> $offset {
+                $wrap(self.0.wrapping_sub(rhs.0))
+            }
+        }
+    };
+}
+
+/// Implement arithmetic of a duration-like type with itself.
+macro_rules! temporal_duration {
+    ($duration: ident) => {
+        impl Add for $duration {
+            type Output = $duration;
+            #[inline]
+            fn add(self, rhs: $duration) -> $duration {
+                $duration(self.0.wrapping_add(rhs.0))
+            }
+        }
+
+        impl Sub for $duration {
+            type Output = $duration;
+            #[inline]
+            fn sub(self, rhs: $duration) -> $duration {
+                $duration(self.0.wrapping_sub(rhs.0))
+            }
+        }
+
+        impl AddAssign for $duration {
+            #[inline]
+            fn add_assign(&mut self, rhs: $duration) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl SubAssign for $duration {
+            #[inline]
+            fn sub_assign(&mut self, rhs: $duration) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl Neg for $duration {
+            type Output = $duration;
+            #[inline]
+            fn neg(self) -> $duration {
+                $duration(self.0.wrapping_neg())
+            }
+        }
+    };
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// q timestamp: elapsed time in nanoseconds since `2000.01.01D00:00:00`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QTimestamp(pub i64);
+
+/// q timespan: duration in nanoseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QTimespan(pub i64);
+
+/// q date: elapsed time in days since `2000.01.01`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QDate(pub i32);
+
+/// q month: elapsed time in months since `2000.01.01`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QMonth(pub i32);
+
+/// q time: duration in milliseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QTime(pub i32);
+
+/// q minute: duration in minutes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QMinute(pub i32);
+
+/// q second: duration in seconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QSecond(pub i32);
+
+/// q datetime: elapsed time in days with a fraction of milliseconds since `2000.01.01T00:00:00.000`.
+/// # Note
+/// As the underlying value is a float, the null is `NaN` and comparison follows IEEE754, i.e., `QDatetime::NULL != QDatetime::NULL`.
+///  Use [`is_null`](#method.is_null) to test it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct QDatetime(pub f64);
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Common %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+temporal_common!(QTimestamp, i64, qnull_base::J, qinf_base::J, qninf_base::J);
+temporal_common!(QTimespan, i64, qnull_base::J, qinf_base::J, qninf_base::J);
+temporal_common!(QDate, i32, qnull_base::I, qinf_base::I, qninf_base::I);
+temporal_common!(QMonth, i32, qnull_base::I, qinf_base::I, qninf_base::I);
+temporal_common!(QTime, i32, qnull_base::I, qinf_base::I, qninf_base::I);
+temporal_common!(QMinute, i32, qnull_base::I, qinf_base::I, qninf_base::I);
+temporal_common!(QSecond, i32, qnull_base::I, qinf_base::I, qninf_base::I);
+temporal_common!(QDatetime, f64, qnull_base::F, qinf_base::F, qninf_base::F);
+
+temporal_integer_null!(QTimestamp);
+temporal_integer_null!(QTimespan);
+temporal_integer_null!(QDate);
+temporal_integer_null!(QMonth);
+temporal_integer_null!(QTime);
+temporal_integer_null!(QMinute);
+temporal_integer_null!(QSecond);
+
+impl QDatetime {
+    /// Check if the value is the q null of datetime (`NaN`).
+    #[inline]
+    pub fn is_null(self) -> bool {
+        self.0.is_nan()
+    }
+}
+
+//%% Arithmetic %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+// timestamp +/- timespan = timestamp, timestamp - timestamp = timespan
+temporal_point!(QTimestamp, QTimespan, QTimespan, |span: QTimespan| span.0);
+// date +/- days = date, date - date = days
+temporal_point!(QDate, i32, |days: i32| days, |days: i32| days);
+// month +/- months = month, month - month = months
+temporal_point!(QMonth, i32, |months: i32| months, |months: i32| months);
+
+temporal_duration!(QTimespan);
+temporal_duration!(QTime);
+temporal_duration!(QMinute);
+temporal_duration!(QSecond);
+
+impl Add<QTimespan> for QDate {
+    type Output = QTimestamp;
+    /// Date plus timespan is a timestamp as in q (`2000.01.02 + 0D12:00:00`).
+    #[inline]
+    fn add(self, rhs: QTimespan) -> QTimestamp {
+        QTimestamp(
+            (self.0 as i64)
+                .wrapping_mul(ONE_DAY_NANOS)
+                .wrapping_add(rhs.0),
+        )
+    }
+}
+
+impl Add<f64> for QDatetime {
+    type Output = QDatetime;
+    /// Add days (possibly with a fraction).
+    #[inline]
+    fn add(self, days: f64) -> QDatetime {
+        QDatetime(self.0 + days)
+    }
+}
+
+impl Sub<f64> for QDatetime {
+    type Output = QDatetime;
+    /// Subtract days (possibly with a fraction).
+    #[inline]
+    fn sub(self, days: f64) -> QDatetime {
+        QDatetime(self.0 - days)
+    }
+}
+
+impl Sub for QDatetime {
+    type Output = f64;
+    /// Difference in days.
+    #[inline]
+    fn sub(self, rhs: QDatetime) -> f64 {
+        self.0 - rhs.0
+    }
+}
+
+//%% Display %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Display a temporal newtype backed by an integer in the same manner as q console, e.g., `2000.01.01` for a date
+///  and `0Nd` for the null date.
+macro_rules! temporal_display {
+    ($newtype: ident, $type_char: expr, $format: ident) => {
+        impl fmt::Display for $newtype {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                match *self {
+                    $newtype::NULL => write!(f, "0N{}", $type_char),
+                    $newtype::INF => write!(f, "0W{}", $type_char),
+                    $newtype::NINF => write!(f, "-0W{}", $type_char),
+                    $newtype(raw) => f.write_str(&$format(raw)),
+                }
+            }
+        }
+    };
+}
+
+temporal_display!(QTimestamp, 'p', format_timestamp);
+temporal_display!(QTimespan, 'n', format_timespan);
+temporal_display!(QDate, 'd', format_date);
+temporal_display!(QTime, 't', format_time);
+temporal_display!(QMinute, 'u', format_minute);
+temporal_display!(QSecond, 'v', format_second);
+
+impl fmt::Display for QMonth {
+    /// A month carries the suffix `m` as in q console, e.g., `2000.01m`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            QMonth::NULL => f.write_str("0Nm"),
+            QMonth::INF => f.write_str("0Wm"),
+            QMonth::NINF => f.write_str("-0Wm"),
+            QMonth(months) => write!(f, "{}m", format_month(months)),
+        }
+    }
+}
+
+impl fmt::Display for QDatetime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            days if days.is_nan() => f.write_str("0Nz"),
+            days if days == f64::INFINITY => f.write_str("0wz"),
+            days if days == f64::NEG_INFINITY => f.write_str("-0wz"),
+            days => f.write_str(&format_datetime(days)),
+        }
+    }
+}
+
+//%% Chrono %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+#[cfg(feature = "chrono")]
+impl From<QTimestamp> for DateTime<Utc> {
+    /// Nulls and infinities are converted as plain values, i.e., the results are around 1707 and 2292.
+    fn from(timestamp: QTimestamp) -> Self {
+        // Add the offset separately to avoid overflow.
+        Utc.timestamp_nanos(timestamp.0) + Duration::nanoseconds(KDB_TIMESTAMP_OFFSET)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<DateTime<Utc>> for QTimestamp {
+    /// A datetime which cannot be represented as a q timestamp is converted to `0Np`.
+    fn from(timestamp: DateTime<Utc>) -> Self {
+        QTimestamp(
+            (timestamp - Duration::nanoseconds(KDB_TIMESTAMP_OFFSET))
+                .timestamp_nanos_opt()
+                .unwrap_or(qnull_base::J),
+        )
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<QTimespan> for Duration {
+    fn from(timespan: QTimespan) -> Self {
+        Duration::nanoseconds(timespan.0)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<Duration> for QTimespan {
+    /// A duration overflowing `i64` nanoseconds is converted to `0Nn`.
+    fn from(duration: Duration) -> Self {
+        QTimespan(duration.num_nanoseconds().unwrap_or(qnull_base::J))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<QDate> for NaiveDate {
+    type Error = &'static str;
+    /// Fails for null and for dates out of the range of `NaiveDate` including infinities.
+    fn try_from(date: QDate) -> Result<Self, Self::Error> {
+        if date.is_null() {
+            return Err("null date\0");
+        }
+        kdb_epoch()
+            .checked_add_signed(Duration::days(date.0 as i64))
+            .ok_or("date out of range\0")
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<NaiveDate> for QDate {
+    fn from(date: NaiveDate) -> Self {
+        QDate(date.signed_duration_since(kdb_epoch()).num_days() as i32)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<QMonth> for NaiveDate {
+    type Error = &'static str;
+    /// Convert into the first day of the month. Fails for null and for months out of the range of `NaiveDate`
+    ///  including infinities.
+    fn try_from(month: QMonth) -> Result<Self, Self::Error> {
+        if month.is_null() {
+            return Err("null month\0");
+        }
+        let year = 2000 + month.0.div_euclid(12);
+        NaiveDate::from_ymd_opt(year, 1 + month.0.rem_euclid(12) as u32, 1)
+            .ok_or("month out of range\0")
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<NaiveDate> for QMonth {
+    /// The day of the month is truncated.
+    fn from(date: NaiveDate) -> Self {
+        QMonth((date.year() - 2000) * 12 + date.month0() as i32)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<QDatetime> for DateTime<Utc> {
+    type Error = &'static str;
+    /// Fails for null and for datetimes out of the range of `DateTime` including infinities.
+    fn try_from(datetime: QDatetime) -> Result<Self, Self::Error> {
+        if datetime.is_null() {
+            return Err("null datetime\0");
+        }
+        let millis = (datetime.0 * ONE_DAY_MILLIS as f64).round();
+        if !millis.is_finite() || millis.abs() >= i64::MAX as f64 {
+            return Err("datetime out of range\0");
+        }
+        kdb_epoch()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+            .checked_add_signed(Duration::milliseconds(millis as i64))
+            .ok_or("datetime out of range\0")
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<DateTime<Utc>> for QDatetime {
+    fn from(datetime: DateTime<Utc>) -> Self {
+        let epoch = kdb_epoch().and_hms_opt(0, 0, 0).unwrap().and_utc();
+        QDatetime(
+            datetime.signed_duration_since(epoch).num_milliseconds() as f64 / ONE_DAY_MILLIS as f64,
+        )
+    }
+}
+
+/// Conversion between a duration-like newtype and `chrono::Duration`. A duration overflowing the underlying
+///  `i32` is converted to null.
+macro_rules! chrono_duration {
+    ($newtype: ident, $to_duration: ident, $from_duration: ident) => {
+        #[cfg(feature = "chrono")]
+        impl From<$newtype> for Duration {
+            fn from(value: $newtype) -> Self {
+                Duration::$to_duration(value.0 as i64)
+            }
+        }
+
+        #[cfg(feature = "chrono")]
+        impl From<Duration> for $newtype {
+            fn from(duration: Duration) -> Self {
+                $newtype(i32::try_from(duration.$from_duration()).unwrap_or(qnull_base::I))
+            }
+        }
+    };
+}
+
+chrono_duration!(QTime, milliseconds, num_milliseconds);
+chrono_duration!(QMinute, minutes, num_minutes);
+chrono_duration!(QSecond, seconds, num_seconds);
+
+//%% KVal %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Typed accessors of `KVal` returning the temporal newtype of the variant instead of the raw value.
+macro_rules! temporal_accessor {
+    ($variant: ident, $newtype: ident, $atom: ident, $list: ident) => {
+        impl<'a> KVal<'a> {
+            /// Get the atom as the temporal newtype of its variant. Returns `None` if the value is not an atom of the
+            ///  variant.
+            pub fn $atom(&self) -> Option<$newtype> {
+                match self {
+                    KVal::$variant(KData::Atom(value)) => Some($newtype(**value)),
+                    _ => None,
+                }
+            }
+
+            /// Get the elements of the list as the temporal newtype of its variant. Returns `None` if the value is not
+            ///  a list of the variant.
+            pub fn $list(&self) -> Option<Vec<$newtype>> {
+                match self {
+                    KVal::$variant(KData::List(list)) => {
+                        Some(list.iter().copied().map($newtype).collect())
+                    }
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+temporal_accessor!(Timestamp, QTimestamp, as_timestamp_atom, as_timestamp_list);
+temporal_accessor!(Timespan, QTimespan, as_timespan_atom, as_timespan_list);
+temporal_accessor!(Date, QDate, as_date_atom, as_date_list);
+temporal_accessor!(Month, QMonth, as_month_atom, as_month_list);
+temporal_accessor!(Time, QTime, as_time_atom, as_time_list);
+temporal_accessor!(Minute, QMinute, as_minute_atom, as_minute_list);
+temporal_accessor!(Second, QSecond, as_second_atom, as_second_list);
+temporal_accessor!(Datetime, QDatetime, as_datetime_atom, as_datetime_list);
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// One day in nanoseconds.
+const ONE_DAY_NANOS: i64 = 86400000000000;
+
+/// One day in milliseconds.
+const ONE_DAY_MILLIS: i64 = 86400000;
+
+/// Timestamp of `2000.01.01D00:00:00` in nanoseconds since the Unix epoch.
+#[cfg(feature = "chrono")]
+const KDB_TIMESTAMP_OFFSET: i64 = 946684800000000000;
+
+/// `2000.01.01`.
+#[cfg(feature = "chrono")]
+fn kdb_epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2000, 1, 1).unwrap()
+}
+
+/// Convert days since `2000.01.01` into (year, month, day) of the proleptic Gregorian calendar.
+pub(super) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Days since `0000.03.01` so that a leap day comes at the end of a year.
+    let shifted = days + 730425;
+    let era = shifted.div_euclid(146097);
+    let day_of_era = shifted.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = era * 400 + year_of_era + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// Convert (year, month, day) of the proleptic Gregorian calendar into days since `2000.01.01`.
+pub(super) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_index = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 730425
+}
+
+/// Format days since `2000.01.01` as `yyyy.mm.dd`.
+pub(super) fn format_date(days: i32) -> String {
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+/// Format months since `2000.01` as `yyyy.mm`.
+pub(super) fn format_month(months: i32) -> String {
+    format!(
+        "{:04}.{:02}",
+        2000 + months.div_euclid(12) as i64,
+        1 + months.rem_euclid(12)
+    )
+}
+
+/// Format nanoseconds since `2000.01.01D00:00:00` as `yyyy.mm.ddDhh:mm:ss.nnnnnnnnn`.
+pub(super) fn format_timestamp(nanos: i64) -> String {
+    format!(
+        "{}D{}",
+        format_date(nanos.div_euclid(ONE_DAY_NANOS) as i32),
+        format_clock(nanos.rem_euclid(ONE_DAY_NANOS) as u64, 1_000_000_000, 9)
+    )
+}
+
+/// Format a duration in nanoseconds as `[-]dDhh:mm:ss.nnnnnnnnn`.
+pub(super) fn format_timespan(nanos: i64) -> String {
+    let sign = if nanos < 0 { "-" } else { "" };
+    let nanos = nanos.unsigned_abs();
+    format!(
+        "{}{}D{}",
+        sign,
+        nanos / ONE_DAY_NANOS as u64,
+        format_clock(nanos % ONE_DAY_NANOS as u64, 1_000_000_000, 9)
+    )
+}
+
+/// Format days with a fraction since `2000.01.01` as `yyyy.mm.ddThh:mm:ss.mmm`.
+pub(super) fn format_datetime(days: f64) -> String {
+    let millis = (days * ONE_DAY_MILLIS as f64).round() as i64;
+    format!(
+        "{}T{}",
+        format_date(millis.div_euclid(ONE_DAY_MILLIS) as i32),
+        format_clock(millis.rem_euclid(ONE_DAY_MILLIS) as u64, 1000, 3)
+    )
+}
+
+/// Format a duration in milliseconds as `[-]hh:mm:ss.mmm`.
+pub(super) fn format_time(millis: i32) -> String {
+    let sign = if millis < 0 { "-" } else { "" };
+    format!(
+        "{}{}",
+        sign,
+        format_clock(millis.unsigned_abs() as u64, 1000, 3)
+    )
+}
+
+/// Format a duration in minutes as `[-]hh:mm`.
+pub(super) fn format_minute(minutes: i32) -> String {
+    let sign = if minutes < 0 { "-" } else { "" };
+    let minutes = minutes.unsigned_abs();
+    format!("{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+}
+
+/// Format a duration in seconds as `[-]hh:mm:ss`.
+pub(super) fn format_second(seconds: i32) -> String {
+    let sign = if seconds < 0 { "-" } else { "" };
+    let seconds = seconds.unsigned_abs();
+    format!(
+        "{}{:02}:{:02}:{:02}",
+        sign,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Format a non-negative duration as `hh:mm:ss.fff` with the specified number of digits of the fraction.
+fn format_clock(duration: u64, units_per_second: u64, digits: usize) -> String {
+    let seconds = duration / units_per_second;
+    format!(
+        "{:02}:{:02}:{:02}.{:0digits$}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        duration % units_per_second,
+        digits = digits
+    )
+}
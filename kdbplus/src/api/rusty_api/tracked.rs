@@ -0,0 +1,140 @@
+//! `KVal` which remembers the q object it was built from, so that an unmodified value is passed back to q without
+//!  building a new object.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use super::super::{increment_reference_count, KUtility, G, K};
+use super::kval::as_slice;
+use super::{KData, KVal};
+use std::borrow::Cow;
+use std::ops::{Deref, DerefMut};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Structs
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// `KVal` built by [`KVal::from_raw_tracked`](enum.KVal.html#method.from_raw_tracked) together with the q object it
+///  borrows. The value is read and modified through `Deref` and `DerefMut` in the same manner as `KVal`.
+/// # Note
+/// The q object must outlive this value in the same manner as for [`KVal::from_raw`](enum.KVal.html#method.from_raw).
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackedKVal<'a> {
+    value: KVal<'a>,
+    /// Object the value was built from.
+    raw: K,
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Implementation
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+impl<'a> KVal<'a> {
+    /// Build `KVal` from a q object in the same manner as [`from_raw`](#method.from_raw) and remember the object, so
+    ///  that [`TrackedKVal::to_k`](struct.TrackedKVal.html#method.to_k) returns the object itself while the value is
+    ///  unmodified. The reference count of the q object is not changed.
+    /// # Example
+    /// ```no_run
+    /// use kdbplus::api::*;
+    /// use kdbplus::api::rusty_api::*;
+    ///
+    /// #[no_mangle]
+    /// pub extern "C" fn echo(list: K) -> K{
+    ///   KVal::from_raw_tracked(list).to_k()
+    /// }
+    /// ```
+    /// ```q
+    /// q)echo: `libapi_examples 2: (`echo; 1);
+    /// q)list: til 1000000
+    /// q)-16!echo list
+    /// 2i
+    /// ```
+    pub fn from_raw_tracked(k: K) -> TrackedKVal<'a> {
+        TrackedKVal {
+            value: KVal::from_raw(k),
+            raw: k,
+        }
+    }
+}
+
+impl<'a> TrackedKVal<'a> {
+    /// Check if the value still borrows the whole buffer of the q object as the same type. Only a simple list other
+    ///  than bool and symbol lists, a string and an enum list borrow the buffer, so any other value is never
+    ///  unmodified, e.g., a symbol list which is decoded into strings on conversion.
+    pub fn is_unmodified(&self) -> bool {
+        if self.raw.is_null() || self.value.qtype() != self.raw.get_type() {
+            return false;
+        }
+        match borrowed_buffer(&self.value) {
+            Some((pointer, len)) => {
+                pointer == as_slice::<G>(self.raw).as_ptr() && len == self.raw.len() as usize
+            }
+            None => false,
+        }
+    }
+
+    /// Build a q object of the value. The original object is returned with its reference count incremented if the
+    ///  value is [unmodified](#method.is_unmodified). Otherwise a new object is built by
+    ///  [`KVal::to_k`](enum.KVal.html#method.to_k).
+    pub fn to_k(&self) -> K {
+        if self.is_unmodified() {
+            increment_reference_count(self.raw)
+        } else {
+            self.value.to_k()
+        }
+    }
+
+    /// Take the value out, forgetting the q object.
+    pub fn into_inner(self) -> KVal<'a> {
+        self.value
+    }
+}
+
+impl<'a> Deref for TrackedKVal<'a> {
+    type Target = KVal<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<'a> DerefMut for TrackedKVal<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Get the pointer to and the number of elements of a borrowed buffer of a value. An enum list is borrowed only
+///  without a source, which is how it is built from q.
+fn borrowed_buffer(value: &KVal) -> Option<(*const G, usize)> {
+    fn of<T: Clone>(data: &KData<T>) -> Option<(*const G, usize)> {
+        match data {
+            KData::List(Cow::Borrowed(list)) => Some((list.as_ptr() as *const G, list.len())),
+            _ => None,
+        }
+    }
+    match value {
+        KVal::Guid(data) => of(data),
+        KVal::Byte(data) => of(data),
+        KVal::Short(data) => of(data),
+        KVal::Int(data)
+        | KVal::Month(data)
+        | KVal::Date(data)
+        | KVal::Minute(data)
+        | KVal::Second(data)
+        | KVal::Time(data) => of(data),
+        KVal::Long(data)
+        | KVal::Timestamp(data)
+        | KVal::Timespan(data)
+        | KVal::Enum(data, None) => of(data),
+        KVal::Real(data) => of(data),
+        KVal::Float(data) | KVal::Datetime(data) => of(data),
+        KVal::String(Cow::Borrowed(string)) => Some((string.as_ptr(), string.len())),
+        _ => None,
+    }
+}
@@ -0,0 +1,89 @@
+//! Tests of the limit of the length of a q list built from `KVal`. The limit is shared by the whole process and
+//!  therefore these tests are separated from the other tests and run in a single test function.
+
+#![cfg(feature = "api")]
+
+//+++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+//                     Load Library                      //
+//+++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+mod mock;
+
+use kdbplus::api::rusty_api::*;
+use kdbplus::api::*;
+use std::borrow::Cow;
+
+//+++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+//                         Tests                         //
+//+++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+#[test]
+fn length_limit_test() {
+    // The default limit is the largest length q can hold.
+    assert_eq!(max_list_length(), i64::MAX as usize);
+    assert_eq!(set_max_list_length(usize::MAX), i64::MAX as usize);
+    assert_eq!(max_list_length(), i64::MAX as usize);
+
+    set_max_list_length(3);
+    let too_long = |value: KVal| {
+        mock::clear_error();
+        assert!(value.to_k().is_null());
+        assert_eq!(
+            mock::last_error().as_deref(),
+            Some("too long: 4 elements exceed the limit of 3")
+        );
+        assert_eq!(mock::live_objects(), 0);
+    };
+
+    // Every simple list.
+    too_long(KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3, 4]))));
+    too_long(KVal::Bool(KData::List(Cow::Owned(vec![true; 4]))));
+    too_long(KVal::Guid(KData::List(Cow::Owned(vec![[0; 16]; 4]))));
+    too_long(KVal::Symbol(KData::List(Cow::Owned(vec![
+        "a".to_string();
+        4
+    ]))));
+    too_long(KVal::String(Cow::Borrowed(b"abcd")));
+    too_long(KVal::Enum(
+        KData::List(Cow::Owned(vec![0; 4])),
+        Some("sym".to_string()),
+    ));
+    too_long(KVal::CompoundList(vec![KVal::Null; 4]));
+
+    // A nested list is too long even if its parent is not. The converted elements are freed.
+    too_long(KVal::CompoundList(vec![
+        KVal::Long(KData::List(Cow::Owned(vec![1]))),
+        KVal::Float(KData::List(Cow::Owned(vec![1.0; 4]))),
+    ]));
+    too_long(KVal::Table(KTable::new(KDict::new(
+        KVal::Symbol(KData::List(Cow::Owned(vec!["a".to_string()]))),
+        KVal::CompoundList(vec![KVal::Long(KData::List(Cow::Owned(vec![1; 4])))]),
+    ))));
+
+    // Lists within the limit are converted.
+    let k = KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3]))).to_k();
+    assert_eq!(k.len(), 3);
+    decrement_reference_count(k);
+
+    // Join fails before building the result.
+    let base = KVal::Long(KData::List(Cow::Owned(vec![1, 2])));
+    assert_eq!(
+        KVal::join(base.clone(), KVal::Long(KData::Atom(Cow::Owned(3)))).unwrap(),
+        KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3])))
+    );
+    assert_eq!(
+        KVal::join(base, KVal::Long(KData::List(Cow::Owned(vec![3, 4])))).unwrap_err(),
+        KError::TooLong { len: 4, limit: 3 }
+    );
+
+    // Take fails before repeating the elements.
+    let single = KVal::Long(KData::List(Cow::Owned(vec![1])));
+    assert_eq!(
+        single.clone().take(3).unwrap(),
+        KVal::Long(KData::List(Cow::Owned(vec![1; 3])))
+    );
+    assert_eq!(single.take(-4).unwrap_err(), errors::TOO_LONG);
+
+    set_max_list_length(usize::MAX);
+    assert_eq!(mock::live_objects(), 0);
+}
@@ -0,0 +1,687 @@
+//! Mock of the q C API so that `api` can be tested without a q process. The functions declared in
+//!  `kdbplus::api::native` are defined here with the same symbol names and resolved at link time.
+//!
+//! - q objects are allocated from the Rust allocator and tracked per thread so that tests can assert
+//!   that nothing leaked with [`live_objects`].
+//! - A reference count of 0 means a single reference as in q. `r0` frees an object whose count is 0.
+//! - `krr` records the error message and returns a null pointer as q does.
+//! - `k` is variadic in C. It is defined with a fixed number of `K` arguments, which shares the calling
+//!   convention for integer and pointer arguments on x86_64, and dispatched to a handler set by
+//!   [`set_k_handler`]. Arguments after the first null pointer are ignored.
+//! - Functions which have no sensible mock (IPC, serialization, ...) signal an error.
+
+#![allow(dead_code, non_snake_case)]
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use kdbplus::api::{const_S, F, I, J, K, KNULL, S, U, V};
+use kdbplus::qtype;
+use std::alloc::{alloc_zeroed, dealloc, Layout};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::sync::Mutex;
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Size of the header of `k0` before the list elements (`m`, `a`, `t`, `u`, `r` and `n`).
+const HEADER_SIZE: usize = 16;
+
+/// Interned symbols. A `CString` keeps its buffer at the same address when the map is rehashed.
+static SYMBOLS: Mutex<Option<HashMap<Vec<u8>, CString>>> = Mutex::new(None);
+
+/// Handler of `k`.
+type KHandler = Box<dyn FnMut(&str, &[K]) -> K>;
+
+thread_local! {
+    /// Layouts of the objects allocated by this thread and not freed yet.
+    static LIVE: RefCell<HashMap<usize, Layout>> = RefCell::new(HashMap::new());
+    /// Message of the last error signalled by `krr`.
+    static LAST_ERROR: RefCell<Option<S>> = const { RefCell::new(None) };
+    /// Handler of `k`.
+    static K_HANDLER: RefCell<Option<KHandler>> = RefCell::new(None);
+    /// Value set by `setm`.
+    static SYMBOL_LOCK: RefCell<I> = const { RefCell::new(0) };
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Test Helpers
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Number of q objects allocated by this thread and not freed yet.
+pub fn live_objects() -> usize {
+    LIVE.with(|live| live.borrow().len())
+}
+
+/// Check if the object was allocated by the mock and not freed yet.
+pub fn is_live(k: K) -> bool {
+    LIVE.with(|live| live.borrow().contains_key(&(k as usize)))
+}
+
+/// Message of the last error signalled by `krr` or `orr`.
+pub fn last_error() -> Option<String> {
+    LAST_ERROR.with(|error| {
+        error.borrow().map(|message| {
+            unsafe { CStr::from_ptr(message) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    })
+}
+
+/// Clear the last error.
+pub fn clear_error() {
+    LAST_ERROR.with(|error| *error.borrow_mut() = None);
+}
+
+/// Set a handler of `k`. The handler receives the query and the non-null arguments and is responsible for
+///  the reference counts of the arguments in the same manner as q.
+pub fn set_k_handler(handler: impl FnMut(&str, &[K]) -> K + 'static) {
+    K_HANDLER.with(|cell| *cell.borrow_mut() = Some(Box::new(handler)));
+}
+
+/// Remove the handler of `k`.
+pub fn clear_k_handler() {
+    K_HANDLER.with(|cell| *cell.borrow_mut() = None);
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Size of an element of a list.
+fn element_size(qtype: i8) -> usize {
+    match qtype {
+        qtype::BOOL_LIST | qtype::BYTE_LIST | qtype::STRING => 1,
+        qtype::GUID_LIST => 16,
+        qtype::SHORT_LIST => 2,
+        qtype::INT_LIST
+        | qtype::REAL_LIST
+        | qtype::MONTH_LIST
+        | qtype::DATE_LIST
+        | qtype::MINUTE_LIST
+        | qtype::SECOND_LIST
+        | qtype::TIME_LIST => 4,
+        _ => 8,
+    }
+}
+
+/// Allocate an object with room for `capacity` elements.
+fn allocate(qtype: i8, capacity: usize) -> K {
+    let size = HEADER_SIZE + (capacity * element_size(qtype)).max(16);
+    let layout = Layout::from_size_align(size, 16).unwrap();
+    let k = unsafe { alloc_zeroed(layout) } as K;
+    assert!(!k.is_null(), "mock: allocation failure");
+    LIVE.with(|live| live.borrow_mut().insert(k as usize, layout));
+    unsafe {
+        (*k).qtype = qtype;
+        (*k).refcount = 0;
+    }
+    k
+}
+
+/// Pointer to the head of list elements.
+fn elements(k: K) -> *mut u8 {
+    unsafe { (k as *mut u8).add(HEADER_SIZE) }
+}
+
+/// Length of a list.
+fn length(k: K) -> usize {
+    unsafe { (*k).value.list.n as usize }
+}
+
+/// Free an object without looking at the reference count.
+fn free(k: K) {
+    let qtype = unsafe { (*k).qtype };
+    match qtype {
+        qtype::COMPOUND_LIST | qtype::DICTIONARY | qtype::SORTED_DICTIONARY => {
+            let children = elements(k) as *mut K;
+            for i in 0..length(k) {
+                let child = unsafe { *children.add(i) };
+                if !child.is_null() {
+                    r0(child);
+                }
+            }
+        }
+        qtype::TABLE => {
+            r0(unsafe { (*k).value.table });
+        }
+        qtype::FOREIGN => {
+            // q calls the first element with the foreign object itself.
+            let destructor = unsafe { *(elements(k) as *mut K) };
+            if !destructor.is_null() {
+                let destructor: extern "C" fn(K) -> K = unsafe { std::mem::transmute(destructor) };
+                destructor(k);
+            }
+        }
+        _ => {}
+    }
+    let layout = LIVE
+        .with(|live| live.borrow_mut().remove(&(k as usize)))
+        .expect("mock: free of an object which is not alive");
+    unsafe { dealloc(k as *mut u8, layout) };
+}
+
+/// Grow a list in place (reallocate if necessary) and return the new list with the updated length.
+fn grow(list: *mut K, additional: usize) -> K {
+    let old = unsafe { *list };
+    let qtype = unsafe { (*old).qtype };
+    let n = length(old);
+    let new = allocate(qtype, n + additional);
+    unsafe {
+        std::ptr::copy_nonoverlapping(elements(old), elements(new), n * element_size(qtype));
+        (*new).value.list.n = (n + additional) as J;
+        (*new).refcount = (*old).refcount;
+        (*new).attribute = (*old).attribute;
+    }
+    // Free the old buffer without freeing the moved elements.
+    let layout = LIVE
+        .with(|live| live.borrow_mut().remove(&(old as usize)))
+        .expect("mock: grow an object which is not alive");
+    unsafe {
+        dealloc(old as *mut u8, layout);
+        *list = new;
+    }
+    new
+}
+
+/// Intern a byte string.
+fn intern(bytes: &[u8]) -> S {
+    // Symbols end at the first null character.
+    let bytes = match bytes.iter().position(|byte| *byte == 0) {
+        Some(end) => &bytes[..end],
+        None => bytes,
+    };
+    let mut symbols = SYMBOLS.lock().unwrap();
+    let symbols = symbols.get_or_insert_with(HashMap::new);
+    symbols
+        .entry(bytes.to_vec())
+        .or_insert_with(|| CString::new(bytes).unwrap())
+        .as_ptr() as S
+}
+
+/// Signal an error with a static message.
+fn error(message: &str) -> K {
+    krr(intern(message.as_bytes()))
+}
+
+/// Days from 2000.01.01 of a civil date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 730425
+}
+
+/// Civil date of days from 2000.01.01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 730425;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Mocked C API
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+//%% Constructors %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+#[no_mangle]
+pub extern "C" fn ka(qtype: I) -> K {
+    // Room for a GUID which is placed after the header.
+    allocate(qtype as i8, 2)
+}
+
+#[no_mangle]
+pub extern "C" fn kb(boolean: I) -> K {
+    let k = ka(qtype::BOOL_ATOM as I);
+    unsafe { (*k).value.byte = (boolean != 0) as u8 };
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn ku(array: U) -> K {
+    let k = ka(qtype::GUID_ATOM as I);
+    unsafe {
+        (*k).value.list.n = 1;
+        std::ptr::copy_nonoverlapping(array.guid.as_ptr(), elements(k), 16);
+    }
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn kg(byte: I) -> K {
+    let k = ka(qtype::BYTE_ATOM as I);
+    unsafe { (*k).value.byte = byte as u8 };
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn kh(short: I) -> K {
+    let k = ka(qtype::SHORT_ATOM as I);
+    unsafe { (*k).value.short = short as i16 };
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn ki(int: I) -> K {
+    let k = ka(qtype::INT_ATOM as I);
+    unsafe { (*k).value.int = int };
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn kj(long: J) -> K {
+    let k = ka(qtype::LONG_ATOM as I);
+    unsafe { (*k).value.long = long };
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn ke(real: F) -> K {
+    let k = ka(qtype::REAL_ATOM as I);
+    unsafe { (*k).value.real = real as f32 };
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn kf(float: F) -> K {
+    let k = ka(qtype::FLOAT_ATOM as I);
+    unsafe { (*k).value.float = float };
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn kc(character: I) -> K {
+    let k = ka(qtype::CHAR as I);
+    unsafe { (*k).value.byte = character as u8 };
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn ks(symbol: S) -> K {
+    let k = ka(qtype::SYMBOL_ATOM as I);
+    unsafe { (*k).value.symbol = ss(symbol) };
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn ktj(qtype: I, nanoseconds: J) -> K {
+    let k = ka(qtype);
+    unsafe { (*k).value.long = nanoseconds };
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn kd(date: I) -> K {
+    let k = ka(qtype::DATE_ATOM as I);
+    unsafe { (*k).value.int = date };
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn kz(datetime: F) -> K {
+    let k = ka(qtype::DATETIME_ATOM as I);
+    unsafe { (*k).value.float = datetime };
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn kt(milliseconds: I) -> K {
+    let k = ka(qtype::TIME_ATOM as I);
+    unsafe { (*k).value.int = milliseconds };
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn ktn(qtype: I, length: J) -> K {
+    let k = allocate(qtype as i8, length as usize);
+    unsafe { (*k).value.list.n = length };
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn kp(chararray: S) -> K {
+    let length = unsafe { CStr::from_ptr(chararray) }.to_bytes().len();
+    kpn(chararray, length as J)
+}
+
+#[no_mangle]
+pub extern "C" fn kpn(chararray: S, length: J) -> K {
+    let k = ktn(qtype::STRING as I, length);
+    unsafe { std::ptr::copy_nonoverlapping(chararray as *const u8, elements(k), length as usize) };
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn xD(keys: K, values: K) -> K {
+    let k = ktn(qtype::DICTIONARY as I, 2);
+    unsafe {
+        *(elements(k) as *mut K) = keys;
+        *(elements(k) as *mut K).add(1) = values;
+    }
+    k
+}
+
+#[no_mangle]
+pub extern "C" fn xT(dictionary: K) -> K {
+    let pair = elements(dictionary) as *mut K;
+    let (keys, values) = unsafe { (*pair, *pair.add(1)) };
+    let is_table = unsafe {
+        (*dictionary).qtype == qtype::DICTIONARY
+            && (*keys).qtype == qtype::SYMBOL_LIST
+            && (*values).qtype == qtype::COMPOUND_LIST
+            && length(keys) == length(values)
+    };
+    if !is_table {
+        r0(dictionary);
+        return error("type");
+    }
+    let columns = elements(values) as *mut K;
+    let rows = (0..length(values))
+        .map(|i| unsafe { *columns.add(i) })
+        .map(|column| unsafe {
+            match (*column).qtype {
+                qtype::COMPOUND_LIST..=qtype::ENUM_LIST => Some(length(column)),
+                _ => None,
+            }
+        })
+        .collect::<Option<Vec<_>>>();
+    match rows {
+        Some(rows) if rows.windows(2).all(|pair| pair[0] == pair[1]) => {
+            let k = ka(qtype::TABLE as I);
+            unsafe { (*k).value.table = dictionary };
+            k
+        }
+        _ => {
+            r0(dictionary);
+            error("length")
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn ktd(keyedtable: K) -> K {
+    r0(keyedtable);
+    error("mock: ktd is not supported")
+}
+
+#[no_mangle]
+pub extern "C" fn knt(_keynum: J, table: K) -> K {
+    r0(table);
+    error("mock: knt is not supported")
+}
+
+#[no_mangle]
+pub extern "C" fn knk(_qtype: I, _a: K, _b: K, _c: K, _d: K, _e: K, _f: K) -> K {
+    error("mock: knk is not supported")
+}
+
+//%% Error %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+#[no_mangle]
+pub extern "C" fn krr(message: const_S) -> K {
+    LAST_ERROR.with(|error| *error.borrow_mut() = Some(message as S));
+    KNULL
+}
+
+#[no_mangle]
+pub extern "C" fn orr(message: const_S) -> K {
+    krr(message)
+}
+
+#[no_mangle]
+pub extern "C" fn ee(result: K) -> K {
+    if result.is_null() {
+        let k = ka(qtype::ERROR as I);
+        let message = LAST_ERROR.with(|error| error.borrow_mut().take());
+        unsafe { (*k).value.symbol = message.unwrap_or(std::ptr::null_mut()) };
+        k
+    } else {
+        result
+    }
+}
+
+//%% List %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+#[no_mangle]
+pub extern "C" fn ja(list: *mut K, value: *mut V) -> K {
+    let size = element_size(unsafe { (**list).qtype });
+    let new = grow(list, 1);
+    unsafe {
+        std::ptr::copy_nonoverlapping(
+            value as *const u8,
+            elements(new).add((length(new) - 1) * size),
+            size,
+        )
+    };
+    new
+}
+
+#[no_mangle]
+pub extern "C" fn jv(list1: *mut K, list2: K) -> K {
+    let qtype = unsafe { (**list1).qtype };
+    if qtype != unsafe { (*list2).qtype } {
+        return error("type");
+    }
+    let n = length(unsafe { *list1 });
+    let m = length(list2);
+    let size = element_size(qtype);
+    let new = grow(list1, m);
+    unsafe {
+        std::ptr::copy_nonoverlapping(elements(list2), elements(new).add(n * size), m * size)
+    };
+    if qtype == qtype::COMPOUND_LIST {
+        for i in 0..m {
+            r1(unsafe { *(elements(list2) as *mut K).add(i) });
+        }
+    }
+    new
+}
+
+#[no_mangle]
+pub extern "C" fn jk(list: *mut K, value: K) -> K {
+    let mut value = value;
+    ja(list, &mut value as *mut K as *mut V)
+}
+
+#[no_mangle]
+pub extern "C" fn js(list: *mut K, symbol: S) -> K {
+    let mut symbol = symbol;
+    ja(list, &mut symbol as *mut S as *mut V)
+}
+
+//%% Symbol %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+#[no_mangle]
+pub extern "C" fn sn(string: S, n: I) -> S {
+    intern(unsafe { std::slice::from_raw_parts(string as *const u8, n as usize) })
+}
+
+#[no_mangle]
+pub extern "C" fn ss(string: S) -> S {
+    intern(unsafe { CStr::from_ptr(string) }.to_bytes())
+}
+
+#[no_mangle]
+pub extern "C" fn setm(lock: I) -> I {
+    SYMBOL_LOCK.with(|cell| cell.replace(lock))
+}
+
+//%% Reference Count %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+/// Returns `u8` in place of `void` so that the caller reads a valid value of `V`.
+#[no_mangle]
+pub extern "C" fn r0(qobject: K) -> u8 {
+    if qobject.is_null() {
+        return 0;
+    }
+    if unsafe { (*qobject).refcount } > 0 {
+        unsafe { (*qobject).refcount -= 1 };
+    } else {
+        free(qobject);
+    }
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn r1(qobject: K) -> K {
+    unsafe { (*qobject).refcount += 1 };
+    qobject
+}
+
+#[no_mangle]
+pub extern "C" fn m9() -> u8 {
+    0
+}
+
+//%% Evaluation %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+#[no_mangle]
+pub extern "C" fn k(
+    _socket: I,
+    query: const_S,
+    a1: K,
+    a2: K,
+    a3: K,
+    a4: K,
+    a5: K,
+    a6: K,
+    a7: K,
+    a8: K,
+) -> K {
+    let query = unsafe { CStr::from_ptr(query) }
+        .to_string_lossy()
+        .into_owned();
+    let args = [a1, a2, a3, a4, a5, a6, a7, a8]
+        .into_iter()
+        .take_while(|arg| !arg.is_null())
+        .collect::<Vec<_>>();
+    let handler = K_HANDLER.with(|cell| cell.borrow_mut().take());
+    match handler {
+        Some(mut handler) => {
+            let result = handler(&query, &args);
+            K_HANDLER.with(|cell| {
+                cell.borrow_mut().get_or_insert(handler);
+            });
+            result
+        }
+        None => {
+            args.into_iter().for_each(|arg| {
+                r0(arg);
+            });
+            error("mock: no handler of k")
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dot(_func: K, _args: K) -> K {
+    error("mock: dot is not supported")
+}
+
+#[no_mangle]
+pub extern "C" fn dl(_func: *const V, _n: J) -> K {
+    error("mock: dl is not supported")
+}
+
+//%% Serialization %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+#[no_mangle]
+pub extern "C" fn b9(_mode: I, _qobject: K) -> K {
+    error("mock: b9 is not supported")
+}
+
+#[no_mangle]
+pub extern "C" fn d9(_bytes: K) -> K {
+    error("mock: d9 is not supported")
+}
+
+#[no_mangle]
+pub extern "C" fn okx(_bytes: K) -> I {
+    0
+}
+
+//%% Date %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+#[no_mangle]
+pub extern "C" fn ymd(year: I, month: I, date: I) -> I {
+    days_from_civil(year as i64, month as i64, date as i64) as I
+}
+
+#[no_mangle]
+pub extern "C" fn dj(days: I) -> I {
+    let (year, month, day) = civil_from_days(days as i64);
+    (year * 10000 + month * 100 + day) as I
+}
+
+//%% Socket %%//vvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvvv/
+
+#[no_mangle]
+pub extern "C" fn sd0(_socket: I) -> u8 {
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn sd0x(_socket: I, _condition: I) -> u8 {
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn sd1(_socket: I, _function: extern "C" fn(I) -> K) -> K {
+    error("mock: sd1 is not supported")
+}
+
+#[no_mangle]
+pub extern "C" fn khpunc(_host: S, _port: I, _credential: S, _timeout: I, _capability: I) -> I {
+    -1
+}
+
+#[no_mangle]
+pub extern "C" fn khpun(_host: const_S, _port: I, _credential: const_S, _timeout: I) -> I {
+    -1
+}
+
+#[no_mangle]
+pub extern "C" fn khpu(_host: const_S, _port: I, _credential: const_S) -> I {
+    -1
+}
+
+#[no_mangle]
+pub extern "C" fn khp(_host: const_S, _port: I) -> I {
+    -1
+}
+
+#[no_mangle]
+pub extern "C" fn kclose(_socket: I) -> u8 {
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn sslInfo(_: K) -> K {
+    error("mock: sslInfo is not supported")
+}
+
+#[no_mangle]
+pub extern "C" fn ver() -> I {
+    0
+}
@@ -0,0 +1,7112 @@
+//! Tests of `api` run against the mock of the q C API.
+
+#![cfg(feature = "api")]
+
+//+++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+//                     Load Library                      //
+//+++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+mod mock;
+
+use kdbplus::api::rusty_api::*;
+use kdbplus::api::*;
+use kdbplus::{kval, qattribute, qinf_base, qninf_base, qnull_base, qtype, str_to_S};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+//+++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+//                    Test Utilities                     //
+//+++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+thread_local! {
+    static DROPPED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Value counting how many times it was dropped.
+struct Tracked {
+    id: i64,
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        DROPPED.with(|dropped| dropped.set(dropped.get() + 1));
+    }
+}
+
+fn dropped() -> usize {
+    DROPPED.with(Cell::get)
+}
+
+/// Call the destructor stored in the first element of a foreign object as q does.
+fn call_destructor(destructor: K, obj: K) -> K {
+    let destructor: extern "C" fn(K) -> K = unsafe { std::mem::transmute(destructor) };
+    destructor(obj)
+}
+
+//+++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+//                     Test Functions                    //
+//+++++++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+#[test]
+fn foreign_lifecycle_test() {
+    let obj = Foreign::new(Tracked { id: 42 });
+    assert_eq!(obj.get_type(), qtype::FOREIGN);
+    assert_eq!(Foreign::<Tracked>::from_raw(obj).unwrap().id, 42);
+    Foreign::<Tracked>::from_raw_mut(obj).unwrap().id += 1;
+    assert_eq!(Foreign::<Tracked>::from_raw(obj).unwrap().id, 43);
+    assert_eq!(
+        Foreign::<String>::from_raw(obj).err(),
+        Some("foreign object holds another type\0")
+    );
+    assert_eq!(dropped(), 0);
+
+    // Shared reference.
+    increment_reference_count(obj);
+    decrement_reference_count(obj);
+    assert_eq!(dropped(), 0);
+
+    decrement_reference_count(obj);
+    assert_eq!(dropped(), 1);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn foreign_double_drop_test() {
+    let obj = Foreign::new(Tracked { id: 0 });
+    let destructor = obj.as_mut_slice::<K>()[0];
+
+    call_destructor(destructor, obj);
+    assert_eq!(dropped(), 1);
+    assert_eq!(
+        Foreign::<Tracked>::from_raw(obj).err(),
+        Some("foreign object was already dropped\0")
+    );
+
+    call_destructor(destructor, obj);
+    assert_eq!(dropped(), 1);
+
+    // Freeing the object does not call the destructor again through the cleared slot.
+    decrement_reference_count(obj);
+    assert_eq!(dropped(), 1);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn foreign_tampered_test() {
+    let obj = Foreign::new(Tracked { id: 0 });
+    let destructor = obj.as_mut_slice::<K>()[0];
+    let payload = obj.as_mut_slice::<K>()[1];
+
+    // Payload replaced with memory not built by `Foreign`.
+    let mut fake = [0_u64; 8];
+    obj.as_mut_slice::<K>()[1] = fake.as_mut_ptr() as K;
+    call_destructor(destructor, obj);
+    assert_eq!(dropped(), 0);
+    assert_eq!(
+        Foreign::<Tracked>::from_raw(obj).err(),
+        Some("foreign object was not built by Foreign\0")
+    );
+    obj.as_mut_slice::<K>()[1] = payload;
+
+    // Length changed.
+    unsafe { (*obj).value.list.n = 3 };
+    call_destructor(destructor, obj);
+    assert_eq!(dropped(), 0);
+    unsafe { (*obj).value.list.n = 2 };
+
+    // Type changed.
+    unsafe { (*obj).qtype = qtype::COMPOUND_LIST };
+    call_destructor(destructor, obj);
+    assert_eq!(dropped(), 0);
+    unsafe { (*obj).qtype = qtype::FOREIGN };
+
+    // Destructor applied to a non-foreign object.
+    let list = new_list(qtype::LONG_LIST, 2);
+    list.as_mut_slice::<J>().copy_from_slice(&[1, 2]);
+    call_destructor(destructor, list);
+    assert_eq!(list.as_mut_slice::<J>(), &[1, 2]);
+    decrement_reference_count(list);
+
+    assert_eq!(Foreign::<Tracked>::from_raw(obj).unwrap().id, 0);
+    decrement_reference_count(obj);
+    assert_eq!(dropped(), 1);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn drop_q_object_test() {
+    // Dropped once.
+    let mut obj = new_list(qtype::COMPOUND_LIST, 2);
+    obj.as_mut_slice::<K>()[0] = KNULL;
+    obj.as_mut_slice::<K>()[1] = Box::into_raw(Box::new([0_u64; 3])) as K;
+    obj.set_type(qtype::FOREIGN);
+    drop_q_object(obj);
+    assert!(obj.as_mut_slice::<K>()[1].is_null());
+    drop_q_object(obj);
+    decrement_reference_count(obj);
+
+    // Not a foreign object.
+    let list = new_list(qtype::COMPOUND_LIST, 2);
+    list.as_mut_slice::<K>()[0] = new_long(1);
+    list.as_mut_slice::<K>()[1] = new_long(2);
+    drop_q_object(list);
+    assert_eq!(list.as_mut_slice::<K>()[1].get_long(), Ok(2));
+    decrement_reference_count(list);
+
+    // Wrong length.
+    let mut obj = new_list(qtype::COMPOUND_LIST, 3);
+    obj.as_mut_slice::<K>().copy_from_slice(&[KNULL; 3]);
+    obj.set_type(qtype::FOREIGN);
+    drop_q_object(obj);
+    decrement_reference_count(obj);
+
+    // Null pointer.
+    assert!(drop_q_object(KNULL).is_null());
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn kval_slice_test() {
+    let list = new_list(qtype::LONG_LIST, 1_000_000);
+    list.as_mut_slice::<J>()
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, long)| *long = i as J);
+    let value = KVal::from_raw(list);
+    match value.slice(499_000..501_000).unwrap() {
+        KVal::Long(KData::List(Cow::Borrowed(window))) => {
+            assert_eq!(window.len(), 2_000);
+            assert_eq!(window[0], 499_000);
+            // Borrows the buffer of the q list.
+            assert!(std::ptr::eq(
+                window,
+                &list.as_mut_slice::<J>()[499_000..501_000]
+            ));
+        }
+        _ => panic!("slice of a long list must be a borrowed long list"),
+    }
+    assert_eq!(value.slice(7..7).unwrap().len(), 0);
+    assert_eq!(
+        value.slice(999_999..1_000_001).err(),
+        Some("index out of bounds\0")
+    );
+    #[allow(clippy::reversed_empty_ranges)]
+    let decreasing = value.slice(6..3);
+    assert_eq!(decreasing.err(), Some("index out of bounds\0"));
+
+    let string = KVal::String(Cow::Borrowed(b"kdbplus"));
+    match string.slice(3..7).unwrap() {
+        KVal::String(Cow::Borrowed(plus)) => assert_eq!(plus, b"plus"),
+        _ => panic!("slice of a string must be a borrowed string"),
+    }
+    // A string is sliced by bytes even inside a multi-byte character.
+    let multibyte = KVal::String(Cow::Borrowed("café".as_bytes()));
+    assert_eq!(
+        multibyte.slice(3..4).unwrap(),
+        KVal::String(Cow::Borrowed(&[0xc3]))
+    );
+
+    let compound = KVal::CompoundList(vec![KVal::Long(KData::Atom(Cow::Owned(1))), KVal::Null]);
+    assert_eq!(compound.slice(1..2).unwrap().len(), 1);
+    assert_eq!(
+        KVal::Long(KData::Atom(Cow::Owned(1))).slice(0..1).err(),
+        Some("not a list\0")
+    );
+    assert_eq!(KVal::Null.slice(0..1).err(), Some("not a list\0"));
+
+    decrement_reference_count(list);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+/// Build a table of `sym`, `price` and `size` with the specified number of rows.
+fn trades_table(rows: usize) -> KTable<'static> {
+    let symbols = ["a", "b", "c"]
+        .iter()
+        .cycle()
+        .take(rows)
+        .map(|symbol| symbol.to_string())
+        .collect::<Vec<_>>();
+    let prices = (0..rows).map(|i| i as f64 + 0.5).collect::<Vec<_>>();
+    let sizes = (0..rows).map(|i| i as i64 * 100).collect::<Vec<_>>();
+    KTable::new(KDict::new(
+        KVal::Symbol(KData::List(Cow::Owned(vec![
+            "sym".to_string(),
+            "price".to_string(),
+            "size".to_string(),
+        ]))),
+        KVal::CompoundList(vec![
+            KVal::Symbol(KData::List(Cow::Owned(symbols))),
+            KVal::Float(KData::List(Cow::Owned(prices))),
+            KVal::Long(KData::List(Cow::Owned(sizes))),
+        ]),
+    ))
+}
+
+/// Build a long atom.
+fn long(value: i64) -> KVal<'static> {
+    KVal::Long(KData::Atom(Cow::Owned(value)))
+}
+
+/// Build a long list.
+fn longs(list: Vec<i64>) -> KVal<'static> {
+    KVal::Long(KData::List(Cow::Owned(list)))
+}
+
+/// Build a q table of `sym`, `price` and `size` with the specified number of rows.
+fn trades(rows: usize) -> K {
+    trades_table(rows).to_k()
+}
+
+#[test]
+fn peek_table_test() {
+    for rows in [0, 1, 1000] {
+        let table = trades(rows);
+        let converted = KTable::from_raw(table);
+        assert_eq!(peek_table_rows(table), Ok(converted.len() as i64));
+        let names = match converted.dict.keys.as_ref() {
+            KVal::Symbol(KData::List(names)) => names.to_vec(),
+            _ => panic!("column names must be a symbol list"),
+        };
+        assert_eq!(
+            peek_table_schema(table).unwrap(),
+            names
+                .into_iter()
+                .zip([qtype::SYMBOL_LIST, qtype::FLOAT_LIST, qtype::LONG_LIST])
+                .collect::<Vec<_>>()
+        );
+        decrement_reference_count(table);
+    }
+
+    // Keyed table.
+    let keys = KTable::new(KDict::new(
+        KVal::Symbol(KData::List(Cow::Owned(vec!["id".to_string()]))),
+        KVal::CompoundList(vec![KVal::Int(KData::List(Cow::Owned(vec![1, 2])))]),
+    ))
+    .to_k();
+    let keyed = new_dictionary(keys, trades(2));
+    assert_eq!(peek_table_rows(keyed), Ok(2));
+    assert_eq!(
+        peek_table_schema(keyed).unwrap(),
+        vec![
+            ("id".to_string(), qtype::INT_LIST),
+            ("sym".to_string(), qtype::SYMBOL_LIST),
+            ("price".to_string(), qtype::FLOAT_LIST),
+            ("size".to_string(), qtype::LONG_LIST),
+        ]
+    );
+    decrement_reference_count(keyed);
+
+    // Not a table.
+    let list = new_list(qtype::LONG_LIST, 3);
+    assert_eq!(
+        peek_table_rows(list).unwrap_err().to_string(),
+        "invalid operation peek_table_rows on long list. expected: table"
+    );
+    assert_eq!(
+        peek_table_schema(KNULL).unwrap_err().to_string(),
+        "invalid operation peek_table_schema on general null. expected: table"
+    );
+    let dictionary = new_dictionary(new_list(qtype::LONG_LIST, 0), list);
+    assert_eq!(
+        peek_table_rows(dictionary).unwrap_err().to_string(),
+        "invalid operation peek_table_rows on dictionary. expected: table"
+    );
+    decrement_reference_count(dictionary);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn kval_iter_test() {
+    let list = new_list(qtype::LONG_LIST, 4);
+    list.as_mut_slice::<J>().copy_from_slice(&[1, 2, 3, 4]);
+    let value = KVal::from_raw(list);
+    let iter = value.iter();
+    assert_eq!(iter.len(), 4);
+    let longs = iter
+        .map(|element| match element {
+            KVal::Long(KData::Atom(Cow::Borrowed(long))) => *long,
+            _ => panic!("element of a long list must be a borrowed long atom"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(longs, vec![1, 2, 3, 4]);
+    let mut iter = value.iter();
+    iter.next();
+    iter.next_back();
+    assert_eq!(iter.len(), 2);
+    assert_eq!(iter.rev().count(), 2);
+    decrement_reference_count(list);
+
+    let string = KVal::String(Cow::Borrowed(b"kdb"));
+    let chars = string
+        .iter()
+        .map(|element| match element {
+            KVal::Char(character) => character,
+            _ => panic!("element of a string must be a char"),
+        })
+        .collect::<String>();
+    assert_eq!(chars, "kdb");
+
+    let compound = KVal::CompoundList(vec![KVal::Null, KVal::Char('a')]);
+    assert_eq!(compound.iter().len(), 2);
+    assert!(matches!(compound.iter().next_back(), Some(KVal::Char('a'))));
+
+    let enums = KVal::Enum(
+        KData::List(Cow::Owned(vec![0, 1])),
+        Some("source".to_string()),
+    );
+    assert!(enums.iter().all(
+        |element| matches!(element, KVal::Enum(KData::Atom(_), Some(source)) if source == "source")
+    ));
+
+    let atom = KVal::Float(KData::Atom(Cow::Owned(1.5)));
+    assert_eq!(atom.iter().len(), 1);
+    assert!(matches!(atom.iter().next(), Some(KVal::Float(KData::Atom(float))) if *float == 1.5));
+
+    let table = trades(3);
+    assert_eq!(KVal::from_raw(table).iter().count(), 0);
+    decrement_reference_count(table);
+    assert_eq!(KVal::Null.iter().len(), 0);
+    assert!(KVal::Error(Cow::Borrowed("type")).iter().next().is_none());
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn kval_collect_test() {
+    let list = (0..5)
+        .map(|i| KVal::Long(KData::Atom(Cow::Owned(i))))
+        .chain(KVal::Int(KData::Atom(Cow::Owned(5))))
+        .collect::<KVal>();
+    let k = list.to_k();
+    assert_eq!(k.get_type(), qtype::COMPOUND_LIST);
+    assert_eq!(k.len(), 6);
+    assert_eq!(k.as_mut_slice::<K>()[4].get_long(), Ok(4));
+    assert_eq!(k.as_mut_slice::<K>()[5].get_int(), Ok(5));
+    decrement_reference_count(k);
+
+    let mut total = 0;
+    for element in &list {
+        if let KVal::Long(KData::Atom(long)) = element {
+            total += *long;
+        }
+    }
+    assert_eq!(total, 10);
+
+    let evens = list
+        .into_iter()
+        .filter(|element| matches!(element, KVal::Long(KData::Atom(long)) if **long % 2 == 0))
+        .collect::<KVal>();
+    assert_eq!(evens.len(), 3);
+
+    let atom = KVal::Symbol(KData::Atom(Cow::Owned("a".to_string())));
+    assert!(matches!(
+        atom.into_iter().collect::<Vec<_>>().as_slice(),
+        [KVal::Symbol(KData::Atom(_))]
+    ));
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn real_round_trip_test() {
+    let reals = [
+        0.1_f32,
+        f32::MAX,
+        f32::MIN_POSITIVE,
+        // Subnormal.
+        f32::from_bits(1),
+        f32::NAN,
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+        -0.0,
+    ];
+
+    // List through `KVal`.
+    let list = KVal::Real(KData::List(Cow::Borrowed(&reals))).to_k();
+    assert_eq!(list.get_type(), qtype::REAL_LIST);
+    assert_eq!(list.len(), reals.len() as i64);
+    let bits = |list: &[f32]| list.iter().map(|real| real.to_bits()).collect::<Vec<_>>();
+    assert_eq!(bits(list.as_mut_slice::<E>()), bits(&reals));
+    match KVal::from_raw(list) {
+        KVal::Real(KData::List(round_trip)) => assert_eq!(bits(&round_trip), bits(&reals)),
+        _ => panic!("real list must be converted into a real list"),
+    }
+
+    // Atoms through `KVal` and `simple_to_compound`.
+    for (i, real) in reals.iter().enumerate() {
+        let atom = KVal::Real(KData::Atom(Cow::Borrowed(real))).to_k();
+        assert_eq!(atom.get_real().unwrap().to_bits(), real.to_bits());
+        match KVal::from_raw(atom) {
+            KVal::Real(KData::Atom(round_trip)) => assert_eq!(round_trip.to_bits(), real.to_bits()),
+            _ => panic!("real atom must be converted into a real atom"),
+        }
+        decrement_reference_count(atom);
+        let element = KVal::from_raw(list).get(i).unwrap().to_k();
+        assert_eq!(element.get_real().unwrap().to_bits(), real.to_bits());
+        decrement_reference_count(element);
+    }
+    let compound = simple_to_compound(list, "");
+    assert_eq!(
+        compound
+            .as_mut_slice::<K>()
+            .iter()
+            .map(|atom| atom.get_real().unwrap().to_bits())
+            .collect::<Vec<_>>(),
+        bits(&reals)
+    );
+    decrement_reference_count(compound);
+
+    // `new_real` narrows `F` into `E`.
+    let narrowed = new_real(0.1);
+    assert_eq!(narrowed.get_real(), Ok(0.1_f32));
+    assert_ne!(narrowed.get_real().unwrap() as f64, 0.1_f64);
+    decrement_reference_count(narrowed);
+    let exact = new_real32(0.1);
+    assert_eq!(exact.get_real(), Ok(0.1_f32));
+    decrement_reference_count(exact);
+
+    // Element size is checked.
+    let mut list = new_list(qtype::REAL_LIST, 0);
+    assert_eq!(
+        list.push_raw(1.5_f64).err(),
+        Some("size of the atom does not match the list\0")
+    );
+    list.push_raw(1.5_f32).unwrap();
+    assert_eq!(list.as_mut_slice::<E>(), &[1.5]);
+    decrement_reference_count(list);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+/// Build a keyed table of `id` and the table built by `trades_table`.
+fn keyed_trades(rows: usize) -> KDict<'static> {
+    let keys = KTable::new(KDict::new(
+        KVal::Symbol(KData::List(Cow::Owned(vec!["id".to_string()]))),
+        KVal::CompoundList(vec![KVal::Long(KData::List(Cow::Owned(
+            (0..rows as i64).collect(),
+        )))]),
+    ));
+    KDict::new(KVal::Table(keys), KVal::Table(trades_table(rows)))
+}
+
+#[test]
+fn insert_into_test() {
+    // Mock of `insert` and `upsert` into a table `trades` with 3 columns.
+    let total = Rc::new(Cell::new(0_i64));
+    let table_total = total.clone();
+    mock::set_k_handler(move |query, args| {
+        let name = unsafe { (*args[0]).value.symbol };
+        let rows = args[1];
+        let result = if S_to_str(name) != "trades" {
+            unsafe { native::ee(native::krr(name)) }
+        } else {
+            match peek_table_schema(rows) {
+                // A keyed table has a key column.
+                Ok(schema) if schema.len() == 3 + (query == "upsert") as usize => {
+                    let inserted = peek_table_rows(rows).unwrap();
+                    table_total.set(table_total.get() + inserted);
+                    if query == "insert" {
+                        let indices = new_list(qtype::LONG_LIST, inserted);
+                        indices
+                            .as_mut_slice::<J>()
+                            .iter_mut()
+                            .enumerate()
+                            .for_each(|(i, index)| *index = i as J);
+                        indices
+                    } else {
+                        increment_reference_count(args[0])
+                    }
+                }
+                Ok(_) => unsafe { native::ee(new_error("mismatch\0")) },
+                Err(_) => unsafe { native::ee(new_error("type\0")) },
+            }
+        };
+        for arg in args {
+            decrement_reference_count(*arg);
+        }
+        result
+    });
+
+    assert_eq!(insert_into("trades", trades_table(2)), Ok(2));
+    assert_eq!(insert_into("trades", trades_table(3)), Ok(3));
+    assert_eq!(total.get(), 5);
+    assert_eq!(upsert_keyed("trades", keyed_trades(4)), Ok(4));
+    assert_eq!(total.get(), 9);
+
+    let error = insert_into("quotes", trades_table(1)).unwrap_err();
+    assert_eq!(error.kind(), Some(QErrorKind::NotFound));
+    assert_eq!(error.to_string(), "quotes");
+
+    let narrow = KTable::new(KDict::new(
+        KVal::Symbol(KData::List(Cow::Owned(vec!["sym".to_string()]))),
+        KVal::CompoundList(vec![KVal::Symbol(KData::List(Cow::Owned(vec![
+            "a".to_string()
+        ])))]),
+    ));
+    assert_eq!(
+        insert_into("trades", narrow).unwrap_err().kind(),
+        Some(QErrorKind::Mismatch)
+    );
+    assert_eq!(
+        insert_into("tra\0des", trades_table(1))
+            .unwrap_err()
+            .to_string(),
+        "symbol contains a null character"
+    );
+    assert_eq!(total.get(), 9);
+    mock::clear_k_handler();
+    assert_eq!(mock::live_objects(), 0);
+}
+
+/// Expected q type code of each variant. The match has no wildcard so that a new variant must be added here.
+fn expected_qtype(value: &KVal) -> i8 {
+    let atom_or_list = |is_atom: bool, list: i8| if is_atom { -list } else { list };
+    match value {
+        KVal::CompoundList(_) => 0,
+        KVal::Bool(data) => atom_or_list(matches!(data, KData::Atom(_)), 1),
+        KVal::Guid(data) => atom_or_list(matches!(data, KData::Atom(_)), 2),
+        KVal::Byte(data) => atom_or_list(matches!(data, KData::Atom(_)), 4),
+        KVal::Short(data) => atom_or_list(matches!(data, KData::Atom(_)), 5),
+        KVal::Int(data) => atom_or_list(matches!(data, KData::Atom(_)), 6),
+        KVal::Long(data) => atom_or_list(matches!(data, KData::Atom(_)), 7),
+        KVal::Real(data) => atom_or_list(matches!(data, KData::Atom(_)), 8),
+        KVal::Float(data) => atom_or_list(matches!(data, KData::Atom(_)), 9),
+        KVal::Char(_) => -10,
+        KVal::Symbol(data) => atom_or_list(matches!(data, KData::Atom(_)), 11),
+        KVal::String(_) => 10,
+        KVal::Timestamp(data) => atom_or_list(matches!(data, KData::Atom(_)), 12),
+        KVal::Month(data) => atom_or_list(matches!(data, KData::Atom(_)), 13),
+        KVal::Date(data) => atom_or_list(matches!(data, KData::Atom(_)), 14),
+        KVal::Datetime(data) => atom_or_list(matches!(data, KData::Atom(_)), 15),
+        KVal::Timespan(data) => atom_or_list(matches!(data, KData::Atom(_)), 16),
+        KVal::Minute(data) => atom_or_list(matches!(data, KData::Atom(_)), 17),
+        KVal::Second(data) => atom_or_list(matches!(data, KData::Atom(_)), 18),
+        KVal::Time(data) => atom_or_list(matches!(data, KData::Atom(_)), 19),
+        KVal::Enum(data, _) => atom_or_list(matches!(data, KData::Atom(_)), 20),
+        KVal::Table(_) => 98,
+        KVal::Dictionary(_) => 99,
+        KVal::Error(_) => -128,
+        KVal::Null => 101,
+    }
+}
+
+#[test]
+fn kval_qtype_test() {
+    // Mock of an enum source `enum` and the cast to it.
+    mock::set_k_handler(|query, args| match query {
+        "enum" => KVal::Symbol(KData::List(Cow::Owned(vec!["a".to_string(); 3]))).to_k(),
+        "{`enum$enum x}" => {
+            let indices = args[0];
+            let casted = match indices.get_long() {
+                Ok(index) => {
+                    let atom = new_long(index);
+                    unsafe { (*atom).qtype = qtype::ENUM_ATOM };
+                    atom
+                }
+                Err(_) => {
+                    let list = new_list(qtype::ENUM_LIST, indices.len());
+                    list.as_mut_slice::<J>()
+                        .copy_from_slice(indices.as_mut_slice::<J>());
+                    list
+                }
+            };
+            decrement_reference_count(indices);
+            casted
+        }
+        _ => unsafe { native::ee(new_error("unexpected query\0")) },
+    });
+
+    macro_rules! atom_and_list {
+        ($variant: path, $value: expr) => {
+            [
+                $variant(KData::Atom(Cow::Owned($value))),
+                $variant(KData::List(Cow::Owned(vec![$value, $value]))),
+            ]
+        };
+    }
+    let values = [
+        atom_and_list!(KVal::Bool, true),
+        atom_and_list!(KVal::Guid, [1_u8; 16]),
+        atom_and_list!(KVal::Byte, 1_u8),
+        atom_and_list!(KVal::Short, 1_i16),
+        atom_and_list!(KVal::Int, 1_i32),
+        atom_and_list!(KVal::Long, 1_i64),
+        atom_and_list!(KVal::Real, 1.5_f32),
+        atom_and_list!(KVal::Float, 1.5_f64),
+        atom_and_list!(KVal::Symbol, "a".to_string()),
+        atom_and_list!(KVal::Timestamp, 1_i64),
+        atom_and_list!(KVal::Month, 1_i32),
+        atom_and_list!(KVal::Date, 1_i32),
+        atom_and_list!(KVal::Datetime, 1.5_f64),
+        atom_and_list!(KVal::Timespan, 1_i64),
+        atom_and_list!(KVal::Minute, 1_i32),
+        atom_and_list!(KVal::Second, 1_i32),
+        atom_and_list!(KVal::Time, 1_i32),
+        [
+            KVal::Enum(KData::Atom(Cow::Owned(1)), Some("enum".to_string())),
+            KVal::Enum(
+                KData::List(Cow::Owned(vec![0, 2])),
+                Some("enum".to_string()),
+            ),
+        ],
+        [KVal::Char('a'), KVal::String(Cow::Borrowed(b"text"))],
+        [
+            KVal::CompoundList(vec![KVal::Null, KVal::Char('a')]),
+            KVal::Table(trades_table(2)),
+        ],
+        [
+            KVal::Dictionary(KDict::new(
+                KVal::Symbol(KData::List(Cow::Owned(vec!["a".to_string()]))),
+                KVal::Long(KData::List(Cow::Owned(vec![1]))),
+            )),
+            KVal::Dictionary(keyed_trades(2)),
+        ],
+        [KVal::Null, KVal::Error(Cow::Borrowed("type"))],
+    ];
+
+    for value in values.iter().flatten() {
+        let qtype = value.qtype();
+        assert_eq!(qtype, expected_qtype(value), "{:?}", value);
+        let k = match value {
+            // `to_k` signals the error.
+            KVal::Error(_) => unsafe { native::ee(value.to_k()) },
+            _ => value.to_k(),
+        };
+        assert_eq!(k.get_type(), qtype, "{:?}", value);
+        decrement_reference_count(k);
+
+        let expected_name = match qtype {
+            qtype::CHAR => "char".to_string(),
+            qtype::STRING => "string".to_string(),
+            qtype::COMPOUND_LIST => "compound list".to_string(),
+            qtype::TABLE => "table".to_string(),
+            qtype::DICTIONARY => "dictionary".to_string(),
+            qtype::ERROR => "error".to_string(),
+            qtype::NULL => "general null".to_string(),
+            _ => {
+                let base = format!("{:?}", value);
+                let variant = base[..base.find('(').unwrap()].to_lowercase();
+                format!("{} {}", variant, if qtype < 0 { "atom" } else { "list" })
+            }
+        };
+        assert_eq!(value.type_name(), expected_name);
+    }
+    mock::clear_k_handler();
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn ktable_project_test() {
+    // Table of 50 long columns `c0`..`c49` of 1000 rows.
+    let names = (0..50).map(|i| format!("c{}", i)).collect::<Vec<_>>();
+    let columns = (0..50_i64)
+        .map(|i| {
+            KVal::Long(KData::List(Cow::Owned(
+                (0..1000).map(|row| i * 1000 + row).collect(),
+            )))
+        })
+        .collect::<KVal>();
+    let table = KTable::new(KDict::new(
+        KVal::Symbol(KData::List(Cow::Owned(names))),
+        columns,
+    ))
+    .to_k();
+    let value = KTable::from_raw(table);
+    let q_columns = unsafe { (*table).value.table }.as_mut_slice::<K>()[1].as_mut_slice::<K>();
+
+    let projected = value
+        .project(Some(&["c7", "c42", "c3"]), Some(10..20))
+        .unwrap();
+    assert_eq!(projected.len(), 10);
+    match (projected.dict.keys.as_ref(), projected.dict.values.as_ref()) {
+        (KVal::Symbol(KData::List(names)), KVal::CompoundList(columns)) => {
+            assert_eq!(names.as_ref(), &["c7", "c42", "c3"]);
+            assert_eq!(columns.len(), 3);
+            for (column, source) in columns.iter().zip([7, 42, 3]) {
+                match column {
+                    // Borrows the rows of the q column.
+                    KVal::Long(KData::List(Cow::Borrowed(rows))) => {
+                        assert!(std::ptr::eq(
+                            *rows,
+                            &q_columns[source].as_mut_slice::<J>()[10..20]
+                        ));
+                        assert_eq!(rows[0], source as i64 * 1000 + 10);
+                    }
+                    _ => panic!("projected column must be a borrowed long list"),
+                }
+            }
+        }
+        _ => panic!("projected table must be a table"),
+    }
+
+    assert_eq!(value.project(None, None).unwrap().dict.len(), 50);
+    assert_eq!(
+        value.project(Some(&["c1", "x", "y"]), None).unwrap_err(),
+        KError::MissingColumns(vec!["x".to_string(), "y".to_string()])
+    );
+    assert_eq!(
+        value
+            .project(None, Some(990..1001))
+            .unwrap_err()
+            .to_string(),
+        "index out of bounds: 1001 for length 1000"
+    );
+    assert!(value.project(None, Some(-1..3)).is_err());
+
+    decrement_reference_count(table);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn kval_display_test() {
+    let display = |value: KVal| value.to_string();
+
+    // Atoms and lists
+    assert_eq!(
+        display(KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3])))),
+        "1 2 3"
+    );
+    assert_eq!(
+        display(KVal::Int(KData::List(Cow::Owned(vec![1, qnull_base::I])))),
+        "1 0Ni"
+    );
+    assert_eq!(display(KVal::Short(KData::Atom(Cow::Owned(5)))), "5h");
+    assert_eq!(
+        display(KVal::Long(KData::Atom(Cow::Owned(qinf_base::J)))),
+        "0W"
+    );
+    assert_eq!(display(KVal::Long(KData::List(Cow::Owned(vec![7])))), ",7");
+    assert_eq!(
+        display(KVal::Long(KData::List(Cow::Owned(vec![])))),
+        "`long$()"
+    );
+    assert_eq!(display(KVal::Float(KData::Atom(Cow::Owned(1.0)))), "1f");
+    assert_eq!(
+        display(KVal::Float(KData::List(Cow::Owned(vec![1.0, 2.0])))),
+        "1 2f"
+    );
+    assert_eq!(
+        display(KVal::Float(KData::List(Cow::Owned(vec![
+            1.5,
+            f64::NAN,
+            f64::INFINITY
+        ])))),
+        "1.5 0n 0w"
+    );
+    assert_eq!(
+        display(KVal::Float(KData::List(Cow::Owned(vec![
+            1e10, 0.00001, 0.1
+        ])))),
+        "1e+10 1e-05 0.1"
+    );
+    assert_eq!(display(KVal::Real(KData::Atom(Cow::Owned(1.1)))), "1.1e");
+    assert_eq!(
+        display(KVal::Bool(KData::List(Cow::Owned(vec![true, false, true])))),
+        "101b"
+    );
+    assert_eq!(
+        display(KVal::Byte(KData::List(Cow::Owned(vec![1, 0xff])))),
+        "0x01ff"
+    );
+    let guid: [u8; 16] = std::array::from_fn(|i| i as u8);
+    assert_eq!(
+        display(KVal::Guid(KData::Atom(Cow::Owned(guid)))),
+        "00010203-0405-0607-0809-0a0b0c0d0e0f"
+    );
+    assert_eq!(
+        display(KVal::Symbol(KData::List(Cow::Owned(vec![
+            "a".to_string(),
+            "b".to_string()
+        ])))),
+        "`a`b"
+    );
+    assert_eq!(
+        display(KVal::Symbol(KData::Atom(Cow::Owned(String::new())))),
+        "`"
+    );
+    assert_eq!(display(KVal::Char('a')), "\"a\"");
+    assert_eq!(
+        display(KVal::String(Cow::Borrowed(b"a\"b\n"))),
+        "\"a\\\"b\\n\""
+    );
+    assert_eq!(display(KVal::String(Cow::Borrowed(b"a"))), ",\"a\"");
+    assert_eq!(
+        display(KVal::CompoundList(vec![
+            KVal::Long(KData::Atom(Cow::Owned(1))),
+            KVal::Symbol(KData::Atom(Cow::Owned("a".to_string()))),
+            KVal::String(Cow::Borrowed(b"xy"))
+        ])),
+        "(1;`a;\"xy\")"
+    );
+    assert_eq!(
+        display(KVal::Enum(
+            KData::Atom(Cow::Owned(1)),
+            Some("sym".to_string())
+        )),
+        "`sym!1"
+    );
+    assert_eq!(display(KVal::Error(Cow::Borrowed("type"))), "'type");
+    assert_eq!(display(KVal::Null), "::");
+
+    // Temporal types
+    assert_eq!(
+        display(KVal::Timestamp(KData::List(Cow::Owned(vec![0, -1])))),
+        "2000.01.01D00:00:00.000000000 1999.12.31D23:59:59.999999999"
+    );
+    assert_eq!(
+        display(KVal::Timespan(KData::Atom(Cow::Owned(-1_500_000_000)))),
+        "-0D00:00:01.500000000"
+    );
+    assert_eq!(
+        display(KVal::Month(KData::List(Cow::Owned(vec![-1, 13])))),
+        "1999.12 2001.02m"
+    );
+    assert_eq!(
+        display(KVal::Date(KData::List(Cow::Owned(vec![
+            qnull_base::I,
+            qinf_base::I
+        ])))),
+        "0N 0Wd"
+    );
+    assert_eq!(
+        display(KVal::Datetime(KData::Atom(Cow::Owned(1.5)))),
+        "2000.01.02T12:00:00.000"
+    );
+    assert_eq!(display(KVal::Minute(KData::Atom(Cow::Owned(90)))), "01:30");
+    assert_eq!(
+        display(KVal::Second(KData::Atom(Cow::Owned(3661)))),
+        "01:01:01"
+    );
+    assert_eq!(
+        display(KVal::Time(KData::Atom(Cow::Owned(45_296_789)))),
+        "12:34:56.789"
+    );
+    assert_eq!(QDate(-36524).to_string(), "1900.01.01");
+    assert_eq!(QDate(0).to_string(), "2000.01.01");
+    assert_eq!(QTimestamp::INF.to_string(), "0Wp");
+    assert_eq!(QMonth::NINF.to_string(), "-0Wm");
+    assert_eq!(QDatetime::NULL.to_string(), "0Nz");
+
+    // Tables and dictionaries
+    assert_eq!(
+        display(KVal::Dictionary(KDict::new(
+            KVal::Symbol(KData::List(Cow::Owned(vec![
+                "a".to_string(),
+                "bcd".to_string()
+            ]))),
+            KVal::Long(KData::List(Cow::Owned(vec![1, qnull_base::J])))
+        ))),
+        "a  | 1\nbcd|"
+    );
+    assert_eq!(
+        display(KVal::Table(trades_table(2))),
+        "sym price size\n--------------\na   0.5   0\nb   1.5   100"
+    );
+    assert_eq!(
+        display(KVal::Dictionary(keyed_trades(1))),
+        "id| sym price size\n--| --------------\n0 | a   0.5   0"
+    );
+    let long_table = display(KVal::Table(trades_table(25)));
+    let lines = long_table.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 23);
+    assert_eq!(lines[21], "b   19.5  1900");
+    assert_eq!(lines[22], "..");
+}
+
+#[test]
+fn temporal_accessor_test() {
+    let timestamp = KVal::Timestamp(KData::Atom(Cow::Owned(43_200_000_000_000)));
+    assert_eq!(
+        timestamp.as_timestamp_atom(),
+        Some(QTimestamp(43_200_000_000_000))
+    );
+    assert_eq!(timestamp.as_timespan_atom(), None);
+    assert_eq!(timestamp.as_timestamp_list(), None);
+
+    let dates = KVal::Date(KData::List(Cow::Owned(vec![0, qnull_base::I])));
+    assert_eq!(dates.as_date_list(), Some(vec![QDate(0), QDate::NULL]));
+    assert_eq!(dates.as_date_atom(), None);
+    assert_eq!(long(1).as_timespan_atom(), None);
+
+    let datetime = KVal::Datetime(KData::Atom(Cow::Owned(f64::NAN)));
+    assert!(datetime.as_datetime_atom().unwrap().is_null());
+    assert_eq!(
+        KVal::Minute(KData::List(Cow::Owned(vec![90]))).as_minute_list(),
+        Some(vec![QMinute(90)])
+    );
+}
+
+#[test]
+fn calendar_test() {
+    let dates = |days: Vec<i32>| KVal::Date(KData::List(Cow::Owned(days)));
+    let date = |year: i32, month: i32, day: i32| ymd_to_days(year, month, day);
+
+    // 2020.01.31 2019.01.31 2020.03.31 2000.02.29 1999.12.31 0Nd 0Wd
+    let month_ends = dates(vec![
+        date(2020, 1, 31),
+        date(2019, 1, 31),
+        date(2020, 3, 31),
+        date(2000, 2, 29),
+        date(1999, 12, 31),
+        qnull_base::I,
+        qinf_base::I,
+    ]);
+    assert_eq!(
+        date_add_months(&month_ends, 1).unwrap().to_string(),
+        "2020.02.29 2019.02.28 2020.04.30 2000.03.29 2000.01.31 0N 0W"
+    );
+    assert_eq!(
+        date_add_months(&month_ends, -13).unwrap().to_string(),
+        "2018.12.31 2017.12.31 2019.02.28 1999.01.29 1998.11.30 0N 0W"
+    );
+    assert_eq!(
+        date_add_months(&month_ends, 48).unwrap().to_string(),
+        "2024.01.31 2023.01.31 2024.03.31 2004.02.29 2003.12.31 0N 0W"
+    );
+    assert_eq!(
+        date_add_days(&month_ends, 1).unwrap().to_string(),
+        "2020.02.01 2019.02.01 2020.04.01 2000.03.01 2000.01.01 0N 0W"
+    );
+    assert_eq!(
+        date_add_days(&KVal::Date(KData::Atom(Cow::Owned(date(2020, 3, 1)))), -1)
+            .unwrap()
+            .to_string(),
+        "2020.02.29"
+    );
+
+    // 1900.02.10 2000.02.10 2019.02.10 2020.02.10 2100.02.10 2020.12.01
+    let leap_days = dates(vec![
+        date(1900, 2, 10),
+        date(2000, 2, 10),
+        date(2019, 2, 10),
+        date(2020, 2, 10),
+        date(2100, 2, 10),
+        date(2020, 12, 1),
+    ]);
+    assert_eq!(
+        end_of_month(&leap_days).unwrap().to_string(),
+        "1900.02.28 2000.02.29 2019.02.28 2020.02.29 2100.02.28 2020.12.31"
+    );
+    // q)1900.02.10 2000.02.10 2019.02.10 2020.02.10 2100.02.10 2020.12.01 mod 7
+    // 0 5 1 2 4 3i
+    assert_eq!(day_of_week(&leap_days).unwrap().to_string(), "0 5 1 2 4 3i");
+    assert_eq!(
+        day_of_week(&dates(vec![-1, qnull_base::I]))
+            .unwrap()
+            .to_string(),
+        "6 0Ni"
+    );
+
+    let months = KVal::Month(KData::List(Cow::Owned(vec![0, -1, 250, qnull_base::I])));
+    assert_eq!(
+        month_add(&months, 3).unwrap().to_string(),
+        "2000.04 2000.03 2021.02 0Nm"
+    );
+
+    let ymd = date_to_ymd(&dates(vec![date(2020, 2, 29), -1, qnull_base::I])).unwrap();
+    assert_eq!(ymd.to_string(), "20200229 19991231 0Ni");
+    assert_eq!(
+        ymd_to_date(&ymd).unwrap().to_string(),
+        "2020.02.29 1999.12.31 0N"
+    );
+
+    assert_eq!(
+        date_add_months(&months, 1).unwrap_err().to_string(),
+        "invalid operation date_add_months on month list. expected: date"
+    );
+}
+
+/// Check the order of the null, the negative infinity, a value and the infinity of a numeric type.
+fn check_borders<T: Clone>(
+    atom: fn(T) -> KVal<'static>,
+    list: fn(Vec<T>) -> KVal<'static>,
+    [null, ninf, value, inf]: [T; 4],
+) {
+    let name = atom(value.clone()).type_name();
+    assert_eq!(atom(null.clone()), atom(null.clone()), "{}", name);
+    assert!(atom(null.clone()) < atom(ninf.clone()), "{}", name);
+    assert!(atom(ninf.clone()) < atom(value.clone()), "{}", name);
+    assert!(atom(value.clone()) < atom(inf.clone()), "{}", name);
+    assert_ne!(atom(inf.clone()), atom(ninf.clone()), "{}", name);
+    assert_ne!(atom(null.clone()), atom(value.clone()), "{}", name);
+    assert_eq!(
+        list(vec![null.clone(), value.clone(), inf.clone()]),
+        list(vec![null.clone(), value.clone(), inf.clone()]),
+        "{}",
+        name
+    );
+    assert_ne!(
+        list(vec![null.clone(), value.clone()]),
+        list(vec![value.clone(), null.clone()]),
+        "{}",
+        name
+    );
+    assert!(
+        list(vec![null.clone(), inf.clone()]) < list(vec![value.clone()]),
+        "{}",
+        name
+    );
+    assert!(list(vec![value.clone()]) < list(vec![value.clone(), null.clone()]));
+    assert_ne!(atom(null.clone()), list(vec![null.clone()]), "{}", name);
+    assert_eq!(atom(null.clone()).partial_cmp(&list(vec![null])), None);
+}
+
+#[test]
+fn kval_eq_test() {
+    // Nulls and infinities of every numeric variant
+    check_borders(
+        |x| KVal::Short(KData::Atom(Cow::Owned(x))),
+        |x| KVal::Short(KData::List(Cow::Owned(x))),
+        [qnull_base::H, qninf_base::H, 0, qinf_base::H],
+    );
+    check_borders(
+        |x| KVal::Int(KData::Atom(Cow::Owned(x))),
+        |x| KVal::Int(KData::List(Cow::Owned(x))),
+        [qnull_base::I, qninf_base::I, 0, qinf_base::I],
+    );
+    check_borders(
+        |x| KVal::Long(KData::Atom(Cow::Owned(x))),
+        |x| KVal::Long(KData::List(Cow::Owned(x))),
+        [qnull_base::J, qninf_base::J, 0, qinf_base::J],
+    );
+    check_borders(
+        |x| KVal::Real(KData::Atom(Cow::Owned(x))),
+        |x| KVal::Real(KData::List(Cow::Owned(x))),
+        [qnull_base::E, qninf_base::E, 0.0, qinf_base::E],
+    );
+    check_borders(
+        |x| KVal::Float(KData::Atom(Cow::Owned(x))),
+        |x| KVal::Float(KData::List(Cow::Owned(x))),
+        [qnull_base::F, qninf_base::F, 0.0, qinf_base::F],
+    );
+    check_borders(
+        |x| KVal::Timestamp(KData::Atom(Cow::Owned(x))),
+        |x| KVal::Timestamp(KData::List(Cow::Owned(x))),
+        [qnull_base::J, qninf_base::J, 0, qinf_base::J],
+    );
+    check_borders(
+        |x| KVal::Month(KData::Atom(Cow::Owned(x))),
+        |x| KVal::Month(KData::List(Cow::Owned(x))),
+        [qnull_base::I, qninf_base::I, 0, qinf_base::I],
+    );
+    check_borders(
+        |x| KVal::Date(KData::Atom(Cow::Owned(x))),
+        |x| KVal::Date(KData::List(Cow::Owned(x))),
+        [qnull_base::I, qninf_base::I, 0, qinf_base::I],
+    );
+    check_borders(
+        |x| KVal::Datetime(KData::Atom(Cow::Owned(x))),
+        |x| KVal::Datetime(KData::List(Cow::Owned(x))),
+        [qnull_base::F, qninf_base::F, 0.0, qinf_base::F],
+    );
+    check_borders(
+        |x| KVal::Timespan(KData::Atom(Cow::Owned(x))),
+        |x| KVal::Timespan(KData::List(Cow::Owned(x))),
+        [qnull_base::J, qninf_base::J, 0, qinf_base::J],
+    );
+    check_borders(
+        |x| KVal::Minute(KData::Atom(Cow::Owned(x))),
+        |x| KVal::Minute(KData::List(Cow::Owned(x))),
+        [qnull_base::I, qninf_base::I, 0, qinf_base::I],
+    );
+    check_borders(
+        |x| KVal::Second(KData::Atom(Cow::Owned(x))),
+        |x| KVal::Second(KData::List(Cow::Owned(x))),
+        [qnull_base::I, qninf_base::I, 0, qinf_base::I],
+    );
+    check_borders(
+        |x| KVal::Time(KData::Atom(Cow::Owned(x))),
+        |x| KVal::Time(KData::List(Cow::Owned(x))),
+        [qnull_base::I, qninf_base::I, 0, qinf_base::I],
+    );
+    // A null float is equal regardless of its bit pattern, and zeros of both signs are equal.
+    assert_eq!(
+        KVal::Float(KData::Atom(Cow::Owned(-f64::NAN))),
+        KVal::Float(KData::Atom(Cow::Owned(f64::NAN)))
+    );
+    assert_eq!(
+        KVal::Real(KData::Atom(Cow::Owned(-0.0))),
+        KVal::Real(KData::Atom(Cow::Owned(0.0)))
+    );
+
+    // GUID, symbol, char and string
+    let guid = |last: u8| {
+        let mut guid = [7_u8; 16];
+        guid[15] = last;
+        KVal::Guid(KData::Atom(Cow::Owned(guid)))
+    };
+    assert_eq!(guid(1), guid(1));
+    assert_ne!(guid(1), guid(2));
+    assert!(guid(1) < guid(2));
+    assert_eq!(
+        KVal::Guid(KData::Atom(Cow::Owned(qnull_base::U))),
+        KVal::Guid(KData::Atom(Cow::Owned([0; 16])))
+    );
+    let symbols = |symbols: &[&str]| {
+        KVal::Symbol(KData::List(Cow::Owned(
+            symbols.iter().map(|symbol| symbol.to_string()).collect(),
+        )))
+    };
+    assert_eq!(symbols(&["a", "b"]), symbols(&["a", "b"]));
+    assert!(symbols(&["", "b"]) < symbols(&["a"]));
+    assert!(KVal::String(Cow::Borrowed(b"ab")) < KVal::String(Cow::Owned(b"b".to_vec())));
+    assert_ne!(KVal::Char('a'), KVal::String(Cow::Borrowed(b"a")));
+
+    // Enum compares both indices and sources.
+    let enumeration = |index: i64, source: Option<&str>| {
+        KVal::Enum(KData::Atom(Cow::Owned(index)), source.map(str::to_string))
+    };
+    assert_eq!(enumeration(1, Some("sym")), enumeration(1, Some("sym")));
+    assert_ne!(enumeration(1, Some("sym")), enumeration(1, Some("other")));
+    assert_ne!(enumeration(1, Some("sym")), enumeration(1, None));
+    assert!(enumeration(1, Some("sym")) < enumeration(2, Some("sym")));
+    assert_eq!(
+        enumeration(1, Some("sym")).partial_cmp(&enumeration(2, None)),
+        None
+    );
+
+    // Different types are neither equal nor ordered.
+    let long = KVal::Long(KData::Atom(Cow::Owned(1)));
+    let int = KVal::Int(KData::Atom(Cow::Owned(1)));
+    assert_ne!(long, int);
+    assert_eq!(long.partial_cmp(&int), None);
+
+    // Compound list, dictionary and table compare recursively.
+    let compound = |last: f64| {
+        KVal::CompoundList(vec![
+            KVal::Long(KData::Atom(Cow::Owned(1))),
+            KVal::Float(KData::List(Cow::Owned(vec![f64::NAN, last]))),
+        ])
+    };
+    assert_eq!(compound(1.0), compound(1.0));
+    assert_ne!(compound(1.0), compound(f64::NAN));
+    assert!(compound(f64::NAN) < compound(1.0));
+    assert_eq!(KVal::Null, KVal::Null);
+    assert_eq!(
+        KVal::Null.partial_cmp(&KVal::Null),
+        Some(std::cmp::Ordering::Equal)
+    );
+    assert_eq!(KVal::Table(trades_table(3)), KVal::Table(trades_table(3)));
+    assert_ne!(KVal::Table(trades_table(3)), KVal::Table(trades_table(2)));
+    assert_eq!(
+        KVal::Table(trades_table(3)).partial_cmp(&KVal::Table(trades_table(2))),
+        None
+    );
+    assert_eq!(keyed_trades(2), keyed_trades(2));
+    assert_ne!(keyed_trades(2), keyed_trades(1));
+
+    // A value borrowing a q object equals the value it was built from.
+    let table = trades(3);
+    assert_eq!(KVal::from_raw(table), KVal::Table(trades_table(3)));
+    decrement_reference_count(table);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn general_null_test() {
+    // (1; ::; `a)
+    let list = KVal::CompoundList(vec![
+        KVal::Long(KData::Atom(Cow::Owned(1))),
+        KVal::Null,
+        KVal::Symbol(KData::Atom(Cow::Owned("a".to_string()))),
+    ]);
+    assert_eq!(list.to_string(), "(1;::;`a)");
+    let q_list = list.to_k();
+    let elements = q_list.as_mut_slice::<K>();
+    assert_eq!(elements[1].get_type(), qtype::NULL);
+    assert_eq!(unsafe { (*elements[1]).value.byte }, 0);
+    assert_eq!(KVal::from_raw(q_list), list);
+
+    // Each conversion allocates a new general null.
+    let null = KVal::Null.to_k();
+    assert_ne!(null, elements[1]);
+    assert_eq!(KVal::from_raw(null), KVal::Null);
+    assert_eq!(KVal::from_raw(KNULL), KVal::Null);
+    decrement_reference_count(null);
+
+    // A unary primitive has the same type but is not a general null.
+    let negate = new_null();
+    unsafe { (*negate).value.byte = 2 };
+    assert_eq!(
+        KVal::from_raw(negate),
+        KVal::Error(Cow::Borrowed("unsupported type: 101h"))
+    );
+    decrement_reference_count(negate);
+
+    decrement_reference_count(q_list);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn deep_clone_test() {
+    let expected = KVal::CompoundList(vec![
+        KVal::Table(trades_table(3)),
+        KVal::Dictionary(keyed_trades(2)),
+        KVal::Guid(KData::List(Cow::Owned(vec![[1; 16], [2; 16]]))),
+        KVal::String(Cow::Borrowed(b"text")),
+        KVal::Symbol(KData::Atom(Cow::Owned("a".to_string()))),
+        KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3]))),
+    ]);
+    let q_list = expected.to_k();
+    let borrowed = KVal::from_raw(q_list);
+    let copy = borrowed.deep_clone();
+    match &copy {
+        KVal::CompoundList(elements) => match &elements[5] {
+            KVal::Long(KData::List(Cow::Owned(longs))) => assert_eq!(longs, &vec![1, 2, 3]),
+            _ => panic!("copied list must own its elements"),
+        },
+        _ => panic!("copy must be a compound list"),
+    }
+    assert_eq!(copy, borrowed);
+    drop(borrowed);
+
+    // The copy outlives the q object and moves to another thread.
+    decrement_reference_count(q_list);
+    assert_eq!(mock::live_objects(), 0);
+    let copy = std::thread::spawn(move || copy).join().unwrap();
+    assert_eq!(copy, expected);
+}
+
+#[test]
+fn kval_cast_test() {
+    let shorts = |list: Vec<i16>| KVal::Short(KData::List(Cow::Owned(list)));
+    let ints = |list: Vec<i32>| KVal::Int(KData::List(Cow::Owned(list)));
+    let reals = |list: Vec<f32>| KVal::Real(KData::List(Cow::Owned(list)));
+    let floats = |list: Vec<f64>| KVal::Float(KData::List(Cow::Owned(list)));
+    let out_of_range = |value: &str, target| {
+        Err(KError::OutOfRange {
+            value: value.to_string(),
+            target,
+        })
+    };
+    let invalid_cast = |from, to| Err(KError::InvalidCast { from, to });
+
+    // Widening keeps nulls and infinities.
+    let borders = shorts(vec![1, qnull_base::H, qinf_base::H, qninf_base::H]);
+    assert_eq!(
+        borders.clone().cast(qtype::INT_LIST).unwrap(),
+        ints(vec![1, qnull_base::I, qinf_base::I, qninf_base::I])
+    );
+    assert_eq!(
+        borders.clone().cast(qtype::LONG_LIST).unwrap(),
+        longs(vec![1, qnull_base::J, qinf_base::J, qninf_base::J])
+    );
+    assert_eq!(
+        borders.clone().cast(qtype::REAL_LIST).unwrap(),
+        reals(vec![1.0, qnull_base::E, qinf_base::E, qninf_base::E])
+    );
+    assert_eq!(
+        borders.cast(qtype::FLOAT_LIST).unwrap(),
+        floats(vec![1.0, qnull_base::F, qinf_base::F, qninf_base::F])
+    );
+
+    // Narrowing maps nulls and infinities and checks the range.
+    assert_eq!(
+        longs(vec![1, qnull_base::J, qinf_base::J, qninf_base::J])
+            .cast(qtype::SHORT_LIST)
+            .unwrap(),
+        shorts(vec![1, qnull_base::H, qinf_base::H, qninf_base::H])
+    );
+    assert_eq!(
+        floats(vec![2.5, -2.5, qnull_base::F, qinf_base::F, qninf_base::F])
+            .cast(qtype::INT_LIST)
+            .unwrap(),
+        ints(vec![3, -3, qnull_base::I, qinf_base::I, qninf_base::I])
+    );
+    assert_eq!(
+        reals(vec![qnull_base::E, 1.5])
+            .cast(qtype::LONG_LIST)
+            .unwrap(),
+        longs(vec![qnull_base::J, 2])
+    );
+    assert_eq!(
+        floats(vec![qnull_base::F, qinf_base::F, 0.5])
+            .cast(qtype::REAL_LIST)
+            .unwrap(),
+        reals(vec![qnull_base::E, qinf_base::E, 0.5])
+    );
+    // A finite value equal to the null of the target type must not turn into the null.
+    assert_eq!(
+        longs(vec![i32::MIN as i64]).cast(qtype::INT_LIST),
+        out_of_range("-2147483648", "int list")
+    );
+    assert_eq!(
+        longs(vec![40000]).cast(qtype::SHORT_LIST),
+        out_of_range("40000", "short list")
+    );
+    assert_eq!(
+        floats(vec![9.3e18]).cast(qtype::LONG_LIST),
+        out_of_range("9.3e18", "long list")
+    );
+    assert_eq!(
+        floats(vec![-(2.0_f64.powi(63))]).cast(qtype::LONG_LIST),
+        out_of_range("-9.223372036854776e18", "long list")
+    );
+    assert_eq!(
+        floats(vec![1e39]).cast(qtype::REAL_LIST),
+        out_of_range("1e39", "real list")
+    );
+
+    // Bool and byte have no null.
+    assert_eq!(
+        KVal::Bool(KData::List(Cow::Owned(vec![true, false])))
+            .cast(qtype::BYTE_LIST)
+            .unwrap(),
+        KVal::Byte(KData::List(Cow::Owned(vec![1, 0])))
+    );
+    assert_eq!(
+        KVal::Byte(KData::Atom(Cow::Owned(200)))
+            .cast(qtype::SHORT_ATOM)
+            .unwrap(),
+        KVal::Short(KData::Atom(Cow::Owned(200)))
+    );
+    assert_eq!(
+        ints(vec![0, 1]).cast(qtype::BOOL_LIST).unwrap(),
+        KVal::Bool(KData::List(Cow::Owned(vec![false, true])))
+    );
+    assert_eq!(
+        ints(vec![2]).cast(qtype::BOOL_LIST),
+        out_of_range("2", "bool list")
+    );
+    assert_eq!(
+        ints(vec![qnull_base::I]).cast(qtype::BYTE_LIST),
+        out_of_range("0N", "byte list")
+    );
+    assert_eq!(
+        floats(vec![qinf_base::F]).cast(qtype::BYTE_LIST),
+        out_of_range("0W", "byte list")
+    );
+
+    // Temporal types are cast only into the underlying type and back without copying.
+    let dates = KVal::Date(KData::List(Cow::Owned(vec![1, qnull_base::I])));
+    assert_eq!(
+        dates.clone().cast(qtype::INT_LIST).unwrap(),
+        ints(vec![1, qnull_base::I])
+    );
+    assert_eq!(
+        ints(vec![1, qnull_base::I]).cast(qtype::DATE_LIST).unwrap(),
+        dates
+    );
+    assert_eq!(
+        dates.clone().cast(qtype::LONG_LIST),
+        invalid_cast("date list", "long list")
+    );
+    assert_eq!(
+        dates.clone().cast(qtype::MONTH_LIST),
+        invalid_cast("date list", "month list")
+    );
+    let buffer = [1_i64, 2];
+    match KVal::Timestamp(KData::List(Cow::Borrowed(&buffer)))
+        .cast(qtype::LONG_LIST)
+        .unwrap()
+    {
+        KVal::Long(KData::List(Cow::Borrowed(list))) => assert!(std::ptr::eq(list, &buffer)),
+        _ => panic!("timestamp must be cast into a borrowed long list"),
+    }
+    assert_eq!(
+        KVal::Datetime(KData::Atom(Cow::Owned(1.5)))
+            .cast(qtype::FLOAT_ATOM)
+            .unwrap(),
+        KVal::Float(KData::Atom(Cow::Owned(1.5)))
+    );
+
+    // Same type, atom type and invalid types
+    assert_eq!(dates.clone().cast(qtype::DATE_ATOM).unwrap(), dates);
+    assert_eq!(
+        KVal::Symbol(KData::Atom(Cow::Owned("a".to_string()))).cast(qtype::LONG_ATOM),
+        invalid_cast("symbol atom", "long atom")
+    );
+    assert_eq!(
+        longs(vec![1]).cast(qtype::SYMBOL_LIST),
+        invalid_cast("long list", "symbol list")
+    );
+    assert_eq!(
+        longs(vec![1]).cast(qtype::ERROR),
+        invalid_cast("long list", "error")
+    );
+}
+
+#[test]
+fn errors_test() {
+    // Raising a prebuilt error allocates nothing and can be repeated.
+    for _ in 0..1000 {
+        assert!(new_error(errors::NOT_A_LIST).is_null());
+    }
+    assert_eq!(mock::live_objects(), 0);
+    assert_eq!(mock::last_error().as_deref(), Some("not a list"));
+    mock::clear_error();
+
+    let atom = KVal::Long(KData::Atom(Cow::Owned(1)));
+    assert_eq!(atom.slice(0..1).unwrap_err(), errors::NOT_A_LIST);
+    let list = KVal::Long(KData::List(Cow::Owned(vec![1, 2])));
+    assert_eq!(list.slice(1..3).unwrap_err(), errors::INDEX_OUT_OF_BOUNDS);
+    for message in [
+        errors::NOT_A_LIST,
+        errors::TYPE,
+        errors::INDEX_OUT_OF_BOUNDS,
+        errors::INVALID_CAST,
+        errors::OUT_OF_RANGE,
+    ] {
+        assert!(message.ends_with('\0'));
+    }
+}
+
+#[test]
+fn compound_aliasing_test() {
+    let original = KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3]))).to_k();
+    let borrowed = KVal::from_raw(original);
+    let compound = KVal::CompoundList(vec![borrowed.clone(), borrowed]).to_k();
+
+    let elements = compound.as_mut_slice::<K>();
+    assert_ne!(elements[0], elements[1]);
+    for element in elements.iter() {
+        assert_ne!(*element, original);
+        assert_eq!(element.get_refcount(), 0);
+        assert_eq!(element.as_mut_slice::<J>(), &[1, 2, 3]);
+    }
+    assert_eq!(original.get_refcount(), 0);
+
+    // Each element is freed exactly once with the compound list.
+    decrement_reference_count(compound);
+    assert_eq!(mock::live_objects(), 1);
+    assert!(mock::is_live(original));
+    decrement_reference_count(original);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn to_simple_list_test() {
+    let longs = KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3])));
+    let compound = longs.clone().to_compound_list();
+    assert_eq!(
+        compound,
+        KVal::CompoundList(vec![long(1), long(2), long(3)])
+    );
+    assert_eq!(compound.to_simple_list().unwrap(), longs);
+
+    // Atoms borrow the elements of a borrowed list.
+    let buffer = [4_i64, 5];
+    let borrowed = KVal::Long(KData::List(Cow::Borrowed(&buffer)));
+    match borrowed.to_compound_list() {
+        KVal::CompoundList(atoms) => assert!(atoms
+            .iter()
+            .all(|atom| matches!(atom, KVal::Long(KData::Atom(Cow::Borrowed(_)))))),
+        _ => panic!("must be a compound list"),
+    }
+
+    // Atoms take over the elements of an owned list without cloning them.
+    let symbols = (0..1000).map(|i| format!("sym{}", i)).collect::<Vec<_>>();
+    let addresses = symbols
+        .iter()
+        .map(|symbol| symbol.as_ptr())
+        .collect::<Vec<_>>();
+    let expected = KVal::CompoundList(
+        symbols
+            .iter()
+            .map(|symbol| KVal::Symbol(KData::Atom(Cow::Owned(symbol.clone()))))
+            .collect(),
+    );
+    let compound = KVal::Symbol(KData::List(Cow::Owned(symbols))).to_compound_list();
+    match &compound {
+        KVal::CompoundList(atoms) => {
+            for (atom, address) in atoms.iter().zip(addresses) {
+                match atom {
+                    KVal::Symbol(KData::Atom(Cow::Owned(symbol))) => {
+                        assert_eq!(symbol.as_ptr(), address)
+                    }
+                    _ => panic!("must be an owned symbol atom"),
+                }
+            }
+        }
+        _ => panic!("must be a compound list"),
+    }
+    // q sees the same list as one built from cloned atoms.
+    let (k, expected_k) = (compound.to_k(), expected.to_k());
+    assert_eq!(KVal::from_raw(k), KVal::from_raw(expected_k));
+    decrement_reference_count(k);
+    decrement_reference_count(expected_k);
+    assert_eq!(mock::live_objects(), 0);
+
+    let string = KVal::String(Cow::Borrowed(b"kdb"));
+    let chars = string.clone().to_compound_list();
+    assert_eq!(chars.len(), 3);
+    assert_eq!(chars.to_simple_list().unwrap(), string);
+    let chars = KVal::CompoundList(vec![KVal::Char('é'), KVal::Char('a')]);
+    let string = chars.to_simple_list().unwrap();
+    assert_eq!(string, KVal::String(Cow::Borrowed(b"\xe9a")));
+    assert_eq!(string.len(), 2);
+    assert_eq!(
+        string.to_compound_list(),
+        KVal::CompoundList(vec![KVal::Char('é'), KVal::Char('a')])
+    );
+    assert_eq!(
+        KVal::CompoundList(vec![KVal::Char('a'), KVal::Char('€')])
+            .to_simple_list()
+            .unwrap_err(),
+        errors::CHAR_OUT_OF_RANGE
+    );
+
+    let enums = KVal::Enum(KData::List(Cow::Owned(vec![0, 1])), Some("sym".to_string()));
+    assert_eq!(
+        enums.clone().to_compound_list().to_simple_list().unwrap(),
+        enums
+    );
+
+    // A simple list is returned as it is.
+    assert_eq!(longs.clone().to_simple_list().unwrap(), longs);
+
+    // Errors.
+    assert_eq!(
+        KVal::CompoundList(vec![long(1), KVal::Char('a')])
+            .to_simple_list()
+            .unwrap_err(),
+        errors::MIXED_TYPES
+    );
+    assert_eq!(
+        KVal::CompoundList(vec![long(1), KVal::Int(KData::Atom(Cow::Owned(1)))])
+            .to_simple_list()
+            .unwrap_err(),
+        errors::MIXED_TYPES
+    );
+    assert_eq!(
+        KVal::CompoundList(vec![
+            KVal::Enum(KData::Atom(Cow::Owned(0)), Some("sym".to_string())),
+            KVal::Enum(KData::Atom(Cow::Owned(1)), Some("other".to_string())),
+        ])
+        .to_simple_list()
+        .unwrap_err(),
+        errors::ENUM_SOURCE_MISMATCH
+    );
+    assert_eq!(
+        KVal::CompoundList(vec![
+            KVal::Enum(KData::Atom(Cow::Owned(0)), Some("sym".to_string())),
+            KVal::Enum(KData::Atom(Cow::Owned(1)), None),
+        ])
+        .to_simple_list()
+        .unwrap_err(),
+        errors::ENUM_SOURCE_MISMATCH
+    );
+    assert_eq!(
+        KVal::CompoundList(vec![long(1), longs.clone()])
+            .to_simple_list()
+            .unwrap_err(),
+        errors::NOT_AN_ATOM
+    );
+    assert_eq!(
+        KVal::CompoundList(vec![KVal::Null, KVal::Null])
+            .to_simple_list()
+            .unwrap_err(),
+        errors::NOT_AN_ATOM
+    );
+    assert_eq!(
+        KVal::CompoundList(vec![]).to_simple_list().unwrap_err(),
+        errors::EMPTY_LIST
+    );
+    assert_eq!(long(1).to_simple_list().unwrap_err(), errors::NOT_A_LIST);
+
+    // Through q.
+    let q_list = KVal::CompoundList(vec![long(7), long(8)]).to_k();
+    let simple = KVal::from_raw(q_list).to_simple_list().unwrap().to_k();
+    assert_eq!(simple.get_type(), qtype::LONG_LIST);
+    assert_eq!(simple.as_mut_slice::<J>(), &[7, 8]);
+    decrement_reference_count(simple);
+    decrement_reference_count(q_list);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn par_rows_test() {
+    use rayon::prelude::*;
+
+    let table = trades_table(1000);
+    let rows = table.par_rows().collect::<Vec<_>>();
+    assert_eq!(rows.len(), 1000);
+    for (index, row) in rows.iter().enumerate() {
+        let columns = match table.dict.values.as_ref() {
+            KVal::CompoundList(columns) => columns,
+            _ => unreachable!(),
+        };
+        let expected = columns
+            .iter()
+            .map(|column| column.get(index).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(row, &expected);
+    }
+}
+
+#[test]
+fn null_of_test() {
+    let atom_of = |value: &KVal| match value.to_k() {
+        k if k.is_null() => panic!("conversion failed"),
+        k => k,
+    };
+
+    // Every type with a null. The atom type and the list type are equivalent.
+    for qtype in [
+        qtype::GUID_ATOM,
+        qtype::SHORT_ATOM,
+        qtype::INT_ATOM,
+        qtype::LONG_ATOM,
+        qtype::REAL_ATOM,
+        qtype::FLOAT_ATOM,
+        qtype::CHAR,
+        qtype::SYMBOL_ATOM,
+        qtype::TIMESTAMP_ATOM,
+        qtype::MONTH_ATOM,
+        qtype::DATE_ATOM,
+        qtype::DATETIME_ATOM,
+        qtype::TIMESPAN_ATOM,
+        qtype::MINUTE_ATOM,
+        qtype::SECOND_ATOM,
+        qtype::TIME_ATOM,
+    ] {
+        let null = KVal::null_of(qtype).unwrap();
+        assert_eq!(null.qtype(), qtype);
+        assert_eq!(KVal::null_of(-qtype).unwrap(), null);
+        let k = atom_of(&null);
+        assert_eq!(k.get_type(), qtype);
+        decrement_reference_count(k);
+    }
+    assert_eq!(
+        KVal::null_of(qtype::GUID_ATOM).unwrap(),
+        KVal::Guid(KData::Atom(Cow::Owned(qnull_base::U)))
+    );
+    assert_eq!(
+        KVal::null_of(qtype::STRING).unwrap(),
+        KVal::Char(qnull_base::C)
+    );
+    assert_eq!(
+        KVal::null_of(qtype::SYMBOL_LIST).unwrap(),
+        KVal::Symbol(KData::Atom(Cow::Owned(qnull_base::S.to_string())))
+    );
+    assert_eq!(
+        KVal::null_of(qtype::SHORT_ATOM).unwrap(),
+        KVal::Short(KData::Atom(Cow::Owned(qnull_base::H)))
+    );
+    assert_eq!(
+        KVal::null_of(qtype::MONTH_ATOM).unwrap(),
+        KVal::Month(KData::Atom(Cow::Owned(qnull_base::I)))
+    );
+    assert_eq!(
+        KVal::null_of(qtype::TIMESPAN_ATOM).unwrap(),
+        KVal::Timespan(KData::Atom(Cow::Owned(qnull_base::J)))
+    );
+    match KVal::null_of(qtype::REAL_ATOM).unwrap() {
+        KVal::Real(KData::Atom(real)) => assert!(real.is_nan()),
+        _ => panic!("must be a real atom"),
+    }
+    match KVal::null_of(qtype::DATETIME_ATOM).unwrap() {
+        KVal::Datetime(KData::Atom(datetime)) => assert!(datetime.is_nan()),
+        _ => panic!("must be a datetime atom"),
+    }
+
+    // Infinities.
+    assert_eq!(
+        KVal::inf_of(qtype::SHORT_ATOM).unwrap(),
+        KVal::Short(KData::Atom(Cow::Owned(qinf_base::H)))
+    );
+    assert_eq!(
+        KVal::ninf_of(qtype::INT_LIST).unwrap(),
+        KVal::Int(KData::Atom(Cow::Owned(qninf_base::I)))
+    );
+    assert_eq!(
+        KVal::inf_of(qtype::TIMESTAMP_ATOM).unwrap(),
+        KVal::Timestamp(KData::Atom(Cow::Owned(qinf_base::J)))
+    );
+    assert_eq!(
+        KVal::ninf_of(qtype::REAL_ATOM).unwrap(),
+        KVal::Real(KData::Atom(Cow::Owned(qninf_base::E)))
+    );
+    assert_eq!(
+        KVal::inf_of(qtype::DATETIME_ATOM).unwrap(),
+        KVal::Datetime(KData::Atom(Cow::Owned(qinf_base::F)))
+    );
+    assert_eq!(
+        KVal::ninf_of(qtype::TIME_ATOM).unwrap(),
+        KVal::Time(KData::Atom(Cow::Owned(qninf_base::I)))
+    );
+
+    // Types without a null or an infinity.
+    for qtype in [
+        qtype::BOOL_ATOM,
+        qtype::BYTE_LIST,
+        qtype::COMPOUND_LIST,
+        qtype::ERROR,
+    ] {
+        assert_eq!(KVal::null_of(qtype).unwrap_err(), errors::NO_NULL);
+    }
+    for qtype in [
+        qtype::BOOL_ATOM,
+        qtype::GUID_ATOM,
+        qtype::CHAR,
+        qtype::SYMBOL_ATOM,
+        qtype::TABLE,
+    ] {
+        assert_eq!(KVal::inf_of(qtype).unwrap_err(), errors::NO_INFINITY);
+        assert_eq!(KVal::ninf_of(qtype).unwrap_err(), errors::NO_INFINITY);
+    }
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn content_hash_test() {
+    let table = KVal::Table(trades_table(100));
+    let q_table = table.to_k();
+    let borrowed = KVal::from_raw(q_table);
+    assert_eq!(borrowed.content_hash(), table.content_hash());
+    assert_eq!(borrowed.content_hash128(), table.content_hash128());
+    assert_eq!(
+        table.content_hash128() as u64,
+        table.content_hash(),
+        "low bits of the 128-bit hash must be the 64-bit hash"
+    );
+    assert_eq!(
+        KVal::Table(trades_table(100)).deep_clone().content_hash(),
+        table.content_hash()
+    );
+    assert_ne!(
+        KVal::Table(trades_table(99)).content_hash(),
+        table.content_hash()
+    );
+    drop(borrowed);
+    decrement_reference_count(q_table);
+
+    // Float nulls and zeros.
+    let floats = |list: Vec<f64>| KVal::Float(KData::List(Cow::Owned(list)));
+    let nan = f64::from_bits(0x7ff8_dead_beef_0001);
+    assert_eq!(
+        floats(vec![f64::NAN, 0.0]).content_hash(),
+        floats(vec![nan, -0.0]).content_hash()
+    );
+    assert_ne!(
+        floats(vec![f64::NAN]).content_hash(),
+        floats(vec![f64::INFINITY]).content_hash()
+    );
+    let reals = KVal::Real(KData::Atom(Cow::Owned(f32::NAN)));
+    assert_eq!(
+        reals.content_hash(),
+        KVal::Real(KData::Atom(Cow::Owned(-f32::NAN))).content_hash()
+    );
+
+    // Types, shapes and structure.
+    let long_atom = KVal::Long(KData::Atom(Cow::Owned(1)));
+    let long_list = KVal::Long(KData::List(Cow::Owned(vec![1])));
+    let timespan = KVal::Timespan(KData::Atom(Cow::Owned(1)));
+    assert_ne!(long_atom.content_hash(), long_list.content_hash());
+    assert_ne!(long_atom.content_hash(), timespan.content_hash());
+    assert_ne!(
+        KVal::CompoundList(vec![long_list.clone()]).content_hash(),
+        long_list.content_hash()
+    );
+    assert_ne!(
+        KVal::String(Cow::Borrowed(b"ab")).content_hash(),
+        KVal::Symbol(KData::Atom(Cow::Owned("ab".to_string()))).content_hash()
+    );
+    assert_ne!(
+        KVal::Symbol(KData::List(Cow::Owned(vec![
+            "ab".to_string(),
+            "c".to_string()
+        ])))
+        .content_hash(),
+        KVal::Symbol(KData::List(Cow::Owned(vec![
+            "a".to_string(),
+            "bc".to_string()
+        ])))
+        .content_hash()
+    );
+    assert_ne!(
+        KVal::Enum(KData::Atom(Cow::Owned(0)), Some("sym".to_string())).content_hash(),
+        KVal::Enum(KData::Atom(Cow::Owned(0)), None).content_hash()
+    );
+
+    // The hash is stable across runs. Bump `CONTENT_HASH_VERSION` if these values change.
+    assert_eq!(CONTENT_HASH_VERSION, 1);
+    assert_eq!(long_list.content_hash(), 0xc17903beb79f7546);
+    assert_eq!(
+        KVal::String(Cow::Borrowed(b"kdb+")).content_hash128(),
+        0x7288dcbc8d6d246a5e231c6862aa2257
+    );
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn null_predicate_test() {
+    let atom = |value: KVal<'static>| {
+        (
+            value.is_null_atom(),
+            value.is_inf_atom(),
+            value.is_ninf_atom(),
+        )
+    };
+    assert_eq!(
+        atom(KVal::Short(KData::Atom(Cow::Owned(qnull_base::H)))),
+        (true, false, false)
+    );
+    assert_eq!(
+        atom(KVal::Int(KData::Atom(Cow::Owned(qinf_base::I)))),
+        (false, true, false)
+    );
+    assert_eq!(
+        atom(KVal::Long(KData::Atom(Cow::Owned(qninf_base::J)))),
+        (false, false, true)
+    );
+    assert_eq!(
+        atom(KVal::Real(KData::Atom(Cow::Owned(-f32::NAN)))),
+        (true, false, false)
+    );
+    assert_eq!(
+        atom(KVal::Float(KData::Atom(Cow::Owned(qninf_base::F)))),
+        (false, false, true)
+    );
+    assert_eq!(
+        atom(KVal::Guid(KData::Atom(Cow::Owned(qnull_base::U)))),
+        (true, false, false)
+    );
+    assert_eq!(
+        atom(KVal::Symbol(KData::Atom(Cow::Owned(String::new())))),
+        (true, false, false)
+    );
+    assert_eq!(atom(KVal::Char(qnull_base::C)), (true, false, false));
+    assert_eq!(atom(KVal::Char('a')), (false, false, false));
+    assert_eq!(atom(KVal::Null), (true, false, false));
+    assert_eq!(
+        atom(KVal::Bool(KData::Atom(Cow::Owned(false)))),
+        (false, false, false)
+    );
+    assert_eq!(
+        atom(KVal::Byte(KData::Atom(Cow::Owned(0)))),
+        (false, false, false)
+    );
+    assert_eq!(
+        atom(KVal::Enum(KData::Atom(Cow::Owned(qnull_base::J)), None)),
+        (true, false, false)
+    );
+    assert_eq!(
+        atom(KVal::Long(KData::List(Cow::Owned(vec![qnull_base::J])))),
+        (false, false, false)
+    );
+
+    // Temporal types share the borders of their underlying types.
+    for qtype in [
+        qtype::TIMESTAMP_ATOM,
+        qtype::MONTH_ATOM,
+        qtype::DATE_ATOM,
+        qtype::DATETIME_ATOM,
+        qtype::TIMESPAN_ATOM,
+        qtype::MINUTE_ATOM,
+        qtype::SECOND_ATOM,
+        qtype::TIME_ATOM,
+    ] {
+        assert_eq!(atom(KVal::null_of(qtype).unwrap()), (true, false, false));
+        assert_eq!(atom(KVal::inf_of(qtype).unwrap()), (false, true, false));
+        assert_eq!(atom(KVal::ninf_of(qtype).unwrap()), (false, false, true));
+    }
+
+    // Masks.
+    let dates = KData::List(Cow::Owned(vec![
+        1,
+        qnull_base::I,
+        qinf_base::I,
+        qninf_base::I,
+    ]));
+    assert_eq!(dates.null_mask(), vec![false, true, false, false]);
+    assert_eq!(dates.inf_mask(), vec![false, false, true, false]);
+    assert_eq!(dates.ninf_mask(), vec![false, false, false, true]);
+    let floats = KData::List(Cow::Owned(vec![f64::NAN, 1.0, qinf_base::F]));
+    assert_eq!(floats.null_mask(), vec![true, false, false]);
+    assert_eq!(floats.inf_mask(), vec![false, false, true]);
+    let guids = KData::List(Cow::Owned(vec![qnull_base::U, [1; 16]]));
+    assert_eq!(guids.null_mask(), vec![true, false]);
+    assert_eq!(guids.inf_mask(), vec![false, false]);
+    let bools = KData::List(Cow::Owned(vec![false, true]));
+    assert_eq!(bools.null_mask(), vec![false, false]);
+    assert_eq!(
+        KData::<i16>::Atom(Cow::Owned(qnull_base::H)).null_mask(),
+        vec![true]
+    );
+}
+
+#[test]
+fn join_test() {
+    assert_eq!(KVal::join(long(1), long(2)).unwrap(), longs(vec![1, 2]));
+    assert_eq!(
+        KVal::join(longs(vec![1, 2]), longs(vec![3])).unwrap(),
+        longs(vec![1, 2, 3])
+    );
+
+    // An owned base is extended in place.
+    let mut base = Vec::with_capacity(8);
+    base.extend([1_i64, 2]);
+    let pointer = base.as_ptr();
+    match KVal::join(longs(base), long(3)).unwrap() {
+        KVal::Long(KData::List(Cow::Owned(joined))) => {
+            assert_eq!(joined.as_ptr(), pointer);
+            assert_eq!(joined, vec![1, 2, 3]);
+        }
+        _ => panic!("must be an owned long list"),
+    }
+
+    // Large owned lists are joined without cloning the base.
+    let mut base = Vec::with_capacity(2_000_000);
+    base.extend(0_i64..1_000_000);
+    let pointer = base.as_ptr();
+    match KVal::join(longs(base), longs((1_000_000..2_000_000).collect())).unwrap() {
+        KVal::Long(KData::List(Cow::Owned(joined))) => {
+            assert_eq!(joined.as_ptr(), pointer);
+            assert_eq!(joined.capacity(), 2_000_000);
+            assert!(joined.iter().copied().eq(0..2_000_000));
+        }
+        _ => panic!("must be an owned long list"),
+    }
+
+    // A borrowed base is copied once.
+    let buffer = [1_i64, 2];
+    let borrowed = KVal::Long(KData::List(Cow::Borrowed(&buffer)));
+    match KVal::join(borrowed, longs(vec![3, 4])).unwrap() {
+        KVal::Long(KData::List(Cow::Owned(joined))) => {
+            assert_eq!(joined, vec![1, 2, 3, 4]);
+            assert_eq!(joined.capacity(), 4);
+        }
+        _ => panic!("must be an owned long list"),
+    }
+
+    // Strings and chars.
+    assert_eq!(
+        KVal::join(KVal::String(Cow::Borrowed(b"ab")), KVal::Char('c')).unwrap(),
+        KVal::String(Cow::Borrowed(b"abc"))
+    );
+    assert_eq!(
+        KVal::join(KVal::Char('a'), KVal::Char('b')).unwrap(),
+        KVal::String(Cow::Borrowed(b"ab"))
+    );
+    let joined = KVal::join(KVal::Char('é'), KVal::String(Cow::Borrowed(b"a"))).unwrap();
+    assert_eq!(joined, KVal::String(Cow::Borrowed(b"\xe9a")));
+    assert_eq!(joined.len(), 2);
+    assert_eq!(
+        KVal::join_ref(joined, &KVal::Char('\u{ff}')).unwrap(),
+        KVal::String(Cow::Borrowed(b"\xe9a\xff"))
+    );
+    assert_eq!(
+        KVal::join(KVal::String(Cow::Borrowed(b"ab")), KVal::Char('€'))
+            .unwrap_err()
+            .to_string(),
+        "value \"€\" out of range of char"
+    );
+
+    // Enums.
+    let enums = |list: Vec<i64>, source: &str| {
+        KVal::Enum(KData::List(Cow::Owned(list)), Some(source.to_string()))
+    };
+    assert_eq!(
+        KVal::join(enums(vec![0], "sym"), enums(vec![1], "sym")).unwrap(),
+        enums(vec![0, 1], "sym")
+    );
+    let mixed_sources = KVal::join(enums(vec![0], "sym"), enums(vec![1], "other")).unwrap();
+    assert_eq!(mixed_sources.qtype(), qtype::COMPOUND_LIST);
+    assert_eq!(mixed_sources.len(), 2);
+
+    // Different types result in a compound list.
+    assert_eq!(
+        KVal::join(longs(vec![1, 2]), KVal::Char('a')).unwrap(),
+        KVal::CompoundList(vec![long(1), long(2), KVal::Char('a')])
+    );
+    assert_eq!(
+        KVal::join(KVal::CompoundList(vec![KVal::Null]), longs(vec![1])).unwrap(),
+        KVal::CompoundList(vec![KVal::Null, long(1)])
+    );
+    assert_eq!(
+        KVal::join(KVal::Null, KVal::Null).unwrap(),
+        KVal::CompoundList(vec![KVal::Null, KVal::Null])
+    );
+    assert_eq!(
+        KVal::join(
+            KVal::Int(KData::List(Cow::Owned(vec![1]))),
+            KVal::Date(KData::Atom(Cow::Owned(1)))
+        )
+        .unwrap()
+        .qtype(),
+        qtype::COMPOUND_LIST
+    );
+
+    // Tables and dictionaries cannot be joined.
+    assert_eq!(
+        KVal::join(KVal::Table(trades_table(2)), long(1)).unwrap_err(),
+        KError::InvalidOperation {
+            operator: "join",
+            operand_type: "table",
+            expected: Some("atom or list")
+        }
+    );
+    assert!(KVal::join(long(1), KVal::Error(Cow::Borrowed("type"))).is_err());
+}
+
+#[test]
+fn push_append_test() {
+    // Repeated pushes reuse the buffer of an owned list.
+    let mut buffer = Vec::with_capacity(16);
+    buffer.push(0_i64);
+    let pointer = buffer.as_ptr();
+    let mut list = longs(buffer);
+    for value in 1..16 {
+        list.push(long(value)).unwrap();
+    }
+    match &list {
+        KVal::Long(KData::List(Cow::Owned(pushed))) => {
+            assert_eq!(pushed.as_ptr(), pointer);
+            assert_eq!(*pushed, (0..16).collect::<Vec<_>>());
+        }
+        _ => panic!("must be an owned long list"),
+    }
+
+    // A borrowed list is copied into an owned list.
+    let buffer = [1_i64, 2];
+    let mut borrowed = KVal::Long(KData::List(Cow::Borrowed(&buffer)));
+    borrowed.append(longs(vec![3, 4])).unwrap();
+    assert!(matches!(borrowed, KVal::Long(KData::List(Cow::Owned(_)))));
+    assert_eq!(borrowed, longs(vec![1, 2, 3, 4]));
+
+    // An atom becomes a list.
+    let mut atom = long(1);
+    atom.push(long(2)).unwrap();
+    assert_eq!(atom, longs(vec![1, 2]));
+    let mut character = KVal::Char('a');
+    character.push(KVal::Char('b')).unwrap();
+    character
+        .append(KVal::String(Cow::Borrowed(b"cd")))
+        .unwrap();
+    assert_eq!(character, KVal::String(Cow::Borrowed(b"abcd")));
+
+    // A char is appended to a string as a single byte.
+    let mut string = KVal::String(Cow::Borrowed(b"caf"));
+    string.push(KVal::Char('é')).unwrap();
+    assert_eq!(string, KVal::String(Cow::Borrowed(b"caf\xe9")));
+    assert_eq!(string.len(), 4);
+    let mut character = KVal::Char('é');
+    character.push(KVal::Char('\u{ff}')).unwrap();
+    assert_eq!(character, KVal::String(Cow::Borrowed(b"\xe9\xff")));
+    assert_eq!(
+        string.push(KVal::Char('€')).unwrap_err(),
+        errors::CHAR_OUT_OF_RANGE
+    );
+    let mut character = KVal::Char('a');
+    assert_eq!(
+        character.append(KVal::Char('€')).unwrap_err(),
+        errors::CHAR_OUT_OF_RANGE
+    );
+    assert_eq!(character, KVal::Char('a'));
+    assert_eq!(string, KVal::String(Cow::Borrowed(b"caf\xe9")));
+
+    // A compound list takes a list as an element by push and its elements by append.
+    let mut compound = KVal::CompoundList(vec![KVal::Null]);
+    compound.push(longs(vec![1, 2])).unwrap();
+    compound.append(longs(vec![3, 4])).unwrap();
+    assert_eq!(
+        compound,
+        KVal::CompoundList(vec![KVal::Null, longs(vec![1, 2]), long(3), long(4)])
+    );
+
+    // Enums must share a source.
+    let enums = |list: Vec<i64>, source: &str| {
+        KVal::Enum(KData::List(Cow::Owned(list)), Some(source.to_string()))
+    };
+    let mut sym = enums(vec![0], "sym");
+    sym.push(KVal::Enum(
+        KData::Atom(Cow::Owned(1)),
+        Some("sym".to_string()),
+    ))
+    .unwrap();
+    assert_eq!(sym, enums(vec![0, 1], "sym"));
+    assert_eq!(
+        sym.append(enums(vec![2], "other")).unwrap_err(),
+        errors::ENUM_SOURCE_MISMATCH
+    );
+
+    // Errors leave the value unchanged.
+    let mut list = longs(vec![1, 2]);
+    assert_eq!(list.push(longs(vec![3])).unwrap_err(), errors::NOT_AN_ATOM);
+    assert_eq!(
+        list.push(KVal::Error(Cow::Borrowed("type"))).unwrap_err(),
+        errors::NOT_AN_ATOM
+    );
+    assert_eq!(list.push(KVal::Char('a')).unwrap_err(), errors::TYPE);
+    assert_eq!(
+        list.append(KVal::Int(KData::List(Cow::Owned(vec![3]))))
+            .unwrap_err(),
+        errors::TYPE
+    );
+    assert_eq!(list.append(KVal::Null).unwrap_err(), errors::TYPE);
+    assert_eq!(list, longs(vec![1, 2]));
+    let mut table = KVal::Table(trades_table(1));
+    assert_eq!(table.push(long(1)).unwrap_err(), errors::TYPE);
+}
+
+#[test]
+fn pop_remove_test() {
+    // Popping a borrowed list shortens the slice and removing copies it once.
+    let buffer = [1_i64, 2, 3, 4];
+    let mut longs = KVal::Long(KData::List(Cow::Borrowed(&buffer)));
+    assert_eq!(longs.pop(), Some(long(4)));
+    assert!(matches!(longs, KVal::Long(KData::List(Cow::Borrowed(_)))));
+    let k = longs.to_k();
+    assert_eq!(k.len(), 3);
+    decrement_reference_count(k);
+    assert_eq!(longs.remove(0), Ok(long(1)));
+    assert!(matches!(longs, KVal::Long(KData::List(Cow::Owned(_)))));
+    let k = longs.to_k();
+    assert_eq!(k.as_mut_slice::<J>(), &[2, 3]);
+    decrement_reference_count(k);
+    assert_eq!(longs.remove(2), Err(errors::INDEX_OUT_OF_BOUNDS));
+    assert_eq!(longs.pop(), Some(long(3)));
+    assert_eq!(longs.pop(), Some(long(2)));
+    assert_eq!(longs.pop(), None);
+    let k = longs.to_k();
+    assert_eq!(k.get_type(), qtype::LONG_LIST);
+    assert_eq!(k.len(), 0);
+    decrement_reference_count(k);
+
+    // Strings yield chars.
+    let mut string = KVal::String(Cow::Borrowed(b"abc"));
+    assert_eq!(string.remove(1), Ok(KVal::Char('b')));
+    assert_eq!(string.pop(), Some(KVal::Char('c')));
+    let k = string.to_k();
+    assert_eq!(k.get_string(), Ok("a".to_string()));
+    decrement_reference_count(k);
+
+    // Strings are edited by bytes, including a byte of a multi-byte character.
+    let mut string = KVal::String(Cow::Borrowed("café!".as_bytes()));
+    assert_eq!(string.len(), 6);
+    assert_eq!(string.pop(), Some(KVal::Char('!')));
+    assert_eq!(string.pop(), Some(KVal::Char('\u{a9}')));
+    assert_eq!(string.remove(1), Ok(KVal::Char('a')));
+    assert_eq!(string, KVal::String(Cow::Borrowed(b"cf\xc3")));
+    assert_eq!(string.len(), 3);
+    assert_eq!(string.remove(3), Err(errors::INDEX_OUT_OF_BOUNDS));
+
+    // Compound lists yield their elements as they are.
+    let mut compound = KVal::CompoundList(vec![long(1), KVal::Char('a'), KVal::Null]);
+    assert_eq!(compound.remove(1), Ok(KVal::Char('a')));
+    let k = compound.to_k();
+    assert_eq!(k.get_type(), qtype::COMPOUND_LIST);
+    assert_eq!(k.len(), 2);
+    decrement_reference_count(k);
+    assert_eq!(compound.pop(), Some(KVal::Null));
+
+    // Symbols and enums.
+    let mut symbols = KVal::Symbol(KData::List(Cow::Owned(vec![
+        "a".to_string(),
+        "b".to_string(),
+    ])));
+    assert_eq!(
+        symbols.pop(),
+        Some(KVal::Symbol(KData::Atom(Cow::Owned("b".to_string()))))
+    );
+    let k = symbols.to_k();
+    assert_eq!(k.len(), 1);
+    decrement_reference_count(k);
+    let mut enums = KVal::Enum(KData::List(Cow::Owned(vec![0, 1])), Some("sym".to_string()));
+    assert_eq!(
+        enums.remove(0),
+        Ok(KVal::Enum(
+            KData::Atom(Cow::Owned(0)),
+            Some("sym".to_string())
+        ))
+    );
+    assert_eq!(enums.len(), 1);
+
+    // Atoms and non-lists are not edited.
+    let mut atom = long(1);
+    assert_eq!(atom.pop(), None);
+    assert_eq!(atom.remove(0), Err(errors::NOT_A_LIST));
+    assert_eq!(KVal::Char('a').remove(0), Err(errors::NOT_A_LIST));
+    assert_eq!(
+        KVal::Table(trades_table(2)).remove(0),
+        Err(errors::NOT_A_LIST)
+    );
+    assert_eq!(KVal::Null.pop(), None);
+}
+
+#[test]
+fn string_vec_test() {
+    let strings = vec![
+        "first line".to_string(),
+        String::new(),
+        "multi\nline".to_string(),
+        "x".to_string(),
+    ];
+    let list = KVal::from_string_vec(strings.clone());
+    assert_eq!(list.qtype(), qtype::COMPOUND_LIST);
+    assert_eq!(list.as_string_vec().unwrap(), strings);
+
+    // Round trip through q.
+    let k = list.to_k();
+    assert_eq!(k.get_type(), qtype::COMPOUND_LIST);
+    assert_eq!(k.len(), 4);
+    assert_eq!(k.as_mut_slice::<K>()[1].get_type(), qtype::STRING);
+    assert_eq!(k.as_mut_slice::<K>()[3].get_type(), qtype::STRING);
+    assert_eq!(KVal::from_raw(k).as_string_vec().unwrap(), strings);
+    decrement_reference_count(k);
+    let k = KVal::from_string_vec(Vec::new()).to_k();
+    assert_eq!(
+        KVal::from_raw(k).as_string_vec().unwrap(),
+        Vec::<String>::new()
+    );
+    decrement_reference_count(k);
+
+    // Chars and symbols are converted.
+    let mixed = KVal::CompoundList(vec![
+        KVal::String(Cow::Borrowed(b"ab")),
+        KVal::Char('c'),
+        KVal::Symbol(KData::Atom(Cow::Owned("sym".to_string()))),
+    ]);
+    assert_eq!(mixed.as_string_vec().unwrap(), vec!["ab", "c", "sym"]);
+    assert_eq!(
+        mixed.join_strings(", ").unwrap(),
+        KVal::String(Cow::Borrowed(b"ab, c, sym"))
+    );
+    let symbols = KVal::Symbol(KData::List(Cow::Owned(vec![
+        "a".to_string(),
+        "b".to_string(),
+    ])));
+    assert_eq!(symbols.as_string_vec().unwrap(), vec!["a", "b"]);
+
+    // Joined strings.
+    assert_eq!(
+        KVal::from_string_vec(strings).join_strings("\n").unwrap(),
+        KVal::String(Cow::Borrowed(b"first line\n\nmulti\nline\nx"))
+    );
+    assert_eq!(
+        KVal::from_string_vec(Vec::new())
+            .join_strings("\n")
+            .unwrap(),
+        KVal::String(Cow::Borrowed(b""))
+    );
+
+    // Other types are rejected.
+    assert_eq!(
+        KVal::CompoundList(vec![KVal::Long(KData::Atom(Cow::Owned(1)))])
+            .as_string_vec()
+            .unwrap_err(),
+        KError::InvalidOperation {
+            operator: "as_string_vec",
+            operand_type: "long atom",
+            expected: Some("string, char or symbol")
+        }
+    );
+    assert!(KVal::String(Cow::Borrowed(b"ab")).as_string_vec().is_err());
+    assert!(KVal::Null.join_strings(",").is_err());
+}
+
+#[test]
+fn lazy_dict_test() {
+    let names = |names: &[&str]| {
+        KVal::Symbol(KData::List(Cow::Owned(
+            names.iter().map(|name| name.to_string()).collect(),
+        )))
+    };
+    let snapshot = KVal::Dictionary(KDict::new(
+        names(&["trades", "quotes", "orders"]),
+        KVal::CompoundList(vec![
+            KVal::Table(trades_table(100_000)),
+            KVal::Table(trades_table(200_000)),
+            KVal::Table(trades_table(300_000)),
+        ]),
+    ))
+    .to_k();
+
+    // Only the value which is read is converted, and only once.
+    let lazy = LazyDict::from_raw(snapshot);
+    assert_eq!(lazy.len(), 3);
+    assert_eq!(lazy.converted(), 0);
+    let quotes = lazy.get("quotes").unwrap();
+    assert_eq!(quotes.len(), 200_000);
+    assert_eq!(lazy.converted(), 1);
+    let again = lazy
+        .get_by(&KVal::Symbol(KData::Atom(Cow::Owned("quotes".to_string()))))
+        .unwrap();
+    assert!(std::ptr::eq(quotes, again));
+    assert_eq!(lazy.converted(), 1);
+    assert!(lazy.get("missing").is_none());
+    assert_eq!(lazy.converted(), 1);
+
+    // The remaining values are converted into the same dictionary as the eager conversion.
+    let eager = KDict::from_raw(snapshot);
+    assert_eq!(eager.get("orders").unwrap().len(), 300_000);
+    assert_eq!(lazy.into_dict(), eager);
+    decrement_reference_count(snapshot);
+
+    // Values in a simple list.
+    let prices = KVal::Dictionary(KDict::new(
+        names(&["a", "b"]),
+        KVal::Float(KData::List(Cow::Owned(vec![1.5, 2.5]))),
+    ))
+    .to_k();
+    let lazy = LazyDict::from_raw(prices);
+    assert_eq!(
+        lazy.get("b"),
+        Some(&KVal::Float(KData::Atom(Cow::Owned(2.5))))
+    );
+    assert_eq!(
+        KDict::from_raw(prices).get("a"),
+        Some(KVal::Float(KData::Atom(Cow::Owned(1.5))))
+    );
+    decrement_reference_count(prices);
+
+    // Keys other than symbols.
+    let squares = KDict::new(
+        KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3]))),
+        KVal::Long(KData::List(Cow::Owned(vec![1, 4, 9]))),
+    );
+    assert_eq!(
+        squares.get_by(&KVal::Long(KData::Atom(Cow::Owned(3)))),
+        Some(KVal::Long(KData::Atom(Cow::Owned(9))))
+    );
+    assert!(squares.get("a").is_none());
+}
+
+#[test]
+fn insert_test() {
+    // At the head, in the middle and at the end.
+    let buffer = [2_i64, 4];
+    let mut list = KVal::Long(KData::List(Cow::Borrowed(&buffer)));
+    list.insert(0, long(1)).unwrap();
+    list.insert(2, long(3)).unwrap();
+    list.insert(4, long(5)).unwrap();
+    assert_eq!(list, longs(vec![1, 2, 3, 4, 5]));
+    let mut empty = longs(Vec::new());
+    empty.insert(0, long(1)).unwrap();
+    assert_eq!(empty, longs(vec![1]));
+
+    // Strings, compound lists and enums.
+    let mut string = KVal::String(Cow::Borrowed(b"bd"));
+    string.insert(0, KVal::Char('a')).unwrap();
+    string.insert(2, KVal::Char('c')).unwrap();
+    string.insert(4, KVal::Char('e')).unwrap();
+    assert_eq!(string, KVal::String(Cow::Borrowed(b"abcde")));
+    // A char is inserted as a single byte in the same manner as `to_k` builds a char atom.
+    let mut string = KVal::String(Cow::Borrowed("café".as_bytes()));
+    assert_eq!(
+        string.insert(0, KVal::Char('🦀')).unwrap_err(),
+        errors::CHAR_OUT_OF_RANGE
+    );
+    string.insert(0, KVal::Char('é')).unwrap();
+    string.insert(5, KVal::Char('!')).unwrap();
+    assert_eq!(string, KVal::String(Cow::Borrowed(b"\xe9caf\xc3!\xa9")));
+    assert_eq!(string.len(), 7);
+    assert_eq!(string.get(0), Some(KVal::Char('é')));
+    // A char is set to a string as a single byte as well, leaving the other bytes in place.
+    string.set(4, KVal::Char('ü')).unwrap();
+    assert_eq!(string, KVal::String(Cow::Borrowed(b"\xe9caf\xfc!\xa9")));
+    assert_eq!(string.len(), 7);
+    assert_eq!(
+        string.set(1, KVal::Char('€')).unwrap_err(),
+        errors::CHAR_OUT_OF_RANGE
+    );
+    assert_eq!(string, KVal::String(Cow::Borrowed(b"\xe9caf\xfc!\xa9")));
+    let mut compound = KVal::CompoundList(vec![long(1)]);
+    compound.insert(0, longs(vec![2, 3])).unwrap();
+    compound.insert(2, KVal::Null).unwrap();
+    assert_eq!(
+        compound,
+        KVal::CompoundList(vec![longs(vec![2, 3]), long(1), KVal::Null])
+    );
+    let mut enums = KVal::Enum(KData::List(Cow::Owned(vec![0, 2])), Some("sym".to_string()));
+    enums
+        .insert(
+            1,
+            KVal::Enum(KData::Atom(Cow::Owned(1)), Some("sym".to_string())),
+        )
+        .unwrap();
+    assert_eq!(
+        enums,
+        KVal::Enum(
+            KData::List(Cow::Owned(vec![0, 1, 2])),
+            Some("sym".to_string())
+        )
+    );
+    assert_eq!(
+        enums
+            .insert(
+                0,
+                KVal::Enum(KData::Atom(Cow::Owned(0)), Some("other".to_string()))
+            )
+            .unwrap_err(),
+        errors::ENUM_SOURCE_MISMATCH
+    );
+
+    // Errors leave the value unchanged.
+    let mut list = longs(vec![1, 2]);
+    assert_eq!(
+        list.insert(3, long(3)).unwrap_err(),
+        errors::INDEX_OUT_OF_BOUNDS
+    );
+    assert_eq!(list.insert(0, KVal::Char('a')).unwrap_err(), errors::TYPE);
+    assert_eq!(
+        list.insert(0, longs(vec![3])).unwrap_err(),
+        errors::NOT_AN_ATOM
+    );
+    assert_eq!(list.insert(0, KVal::Null).unwrap_err(), errors::NOT_AN_ATOM);
+    assert_eq!(list, longs(vec![1, 2]));
+    assert_eq!(
+        KVal::CompoundList(Vec::new())
+            .insert(1, long(1))
+            .unwrap_err(),
+        errors::INDEX_OUT_OF_BOUNDS
+    );
+    assert_eq!(long(1).insert(0, long(2)).unwrap_err(), errors::NOT_A_LIST);
+    assert_eq!(
+        KVal::Char('a').insert(0, KVal::Char('b')).unwrap_err(),
+        errors::NOT_A_LIST
+    );
+    assert_eq!(
+        KVal::Table(trades_table(1)).insert(0, long(1)).unwrap_err(),
+        errors::NOT_A_LIST
+    );
+}
+
+#[test]
+fn reverse_rotate_test() {
+    // An owned list is edited in place.
+    let mut buffer = Vec::with_capacity(4);
+    buffer.extend([1_i64, 2, 3, 4]);
+    let pointer = buffer.as_ptr();
+    let mut list = longs(buffer);
+    list.reverse().unwrap();
+    list.rotate(1).unwrap();
+    match &list {
+        KVal::Long(KData::List(Cow::Owned(edited))) => {
+            assert_eq!(edited.as_ptr(), pointer);
+            assert_eq!(*edited, vec![3, 2, 1, 4]);
+        }
+        _ => panic!("must be an owned long list"),
+    }
+    list.rotate(-5).unwrap();
+    assert_eq!(list, longs(vec![4, 3, 2, 1]));
+    list.rotate(isize::MIN).unwrap();
+    assert_eq!(list, longs(vec![4, 3, 2, 1]));
+
+    // A borrowed list is copied only when it changes.
+    let buffer = [1_i64, 2, 3];
+    let mut borrowed = KVal::Long(KData::List(Cow::Borrowed(&buffer)));
+    borrowed.rotate(3).unwrap();
+    assert!(matches!(
+        borrowed,
+        KVal::Long(KData::List(Cow::Borrowed(_)))
+    ));
+    borrowed.rotate(-1).unwrap();
+    assert!(matches!(borrowed, KVal::Long(KData::List(Cow::Owned(_)))));
+    assert_eq!(borrowed, longs(vec![3, 1, 2]));
+    let single = [1_i64];
+    let mut borrowed = KVal::Long(KData::List(Cow::Borrowed(&single)));
+    borrowed.reverse().unwrap();
+    assert!(matches!(
+        borrowed,
+        KVal::Long(KData::List(Cow::Borrowed(_)))
+    ));
+
+    // Strings, compound lists and enums.
+    let mut string = KVal::String(Cow::Borrowed(b"abcd"));
+    string.reverse().unwrap();
+    assert_eq!(string, KVal::String(Cow::Borrowed(b"dcba")));
+    string.rotate(-1).unwrap();
+    assert_eq!(string, KVal::String(Cow::Borrowed(b"adcb")));
+    // Strings are reordered by bytes, including the bytes of a multi-byte character.
+    let mut string = KVal::String(Cow::Borrowed("éab".as_bytes()));
+    string.reverse().unwrap();
+    assert_eq!(string, KVal::String(Cow::Borrowed(b"ba\xa9\xc3")));
+    string.rotate(1).unwrap();
+    assert_eq!(string, KVal::String(Cow::Borrowed(b"a\xa9\xc3b")));
+    string.rotate(-5).unwrap();
+    assert_eq!(string, KVal::String(Cow::Borrowed(b"ba\xa9\xc3")));
+    let mut compound = KVal::CompoundList(vec![longs(vec![1]), KVal::Char('a'), KVal::Null]);
+    compound.rotate(2).unwrap();
+    assert_eq!(
+        compound,
+        KVal::CompoundList(vec![KVal::Null, longs(vec![1]), KVal::Char('a')])
+    );
+    let mut enums = KVal::Enum(KData::List(Cow::Owned(vec![0, 1])), Some("sym".to_string()));
+    enums.reverse().unwrap();
+    assert_eq!(
+        enums,
+        KVal::Enum(KData::List(Cow::Owned(vec![1, 0])), Some("sym".to_string()))
+    );
+
+    // Empty lists and atoms are left as they are.
+    let mut empty = longs(Vec::new());
+    empty.rotate(3).unwrap();
+    empty.reverse().unwrap();
+    assert_eq!(empty, longs(Vec::new()));
+    let mut atom = KVal::Long(KData::Atom(Cow::Owned(1)));
+    atom.reverse().unwrap();
+    atom.rotate(1).unwrap();
+    assert_eq!(atom, KVal::Long(KData::Atom(Cow::Owned(1))));
+    let mut character = KVal::Char('a');
+    character.rotate(1).unwrap();
+    assert_eq!(character, KVal::Char('a'));
+
+    // Non-lists are rejected.
+    assert_eq!(
+        KVal::Table(trades_table(2)).reverse(),
+        Err(errors::NOT_A_LIST)
+    );
+    assert_eq!(KVal::Null.rotate(1), Err(errors::NOT_A_LIST));
+}
+
+#[test]
+fn sort_test() {
+    // Nulls come first, then negative infinity.
+    let sorted = longs(vec![3, qnull_base::J, 1, qninf_base::J, 2, qinf_base::J])
+        .sort()
+        .unwrap();
+    assert_eq!(
+        *sorted,
+        longs(vec![qnull_base::J, qninf_base::J, 1, 2, 3, qinf_base::J])
+    );
+    let k = sorted.to_k();
+    assert_eq!(k.get_type(), qtype::LONG_LIST);
+    assert_eq!(k.get_attribute(), qattribute::SORTED);
+    assert_eq!(
+        k.as_mut_slice::<J>(),
+        &[qnull_base::J, qninf_base::J, 1, 2, 3, qinf_base::J]
+    );
+    decrement_reference_count(k);
+
+    let floats = KVal::Float(KData::List(Cow::Owned(vec![
+        2.5,
+        qnull_base::F,
+        qninf_base::F,
+        -0.5,
+    ])));
+    match floats.clone().sort().unwrap().into_inner() {
+        KVal::Float(KData::List(sorted)) => {
+            assert!(sorted[0].is_nan());
+            assert_eq!(&sorted[1..], &[qninf_base::F, -0.5, 2.5]);
+        }
+        _ => panic!("must be a float list"),
+    }
+    match floats.sort_desc().unwrap() {
+        KVal::Float(KData::List(sorted)) => {
+            assert_eq!(&sorted[..3], &[2.5, -0.5, qninf_base::F]);
+            assert!(sorted[3].is_nan());
+        }
+        _ => panic!("must be a float list"),
+    }
+
+    // Symbols, strings and temporal types.
+    let symbols = KVal::Symbol(KData::List(Cow::Owned(
+        ["b", "", "a"]
+            .iter()
+            .map(|symbol| symbol.to_string())
+            .collect(),
+    )));
+    assert_eq!(
+        *symbols.sort().unwrap(),
+        KVal::Symbol(KData::List(Cow::Owned(
+            ["", "a", "b"]
+                .iter()
+                .map(|symbol| symbol.to_string())
+                .collect()
+        )))
+    );
+    let string = KVal::String(Cow::Borrowed(b"kdb+"));
+    let sorted = string.sort().unwrap();
+    assert_eq!(*sorted, KVal::String(Cow::Borrowed(b"+bdk")));
+    let k = sorted.to_k();
+    assert_eq!(k.get_attribute(), qattribute::SORTED);
+    decrement_reference_count(k);
+    let multibyte = KVal::String(Cow::Borrowed("éa".as_bytes()));
+    assert_eq!(
+        *multibyte.clone().sort().unwrap(),
+        KVal::String(Cow::Borrowed(b"a\xa9\xc3"))
+    );
+    assert_eq!(
+        multibyte.sort_desc().unwrap(),
+        KVal::String(Cow::Borrowed(b"\xc3\xa9a"))
+    );
+    let dates = KVal::Date(KData::List(Cow::Owned(vec![3, qnull_base::I, 1])));
+    assert_eq!(
+        dates.sort_desc().unwrap(),
+        KVal::Date(KData::List(Cow::Owned(vec![3, 1, qnull_base::I])))
+    );
+
+    // A sorted borrowed list is not copied and an unsorted one is.
+    let buffer = [1_i64, 1, 2];
+    let borrowed = KVal::Long(KData::List(Cow::Borrowed(&buffer)));
+    assert!(matches!(
+        borrowed.sort().unwrap().into_inner(),
+        KVal::Long(KData::List(Cow::Borrowed(_)))
+    ));
+    let borrowed = KVal::Long(KData::List(Cow::Borrowed(&buffer)));
+    assert!(matches!(
+        borrowed.sort_desc().unwrap(),
+        KVal::Long(KData::List(Cow::Owned(_)))
+    ));
+    let k = longs(Vec::new()).sort().unwrap().to_k();
+    assert_eq!(k.len(), 0);
+    assert_eq!(k.get_attribute(), qattribute::SORTED);
+    decrement_reference_count(k);
+
+    // Compound lists, enums and non-lists are rejected.
+    assert_eq!(
+        KVal::CompoundList(vec![KVal::Null]).sort().unwrap_err(),
+        errors::TYPE
+    );
+    assert_eq!(
+        KVal::Enum(KData::List(Cow::Owned(vec![1, 0])), Some("sym".to_string()))
+            .sort()
+            .unwrap_err(),
+        errors::TYPE
+    );
+    assert_eq!(
+        KVal::Long(KData::Atom(Cow::Owned(1))).sort().unwrap_err(),
+        errors::NOT_A_LIST
+    );
+    assert_eq!(KVal::Char('a').sort_desc().unwrap_err(), errors::NOT_A_LIST);
+    assert_eq!(
+        KVal::Table(trades_table(2)).sort().unwrap_err(),
+        errors::NOT_A_LIST
+    );
+}
+
+/// Build `value` into q, check the type and the length of the object and convert it back.
+fn check_to_k(value: &KVal, qtype: i8) {
+    let live = mock::live_objects();
+    let k = value.to_k();
+    assert_eq!(k.get_type(), qtype);
+    if qtype >= 0 {
+        assert_eq!(k.len(), value.len() as i64);
+    }
+    match (KVal::from_raw(k), value) {
+        // The source of an enum passed from q is not known.
+        (KVal::Enum(data, None), KVal::Enum(expected, Some(_))) => assert_eq!(data, *expected),
+        (converted, expected) => assert_eq!(converted, *expected),
+    }
+    decrement_reference_count(k);
+    assert_eq!(mock::live_objects(), live);
+}
+
+#[test]
+fn to_k_family_test() {
+    mock::set_k_handler(|query, args| match query {
+        "{`enum$enum x}" => {
+            let indices = args[0];
+            let list = new_list(qtype::ENUM_LIST, indices.len());
+            list.as_mut_slice::<J>()
+                .copy_from_slice(indices.as_mut_slice::<J>());
+            decrement_reference_count(indices);
+            list
+        }
+        _ => unsafe { native::ee(new_error("unexpected query\0")) },
+    });
+
+    // Atoms.
+    check_to_k(&KVal::Bool(KData::Atom(Cow::Owned(true))), qtype::BOOL_ATOM);
+    check_to_k(
+        &KVal::Guid(KData::Atom(Cow::Owned([7; 16]))),
+        qtype::GUID_ATOM,
+    );
+    check_to_k(
+        &KVal::Short(KData::Atom(Cow::Owned(qnull_base::H))),
+        qtype::SHORT_ATOM,
+    );
+    check_to_k(&KVal::Real(KData::Atom(Cow::Owned(1.5))), qtype::REAL_ATOM);
+    check_to_k(&KVal::Char('a'), qtype::CHAR);
+    check_to_k(
+        &KVal::Symbol(KData::Atom(Cow::Owned("sym".to_string()))),
+        qtype::SYMBOL_ATOM,
+    );
+    check_to_k(
+        &KVal::Timespan(KData::Atom(Cow::Owned(1))),
+        qtype::TIMESPAN_ATOM,
+    );
+    check_to_k(&KVal::Null, qtype::NULL);
+
+    for length in [0, 1, 100_000] {
+        // Simple lists.
+        let longs = (0..length as i64).collect::<Vec<_>>();
+        check_to_k(
+            &KVal::Long(KData::List(Cow::Borrowed(&longs))),
+            qtype::LONG_LIST,
+        );
+        check_to_k(
+            &KVal::Date(KData::List(Cow::Owned(vec![qnull_base::I; length]))),
+            qtype::DATE_LIST,
+        );
+        check_to_k(
+            &KVal::Float(KData::List(Cow::Owned(vec![qnull_base::F; length]))),
+            qtype::FLOAT_LIST,
+        );
+        check_to_k(
+            &KVal::Guid(KData::List(Cow::Owned(vec![[1; 16]; length]))),
+            qtype::GUID_LIST,
+        );
+        check_to_k(
+            &KVal::String(Cow::Owned("q".repeat(length).into_bytes())),
+            qtype::STRING,
+        );
+
+        // Bool lists.
+        let bools = (0..length).map(|i| i % 3 == 0).collect::<Vec<_>>();
+        check_to_k(
+            &KVal::Bool(KData::List(Cow::Owned(bools))),
+            qtype::BOOL_LIST,
+        );
+
+        // Symbol lists.
+        let symbols = (0..length)
+            .map(|i| format!("s{}", i % 7))
+            .collect::<Vec<_>>();
+        check_to_k(
+            &KVal::Symbol(KData::List(Cow::Owned(symbols))),
+            qtype::SYMBOL_LIST,
+        );
+
+        // Enum lists.
+        check_to_k(
+            &KVal::Enum(KData::List(Cow::Borrowed(&longs)), Some("enum".to_string())),
+            qtype::ENUM_LIST,
+        );
+
+        // Compound lists.
+        let compound = (0..length)
+            .map(|i| match i % 3 {
+                0 => KVal::Long(KData::Atom(Cow::Owned(i as i64))),
+                1 => KVal::String(Cow::Owned(i.to_string().into_bytes())),
+                _ => KVal::Null,
+            })
+            .collect::<Vec<_>>();
+        check_to_k(&KVal::CompoundList(compound), qtype::COMPOUND_LIST);
+
+        // Tables and dictionaries.
+        check_to_k(&KVal::Table(trades_table(length)), qtype::TABLE);
+        check_to_k(
+            &KVal::Dictionary(KDict::new(
+                KVal::Long(KData::List(Cow::Borrowed(&longs))),
+                KVal::Long(KData::List(Cow::Borrowed(&longs))),
+            )),
+            qtype::DICTIONARY,
+        );
+    }
+
+    // An element which cannot be built frees the elements built before it.
+    let live = mock::live_objects();
+    let compound = KVal::CompoundList(vec![
+        KVal::Long(KData::List(Cow::Owned(vec![1, 2]))),
+        KVal::Char('\u{100}'),
+    ]);
+    assert!(compound.to_k().is_null());
+    assert_eq!(
+        mock::last_error().as_deref(),
+        Some("char out of byte range")
+    );
+    assert_eq!(mock::live_objects(), live);
+    let symbols = KVal::Symbol(KData::List(Cow::Owned(vec!["a\0b".to_string()])));
+    assert!(symbols.to_k().is_null());
+    assert_eq!(
+        mock::last_error().as_deref(),
+        Some("symbol contains a null character")
+    );
+    assert_eq!(mock::live_objects(), live);
+
+    // An enum is cast to its source, which it cannot be without one.
+    let enums = KVal::Enum(KData::List(Cow::Owned(vec![0, 1])), None);
+    assert!(enums.to_k().is_null());
+    assert_eq!(
+        mock::last_error().as_deref(),
+        Some("enum requires a source")
+    );
+    mock::clear_error();
+    assert!(KVal::Enum(KData::Atom(Cow::Owned(1)), None)
+        .to_k()
+        .is_null());
+    assert_eq!(
+        mock::last_error().as_deref(),
+        Some("enum requires a source")
+    );
+    assert_eq!(mock::live_objects(), live);
+    mock::clear_error();
+    mock::clear_k_handler();
+}
+
+#[test]
+fn join_ref_test() {
+    // The same borrowed value is joined to a base ten times. The owned buffer of the base is reused and `other`
+    //  keeps borrowing its data.
+    let data = [7_i64, 8];
+    let other = KVal::Long(KData::List(Cow::Borrowed(&data)));
+    let mut buffer = Vec::with_capacity(32);
+    buffer.push(0_i64);
+    let pointer = buffer.as_ptr();
+    let mut base = longs(buffer);
+    for _ in 0..10 {
+        base = KVal::join_ref(base, &other).unwrap();
+    }
+    match &base {
+        KVal::Long(KData::List(Cow::Owned(joined))) => {
+            assert_eq!(joined.as_ptr(), pointer);
+            assert_eq!(joined.len(), 21);
+        }
+        _ => panic!("must be an owned long list"),
+    }
+    assert!(
+        matches!(&other, KVal::Long(KData::List(Cow::Borrowed(list))) if list.as_ptr() == data.as_ptr())
+    );
+
+    // The result is the same as `join` for every kind of value.
+    let symbols = KVal::Symbol(KData::List(Cow::Owned(vec!["a".to_string()])));
+    let cases = vec![
+        (longs(vec![1, 2]), KVal::Long(KData::Atom(Cow::Owned(3)))),
+        (KVal::String(Cow::Borrowed(b"ab")), KVal::Char('c')),
+        (KVal::Char('a'), KVal::String(Cow::Borrowed(b"bc"))),
+        (longs(vec![1]), symbols.clone()),
+        (
+            KVal::CompoundList(vec![KVal::Null]),
+            KVal::CompoundList(vec![symbols, longs(vec![1])]),
+        ),
+        (longs(vec![1]), KVal::Null),
+        (
+            KVal::Enum(KData::List(Cow::Owned(vec![0])), Some("sym".to_string())),
+            KVal::Enum(KData::Atom(Cow::Owned(1)), Some("other".to_string())),
+        ),
+    ];
+    for (base, other) in cases {
+        assert_eq!(
+            KVal::join_ref(base.clone(), &other),
+            KVal::join(base, other.clone())
+        );
+    }
+
+    // Errors are those of `join`.
+    let table = KVal::Table(trades_table(1));
+    assert_eq!(
+        KVal::join_ref(longs(vec![1]), &table),
+        KVal::join(longs(vec![1]), table.clone())
+    );
+}
+
+#[test]
+fn distinct_test() {
+    // The first occurrences are kept in order.
+    let distinct = longs(vec![3, 1, 3, qnull_base::J, 1, qnull_base::J])
+        .distinct()
+        .unwrap();
+    assert_eq!(*distinct, longs(vec![3, 1, qnull_base::J]));
+
+    // Every float null is equal and so are the zeros.
+    let floats = KVal::Float(KData::List(Cow::Owned(vec![
+        f64::NAN,
+        0.0,
+        -f64::NAN,
+        -0.0,
+        1.5,
+    ])));
+    assert_eq!(
+        floats.distinct().unwrap().into_inner(),
+        KVal::Float(KData::List(Cow::Owned(vec![f64::NAN, 0.0, 1.5])))
+    );
+
+    // A borrowed list without a duplicate is not copied.
+    let symbols = ["a".to_string(), "b".to_string()];
+    let borrowed = KVal::Symbol(KData::List(Cow::Borrowed(&symbols)));
+    assert!(matches!(
+        borrowed.distinct().unwrap().into_inner(),
+        KVal::Symbol(KData::List(Cow::Borrowed(_)))
+    ));
+    let string = KVal::String(Cow::Borrowed(b"abca"));
+    assert_eq!(
+        string.distinct().unwrap().into_inner(),
+        KVal::String(Cow::Borrowed(b"abc"))
+    );
+    let multibyte = KVal::String(Cow::Borrowed("éè".as_bytes()));
+    let distinct = multibyte.distinct().unwrap().into_inner();
+    assert_eq!(distinct, KVal::String(Cow::Borrowed(b"\xc3\xa9\xa8")));
+    assert_eq!(distinct.len(), 3);
+
+    // A compound list is compared by `==` and an enum list by its indices.
+    let compound = KVal::CompoundList(vec![
+        longs(vec![1, 2]),
+        KVal::Char('a'),
+        longs(vec![1, 2]),
+        KVal::Float(KData::Atom(Cow::Owned(f64::NAN))),
+        KVal::Float(KData::Atom(Cow::Owned(f64::NAN))),
+    ]);
+    assert_eq!(
+        compound.distinct().unwrap().into_inner(),
+        KVal::CompoundList(vec![
+            longs(vec![1, 2]),
+            KVal::Char('a'),
+            KVal::Float(KData::Atom(Cow::Owned(f64::NAN))),
+        ])
+    );
+    let enums = KVal::Enum(
+        KData::List(Cow::Owned(vec![0, 1, 0])),
+        Some("sym".to_string()),
+    );
+    assert_eq!(
+        enums.distinct().unwrap().into_inner(),
+        KVal::Enum(KData::List(Cow::Owned(vec![0, 1])), Some("sym".to_string()))
+    );
+
+    // The unique attribute is set on a simple list only.
+    let live = mock::live_objects();
+    let k = longs(vec![2, 1, 2]).distinct().unwrap().to_k();
+    assert_eq!(k.get_attribute(), qattribute::UNIQUE);
+    decrement_reference_count(k);
+    let k = KVal::CompoundList(vec![KVal::Char('a')])
+        .distinct()
+        .unwrap()
+        .to_k();
+    assert_eq!(k.get_attribute(), qattribute::NONE);
+    decrement_reference_count(k);
+    assert_eq!(mock::live_objects(), live);
+
+    assert_eq!(
+        KVal::Long(KData::Atom(Cow::Owned(1)))
+            .distinct()
+            .unwrap_err(),
+        errors::NOT_A_LIST
+    );
+    assert_eq!(
+        KVal::Table(trades_table(1)).distinct().unwrap_err(),
+        errors::NOT_A_LIST
+    );
+}
+
+#[test]
+fn render_test() {
+    let table = trades_table(4);
+    assert_eq!(
+        table.render(3, 80),
+        "\
+sym price size
+--------------
+a   0.5   0
+b   1.5   100
+c   2.5   200
+.."
+    );
+    // The `size` column does not fit in the width.
+    assert_eq!(
+        table.render(3, 10),
+        "\
+sym pric..
+--------..
+a   0.5 ..
+b   1.5 ..
+c   2.5 ..
+.."
+    );
+    assert_eq!(table.render(4, 80), KVal::Table(table).to_string());
+
+    // The size is read from q once.
+    let live = mock::live_objects();
+    mock::set_k_handler(|query, _| {
+        assert_eq!(query, "@[system; \"c\"; 25 80i]");
+        let size = new_list(qtype::INT_LIST, 2);
+        size.as_mut_slice::<I>().copy_from_slice(&[30, 100]);
+        size
+    });
+    assert_eq!(console_size(), (30, 100));
+    mock::set_k_handler(|_, _| panic!("console size must be cached"));
+    assert_eq!(console_size(), (30, 100));
+    assert_eq!(mock::live_objects(), live);
+    mock::clear_k_handler();
+}
+
+#[test]
+fn search_test() {
+    let symbol = |symbol: &str| KVal::Symbol(KData::Atom(Cow::Owned(symbol.to_string())));
+    let indices = |list: Vec<i64>| KVal::Long(KData::List(Cow::Owned(list)));
+
+    let symbols = KVal::Symbol(KData::List(Cow::Owned(
+        ["a", "b", "a"].iter().map(|s| s.to_string()).collect(),
+    )));
+    assert_eq!(symbols.index_of(&symbol("a")), Some(0));
+    assert_eq!(symbols.find_all(&symbol("a")), indices(vec![0, 2]));
+    assert!(symbols.contains(&symbol("b")));
+    assert!(!symbols.contains(&symbol("c")));
+
+    // Nulls are found.
+    let floats = KVal::Float(KData::List(Cow::Owned(vec![f64::NAN, 1.0, -f64::NAN])));
+    let null = KVal::Float(KData::Atom(Cow::Owned(f64::NAN)));
+    assert_eq!(floats.find_all(&null), indices(vec![0, 2]));
+
+    // A char is searched by bytes, so that the index points at the byte taken by `get`.
+    let string = KVal::String(Cow::Borrowed("héllo".as_bytes()));
+    assert_eq!(string.index_of(&KVal::Char('l')), Some(3));
+    assert_eq!(string.get(3), Some(KVal::Char('l')));
+    let string = KVal::String(Cow::Borrowed("café".as_bytes()));
+    assert_eq!(string.index_of(&KVal::Char('é')), None);
+    assert_eq!(string.index_of(&KVal::Char('Ã')), Some(3));
+    assert_eq!(string.get(3), Some(KVal::Char('Ã')));
+    assert_eq!(string.find_all(&KVal::Char('\u{a9}')), indices(vec![4]));
+    assert!(!string.contains(&KVal::Char('🦀')));
+
+    // Any value is searched in a compound list.
+    let compound = KVal::CompoundList(vec![KVal::Char('a'), indices(vec![1, 2]), null.clone()]);
+    assert_eq!(compound.index_of(&indices(vec![1, 2])), Some(1));
+    assert_eq!(compound.index_of(&null), Some(2));
+
+    // Enums must share a source.
+    let enums = KVal::Enum(KData::List(Cow::Owned(vec![0, 1])), Some("sym".to_string()));
+    assert_eq!(
+        enums.index_of(&KVal::Enum(
+            KData::Atom(Cow::Owned(1)),
+            Some("sym".to_string())
+        )),
+        Some(1)
+    );
+    assert_eq!(
+        enums.index_of(&KVal::Enum(
+            KData::Atom(Cow::Owned(1)),
+            Some("other".to_string())
+        )),
+        None
+    );
+
+    // Mismatched types and non-lists find nothing.
+    assert_eq!(
+        floats.index_of(&KVal::Real(KData::Atom(Cow::Owned(1.0)))),
+        None
+    );
+    assert_eq!(symbols.index_of(&symbols), None);
+    assert_eq!(null.index_of(&null), None);
+    assert_eq!(
+        KVal::Table(trades_table(2)).find_all(&symbol("a")),
+        indices(vec![])
+    );
+}
+
+#[test]
+fn checked_narrowing_test() {
+    let live = mock::live_objects();
+    for value in [0, 255] {
+        let byte = try_new_byte(value).unwrap();
+        assert_eq!(byte.get_byte(), Ok(value as u8));
+        decrement_reference_count(byte);
+    }
+    for value in [-1, 256] {
+        assert_eq!(
+            try_new_byte(value).unwrap_err(),
+            KError::OutOfRange {
+                value: value.to_string(),
+                target: "byte atom"
+            }
+        );
+    }
+    // The null and the infinities are within the range of short.
+    for value in [
+        32767,
+        -32767,
+        qnull_base::H as I,
+        qinf_base::H as I,
+        qninf_base::H as I,
+    ] {
+        let short = try_new_short(value).unwrap();
+        assert_eq!(short.get_short(), Ok(value as i16));
+        decrement_reference_count(short);
+    }
+    for value in [32768, -32769] {
+        assert_eq!(
+            try_new_short(value).unwrap_err().to_string(),
+            format!("value {} out of range of short atom", value)
+        );
+    }
+    assert_eq!(mock::live_objects(), live);
+
+    // The wrapping constructors keep the low bits.
+    let byte = new_byte_wrapping(300);
+    assert_eq!(byte.get_byte(), Ok(44));
+    decrement_reference_count(byte);
+    let short = new_short_wrapping(32768);
+    assert_eq!(short.get_short(), Ok(qnull_base::H));
+    decrement_reference_count(short);
+
+    // KVal keeps its byte and short atoms as they are.
+    let k = KVal::Byte(KData::Atom(Cow::Owned(255))).to_k();
+    assert_eq!(k.get_byte(), Ok(255));
+    decrement_reference_count(k);
+    assert_eq!(
+        KVal::Long(KData::Atom(Cow::Owned(256))).cast(qtype::BYTE_ATOM),
+        Err(KError::OutOfRange {
+            value: "256".to_string(),
+            target: "byte atom"
+        })
+    );
+
+    // A char is a byte in q. A char within Latin-1 is kept and any other char is rejected or truncated explicitly.
+    let k = try_new_char('é').unwrap();
+    assert_eq!(k.get_char(), Ok('é'));
+    assert_eq!(KVal::from_raw(k), KVal::Char('é'));
+    decrement_reference_count(k);
+    assert_eq!(
+        try_new_char('🦀').unwrap_err(),
+        KError::OutOfRange {
+            value: "U+1F980".to_string(),
+            target: "char atom"
+        }
+    );
+    let k = new_char_truncating('🦀');
+    assert_eq!(unsafe { (*k).value.byte }, 0x80);
+    decrement_reference_count(k);
+    assert!(KVal::Char('🦀').to_k().is_null());
+    assert_eq!(
+        mock::last_error().as_deref(),
+        Some("char out of byte range")
+    );
+    mock::clear_error();
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn take_drop_test() {
+    // Within the length, the result borrows the same memory.
+    let buffer = [1_i64, 2, 3, 4];
+    let borrowed = KVal::Long(KData::List(Cow::Borrowed(&buffer)));
+    match borrowed.clone().take(-2).unwrap() {
+        KVal::Long(KData::List(Cow::Borrowed(list))) => assert_eq!(list, &buffer[2..]),
+        _ => panic!("must be a borrowed long list"),
+    }
+    match borrowed.clone().drop(1).unwrap() {
+        KVal::Long(KData::List(Cow::Borrowed(list))) => assert_eq!(list, &buffer[1..]),
+        _ => panic!("must be a borrowed long list"),
+    }
+    assert_eq!(borrowed.clone().drop(-3).unwrap(), longs(vec![1]));
+    assert_eq!(borrowed.clone().drop(10).unwrap(), longs(vec![]));
+    assert_eq!(borrowed.clone().drop(i64::MIN).unwrap(), longs(vec![]));
+    assert_eq!(borrowed.take(0).unwrap(), longs(vec![]));
+
+    // An owned list is shortened in place.
+    let mut list = Vec::with_capacity(8);
+    list.extend([1_i64, 2, 3, 4]);
+    let pointer = list.as_ptr();
+    match longs(list).drop(-1).unwrap() {
+        KVal::Long(KData::List(Cow::Owned(list))) => {
+            assert_eq!(list.as_ptr(), pointer);
+            assert_eq!(list, vec![1, 2, 3]);
+        }
+        _ => panic!("must be an owned long list"),
+    }
+
+    // Taking more than the length repeats the elements.
+    assert_eq!(
+        longs(vec![1, 2]).take(5).unwrap(),
+        longs(vec![1, 2, 1, 2, 1])
+    );
+    assert_eq!(
+        longs(vec![1, 2, 3]).take(-5).unwrap(),
+        longs(vec![2, 3, 1, 2, 3])
+    );
+    assert_eq!(
+        KVal::String(Cow::Borrowed(b"ab")).take(-3).unwrap(),
+        KVal::String(Cow::Borrowed(b"bab"))
+    );
+
+    // A string is cut by bytes.
+    let string = KVal::String(Cow::Borrowed("héllo".as_bytes()));
+    assert_eq!(
+        string.clone().take(2).unwrap(),
+        KVal::String(Cow::Borrowed(b"h\xc3"))
+    );
+    assert_eq!(string.drop(-5).unwrap(), KVal::String(Cow::Borrowed(b"h")));
+    let string = KVal::String(Cow::Borrowed("éa".as_bytes()));
+    assert_eq!(string.clone().take(2).unwrap().len(), 2);
+    assert_eq!(
+        string.clone().drop(1).unwrap(),
+        KVal::String(Cow::Borrowed(b"\xa9a"))
+    );
+    assert_eq!(
+        string.take(-4).unwrap(),
+        KVal::String(Cow::Borrowed(b"a\xc3\xa9a"))
+    );
+
+    // Compound and enum lists.
+    let compound = KVal::CompoundList(vec![KVal::Char('a'), longs(vec![1]), KVal::Null]);
+    assert_eq!(
+        compound.clone().drop(1).unwrap(),
+        KVal::CompoundList(vec![longs(vec![1]), KVal::Null])
+    );
+    assert_eq!(
+        compound.take(-4).unwrap(),
+        KVal::CompoundList(vec![
+            KVal::Null,
+            KVal::Char('a'),
+            longs(vec![1]),
+            KVal::Null
+        ])
+    );
+    let enums = KVal::Enum(KData::List(Cow::Owned(vec![0, 1])), Some("sym".to_string()));
+    assert_eq!(
+        enums.take(3).unwrap(),
+        KVal::Enum(
+            KData::List(Cow::Owned(vec![0, 1, 0])),
+            Some("sym".to_string())
+        )
+    );
+
+    // Empty lists.
+    assert_eq!(longs(vec![]).take(0).unwrap(), longs(vec![]));
+    assert_eq!(longs(vec![]).drop(-1).unwrap(), longs(vec![]));
+    assert_eq!(longs(vec![]).take(-1).unwrap_err(), errors::EMPTY_LIST);
+    assert_eq!(
+        KVal::CompoundList(vec![]).take(2).unwrap_err(),
+        errors::EMPTY_LIST
+    );
+
+    // Errors
+    assert_eq!(
+        KVal::Long(KData::Atom(Cow::Owned(1))).take(1).unwrap_err(),
+        errors::NOT_A_LIST
+    );
+    assert_eq!(KVal::Char('a').drop(1).unwrap_err(), errors::NOT_A_LIST);
+    assert_eq!(
+        KVal::Table(trades_table(2)).take(1).unwrap_err(),
+        errors::NOT_A_LIST
+    );
+}
+
+#[test]
+fn split_chunks_test() {
+    // Chunks of a borrowed list borrow the same memory.
+    let buffer = (0_i64..10).collect::<Vec<_>>();
+    let borrowed = KVal::Long(KData::List(Cow::Borrowed(&buffer)));
+    let chunks = borrowed.chunks(4).unwrap().collect::<Vec<_>>();
+    assert_eq!(chunks.len(), 3);
+    for (chunk, expected) in chunks.iter().zip(buffer.chunks(4)) {
+        match chunk {
+            KVal::Long(KData::List(Cow::Borrowed(list))) => {
+                assert_eq!(list.as_ptr(), expected.as_ptr());
+                assert_eq!(list.len(), expected.len());
+            }
+            _ => panic!("must be a borrowed long list"),
+        }
+    }
+    assert_eq!(longs(vec![]).chunks(3).unwrap().count(), 0);
+
+    // Halves of a borrowed list borrow the same memory.
+    match borrowed.split_at(7).unwrap() {
+        (
+            KVal::Long(KData::List(Cow::Borrowed(head))),
+            KVal::Long(KData::List(Cow::Borrowed(tail))),
+        ) => {
+            assert_eq!(head, &buffer[..7]);
+            assert_eq!(tail.as_ptr(), buffer[7..].as_ptr());
+        }
+        _ => panic!("must be borrowed long lists"),
+    }
+    // An owned list keeps the head in place.
+    let list = (0_i64..4).collect::<Vec<_>>();
+    let pointer = list.as_ptr();
+    match longs(list).split_at(1).unwrap() {
+        (KVal::Long(KData::List(Cow::Owned(head))), tail) => {
+            assert_eq!(head.as_ptr(), pointer);
+            assert_eq!(head, vec![0]);
+            assert_eq!(tail, longs(vec![1, 2, 3]));
+        }
+        _ => panic!("must be an owned long list"),
+    }
+    assert_eq!(
+        longs(vec![1, 2]).split_at(2).unwrap(),
+        (longs(vec![1, 2]), longs(vec![]))
+    );
+
+    // A string is split and chunked by bytes.
+    let string = KVal::String(Cow::Borrowed("héllo".as_bytes()));
+    assert_eq!(
+        string.chunks(2).unwrap().collect::<Vec<_>>(),
+        vec![
+            KVal::String(Cow::Borrowed(b"h\xc3")),
+            KVal::String(Cow::Borrowed(b"\xa9l")),
+            KVal::String(Cow::Borrowed(b"lo"))
+        ]
+    );
+    let multibyte = KVal::String(Cow::Borrowed("éa".as_bytes()));
+    assert_eq!(multibyte.clone().chunks(1).unwrap().count(), 3);
+    assert_eq!(
+        multibyte.split_at(1).unwrap(),
+        (
+            KVal::String(Cow::Borrowed(b"\xc3")),
+            KVal::String(Cow::Borrowed(b"\xa9a"))
+        )
+    );
+    assert_eq!(
+        KVal::String(Cow::Owned("héllo".to_string().into_bytes()))
+            .split_at(6)
+            .unwrap(),
+        (string, KVal::String(Cow::Borrowed(b"")))
+    );
+
+    // Compound and enum lists.
+    let compound = KVal::CompoundList(vec![KVal::Char('a'), longs(vec![1]), KVal::Null]);
+    assert_eq!(
+        compound.chunks(2).unwrap().collect::<Vec<_>>(),
+        vec![
+            KVal::CompoundList(vec![KVal::Char('a'), longs(vec![1])]),
+            KVal::CompoundList(vec![KVal::Null])
+        ]
+    );
+    assert_eq!(
+        compound.clone().split_at(0).unwrap(),
+        (KVal::CompoundList(vec![]), compound)
+    );
+    let source = Some("sym".to_string());
+    let enums = KVal::Enum(KData::List(Cow::Owned(vec![0, 1, 2])), source.clone());
+    assert_eq!(
+        enums.chunks(2).unwrap().collect::<Vec<_>>(),
+        vec![
+            KVal::Enum(KData::List(Cow::Owned(vec![0, 1])), source.clone()),
+            KVal::Enum(KData::List(Cow::Owned(vec![2])), source.clone())
+        ]
+    );
+    assert_eq!(
+        enums.split_at(1).unwrap(),
+        (
+            KVal::Enum(KData::List(Cow::Owned(vec![0])), source.clone()),
+            KVal::Enum(KData::List(Cow::Owned(vec![1, 2])), source)
+        )
+    );
+
+    // Errors
+    assert_eq!(
+        longs(vec![1, 2]).split_at(3).unwrap_err(),
+        errors::INDEX_OUT_OF_BOUNDS
+    );
+    assert_eq!(
+        KVal::String(Cow::Borrowed(b"ab")).split_at(3).unwrap_err(),
+        errors::INDEX_OUT_OF_BOUNDS
+    );
+    assert_eq!(
+        KVal::Long(KData::Atom(Cow::Owned(1)))
+            .split_at(0)
+            .unwrap_err(),
+        errors::NOT_A_LIST
+    );
+    assert!(KVal::Char('a').chunks(1).is_err());
+    assert!(KVal::Table(trades_table(2)).chunks(1).is_err());
+    assert_eq!(
+        longs(vec![1]).chunks(0).err(),
+        Some("chunk size must be positive\0")
+    );
+}
+
+#[test]
+fn from_row_iter_test() {
+    let schema = [("sym", qtype::SYMBOL_LIST), ("size", qtype::LONG_LIST)];
+    let row = |index: usize| -> RowValues {
+        vec![
+            KVal::Symbol(KData::Atom(Cow::Owned(
+                ["a", "b", "c"][index % 3].to_string(),
+            ))),
+            KVal::Long(KData::Atom(Cow::Owned(index as i64))),
+        ]
+    };
+    let stream = |len: usize, failure: Option<usize>| {
+        (0..len).map(move |index| match failure {
+            Some(failure) if failure == index => Err(format!("bad line {}", index + 1)),
+            _ => Ok(row(index)),
+        })
+    };
+
+    // Chunks have at most `chunk_rows` rows and together hold every row.
+    let mut total = 0;
+    for chunk in KTable::row_chunks(&schema, stream(1_000_000, None), 65_536).unwrap() {
+        let chunk = chunk.unwrap();
+        assert!(chunk.len() <= 65_536);
+        match chunk.dict.values.as_ref() {
+            KVal::CompoundList(columns) => match &columns[1] {
+                KVal::Long(KData::List(sizes)) => {
+                    assert_eq!(sizes.first(), Some(&(total as i64)));
+                }
+                _ => panic!("size must be a long list"),
+            },
+            _ => panic!("columns must be a compound list"),
+        }
+        total += chunk.len();
+    }
+    assert_eq!(total, 1_000_000);
+
+    let table = KTable::from_row_iter(&schema, stream(1_000_000, None), 65_536).unwrap();
+    assert_eq!(table.len(), 1_000_000);
+    assert_eq!(table.deep_clone().dict.keys, table.dict.keys);
+    match table.dict.values.as_ref() {
+        KVal::CompoundList(columns) => {
+            assert_eq!(
+                columns[0].get(4),
+                Some(KVal::Symbol(KData::Atom(Cow::Owned("b".to_string()))))
+            );
+            assert_eq!(
+                columns[1].get(999_999),
+                Some(KVal::Long(KData::Atom(Cow::Owned(999_999))))
+            );
+        }
+        _ => panic!("columns must be a compound list"),
+    }
+
+    // An error of the stream aborts with the index of the row. Chunks before the error are yielded.
+    let error =
+        KTable::from_row_iter(&schema, stream(1_000_000, Some(500_000)), 65_536).unwrap_err();
+    assert_eq!(
+        error,
+        KError::InvalidRow {
+            row: 500_000,
+            message: "bad line 500001".to_string()
+        }
+    );
+    let chunks = KTable::row_chunks(&schema, stream(1_000_000, Some(500_000)), 65_536)
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(chunks.len(), 500_000 / 65_536 + 1);
+    assert!(chunks[..chunks.len() - 1].iter().all(|chunk| chunk.is_ok()));
+    assert!(chunks.last().unwrap().is_err());
+
+    // An empty stream builds an empty table of the schema.
+    let empty = KTable::from_row_iter(&schema, stream(0, None), 10).unwrap();
+    assert!(empty.is_empty());
+    assert_eq!(
+        *empty.dict.values,
+        KVal::CompoundList(vec![
+            KVal::Symbol(KData::List(Cow::Owned(vec![]))),
+            KVal::Long(KData::List(Cow::Owned(vec![])))
+        ])
+    );
+
+    // Rows which do not match the schema.
+    let rows = vec![
+        Ok::<RowValues, String>(row(0)),
+        Ok(vec![
+            KVal::Char('a'),
+            KVal::Long(KData::Atom(Cow::Owned(1))),
+        ]),
+    ];
+    assert_eq!(
+        KTable::from_row_iter(&schema, rows, 0).unwrap_err(),
+        KError::InvalidRow {
+            row: 1,
+            message: "char in column sym of symbol list".to_string()
+        }
+    );
+    let rows = vec![Ok::<RowValues, String>(vec![KVal::Char('a')])];
+    assert_eq!(
+        KTable::from_row_iter(&schema, rows, 1)
+            .unwrap_err()
+            .to_string(),
+        "invalid row 0: 1 values for 2 columns"
+    );
+    assert!(matches!(
+        KTable::row_chunks(&[("e", qtype::ENUM_LIST)], stream(1, None), 1),
+        Err(KError::InvalidOperation { .. })
+    ));
+}
+
+#[test]
+fn concat_test() {
+    let fold = |parts: Vec<KVal<'static>>| {
+        parts
+            .into_iter()
+            .reduce(|joined, part| KVal::join(joined, part).unwrap())
+            .unwrap()
+    };
+
+    // Many parts give the same result as repeated joins.
+    let parts = (0..1_000)
+        .map(|i| longs((i..i + 100).collect()))
+        .collect::<Vec<_>>();
+    let concatenated = KVal::concat(parts.clone()).unwrap();
+    assert_eq!(concatenated, fold(parts));
+    assert_eq!(concatenated.len(), 100_000);
+
+    // The buffer of an owned first part is extended in place.
+    let mut first = Vec::with_capacity(8);
+    first.extend([1_i64, 2]);
+    let pointer = first.as_ptr();
+    match KVal::concat(vec![
+        longs(first),
+        longs(vec![3, 4]),
+        KVal::Long(KData::Atom(Cow::Owned(5))),
+    ])
+    .unwrap()
+    {
+        KVal::Long(KData::List(Cow::Owned(list))) => {
+            assert_eq!(list.as_ptr(), pointer);
+            assert_eq!(list, vec![1, 2, 3, 4, 5]);
+        }
+        _ => panic!("must be an owned long list"),
+    }
+
+    // Chars are razed into a string one byte each.
+    assert_eq!(
+        KVal::concat(vec![
+            KVal::Char('é'),
+            KVal::String(Cow::Borrowed(b"ab")),
+            KVal::Char('\u{ff}')
+        ])
+        .unwrap(),
+        KVal::String(Cow::Borrowed(b"\xe9ab\xff"))
+    );
+    assert_eq!(
+        KVal::concat(vec![KVal::String(Cow::Borrowed(b"ab")), KVal::Char('€')]).unwrap_err(),
+        errors::CHAR_OUT_OF_RANGE
+    );
+
+    // Same results as join for other combinations.
+    let buffer = [1_i64, 2];
+    let cases: Vec<Vec<KVal<'static>>> = vec![
+        vec![
+            KVal::String(Cow::Borrowed(b"ab")),
+            KVal::Char('c'),
+            KVal::String(Cow::Owned("de".to_string().into_bytes())),
+        ],
+        vec![KVal::Char('a'), KVal::Char('b')],
+        vec![
+            longs(vec![1, 2]),
+            KVal::Symbol(KData::Atom(Cow::Owned("a".to_string()))),
+            KVal::String(Cow::Borrowed(b"bc")),
+        ],
+        vec![
+            KVal::CompoundList(vec![KVal::Char('a'), KVal::Null]),
+            longs(vec![1]),
+        ],
+        vec![KVal::Null, longs(vec![1, 2])],
+        vec![
+            KVal::Enum(KData::List(Cow::Owned(vec![0, 1])), Some("sym".to_string())),
+            KVal::Enum(KData::Atom(Cow::Owned(2)), Some("sym".to_string())),
+        ],
+        vec![
+            KVal::Enum(KData::List(Cow::Owned(vec![0, 1])), Some("sym".to_string())),
+            KVal::Enum(KData::Atom(Cow::Owned(2)), Some("other".to_string())),
+        ],
+    ];
+    for parts in cases {
+        assert_eq!(KVal::concat(parts.clone()).unwrap(), fold(parts));
+    }
+    let borrowed = KVal::Long(KData::List(Cow::Borrowed(&buffer)));
+    assert_eq!(
+        KVal::concat(vec![borrowed.clone(), borrowed]).unwrap(),
+        longs(vec![1, 2, 1, 2])
+    );
+    assert_eq!(
+        KVal::concat(vec![KVal::Date(KData::Atom(Cow::Owned(1)))]).unwrap(),
+        KVal::Date(KData::List(Cow::Owned(vec![1])))
+    );
+    assert_eq!(KVal::concat(vec![]).unwrap(), KVal::CompoundList(vec![]));
+
+    // Errors
+    assert_eq!(
+        KVal::concat(vec![longs(vec![1]), KVal::Table(trades_table(2))]).unwrap_err(),
+        errors::TYPE
+    );
+    assert_eq!(
+        KVal::concat(vec![KVal::Error(Cow::Borrowed("type"))]).unwrap_err(),
+        errors::TYPE
+    );
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn symbols_to_arrow_test() {
+    use arrow_array::builder::StringDictionaryBuilder;
+    use arrow_array::types::Int32Type;
+    use arrow_array::{Array, StringArray};
+
+    let symbols = (0..10_000)
+        .map(|row| match row % 13 {
+            0 => String::new(),
+            index => format!("sym{}", index * 7 % 5 + row % 3),
+        })
+        .chain(["ünïcode".to_string()])
+        .collect::<Vec<_>>();
+    let list = KVal::Symbol(KData::List(Cow::Owned(symbols.clone()))).to_k();
+    let direct = symbols_to_arrow(list).unwrap();
+    decrement_reference_count(list);
+
+    // Same as dictionary-encoding the decoded strings.
+    let mut builder = StringDictionaryBuilder::<Int32Type>::new();
+    for symbol in &symbols {
+        match symbol.as_str() {
+            "" => builder.append_null(),
+            symbol => {
+                builder.append_value(symbol);
+            }
+        }
+    }
+    let naive = builder.finish();
+    assert_eq!(direct.keys(), naive.keys());
+    let values = |array: &arrow_array::DictionaryArray<Int32Type>| {
+        array
+            .values()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(values(&direct), values(&naive));
+    assert_eq!(direct.len(), 10_001);
+    assert_eq!(direct.null_count(), 770);
+    assert_eq!(direct.values().len(), 8);
+    assert_eq!(values(&direct).value(7), "ünïcode");
+
+    // Empty list and wrong type.
+    let empty = KVal::Symbol(KData::List(Cow::Owned(vec![]))).to_k();
+    assert!(symbols_to_arrow(empty).unwrap().is_empty());
+    decrement_reference_count(empty);
+    let longs = KVal::Long(KData::List(Cow::Owned(vec![1, 2]))).to_k();
+    assert_eq!(
+        symbols_to_arrow(longs).unwrap_err().to_string(),
+        "Invalid argument error: expected symbol list, got long list"
+    );
+    decrement_reference_count(longs);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn chained_error_test() {
+    // Wrapping keeps the class of a q error.
+    let inner = KError::Q {
+        kind: QErrorKind::Mismatch,
+        message: "mismatch".to_string(),
+    };
+    let wrapped = KError::wrap("insert trades", inner);
+    assert_eq!(wrapped.to_string(), "insert trades: mismatch");
+    assert_eq!(wrapped.kind(), Some(QErrorKind::Mismatch));
+    assert!(wrapped.to_k().is_null());
+    assert_eq!(
+        mock::last_error().as_deref(),
+        Some("insert trades: mismatch")
+    );
+
+    // An error raised in Rust is classified as `Other`.
+    let wrapped = KError::wrap(
+        "cast price",
+        KError::InvalidCast {
+            from: "symbol list",
+            to: "long list",
+        },
+    );
+    assert_eq!(wrapped.kind(), Some(QErrorKind::Other));
+    assert_eq!(
+        wrapped.to_string(),
+        "cast price: invalid cast from symbol list to long list"
+    );
+
+    // Same text as `KError::wrap` without building `KError`.
+    assert!(new_error_chained("parse", "length").is_null());
+    assert_eq!(mock::last_error().as_deref(), Some("parse: length"));
+    assert!(new_error_errno("open trades.csv").is_null());
+    assert_eq!(mock::last_error().as_deref(), Some("open trades.csv"));
+    mock::clear_error();
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn tracked_to_k_test() {
+    let list = new_list(qtype::LONG_LIST, 3);
+    list.as_mut_slice::<J>().copy_from_slice(&[1, 2, 3]);
+
+    // Unmodified value passes the object back.
+    let tracked = KVal::from_raw_tracked(list);
+    assert!(tracked.is_unmodified());
+    let passed = tracked.to_k();
+    assert_eq!(passed, list);
+    assert_eq!(list.get_refcount(), 1);
+    decrement_reference_count(passed);
+
+    // A modified value builds a new object.
+    let mut tracked = KVal::from_raw_tracked(list);
+    tracked
+        .push(KVal::Long(KData::Atom(Cow::Owned(4))))
+        .unwrap();
+    assert!(!tracked.is_unmodified());
+    let built = tracked.to_k();
+    assert_ne!(built, list);
+    assert_eq!(built.as_mut_slice::<J>(), &[1, 2, 3, 4]);
+    assert_eq!(list.get_refcount(), 0);
+    decrement_reference_count(built);
+
+    // A symbol list is decoded on conversion, so it is always rebuilt.
+    let mut symbols = new_list(qtype::SYMBOL_LIST, 0);
+    symbols.push_symbol("a").unwrap();
+    let built = KVal::from_raw_tracked(symbols).to_k();
+    assert_ne!(built, symbols);
+    decrement_reference_count(built);
+    decrement_reference_count(symbols);
+
+    decrement_reference_count(list);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn symbol_list_to_k_test() {
+    let names = (0..100)
+        .map(|i| format!("leak_check_{}", i))
+        .collect::<Vec<_>>();
+    let symbols = KVal::Symbol(KData::List(Cow::Borrowed(&names)));
+
+    // Each element is the interned symbol itself, so repeated calls neither copy the symbols nor leave objects.
+    let first = symbols.to_k();
+    let interned = first.as_mut_slice::<S>().to_vec();
+    for _ in 0..1000 {
+        let k = symbols.to_k();
+        assert_eq!(k.as_mut_slice::<S>(), interned.as_slice());
+        decrement_reference_count(k);
+    }
+    for (symbol, name) in interned.iter().zip(&names) {
+        assert_eq!(S_to_str(*symbol), name);
+    }
+    decrement_reference_count(first);
+    assert_eq!(mock::live_objects(), 0);
+
+    // A null character is a q error rather than a panic.
+    let names = vec!["a".to_string(), "b\0c".to_string()];
+    assert!(KVal::Symbol(KData::List(Cow::Owned(names)))
+        .to_k()
+        .is_null());
+    assert_eq!(
+        mock::last_error().as_deref(),
+        Some("symbol contains a null character")
+    );
+    mock::clear_error();
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn bool_list_bytes_test() {
+    // q stores a bool as a byte. A byte other than 0 or 1 must not be reinterpreted as a Rust bool.
+    let list = new_list(qtype::BOOL_LIST, 4);
+    list.as_mut_slice::<G>().copy_from_slice(&[0, 1, 2, 255]);
+    let bools = KVal::from_raw(list);
+    assert_eq!(
+        bools,
+        KVal::Bool(KData::List(Cow::Owned(vec![false, true, true, true])))
+    );
+
+    // Bytes written back are 0 or 1.
+    let built = bools.to_k();
+    assert_eq!(built.get_type(), qtype::BOOL_LIST);
+    assert_eq!(built.as_mut_slice::<G>(), &[0, 1, 1, 1]);
+    decrement_reference_count(built);
+
+    let atom = new_bool(0);
+    unsafe { (*atom).value.byte = 7 };
+    assert_eq!(
+        KVal::from_raw(atom),
+        KVal::Bool(KData::Atom(Cow::Owned(true)))
+    );
+
+    decrement_reference_count(atom);
+    decrement_reference_count(list);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn error_from_raw_test() {
+    mock::set_k_handler(|query, _| match query {
+        "1+`a" => new_error("type\0"),
+        _ => unsafe { native::ee(new_error("unexpected query\0")) },
+    });
+
+    // The message of an error caught from q is held as a symbol.
+    let caught = error_to_string(unsafe { native::k(0, str_to_S!("1+`a"), KNULL) });
+    assert_eq!(caught.get_type(), qtype::ERROR);
+    assert_eq!(KVal::from_raw(caught), KVal::Error(Cow::Borrowed("type")));
+    decrement_reference_count(caught);
+
+    // `KNULL` caught as an error has no message.
+    let caught = error_to_string(KNULL);
+    assert_eq!(caught.get_type(), qtype::ERROR);
+    assert_eq!(KVal::from_raw(caught), KVal::Null);
+    decrement_reference_count(caught);
+
+    mock::clear_k_handler();
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn non_utf8_string_test() {
+    let bytes = [0xff_u8, 0, b'b', b'i', b'n'];
+    let string = new_list(qtype::STRING, bytes.len() as J);
+    string.as_mut_slice::<G>().copy_from_slice(&bytes);
+
+    // The bytes are kept as they are and each of them is an element.
+    let value = KVal::from_raw(string);
+    assert_eq!(value, KVal::String(Cow::Borrowed(&bytes)));
+    assert_eq!(value.len(), bytes.len());
+    assert_eq!(value.get(0), Some(KVal::Char('\u{ff}')));
+    assert_eq!(value.to_string(), "\"\\377\\000bin\"");
+    assert_eq!(string.get_str(), Err("string is not valid UTF-8\0"));
+    assert_eq!(String::try_from(value), Err(errors::INVALID_UTF8));
+
+    // The original bytes are passed back while the string is unmodified.
+    let mut tracked = KVal::from_raw_tracked(string);
+    assert!(tracked.is_unmodified());
+    let passed = tracked.to_k();
+    assert_eq!(passed, string);
+    decrement_reference_count(passed);
+    tracked.push(KVal::Char('!')).unwrap();
+    let built = tracked.to_k();
+    assert_eq!(built.as_mut_slice::<G>(), b"\xff\0bin!");
+    decrement_reference_count(built);
+
+    // An unmodified string is committed as it is.
+    let tracked = KVal::from_raw_tracked(string);
+    assert_eq!(tracked.commit(string), Ok(()));
+    assert_eq!(string.as_mut_slice::<G>(), bytes);
+
+    // A table with a char column of non UTF-8 bytes is read without panicking.
+    let table =
+        KTable::from_columns(vec![("note", KVal::String(Cow::Borrowed(b"\xe9a")))]).unwrap();
+    let k_table = table.to_k();
+    let read = KTable::from_raw(k_table);
+    assert_eq!(read.len(), 2);
+    assert_eq!(
+        read.get_column("note"),
+        Some(KVal::String(Cow::Borrowed(b"\xe9a")))
+    );
+    assert_eq!(read.get_cell(0, "note", None), Ok(KVal::Char('\u{e9}')));
+    decrement_reference_count(k_table);
+
+    decrement_reference_count(string);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+/// Build a table with a column of each type. The second row is null where the type has a null.
+#[cfg(feature = "serde")]
+fn all_types_table() -> KTable<'static> {
+    macro_rules! column {
+        ($variant: path, $values: expr) => {
+            $variant(KData::List(Cow::Owned($values.to_vec())))
+        };
+    }
+    let names = [
+        "bool",
+        "guid",
+        "byte",
+        "short",
+        "int",
+        "long",
+        "real",
+        "float",
+        "string",
+        "symbol",
+        "timestamp",
+        "month",
+        "date",
+        "datetime",
+        "timespan",
+        "minute",
+        "second",
+        "time",
+        "enum",
+    ];
+    let guid = [
+        0x8c, 0x68, 0x0a, 0x01, 0x5a, 0x49, 0x5a, 0xab, 0x5a, 0x65, 0xd4, 0xbf, 0xdd, 0xb6, 0xa6,
+        0x61,
+    ];
+    KTable::new(KDict::new(
+        KVal::Symbol(KData::List(Cow::Owned(
+            names.iter().map(|name| name.to_string()).collect(),
+        ))),
+        KVal::CompoundList(vec![
+            column!(KVal::Bool, [true, false]),
+            column!(KVal::Guid, [guid, qnull_base::U]),
+            column!(KVal::Byte, [0x2a_u8, 0]),
+            column!(KVal::Short, [-7_i16, qnull_base::H]),
+            column!(KVal::Int, [86400_i32, qnull_base::I]),
+            column!(KVal::Long, [1_i64 << 40, qnull_base::J]),
+            column!(KVal::Real, [1.5_f32, qnull_base::E]),
+            column!(KVal::Float, [-0.25_f64, qnull_base::F]),
+            KVal::CompoundList(vec![
+                KVal::String(Cow::Borrowed("héllo".as_bytes())),
+                KVal::String(Cow::Borrowed(b"")),
+            ]),
+            column!(KVal::Symbol, ["kdb".to_string(), String::new()]),
+            column!(KVal::Timestamp, [46_800_000_000_123_i64, qnull_base::J]),
+            column!(KVal::Month, [-1_i32, qnull_base::I]),
+            column!(KVal::Date, [7_i32, qnull_base::I]),
+            column!(KVal::Datetime, [1.5_f64, qnull_base::F]),
+            column!(KVal::Timespan, [-90_061_000_000_001_i64, qnull_base::J]),
+            column!(KVal::Minute, [61_i32, qnull_base::I]),
+            column!(KVal::Second, [3661_i32, qnull_base::I]),
+            column!(KVal::Time, [3_661_001_i32, qnull_base::I]),
+            KVal::Enum(
+                KData::List(Cow::Owned(vec![2, qnull_base::J])),
+                Some("sym".to_string()),
+            ),
+        ]),
+    ))
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_golden_test() {
+    let table = KVal::Table(all_types_table());
+    let raw = serde_json::to_string_pretty(&table).unwrap();
+    assert_eq!(raw, include_str!("golden/serialize_raw.json").trim_end());
+    let iso = serde_json::to_string_pretty(&table.serialize_with(TemporalFormat::Iso)).unwrap();
+    assert_eq!(iso, include_str!("golden/serialize_iso.json").trim_end());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_test() {
+    let json = |value: &KVal| serde_json::to_string(value).unwrap();
+
+    // Dictionaries are maps and a keyed table is a sequence of rows holding the key columns.
+    let dictionary = KVal::Dictionary(KDict::new(
+        KVal::Symbol(KData::List(Cow::Owned(vec![
+            "a".to_string(),
+            "b".to_string(),
+        ]))),
+        KVal::CompoundList(vec![
+            KVal::Long(KData::Atom(Cow::Owned(1))),
+            KVal::String(Cow::Borrowed(b"text")),
+        ]),
+    ));
+    assert_eq!(json(&dictionary), r#"{"a":1,"b":"text"}"#);
+    let keyed = KVal::Dictionary(keyed_trades(2));
+    assert_eq!(
+        json(&keyed),
+        r#"[{"id":0,"sym":"a","price":0.5,"size":0},{"id":1,"sym":"b","price":1.5,"size":100}]"#
+    );
+
+    // Atoms, nulls and errors.
+    assert_eq!(json(&KVal::Char('q')), r#""q""#);
+    assert_eq!(json(&KVal::Char(' ')), "null");
+    assert_eq!(
+        json(&KVal::Timestamp(KData::Atom(Cow::Owned(qnull_base::J)))),
+        "null"
+    );
+    assert_eq!(json(&KVal::Null), "null");
+    assert_eq!(
+        json(&KVal::Error(Cow::Borrowed("type"))),
+        r#"{"error":"type"}"#
+    );
+
+    // Infinities have no ISO 8601 representation.
+    let dates = KVal::Date(KData::List(Cow::Owned(vec![qinf_base::I, qninf_base::I])));
+    assert_eq!(
+        serde_json::to_string(&dates.serialize_with(TemporalFormat::Iso)).unwrap(),
+        r#"["0W","-0W"]"#
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn from_json_test() {
+    let float = |value: f64| KVal::Float(KData::Atom(Cow::Owned(value)));
+
+    // Atoms and simple lists.
+    assert_eq!(KVal::from_json("5001").unwrap(), long(5001));
+    assert_eq!(KVal::from_json("0.5").unwrap(), float(0.5));
+    assert_eq!(
+        KVal::from_json("18446744073709551615").unwrap(),
+        float(u64::MAX as f64)
+    );
+    assert_eq!(KVal::from_json("null").unwrap(), KVal::Null);
+    assert_eq!(
+        KVal::from_json(r#""text""#).unwrap(),
+        KVal::String(Cow::Borrowed(b"text"))
+    );
+    assert_eq!(
+        KVal::from_json("[true, false]").unwrap(),
+        KVal::Bool(KData::List(Cow::Owned(vec![true, false])))
+    );
+    assert_eq!(
+        KVal::from_json("[1, 2]").unwrap(),
+        KVal::Long(KData::List(Cow::Owned(vec![1, 2])))
+    );
+
+    // Integers among floats and nulls among numbers are floats.
+    assert_eq!(
+        KVal::from_json("[1, 0.5]").unwrap(),
+        KVal::Float(KData::List(Cow::Owned(vec![1.0, 0.5])))
+    );
+    match KVal::from_json("[1, null]").unwrap() {
+        KVal::Float(KData::List(list)) => {
+            assert_eq!(list[0], 1.0);
+            assert!(list[1].is_nan());
+        }
+        value => panic!("expected float list: {:?}", value),
+    }
+
+    // Mixed and empty arrays are compound lists.
+    assert_eq!(
+        KVal::from_json(r#"[1, "a"]"#).unwrap(),
+        KVal::CompoundList(vec![long(1), KVal::String(Cow::Borrowed(b"a"))])
+    );
+    assert_eq!(KVal::from_json("[]").unwrap(), KVal::CompoundList(vec![]));
+    assert_eq!(
+        KVal::from_json("[null, null]").unwrap(),
+        KVal::CompoundList(vec![KVal::Null, KVal::Null])
+    );
+
+    // Objects are dictionaries and an array of objects with the same keys is a table.
+    assert_eq!(
+        KVal::from_json(r#"{"port": 5001, "weights": [1, 0.5]}"#).unwrap(),
+        KVal::Dictionary(KDict::new(
+            KVal::Symbol(KData::List(Cow::Owned(vec![
+                "port".to_string(),
+                "weights".to_string()
+            ]))),
+            KVal::CompoundList(vec![
+                long(5001),
+                KVal::Float(KData::List(Cow::Owned(vec![1.0, 0.5])))
+            ])
+        ))
+    );
+    let rows =
+        r#"[{"sym": "a", "price": 0.5, "size": 0}, {"sym": "b", "price": 1.5, "size": 100}]"#;
+    assert_eq!(
+        KVal::from_json_with(rows, StringFormat::Symbol).unwrap(),
+        KVal::Table(trades_table(2))
+    );
+    assert!(matches!(
+        KVal::from_json(r#"[{"a": 1}, {"b": 2}]"#).unwrap(),
+        KVal::CompoundList(_)
+    ));
+
+    // Round trip of serialization.
+    let table = KVal::Table(trades_table(2));
+    assert_eq!(
+        KVal::from_json_with(
+            &serde_json::to_string(&table).unwrap(),
+            StringFormat::Symbol
+        )
+        .unwrap(),
+        table
+    );
+
+    // Null symbols are filled among symbols.
+    assert_eq!(
+        KVal::from_json_with(r#"["a", null]"#, StringFormat::Symbol).unwrap(),
+        KVal::Symbol(KData::List(Cow::Owned(vec![
+            "a".to_string(),
+            String::new()
+        ])))
+    );
+
+    // Invalid documents.
+    assert_eq!(
+        KVal::from_json(r#"{"port": }"#).unwrap_err(),
+        "expected value at line 1 column 10"
+    );
+    assert_eq!(
+        KVal::from_json("1 2").unwrap_err(),
+        "trailing characters at line 1 column 3"
+    );
+}
+
+#[test]
+fn kval_macro_test() {
+    let symbol = |value: &str| KVal::Symbol(KData::Atom(Cow::Owned(value.to_string())));
+
+    // Atoms follow the suffixes and an integer without a suffix is a long.
+    assert_eq!(kval!(1), KVal::Long(KData::Atom(Cow::Owned(1))));
+    assert_eq!(kval!(-1i32), KVal::Int(KData::Atom(Cow::Owned(-1))));
+    assert_eq!(kval!(0x10u8), KVal::Byte(KData::Atom(Cow::Owned(16))));
+    assert_eq!(kval!(2.5f32), KVal::Real(KData::Atom(Cow::Owned(2.5))));
+    assert_eq!(kval!(true), KVal::Bool(KData::Atom(Cow::Owned(true))));
+    assert_eq!(kval!('q'), KVal::Char('q'));
+    assert_eq!(kval!("sym"), symbol("sym"));
+
+    // Lists of atoms of the same type are simple lists.
+    assert_eq!(
+        kval!([1i64, 2, 3]),
+        KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3])))
+    );
+    assert_eq!(kval!(['a', 'b']), KVal::String(Cow::Borrowed(b"ab")));
+    assert_eq!(
+        kval!([-1i16, -2i16,]),
+        KVal::Short(KData::List(Cow::Owned(vec![-1, -2])))
+    );
+    assert_eq!(
+        kval!([1i32, 2]),
+        KVal::CompoundList(vec![
+            KVal::Int(KData::Atom(Cow::Owned(1))),
+            KVal::Long(KData::Atom(Cow::Owned(2)))
+        ])
+    );
+    assert_eq!(kval!([]), KVal::CompoundList(vec![]));
+
+    // Nested values and expressions.
+    let name = "nested";
+    let text = KVal::String(Cow::Borrowed(b"text"));
+    assert_eq!(
+        kval!([[1, 2], -3, {"a" => name}, (text.clone())]),
+        KVal::CompoundList(vec![
+            KVal::Long(KData::List(Cow::Owned(vec![1, 2]))),
+            KVal::Long(KData::Atom(Cow::Owned(-3))),
+            KVal::Dictionary(KDict::new(
+                KVal::Symbol(KData::List(Cow::Owned(vec!["a".to_string()]))),
+                KVal::Symbol(KData::List(Cow::Owned(vec!["nested".to_string()])))
+            )),
+            text
+        ])
+    );
+
+    // Values of a dictionary are collapsed in the same manner as a list.
+    assert_eq!(
+        kval!({"a" => 1, "b" => 2}),
+        KVal::Dictionary(KDict::new(
+            KVal::Symbol(KData::List(Cow::Owned(vec![
+                "a".to_string(),
+                "b".to_string()
+            ]))),
+            KVal::Long(KData::List(Cow::Owned(vec![1, 2])))
+        ))
+    );
+    assert_eq!(
+        kval!({"a" => [1, 2], "b" => -1i32, "c" => "x",}),
+        KVal::Dictionary(KDict::new(
+            KVal::Symbol(KData::List(Cow::Owned(vec![
+                "a".to_string(),
+                "b".to_string(),
+                "c".to_string()
+            ]))),
+            KVal::CompoundList(vec![
+                KVal::Long(KData::List(Cow::Owned(vec![1, 2]))),
+                KVal::Int(KData::Atom(Cow::Owned(-1))),
+                symbol("x")
+            ])
+        ))
+    );
+}
+
+#[test]
+fn to_q_literal_test() {
+    let literal = |value: KVal| value.to_q_literal();
+
+    // Atoms and simple lists are exact.
+    assert_eq!(literal(kval!(0.1)), "0.1");
+    assert_eq!(literal(kval!([1.0, 2.0])), "1 2f");
+    assert_eq!(
+        literal(KVal::Float(KData::List(Cow::Owned(vec![
+            1e300,
+            f64::NAN,
+            f64::INFINITY
+        ])))),
+        "1e300 0n 0w"
+    );
+    assert_eq!(
+        literal(KVal::Real(KData::List(Cow::Owned(vec![0.1, f32::NAN])))),
+        "0.1 0Ne"
+    );
+    assert_eq!(literal(kval!([1, 2, 3])), "1 2 3");
+    assert_eq!(literal(kval!([1i16])), ",1h");
+    assert_eq!(
+        literal(KVal::Timestamp(KData::Atom(Cow::Owned(
+            639_014_400_000_000_000
+        )))),
+        "2020.04.01D00:00:00.000000000"
+    );
+    assert_eq!(
+        literal(KVal::String(Cow::Borrowed(b"a\"b\n"))),
+        "\"a\\\"b\\n\""
+    );
+
+    // Datetimes which are not whole milliseconds are cast from floats.
+    assert_eq!(
+        literal(KVal::Datetime(KData::Atom(Cow::Owned(0.5)))),
+        "2000.01.01T12:00:00.000"
+    );
+    assert_eq!(
+        literal(KVal::Datetime(KData::List(Cow::Owned(vec![1e-9, 1.0])))),
+        "`datetime$1e-9 1"
+    );
+
+    // Symbols and guids which are not names are cast from strings.
+    assert_eq!(literal(kval!(["a", "b.c"])), "`a`b.c");
+    assert_eq!(literal(kval!(["a", "b c"])), "`$(\"a\";\"b c\")");
+    assert_eq!(literal(kval!("a b")), "`$\"a b\"");
+    assert_eq!(
+        literal(KVal::Symbol(KData::List(Cow::Owned(vec![
+            "a-b".to_string()
+        ])))),
+        ",`$\"a-b\""
+    );
+    assert_eq!(
+        literal(KVal::Guid(KData::Atom(Cow::Owned([0x11; 16])))),
+        "\"G\"$\"11111111-1111-1111-1111-111111111111\""
+    );
+    assert_eq!(
+        literal(KVal::Guid(KData::List(Cow::Owned(vec![])))),
+        "`guid$()"
+    );
+
+    // Compound lists of atoms of the same type stay general.
+    assert_eq!(literal(kval!([1i32, "x", 2.5])), "(1i;`x;2.5)");
+    assert_eq!(
+        literal(KVal::CompoundList(vec![kval!(1), kval!(2)])),
+        "-1_(1;2;::)"
+    );
+    assert_eq!(literal(KVal::CompoundList(vec![kval!(1)])), "-1_(1;::)");
+    assert_eq!(literal(kval!([[1, 2]])), ",1 2");
+
+    // Dictionaries and tables.
+    assert_eq!(literal(kval!({"a" => 1})), "(,`a)!,1");
+    assert_eq!(
+        literal(KVal::Dictionary(keyed_trades(1))),
+        "(+(,`id)!,,0)!(+(`sym`price`size)!(,`a;,0.5;,0))"
+    );
+    assert_eq!(literal(KVal::Null), "::");
+}
+
+#[test]
+fn parse_test() {
+    let longs = |values: &[i64]| KVal::Long(KData::List(Cow::Owned(values.to_vec())));
+    let symbols = |values: &[&str]| {
+        KVal::Symbol(KData::List(Cow::Owned(
+            values.iter().map(|value| value.to_string()).collect(),
+        )))
+    };
+    let cases = [
+        // Numbers and type suffixes.
+        ("1", kval!(1)),
+        ("-2i", kval!(-2i32)),
+        ("3h", kval!(3i16)),
+        ("4j", kval!(4i64)),
+        ("2.5e", kval!(2.5f32)),
+        ("1e", kval!(1.0f32)),
+        ("2.5", kval!(2.5)),
+        ("1e-3", kval!(1e-3)),
+        (".5f", kval!(0.5)),
+        ("1b", kval!(true)),
+        ("101b", kval!([true, false, true])),
+        ("0N", KVal::Long(KData::Atom(Cow::Owned(qnull_base::J)))),
+        ("0Wi", KVal::Int(KData::Atom(Cow::Owned(qinf_base::I)))),
+        ("-0Wh", KVal::Short(KData::Atom(Cow::Owned(qninf_base::H)))),
+        ("-0w", KVal::Float(KData::Atom(Cow::Owned(qninf_base::F)))),
+        // Simple lists.
+        ("1 2 3", longs(&[1, 2, 3])),
+        ("1 -2  0N", longs(&[1, -2, qnull_base::J])),
+        ("1 2 3h", kval!([1i16, 2i16, 3i16])),
+        ("1 2.5", kval!([1.0, 2.5])),
+        ("0W 1.5e", kval!([(f32::INFINITY), 1.5f32])),
+        // Temporal values.
+        ("2020.04.01", KVal::Date(KData::Atom(Cow::Owned(7396)))),
+        (
+            "2020.04.01 0N 2000.01.01",
+            KVal::Date(KData::List(Cow::Owned(vec![7396, qnull_base::I, 0]))),
+        ),
+        ("0Nd", KVal::Date(KData::Atom(Cow::Owned(qnull_base::I)))),
+        (
+            "2020.04.01D00:00:00.000000001",
+            KVal::Timestamp(KData::Atom(Cow::Owned(639_014_400_000_000_001))),
+        ),
+        (
+            "2000.01.01D12:30 2000.01.01D00:00:01.5",
+            KVal::Timestamp(KData::List(Cow::Owned(vec![
+                45_000_000_000_000,
+                1_500_000_000,
+            ]))),
+        ),
+        (
+            "0Np",
+            KVal::Timestamp(KData::Atom(Cow::Owned(qnull_base::J))),
+        ),
+        // Symbols and strings.
+        ("`a", kval!("a")),
+        ("`", kval!("")),
+        ("`a`b`", symbols(&["a", "b", ""])),
+        ("`:path/to.file", kval!(":path/to.file")),
+        ("\"a\"", KVal::Char('a')),
+        ("\"\"", KVal::String(Cow::Borrowed(b""))),
+        (
+            "\"te\\\"x\\tt\\n\\001\"",
+            KVal::String(Cow::Borrowed(b"te\"x\tt\n\x01")),
+        ),
+        (",\"a\"", KVal::String(Cow::Borrowed(b"a"))),
+        // General lists.
+        ("()", KVal::CompoundList(vec![])),
+        ("(1i;`x;2.5)", kval!([1i32, "x", 2.5])),
+        ("( 1 ; 2 )", longs(&[1, 2])),
+        (
+            "(1 2;\"ab\")",
+            KVal::CompoundList(vec![longs(&[1, 2]), KVal::String(Cow::Borrowed(b"ab"))]),
+        ),
+        ("(`a)", kval!("a")),
+        (",1", longs(&[1])),
+        (",1 2", KVal::CompoundList(vec![longs(&[1, 2])])),
+        // Dictionaries.
+        ("`a`b!1 2", kval!({"a" => 1, "b" => 2})),
+        ("(,`a)!,`x", kval!({"a" => "x"})),
+        (
+            "`a`b!(1;`c`d!(\"x\";2.5))",
+            kval!({"a" => 1, "b" => {"c" => (KVal::Char('x')), "d" => 2.5}}),
+        ),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(KVal::parse(input), Ok(expected), "input: {}", input);
+    }
+
+    let errors = [
+        ("", "unexpected end of input at 0"),
+        ("1 2 + 3", "unexpected '+' at 4"),
+        ("+1", "unsupported syntax '+' at 0"),
+        ("12:30", "unsupported literal '12:30' at 0"),
+        ("1 2i 3", "type suffix must be at the end of a list at 2"),
+        ("1 2020.01.01", "mixed types in a list at 2"),
+        ("70000h", "invalid or out of range integer '70000' at 0"),
+        ("2020.02.30", "invalid date '2020.02.30' at 0"),
+        (
+            "2020.01.01D24:00",
+            "invalid timestamp '2020.01.01D24:00' at 0",
+        ),
+        (
+            "1 0b",
+            "bools must be written as one number, e.g., 101b at 0",
+        ),
+        ("\"abc", "unterminated string at 0"),
+        ("\"a\\qb\"", "invalid escape at 2"),
+        ("(1;2", "expected ';' or ')' at 4"),
+        ("`a!1", "keys of a dictionary must be a list at 0"),
+        (
+            "`a`b!1 2 3",
+            "length mismatch of keys and values: 2 and 3 at 0",
+        ),
+    ];
+    for (input, message) in errors {
+        assert_eq!(
+            KVal::parse(input).unwrap_err().to_string(),
+            message,
+            "input: {}",
+            input
+        );
+    }
+
+    // Literals of supported values are parsed back into the same values.
+    let value = kval!({"ints" => [1i32, 2i32], "names" => ["a", "b"], "mixed" => [1, "x", 2.5]});
+    assert_eq!(value.to_q_literal().parse::<KVal>(), Ok(value));
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_to_k_test() {
+    // Lists longer than a few chunks are copied by several tasks.
+    const LEN: usize = 200_000;
+    let previous = set_parallel_threshold(LEN);
+
+    let floats = KVal::Float(KData::List(Cow::Owned(
+        (0..LEN).map(|i| i as f64 * 0.5).collect(),
+    )));
+    let k = floats.to_k();
+    assert_eq!(KVal::from_raw(k), floats);
+    decrement_reference_count(k);
+
+    let bools = KVal::Bool(KData::List(Cow::Owned(
+        (0..LEN).map(|i| i % 3 == 0).collect(),
+    )));
+    let k = bools.to_k();
+    assert_eq!(KVal::from_raw(k), bools);
+    decrement_reference_count(k);
+
+    // Symbols are interned with the symbol lock, which is restored afterwards.
+    let symbols = KVal::Symbol(KData::List(Cow::Owned(
+        (0..LEN).map(|i| format!("s{}", i % 100)).collect(),
+    )));
+    let k = symbols.to_k();
+    assert_eq!(KVal::from_raw(k), symbols);
+    decrement_reference_count(k);
+    assert_eq!(unsafe { native::setm(0) }, 0);
+
+    let mut invalid = vec!["a".to_string(); LEN];
+    invalid[LEN - 1] = "b\0c".to_string();
+    let k = KVal::Symbol(KData::List(Cow::Owned(invalid))).to_k();
+    assert!(k.is_null());
+    assert_eq!(
+        mock::last_error().as_deref(),
+        Some("symbol contains a null character")
+    );
+    mock::clear_error();
+
+    // Shorter lists are copied serially.
+    set_parallel_threshold(usize::MAX);
+    let k = floats.to_k();
+    assert_eq!(KVal::from_raw(k), floats);
+    decrement_reference_count(k);
+
+    set_parallel_threshold(previous);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn hash_key_test() {
+    // Index the first row of each symbol of a table column.
+    let table = trades_table(7);
+    let column = table.dict.values.get(0).unwrap();
+    let mut index = HashMap::new();
+    for (row, symbol) in column.iter().enumerate() {
+        index.entry(symbol).or_insert(row);
+    }
+    assert_eq!(index.len(), 3);
+    assert_eq!(index[&kval!("a")], 0);
+    assert_eq!(index[&kval!("c")], 2);
+    assert!(!index.contains_key(&kval!("d")));
+
+    // Every float null is the same key and so are 0.0 and -0.0.
+    let keys = [f64::NAN, -f64::NAN, 0.0, -0.0, 1.5]
+        .iter()
+        .map(|float| KVal::Float(KData::Atom(Cow::Borrowed(float))))
+        .collect::<HashSet<_>>();
+    assert_eq!(keys.len(), 3);
+    let reals = [f32::NAN, -f32::NAN]
+        .into_iter()
+        .map(|real| kval!(real))
+        .collect::<HashSet<_>>();
+    assert_eq!(reals.len(), 1);
+
+    // A temporal value is a key apart from its underlying type and an enum apart from another source.
+    let keys = HashSet::from([
+        kval!(7396i32),
+        KVal::Date(KData::Atom(Cow::Owned(7396))),
+        KVal::Enum(KData::Atom(Cow::Owned(1)), Some("sym".to_string())),
+        KVal::Enum(KData::Atom(Cow::Owned(1)), Some("ticker".to_string())),
+        KVal::Enum(KData::Atom(Cow::Owned(1)), Some("sym".to_string())),
+    ]);
+    assert_eq!(keys.len(), 4);
+
+    // GUIDs key a map and borrowed atoms find owned ones.
+    let guids = [[1_u8; 16], [2_u8; 16]];
+    let positions = guids
+        .iter()
+        .enumerate()
+        .map(|(position, guid)| (KVal::Guid(KData::Atom(Cow::Owned(*guid))), position))
+        .collect::<HashMap<_, _>>();
+    assert_eq!(
+        positions[&KVal::Guid(KData::Atom(Cow::Borrowed(&guids[1])))],
+        1
+    );
+}
+
+#[test]
+fn kdata_accessor_test() {
+    let atom = KData::Atom(Cow::Owned(1.5_f64));
+    assert_eq!(atom.as_slice(), &[1.5]);
+    assert_eq!(atom.first(), Some(&1.5));
+    assert_eq!(atom.last(), Some(&1.5));
+    assert_eq!(atom.to_vec(), vec![1.5]);
+
+    let symbols = ["a".to_string(), "b".to_string(), "c".to_string()];
+    let list = KData::List(Cow::Borrowed(&symbols[..]));
+    assert_eq!(list.as_slice(), &symbols);
+    assert_eq!(list.first().map(String::as_str), Some("a"));
+    assert_eq!(list.last().map(String::as_str), Some("c"));
+    assert_eq!(list.to_vec(), symbols.to_vec());
+
+    let empty = KData::<i64>::List(Cow::Owned(vec![]));
+    assert!(empty.as_slice().is_empty());
+    assert_eq!(empty.first(), None);
+    assert_eq!(empty.last(), None);
+    assert!(empty.to_vec().is_empty());
+
+    // Generic code reads an atom and a list alike.
+    fn total<T: Clone + Into<f64>>(data: &KData<T>) -> f64 {
+        data.as_slice()
+            .iter()
+            .map(|element| element.clone().into())
+            .sum()
+    }
+    assert_eq!(total(&KData::<i32>::Atom(Cow::Owned(3))), 3.0);
+    assert_eq!(total(&KData::<f32>::List(Cow::Owned(vec![1.5, 2.5]))), 4.0);
+
+    // A list of q memory is borrowed and copied only when taken as a vector.
+    let k = new_list(qtype::LONG_LIST, 3);
+    k.as_mut_slice::<J>().copy_from_slice(&[10, 20, 30]);
+    match KVal::from_raw(k) {
+        KVal::Long(data) => {
+            assert_eq!(data.as_slice(), &[10, 20, 30]);
+            assert_eq!(data.last(), Some(&30));
+            assert_eq!(data.to_vec(), vec![10, 20, 30]);
+        }
+        _ => unreachable!(),
+    }
+    decrement_reference_count(k);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn kdata_map_test() {
+    let prices = KData::List(Cow::Owned(vec![1.5_f64, f64::NAN, 3.0]));
+    assert_eq!(prices.iter().filter(|price| !price.is_nan()).count(), 2);
+    assert_eq!(
+        KData::<i32>::Atom(Cow::Owned(2)).iter().collect::<Vec<_>>(),
+        vec![&2]
+    );
+
+    let ticks = prices.map(|price| match price.is_nan() {
+        true => qnull_base::J,
+        false => (price * 2.0) as i64,
+    });
+    assert_eq!(ticks, KData::List(Cow::Owned(vec![3, qnull_base::J, 6])));
+    assert_eq!(
+        KData::<String>::Atom(Cow::Owned("a".to_string())).map(|symbol| symbol.len() as i16),
+        KData::Atom(Cow::Owned(1_i16))
+    );
+
+    // Owned data is modified without reallocation.
+    let mut sizes = KData::List(Cow::Owned(vec![1_i64, 2, 3]));
+    let buffer = sizes.as_slice().as_ptr();
+    sizes.map_in_place(|size| *size *= 100);
+    assert_eq!(sizes.as_slice(), &[100, 200, 300]);
+    assert_eq!(sizes.as_slice().as_ptr(), buffer);
+
+    let mut atom = KData::<f32>::Atom(Cow::Owned(1.5));
+    atom.map_in_place(|real| *real = -*real);
+    assert_eq!(atom, KData::Atom(Cow::Owned(-1.5)));
+
+    // Data borrowed from q is copied and the q object is left as it is.
+    let k = new_list(qtype::FLOAT_LIST, 2);
+    k.as_mut_slice::<F>().copy_from_slice(&[1.5, 2.5]);
+    match KVal::from_raw(k) {
+        KVal::Float(mut data) => {
+            data.map_in_place(|price| *price *= 2.0);
+            assert_eq!(data.as_slice(), &[3.0, 5.0]);
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(k.as_mut_slice::<F>(), &[1.5, 2.5]);
+    decrement_reference_count(k);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn guid_layout_test() {
+    /// Bytes after the header of a q object: one GUID for an atom and 16 bytes per element for a list.
+    fn raw_bytes<'a>(k: K) -> &'a [u8] {
+        let count = match k.get_type() {
+            qtype::GUID_ATOM => 1,
+            _ => k.len() as usize,
+        };
+        unsafe { std::slice::from_raw_parts((*k).value.list.G0.as_ptr(), count * 16) }
+    }
+    let guids: Vec<[u8; 16]> = (0..5_u8)
+        .map(|row| std::array::from_fn(|i| row * 16 + i as u8))
+        .collect();
+
+    // The atom is placed where the first element of a list is.
+    let atom = unsafe { native::ku(U::new(guids[3])) };
+    let value = KVal::from_raw(atom);
+    assert_eq!(value, KVal::Guid(KData::Atom(Cow::Owned(guids[3]))));
+    let k = value.to_k();
+    assert_eq!(k.get_type(), qtype::GUID_ATOM);
+    assert_eq!(raw_bytes(k), raw_bytes(atom));
+    assert_eq!(raw_bytes(k), &guids[3]);
+    decrement_reference_count(k);
+    decrement_reference_count(atom);
+
+    // The elements of a list are contiguous with a stride of 16 bytes.
+    let list = unsafe { native::ktn(qtype::GUID_LIST as I, guids.len() as J) };
+    list.as_mut_slice::<U>()
+        .iter_mut()
+        .zip(guids.iter())
+        .for_each(|(element, guid)| *element = U::new(*guid));
+    let value = KVal::from_raw(list);
+    assert_eq!(value, KVal::Guid(KData::List(Cow::Borrowed(&guids[..]))));
+    assert_eq!(
+        value.get(4),
+        Some(KVal::Guid(KData::Atom(Cow::Borrowed(&guids[4]))))
+    );
+    let k = value.to_k();
+    assert_eq!(k.get_type(), qtype::GUID_LIST);
+    assert_eq!(k.len(), 5);
+    assert_eq!(raw_bytes(k), raw_bytes(list));
+    assert_eq!(raw_bytes(k), guids.concat());
+    decrement_reference_count(k);
+
+    // A list built from owned GUIDs and an atom taken from a list round trip alike.
+    let k = KVal::Guid(KData::List(Cow::Owned(guids.clone()))).to_k();
+    assert_eq!(raw_bytes(k), raw_bytes(list));
+    decrement_reference_count(k);
+    let k = KVal::from_raw(list).get(1).unwrap().to_k();
+    assert_eq!(raw_bytes(k), &guids[1]);
+    decrement_reference_count(k);
+    decrement_reference_count(list);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn commit_test() {
+    // A modified list is written back into the object it was built from.
+    let k = new_list(qtype::LONG_LIST, 3);
+    k.as_mut_slice::<J>().copy_from_slice(&[1, 2, 3]);
+    let mut value = KVal::from_raw(k);
+    if let KVal::Long(data) = &mut value {
+        data.map_in_place(|long| *long *= 2);
+    }
+    assert_eq!(k.as_mut_slice::<J>(), &[1, 2, 3]);
+    assert_eq!(value.commit(k), Ok(()));
+    assert_eq!(k.as_mut_slice::<J>(), &[2, 4, 6]);
+    // An unmodified value still borrowing the object is committed as it is.
+    assert_eq!(KVal::from_raw(k).commit(k), Ok(()));
+    assert_eq!(k.as_mut_slice::<J>(), &[2, 4, 6]);
+
+    // Errors leave the object untouched.
+    assert_eq!(longs(vec![1, 2]).commit(k), Err("length\0"));
+    assert_eq!(
+        KVal::Int(KData::List(Cow::Owned(vec![1, 2, 3]))).commit(k),
+        Err("type\0")
+    );
+    assert_eq!(kval!(1).commit(k), Err("not a list\0"));
+    assert_eq!(longs(vec![1, 2, 3]).commit(KNULL), Err("not a list\0"));
+    unsafe { (*k).attribute = qattribute::SORTED };
+    assert_eq!(
+        longs(vec![7, 8, 9]).commit(k),
+        Err("list has an attribute\0")
+    );
+    assert_eq!(k.as_mut_slice::<J>(), &[2, 4, 6]);
+    decrement_reference_count(k);
+
+    // Bools, symbols and strings are written in the representation of q.
+    let bools = kval!([true, false]).to_k();
+    assert_eq!(kval!([false, true]).commit(bools), Ok(()));
+    assert_eq!(bools.as_mut_slice::<G>(), &[0, 1]);
+    let symbols = kval!(["a", "b"]).to_k();
+    assert_eq!(kval!(["c", "a"]).commit(symbols), Ok(()));
+    assert_eq!(KVal::from_raw(symbols), kval!(["c", "a"]));
+    assert_eq!(
+        kval!(["c", "d\0"]).commit(symbols),
+        Err("symbol contains a null character\0")
+    );
+    assert_eq!(KVal::from_raw(symbols), kval!(["c", "a"]));
+    let string = KVal::String(Cow::Borrowed(b"abc")).to_k();
+    assert_eq!(KVal::String(Cow::Borrowed(b"xyz")).commit(string), Ok(()));
+    assert_eq!(KVal::from_raw(string), KVal::String(Cow::Borrowed(b"xyz")));
+    assert_eq!(
+        KVal::String(Cow::Borrowed("é".as_bytes())).commit(string),
+        Err("length\0")
+    );
+    for k in [bools, symbols, string] {
+        decrement_reference_count(k);
+    }
+
+    // A column of a table is updated in place and the whole table is checked before anything is written.
+    let table = trades(3);
+    let mut value = KTable::from_raw(table);
+    if let KVal::CompoundList(columns) = value.dict.values.as_mut() {
+        if let KVal::Float(prices) = &mut columns[1] {
+            prices.map_in_place(|price| *price *= 2.0);
+        }
+        columns[2] = KVal::Long(KData::List(Cow::Owned(vec![1, 2])));
+    }
+    let mut value = KVal::Table(value);
+    assert_eq!(value.commit(table), Err("length\0"));
+    assert_eq!(KTable::from_raw(table), trades_table(3));
+    if let KVal::Table(value) = &mut value {
+        if let KVal::CompoundList(columns) = value.dict.values.as_mut() {
+            columns[2] = KVal::Long(KData::List(Cow::Owned(vec![0, 100, 200])));
+        }
+    }
+    assert_eq!(value.commit(table), Ok(()));
+    let expected = trades_table(3);
+    match (
+        KTable::from_raw(table).dict.values.as_ref(),
+        expected.dict.values.as_ref(),
+    ) {
+        (KVal::CompoundList(actual), KVal::CompoundList(expected)) => {
+            assert_eq!(actual[0], expected[0]);
+            assert_eq!(actual[1], kval!([1.0, 3.0, 5.0]));
+            assert_eq!(actual[2], expected[2]);
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(kval!([1, 2, 3]).commit(table), Err("type\0"));
+    decrement_reference_count(table);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn aggregate_test() {
+    // KData skips nulls and returns None when nothing is left.
+    let ints = KData::<i32>::List(Cow::Owned(vec![
+        5,
+        qnull_base::I,
+        qinf_base::I,
+        qninf_base::I,
+        2,
+    ]));
+    assert_eq!(ints.count_non_null(), 4);
+    assert_eq!(ints.sum(), Some(7));
+    assert_eq!(ints.min(), Some(qninf_base::I));
+    assert_eq!(ints.max(), Some(qinf_base::I));
+    assert_eq!(ints.mean(), Some(1.75));
+    let nulls = KData::<f64>::List(Cow::Owned(vec![f64::NAN, -f64::NAN]));
+    assert_eq!(nulls.count_non_null(), 0);
+    assert_eq!(nulls.sum(), None);
+    assert_eq!(nulls.min(), None);
+    assert_eq!(nulls.max(), None);
+    assert_eq!(nulls.mean(), None);
+    let atom = KData::<u8>::Atom(Cow::Owned(0xff));
+    assert_eq!(atom.sum(), Some(255));
+    assert_eq!(atom.mean(), Some(255.0));
+
+    // KVal widens a sum and returns an atom of the type of q.
+    let summary = |value: KVal| {
+        [value.sum(), value.min(), value.max(), value.mean()]
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+    };
+    assert_eq!(
+        summary(kval!([3, (qnull_base::J), 1, 4])),
+        Ok(vec![kval!(8), kval!(1), kval!(4), kval!(8.0 / 3.0)])
+    );
+    assert_eq!(
+        summary(KVal::Short(KData::List(Cow::Owned(vec![
+            i16::MAX,
+            i16::MAX,
+            qnull_base::H
+        ])))),
+        Ok(vec![
+            kval!(65534),
+            kval!(i16::MAX),
+            kval!(i16::MAX),
+            kval!(32767.0)
+        ])
+    );
+    assert_eq!(
+        summary(kval!([1.5, (f64::NAN), (f64::INFINITY), 2.0])),
+        Ok(vec![
+            kval!(f64::INFINITY),
+            kval!(1.5),
+            kval!(f64::INFINITY),
+            kval!(f64::INFINITY)
+        ])
+    );
+    assert_eq!(
+        summary(kval!([1.5f32, (f32::NAN), 2.5f32])),
+        Ok(vec![kval!(4.0), kval!(1.5f32), kval!(2.5f32), kval!(2.0)])
+    );
+    assert_eq!(
+        summary(kval!([true, false, true, true])),
+        Ok(vec![kval!(3), kval!(false), kval!(true), kval!(0.75)])
+    );
+
+    // Nulls and empty lists result in the identities of q.
+    assert_eq!(
+        summary(kval!([(f64::NAN), (f64::NAN)])),
+        Ok(vec![
+            kval!(0.0),
+            kval!(f64::INFINITY),
+            kval!(f64::NEG_INFINITY),
+            kval!(f64::NAN)
+        ])
+    );
+    assert_eq!(
+        summary(KVal::Long(KData::List(Cow::Owned(vec![])))),
+        Ok(vec![
+            kval!(0),
+            kval!(qinf_base::J),
+            kval!(qninf_base::J),
+            kval!(f64::NAN)
+        ])
+    );
+    let bytes = KVal::Byte(KData::List(Cow::Owned(vec![])));
+    assert_eq!(bytes.min(), Ok(kval!(0xffu8)));
+    assert_eq!(bytes.max(), Ok(kval!(0u8)));
+    let bools = KVal::Bool(KData::List(Cow::Owned(vec![])));
+    assert_eq!(bools.min(), Ok(kval!(true)));
+    assert_eq!(bools.max(), Ok(kval!(false)));
+
+    // Temporal values keep their types.
+    let dates = KVal::Date(KData::List(Cow::Owned(vec![7396, qnull_base::I, 7000])));
+    assert_eq!(dates.min(), Ok(KVal::Date(KData::Atom(Cow::Owned(7000)))));
+    assert_eq!(dates.max(), Ok(KVal::Date(KData::Atom(Cow::Owned(7396)))));
+    assert_eq!(
+        KVal::Date(KData::List(Cow::Owned(vec![qnull_base::I]))).min(),
+        Ok(KVal::Date(KData::Atom(Cow::Owned(qinf_base::I))))
+    );
+    assert_eq!(dates.sum(), Err("type\0"));
+    assert_eq!(dates.mean(), Err("type\0"));
+    let spans = KVal::Timespan(KData::List(Cow::Owned(vec![1_000, qnull_base::J, 500])));
+    assert_eq!(
+        spans.sum(),
+        Ok(KVal::Timespan(KData::Atom(Cow::Owned(1_500))))
+    );
+
+    assert_eq!(kval!(["a", "b"]).sum(), Err("type\0"));
+    assert_eq!(kval!(["a", "b"]).min(), Err("type\0"));
+    assert_eq!(KVal::CompoundList(vec![]).mean(), Err("type\0"));
+}
+
+#[test]
+fn bin_test() {
+    // KData searches in the order of q like `slice::binary_search`.
+    let longs = KData::<i64>::List(Cow::Owned(vec![qnull_base::J, 1, 3, 3, 5]));
+    assert_eq!(longs.binary_search(&qnull_base::J), Ok(0));
+    assert_eq!(longs.binary_search(&5), Ok(4));
+    assert!(matches!(longs.binary_search(&3), Ok(2 | 3)));
+    assert_eq!(longs.binary_search(&0), Err(1));
+    assert_eq!(longs.binary_search(&9), Err(5));
+    let atom = KData::<i32>::Atom(Cow::Owned(7));
+    assert_eq!(atom.binary_search(&7), Ok(0));
+    assert_eq!(atom.binary_search(&8), Err(1));
+    let symbols = KData::<String>::List(Cow::Owned(vec![
+        String::new(),
+        "a".to_string(),
+        "c".to_string(),
+    ]));
+    assert_eq!(symbols.binary_search(&"b".to_string()), Err(2));
+
+    // KVal returns the last index not greater than the needle like `bin` in q.
+    let sorted = kval!([1, 3, 3, 3, 5, (qinf_base::J)]);
+    let bin = |needle: i64| sorted.bin(&kval!(needle));
+    assert_eq!(bin(qninf_base::J), Ok(-1));
+    assert_eq!(bin(0), Ok(-1));
+    assert_eq!(bin(1), Ok(0));
+    assert_eq!(bin(2), Ok(0));
+    assert_eq!(bin(3), Ok(3));
+    assert_eq!(bin(4), Ok(3));
+    assert_eq!(bin(6), Ok(4));
+    assert_eq!(bin(qinf_base::J), Ok(5));
+    assert_eq!(bin(qnull_base::J), Ok(-1));
+
+    let floats = kval!([(f64::NAN), 1.5, 2.5, (f64::INFINITY)]);
+    assert_eq!(floats.bin(&kval!(f64::NAN)), Ok(0));
+    assert_eq!(floats.bin(&kval!(1.0)), Ok(0));
+    assert_eq!(floats.bin(&kval!(2.5)), Ok(2));
+    assert_eq!(floats.bin(&kval!(f64::INFINITY)), Ok(3));
+    assert_eq!(
+        KVal::String(Cow::Borrowed(b"acce")).bin(&KVal::Char('d')),
+        Ok(2)
+    );
+    // A string is searched by bytes and a non-ASCII char is compared as the byte of its code point.
+    let sorted = KVal::String(Cow::Borrowed(b"abz"));
+    assert_eq!(sorted.bin(&KVal::Char('b')), Ok(1));
+    assert_eq!(sorted.bin(&KVal::Char('\u{7f}')), Ok(2));
+    assert_eq!(sorted.bin(&KVal::Char('é')), Ok(2));
+    assert_eq!(sorted.bin(&KVal::Char('🦀')), Ok(2));
+    assert_eq!(sorted.bin(&KVal::Char('a')), Ok(0));
+    assert_eq!(sorted.bin(&KVal::Char('\0')), Ok(-1));
+    let dates = KVal::Date(KData::List(Cow::Owned(vec![7305, 7336])));
+    assert_eq!(dates.bin(&KVal::Date(KData::Atom(Cow::Owned(7319)))), Ok(0));
+    assert_eq!(
+        KVal::Long(KData::List(Cow::Owned(vec![]))).bin(&kval!(1)),
+        Ok(-1)
+    );
+
+    // The needle must be an atom of the type of the list.
+    assert_eq!(sorted.bin(&kval!(2i32)), Err("type\0"));
+    assert_eq!(sorted.bin(&kval!([1, 2])), Err("type\0"));
+    assert_eq!(dates.bin(&kval!(7319i32)), Err("type\0"));
+    assert_eq!(
+        KVal::Enum(KData::List(Cow::Owned(vec![0, 1])), Some("sym".to_string()))
+            .bin(&KVal::Enum(KData::Atom(Cow::Owned(0)), None)),
+        Err("type\0")
+    );
+    assert_eq!(kval!([1, "a"]).bin(&kval!(1)), Err("type\0"));
+    assert_eq!(kval!(1).bin(&kval!(1)), Err("not a list\0"));
+    assert_eq!(KVal::Null.bin(&kval!(1)), Err("not a list\0"));
+}
+
+#[test]
+fn config_getter_test() {
+    let config = match kval!({
+        "host" => "feed",
+        "port" => 5001,
+        "interval" => 0.5,
+        "instruments" => (KVal::Table(trades_table(3))),
+        "retries" => 3i32
+    }) {
+        KVal::Dictionary(config) => config,
+        _ => unreachable!(),
+    };
+    assert_eq!(config.get_symbol("host"), Ok("feed".to_string()));
+    assert_eq!(config.get_long("port"), Ok(5001));
+    assert_eq!(config.get_float("interval"), Ok(0.5));
+    assert_eq!(
+        config.get_table("instruments").map(|table| table.len()),
+        Ok(3)
+    );
+
+    // Missing keys and values of a wrong type.
+    assert_eq!(config.get_long("user"), Err("key not found\0"));
+    assert_eq!(config.get_long("retries"), Err("not a long\0"));
+    assert_eq!(config.get_float("port"), Err("not a float\0"));
+    assert_eq!(config.get_symbol("port"), Err("not a symbol\0"));
+    assert_eq!(config.get_table("host"), Err("not a table\0"));
+
+    // String keys.
+    let labels = KDict::new(
+        KVal::CompoundList(vec![
+            KVal::String(Cow::Owned("open".to_string().into_bytes())),
+            KVal::String(Cow::Owned("close".to_string().into_bytes())),
+        ]),
+        KVal::Long(KData::List(Cow::Owned(vec![9, 17]))),
+    );
+    assert_eq!(labels.get_long("close"), Ok(17));
+    assert_eq!(labels.get("noon"), None);
+
+    // A round trip through q.
+    let raw = KVal::Dictionary(config).to_k();
+    let config = KDict::from_raw(raw);
+    assert_eq!(config.get_long("port"), Ok(5001));
+    assert_eq!(config.get_symbol("interval"), Err("not a symbol\0"));
+    decrement_reference_count(raw);
+}
+
+#[test]
+fn dictionary_edit_test() {
+    // Built incrementally from an empty q dictionary.
+    let empty = KVal::Dictionary(KDict::new(
+        KVal::CompoundList(Vec::new()),
+        KVal::CompoundList(Vec::new()),
+    ))
+    .to_k();
+    let mut limits = KDict::from_raw(empty);
+    assert_eq!(limits.insert(kval!("a"), kval!(100)), Ok(None));
+    assert_eq!(limits.insert(kval!("b"), kval!(200)), Ok(None));
+    assert_eq!(limits, KDict::new(kval!(["a", "b"]), kval!([100, 200])));
+    decrement_reference_count(empty);
+
+    // An existing key is not overwritten by insert but by upsert.
+    assert_eq!(limits.insert(kval!("a"), kval!(150)), Ok(Some(kval!(150))));
+    assert_eq!(limits.upsert(kval!("a"), kval!(150)), Ok(Some(kval!(100))));
+    assert_eq!(limits.upsert(kval!("c"), kval!(300)), Ok(None));
+    assert_eq!(
+        limits,
+        KDict::new(kval!(["a", "b", "c"]), kval!([150, 200, 300]))
+    );
+
+    // Values of another type make a compound list.
+    assert_eq!(
+        limits.upsert(kval!("b"), kval!("none")),
+        Ok(Some(kval!(200)))
+    );
+    assert_eq!(limits.insert(kval!(1), kval!(2.5)), Ok(None));
+    assert_eq!(
+        limits,
+        KDict::new(kval!(["a", "b", "c", 1]), kval!([150, "none", 300, 2.5]))
+    );
+
+    // Removal keeps keys and values aligned.
+    assert_eq!(limits.remove(&kval!("b")), Some(kval!("none")));
+    assert_eq!(limits.remove(&kval!("b")), None);
+    assert_eq!(limits.remove(&kval!(1)), Some(kval!(2.5)));
+    assert_eq!(limits.len(), 2);
+    assert_eq!(limits.get_long("c"), Ok(300));
+
+    // Enums of another source do not fit an enum list.
+    let sym = Some("sym".to_string());
+    let mut enums = KDict::new(
+        kval!(["x"]),
+        KVal::Enum(KData::List(Cow::Owned(vec![0])), sym.clone()),
+    );
+    enums
+        .insert(
+            kval!("y"),
+            KVal::Enum(KData::Atom(Cow::Owned(1)), Some("other".to_string())),
+        )
+        .unwrap();
+    assert_eq!(enums.values.qtype(), qtype::COMPOUND_LIST);
+    enums
+        .insert(kval!("z"), KVal::Enum(KData::Atom(Cow::Owned(2)), sym))
+        .unwrap();
+    assert_eq!(enums.len(), 3);
+
+    // A keyed table cannot be edited by key.
+    let mut keyed = KDict::new(KVal::Table(trades_table(2)), KVal::Table(trades_table(2)));
+    assert_eq!(keyed.insert(kval!("a"), kval!(1)), Err("not a list\0"));
+    assert_eq!(keyed.remove(&kval!("a")), None);
+}
+
+#[test]
+fn dictionary_merge_test() {
+    // The right value is taken on collisions and new keys follow in the right order.
+    let left = KDict::new(kval!(["a", "b", "c"]), kval!([1, 2, 3]));
+    let right = KDict::new(kval!(["d", "b"]), kval!([40, 20]));
+    assert_eq!(
+        left.clone().merge(right),
+        Ok(KDict::new(
+            kval!(["a", "b", "c", "d"]),
+            kval!([1, 20, 3, 40])
+        ))
+    );
+
+    // Values of different types make a compound list.
+    let right = KDict::new(
+        kval!(["c", "e"]),
+        KVal::Int(KData::List(Cow::Owned(vec![30, 50]))),
+    );
+    let merged = left.clone().merge(right).unwrap();
+    assert_eq!(merged.values.qtype(), qtype::COMPOUND_LIST);
+    assert_eq!(
+        merged.get("c"),
+        Some(KVal::Int(KData::Atom(Cow::Owned(30))))
+    );
+    assert_eq!(merged.get_long("a"), Ok(1));
+    assert_eq!(merged.len(), 4);
+
+    // An empty dictionary from q on either side.
+    let empty = KVal::Dictionary(KDict::new(
+        KVal::CompoundList(Vec::new()),
+        KVal::CompoundList(Vec::new()),
+    ))
+    .to_k();
+    assert_eq!(KDict::from_raw(empty).merge(left.clone()), Ok(left.clone()));
+    assert_eq!(left.clone().merge(KDict::from_raw(empty)), Ok(left.clone()));
+    decrement_reference_count(empty);
+
+    // Custom resolution of collisions.
+    let right = KDict::new(
+        kval!(["b", "c", "d"]),
+        KVal::Long(KData::List(Cow::Owned(vec![
+            qnull_base::J,
+            30,
+            qnull_base::J,
+        ]))),
+    );
+    let filled = left
+        .clone()
+        .merge_with(
+            right,
+            |left, right| {
+                if right.is_null_atom() {
+                    left
+                } else {
+                    right
+                }
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        filled,
+        KDict::new(
+            kval!(["a", "b", "c", "d"]),
+            KVal::Long(KData::List(Cow::Owned(vec![1, 2, 30, qnull_base::J])))
+        )
+    );
+
+    // Keyed tables are rejected.
+    let keyed = KDict::new(KVal::Table(trades_table(1)), KVal::Table(trades_table(1)));
+    assert_eq!(left.merge(keyed), Err("not a list\0"));
+}
+
+#[test]
+fn dictionary_iter_test() {
+    let mixed = match kval!({
+        "a" => 1,
+        "b" => [1.5, 2.5],
+        "c" => (KVal::String(Cow::Borrowed(b"text"))),
+        "d" => (KVal::Table(trades_table(2)))
+    }) {
+        KVal::Dictionary(mixed) => mixed,
+        _ => unreachable!(),
+    };
+    let raw = KVal::Dictionary(mixed).to_k();
+    let dictionary = KDict::from_raw(raw);
+
+    // Borrowed iteration yields symbol atoms paired with the values.
+    let pairs = dictionary.iter().collect::<Vec<_>>();
+    assert_eq!(pairs.len(), 4);
+    assert_eq!(pairs[0], (kval!("a"), kval!(1)));
+    assert_eq!(pairs[1], (kval!("b"), kval!([1.5, 2.5])));
+    assert_eq!(pairs[2].1, KVal::String(Cow::Borrowed(b"text")));
+    assert_eq!(pairs[3].1.qtype(), qtype::TABLE);
+    assert_eq!(
+        dictionary.keys_iter().collect::<Vec<_>>(),
+        vec![kval!("a"), kval!("b"), kval!("c"), kval!("d")]
+    );
+    assert_eq!(dictionary.values_iter().len(), 4);
+    assert_eq!(dictionary.iter().next_back().unwrap().0, kval!("d"));
+    let mut count = 0;
+    for (key, _) in &dictionary {
+        assert_eq!(key.qtype(), -qtype::SYMBOL_LIST);
+        count += 1;
+    }
+    assert_eq!(count, 4);
+
+    // Consuming iteration takes over the elements.
+    let owned = KDict::from_raw(raw).into_iter().collect::<Vec<_>>();
+    assert_eq!(owned, pairs);
+    decrement_reference_count(raw);
+    let prices = KDict::new(kval!(["a", "b"]), kval!([1.5, 2.5]));
+    assert_eq!(
+        prices.into_iter().collect::<Vec<_>>(),
+        vec![(kval!("a"), kval!(1.5)), (kval!("b"), kval!(2.5))]
+    );
+
+    // A keyed table yields nothing.
+    let keyed = KDict::new(KVal::Table(trades_table(2)), KVal::Table(trades_table(2)));
+    assert_eq!(keyed.iter().count(), 0);
+    assert_eq!(keyed.into_iter().count(), 0);
+}
+
+#[test]
+fn dictionary_map_test() {
+    // Symbol keys and mixed values through q.
+    let raw = kval!({
+        "port" => 5001,
+        "host" => "feed",
+        "weights" => [0.5, 1.5],
+        "name" => (KVal::String(Cow::Borrowed(b"primary")))
+    })
+    .to_k();
+    let config = KDict::from_raw(raw);
+    let map = config.to_hashmap::<String, KVal>().unwrap();
+    assert_eq!(map.len(), 4);
+    assert_eq!(map["port"], kval!(5001));
+    assert_eq!(map["weights"], kval!([0.5, 1.5]));
+    let names = config
+        .to_btreemap::<String, String>()
+        .map(|map| map.into_keys().collect::<Vec<_>>());
+    assert_eq!(names, Err("type\0"));
+
+    // A sorted dictionary is built from the map and makes a round trip through q.
+    let sorted = KDict::try_from(map).unwrap();
+    assert_eq!(
+        sorted.keys_iter().collect::<Vec<_>>(),
+        vec![
+            kval!("host"),
+            kval!("name"),
+            kval!("port"),
+            kval!("weights")
+        ]
+    );
+    let round_trip = sorted.to_k();
+    let sorted = KDict::from_raw(round_trip);
+    assert_eq!(sorted.get_symbol("host"), Ok("feed".to_string()));
+    assert_eq!(sorted.get_long("port"), Ok(5001));
+    assert_eq!(
+        sorted.get("name"),
+        Some(KVal::String(Cow::Borrowed(b"primary")))
+    );
+    decrement_reference_count(round_trip);
+    decrement_reference_count(raw);
+
+    // Typed maps and the first value of a duplicate key.
+    let limits = KDict::new(kval!(["a", "b", "a"]), kval!([100, 200, 300]));
+    assert_eq!(
+        limits.to_hashmap::<String, i64>(),
+        Ok(HashMap::from([
+            ("a".to_string(), 100),
+            ("b".to_string(), 200)
+        ]))
+    );
+    assert_eq!(limits.to_btreemap::<String, f64>(), Err("type\0"));
+
+    // Values of the same type make a simple list.
+    let collected = vec![("x".to_string(), kval!(1.5)), ("y".to_string(), kval!(2.5))]
+        .into_iter()
+        .collect::<KDict>();
+    assert_eq!(collected, KDict::new(kval!(["x", "y"]), kval!([1.5, 2.5])));
+    let ordered = std::collections::BTreeMap::from([
+        ("b".to_string(), kval!(1)),
+        ("a".to_string(), kval!("x")),
+    ]);
+    assert_eq!(
+        KDict::try_from(ordered),
+        Ok(KDict::new(kval!(["a", "b"]), kval!(["x", 1])))
+    );
+    let invalid = HashMap::from([("a\0b".to_string(), kval!(1))]);
+    assert_eq!(
+        KDict::try_from(invalid),
+        Err("symbol contains a null character\0")
+    );
+
+    // Primitives from atoms.
+    assert_eq!(String::try_from(kval!("sym")), Ok("sym".to_string()));
+    assert_eq!(
+        bool::try_from(KVal::Bool(KData::Atom(Cow::Owned(true)))),
+        Ok(true)
+    );
+    assert_eq!(i64::try_from(kval!(1.5)), Err("not a long\0"));
+    assert_eq!(f64::try_from(kval!(1.5)), Ok(1.5));
+}
+
+#[test]
+fn contains_key_test() {
+    // The first of duplicate keys wins in the same manner as a lookup in q.
+    let raw = KVal::Dictionary(KDict::new(
+        kval!(["verbose", "depth", "verbose"]),
+        kval!([1, 3, 0]),
+    ))
+    .to_k();
+    let options = KDict::from_raw(raw);
+    assert!(options.contains_key("verbose"));
+    assert!(options.contains_key("depth"));
+    assert!(!options.contains_key("limit"));
+    assert_eq!(options.position(&kval!("verbose")), Some(0));
+    assert_eq!(options.position(&kval!("depth")), Some(1));
+    assert_eq!(options.position(&kval!("limit")), None);
+    assert_eq!(options.get_long("verbose"), Ok(1));
+    assert_eq!(options.get_by(&kval!("verbose")), Some(kval!(1)));
+
+    // Only keys are read from a lazy dictionary.
+    let lazy = LazyDict::from_raw(raw);
+    assert!(lazy.contains_key("depth"));
+    assert!(!lazy.contains_key("limit"));
+    decrement_reference_count(raw);
+
+    // Keys other than symbols.
+    let ids = KDict::new(kval!([7, 8, 7]), kval!(["a", "b", "c"]));
+    assert_eq!(ids.position(&kval!(7)), Some(0));
+    assert_eq!(ids.position(&kval!(8)), Some(1));
+    assert_eq!(ids.position(&kval!("a")), None);
+    assert!(!ids.contains_key("a"));
+    let labels = KDict::new(
+        KVal::CompoundList(vec![
+            KVal::String(Cow::Borrowed(b"open")),
+            kval!("close"),
+            KVal::String(Cow::Borrowed(b"close")),
+        ]),
+        kval!([9, 17, 18]),
+    );
+    assert!(labels.contains_key("open"));
+    assert_eq!(
+        labels.position(&KVal::String(Cow::Borrowed(b"close"))),
+        Some(2)
+    );
+    assert_eq!(labels.position(&kval!("close")), Some(1));
+    assert_eq!(labels.get_long("close"), Ok(17));
+}
+
+#[test]
+fn sorted_dictionary_test() {
+    // Keys are sorted together with values.
+    let rates = KDict::new(kval!([10, 0, 5]), kval!([0.03, 0.01, 0.02]));
+    assert!(!rates.is_sorted());
+    let rates = rates.into_sorted().unwrap();
+    assert!(rates.is_sorted());
+    assert_eq!(*rates.keys, kval!([0, 5, 10]));
+    assert_eq!(*rates.values, kval!([0.01, 0.02, 0.03]));
+
+    // A sorted dictionary is built with the sorted attribute on its keys and read back as sorted.
+    let raw = rates.to_k();
+    assert_eq!(raw.get_type(), qtype::SORTED_DICTIONARY);
+    assert_eq!(
+        raw.as_mut_slice::<K>()[0].get_attribute(),
+        qattribute::SORTED
+    );
+    let read = KDict::from_raw(raw);
+    assert!(read.is_sorted());
+    assert_eq!(read, rates);
+    assert!(LazyDict::from_raw(raw).into_dict().is_sorted());
+    assert_eq!(
+        KVal::Dictionary(read).to_q_literal(),
+        "`s#(0 5 10)!0.01 0.02 0.03"
+    );
+    decrement_reference_count(raw);
+
+    // Broken order falls back to a plain dictionary.
+    let mut broken = rates.clone();
+    broken.insert(kval!(1), kval!(0.015)).unwrap();
+    assert!(broken.is_sorted());
+    let raw = broken.to_k();
+    assert_eq!(raw.get_type(), qtype::DICTIONARY);
+    assert_eq!(raw.as_mut_slice::<K>()[0].get_attribute(), qattribute::NONE);
+    decrement_reference_count(raw);
+
+    // Compound values stay compound and sorted keys are not copied.
+    let buffer = [1_i64, 2, 3];
+    let ordered = KDict::new(
+        KVal::Long(KData::List(Cow::Borrowed(&buffer))),
+        kval!(["a", 1, 2.5]),
+    )
+    .into_sorted()
+    .unwrap();
+    assert!(matches!(
+        ordered.keys.as_ref(),
+        KVal::Long(KData::List(Cow::Borrowed(_)))
+    ));
+    let mixed = KDict::new(kval!(["c", "a", "b"]), kval!([1, "x", 2.5]))
+        .into_sorted()
+        .unwrap();
+    assert_eq!(*mixed.values, kval!(["x", 2.5, 1]));
+
+    // Keys which cannot be sorted.
+    let compound = KDict::new(kval!([1, "a"]), kval!([1, 2]));
+    assert_eq!(compound.into_sorted(), Err("type\0"));
+    let keyed = KDict::new(KVal::Table(trades_table(1)), KVal::Table(trades_table(1)));
+    assert_eq!(keyed.into_sorted(), Err("not a list\0"));
+}
+
+#[test]
+fn lazy_table_test() {
+    let raw = KVal::Table(trades_table(1_000)).to_k();
+
+    // Only the columns which are read are converted, and only once.
+    let lazy = match KVal::from_raw_lazy(raw) {
+        LazyKVal::Table(table) => table,
+        other => panic!("expected a table: {:?}", other),
+    };
+    assert_eq!(lazy.len(), 1_000);
+    assert_eq!(lazy.converted(), 0);
+    let price = lazy.get_column("price").unwrap();
+    assert_eq!(price.get(2), Some(kval!(2.5)));
+    assert_eq!(lazy.converted(), 1);
+    assert!(std::ptr::eq(price, lazy.column_at(1).unwrap()));
+    assert_eq!(lazy.converted(), 1);
+    assert!(lazy.get_column("bid").is_none());
+    assert!(lazy.column_at(3).is_none());
+
+    // The eager conversion reads the same column.
+    let eager = KTable::from_raw(raw);
+    assert_eq!(eager.get_column("price").as_ref(), Some(price));
+    assert_eq!(eager.dict.value_at(1).as_ref(), Some(price));
+    assert_eq!(lazy.into_table(), eager);
+
+    // Dictionaries and other objects.
+    let dictionary = KVal::Dictionary(KDict::new(kval!(["a", "b"]), kval!([1, 2]))).to_k();
+    match KVal::from_raw_lazy(dictionary) {
+        LazyKVal::Dictionary(lazy) => assert_eq!(lazy.value_at(1), Some(&kval!(2))),
+        other => panic!("expected a dictionary: {:?}", other),
+    }
+    assert_eq!(
+        KVal::from_raw_lazy(dictionary).into_kval(),
+        KVal::from_raw(dictionary)
+    );
+    assert_eq!(KVal::from_raw_lazy(KNULL).into_kval(), KVal::Null);
+    decrement_reference_count(dictionary);
+    decrement_reference_count(raw);
+}
+
+#[test]
+fn rebuilt_table_soak_test() {
+    let table = trades(10);
+    let keyed = KVal::Dictionary(KDict::new(
+        KVal::Table(trades_table(10).project(Some(&["sym"]), None).unwrap()),
+        KVal::Table(
+            trades_table(10)
+                .project(Some(&["price", "size"]), None)
+                .unwrap(),
+        ),
+    ))
+    .to_k();
+    let live = mock::live_objects();
+
+    // A rebuilt table returned to q and dropped there leaves nothing behind and does not touch the argument.
+    for _ in 0..100_000 {
+        let rebuilt = KTable::from_raw(table).to_k();
+        assert_eq!(rebuilt.get_type(), qtype::TABLE);
+        decrement_reference_count(rebuilt);
+    }
+    for _ in 0..10_000 {
+        let rebuilt = KVal::from_raw(keyed).to_k();
+        assert_eq!(rebuilt.get_type(), qtype::DICTIONARY);
+        decrement_reference_count(rebuilt);
+    }
+    assert_eq!(mock::live_objects(), live);
+    assert_eq!(table.get_refcount(), 0);
+    assert_eq!(keyed.get_refcount(), 0);
+    assert_eq!(KTable::from_raw(table), trades_table(10));
+
+    // A table whose dictionary is marked as sorted is still built into a table.
+    let mut sorted = KTable::from_raw(table);
+    sorted.dict = sorted.dict.into_sorted().unwrap();
+    let rebuilt = sorted.to_k();
+    assert_eq!(rebuilt.get_type(), qtype::TABLE);
+    decrement_reference_count(rebuilt);
+
+    // A failed table frees the dictionary built for it.
+    let ragged = KTable {
+        dict: KDict::new(
+            kval!(["a", "b"]),
+            KVal::CompoundList(vec![kval!([1, 2]), kval!([1])]),
+        ),
+    };
+    assert!(ragged.to_k().is_null());
+    mock::clear_error();
+    assert!(flip(KVal::Long(KData::Atom(Cow::Owned(1))).to_k()).is_null());
+    mock::clear_error();
+    assert_eq!(mock::live_objects(), live);
+
+    decrement_reference_count(keyed);
+    decrement_reference_count(table);
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn nested_access_test() {
+    let config = KDict::new(
+        kval!(["conn", "feeds", "limits"]),
+        kval!([
+            {"host" => "feed", "port" => 5001, "retry" => {"count" => 3, "delay" => 0.5}},
+            [{"name" => "trades"}, {"name" => "quotes"}],
+            [100, 200]
+        ]),
+    );
+
+    // Nested dictionaries and compound lists.
+    assert_eq!(config.get_in(&["conn", "host"]), Some(kval!("feed")));
+    assert_eq!(config.get_in(&["conn", "retry", "delay"]), Some(kval!(0.5)));
+    assert_eq!(
+        config.get_in(&["feeds", "1", "name"]),
+        Some(kval!("quotes"))
+    );
+    assert_eq!(config.get_in(&["limits", "0"]), Some(kval!(100)));
+    assert_eq!(config.get_in(&["limits"]), Some(kval!([100, 200])));
+
+    // Missing steps.
+    assert_eq!(config.get_in(&[]), None);
+    assert_eq!(config.get_in(&["user"]), None);
+    assert_eq!(config.get_in(&["auth", "user"]), None);
+    assert_eq!(config.get_in(&["conn", "retry", "backoff"]), None);
+    assert_eq!(config.get_in(&["feeds", "2", "name"]), None);
+    assert_eq!(config.get_in(&["feeds", "-1", "name"]), None);
+
+    // Wrong intermediate types.
+    assert_eq!(config.get_in(&["conn", "host", "name"]), None);
+    assert_eq!(config.get_in(&["conn", "port", "0"]), None);
+    assert_eq!(config.get_in(&["feeds", "name"]), None);
+    assert_eq!(config.get_in(&["limits", "0", "value"]), None);
+
+    // Typed extraction with defaults.
+    assert_eq!(config.get_in_or(&["conn", "port"], 5000_i64), 5001);
+    assert_eq!(
+        config.get_in_or(&["conn", "user"], String::from("guest")),
+        "guest"
+    );
+    assert_eq!(config.get_in_or(&["conn", "host"], 0_i64), 0);
+    assert_eq!(config.get_in_or(&["conn", "retry", "count"], 1_i64), 3);
+    assert_eq!(
+        config.get_in_or(&["conn", "retry"], KVal::Null),
+        config.get_in(&["conn", "retry"]).unwrap()
+    );
+
+    // The same paths through a q object.
+    let raw = KVal::Dictionary(config.clone()).to_k();
+    let read = KDict::from_raw(raw);
+    assert_eq!(read.get_in(&["feeds", "0", "name"]), Some(kval!("trades")));
+    assert_eq!(read.get_in_or(&["conn", "retry", "delay"], 1.0), 0.5);
+    decrement_reference_count(raw);
+}
+
+#[test]
+fn dictionary_filter_map_test() {
+    let quote = KDict::new(
+        kval!(["px_bid", "px_ask", "qty", "px_src"]),
+        kval!([1.5, 2.5, 100, "feed"]),
+    );
+    let is_price = |key: &KVal, _: &KVal| matches!(key, KVal::Symbol(KData::Atom(key)) if key.starts_with("px_"));
+
+    // Key order is preserved and compound values stay compound.
+    let prices = quote.filter(is_price);
+    assert_eq!(*prices.keys, kval!(["px_bid", "px_ask", "px_src"]));
+    assert_eq!(*prices.values, kval!([1.5, 2.5, "feed"]));
+    let floats = quote.filter(|_, value| matches!(value, KVal::Float(_)));
+    assert_eq!(
+        *floats.values,
+        KVal::CompoundList(vec![kval!(1.5), kval!(2.5)])
+    );
+
+    // Simple lists stay simple lists of the same type even when nothing is kept.
+    let limits = KDict::new(kval!(["a", "b", "c"]), kval!([100, 200, 300]));
+    assert_eq!(
+        limits.filter(|_, value| *value != kval!(200)),
+        KDict::new(kval!(["a", "c"]), kval!([100, 300]))
+    );
+    let none = limits.filter(|_, _| false);
+    assert!(none.is_empty());
+    assert_eq!(none.keys.qtype(), qtype::SYMBOL_LIST);
+    assert_eq!(none.values.qtype(), qtype::LONG_LIST);
+
+    // A sorted dictionary stays sorted.
+    let steps = KDict::new(kval!([0, 5, 10]), kval!([1, 2, 3]))
+        .into_sorted()
+        .unwrap();
+    assert!(steps.filter(|key, _| *key != kval!(5)).is_sorted());
+
+    // Mapped values are upgraded to a compound list when their types differ.
+    let labelled = limits
+        .clone()
+        .map_values(|value| match value {
+            KVal::Long(KData::Atom(long)) if *long > 150 => kval!(*long * 2),
+            _ => kval!("small"),
+        })
+        .unwrap();
+    assert_eq!(*labelled.keys, kval!(["a", "b", "c"]));
+    assert_eq!(*labelled.values, kval!(["small", 400, 600]));
+    let doubled = prices
+        .map_values(|value| match value {
+            KVal::Float(KData::Atom(float)) => kval!(*float * 2.0),
+            other => other,
+        })
+        .unwrap();
+    assert_eq!(*doubled.values, kval!([3.0, 5.0, "feed"]));
+    let uniform = KDict::new(kval!(["a", "b"]), kval!([1, "x"]))
+        .map_values(|_| kval!(0))
+        .unwrap();
+    assert_eq!(*uniform.values, kval!([0, 0]));
+    assert_eq!(uniform.values.qtype(), qtype::LONG_LIST);
+
+    // Keyed tables.
+    let keyed = KDict::new(KVal::Table(trades_table(2)), KVal::Table(trades_table(2)));
+    assert_eq!(keyed.filter(|_, _| false), keyed);
+    assert_eq!(keyed.map_values(|value| value).unwrap_err(), "not a list\0");
+}
+
+#[test]
+fn column_by_name_test() {
+    let table = trades_table(3);
+    assert_eq!(table.column_names(), vec!["sym", "price", "size"]);
+    assert_eq!(table.column_index("price"), Some(1));
+    assert_eq!(table.column_index("bid"), None);
+    assert_eq!(
+        table.get_column_by_name("size", None).unwrap(),
+        kval!([0, 100, 200])
+    );
+    assert_eq!(
+        table.get_column_by_name("size", Some("sym")).unwrap(),
+        kval!([0, 100, 200])
+    );
+
+    // The error names the missing column.
+    let error = table.get_column_by_name("bid", None).unwrap_err();
+    assert_eq!(error, KError::MissingColumns(vec!["bid".to_string()]));
+    assert_eq!(error.to_string(), "missing columns: bid");
+
+    // An enum column passed from q gains its source.
+    let indices = [0_i64, 1, 0];
+    let enumerated = KTable::new(KDict::new(
+        kval!(["sym"]),
+        KVal::CompoundList(vec![KVal::Enum(KData::List(Cow::Borrowed(&indices)), None)]),
+    ));
+    assert_eq!(
+        enumerated.get_column_by_name("sym", Some("sym")).unwrap(),
+        KVal::Enum(
+            KData::List(Cow::Borrowed(&indices)),
+            Some("sym".to_string())
+        )
+    );
+    assert_eq!(
+        enumerated.get_column_by_name("sym", None).unwrap(),
+        KVal::Enum(KData::List(Cow::Borrowed(&indices)), None)
+    );
+
+    // Names are read from a table passed from q.
+    let raw = trades(2);
+    let read = KTable::from_raw(raw);
+    assert_eq!(read.column_names(), vec!["sym", "price", "size"]);
+    assert_eq!(read.column_index("size"), Some(2));
+    decrement_reference_count(raw);
+
+    // A table whose names are not a symbol list.
+    let unnamed = KTable::new(KDict::new(kval!([1, 2]), kval!([[1], [2]])));
+    assert!(unnamed.column_names().is_empty());
+    assert_eq!(unnamed.column_index("1"), None);
+}
+
+#[test]
+fn from_columns_test() {
+    let table = KTable::from_columns(vec![
+        ("sym", kval!(["a", "b", "c"])),
+        ("price", kval!([0.5, 1.5, 2.5])),
+        ("size", kval!([0, 100, 200])),
+    ])
+    .unwrap();
+    assert_eq!(table, trades_table(3));
+
+    // A single column is still held in a compound list.
+    let single = KTable::from_columns(vec![("size", kval!([1, 2]))]).unwrap();
+    assert_eq!(*single.dict.values, KVal::CompoundList(vec![kval!([1, 2])]));
+    let raw = single.to_k();
+    assert_eq!(raw.get_type(), qtype::TABLE);
+    assert_eq!(KTable::from_raw(raw), single);
+    decrement_reference_count(raw);
+
+    // The error names the offending column.
+    let error = KTable::from_columns(vec![
+        ("sym", kval!(["a", "b"])),
+        ("price", kval!([1.5, 2.5])),
+        ("size", kval!([100])),
+    ])
+    .unwrap_err();
+    assert_eq!(error.to_string(), "size: length mismatch: 2 and 1");
+    let error =
+        KTable::from_columns(vec![("sym", kval!(["a", "b"])), ("price", kval!(1.5))]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "price: invalid operation from_columns on float atom. expected: list"
+    );
+    let error = KTable::from_columns(vec![("quotes", KVal::Table(trades_table(1)))]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "quotes: invalid operation from_columns on table. expected: list"
+    );
+    assert!(KTable::from_columns(Vec::new()).unwrap().dict.is_empty());
+
+    // Empty tables of a schema.
+    let empty = KTable::empty(&[
+        ("sym", qtype::SYMBOL_LIST),
+        ("price", qtype::FLOAT_LIST),
+        ("note", qtype::COMPOUND_LIST),
+    ])
+    .unwrap();
+    assert!(empty.is_empty());
+    assert_eq!(empty.column_names(), vec!["sym", "price", "note"]);
+    assert_eq!(
+        empty.get_column("price").unwrap().qtype(),
+        qtype::FLOAT_LIST
+    );
+    let raw = empty.to_k();
+    assert_eq!(raw.get_type(), qtype::TABLE);
+    decrement_reference_count(raw);
+    assert_eq!(
+        KTable::empty(&[("sym", qtype::ENUM_LIST)]).unwrap_err().to_string(),
+        "invalid operation empty on enum list. expected: simple list type except enum or compound list type"
+    );
+    assert!(KTable::empty(&[("price", qtype::FLOAT_ATOM)]).is_err());
+}
+
+#[test]
+fn append_row_test() {
+    // Rows in column order and dictionaries in any order build the same table as q.
+    let mut table = KTable::empty(&[
+        ("sym", qtype::SYMBOL_LIST),
+        ("price", qtype::FLOAT_LIST),
+        ("size", qtype::LONG_LIST),
+    ])
+    .unwrap();
+    table.append_row(kval!(["a", 0.5, 0])).unwrap();
+    table
+        .append_row(kval!({"size" => 100, "sym" => "b", "price" => 1.5}))
+        .unwrap();
+    table.append_row(kval!(["c", 2.5, 200])).unwrap();
+    let raw = trades(3);
+    assert_eq!(table, KTable::from_raw(raw));
+
+    // Rows appended to a table passed from q.
+    let mut extended = KTable::from_raw(raw);
+    extended.append_row(kval!(["a", 3.5, 300])).unwrap();
+    assert_eq!(extended, trades_table(4));
+    decrement_reference_count(raw);
+
+    // A mismatched type upgrades the column to a compound list.
+    table.append_row(kval!(["d", 3.5, 1.5])).unwrap();
+    assert_eq!(table.get_column("size"), Some(kval!([0, 100, 200, 1.5])));
+    assert_eq!(
+        table.get_column("price").unwrap().qtype(),
+        qtype::FLOAT_LIST
+    );
+    table.append_row(kval!(["e", [1.0, 2.0], 1])).unwrap();
+    assert_eq!(table.len(), 5);
+    assert_eq!(
+        table.get_column("price").unwrap().qtype(),
+        qtype::COMPOUND_LIST
+    );
+
+    // Errors identify the row and the column, and leave the table unchanged.
+    let before = table.clone();
+    let error = table
+        .append_row(kval!({"sym" => "f", "price" => 1.0}))
+        .unwrap_err();
+    assert_eq!(error.to_string(), "invalid row 5: missing columns: size");
+    let error = table
+        .append_row(kval!({"sym" => "f", "price" => 1.0, "size" => 1, "bid" => 1.0}))
+        .unwrap_err();
+    assert_eq!(error.to_string(), "invalid row 5: unknown column bid");
+    let error = table.append_row(kval!(["f", 1.0])).unwrap_err();
+    assert_eq!(error.to_string(), "invalid row 5: 2 values for 3 columns");
+    let error = table.append_row(kval!(1)).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid row 5: long atom is neither a dictionary nor a compound list"
+    );
+    assert_eq!(table, before);
+
+    // Enum columns validate the source.
+    let indices = [0_i64, 1];
+    let mut enumerated = KTable::new(KDict::new(
+        kval!(["sym", "size"]),
+        KVal::CompoundList(vec![
+            KVal::Enum(
+                KData::List(Cow::Borrowed(&indices)),
+                Some("sym".to_string()),
+            ),
+            kval!([1, 2]),
+        ]),
+    ));
+    let same = KVal::Enum(KData::Atom(Cow::Owned(1)), Some("sym".to_string()));
+    enumerated
+        .append_row(KVal::CompoundList(vec![same, kval!(3)]))
+        .unwrap();
+    assert_eq!(enumerated.len(), 3);
+    let other = KVal::Enum(KData::Atom(Cow::Owned(1)), Some("venue".to_string()));
+    let error = enumerated
+        .append_row(KVal::CompoundList(vec![other, kval!(4)]))
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid row 3: enum sources differ in column sym"
+    );
+    let error = enumerated.append_row(kval!(["a", 4])).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid row 3: symbol atom in column sym of enum list"
+    );
+    assert_eq!(enumerated.len(), 3);
+
+    // A keyed table is not a table.
+    let mut keyed = KTable::new(KDict::new(
+        KVal::Table(trades_table(1)),
+        KVal::Table(trades_table(1)),
+    ));
+    assert!(matches!(
+        keyed.append_row(kval!(["a", 1.0, 1])),
+        Err(KError::InvalidOperation { .. })
+    ));
+}
+
+#[test]
+fn table_append_union_test() {
+    // Append joins the columns of the same position.
+    let appended = trades_table(2).append(trades_table(3)).unwrap();
+    assert_eq!(appended.len(), 5);
+    assert_eq!(
+        appended.get_column("price").unwrap(),
+        kval!([0.5, 1.5, 0.5, 1.5, 2.5])
+    );
+
+    // Conflicting types result in a compound column.
+    let floats = KTable::from_columns(vec![("size", kval!([1.5]))]).unwrap();
+    let longs = KTable::from_columns(vec![("size", kval!([100]))]).unwrap();
+    let upgraded = longs.clone().append(floats.clone()).unwrap();
+    assert_eq!(
+        upgraded.get_column("size").unwrap(),
+        KVal::CompoundList(vec![kval!(100), kval!(1.5)])
+    );
+
+    // Append requires the same columns in the same order.
+    let reordered = KTable::from_columns(vec![
+        ("price", kval!([1.0])),
+        ("sym", kval!(["a"])),
+        ("size", kval!([1])),
+    ])
+    .unwrap();
+    let error = trades_table(1).append(reordered.clone()).unwrap_err();
+    assert_eq!(error.to_string(), "mismatch");
+
+    // Union aligns the columns by name and fills the missing ones with nulls.
+    let union = trades_table(2).union(reordered).unwrap();
+    assert_eq!(union.column_names(), vec!["sym", "price", "size"]);
+    assert_eq!(union.get_column("price").unwrap(), kval!([0.5, 1.5, 1.0]));
+    let extra = KTable::from_columns(vec![
+        ("sym", kval!(["c"])),
+        ("bid", kval!([2.5])),
+        ("flag", kval!([true])),
+        (
+            "note",
+            KVal::CompoundList(vec![KVal::String(Cow::Borrowed(b"x"))]),
+        ),
+    ])
+    .unwrap();
+    let union = trades_table(2).union(extra).unwrap();
+    assert_eq!(union.len(), 3);
+    assert_eq!(
+        union.column_names(),
+        vec!["sym", "price", "size", "bid", "flag", "note"]
+    );
+    assert_eq!(union.get_column("sym").unwrap(), kval!(["a", "b", "c"]));
+    assert_eq!(
+        union.get_column("size").unwrap(),
+        KVal::Long(KData::List(Cow::Owned(vec![0, 100, qnull_base::J])))
+    );
+    let bid = union.get_column("bid").unwrap();
+    assert!(matches!(&bid, KVal::Float(KData::List(bids)) if bids[0].is_nan() && bids[2] == 2.5));
+    assert_eq!(
+        union.get_column("flag").unwrap(),
+        kval!([false, false, true])
+    );
+    assert_eq!(
+        union.get_column("note").unwrap(),
+        KVal::CompoundList(vec![
+            KVal::String(Cow::Borrowed(b"")),
+            KVal::String(Cow::Borrowed(b"")),
+            KVal::String(Cow::Borrowed(b"x")),
+        ])
+    );
+
+    // Union upgrades conflicting columns and keeps an empty side typed.
+    let upgraded = longs.union(floats.clone()).unwrap();
+    assert_eq!(upgraded.get_column("size").unwrap().qtype(), 0);
+    let empty = KTable::empty(&[("sym", qtype::SYMBOL_LIST)]).unwrap();
+    let union = empty.union(floats).unwrap();
+    assert_eq!(union.get_column("sym").unwrap(), kval!([""]));
+    assert_eq!(union.get_column("size").unwrap(), kval!([1.5]));
+
+    // A keyed table is not a table.
+    let keyed = KTable::new(KDict::new(
+        KVal::Table(trades_table(1)),
+        KVal::Table(trades_table(1)),
+    ));
+    assert!(matches!(
+        keyed.union(trades_table(1)),
+        Err(KError::InvalidOperation { .. })
+    ));
+}
+
+#[test]
+fn table_rows_test() {
+    let table = trades(3000);
+    let borrowed = KTable::from_raw(table);
+
+    // A page borrows the columns of the table.
+    let page = borrowed.get_rows(1000..2000).unwrap();
+    assert_eq!(page.len(), 1000);
+    assert_eq!(page.column_names(), vec!["sym", "price", "size"]);
+    match (
+        page.get_column("size").unwrap(),
+        borrowed.get_column("size").unwrap(),
+    ) {
+        (KVal::Long(KData::List(Cow::Borrowed(page))), KVal::Long(KData::List(all))) => {
+            assert_eq!(page, &all[1000..2000]);
+        }
+        _ => panic!("page does not borrow the column"),
+    }
+    assert!(borrowed.get_rows(3000..3000).unwrap().is_empty());
+    assert_eq!(
+        borrowed.get_rows(2500..3001).unwrap_err(),
+        "index out of bounds\0"
+    );
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = borrowed.get_rows(2..1);
+    assert_eq!(reversed.unwrap_err(), "index out of bounds\0");
+
+    // Rows are picked in the order of the indices and an index out of bounds picks nulls.
+    let picked = borrowed.take_rows(&[2, 0, 2, 3000, -1]).unwrap();
+    assert_eq!(picked.len(), 5);
+    assert_eq!(
+        picked.get_column("sym").unwrap(),
+        kval!(["c", "a", "c", "", ""])
+    );
+    assert_eq!(
+        picked.get_column("size").unwrap(),
+        KVal::Long(KData::List(Cow::Owned(vec![
+            200,
+            0,
+            200,
+            qnull_base::J,
+            qnull_base::J
+        ])))
+    );
+    assert!(borrowed.take_rows(&[]).unwrap().is_empty());
+    decrement_reference_count(table);
+
+    // Enum sources, bool and compound columns.
+    let indices = [1_i64, 0];
+    let mixed = KTable::from_columns(vec![
+        (
+            "sym",
+            KVal::Enum(
+                KData::List(Cow::Borrowed(&indices)),
+                Some("sym".to_string()),
+            ),
+        ),
+        ("flag", kval!([true, false])),
+        (
+            "note",
+            KVal::CompoundList(vec![
+                KVal::String(Cow::Borrowed(b"x")),
+                KVal::String(Cow::Borrowed(b"yy")),
+            ]),
+        ),
+    ])
+    .unwrap();
+    let picked = mixed.take_rows(&[1, 2]).unwrap();
+    assert_eq!(
+        picked.get_column("sym").unwrap(),
+        KVal::Enum(
+            KData::List(Cow::Owned(vec![0, qnull_base::J])),
+            Some("sym".to_string())
+        )
+    );
+    assert_eq!(picked.get_column("flag").unwrap(), kval!([false, false]));
+    assert_eq!(
+        picked.get_column("note").unwrap(),
+        KVal::CompoundList(vec![
+            KVal::String(Cow::Borrowed(b"yy")),
+            KVal::String(Cow::Borrowed(b""))
+        ])
+    );
+    let sliced = mixed.get_rows(1..2).unwrap();
+    assert!(
+        matches!(sliced.get_column("sym"), Some(KVal::Enum(_, Some(source))) if source == "sym")
+    );
+
+    // A char column is picked by bytes and an index out of bounds gives a blank.
+    let grades =
+        KTable::from_columns(vec![("grade", KVal::String(Cow::Borrowed(b"\xe9a\xff")))]).unwrap();
+    assert_eq!(
+        grades
+            .take_rows(&[2, 0, 3])
+            .unwrap()
+            .get_column("grade")
+            .unwrap(),
+        KVal::String(Cow::Borrowed(b"\xff\xe9 "))
+    );
+    assert_eq!(
+        grades
+            .where_indices(&[0, 2])
+            .unwrap()
+            .get_column("grade")
+            .unwrap(),
+        KVal::String(Cow::Borrowed(b"\xe9\xff"))
+    );
+
+    // A keyed table is not a table.
+    let keyed = KTable::new(KDict::new(
+        KVal::Table(trades_table(1)),
+        KVal::Table(trades_table(1)),
+    ));
+    assert_eq!(keyed.take_rows(&[0]).unwrap_err(), "type\0");
+}
+
+#[test]
+fn table_cell_test() {
+    let indices = [1_i64, 2];
+    let table = KTable::from_columns(vec![
+        ("time", KVal::Timestamp(KData::List(Cow::Owned(vec![1, 2])))),
+        (
+            "sym",
+            KVal::Enum(KData::List(Cow::Borrowed(&indices)), None),
+        ),
+        ("go", KVal::String(Cow::Borrowed(b"ox"))),
+        (
+            "miscellaneous",
+            KVal::CompoundList(vec![KVal::String(Cow::Borrowed(b"cow")), kval!("lion")]),
+        ),
+    ])
+    .unwrap();
+
+    assert_eq!(
+        table.get_cell(1, "time", None).unwrap(),
+        KVal::Timestamp(KData::Atom(Cow::Owned(2)))
+    );
+    assert_eq!(
+        table.get_cell(1, "sym", Some("sym")).unwrap(),
+        KVal::Enum(KData::Atom(Cow::Owned(2)), Some("sym".to_string()))
+    );
+    assert_eq!(
+        table.get_cell(0, "sym", None).unwrap(),
+        KVal::Enum(KData::Atom(Cow::Owned(1)), None)
+    );
+    assert_eq!(
+        table.get_cell(1, "go", Some("sym")).unwrap(),
+        KVal::Char('x')
+    );
+    assert_eq!(
+        table.get_cell(0, "miscellaneous", None).unwrap(),
+        KVal::String(Cow::Borrowed(b"cow"))
+    );
+    assert_eq!(
+        table.get_cell(1, "miscellaneous", None).unwrap(),
+        kval!("lion")
+    );
+
+    // A simple column is borrowed rather than copied.
+    match table.get_cell(0, "time", None).unwrap() {
+        KVal::Timestamp(KData::Atom(Cow::Borrowed(_))) => {}
+        other => panic!("cell is not borrowed: {:?}", other),
+    }
+
+    assert_eq!(
+        table.get_cell(2, "go", None).unwrap_err(),
+        "index out of bounds\0"
+    );
+    assert_eq!(
+        table.get_cell(-1, "go", None).unwrap_err(),
+        "index out of bounds\0"
+    );
+    assert_eq!(
+        table.get_cell(0, "size", None).unwrap_err(),
+        "no such column\0"
+    );
+}
+
+#[test]
+fn table_sort_by_test() {
+    let table = KTable::from_columns(vec![
+        ("sym", kval!(["b", "a", "", "b", "a", "c"])),
+        (
+            "price",
+            KVal::Float(KData::List(Cow::Owned(vec![
+                1.0,
+                f64::NAN,
+                2.0,
+                f64::NEG_INFINITY,
+                3.0,
+                2.0,
+            ]))),
+        ),
+        (
+            "size",
+            KVal::Long(KData::List(Cow::Owned(vec![qnull_base::J, 5, 3, 1, 5, 2]))),
+        ),
+        (
+            "note",
+            KVal::CompoundList(
+                ["bb", "a", "", "ab", "a", "c"]
+                    .iter()
+                    .map(|note| KVal::String(Cow::Borrowed(note.as_bytes())))
+                    .collect(),
+            ),
+        ),
+    ])
+    .unwrap();
+    let size = |sorted: &SortedTable| match sorted.get_column("size").unwrap() {
+        KVal::Long(KData::List(sizes)) => sizes.to_vec(),
+        _ => panic!("size is not a long list"),
+    };
+
+    // Float nulls come first and ties keep the order of the rows.
+    let sorted = table.sort_by(&[("price", false)]).unwrap();
+    assert_eq!(size(&sorted), vec![5, 1, qnull_base::J, 3, 2, 5]);
+
+    // Nulls come last in descending order.
+    let sorted = table.sort_by(&[("size", true)]).unwrap();
+    assert_eq!(size(&sorted), vec![5, 5, 3, 2, 1, qnull_base::J]);
+    assert_eq!(
+        sorted.get_column("sym").unwrap(),
+        kval!(["a", "a", "", "c", "b", "b"])
+    );
+
+    // Ties of the first column are ordered by the next one.
+    let sorted = table.sort_by(&[("sym", false), ("price", true)]).unwrap();
+    assert_eq!(
+        sorted.get_column("sym").unwrap(),
+        kval!(["", "a", "a", "b", "b", "c"])
+    );
+    assert_eq!(size(&sorted), vec![3, 5, 5, qnull_base::J, 1, 2]);
+
+    // A compound column of strings is ordered lexicographically.
+    let sorted = table.sort_by(&[("note", false), ("size", false)]).unwrap();
+    assert_eq!(size(&sorted), vec![3, 5, 5, 1, qnull_base::J, 2]);
+
+    // A char column is ordered by bytes.
+    let chars = KTable::from_columns(vec![
+        ("grade", KVal::String(Cow::Borrowed(b"\xe9a\xc3"))),
+        ("size", KVal::Long(KData::List(Cow::Owned(vec![1, 2, 3])))),
+    ])
+    .unwrap();
+    let sorted = chars.sort_by(&[("grade", false)]).unwrap();
+    assert_eq!(size(&sorted), vec![2, 3, 1]);
+
+    // The first ascending column carries the sorted attribute.
+    for (descending, attribute) in [(false, qattribute::SORTED), (true, qattribute::NONE)] {
+        let k = table.sort_by(&[("price", descending)]).unwrap().to_k();
+        let columns = unsafe { (*k).value.table }.as_mut_slice::<K>()[1].as_mut_slice::<K>();
+        assert_eq!(columns[1].get_attribute(), attribute);
+        assert_eq!(columns[0].get_attribute(), qattribute::NONE);
+        decrement_reference_count(k);
+    }
+
+    assert_eq!(
+        table.sort_by(&[("bid", false)]).unwrap_err(),
+        "no such column\0"
+    );
+    let indices = [1_i64, 0];
+    let enumerated = KTable::from_columns(vec![(
+        "sym",
+        KVal::Enum(KData::List(Cow::Borrowed(&indices)), None),
+    )])
+    .unwrap();
+    assert_eq!(enumerated.sort_by(&[("sym", false)]).unwrap_err(), "type\0");
+
+    // Columns of different lengths are rejected, whichever column is sorted.
+    let ragged = KTable {
+        dict: KDict::new(
+            kval!(["sym", "size"]),
+            KVal::CompoundList(vec![kval!(["b", "a", "c"]), kval!([2, 1])]),
+        ),
+    };
+    for name in ["sym", "size"] {
+        assert_eq!(ragged.sort_by(&[(name, false)]).unwrap_err(), "length\0");
+    }
+}
+
+#[test]
+fn table_filter_test() {
+    let table = trades_table(10);
+
+    // Rows are passed as dictionaries of the column names and the elements.
+    let selected = table
+        .filter(|row| match row {
+            KVal::Dictionary(row) => {
+                row.get_symbol("sym").as_deref() == Ok("b")
+                    && row.get_long("size").is_ok_and(|size| size > 100)
+            }
+            _ => false,
+        })
+        .unwrap();
+    assert_eq!(selected.get_column("price").unwrap(), kval!([4.5, 7.5]));
+    assert!(table.filter(|_| false).unwrap().is_empty());
+    assert_eq!(table.filter(|_| true).unwrap(), table);
+
+    // Indices computed over a column select the same rows.
+    let indices = match table.get_column("sym").unwrap() {
+        KVal::Symbol(KData::List(symbols)) => symbols
+            .iter()
+            .enumerate()
+            .filter_map(|(i, symbol)| (symbol == "b" && i > 1).then_some(i as i64))
+            .collect::<Vec<_>>(),
+        _ => panic!("sym is not a symbol list"),
+    };
+    assert_eq!(table.where_indices(&indices).unwrap(), selected);
+    assert_eq!(
+        table
+            .where_indices(&[9, 0])
+            .unwrap()
+            .get_column("size")
+            .unwrap(),
+        kval!([900, 0])
+    );
+    assert_eq!(
+        table.where_indices(&[10]).unwrap_err(),
+        "index out of bounds\0"
+    );
+    assert_eq!(
+        table.where_indices(&[-1]).unwrap_err(),
+        "index out of bounds\0"
+    );
+
+    // Columns of different lengths are rejected before any row is passed, whichever column is shorter.
+    for columns in [
+        vec![kval!(["a", "b"]), kval!([1])],
+        vec![kval!(["a"]), kval!([1, 2])],
+    ] {
+        let ragged = KTable {
+            dict: KDict::new(kval!(["sym", "size"]), KVal::CompoundList(columns)),
+        };
+        assert_eq!(
+            ragged.filter(|_| panic!("no row is passed")).unwrap_err(),
+            "length\0"
+        );
+    }
+
+    let keyed = KTable::new(KDict::new(
+        KVal::Table(trades_table(1)),
+        KVal::Table(trades_table(1)),
+    ));
+    assert_eq!(keyed.filter(|_| true).unwrap_err(), "type\0");
+}
+
+#[test]
+fn table_join_on_test() {
+    let orders = KTable::from_columns(vec![
+        ("sym", kval!(["a", "b", "c", "a", "d"])),
+        ("id", kval!([1, 2, 3, 1, 5])),
+        ("size", kval!([100, 200, 300, 400, 500])),
+    ])
+    .unwrap();
+    let sources = [0_i64, 1, 2, 1];
+    let venues = KTable::from_columns(vec![
+        ("sym", kval!(["a", "c", "e", "c"])),
+        (
+            "venue",
+            KVal::Enum(
+                KData::List(Cow::Borrowed(&sources)),
+                Some("venue".to_string()),
+            ),
+        ),
+        ("fee", kval!([0.1, 0.2, 0.3, 0.4])),
+    ])
+    .unwrap();
+
+    // Every row is kept, the last match wins and a row without a match is filled with nulls.
+    let joined = orders.left_join(&venues, &["sym"]).unwrap();
+    assert_eq!(
+        joined.column_names(),
+        vec!["sym", "id", "size", "venue", "fee"]
+    );
+    assert_eq!(joined.len(), 5);
+    assert_eq!(
+        joined.get_column("venue").unwrap(),
+        KVal::Enum(
+            KData::List(Cow::Owned(vec![0, qnull_base::J, 1, 0, qnull_base::J])),
+            Some("venue".to_string())
+        )
+    );
+    match joined.get_column("fee").unwrap() {
+        KVal::Float(KData::List(fees)) => {
+            assert_eq!((fees[0], fees[2], fees[3]), (0.1, 0.4, 0.1));
+            assert!(fees[1].is_nan() && fees[4].is_nan());
+        }
+        _ => panic!("fee is not a float list"),
+    }
+    // Columns of the left table are borrowed.
+    assert!(matches!(
+        joined.get_column("size"),
+        Some(KVal::Long(KData::List(Cow::Borrowed(_))))
+    ));
+
+    // Only matching rows are kept.
+    let joined = orders.inner_join(&venues, &["sym"]).unwrap();
+    assert_eq!(joined.get_column("sym").unwrap(), kval!(["a", "c", "a"]));
+    assert_eq!(joined.get_column("size").unwrap(), kval!([100, 300, 400]));
+    assert_eq!(joined.get_column("fee").unwrap(), kval!([0.1, 0.4, 0.1]));
+
+    // Keys of more than one column.
+    let limits = KTable::from_columns(vec![
+        ("id", kval!([1, 2, 3])),
+        ("sym", kval!(["a", "b", "a"])),
+        ("limit", kval!([10, 20, 30])),
+    ])
+    .unwrap();
+    let joined = orders.inner_join(&limits, &["sym", "id"]).unwrap();
+    assert_eq!(joined.get_column("size").unwrap(), kval!([100, 200, 400]));
+    assert_eq!(joined.get_column("limit").unwrap(), kval!([10, 20, 10]));
+    let joined = orders.left_join(&limits, &["sym", "id"]).unwrap();
+    assert_eq!(
+        joined.get_column("limit").unwrap(),
+        KVal::Long(KData::List(Cow::Owned(vec![
+            10,
+            20,
+            qnull_base::J,
+            10,
+            qnull_base::J
+        ])))
+    );
+
+    assert_eq!(
+        orders.left_join(&venues, &["id"]).unwrap_err(),
+        "no such column\0"
+    );
+    assert_eq!(
+        orders.inner_join(&limits, &["sym"]).unwrap_err(),
+        "duplicate column\0"
+    );
+
+    // A key column shorter or longer than the first column is rejected on either side.
+    let ragged = |sym: KVal<'static>, size: KVal<'static>| KTable {
+        dict: KDict::new(kval!(["size", "sym"]), KVal::CompoundList(vec![size, sym])),
+    };
+    for table in [
+        ragged(kval!(["a"]), kval!([1, 2])),
+        ragged(kval!(["a", "b", "c"]), kval!([1, 2])),
+    ] {
+        assert_eq!(table.left_join(&venues, &["sym"]).unwrap_err(), "length\0");
+        assert_eq!(table.inner_join(&venues, &["sym"]).unwrap_err(), "length\0");
+        assert_eq!(venues.left_join(&table, &["sym"]).unwrap_err(), "length\0");
+    }
+}
+
+#[test]
+fn keyed_table_test() {
+    let table = trades_table(3);
+
+    // The first columns become the keys.
+    let keyed = table.clone().enkey(2).unwrap();
+    assert_eq!(keyed.keys().column_names(), vec!["sym", "price"]);
+    assert_eq!(keyed.values().column_names(), vec!["size"]);
+    assert_eq!(keyed.len(), 3);
+    assert_eq!(table.clone().enkey(0).unwrap_err(), "length\0");
+    assert_eq!(table.clone().enkey(3).unwrap_err(), "length\0");
+
+    // Rows are found by a list, a dictionary or an atom of keys.
+    assert_eq!(keyed.lookup(&kval!(["b", 1.5])), Some(1));
+    assert_eq!(
+        keyed.lookup(&kval!({"price" => 2.5, "sym" => "c"})),
+        Some(2)
+    );
+    assert_eq!(keyed.lookup(&kval!(["b", 2.5])), None);
+    assert_eq!(keyed.lookup(&kval!("b")), None);
+    assert_eq!(keyed.lookup(&kval!({"sym" => "b"})), None);
+    let by_sym = table.clone().enkey(1).unwrap();
+    assert_eq!(by_sym.lookup(&kval!("c")), Some(2));
+
+    // A keyed table is a dictionary of two tables in q.
+    let k = keyed.to_k();
+    assert_eq!(k.get_type(), qtype::DICTIONARY);
+    let pair = k.as_mut_slice::<K>();
+    assert_eq!(pair[0].get_type(), qtype::TABLE);
+    assert_eq!(pair[1].get_type(), qtype::TABLE);
+    let converted = KVal::from_raw(k);
+    assert!(
+        matches!(&converted, KVal::Dictionary(dictionary) if matches!(dictionary.keys.as_ref(), KVal::Table(_)))
+    );
+    let round_trip = KKeyedTable::try_from(converted).unwrap();
+    assert_eq!(round_trip, keyed);
+    assert_eq!(KKeyedTable::from_raw(k), keyed);
+    decrement_reference_count(k);
+
+    // Unkeying restores the table.
+    assert_eq!(
+        KVal::from(keyed.clone()),
+        KVal::Dictionary(KDict::new(
+            KVal::Table(keyed.keys().clone()),
+            KVal::Table(keyed.values().clone()),
+        ))
+    );
+    assert_eq!(keyed.unkey(), table);
+    assert_eq!(
+        KKeyedTable::try_from(KVal::Table(table)).unwrap_err(),
+        "type\0"
+    );
+    assert_eq!(
+        KKeyedTable::try_from(kval!({"a" => 1})).unwrap_err(),
+        "type\0"
+    );
+}
+
+#[test]
+fn table_upsert_test() {
+    let updates = vec![
+        (vec!["b", "d"], vec![20, 40]),
+        (vec!["a", "d", "e", "a"], vec![10, 41, 50, 11]),
+        (vec![], vec![]),
+        (vec!["c", "f", "f"], vec![30, 60, 61]),
+    ];
+
+    // Replay the stream and keep the expected state by a linear search.
+    let mut table = KTable::from_columns(vec![
+        ("sym", kval!(["a", "b", "c"])),
+        ("size", kval!([1, 2, 3])),
+    ])
+    .unwrap();
+    let mut keyed = table.clone().enkey(1).unwrap();
+    let mut expected = vec![("a", 1_i64), ("b", 2), ("c", 3)];
+    for (syms, sizes) in updates {
+        let rows = KTable::from_columns(vec![
+            (
+                "sym",
+                KVal::Symbol(KData::List(Cow::Owned(
+                    syms.iter().map(|sym| sym.to_string()).collect(),
+                ))),
+            ),
+            ("size", KVal::Long(KData::List(Cow::Owned(sizes.clone())))),
+        ])
+        .unwrap();
+        table.upsert(&["sym"], rows.clone()).unwrap();
+        keyed.upsert(rows).unwrap();
+        for (sym, size) in syms.into_iter().zip(sizes) {
+            match expected.iter_mut().find(|(key, _)| *key == sym) {
+                Some(row) => row.1 = size,
+                None => expected.push((sym, size)),
+            }
+        }
+    }
+    let (syms, sizes): (Vec<&str>, Vec<i64>) = expected.into_iter().unzip();
+    assert_eq!(
+        table.get_column("sym").unwrap(),
+        KVal::Symbol(KData::List(Cow::Owned(
+            syms.iter().map(|sym| sym.to_string()).collect()
+        )))
+    );
+    assert_eq!(
+        table.get_column("size").unwrap(),
+        KVal::Long(KData::List(Cow::Owned(sizes)))
+    );
+    assert_eq!(keyed.clone().unkey(), table);
+
+    // Rows are checked before the table is modified.
+    let before = table.clone();
+    let swapped = KTable::from_columns(vec![("size", kval!([1])), ("sym", kval!(["a"]))]).unwrap();
+    assert_eq!(
+        table.upsert(&["sym"], swapped.clone()).unwrap_err(),
+        "mismatch\0"
+    );
+    let floats = KTable::from_columns(vec![("sym", kval!(["a"])), ("size", kval!([1.5]))]).unwrap();
+    assert_eq!(
+        table.upsert(&["sym"], floats.clone()).unwrap_err(),
+        "type\0"
+    );
+    let rows = KTable::from_columns(vec![("sym", kval!(["z"])), ("size", kval!([9]))]).unwrap();
+    assert_eq!(
+        table.upsert(&["id"], rows.clone()).unwrap_err(),
+        "no such column\0"
+    );
+    assert_eq!(table.upsert(&[], rows.clone()).unwrap_err(), "length\0");
+    let ragged = KTable {
+        dict: KDict::new(
+            kval!(["sym", "size"]),
+            KVal::CompoundList(vec![kval!(["a", "z"]), kval!([9])]),
+        ),
+    };
+    assert_eq!(
+        table.upsert(&["sym"], ragged.clone()).unwrap_err(),
+        "length\0"
+    );
+    let compound = KTable::from_columns(vec![
+        ("sym", kval!(["z"])),
+        ("size", KVal::CompoundList(vec![KVal::Null])),
+    ])
+    .unwrap();
+    assert_eq!(
+        table.upsert(&["sym"], compound.clone()).unwrap_err(),
+        "type\0"
+    );
+    assert_eq!(table, before);
+
+    // A keyed table is checked in the same manner and keeps the index of its rows across the errors.
+    let keyed_before = keyed.clone();
+    assert_eq!(keyed.upsert(swapped).unwrap_err(), "mismatch\0");
+    assert_eq!(keyed.upsert(floats).unwrap_err(), "type\0");
+    assert_eq!(keyed.upsert(ragged).unwrap_err(), "length\0");
+    assert_eq!(keyed.upsert(compound).unwrap_err(), "type\0");
+    assert_eq!(keyed, keyed_before);
+    keyed.upsert(rows).unwrap();
+    assert_eq!(keyed.lookup(&kval!("z")), Some(keyed.len() - 1));
+
+    // Keys modified through keys_mut are indexed again, even if the number of rows does not change.
+    *keyed.keys_mut().dict.values = KVal::CompoundList(vec![kval!(["a", "z", "y"])]);
+    *keyed.values_mut().dict.values = KVal::CompoundList(vec![kval!([1, 2, 3])]);
+    assert_eq!(keyed.lookup(&kval!("y")), Some(2));
+    if let KVal::CompoundList(columns) = keyed.keys_mut().dict.values.as_mut() {
+        columns[0].set(2, kval!("w")).unwrap();
+    }
+    assert_eq!(keyed.lookup(&kval!("y")), None);
+    assert_eq!(keyed.lookup(&kval!("w")), Some(2));
+    let rows =
+        KTable::from_columns(vec![("sym", kval!(["w", "y"])), ("size", kval!([30, 4]))]).unwrap();
+    keyed.upsert(rows.clone()).unwrap();
+    assert_eq!(
+        keyed.keys().get_column("sym").unwrap(),
+        kval!(["a", "z", "w", "y"])
+    );
+    assert_eq!(
+        keyed.values().get_column("size").unwrap(),
+        kval!([1, 2, 30, 4])
+    );
+    let mut ragged = keyed.clone();
+    *ragged.keys_mut().dict.values = KVal::CompoundList(vec![kval!(["a"])]);
+    assert_eq!(ragged.lookup(&kval!("a")), None);
+    assert_eq!(ragged.upsert(rows.clone()).unwrap_err(), "length\0");
+
+    // A stream upserted one row at a time is located through the index kept by the keyed table.
+    let length = 10_000_i64;
+    let mut stream = KTable::from_columns(vec![
+        (
+            "id",
+            KVal::Long(KData::List(Cow::Owned((0..length).collect()))),
+        ),
+        (
+            "size",
+            KVal::Long(KData::List(Cow::Owned(vec![0; length as usize]))),
+        ),
+    ])
+    .unwrap()
+    .enkey(1)
+    .unwrap();
+    for id in length - 500..length + 500 {
+        let row = KTable::from_columns(vec![("id", kval!([id])), ("size", kval!([id]))]).unwrap();
+        stream.upsert(row).unwrap();
+    }
+    assert_eq!(stream.len(), length as usize + 500);
+    let id = KVal::Long(KData::Atom(Cow::Owned(length + 10)));
+    let row = stream.lookup(&id).unwrap();
+    assert_eq!(row, length as usize + 10);
+    assert_eq!(stream.values().get_cell(row as i64, "size", None), Ok(id));
+    let id = KVal::Long(KData::Atom(Cow::Owned(length - 10)));
+    assert_eq!(stream.lookup(&id), Some(length as usize - 10));
+    assert_eq!(stream.values().get_cell(length - 10, "size", None), Ok(id));
+
+    // A table borrowing q memory is upserted by the keys of two columns.
+    let k = trades(3);
+    let mut borrowed = KTable::from_raw(k);
+    let rows = KTable::from_columns(vec![
+        ("sym", kval!(["b", "a"])),
+        ("price", kval!([1.5, 9.5])),
+        ("size", kval!([-1, -2])),
+    ])
+    .unwrap();
+    borrowed.upsert(&["sym", "price"], rows).unwrap();
+    assert_eq!(borrowed.len(), 4);
+    assert_eq!(
+        borrowed.get_column("size").unwrap(),
+        kval!([0, -1, 200, -2])
+    );
+    assert_eq!(KTable::from_raw(k), trades_table(3));
+    decrement_reference_count(k);
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn table_arrow_test() {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Date32Type, Int32Type, TimestampNanosecondType};
+    use arrow_array::{
+        Array, ArrayRef, BooleanArray, Int32Array, RecordBatch, StringArray,
+        TimestampNanosecondArray,
+    };
+    use arrow_schema::{DataType, Field, Schema, TimeUnit};
+    use std::sync::Arc;
+
+    let strings = |strings: &[&'static str]| {
+        KVal::CompoundList(
+            strings
+                .iter()
+                .map(|string| KVal::String(Cow::Borrowed(string.as_bytes())))
+                .collect(),
+        )
+    };
+    let table = KTable::from_columns(vec![
+        (
+            "size",
+            KVal::Long(KData::List(Cow::Owned(vec![
+                1,
+                qnull_base::J,
+                qinf_base::J,
+            ]))),
+        ),
+        (
+            "price",
+            KVal::Float(KData::List(Cow::Owned(vec![
+                1.5,
+                qnull_base::F,
+                qninf_base::F,
+            ]))),
+        ),
+        ("sym", kval!(["a", "", "a"])),
+        (
+            "time",
+            KVal::Timestamp(KData::List(Cow::Owned(vec![
+                0,
+                qnull_base::J,
+                qninf_base::J,
+            ]))),
+        ),
+        (
+            "date",
+            KVal::Date(KData::List(Cow::Owned(vec![
+                -1,
+                qnull_base::I,
+                qinf_base::I,
+            ]))),
+        ),
+        ("flag", kval!([true, false, true])),
+        ("note", strings(&["x", "", "yz"])),
+    ])
+    .unwrap();
+
+    // q nulls become the validity bitmaps and temporal values are shifted to the Unix epoch.
+    let batch = table.to_arrow().unwrap();
+    assert_eq!(batch.num_rows(), 3);
+    assert_eq!(
+        batch
+            .columns()
+            .iter()
+            .map(|column| column.null_count())
+            .collect::<Vec<_>>(),
+        vec![1, 1, 1, 1, 1, 0, 1]
+    );
+    assert_eq!(
+        batch.schema().field(3).data_type(),
+        &DataType::Timestamp(TimeUnit::Nanosecond, None)
+    );
+    let time = batch.column(3).as_primitive::<TimestampNanosecondType>();
+    assert_eq!(time.value(0), 946_684_800_000_000_000);
+    assert_eq!(time.value(2), qninf_base::J);
+    let date = batch.column(4).as_primitive::<Date32Type>();
+    assert_eq!(date.value(0), 10956);
+    assert_eq!(date.value(2), qinf_base::I);
+    let sym = batch.column(2).as_dictionary::<Int32Type>();
+    assert_eq!(sym.values().len(), 1);
+    assert!(!batch.schema().field(5).is_nullable());
+    assert_eq!(batch.column(6).as_string::<i64>().value(2), "yz");
+
+    // Round trip restores the table.
+    assert_eq!(KTable::from_arrow(&batch).unwrap(), table);
+    let empty = table.get_rows(0..0).unwrap();
+    let empty_batch = empty.to_arrow().unwrap();
+    assert_eq!(empty_batch.num_rows(), 0);
+    assert_eq!(KTable::from_arrow(&empty_batch).unwrap(), empty);
+
+    // Arrays which `to_arrow` does not build are also taken.
+    let schema = Schema::new(vec![
+        Field::new(
+            "time",
+            DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+            true,
+        ),
+        Field::new("note", DataType::Utf8, true),
+        Field::new("flag", DataType::Boolean, true),
+    ]);
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            TimestampNanosecondArray::from(vec![Some(946_684_800_000_000_001), None])
+                .with_timezone("UTC"),
+        ),
+        Arc::new(StringArray::from(vec![None, Some("b")])),
+        Arc::new(BooleanArray::from(vec![None, Some(true)])),
+    ];
+    let batch = RecordBatch::try_new(Arc::new(schema), columns).unwrap();
+    assert_eq!(
+        KTable::from_arrow(&batch).unwrap(),
+        KTable::from_columns(vec![
+            (
+                "time",
+                KVal::Timestamp(KData::List(Cow::Owned(vec![1, qnull_base::J])))
+            ),
+            ("note", strings(&["", "b"])),
+            ("flag", kval!([false, true])),
+        ])
+        .unwrap()
+    );
+
+    // Unsupported columns are listed.
+    let table = KTable::from_columns(vec![
+        ("id", kval!([1_i32])),
+        ("size", kval!([1])),
+        ("tags", KVal::CompoundList(vec![kval!(1)])),
+    ])
+    .unwrap();
+    assert_eq!(
+        table.to_arrow().unwrap_err().to_string(),
+        "Invalid argument error: unsupported columns: id (int list), tags (compound list)"
+    );
+    let batch = RecordBatch::try_from_iter(vec![(
+        "id",
+        Arc::new(Int32Array::from(vec![1])) as ArrayRef,
+    )])
+    .unwrap();
+    assert_eq!(
+        KTable::from_arrow(&batch).unwrap_err().to_string(),
+        "Invalid argument error: unsupported columns: id (Int32)"
+    );
+    let keyed = KTable::new(KDict::new(
+        KVal::Table(trades_table(1)),
+        KVal::Table(trades_table(1)),
+    ));
+    assert!(keyed.to_arrow().is_err());
+
+    // Timestamps beyond the range of Arrow are reported with the column.
+    let late = KTable::from_columns(vec![(
+        "time",
+        KVal::Timestamp(KData::List(Cow::Owned(vec![i64::MAX - 1]))),
+    )])
+    .unwrap();
+    assert_eq!(
+        late.to_arrow().unwrap_err().to_string(),
+        "Compute error: timestamp of column time is out of range"
+    );
+}
+
+#[cfg(feature = "derive")]
+#[derive(Clone, Debug, PartialEq, QRow)]
+struct Trade {
+    #[q(type = "timestamp")]
+    time: i64,
+    sym: String,
+    price: f64,
+    size: Option<i64>,
+    #[q(type = "date")]
+    settle: Option<i32>,
+    #[q(type = "string")]
+    note: Option<String>,
+    side: char,
+    r#open: bool,
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn q_row_derive_test() {
+    let trades = (0..5)
+        .map(|row| Trade {
+            time: row * 1_000,
+            sym: ["a", "b"][row as usize % 2].to_string(),
+            price: row as f64 + 0.5,
+            size: (row % 2 == 0).then_some(row * 100),
+            settle: (row != 3).then_some(row as i32),
+            note: (row == 1).then(|| "late".to_string()),
+            side: 'b',
+            r#open: row < 2,
+        })
+        .collect::<Vec<_>>();
+
+    // Fields become columns of the selected types.
+    assert_eq!(
+        Trade::schema(),
+        vec![
+            ("time", qtype::TIMESTAMP_LIST),
+            ("sym", qtype::SYMBOL_LIST),
+            ("price", qtype::FLOAT_LIST),
+            ("size", qtype::LONG_LIST),
+            ("settle", qtype::DATE_LIST),
+            ("note", qtype::COMPOUND_LIST),
+            ("side", qtype::STRING),
+            ("open", qtype::BOOL_LIST),
+        ]
+    );
+    let table = Trade::table_from_slice(&trades);
+    assert_eq!(
+        table.get_column("size").unwrap(),
+        KVal::Long(KData::List(Cow::Owned(vec![
+            0,
+            qnull_base::J,
+            200,
+            qnull_base::J,
+            400
+        ])))
+    );
+    assert_eq!(
+        table.get_column("settle").unwrap(),
+        KVal::Date(KData::List(Cow::Owned(vec![0, 1, 2, qnull_base::I, 4])))
+    );
+    assert_eq!(
+        table.get_column("side").unwrap(),
+        KVal::String(Cow::Borrowed(b"bbbbb"))
+    );
+
+    // Round trip through a q table restores the values with nulls as `None`.
+    let k = table.to_k();
+    assert_eq!(k.get_type(), qtype::TABLE);
+    assert_eq!(
+        peek_table_schema(k).unwrap()[4],
+        ("settle".to_string(), qtype::DATE_LIST)
+    );
+    let restored = Trade::from_table(&KTable::from_raw(k)).unwrap();
+    decrement_reference_count(k);
+    assert_eq!(restored, trades);
+
+    // A row is a dictionary from column names to cells.
+    let row = trades[1].to_kval_row();
+    assert_eq!(
+        row.clone(),
+        KVal::Dictionary(KDict::new(
+            kval!(["time", "sym", "price", "size", "settle", "note", "side", "open"]),
+            KVal::CompoundList(vec![
+                KVal::Timestamp(KData::Atom(Cow::Owned(1_000))),
+                kval!("b"),
+                kval!(1.5),
+                KVal::Long(KData::Atom(Cow::Owned(qnull_base::J))),
+                KVal::Date(KData::Atom(Cow::Owned(1))),
+                KVal::String(Cow::Borrowed(b"late")),
+                KVal::Char('b'),
+                kval!(true),
+            ])
+        ))
+    );
+    assert_eq!(Trade::from_row(&row).unwrap(), trades[1]);
+    assert_eq!(Trade::table_from_slice(&[]).len(), 0);
+
+    // Errors name the row and the column.
+    let narrow = table
+        .project(
+            Some(&["time", "sym", "price", "size", "settle", "note", "side"]),
+            None,
+        )
+        .unwrap();
+    assert_eq!(
+        Trade::from_table(&narrow).unwrap_err(),
+        "row 0: missing column open"
+    );
+    let (names, mut columns) = match (*table.dict.keys, *table.dict.values) {
+        (KVal::Symbol(KData::List(names)), KVal::CompoundList(columns)) => {
+            (names.into_owned(), columns)
+        }
+        _ => unreachable!(),
+    };
+    columns[0] = KVal::Long(KData::List(Cow::Owned(vec![0; 5])));
+    let retyped = KTable::from_columns(
+        names
+            .iter()
+            .map(String::as_str)
+            .zip(columns)
+            .collect::<Vec<_>>(),
+    )
+    .unwrap();
+    assert_eq!(
+        Trade::from_table(&retyped).unwrap_err(),
+        "row 0: column time: type"
+    );
+    let mut ragged = retyped.clone();
+    if let KVal::CompoundList(columns) = ragged.dict.values.as_mut() {
+        columns[2] = kval!([1.5, 2.5]);
+    }
+    assert_eq!(Trade::from_table(&ragged).unwrap_err(), "row 2: length");
+    assert_eq!(
+        Trade::from_row(&kval!(1)).unwrap_err(),
+        "row must be a dictionary"
+    );
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+fn column_as_test() {
+    // Columns of a table borrowing q memory are not copied.
+    let k = trades(3);
+    let table = KTable::from_raw(k);
+    let sizes = table.column_as::<i64>("size").unwrap();
+    assert!(matches!(sizes, Cow::Borrowed(_)));
+    assert_eq!(sizes.as_ref(), &[0, 100, 200]);
+    let columns = unsafe { (*k).value.table }.as_mut_slice::<K>()[1].as_mut_slice::<K>();
+    assert_eq!(sizes.as_ptr(), columns[2].as_mut_slice::<J>().as_ptr());
+    let prices = table.column_as::<f64>("price").unwrap();
+    assert_eq!(prices.as_ptr(), columns[1].as_mut_slice::<F>().as_ptr());
+    assert_eq!(
+        table.column_as::<String>("sym").unwrap().as_ref(),
+        &["a", "b", "c"]
+    );
+
+    // Type mismatches name the type of the column.
+    assert_eq!(
+        table.column_as::<i32>("size").unwrap_err().to_string(),
+        "invalid operation column_as on long list. expected: int list"
+    );
+    assert_eq!(
+        table.column_as::<f64>("volume").unwrap_err().to_string(),
+        "missing columns: volume"
+    );
+    decrement_reference_count(k);
+
+    // An owned table is borrowed as well, and compound columns are collected.
+    let table = KTable::from_columns(vec![
+        ("flag", kval!([true, false])),
+        ("id", kval!([1_i32, 2_i32])),
+        ("size", KVal::CompoundList(vec![kval!(1), kval!(2)])),
+        (
+            "note",
+            KVal::CompoundList(vec![KVal::String(Cow::Borrowed(b"x")), kval!("y")]),
+        ),
+        ("mixed", KVal::CompoundList(vec![kval!(1), kval!(2.5)])),
+    ])
+    .unwrap();
+    let flags = table.column_as::<bool>("flag").unwrap();
+    assert!(matches!(flags, Cow::Borrowed(&[true, false])));
+    assert!(matches!(
+        table.column_as::<i32>("id").unwrap(),
+        Cow::Borrowed(&[1, 2])
+    ));
+    let sizes = table.column_as::<i64>("size").unwrap();
+    assert!(matches!(sizes, Cow::Owned(_)));
+    assert_eq!(sizes.as_ref(), &[1, 2]);
+    assert_eq!(
+        table.column_as::<String>("note").unwrap().as_ref(),
+        &["x", "y"]
+    );
+    assert_eq!(
+        table.column_as::<i64>("mixed").unwrap_err().to_string(),
+        "invalid operation column_as on compound list. expected: long list"
+    );
+    let keyed = KTable::new(KDict::new(
+        KVal::Table(trades_table(1)),
+        KVal::Table(trades_table(1)),
+    ));
+    assert!(matches!(
+        keyed.column_as::<i64>("size").unwrap_err(),
+        KError::InvalidOperation { .. }
+    ));
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+#[allow(deprecated)]
+fn get_row_test() {
+    let k = trades(3);
+    let table = KTable::from_raw(k);
+    let row = table.get_row(1, &[]).unwrap();
+    assert_eq!(
+        row,
+        KVal::Dictionary(KDict::new(
+            kval!(["sym", "price", "size"]),
+            KVal::CompoundList(vec![kval!("b"), kval!(1.5), kval!(100)])
+        ))
+    );
+    // The cells borrow the columns instead of copying them.
+    match row {
+        KVal::Dictionary(row) => match row.values.as_ref() {
+            KVal::CompoundList(cells) => {
+                assert!(matches!(
+                    cells[1],
+                    KVal::Float(KData::Atom(Cow::Borrowed(_)))
+                ))
+            }
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
+    assert_eq!(table.get_row(3, &[]), Err("index out of bounds\0"));
+    assert_eq!(table.get_row(-1, &[]), Err("index out of bounds\0"));
+    decrement_reference_count(k);
+
+    // Sources are given to enum columns in order and kept for `None`.
+    let table = KTable::from_columns(vec![
+        (
+            "sym",
+            KVal::Enum(KData::List(Cow::Owned(vec![0, 1])), Some("sym".to_string())),
+        ),
+        ("price", kval!([1.5, 2.5])),
+        (
+            "venue",
+            KVal::Enum(KData::List(Cow::Owned(vec![1, 0])), None),
+        ),
+    ])
+    .unwrap();
+    let row = match table.get_row(0, &[None, Some("venues")]).unwrap() {
+        KVal::Dictionary(row) => row,
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        row.get("sym"),
+        Some(KVal::Enum(
+            KData::Atom(Cow::Owned(0)),
+            Some("sym".to_string())
+        ))
+    );
+    assert_eq!(
+        row.get("venue"),
+        Some(KVal::Enum(
+            KData::Atom(Cow::Owned(1)),
+            Some("venues".to_string())
+        ))
+    );
+    let keyed = KTable::new(KDict::new(
+        KVal::Table(trades_table(1)),
+        KVal::Table(trades_table(1)),
+    ));
+    assert_eq!(keyed.get_row(0, &[]), Err("type\0"));
+    assert_eq!(mock::live_objects(), 0);
+}
+
+#[test]
+#[allow(deprecated)]
+fn get_row_bounds_test() {
+    // The index is checked against the number of rows whether or not enum sources are given.
+    let table = trades_table(3);
+    for index in [3, 10, -1, i64::MIN] {
+        assert_eq!(table.get_row(index, &[]), Err("index out of bounds\0"));
+        assert_eq!(
+            table.get_row(index, &[Some("sym")]),
+            Err("index out of bounds\0")
+        );
+    }
+    assert!(table.get_row(2, &[]).is_ok());
+
+    // An enum first column without a source does not fail the check.
+    let table = KTable::from_columns(vec![
+        (
+            "sym",
+            KVal::Enum(KData::List(Cow::Owned(vec![0, 1, 0])), None),
+        ),
+        (
+            "note",
+            KVal::CompoundList(vec![kval!("x"), kval!("y"), kval!("z")]),
+        ),
+    ])
+    .unwrap();
+    assert_eq!(table.get_row(10, &[None]), Err("index out of bounds\0"));
+    assert_eq!(table.get_row(3, &[]), Err("index out of bounds\0"));
+    assert!(table.get_row(2, &[None]).is_ok());
+    assert!(table.get_row(0, &[]).is_ok());
+
+    // Columns shorter than the first one are reported instead of read out of bounds.
+    let ragged = KTable {
+        dict: KDict::new(
+            kval!(["price", "size"]),
+            KVal::CompoundList(vec![kval!([1.5, 2.5]), kval!([100])]),
+        ),
+    };
+    assert_eq!(ragged.get_row(1, &[]), Err("length\0"));
+    assert_eq!(ragged.get_row(2, &[]), Err("index out of bounds\0"));
+}
+
+#[test]
+fn get_row_with_sources_test() {
+    let table = KTable::from_columns(vec![
+        (
+            "sym",
+            KVal::Enum(KData::List(Cow::Owned(vec![0, 1])), Some("sym".to_string())),
+        ),
+        ("price", kval!([1.5, 2.5])),
+        (
+            "venue",
+            KVal::Enum(
+                KData::List(Cow::Owned(vec![1, 0])),
+                Some("venue".to_string()),
+            ),
+        ),
+    ])
+    .unwrap();
+    // Only the second enum column is overridden and the first keeps its own source.
+    let sources = HashMap::from([("venue", "venues")]);
+    let row = match table.get_row_with_sources(1, &sources).unwrap() {
+        KVal::Dictionary(row) => row,
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        row.get("sym"),
+        Some(KVal::Enum(
+            KData::Atom(Cow::Owned(1)),
+            Some("sym".to_string())
+        ))
+    );
+    assert_eq!(row.get("price"), Some(kval!(2.5)));
+    assert_eq!(
+        row.get("venue"),
+        Some(KVal::Enum(
+            KData::Atom(Cow::Owned(0)),
+            Some("venues".to_string())
+        ))
+    );
+    // Sources of columns which are not enum columns are ignored.
+    let sources = HashMap::from([("price", "prices")]);
+    assert_eq!(
+        table.get_row_with_sources(0, &sources),
+        table.get_row_with_sources(0, &HashMap::new())
+    );
+    assert_eq!(
+        table.get_row_with_sources(2, &sources),
+        Err("index out of bounds\0")
+    );
+
+    let sources = HashMap::from([("venue", "venues")]);
+    assert_eq!(
+        table.get_column_with_sources("venue", &sources).unwrap(),
+        KVal::Enum(
+            KData::List(Cow::Owned(vec![1, 0])),
+            Some("venues".to_string())
+        )
+    );
+    assert_eq!(
+        table.get_column_with_sources("sym", &sources).unwrap(),
+        KVal::Enum(KData::List(Cow::Owned(vec![0, 1])), Some("sym".to_string()))
+    );
+    assert_eq!(
+        table.get_column_with_sources("size", &sources).unwrap_err(),
+        KError::MissingColumns(vec!["size".to_string()])
+    );
+}
+
+#[test]
+fn try_from_raw_test() {
+    let k = trades(3);
+    assert_eq!(KTable::try_from_raw(k).unwrap(), trades_table(3));
+    decrement_reference_count(k);
+
+    // Objects which are not tables are rejected with their types.
+    let list = new_list(qtype::LONG_LIST, 2);
+    assert_eq!(
+        KTable::try_from_raw(list).unwrap_err().to_string(),
+        "invalid operation try_from_raw on long list. expected: table"
+    );
+    decrement_reference_count(list);
+    assert_eq!(
+        KTable::try_from_raw(std::ptr::null_mut())
+            .unwrap_err()
+            .to_string(),
+        "invalid operation try_from_raw on general null. expected: table"
+    );
+
+    // A long atom whose type is forcibly set to table holds no dictionary.
+    let forged = new_long(0);
+    unsafe { (*forged).qtype = qtype::TABLE };
+    assert_eq!(
+        KTable::try_from_raw(forged).unwrap_err().to_string(),
+        "invalid operation try_from_raw on table. expected: flipped dictionary of symbol list and lists"
+    );
+
+    // A forged table of a dictionary which is not a flipped one is rejected as well.
+    let dictionary = KDict::new(kval!([1, 2]), kval!([3, 4])).to_k();
+    unsafe { (*forged).value.table = dictionary };
+    assert!(matches!(
+        KTable::try_from_raw(forged),
+        Err(KError::InvalidOperation { .. })
+    ));
+    decrement_reference_count(dictionary);
+
+    // Columns of different lengths are reported with their lengths.
+    let ragged = KDict::new(
+        kval!(["price", "size"]),
+        KVal::CompoundList(vec![kval!([1.5, 2.5]), kval!([100])]),
+    )
+    .to_k();
+    unsafe { (*forged).value.table = ragged };
+    assert_eq!(
+        KTable::try_from_raw(forged),
+        Err(KError::LengthMismatch { left: 2, right: 1 })
+    );
+    decrement_reference_count(ragged);
+    unsafe {
+        (*forged).value.table = std::ptr::null_mut();
+        (*forged).qtype = qtype::LONG_ATOM;
+    }
+    decrement_reference_count(forged);
+    assert_eq!(mock::live_objects(), 0);
+}
@@ -0,0 +1,177 @@
+//! Derive macros of `kdbplus`. The macros are re-exported by `kdbplus::api::rusty_api` when `derive` feature of
+//!  `kdbplus` is enabled, and the generated code refers to the items of `kdbplus`.
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Load Libraries
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr};
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Global Variables
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Names accepted by `#[q(type = "...")]` and the list types they select.
+const TYPE_NAMES: [(&str, &str); 18] = [
+    ("bool", "BOOL_LIST"),
+    ("byte", "BYTE_LIST"),
+    ("short", "SHORT_LIST"),
+    ("int", "INT_LIST"),
+    ("long", "LONG_LIST"),
+    ("real", "REAL_LIST"),
+    ("float", "FLOAT_LIST"),
+    ("char", "STRING"),
+    ("symbol", "SYMBOL_LIST"),
+    ("string", "COMPOUND_LIST"),
+    ("timestamp", "TIMESTAMP_LIST"),
+    ("month", "MONTH_LIST"),
+    ("date", "DATE_LIST"),
+    ("datetime", "DATETIME_LIST"),
+    ("timespan", "TIMESPAN_LIST"),
+    ("minute", "MINUTE_LIST"),
+    ("second", "SECOND_LIST"),
+    ("time", "TIME_LIST"),
+];
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Public Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Derive `QRow` for a struct with named fields. Each field becomes a column of the same name whose type is selected
+///  by the type of the field through `QField`, e.g., `i64` as long and `String` as symbol. `#[q(type = "...")]`
+///  selects another type which the field can be stored as, e.g., `"timestamp"` for `i64` or `"string"` for
+///  `String`. A type the field cannot be stored as is rejected at compile time.
+#[proc_macro_derive(QRow, attributes(q))]
+pub fn derive_q_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(expanded) => expanded.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+// >> Private Functions
+//++++++++++++++++++++++++++++++++++++++++++++++++++//
+
+/// Generate the implementation of `QRow`.
+fn expand(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "QRow cannot be derived for a generic struct",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    name,
+                    "QRow can be derived only for a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                name,
+                "QRow can be derived only for a struct with named fields",
+            ))
+        }
+    };
+    let mut schema = Vec::new();
+    let mut values = Vec::new();
+    let mut conversions = Vec::new();
+    let mut checks = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("fields are named");
+        let ty = &field.ty;
+        let column = ident.unraw().to_string();
+        let qtype = match column_type(field)? {
+            Some((type_name, qtype)) => {
+                let qtype = format_ident!("{}", qtype);
+                let message = format!("field `{}` cannot be stored as {}", column, type_name);
+                checks.push(quote! {
+                    const _: () = {
+                        let qtypes = <#ty as ::kdbplus::api::rusty_api::QField>::QTYPES;
+                        let mut found = false;
+                        let mut index = 0;
+                        while index < qtypes.len() {
+                            found |= qtypes[index] == ::kdbplus::qtype::#qtype;
+                            index += 1;
+                        }
+                        assert!(found, #message);
+                    };
+                });
+                quote!(::kdbplus::qtype::#qtype)
+            }
+            None => quote!(<#ty as ::kdbplus::api::rusty_api::QField>::QTYPES[0]),
+        };
+        schema.push(quote!((#column, #qtype)));
+        values.push(quote!(::kdbplus::api::rusty_api::QField::to_atom(&self.#ident, #qtype)));
+        conversions.push(quote! {
+            #ident: <#ty as ::kdbplus::api::rusty_api::QField>::from_row_field(row, #column, #qtype)?
+        });
+    }
+    Ok(quote! {
+        #(#checks)*
+
+        impl ::kdbplus::api::rusty_api::QRow for #name {
+            fn schema() -> ::std::vec::Vec<(&'static str, i8)> {
+                ::std::vec![#(#schema),*]
+            }
+
+            fn to_row_values(&self) -> ::kdbplus::api::rusty_api::RowValues {
+                ::std::vec![#(#values),*]
+            }
+
+            fn from_row(
+                row: &::kdbplus::api::rusty_api::KVal,
+            ) -> ::std::result::Result<Self, ::std::string::String> {
+                ::std::result::Result::Ok(#name {
+                    #(#conversions),*
+                })
+            }
+        }
+    })
+}
+
+/// Read the type name of `#[q(type = "...")]` on a field and the list type it selects.
+fn column_type(field: &syn::Field) -> Result<Option<(String, &'static str)>, Error> {
+    let mut selected = None;
+    for attribute in field
+        .attrs
+        .iter()
+        .filter(|attribute| attribute.path().is_ident("q"))
+    {
+        attribute.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("type") {
+                return Err(meta.error("expected `type = \"...\"`"));
+            }
+            let type_name = meta.value()?.parse::<LitStr>()?;
+            match TYPE_NAMES
+                .iter()
+                .find(|(name, _)| *name == type_name.value())
+            {
+                Some((name, qtype)) => {
+                    selected = Some((name.to_string(), *qtype));
+                    Ok(())
+                }
+                None => Err(Error::new_spanned(
+                    &type_name,
+                    format!(
+                        "unknown q type `{}`, expected one of: {}",
+                        type_name.value(),
+                        TYPE_NAMES.map(|(name, _)| name).join(", ")
+                    ),
+                )),
+            }
+        })?;
+    }
+    Ok(selected)
+}